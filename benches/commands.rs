@@ -0,0 +1,74 @@
+//! Benchmarks for the commands that scale with the number of silos.
+//!
+//! silo is a thin layer over git subprocesses, so these run the real binary
+//! against a temporary repository (via `silo-test`) rather than calling
+//! internal functions. Use `silo --timings` to break a slow command down into
+//! phases and git calls.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use silo_test::TestEnv;
+
+/// Silo counts each benchmark is run with.
+const SILO_COUNTS: &[usize] = &[1, 10];
+
+fn env_with_silos(count: usize) -> TestEnv {
+    let env = TestEnv::builder()
+        .silo_bin(env!("CARGO_BIN_EXE_silo"))
+        .config("warn_shell_integration = false")
+        .build();
+    for i in 0..count {
+        env.create_silo(&format!("bench-{}", i));
+    }
+    env
+}
+
+fn run(env: &TestEnv, args: &[&str]) {
+    TestEnv::assert_success(&env.run_silo(args));
+}
+
+/// Name resolution: `cd` scans storage and resolves a name to a silo.
+fn bench_resolve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve");
+    for &count in SILO_COUNTS {
+        let env = env_with_silos(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &env, |b, env| {
+            b.iter(|| run(env, &["cd", "bench-0"]))
+        });
+    }
+    group.finish();
+}
+
+/// Storage scanning: `gc --dry-run` walks the whole storage directory.
+fn bench_storage_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("storage_scan");
+    for &count in SILO_COUNTS {
+        let env = env_with_silos(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &env, |b, env| {
+            b.iter(|| run(env, &["gc", "--dry-run"]))
+        });
+    }
+    group.finish();
+}
+
+/// List stat collection: several git calls per silo.
+fn bench_list(c: &mut Criterion) {
+    let mut group = c.benchmark_group("list");
+    for &count in SILO_COUNTS {
+        let env = env_with_silos(count);
+        group.bench_with_input(BenchmarkId::new("repo", count), &env, |b, env| {
+            b.iter(|| run(env, &["list"]))
+        });
+        group.bench_with_input(BenchmarkId::new("all", count), &env, |b, env| {
+            b.iter(|| run(env, &["list", "--all"]))
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    // Each iteration spawns processes, so keep the sample count modest
+    config = Criterion::default().sample_size(20);
+    targets = bench_resolve, bench_storage_scan, bench_list
+}
+criterion_main!(benches);
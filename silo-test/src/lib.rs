@@ -0,0 +1,553 @@
+//! Test environments for integration tests against the silo CLI.
+//!
+//! `TestEnv` sets up a temporary git repository and silo storage directory,
+//! runs the silo binary against them, and asserts on the resulting state.
+//! It is used by silo's own integration tests and can be used by plugins and
+//! wrapper tools that drive silo.
+//!
+//! ```no_run
+//! use silo_test::TestEnv;
+//!
+//! let env = TestEnv::builder()
+//!     .config("display_names = \"always-qualified\"")
+//!     .with_remote()
+//!     .build();
+//! env.create_silo("feature");
+//! TestEnv::assert_success(&env.run_silo(&["list"]));
+//! ```
+//!
+//! The silo binary is taken from, in order: [`TestEnvBuilder::silo_bin`],
+//! the `SILO_BIN` environment variable, `CARGO_BIN_EXE_silo` (set by cargo
+//! for silo's own tests), or `silo` on `PATH`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Test environment with temporary directories for repo and silo storage.
+pub struct TestEnv {
+    /// Temporary directory for silo worktree storage
+    pub silo_dir: TempDir,
+    /// Temporary directory for the git repository
+    pub repo_dir: TempDir,
+    /// Bare repository configured as the `origin` remote, if requested
+    pub remote_dir: Option<TempDir>,
+    silo_bin: PathBuf,
+}
+
+/// Builder for a [`TestEnv`].
+#[derive(Default)]
+pub struct TestEnvBuilder {
+    silo_bin: Option<PathBuf>,
+    config: Vec<String>,
+    files: Vec<(String, String)>,
+    remote: bool,
+}
+
+impl TestEnvBuilder {
+    /// Use a specific silo binary.
+    pub fn silo_bin(mut self, path: impl Into<PathBuf>) -> Self {
+        self.silo_bin = Some(path.into());
+        self
+    }
+
+    /// Add a line to the repo's `.silo.toml`.
+    pub fn config(mut self, line: &str) -> Self {
+        self.config.push(line.to_string());
+        self
+    }
+
+    /// Add a file to the repo's initial commit.
+    pub fn file(mut self, path: &str, content: &str) -> Self {
+        self.files.push((path.to_string(), content.to_string()));
+        self
+    }
+
+    /// Create a bare `origin` remote and push the initial commit to it.
+    pub fn with_remote(mut self) -> Self {
+        self.remote = true;
+        self
+    }
+
+    /// Create the test environment.
+    pub fn build(self) -> TestEnv {
+        let silo_dir = TempDir::new().expect("Failed to create silo temp dir");
+        let repo_dir = TempDir::new().expect("Failed to create repo temp dir");
+
+        // Initialize git repo
+        git_in(repo_dir.path(), &["init"]);
+
+        // Configure git user for commits
+        git_in(repo_dir.path(), &["config", "user.email", "test@test.com"]);
+        git_in(repo_dir.path(), &["config", "user.name", "Test User"]);
+
+        // Create initial commit (required for worktrees)
+        fs::write(repo_dir.path().join("README.md"), "# Test Repo\n")
+            .expect("Failed to write README");
+        for (path, content) in &self.files {
+            write_file(&repo_dir.path().join(path), content);
+        }
+        git_in(repo_dir.path(), &["add", "."]);
+        git_in(repo_dir.path(), &["commit", "-m", "Initial commit"]);
+
+        let remote_dir = self.remote.then(|| {
+            let remote = TempDir::new().expect("Failed to create remote temp dir");
+            git_in(remote.path(), &["init", "--bare"]);
+            let url = remote.path().to_string_lossy().into_owned();
+            git_in(repo_dir.path(), &["remote", "add", "origin", &url]);
+            git_in(repo_dir.path(), &["push", "-u", "origin", "HEAD"]);
+            remote
+        });
+
+        let env = TestEnv {
+            silo_dir,
+            repo_dir,
+            remote_dir,
+            silo_bin: self.silo_bin.unwrap_or_else(default_silo_bin),
+        };
+
+        // Create local config pointing to our temp silo dir
+        let config = format!("worktree_dir = \"{}\"", env.silo_dir.path().display());
+        fs::write(env.repo_dir.path().join(".silo.toml"), config).expect("Failed to write config");
+        for line in &self.config {
+            env.append_config(line);
+        }
+
+        env
+    }
+}
+
+/// Find the silo binary when none was given explicitly.
+fn default_silo_bin() -> PathBuf {
+    std::env::var_os("SILO_BIN")
+        .or_else(|| std::env::var_os("CARGO_BIN_EXE_silo"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("silo"))
+}
+
+/// Run git in a directory, panicking if it can't be started.
+fn git_in(dir: &Path, args: &[&str]) -> Output {
+    Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run git command")
+}
+
+/// Write a file, creating parent directories as needed.
+fn write_file(path: &Path, content: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("Failed to create directory");
+    }
+    fs::write(path, content).expect("Failed to write file");
+}
+
+impl TestEnv {
+    /// Create a new test environment with an initialized git repo.
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Start building a customized test environment.
+    pub fn builder() -> TestEnvBuilder {
+        TestEnvBuilder::default()
+    }
+
+    /// Append a line to the repo's `.silo.toml`.
+    pub fn append_config(&self, line: &str) {
+        let path = self.repo_dir.path().join(".silo.toml");
+        let mut config = fs::read_to_string(&path).expect("Failed to read config");
+        config.push('\n');
+        config.push_str(line);
+        config.push('\n');
+        fs::write(&path, config).expect("Failed to write config");
+    }
+
+    /// Get the path to the silo binary.
+    pub fn silo_bin(&self) -> &Path {
+        &self.silo_bin
+    }
+
+    /// Build a silo command run from the main repo.
+    pub fn silo_command(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new(&self.silo_bin);
+        // Tests run inside tmux mustn't mark the developer's windows busy
+        cmd.args(args)
+            .current_dir(&self.repo_dir)
+            .env_remove("TMUX");
+        cmd
+    }
+
+    /// Run silo command with given arguments.
+    pub fn run_silo(&self, args: &[&str]) -> Output {
+        self.silo_command(args)
+            .output()
+            .expect("Failed to run silo command")
+    }
+
+    /// Run silo command with given arguments from a specific silo directory.
+    pub fn run_silo_in(&self, silo_name: &str, args: &[&str]) -> Output {
+        let mut cmd = self.silo_command(args);
+        cmd.current_dir(self.silo_path(silo_name));
+        cmd.output().expect("Failed to run silo command")
+    }
+
+    /// Run silo command with given arguments and environment variables.
+    pub fn run_silo_with_env(&self, args: &[&str], envs: &[(&str, &str)]) -> Output {
+        let mut cmd = self.silo_command(args);
+        for (key, value) in envs {
+            cmd.env(key, value);
+        }
+        cmd.output().expect("Failed to run silo command")
+    }
+
+    /// Run a git command in the main repo.
+    pub fn git(&self, args: &[&str]) -> Output {
+        git_in(self.repo_dir.path(), args)
+    }
+
+    /// Run a git command in a silo.
+    pub fn git_in_silo(&self, silo_name: &str, args: &[&str]) -> Output {
+        git_in(&self.silo_path(silo_name), args)
+    }
+
+    /// Create a silo with the given name.
+    pub fn create_silo(&self, name: &str) {
+        let output = self.run_silo(&["new", name]);
+        assert!(
+            output.status.success(),
+            "Failed to create silo {}: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    /// Create multiple silos.
+    pub fn create_silos(&self, names: &[&str]) {
+        for name in names {
+            self.create_silo(name);
+        }
+    }
+
+    /// Get the full path to a silo directory.
+    pub fn silo_path(&self, name: &str) -> PathBuf {
+        // Silo directories are stored as {repo_name}-{hash}/{name}
+        if let Ok(entries) = fs::read_dir(self.silo_dir.path()) {
+            for entry in entries.flatten() {
+                let silo_path = entry.path().join(name);
+                if silo_path.exists() {
+                    return silo_path;
+                }
+            }
+        }
+        // Return the expected path even if it doesn't exist
+        self.silo_dir.path().join(name)
+    }
+
+    /// Get the tracking directory of a silo (process, run and agent data).
+    pub fn tracking_path(&self, name: &str) -> PathBuf {
+        let silo_path = self.silo_path(name);
+        let parent = silo_path.parent().unwrap_or(&silo_path);
+        parent.join(".tracking").join(name)
+    }
+
+    /// Check if a silo directory exists in the storage.
+    pub fn silo_exists(&self, name: &str) -> bool {
+        if let Ok(entries) = fs::read_dir(self.silo_dir.path()) {
+            for entry in entries.flatten() {
+                let silo_path = entry.path().join(name);
+                if silo_path.exists() {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Assert that a silo exists.
+    pub fn assert_silo_exists(&self, name: &str) {
+        assert!(
+            self.silo_exists(name),
+            "Silo '{}' should exist but doesn't",
+            name
+        );
+    }
+
+    /// Assert that a silo does not exist.
+    pub fn assert_silo_not_exists(&self, name: &str) {
+        assert!(
+            !self.silo_exists(name),
+            "Silo '{}' should not exist but does",
+            name
+        );
+    }
+
+    /// Create a commit in the main repo with a file change.
+    pub fn create_commit(&self, file: &str, content: &str, message: &str) {
+        write_file(&self.repo_dir.path().join(file), content);
+        self.git(&["add", file]);
+        self.git(&["commit", "-m", message]);
+    }
+
+    /// Create a commit in a silo with a file change.
+    pub fn create_commit_in_silo(&self, silo_name: &str, file: &str, content: &str, message: &str) {
+        write_file(&self.silo_path(silo_name).join(file), content);
+        self.git_in_silo(silo_name, &["add", file]);
+        self.git_in_silo(silo_name, &["commit", "-m", message]);
+    }
+
+    /// Create an uncommitted file in a silo.
+    pub fn create_uncommitted_file(&self, silo_name: &str, file: &str, content: &str) {
+        write_file(&self.silo_path(silo_name).join(file), content);
+    }
+
+    /// Check if the output indicates success.
+    pub fn assert_success(output: &Output) {
+        assert!(
+            output.status.success(),
+            "Command failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    /// Check if the output indicates failure.
+    pub fn assert_failure(output: &Output) {
+        assert!(
+            !output.status.success(),
+            "Command should have failed but succeeded"
+        );
+    }
+
+    /// Get stdout as a string.
+    pub fn stdout(output: &Output) -> String {
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    /// Get stderr as a string.
+    pub fn stderr(output: &Output) -> String {
+        String::from_utf8_lossy(&output.stderr).to_string()
+    }
+
+    /// Wait up to 5 seconds for a file to exist with content, and return it.
+    /// Returns whatever is there (possibly empty) after the timeout.
+    pub fn wait_for_file(path: &Path) -> String {
+        for _ in 0..50 {
+            if let Ok(contents) = fs::read_to_string(path)
+                && !contents.is_empty()
+            {
+                return contents;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        fs::read_to_string(path).unwrap_or_default()
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// Remotes
+// =============================================================================
+
+impl TestEnv {
+    /// Get the path of the `origin` remote.
+    /// Panics if the environment was built without [`TestEnvBuilder::with_remote`].
+    pub fn remote_path(&self) -> &Path {
+        self.remote_dir
+            .as_ref()
+            .expect("Test environment has no remote (use with_remote)")
+            .path()
+    }
+
+    /// Push a silo's branch to `origin` and set it as the upstream.
+    pub fn push_silo(&self, silo_name: &str) {
+        let output = self.git_in_silo(silo_name, &["push", "-u", "origin", "HEAD"]);
+        Self::assert_success(&output);
+    }
+
+    /// Commit a file change directly on the remote's `branch`, as if pushed
+    /// from another clone. The local repo doesn't see it until it fetches.
+    pub fn create_commit_on_remote(&self, branch: &str, file: &str, content: &str, message: &str) {
+        let clone = TempDir::new().expect("Failed to create clone temp dir");
+        let url = self.remote_path().to_string_lossy().into_owned();
+        Self::assert_success(&git_in(
+            clone.path(),
+            &["clone", "--branch", branch, &url, "."],
+        ));
+        git_in(clone.path(), &["config", "user.email", "test@test.com"]);
+        git_in(clone.path(), &["config", "user.name", "Test User"]);
+        write_file(&clone.path().join(file), content);
+        git_in(clone.path(), &["add", file]);
+        git_in(clone.path(), &["commit", "-m", message]);
+        Self::assert_success(&git_in(clone.path(), &["push", "origin", branch]));
+    }
+}
+
+// =============================================================================
+// Orphans and empty directories
+// =============================================================================
+
+impl TestEnv {
+    /// Create an orphaned silo by creating a fake silo that points to a non-existent main worktree.
+    /// Returns the path to the orphaned silo directory.
+    pub fn create_orphaned_silo(&self, name: &str) -> PathBuf {
+        // First create a real silo so we have the directory structure
+        self.create_silo(name);
+        let silo_path = self.silo_path(name);
+
+        // Modify the .git file to point to a non-existent main worktree
+        let git_file = silo_path.join(".git");
+        let fake_main = PathBuf::from("/tmp/nonexistent-repo-12345/.git/worktrees").join(name);
+        let content = format!("gitdir: {}", fake_main.display());
+        fs::write(&git_file, content).expect("Failed to write .git file");
+
+        // Now we need to unregister this worktree from the main repo since git still tracks it
+        // Just remove it from git's perspective by removing the worktree reference in .git/worktrees
+        let worktree_ref = self.repo_dir.path().join(".git/worktrees").join(name);
+        if worktree_ref.exists() {
+            let _ = fs::remove_dir_all(&worktree_ref);
+        }
+
+        silo_path
+    }
+
+    /// Move the main repository to a new location, as a user moving it would.
+    /// Its silos keep pointing at the old location, which is returned.
+    pub fn move_repo(&mut self) -> PathBuf {
+        let moved = TempDir::new().expect("Failed to create repo temp dir");
+        let old_path = self.repo_dir.path().to_path_buf();
+        // Renaming onto the new, empty directory replaces it
+        fs::rename(&old_path, moved.path()).expect("Failed to move repo");
+        self.repo_dir = moved;
+        old_path
+    }
+
+    /// Create an empty repo directory in the silo storage.
+    /// Returns the path to the empty directory.
+    pub fn create_empty_repo_dir(&self, name: &str) -> PathBuf {
+        let empty_dir = self.silo_dir.path().join(name);
+        fs::create_dir_all(&empty_dir).expect("Failed to create empty repo dir");
+        empty_dir
+    }
+}
+
+// =============================================================================
+// Fake commands and processes
+// =============================================================================
+
+impl TestEnv {
+    /// Directory holding fake commands, put first on PATH by
+    /// [`TestEnv::run_silo_with_fake_commands`].
+    pub fn fake_bin_dir(&self) -> PathBuf {
+        self.silo_dir.path().join(".fake-bin")
+    }
+
+    /// Install a fake command: a shell script run in place of `name`.
+    #[cfg(unix)]
+    pub fn fake_command(&self, name: &str, script: &str) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bin_dir = self.fake_bin_dir();
+        fs::create_dir_all(&bin_dir).expect("Failed to create fake bin dir");
+        let path = bin_dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).expect("Failed to write fake command");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+            .expect("Failed to make fake command executable");
+    }
+
+    /// Run silo with the fake commands installed by [`TestEnv::fake_command`]
+    /// taking precedence over real ones on PATH.
+    pub fn run_silo_with_fake_commands(&self, args: &[&str]) -> Output {
+        let path = format!(
+            "{}:{}",
+            self.fake_bin_dir().display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        self.run_silo_with_env(args, &[("PATH", &path)])
+    }
+
+    /// Run silo with a fake `docker` on PATH that prints its arguments.
+    /// Lets sandbox commands run without Docker installed.
+    #[cfg(unix)]
+    pub fn run_silo_with_fake_docker(&self, args: &[&str]) -> Output {
+        self.fake_command("docker", "echo \"docker $*\"");
+        self.run_silo_with_fake_commands(args)
+    }
+
+    /// Start a long-running command in a silo via `silo exec`, so silo tracks
+    /// it as an active process. Waits until the process is registered.
+    /// The process is killed when the returned guard is dropped.
+    pub fn start_process(&self, silo_name: &str, command: &[&str]) -> FakeProcess {
+        let mut args = vec!["exec", silo_name];
+        args.extend_from_slice(command);
+        self.start_tracked(silo_name, &args)
+    }
+
+    /// Start silo with `args`, for a command that runs a long-running
+    /// process in silo `silo_name` (e.g. `sandbox claude NAME` with a fake
+    /// `docker`), with fake commands on PATH. Waits until the process is
+    /// registered, like [`TestEnv::start_process`].
+    pub fn start_tracked(&self, silo_name: &str, args: &[&str]) -> FakeProcess {
+        let path = format!(
+            "{}:{}",
+            self.fake_bin_dir().display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        let child = self
+            .silo_command(args)
+            .env("PATH", path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start silo exec");
+
+        let pids_dir = self.tracking_path(silo_name).join("pids");
+        for _ in 0..50 {
+            let registered = fs::read_dir(&pids_dir).is_ok_and(|mut e| e.next().is_some());
+            if registered {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+
+        FakeProcess { child, pids_dir }
+    }
+}
+
+/// A process started in a silo by [`TestEnv::start_process`].
+/// Killed, along with the command it runs, when dropped.
+pub struct FakeProcess {
+    child: Child,
+    pids_dir: PathBuf,
+}
+
+impl FakeProcess {
+    /// The pids silo has registered for the silo.
+    pub fn registered_pids(&self) -> Vec<String> {
+        fs::read_dir(&self.pids_dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for FakeProcess {
+    fn drop(&mut self) {
+        for pid in self.registered_pids() {
+            let _ = Command::new("kill").arg(&pid).output();
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,61 @@
+//! When a silo was last worked on.
+//!
+//! Shown by `list` so stale silos stand out. The latest of the silo's last
+//! commit, its most recently modified uncommitted file and its creation, so
+//! a new silo branched from an old commit doesn't look abandoned.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::created;
+use crate::git;
+
+/// Time of the latest activity in a silo with uncommitted `changed` files,
+/// in seconds since the Unix epoch, or None if nothing about it is dated.
+pub fn last_activity(silo_path: &Path, changed: &[git::ChangedFile]) -> Option<u64> {
+    let newest_file = changed
+        .iter()
+        .filter_map(|file| modified_at(&silo_path.join(&file.path)))
+        .max();
+    [
+        git::get_last_commit_time(silo_path),
+        newest_file,
+        created::load(silo_path).map(|c| c.at),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+}
+
+/// How long ago `at` (seconds since the Unix epoch) was. Times in the
+/// future, e.g. from clock skew, count as now.
+pub fn age(at: u64) -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH + Duration::from_secs(at))
+        .unwrap_or_default()
+}
+
+/// Modification time of a file, unless it was deleted.
+fn modified_at(path: &Path) -> Option<u64> {
+    let modified = fs::symlink_metadata(path).ok()?.modified().ok()?;
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_age_of_future_time_is_zero() {
+        let later = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 3_600;
+        assert_eq!(age(later), Duration::ZERO);
+    }
+}
@@ -0,0 +1,74 @@
+//! Agent runs: sandboxed agents started in the background by `silo agent`.
+//!
+//! Each agent run lives in its own silo. The prompt and output log are kept
+//! in the silo's tracking directory so they can be inspected while the agent
+//! works and after it finishes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::metadata;
+use crate::process;
+
+/// Branch prefix for automatically named agent silos.
+pub const NAME_PREFIX: &str = "agent";
+
+/// Get the directory holding a silo's agent run data.
+pub fn agent_dir(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("agent")
+}
+
+/// Get the path of the prompt given to a silo's agent.
+pub fn prompt_path(silo_path: &Path) -> PathBuf {
+    agent_dir(silo_path).join("prompt.md")
+}
+
+/// Get the path of the output log of a silo's agent.
+pub fn log_path(silo_path: &Path) -> PathBuf {
+    agent_dir(silo_path).join("agent.log")
+}
+
+/// Save the prompt for a silo's agent run.
+pub fn write_prompt(silo_path: &Path, prompt: &str) -> Result<PathBuf, String> {
+    let dir = agent_dir(silo_path);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create agent directory: {}", e))?;
+
+    let path = prompt_path(silo_path);
+    metadata::write_atomic(&path, prompt).map_err(|e| format!("Failed to write prompt: {}", e))?;
+    Ok(path)
+}
+
+/// Pick the first free `agent-N` name.
+pub fn next_name(is_taken: impl Fn(&str) -> bool) -> String {
+    (1..)
+        .map(|n| format!("{}-{}", NAME_PREFIX, n))
+        .find(|name| !is_taken(name))
+        .expect("an unused agent name exists")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_under_tracking() {
+        let silo = Path::new("/var/silo/repo-abc/agent-1");
+        assert_eq!(
+            prompt_path(silo),
+            PathBuf::from("/var/silo/repo-abc/.tracking/agent-1/agent/prompt.md")
+        );
+        assert_eq!(
+            log_path(silo),
+            PathBuf::from("/var/silo/repo-abc/.tracking/agent-1/agent/agent.log")
+        );
+    }
+
+    #[test]
+    fn test_next_name_skips_taken() {
+        assert_eq!(next_name(|_| false), "agent-1");
+        assert_eq!(
+            next_name(|name| name == "agent-1" || name == "agent-2"),
+            "agent-3"
+        );
+    }
+}
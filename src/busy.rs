@@ -0,0 +1,46 @@
+//! Busy indicators: while a command runs in a silo (`exec`, `new CMD`),
+//! mark the tmux window it runs in, so the status line shows which silos
+//! are still working.
+//!
+//! The window's `@silo_busy` option holds the silo's name while the command
+//! runs and is unset when it ends, whether it succeeds or fails. Shell
+//! directives can't do this: the wrapper reads them only after silo exits.
+
+use std::process::{Command, Stdio};
+
+use crate::config::Config;
+
+/// The tmux window option set while a command runs.
+pub const TMUX_OPTION: &str = "@silo_busy";
+
+/// A tmux window marked busy; the mark is cleared when this is dropped.
+pub struct Busy {
+    pane: String,
+}
+
+/// Mark the tmux window silo runs in as busy with silo `name`. None outside
+/// tmux, when `busy_indicator` is off, or if tmux can't be reached.
+pub fn start(name: &str, config: &Config) -> Option<Busy> {
+    if !config.busy_indicator() || std::env::var_os("TMUX").is_none() {
+        return None;
+    }
+    let pane = std::env::var("TMUX_PANE").ok()?;
+    tmux(&["set-option", "-w", "-t", &pane, TMUX_OPTION, name]).then_some(Busy { pane })
+}
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        tmux(&["set-option", "-w", "-u", "-t", &self.pane, TMUX_OPTION]);
+    }
+}
+
+/// Run tmux quietly; whether it succeeded.
+fn tmux(args: &[&str]) -> bool {
+    Command::new("tmux")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
@@ -41,10 +41,28 @@ pub fn yellow_uncommitted(value: u32, use_color: bool) -> String {
     format_with_color('~', value, YELLOW, use_color)
 }
 
+/// Format a pass/fail mark: green "✓" or red "✗"
+pub fn check_mark(passed: bool, use_color: bool) -> String {
+    match (passed, use_color) {
+        (true, true) => format!("{}✓{}", GREEN, RESET),
+        (true, false) => "✓".to_string(),
+        (false, true) => format!("{}✗{}", RED, RESET),
+        (false, false) => "✗".to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_check_mark() {
+        assert_eq!(check_mark(true, false), "✓");
+        assert_eq!(check_mark(false, false), "✗");
+        assert!(check_mark(true, true).contains(GREEN));
+        assert!(check_mark(false, true).contains(RED));
+    }
+
     #[test]
     fn test_green_positive_without_color() {
         assert_eq!(green_positive(5, false), "+5");
@@ -0,0 +1,385 @@
+//! Agent commands: run sandboxed agents in fresh silos.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::agent;
+use crate::color;
+use crate::config::Config;
+use crate::git;
+use crate::names;
+use crate::notes;
+use crate::output::Verbosity;
+use crate::process;
+use crate::runner;
+use crate::silo;
+use crate::verify;
+
+use super::list;
+use super::new;
+use super::{resolve_dash, resolve_silo};
+
+/// Options for `silo agent spawn`.
+pub struct SpawnOptions {
+    /// Branch name for the silo (auto-named if None)
+    pub name: Option<String>,
+    /// Prompt text
+    pub prompt: Option<String>,
+    /// File to read the prompt from
+    pub prompt_file: Option<PathBuf>,
+    /// Extra silos to mount read-only
+    pub mounts: Vec<String>,
+    /// Additional arguments to pass to the agent
+    pub args: Vec<String>,
+}
+
+/// Create a silo and start a sandboxed agent in it in the background.
+pub fn spawn(
+    options: &SpawnOptions,
+    config: &Config,
+    dry_run: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let prompt = read_prompt(options)?;
+    let repo_info = git::get_repo_info()?;
+
+    let branch = match &options.name {
+        Some(name) => name.clone(),
+        None => next_free_name(&repo_info, &[]),
+    };
+    spawn_in(
+        &repo_info, &branch, &prompt, options, config, dry_run, verbosity,
+    )
+}
+
+/// Start `count` agents with the same prompt, each in a fresh silo.
+/// `options.name` is used as a prefix: PREFIX-1 .. PREFIX-N.
+pub fn matrix(
+    options: &SpawnOptions,
+    count: u32,
+    config: &Config,
+    dry_run: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    if count == 0 {
+        return Err("--count must be at least 1".to_string());
+    }
+    let prompt = read_prompt(options)?;
+    let repo_info = git::get_repo_info()?;
+
+    let mut branches: Vec<String> = Vec::new();
+    for i in 1..=count {
+        let branch = match &options.name {
+            Some(prefix) => format!("{}-{}", prefix, i),
+            None => next_free_name(&repo_info, &branches),
+        };
+        branches.push(branch);
+    }
+
+    for branch in &branches {
+        spawn_in(
+            &repo_info, branch, &prompt, options, config, dry_run, verbosity,
+        )?;
+    }
+
+    if !verbosity.is_quiet() && !dry_run {
+        println!(
+            "\nCompare results with: silo agent compare {}",
+            branches.join(" ")
+        );
+    }
+    Ok(())
+}
+
+/// Pick the first free agent-N name, also skipping names in `reserved`.
+fn next_free_name(repo_info: &git::RepoInfo, reserved: &[String]) -> String {
+    let repo_root = &repo_info.main_worktree;
+    agent::next_name(|name| {
+        reserved.iter().any(|r| r == name)
+            || git::branch_exists(repo_root, name)
+            || silo::get_silo_path(&repo_info.name, repo_root, name).is_ok_and(|path| path.exists())
+    })
+}
+
+/// Create a silo for `branch` and start the sandboxed agent in it.
+fn spawn_in(
+    repo_info: &git::RepoInfo,
+    branch: &str,
+    prompt: &str,
+    options: &SpawnOptions,
+    config: &Config,
+    dry_run: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let repo_root = &repo_info.main_worktree;
+    let silo_path = silo::get_silo_path(&repo_info.name, repo_root, branch)?;
+
+    let mut args = vec!["-p".to_string(), prompt.to_string()];
+    args.extend(options.args.iter().cloned());
+
+    // The background process is `silo sandbox claude` itself, so agent runs
+    // are tracked, recorded and verified like foreground sandbox runs
+    let exe =
+        std::env::current_exe().map_err(|e| format!("Failed to locate silo executable: {}", e))?;
+    let mut command = vec![
+        exe.display().to_string(),
+        "sandbox".to_string(),
+        "claude".to_string(),
+        names::silo_id(repo_root, branch),
+    ];
+    for mount in &options.mounts {
+        command.push("--mount-silo".to_string());
+        command.push(mount.clone());
+    }
+    if config.verify_command().is_some() {
+        command.push("--verify".to_string());
+    }
+    command.push("--".to_string());
+    command.extend(args);
+
+    if dry_run {
+        println!("Would create silo at: {}", silo_path.display());
+        println!("Would create branch: {}", branch);
+        println!("Would run in background: {}", command.join(" "));
+        return Ok(());
+    }
+
+    let pending = new::create(&silo_path, branch, None, repo_root, config, verbosity)?;
+    agent::write_prompt(&silo_path, prompt)?;
+
+    let log_path = agent::log_path(&silo_path);
+    let pid = runner::Executor::new(&command)
+        .current_dir(&silo_path)
+        .track(&silo_path)
+        .agent("claude")
+        .silo_env(&silo_path)
+        .spawn_detached(&log_path)?;
+    // From here on the agent's PID file keeps prune away
+    drop(pending);
+
+    if !verbosity.is_quiet() {
+        println!("Started agent in '{}' (pid {})", branch, pid);
+        println!("Log: {}", log_path.display());
+    }
+    Ok(())
+}
+
+/// Per-silo results shown by `silo agent compare`.
+struct CompareRow {
+    name: String,
+    running: bool,
+    ahead: u32,
+    added: u32,
+    removed: u32,
+    uncommitted: u32,
+    verified: Option<bool>,
+}
+
+/// Show agent results side by side. Without names, compares every silo of
+/// the current repository that was started by `silo agent`.
+pub fn compare(names: &[String], use_color: bool) -> Result<(), String> {
+    let silos = if names.is_empty() {
+        let repo_root = git::get_repo_root()?;
+        silo::collect_silos_for_repo(&repo_root)?
+            .into_iter()
+            .filter(|s| agent::prompt_path(&s.storage_path).exists())
+            .collect()
+    } else {
+        names
+            .iter()
+            .map(|name| resolve_silo(&resolve_dash(name)?))
+            .collect::<Result<Vec<_>, String>>()?
+    };
+
+    if silos.is_empty() {
+        println!("No agent silos found.");
+        return Ok(());
+    }
+
+    let rows: Vec<CompareRow> = silos
+        .iter()
+        .map(|s| {
+            let branch = s.branch_name();
+            let main_branch = git::get_main_branch(&s.main_worktree);
+            let (ahead, _behind) = git::get_ahead_behind(&s.storage_path, branch, &main_branch);
+            let (added, removed) = git::get_diff_stats(&s.storage_path, branch, &main_branch);
+            CompareRow {
+                name: s.name.clone(),
+                running: !process::list_active(&s.storage_path).is_empty(),
+                ahead,
+                added,
+                removed,
+                uncommitted: git::get_uncommitted_stats(&s.storage_path).total(),
+                verified: verify::load(&s.storage_path).map(|r| r.passed),
+            }
+        })
+        .collect();
+
+    print_compare(&rows, use_color);
+    Ok(())
+}
+
+/// Print compare rows as an aligned table.
+fn print_compare(rows: &[CompareRow], use_color: bool) {
+    let name_width = rows.iter().map(|r| r.name.len()).max().unwrap_or(4).max(4); // minimum width for "NAME"
+
+    println!(
+        "{:<nw$}  {:<7}  {:>7}  {:>14}  {:<5}  UNCOMMITTED",
+        "NAME",
+        "STATUS",
+        "COMMITS",
+        "LINES",
+        "TESTS",
+        nw = name_width
+    );
+    for row in rows {
+        let status = if row.running { "running" } else { "done" };
+        let commits_visible = format!("+{}", row.ahead);
+        let lines_visible = format!("+{} -{}", row.added, row.removed);
+        let commits_padding = 7_usize.saturating_sub(commits_visible.len());
+        let lines_padding = 14_usize.saturating_sub(lines_visible.len());
+        let uncommitted = if row.uncommitted == 0 {
+            String::new()
+        } else {
+            color::yellow_uncommitted(row.uncommitted, use_color)
+        };
+
+        let line = format!(
+            "{:<nw$}  {:<7}  {:>cp$}{}  {:>lp$}{} {}  {}  {}",
+            row.name,
+            status,
+            "",
+            color::green_positive(row.ahead, use_color),
+            "",
+            color::green_positive(row.added, use_color),
+            color::red_negative(row.removed, use_color),
+            list::format_verified(row.verified, use_color),
+            uncommitted,
+            nw = name_width,
+            cp = commits_padding,
+            lp = lines_padding,
+        );
+        println!("{}", line.trim_end());
+    }
+}
+
+/// Summarize what changed in a silo: commits, diffstat and uncommitted files.
+/// With `pipe`, also feeds the diff to `review_command` and saves its output
+/// to the silo's notes.
+pub fn review(name: String, pipe: bool, config: &Config) -> Result<(), String> {
+    let name = resolve_dash(&name)?;
+    let silo = resolve_silo(&name)?;
+    let path = &silo.storage_path;
+    let main_branch = git::get_main_branch(&silo.main_worktree);
+
+    // Check the config before doing any work
+    let review_command = if pipe {
+        Some(config.review_command().ok_or(
+            "No review_command configured. Set review_command in .silo.toml to use --pipe.",
+        )?)
+    } else {
+        None
+    };
+
+    let commits = git::get_commit_summaries(path, "HEAD", &main_branch)?;
+    println!("Commits since {} ({}):", main_branch, commits.len());
+    for commit in &commits {
+        println!("  {}", commit);
+    }
+
+    let stat = git::get_diff_from_base(path, &main_branch, true)?;
+    println!("\nChanges vs {}:", main_branch);
+    if stat.trim().is_empty() {
+        println!("  (none)");
+    }
+    for line in stat.lines() {
+        println!("  {}", line.trim_start());
+    }
+
+    let uncommitted = git::get_uncommitted_files(path);
+    println!("\nUncommitted files ({}):", uncommitted.len());
+    for file in &uncommitted {
+        println!("  {}", file);
+    }
+
+    if let Some(command) = review_command {
+        let diff = git::get_diff_from_base(path, &main_branch, false)?;
+        let review = run_review_command(command, &diff, path)?;
+        println!("\nReview:\n{}", review.trim_end());
+
+        let head = git::get_head_commit(path)?;
+        let heading = format!("Review of {}", &head[..head.len().min(8)]);
+        let notes_path = notes::append(path, &heading, &review)?;
+        println!("\nSaved review to {}", notes_path.display());
+    }
+
+    Ok(())
+}
+
+/// Run the review command in `dir` with the diff on stdin; returns its output.
+fn run_review_command(command: &[String], diff: &str, dir: &Path) -> Result<String, String> {
+    let (program, args) = command.split_first().ok_or("No review command specified")?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| format!("Failed to run review command: {}", e))?;
+
+    let stdin = child.stdin.take();
+    let output = std::thread::scope(|scope| {
+        // Written from another thread while the output is read, so a
+        // reviewer that writes before it has read the whole diff can't
+        // block on a full pipe
+        if let Some(mut stdin) = stdin {
+            scope.spawn(move || {
+                // A reviewer that stops reading early is not an error
+                let _ = stdin.write_all(diff.as_bytes());
+            });
+        }
+        child.wait_with_output()
+    })
+    .map_err(|e| format!("Failed to wait for review command: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Review command failed with exit code {}",
+            output.status.code().unwrap_or(1)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Read the prompt from the command line or a prompt file.
+fn read_prompt(options: &SpawnOptions) -> Result<String, String> {
+    let prompt = match (&options.prompt, &options.prompt_file) {
+        (Some(prompt), _) => prompt.clone(),
+        (None, Some(path)) => fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read prompt file {}: {}", path.display(), e))?,
+        (None, None) => return Err("No prompt given. Use --prompt or --prompt-file.".to_string()),
+    };
+
+    if prompt.trim().is_empty() {
+        return Err("Prompt is empty".to_string());
+    }
+    Ok(prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_review_command_echoing_a_large_diff() {
+        // Far more than a pipe buffer, echoed back while it's written
+        let diff = "+line\n".repeat(200_000);
+        let dir = tempfile::tempdir().unwrap();
+        let review = run_review_command(&["cat".to_string()], &diff, dir.path()).unwrap();
+        assert_eq!(review.len(), diff.len());
+    }
+}
@@ -1,15 +1,32 @@
 //! The `cd` command: navigate to a silo directory.
+//!
+//! With shell integration, the wrapper changes the shell's directory. With
+//! `--spawn`, a new shell is started in the silo instead, for terminals where
+//! the integration can't be loaded (some CI shells and IDE terminals).
+
+use std::path::Path;
 
 use crate::config::Config;
+use crate::exit;
 use crate::git;
+use crate::output::Verbosity;
+use crate::runner::Executor;
 use crate::shell;
+use crate::silo::Silo;
 
 use super::{resolve_dash, resolve_silo};
 
-pub fn run(name: Option<String>, config: &Config) -> Result<(), String> {
+/// Go to silo `name`, or start a shell there with `spawn`. Returns the exit
+/// code to leave with: the spawned shell's own when it fails.
+pub fn run(
+    name: Option<String>,
+    spawn: bool,
+    config: &Config,
+    verbosity: Verbosity,
+) -> Result<i32, String> {
     // If no name provided, navigate to the main worktree
     let Some(name) = name else {
-        return cd_to_main_worktree(config);
+        return cd_to_main_worktree(spawn, config, verbosity);
     };
 
     let name = resolve_dash(&name)?;
@@ -20,16 +37,23 @@ pub fn run(name: Option<String>, config: &Config) -> Result<(), String> {
         && let Some(main_wt) = worktrees.first()
         && main_wt.branch.as_deref() == Some(name.as_str())
     {
+        if spawn {
+            return spawn_shell(&main_wt.path, main_worktree_env(main_wt), verbosity);
+        }
         // Navigate to the main worktree (don't track as "last" silo)
         shell::write_directive("cd", &main_wt.path.display().to_string());
         println!("{}", main_wt.path.display());
         shell::warn_if_not_enabled(config);
-        return Ok(());
+        return Ok(exit::SUCCESS);
     }
 
     // Otherwise, resolve the silo name
     let silo = resolve_silo(&name)?;
 
+    if spawn {
+        return spawn_shell(&silo.storage_path, silo_env(&name, &silo), verbosity);
+    }
+
     // Write directives for shell wrapper
     shell::write_directive("cd", &silo.storage_path.display().to_string());
     shell::write_directive("last", &name);
@@ -37,10 +61,10 @@ pub fn run(name: Option<String>, config: &Config) -> Result<(), String> {
     // Also print path for non-shell-wrapper usage (cd $(silo cd branch))
     println!("{}", silo.storage_path.display());
     shell::warn_if_not_enabled(config);
-    Ok(())
+    Ok(exit::SUCCESS)
 }
 
-fn cd_to_main_worktree(config: &Config) -> Result<(), String> {
+fn cd_to_main_worktree(spawn: bool, config: &Config, verbosity: Verbosity) -> Result<i32, String> {
     let repo_root =
         git::try_get_repo_root().ok_or_else(|| "Not in a git repository".to_string())?;
 
@@ -49,8 +73,87 @@ fn cd_to_main_worktree(config: &Config) -> Result<(), String> {
         .first()
         .ok_or_else(|| "No worktrees found".to_string())?;
 
+    if spawn {
+        return spawn_shell(&main_wt.path, main_worktree_env(main_wt), verbosity);
+    }
+
     shell::write_directive("cd", &main_wt.path.display().to_string());
     println!("{}", main_wt.path.display());
     shell::warn_if_not_enabled(config);
-    Ok(())
+    Ok(exit::SUCCESS)
+}
+
+/// Variables describing a silo for a shell spawned in it. SILO_CURRENT and
+/// SILO_LAST are set as the shell wrapper would after a `cd`.
+fn silo_env(name: &str, silo: &Silo) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("SILO_CURRENT", name.to_string()),
+        ("SILO_PATH", silo.storage_path.display().to_string()),
+        ("SILO_BRANCH", silo.branch_name().to_string()),
+        ("SILO_REPO", silo.repo_name.clone()),
+        (
+            "SILO_MAIN_WORKTREE",
+            silo.main_worktree.display().to_string(),
+        ),
+    ];
+    if let Ok(current) = std::env::var("SILO_CURRENT") {
+        env.push((shell::LAST_ENV, current));
+    }
+    env
+}
+
+/// Variables describing the main worktree for a shell spawned in it.
+fn main_worktree_env(main_wt: &git::Worktree) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("SILO_PATH", main_wt.path.display().to_string()),
+        ("SILO_MAIN_WORKTREE", main_wt.path.display().to_string()),
+    ];
+    if let Some(branch) = &main_wt.branch {
+        env.push(("SILO_BRANCH", branch.clone()));
+    }
+    env
+}
+
+/// The user's shell: `$SHELL`, else the system shell.
+fn user_shell() -> String {
+    #[cfg(unix)]
+    let (var, fallback) = ("SHELL", "/bin/sh");
+    #[cfg(not(unix))]
+    let (var, fallback) = ("COMSPEC", "cmd.exe");
+    std::env::var(var)
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Start the user's shell in `dir` and wait for it to exit, returning its
+/// exit code. The shell is tracked as a process of the silo, so the silo
+/// isn't removed from under it.
+fn spawn_shell(
+    dir: &Path,
+    env: Vec<(&'static str, String)>,
+    verbosity: Verbosity,
+) -> Result<i32, String> {
+    let shell_program = user_shell();
+    if !verbosity.is_quiet() {
+        eprintln!(
+            "Starting {} in {} (exit it to return)",
+            shell_program,
+            dir.display()
+        );
+    }
+
+    // Commands in the new shell must not send directives to the shell
+    // that's waiting for it
+    let mut executor = Executor::new(std::slice::from_ref(&shell_program))
+        .label("shell")
+        .current_dir(dir)
+        .track(dir)
+        .env_remove(shell::DIRECTIVE_FILE_ENV);
+    for (key, value) in env {
+        executor = executor.env(key, value);
+    }
+
+    let completed = executor.run()?;
+    Ok(exit::status_code(completed.status))
 }
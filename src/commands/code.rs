@@ -0,0 +1,129 @@
+//! The `code` command: open a silo in VS Code (or a compatible editor) with
+//! the main worktree alongside it, read-only, for comparing against the
+//! baseline.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::config::Config;
+use crate::metadata;
+use crate::output::Verbosity;
+use crate::process;
+use crate::runner::Executor;
+use crate::silo::{self, Silo};
+
+use super::{resolve_dash, resolve_silo};
+
+/// Name of the generated workspace file.
+const WORKSPACE_NAME: &str = "silo.code-workspace";
+
+/// Write the silo's workspace file and open it with editor_command. With
+/// `print_only`, print the workspace path instead of opening it.
+pub fn run(
+    name: String,
+    print_only: bool,
+    config: &Config,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let name = resolve_dash(&name)?;
+    let silo = resolve_silo(&name)?;
+
+    let path = workspace_path(&silo);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(&workspace(&silo))
+        .map_err(|e| format!("Failed to serialize workspace: {}", e))?;
+    metadata::write_atomic(&path, content + "\n")
+        .map_err(|e| format!("Failed to write workspace {}: {}", path.display(), e))?;
+
+    if print_only {
+        println!("{}", path.display());
+        return Ok(());
+    }
+
+    let mut command = config.for_dir(&silo.storage_path)?.editor_command();
+    command.push(path.display().to_string());
+    let completed = Executor::new(&command)
+        .label("editor command")
+        .current_dir(&silo.storage_path)
+        .run()?;
+    if !completed.status.success() {
+        return Err(format!(
+            "Editor command failed: {} ({})",
+            command.join(" "),
+            completed.status
+        ));
+    }
+    if !verbosity.is_quiet() {
+        println!("Opened {} with its main worktree", name);
+    }
+    Ok(())
+}
+
+/// Where a silo's workspace file goes: its tracking directory, or the temp
+/// directory for external worktrees, which have none.
+fn workspace_path(silo: &Silo) -> PathBuf {
+    if silo::is_silo_path(&silo.storage_path) {
+        process::tracking_dir(&silo.storage_path).join(WORKSPACE_NAME)
+    } else {
+        std::env::temp_dir().join(format!("silo-{}.code-workspace", silo.id()))
+    }
+}
+
+/// A multi-root workspace: the silo, then the main worktree marked
+/// read-only so edits go to the silo.
+fn workspace(silo: &Silo) -> serde_json::Value {
+    let main = &silo.main_worktree;
+    json!({
+        "folders": [
+            {
+                "name": format!("{} (silo)", silo.name),
+                "path": silo.storage_path,
+            },
+            {
+                "name": format!("{} (main worktree, read-only)", silo.repo_name),
+                "path": main,
+            },
+        ],
+        "settings": {
+            "files.readonlyInclude": {
+                readonly_glob(main): true,
+            },
+        },
+    })
+}
+
+/// Glob matching everything under `dir`.
+fn readonly_glob(dir: &Path) -> String {
+    format!("{}/**", dir.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_has_silo_and_readonly_main() {
+        let silo = Silo {
+            name: "feature".to_string(),
+            branch: Some("feature".to_string()),
+            main_worktree: PathBuf::from("/src/app"),
+            storage_path: PathBuf::from("/silos/app-abc/feature"),
+            repo_name: "app".to_string(),
+        };
+
+        let workspace = workspace(&silo);
+
+        let folders = workspace["folders"].as_array().unwrap();
+        assert_eq!(folders[0]["path"], "/silos/app-abc/feature");
+        assert_eq!(folders[1]["path"], "/src/app");
+        assert_eq!(folders[1]["name"], "app (main worktree, read-only)");
+        assert_eq!(
+            workspace["settings"]["files.readonlyInclude"]["/src/app/**"],
+            true
+        );
+    }
+}
@@ -0,0 +1,28 @@
+//! The `complete` command: completion candidates for external tools
+//! (editors, launchers) that want silo-aware pickers without resolving
+//! names themselves.
+
+use crate::complete::{self, Format};
+
+/// Print the candidates for the word after `for_command` (words of a silo
+/// command, e.g. "rm" or "sandbox claude") that start with `word`. With no
+/// command, the candidates are top-level commands.
+pub fn run(for_command: Option<&str>, word: &str, format: Format) -> Result<(), String> {
+    let path: Vec<String> = for_command
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    if !complete::is_command(&path) {
+        return Err(format!(
+            "Unknown command: silo {} (see `silo --help`)",
+            path.join(" ")
+        ));
+    }
+
+    print!(
+        "{}",
+        complete::format(&complete::candidates(&path, word), format)
+    );
+    Ok(())
+}
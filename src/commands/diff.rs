@@ -0,0 +1,69 @@
+//! The `diff` command: compare the work in two silos, e.g. two agents'
+//! solutions to the same task.
+
+use std::io::IsTerminal;
+
+use crate::git;
+use crate::output::Verbosity;
+use crate::pager;
+use crate::silo::Silo;
+
+use super::{resolve_dash, resolve_silo};
+
+/// Options for `silo diff --between`.
+pub struct DiffOptions {
+    /// Compare the silos' files on disk rather than their HEADs
+    pub uncommitted: bool,
+    /// Print a diffstat instead of the diff
+    pub stat: bool,
+    /// Send output through a pager when stdout is a terminal
+    pub pager: bool,
+}
+
+/// Print the difference from silo `a` to silo `b`.
+pub fn between(
+    a: &str,
+    b: &str,
+    options: &DiffOptions,
+    use_color: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let a = resolve_dash(a)?;
+    let b = resolve_dash(b)?;
+    let silo_a = resolve_silo(&a)?;
+    let silo_b = resolve_silo(&b)?;
+
+    // Only silos of one repository share the objects to diff
+    if silo_a.main_worktree != silo_b.main_worktree {
+        return Err(format!(
+            "Cannot diff '{}' and '{}': they belong to different repositories",
+            a, b
+        ));
+    }
+
+    let from = side(&silo_a, options.uncommitted)?;
+    let to = side(&silo_b, options.uncommitted)?;
+    let diff = git::diff_between(&silo_a.storage_path, &from, &to, options.stat, use_color)?;
+
+    if diff.is_empty() {
+        if !verbosity.is_quiet() {
+            eprintln!("No differences between '{}' and '{}'", a, b);
+        }
+        return Ok(());
+    }
+
+    let _pager = (options.pager && std::io::stdout().is_terminal())
+        .then(pager::start)
+        .flatten();
+    print!("{}", diff);
+    Ok(())
+}
+
+/// What a silo is compared by: its HEAD, or a snapshot of its files.
+fn side(silo: &Silo, uncommitted: bool) -> Result<String, String> {
+    if uncommitted {
+        Ok(git::snapshot_tree(&silo.storage_path)?)
+    } else {
+        Ok(git::get_head_commit(&silo.storage_path)?)
+    }
+}
@@ -0,0 +1,245 @@
+//! The `doctor` command: diagnose the silo environment.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::git;
+use crate::output::Verbosity;
+use crate::shell;
+use crate::storage;
+
+/// Outcome of a single diagnostic check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// A single diagnostic check with its result.
+struct Check {
+    status: Status,
+    message: String,
+}
+
+impl Check {
+    fn ok(message: impl Into<String>) -> Self {
+        Self {
+            status: Status::Ok,
+            message: message.into(),
+        }
+    }
+
+    fn warn(message: impl Into<String>) -> Self {
+        Self {
+            status: Status::Warn,
+            message: message.into(),
+        }
+    }
+
+    fn fail(message: impl Into<String>) -> Self {
+        Self {
+            status: Status::Fail,
+            message: message.into(),
+        }
+    }
+}
+
+pub fn run(config: &Config, verbosity: Verbosity) -> Result<(), String> {
+    let expected_mode = config.storage_mode()?;
+
+    let repo_root = git::try_get_repo_root();
+
+    let mut checks = Vec::new();
+    for base_dir in config.get_worktree_dirs()? {
+        checks.extend(storage_checks(
+            &base_dir,
+            expected_mode,
+            repo_root.as_deref(),
+        ));
+    }
+    if let Some(repo_root) = &repo_root {
+        checks.extend(partial_clone_check(
+            &git::promisor_remotes(repo_root),
+            config.prefetch_blobs(),
+        ));
+    }
+    checks.push(shell_check());
+
+    for check in &checks {
+        if verbosity.is_quiet() && check.status == Status::Ok {
+            continue;
+        }
+        let label = match check.status {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        println!("[{:>4}] {}", label, check.message);
+    }
+
+    let failures = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failures > 0 {
+        return Err(format!("doctor found {} problem(s)", failures));
+    }
+    Ok(())
+}
+
+/// Check the storage directory and its filesystem.
+/// When run inside a repository, also compare with the repository's filesystem.
+fn storage_checks(base_dir: &Path, expected_mode: u32, repo_root: Option<&Path>) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    if !base_dir.exists() {
+        checks.push(Check::warn(format!(
+            "Storage directory does not exist yet: {} (created on first `silo new`)",
+            base_dir.display()
+        )));
+        return checks;
+    }
+    checks.push(Check::ok(format!(
+        "Storage directory: {}",
+        base_dir.display()
+    )));
+
+    match storage::mode(base_dir) {
+        Some(mode) if mode & 0o077 != 0 && mode != expected_mode => {
+            checks.push(Check::warn(format!(
+                "Storage permissions are {:04o}, other users can access your silos \
+                 (expected {:04o}; set storage_mode to silence)",
+                mode, expected_mode
+            )));
+        }
+        Some(mode) => checks.push(Check::ok(format!("Storage permissions: {:04o}", mode))),
+        None => checks.push(Check::warn("Could not read storage permissions")),
+    }
+
+    if !storage::is_owned_by_current_user(base_dir) {
+        let owner = storage::owner_name(base_dir).unwrap_or_default();
+        checks.push(Check::warn(format!(
+            "Storage directory is owned by another user ({}), shared storage mode",
+            owner
+        )));
+    }
+
+    if storage::is_writable(base_dir) {
+        checks.push(Check::ok("Storage directory is writable"));
+    } else {
+        checks.push(Check::fail("Storage directory is not writable"));
+        // Remaining probes need to create files
+        return checks;
+    }
+
+    if storage::supports_symlinks(base_dir) {
+        checks.push(Check::ok("Filesystem supports symlinks"));
+    } else {
+        checks.push(Check::warn("Filesystem does not support symlinks"));
+    }
+
+    let fs = storage::probe_filesystem(base_dir);
+    if fs.case_insensitive {
+        checks.push(Check::warn(
+            "Filesystem is case-insensitive (branches or files differing only in case collide)",
+        ));
+    } else {
+        checks.push(Check::ok("Filesystem is case-sensitive"));
+    }
+    if fs.network {
+        checks.push(Check::warn(
+            "Storage is on a network filesystem (slower git operations, locking may be unreliable)",
+        ));
+    }
+
+    if let Some(repo_root) = repo_root {
+        match storage::same_filesystem(base_dir, repo_root) {
+            Some(true) => checks.push(Check::ok("Storage is on the repository's filesystem")),
+            Some(false) => checks.push(Check::ok(format!(
+                "Storage is on a different filesystem than {}",
+                repo_root.display()
+            ))),
+            None => {}
+        }
+    }
+
+    checks
+}
+
+/// Report a partial clone, whose missing blobs new silos have to fetch.
+fn partial_clone_check(remotes: &[git::PromisorRemote], prefetch: bool) -> Option<Check> {
+    let remote = remotes.first()?;
+    let clone = match &remote.filter {
+        Some(filter) => format!(
+            "Repository is a partial clone ({}, {})",
+            remote.name, filter
+        ),
+        None => format!("Repository is a partial clone ({})", remote.name),
+    };
+    Some(if prefetch {
+        Check::ok(format!(
+            "{}: new silos prefetch missing blobs in one batch",
+            clone
+        ))
+    } else {
+        Check::warn(format!(
+            "{}: new silos fetch missing blobs during checkout (set prefetch_blobs = true to fetch them in one batch)",
+            clone
+        ))
+    })
+}
+
+/// Check whether shell integration is active in this shell.
+fn shell_check() -> Check {
+    let directive_file = std::env::var_os(shell::DIRECTIVE_FILE_ENV).map(PathBuf::from);
+    if let Some(path) = directive_file {
+        if shell::is_directive_file_writable(&path) {
+            Check::ok("Shell integration is enabled")
+        } else {
+            Check::fail(format!(
+                "Shell directive file {} is not writable (run `silo` through the shell function to recreate it, or open a new shell)",
+                path.display()
+            ))
+        }
+    } else {
+        Check::warn("Shell integration is not enabled (`silo cd` cannot change directory)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_checks_missing_dir_warns() {
+        let checks = storage_checks(Path::new("/nonexistent/silo/storage"), 0o700, None);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, Status::Warn);
+    }
+
+    #[test]
+    fn test_partial_clone_check() {
+        assert!(partial_clone_check(&[], false).is_none());
+
+        let remotes = [git::PromisorRemote {
+            name: "origin".to_string(),
+            filter: Some("blob:none".to_string()),
+        }];
+        let check = partial_clone_check(&remotes, false).unwrap();
+        assert_eq!(check.status, Status::Warn);
+        assert!(
+            check
+                .message
+                .starts_with("Repository is a partial clone (origin, blob:none)")
+        );
+        assert_eq!(
+            partial_clone_check(&remotes, true).unwrap().status,
+            Status::Ok
+        );
+    }
+
+    #[test]
+    fn test_storage_checks_temp_dir_has_no_failures() {
+        let dir = std::env::temp_dir();
+        let checks = storage_checks(&dir, 0o700, Some(&dir));
+        assert!(checks.iter().all(|c| c.status != Status::Fail));
+    }
+}
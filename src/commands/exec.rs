@@ -1,22 +1,150 @@
 //! The `exec` command: run a command in a silo directory.
 
+use std::path::PathBuf;
+use std::time::Instant;
+
+use serde::Serialize;
+
 use crate::config::Config;
-use crate::runner;
+use crate::exit;
+use crate::last_run;
+use crate::output::Verbosity;
+use crate::runner::{self, CapturedStream, Terminal};
 use crate::shell;
+use crate::silo;
 
+use super::verify;
 use super::{resolve_dash, resolve_silo};
 
-pub fn run(name: String, command: &[String], config: &Config, quiet: bool) -> Result<(), String> {
+/// Run a command in a silo. With `verify_after`, runs verify_command once
+/// the command succeeds and records the result. Without `track`, no PID
+/// file or last run is recorded. Returns the exit code to leave with: the
+/// command's own when it fails.
+pub fn run(
+    name: String,
+    command: &[String],
+    verify_after: bool,
+    terminal: Terminal,
+    track: bool,
+    config: &Config,
+    verbosity: Verbosity,
+) -> Result<i32, String> {
     let name = resolve_dash(&name)?;
     let silo = resolve_silo(&name)?;
 
     // Track this silo as the last used
     shell::write_directive("last", &name);
 
-    runner::run_command(command, &silo.storage_path, config)?;
+    let code = runner::run_command(command, &silo, config, terminal, track)?;
+    if code != exit::SUCCESS {
+        return Ok(code);
+    }
+
+    if verify_after {
+        verify::verify_silo(&silo.storage_path, config, verbosity)?;
+    }
 
-    if !quiet {
+    if !verbosity.is_quiet() {
         eprintln!("[silo: {}]", name);
     }
-    Ok(())
+    Ok(exit::SUCCESS)
+}
+
+/// A captured stream, as printed by `exec --capture`.
+#[derive(Serialize)]
+struct StreamJson {
+    /// The first bytes written, lossily decoded as UTF-8
+    text: String,
+    bytes: u64,
+    truncated: bool,
+}
+
+impl StreamJson {
+    fn new(stream: &CapturedStream) -> Self {
+        Self {
+            text: String::from_utf8_lossy(&stream.head).into_owned(),
+            bytes: stream.bytes,
+            truncated: stream.truncated(),
+        }
+    }
+}
+
+/// The result printed by `exec --capture`.
+#[derive(Serialize)]
+struct CaptureJson<'a> {
+    silo: &'a str,
+    path: PathBuf,
+    command: &'a [String],
+    /// None if the command was killed by a signal
+    exit_code: Option<i32>,
+    /// Time the command ran
+    duration_ms: u128,
+    /// Time including resolving the silo and setting up the run
+    total_ms: u128,
+    stdout: StreamJson,
+    stderr: StreamJson,
+    /// Full output, stdout and stderr interleaved; None for external
+    /// worktrees, which have no tracking directory, and with --no-track
+    log: Option<PathBuf>,
+}
+
+/// Parse the `--capture` limit in KiB, rejecting values too large to count
+/// in bytes.
+pub fn parse_capture_limit(value: &str) -> Result<usize, String> {
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|kb| kb.checked_mul(1024).is_some())
+        .ok_or_else(|| {
+            format!(
+                "Invalid capture limit '{}': expected a number of KiB up to {}",
+                value,
+                usize::MAX / 1024
+            )
+        })
+}
+
+/// Run a command in a silo with its output captured, and print the result
+/// as JSON: exit code, durations, the first `limit_kb` KiB of each stream
+/// and where the full output was logged. Exits with the command's exit
+/// code, like `exec`.
+pub fn capture(
+    name: String,
+    command: &[String],
+    limit_kb: usize,
+    track: bool,
+    config: &Config,
+) -> Result<i32, String> {
+    let start = Instant::now();
+    let name = resolve_dash(&name)?;
+    let silo = resolve_silo(&name)?;
+    shell::write_directive("last", &name);
+
+    let log = (track && silo::is_silo_path(&silo.storage_path))
+        .then(|| last_run::log_path(&silo.storage_path));
+    let captured = runner::capture_command(
+        command,
+        &silo,
+        config,
+        log.as_deref(),
+        limit_kb.saturating_mul(1024),
+        track,
+    )?;
+
+    let result = CaptureJson {
+        silo: &name,
+        path: silo.storage_path.clone(),
+        command,
+        exit_code: captured.completed.status.code(),
+        duration_ms: captured.completed.duration.as_millis(),
+        total_ms: start.elapsed().as_millis(),
+        stdout: StreamJson::new(&captured.stdout),
+        stderr: StreamJson::new(&captured.stderr),
+        log,
+    };
+    let json = serde_json::to_string_pretty(&result)
+        .map_err(|e| format!("Failed to serialize result: {}", e))?;
+    println!("{}", json);
+
+    Ok(exit::status_code(captured.completed.status))
 }
@@ -0,0 +1,78 @@
+//! The `external` command: register git worktrees outside the storage
+//! directory so `cd`, `list` and `exec` can address them like silos.
+
+use std::path::Path;
+
+use crate::external;
+use crate::git;
+use crate::output::Verbosity;
+use crate::silo;
+
+/// Register the worktree at `path`.
+pub fn add(path: &Path, verbosity: Verbosity) -> Result<(), String> {
+    let path = external::worktree_path(path)?;
+    if silo::is_silo_path(&path) {
+        return Err(format!(
+            "{} is in the storage directory; it is a silo already",
+            path.display()
+        ));
+    }
+
+    // Two silos of a repository with the same name couldn't be told apart
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let main = git::get_main_worktree_from_silo(&path)
+        .ok_or_else(|| format!("{} is not a linked git worktree", path.display()))?;
+    if let Some(existing) = silo::collect_silos_for_repo(&main)?
+        .into_iter()
+        .find(|s| s.name == name && s.storage_path != path)
+    {
+        return Err(format!(
+            "{} already has a silo named '{}' at {}",
+            main.display(),
+            name,
+            existing.storage_path.display()
+        ));
+    }
+
+    let added = external::add(&silo::get_silo_base_dir()?, &path)?;
+    if !verbosity.is_quiet() {
+        if added {
+            println!(
+                "Registered external worktree '{}': {}",
+                name,
+                path.display()
+            );
+        } else {
+            println!("Already registered: {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Unregister the worktree at `path`, leaving the worktree itself alone.
+pub fn remove(path: &Path, verbosity: Verbosity) -> Result<(), String> {
+    let path =
+        std::path::absolute(path).map_err(|e| format!("Invalid path {}: {}", path.display(), e))?;
+    if !external::remove(&silo::get_silo_base_dir()?, &path)? {
+        return Err(format!(
+            "{} is not a registered external worktree",
+            path.display()
+        ));
+    }
+    if !verbosity.is_quiet() {
+        println!(
+            "Unregistered {} (the worktree was left in place)",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// List registered worktrees, marking those that no longer exist.
+pub fn list() -> Result<(), String> {
+    for path in external::load(&silo::get_silo_base_dir()?) {
+        let missing = if path.is_dir() { "" } else { " (missing)" };
+        println!("{}{}", path.display(), missing);
+    }
+    Ok(())
+}
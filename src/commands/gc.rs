@@ -1,138 +1,709 @@
-//! The `gc` command: garbage collect orphaned silos and empty directories.
+//! The `gc` command: garbage collect orphaned silos, stale tracking data
+//! (including sandbox scratch directories) and empty directories.
+//!
+//! Orphans whose repository was moved to a location known to the
+//! [`registry`] are relinked to it with `git worktree repair` rather than
+//! removed. Orphans whose git data is still readable and holds commits that no
+//! remote has are kept unless `--force` is given, in which case the commits
+//! are saved to a bundle in `{storage}/.bundles` first.
+//!
+//! It also tops up the pools of idle silos that `silo reuse` hands out, for
+//! repositories with a `pool_size`.
+//!
+//! Safe to run unattended (e.g. nightly from cron): without a terminal it
+//! only removes things with `--yes`, `--json` prints what was found and
+//! removed, and the exit code is [`exit::ITEMS_REMAIN`] whenever something
+//! is left to clean up.
 
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::exit;
+use crate::git;
+use crate::messages;
+use crate::metrics;
+use crate::output::Verbosity;
+use crate::pending;
+use crate::pool;
 use crate::prompt;
+use crate::registry;
 use crate::silo;
-use std::fs;
+use crate::storage::{self, StorageLock};
+
+use super::orphan_branches;
 
-pub fn run(dry_run: bool, force: bool, quiet: bool) -> Result<(), String> {
-    // Collect orphaned silos and initially empty directories
-    let orphaned_silos = silo::collect_orphaned_silos()?;
-    let initial_empty_dirs = silo::collect_empty_repo_dirs()?;
+/// Options for `silo gc`.
+#[derive(Debug, Default)]
+pub struct GcOptions {
+    /// Print what was found and removed as JSON instead of prose
+    pub json: bool,
+    /// Remove without asking for confirmation
+    pub yes: bool,
+}
+
+/// Something `gc` found to clean up.
+#[derive(Debug, Serialize)]
+struct Item {
+    path: PathBuf,
+    /// For orphaned silos, the main worktree that no longer exists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    missing_main_worktree: Option<PathBuf>,
+    /// For orphaned silos, commits no remote has (None if git can't read them)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unpushed_commits: Option<u32>,
+    /// Bundle the unpushed commits were saved to before removal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundle: Option<PathBuf>,
+    /// For orphaned silos, where their repository was moved to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    relink_to: Option<PathBuf>,
+    removed: bool,
+    /// Whether the orphan was relinked to its moved repository
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    relinked: bool,
+}
 
-    let total_orphaned = orphaned_silos.len();
-    let initial_empty = initial_empty_dirs.len();
+impl Item {
+    fn new(path: PathBuf) -> Self {
+        Item {
+            path,
+            missing_main_worktree: None,
+            unpushed_commits: None,
+            bundle: None,
+            relink_to: None,
+            removed: false,
+            relinked: false,
+        }
+    }
+
+    /// Whether removing this would lose commits that exist nowhere else.
+    fn has_unpushed_commits(&self) -> bool {
+        self.unpushed_commits.is_some_and(|n| n > 0)
+    }
+}
+
+/// Everything `gc` found, as printed by `gc --json`.
+#[derive(Debug, Default, Serialize)]
+struct Report {
+    orphaned_silos: Vec<Item>,
+    stale_tracking_dirs: Vec<Item>,
+    empty_dirs: Vec<Item>,
+}
+
+impl Report {
+    fn items(&self) -> impl Iterator<Item = &Item> {
+        self.orphaned_silos
+            .iter()
+            .chain(&self.stale_tracking_dirs)
+            .chain(&self.empty_dirs)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.items().next().is_none()
+    }
 
-    if total_orphaned == 0 && initial_empty == 0 {
-        if !quiet {
+    /// Number of items that were found but neither removed nor relinked.
+    fn remaining(&self) -> usize {
+        self.items()
+            .filter(|item| !item.removed && !item.relinked)
+            .count()
+    }
+}
+
+/// Clean up orphaned silos, stale tracking directories and empty
+/// directories. Returns the exit code to leave with (see [`finish`]).
+pub fn run(
+    options: &GcOptions,
+    config: &Config,
+    dry_run: bool,
+    force: bool,
+    verbosity: Verbosity,
+) -> Result<i32, String> {
+    // The current repository may be where some orphans' repository moved to
+    let base_dir = silo::get_silo_base_dir()?;
+    if let Some(repo_root) = git::try_get_repo_root()
+        && !silo::is_silo_path(&repo_root)
+        && let Err(e) = registry::register_repo(&base_dir, &repo_root)
+    {
+        eprintln!("Warning: {}", e);
+    }
+
+    // Prose goes to stdout unless -q or --json; a dry run always reports
+    let text = !options.json && (!verbosity.is_quiet() || dry_run);
+    fill_pools(config, dry_run, text)?;
+
+    let mut report = collect(&base_dir)?;
+
+    if report.is_empty() {
+        if text {
             println!("No orphaned silos or empty directories to clean up.");
         }
-        return Ok(());
+        return finish(&report, options);
+    }
+
+    if text {
+        print_found(&report);
+    }
+
+    if dry_run {
+        if text {
+            print_dry_run_summary(&report);
+        }
+        return finish(&report, options);
+    }
+
+    if !force && !options.yes && !prompt::confirm(&confirm_message(&report)) {
+        if text {
+            println!("{}", messages::ABORTED);
+        }
+        return finish(&report, options);
+    }
+
+    // Relink or remove orphaned silos first, saving commits no remote has
+    for item in &mut report.orphaned_silos {
+        if let Some(new_main) = &item.relink_to {
+            match git::repair_worktree(new_main, &item.path, Verbosity::Quiet) {
+                Ok(()) => {
+                    item.relinked = true;
+                    if text {
+                        println!(
+                            "Relinked orphaned silo {} to {}",
+                            item.path.display(),
+                            new_main.display()
+                        );
+                    }
+                }
+                Err(e) => eprintln!(
+                    "Warning: Failed to relink orphaned silo {}: {}",
+                    item.path.display(),
+                    e
+                ),
+            }
+            // Never remove a silo whose repository still exists
+            continue;
+        }
+        if item.has_unpushed_commits() {
+            if !force {
+                if !verbosity.is_quiet() {
+                    eprintln!(
+                        "Skipping orphaned silo {}: {} not pushed to any remote. \
+                         Use --force to save them to a bundle and remove it.",
+                        item.path.display(),
+                        commits(item.unpushed_commits.unwrap_or(0))
+                    );
+                }
+                continue;
+            }
+            // git's own output would break the JSON on stdout
+            let git_verbosity = if options.json {
+                Verbosity::Quiet
+            } else {
+                verbosity
+            };
+            match save_bundle(&item.path, git_verbosity) {
+                Ok(bundle) => {
+                    if text {
+                        println!("Saved unpushed commits to {}", bundle.display());
+                    }
+                    item.bundle = Some(bundle);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Warning: Not removing orphaned silo {}: {}",
+                        item.path.display(),
+                        e
+                    );
+                    continue;
+                }
+            }
+        }
+        item.removed = remove(&item.path, "orphaned silo", text, |_| true);
+    }
+    metrics::record_removed(
+        "gc",
+        report
+            .orphaned_silos
+            .iter()
+            .filter(|item| item.removed)
+            .count(),
+    );
+    // Then tracking data left behind by silos that no longer exist
+    for item in &mut report.stale_tracking_dirs {
+        item.removed = remove(&item.path, "stale tracking directory", text, |path| {
+            !pending::is_pending_tracking_dir(path)
+        });
+    }
+
+    // Re-collect empty directories after removing orphaned silos
+    // This catches directories that became empty as a result
+    report.empty_dirs = silo::collect_empty_repo_dirs()?
+        .into_iter()
+        .map(Item::new)
+        .collect();
+    for item in &mut report.empty_dirs {
+        item.removed = remove(&item.path, "empty directory", text, silo::is_empty_repo_dir);
     }
 
-    // Report what we found
-    if !quiet || dry_run {
-        if total_orphaned > 0 {
-            println!("Found {} orphaned silo(s):", total_orphaned);
-            for orphan in &orphaned_silos {
-                println!("  {}", orphan.storage_path.display());
+    if text {
+        println!("\n{}", cleaned_up_message(&report));
+    }
+
+    finish(&report, options)
+}
+
+/// Top up the pool of every known repository with a `pool_size`, and of
+/// the current one. Pools are filled even if nothing is cleaned up.
+fn fill_pools(config: &Config, dry_run: bool, text: bool) -> Result<(), String> {
+    // A pool is an optimization: nothing here is worth failing gc for
+    let mut repos = orphan_branches::known_repos().unwrap_or_else(|e| {
+        eprintln!(
+            "Warning: Failed to find repositories to fill pools for: {}",
+            e
+        );
+        Vec::new()
+    });
+    if let Some(repo_root) = git::try_get_repo_root()
+        && !silo::is_silo_path(&repo_root)
+        && !repos.contains(&repo_root)
+    {
+        repos.push(repo_root);
+    }
+    for repo in repos {
+        let repo_config = match config.for_dir(&repo) {
+            Ok(repo_config) => repo_config,
+            Err(e) => {
+                eprintln!("Warning: Skipping the pool of {}: {}", repo.display(), e);
+                continue;
+            }
+        };
+        let size = repo_config.pool_size();
+        if size == 0 {
+            continue;
+        }
+        if dry_run {
+            let existing = match pool::worktrees(&repo) {
+                Ok(worktrees) => worktrees.len(),
+                Err(e) => {
+                    eprintln!("Warning: Skipping the pool of {}: {}", repo.display(), e);
+                    continue;
+                }
+            };
+            let missing = size.saturating_sub(existing);
+            if text && missing > 0 {
                 println!(
-                    "    (main worktree missing: {})",
-                    orphan.missing_main_worktree.display()
+                    "Would add {} to the pool of {}",
+                    silos(missing),
+                    repo.display()
                 );
             }
+            continue;
         }
+        // Reported below; git's output for each worktree is just noise
+        match pool::fill(&repo, size, &repo_config, Verbosity::Quiet) {
+            Ok(0) => {}
+            Ok(added) if text => {
+                println!("Added {} to the pool of {}", silos(added), repo.display())
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!(
+                "Warning: Failed to fill the pool of {}: {}",
+                repo.display(),
+                e
+            ),
+        }
+    }
+    Ok(())
+}
 
-        if initial_empty > 0 {
-            println!(
-                "Found {} empty repo director{}:",
-                initial_empty,
-                if initial_empty == 1 { "y" } else { "ies" }
-            );
-            for dir in &initial_empty_dirs {
-                println!("  {}", dir.display());
+/// Collect everything there is to clean up.
+fn collect(base_dir: &Path) -> Result<Report, String> {
+    let known_repos = registry::load(base_dir);
+    // Only consider our own orphans: on shared storage another user's main
+    // worktree may simply be unreadable to us rather than missing.
+    let orphaned_silos = silo::collect_orphaned_silos()?
+        .into_iter()
+        .filter(|o| storage::is_owned_by_current_user(&o.storage_path))
+        .map(|o| Item {
+            relink_to: find_moved_repo(&known_repos, &o.storage_path, &o.missing_main_worktree),
+            missing_main_worktree: Some(o.missing_main_worktree),
+            // The repository data usually went with the main worktree, but
+            // if git can still read it, it may hold the only copy of commits
+            unpushed_commits: git::count_unpushed_commits(&o.storage_path),
+            ..Item::new(o.storage_path)
+        })
+        .collect();
+    let stale_tracking_dirs = silo::collect_stale_tracking_dirs()?
+        .into_iter()
+        .filter(|p| storage::is_owned_by_current_user(p))
+        .map(Item::new)
+        .collect();
+    let empty_dirs = silo::collect_empty_repo_dirs()?
+        .into_iter()
+        .map(Item::new)
+        .collect();
+
+    Ok(Report {
+        orphaned_silos,
+        stale_tracking_dirs,
+        empty_dirs,
+    })
+}
+
+/// Print the JSON report if requested. Returns the exit code to leave
+/// with: [`exit::ITEMS_REMAIN`] if anything is left to clean up.
+fn finish(report: &Report, options: &GcOptions) -> Result<i32, String> {
+    if options.json {
+        let json = serde_json::to_string_pretty(report)
+            .map_err(|e| format!("Failed to serialize gc report: {}", e))?;
+        println!("{}", json);
+    }
+    if report.remaining() > 0 {
+        return Ok(exit::ITEMS_REMAIN);
+    }
+    Ok(exit::SUCCESS)
+}
+
+fn print_found(report: &Report) {
+    if !report.orphaned_silos.is_empty() {
+        println!("Found {}:", orphaned(report.orphaned_silos.len()));
+        for item in &report.orphaned_silos {
+            println!("  {}", item.path.display());
+            if let Some(missing) = &item.missing_main_worktree {
+                println!("    (main worktree missing: {})", missing.display());
+            }
+            if let Some(new_main) = &item.relink_to {
+                println!(
+                    "    (repository moved to {}; will be relinked)",
+                    new_main.display()
+                );
+            } else if item.has_unpushed_commits() {
+                println!(
+                    "    ({} not pushed to any remote; kept unless --force)",
+                    commits(item.unpushed_commits.unwrap_or(0))
+                );
             }
         }
     }
 
-    if dry_run {
-        // In dry-run mode, we can't know exactly how many directories will become
-        // empty after removing orphaned silos, but we note there may be more
-        if total_orphaned > 0 {
-            println!(
-                "\nWould remove {} orphaned silo(s) and at least {} empty director{}.",
-                total_orphaned,
-                initial_empty,
-                if initial_empty == 1 { "y" } else { "ies" }
-            );
-            println!("(Additional directories may become empty after removing orphaned silos.)");
-        } else {
-            println!(
-                "\nWould remove {} empty director{}.",
-                initial_empty,
-                if initial_empty == 1 { "y" } else { "ies" }
-            );
+    if !report.stale_tracking_dirs.is_empty() {
+        println!("Found {}:", stale(report.stale_tracking_dirs.len()));
+        for item in &report.stale_tracking_dirs {
+            println!("  {}", item.path.display());
         }
-        return Ok(());
-    }
-
-    // Confirm before proceeding
-    if !force {
-        let message = if total_orphaned > 0 {
-            format!(
-                "Remove {} orphaned silo(s) and {} empty director{} (plus any that become empty)?",
-                total_orphaned,
-                initial_empty,
-                if initial_empty == 1 { "y" } else { "ies" }
-            )
-        } else {
-            format!(
-                "Remove {} empty director{}?",
-                initial_empty,
-                if initial_empty == 1 { "y" } else { "ies" }
-            )
-        };
-        if !prompt::confirm(&message) {
-            println!("Aborted.");
-            return Ok(());
+    }
+
+    if !report.empty_dirs.is_empty() {
+        println!("Found {}:", empty(report.empty_dirs.len()));
+        for item in &report.empty_dirs {
+            println!("  {}", item.path.display());
         }
     }
+}
 
-    // Remove orphaned silos first
-    let mut removed_silos = 0;
-    for orphan in orphaned_silos {
-        if let Err(e) = fs::remove_dir_all(&orphan.storage_path) {
+fn print_dry_run_summary(report: &Report) {
+    let relink = relink_count(report);
+    let total_orphaned = report.orphaned_silos.len() - relink;
+    let initial_empty = report.empty_dirs.len();
+
+    if relink > 0 {
+        println!(
+            "\nWould relink {} to their moved repository.",
+            orphaned(relink)
+        );
+    }
+
+    if !report.stale_tracking_dirs.is_empty() {
+        println!(
+            "\nWould remove {}.",
+            stale(report.stale_tracking_dirs.len())
+        );
+    }
+    // In dry-run mode, we can't know exactly how many directories will become
+    // empty after removing orphaned silos, but we note there may be more
+    if total_orphaned > 0 {
+        println!(
+            "\nWould remove {} and at least {}.",
+            orphaned(total_orphaned),
+            empty(initial_empty)
+        );
+        println!("(Additional directories may become empty after removing orphaned silos.)");
+    } else if initial_empty > 0 {
+        println!("\nWould remove {}.", empty(initial_empty));
+    }
+}
+
+fn confirm_message(report: &Report) -> String {
+    let relink = relink_count(report);
+    let total_orphaned = report.orphaned_silos.len() - relink;
+    let initial_empty = report.empty_dirs.len();
+    let mut removal = Vec::new();
+    if total_orphaned > 0 {
+        removal.push(orphaned(total_orphaned));
+    }
+    if !report.stale_tracking_dirs.is_empty() {
+        removal.push(stale(report.stale_tracking_dirs.len()));
+    }
+    if total_orphaned > 0 {
+        removal.push(format!(
+            "{} (plus any that become empty)",
+            empty(initial_empty)
+        ));
+    } else if initial_empty > 0 {
+        removal.push(empty(initial_empty));
+    }
+    let removal = messages::and_list(&removal);
+    match (relink > 0, removal.is_empty()) {
+        (true, true) => format!("Relink {} to their moved repository?", orphaned(relink)),
+        (true, false) => format!(
+            "Relink {} to their moved repository and remove {}?",
+            orphaned(relink),
+            removal
+        ),
+        (false, _) => format!("Remove {}?", removal),
+    }
+}
+
+/// Summary of what was relinked and removed, covering every kind of item.
+fn cleaned_up_message(report: &Report) -> String {
+    let removed = |items: &[Item]| items.iter().filter(|item| item.removed).count();
+    let cleaned = messages::and_list(&[
+        orphaned(removed(&report.orphaned_silos)),
+        stale(removed(&report.stale_tracking_dirs)),
+        empty(removed(&report.empty_dirs)),
+    ]);
+    let relinked = report
+        .orphaned_silos
+        .iter()
+        .filter(|item| item.relinked)
+        .count();
+    if relinked > 0 {
+        format!(
+            "Relinked {} and cleaned up {}.",
+            orphaned(relinked),
+            cleaned
+        )
+    } else {
+        format!("Cleaned up {}.", cleaned)
+    }
+}
+
+/// Number of orphaned silos whose repository was found at a new location.
+fn relink_count(report: &Report) -> usize {
+    report
+        .orphaned_silos
+        .iter()
+        .filter(|item| item.relink_to.is_some())
+        .count()
+}
+
+/// Find where an orphaned silo's repository was moved: a known repository
+/// with the same origin whose worktree records still list this silo.
+fn find_moved_repo(
+    known_repos: &[registry::KnownRepo],
+    silo_path: &Path,
+    missing_main_worktree: &Path,
+) -> Option<PathBuf> {
+    let admin_name = git::get_gitdir_from_silo(silo_path)?
+        .file_name()?
+        .to_os_string();
+    registry::other_locations(known_repos, missing_main_worktree)
+        .into_iter()
+        .find(|candidate| {
+            let gitdir_file = candidate
+                .join(".git/worktrees")
+                .join(&admin_name)
+                .join("gitdir");
+            fs::read_to_string(gitdir_file)
+                .is_ok_and(|content| Path::new(content.trim()) == silo_path.join(".git"))
+        })
+}
+
+/// Remove a directory, warning if that fails. Returns whether it was removed.
+///
+/// A `silo new` may have started using the directory since it was found,
+/// so removal happens under the storage lock `new` creates silos under, and
+/// only if `still_removable` holds once the lock is held.
+fn remove(
+    path: &Path,
+    what: &str,
+    text: bool,
+    still_removable: impl FnOnce(&Path) -> bool,
+) -> bool {
+    // Relocated tracking data is outside every storage directory
+    let base_dir = match silo::get_containing_base_dir(path) {
+        Some(dir) => Ok(dir),
+        None => silo::get_silo_base_dir(),
+    };
+    let _lock = match base_dir.and_then(|dir| StorageLock::acquire(&dir)) {
+        Ok(lock) => lock,
+        Err(e) => {
             eprintln!(
-                "Warning: Failed to remove orphaned silo {}: {}",
-                orphan.storage_path.display(),
+                "Warning: Failed to remove {} {}: {}",
+                what,
+                path.display(),
                 e
             );
-        } else {
-            removed_silos += 1;
-            if !quiet {
-                println!("Removed orphaned silo: {}", orphan.storage_path.display());
-            }
+            return false;
         }
+    };
+    if !still_removable(path) {
+        if text {
+            println!("Skipped {} (now in use): {}", what, path.display());
+        }
+        return false;
     }
-
-    // Re-collect empty directories after removing orphaned silos
-    // This catches directories that became empty as a result
-    let empty_dirs = silo::collect_empty_repo_dirs()?;
-
-    // Remove empty directories
-    let mut removed_dirs = 0;
-    for dir in empty_dirs {
-        if let Err(e) = fs::remove_dir_all(&dir) {
+    match fs::remove_dir_all(path) {
+        Ok(()) => {
+            if text {
+                println!("Removed {}: {}", what, path.display());
+            }
+            true
+        }
+        Err(e) => {
             eprintln!(
-                "Warning: Failed to remove empty directory {}: {}",
-                dir.display(),
+                "Warning: Failed to remove {} {}: {}",
+                what,
+                path.display(),
                 e
             );
-        } else {
-            removed_dirs += 1;
-            if !quiet {
-                println!("Removed empty directory: {}", dir.display());
-            }
+            false
         }
     }
+}
 
-    if !quiet {
-        println!(
-            "\nCleaned up {} orphaned silo(s) and {} empty director{}.",
-            removed_silos,
-            removed_dirs,
-            if removed_dirs == 1 { "y" } else { "ies" }
+/// Save an orphaned silo's unpushed commits to a bundle in the storage
+/// directory's [`silo::BUNDLE_DIR`], which gc never removes.
+fn save_bundle(silo_path: &Path, verbosity: Verbosity) -> Result<PathBuf, String> {
+    let repo_dir = silo_path.parent().ok_or("Silo has no parent directory")?;
+    let base_dir = repo_dir.parent().ok_or("Silo has no storage directory")?;
+    let bundle_dir = base_dir.join(silo::BUNDLE_DIR);
+    fs::create_dir_all(&bundle_dir)
+        .map_err(|e| format!("Failed to create bundle directory: {}", e))?;
+
+    let name = format!(
+        "{}-{}",
+        repo_dir.file_name().unwrap_or_default().to_string_lossy(),
+        silo_path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    let mut bundle = bundle_dir.join(format!("{}.bundle", name));
+    let mut n = 1;
+    while bundle.exists() {
+        n += 1;
+        bundle = bundle_dir.join(format!("{}-{}.bundle", name, n));
+    }
+
+    git::create_unpushed_bundle(silo_path, &bundle, verbosity)?;
+    Ok(bundle)
+}
+
+/// "1 commit" / "2 commits"
+fn commits(n: u32) -> String {
+    messages::count(n as usize, "commit", "commits")
+}
+
+/// "1 orphaned silo" / "2 orphaned silos"
+fn orphaned(n: usize) -> String {
+    messages::count(n, "orphaned silo", "orphaned silos")
+}
+
+/// "1 empty repo directory" / "2 empty repo directories"
+fn empty(n: usize) -> String {
+    messages::count(n, "empty repo directory", "empty repo directories")
+}
+
+fn silos(n: usize) -> String {
+    messages::count(n, "silo", "silos")
+}
+
+/// "1 stale tracking directory" / "2 stale tracking directories"
+fn stale(n: usize) -> String {
+    messages::count(n, "stale tracking directory", "stale tracking directories")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_remaining() {
+        let mut report = Report::default();
+        assert!(report.is_empty());
+        assert_eq!(report.remaining(), 0);
+
+        report
+            .orphaned_silos
+            .push(Item::new(PathBuf::from("/s/repo/a")));
+        report.empty_dirs.push(Item {
+            removed: true,
+            ..Item::new(PathBuf::from("/s/other"))
+        });
+        assert!(!report.is_empty());
+        assert_eq!(report.remaining(), 1);
+    }
+
+    #[test]
+    fn test_messages_cover_every_kind_of_item() {
+        let mut report = Report {
+            orphaned_silos: vec![
+                Item::new(PathBuf::from("/s/repo/a")),
+                Item {
+                    relink_to: Some(PathBuf::from("/moved/repo")),
+                    ..Item::new(PathBuf::from("/s/repo/b"))
+                },
+            ],
+            stale_tracking_dirs: vec![Item::new(PathBuf::from("/s/repo/.tracking/c"))],
+            empty_dirs: Vec::new(),
+        };
+        assert_eq!(
+            confirm_message(&report),
+            "Relink 1 orphaned silo to their moved repository and remove 1 orphaned silo, \
+             1 stale tracking directory and 0 empty repo directories (plus any that become empty)?"
+        );
+
+        report.orphaned_silos[0].removed = true;
+        report.orphaned_silos[1].relinked = true;
+        report.stale_tracking_dirs[0].removed = true;
+        assert_eq!(
+            cleaned_up_message(&report),
+            "Relinked 1 orphaned silo and cleaned up 1 orphaned silo, \
+             1 stale tracking directory and 0 empty repo directories."
+        );
+
+        report.orphaned_silos.remove(0);
+        assert_eq!(
+            confirm_message(&report),
+            "Relink 1 orphaned silo to their moved repository and remove 1 stale tracking directory?"
+        );
+        report.stale_tracking_dirs.clear();
+        assert_eq!(
+            confirm_message(&report),
+            "Relink 1 orphaned silo to their moved repository?"
         );
     }
 
-    Ok(())
+    #[test]
+    fn test_report_json() {
+        let report = Report {
+            orphaned_silos: vec![Item {
+                missing_main_worktree: Some(PathBuf::from("/gone/repo")),
+                ..Item::new(PathBuf::from("/s/repo/a"))
+            }],
+            stale_tracking_dirs: Vec::new(),
+            empty_dirs: vec![Item::new(PathBuf::from("/s/other"))],
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "orphaned_silos": [
+                    {"path": "/s/repo/a", "missing_main_worktree": "/gone/repo", "removed": false}
+                ],
+                "stale_tracking_dirs": [],
+                "empty_dirs": [{"path": "/s/other", "removed": false}],
+            })
+        );
+    }
 }
@@ -0,0 +1,291 @@
+//! The `hooks` command: git hooks that steer commits and pushes away from
+//! protected branches and towards silos.
+//!
+//! The installed hooks are small shell scripts that call back into silo
+//! (`silo hooks run`), so changes to protected_branches and
+//! protected_branch_policy apply without reinstalling them.
+
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, ProtectedBranchPolicy};
+use crate::git;
+use crate::output::Verbosity;
+
+/// Hooks silo installs.
+const HOOKS: &[&str] = &["pre-commit", "pre-push"];
+
+/// Line that marks a hook as installed by silo.
+const MARKER: &str = "# Installed by `silo hooks install`";
+
+/// Suffix of a hook that was replaced with --force, restored on uninstall.
+const BACKUP_SUFFIX: &str = ".orig";
+
+/// Install the hooks in the repository's hooks directory. Hooks silo didn't
+/// install are left alone unless `force` is given, which moves them aside.
+pub fn install(dry_run: bool, force: bool, verbosity: Verbosity) -> Result<(), String> {
+    let dir = hooks_dir()?;
+    let silo_bin = std::env::current_exe()
+        .map_err(|e| format!("Failed to get silo path: {}", e))?
+        .display()
+        .to_string();
+
+    let foreign: Vec<&str> = HOOKS
+        .iter()
+        .copied()
+        .filter(|hook| is_foreign(&dir.join(hook)))
+        .collect();
+    if !foreign.is_empty() && !force {
+        return Err(format!(
+            "Hooks not installed by silo already exist in {}: {} (use --force to move them aside)",
+            dir.display(),
+            foreign.join(", ")
+        ));
+    }
+    // Moving another hook aside would overwrite the one kept there before
+    let backed_up: Vec<String> = foreign
+        .iter()
+        .map(|hook| backup_path(&dir.join(hook)))
+        .filter(|backup| backup.exists())
+        .map(|backup| backup.display().to_string())
+        .collect();
+    if !backed_up.is_empty() {
+        return Err(format!(
+            "Hooks moved aside earlier are still kept at {}; restore or remove them first",
+            backed_up.join(", ")
+        ));
+    }
+
+    if dry_run {
+        for hook in HOOKS {
+            println!("Would install {}", dir.join(hook).display());
+        }
+        return Ok(());
+    }
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    for hook in HOOKS {
+        let path = dir.join(hook);
+        if foreign.contains(hook) {
+            let backup = backup_path(&path);
+            fs::rename(&path, &backup)
+                .map_err(|e| format!("Failed to move {} aside: {}", path.display(), e))?;
+            if !verbosity.is_quiet() {
+                println!("Moved existing {} to {}", hook, backup.display());
+            }
+        }
+        write_hook(&path, &script(hook, &silo_bin))?;
+    }
+    if !verbosity.is_quiet() {
+        println!("Installed {} in {}", HOOKS.join(" and "), dir.display());
+    }
+    Ok(())
+}
+
+/// Remove the hooks silo installed, restoring any it moved aside.
+pub fn uninstall(dry_run: bool, verbosity: Verbosity) -> Result<(), String> {
+    let dir = hooks_dir()?;
+    for hook in HOOKS {
+        let path = dir.join(hook);
+        if !is_ours(&path) {
+            continue;
+        }
+        let backup = backup_path(&path);
+        if dry_run {
+            println!("Would remove {}", path.display());
+            continue;
+        }
+        fs::remove_file(&path)
+            .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        if backup.exists() {
+            fs::rename(&backup, &path)
+                .map_err(|e| format!("Failed to restore {}: {}", backup.display(), e))?;
+        }
+        if !verbosity.is_quiet() {
+            println!("Removed {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Run a hook, as called by the installed scripts. Git runs hooks in the
+/// worktree being committed in, which is also where config is loaded from.
+pub fn run(hook: &str, config: &Config) -> Result<(), String> {
+    let cwd =
+        std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?;
+    let protected = config.protected_branches();
+    let violation = match hook {
+        "pre-commit" => pre_commit(&cwd, &protected),
+        "pre-push" => pre_push(std::io::stdin().lock(), &protected),
+        _ => return Err(format!("Unknown hook: {}", hook)),
+    };
+    let Some(violation) = violation else {
+        return Ok(());
+    };
+
+    let hint = "Start a silo instead: silo new <branch> --take-changes";
+    match config.protected_branch_policy() {
+        ProtectedBranchPolicy::Warn => {
+            eprintln!("Warning: {}\n  {}", violation, hint);
+            Ok(())
+        }
+        ProtectedBranchPolicy::Block => Err(format!(
+            "{} is blocked (protected_branch_policy = \"block\").\n  {}\n  Or bypass the hook once with --no-verify.",
+            capitalize(&violation),
+            hint
+        )),
+    }
+}
+
+/// The commit about to be made goes straight to a protected branch. Commits
+/// concluding a merge are let through: that's how silo branches land.
+fn pre_commit(worktree: &Path, protected: &[String]) -> Option<String> {
+    let branch = git::get_current_branch(worktree)?;
+    if !protected.contains(&branch)
+        || git::get_in_progress(worktree) == Some(git::InProgress::Merge)
+    {
+        return None;
+    }
+    Some(format!("committing directly to '{}'", branch))
+}
+
+/// A ref about to be pushed (or deleted) is a protected branch. Git writes
+/// one line per ref to the hook's stdin: `<local ref> <local oid> <remote
+/// ref> <remote oid>`.
+fn pre_push(input: impl BufRead, protected: &[String]) -> Option<String> {
+    let branches: Vec<String> = input
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [_, local_oid, remote_ref, _] = fields[..] else {
+                return None;
+            };
+            let branch = remote_ref.strip_prefix("refs/heads/")?;
+            protected.iter().any(|p| p == branch).then(|| {
+                if is_zero_oid(local_oid) {
+                    format!("deleting '{}'", branch)
+                } else {
+                    format!("pushing directly to '{}'", branch)
+                }
+            })
+        })
+        .collect();
+    (!branches.is_empty()).then(|| branches.join(", "))
+}
+
+/// Whether an object name is the one git uses for a ref that doesn't exist,
+/// e.g. when pushing a deletion. It's as long as the repository's hashes.
+fn is_zero_oid(oid: &str) -> bool {
+    oid.chars().all(|c| c == '0')
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// The hooks directory of the current repository, which all its worktrees
+/// share.
+fn hooks_dir() -> Result<PathBuf, String> {
+    Ok(git::get_hooks_dir(&git::get_repo_root()?)?)
+}
+
+fn backup_path(hook: &Path) -> PathBuf {
+    let mut name = hook.as_os_str().to_owned();
+    name.push(BACKUP_SUFFIX);
+    PathBuf::from(name)
+}
+
+fn is_ours(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| content.contains(MARKER))
+}
+
+fn is_foreign(path: &Path) -> bool {
+    path.exists() && !is_ours(path)
+}
+
+/// The script for a hook. It finds silo on PATH if the binary it was
+/// installed with is gone, and does nothing if there's no silo at all, so
+/// uninstalling silo doesn't break committing.
+fn script(hook: &str, silo_bin: &str) -> String {
+    format!(
+        r#"#!/bin/sh
+{marker}; remove with `silo hooks uninstall`
+silo={silo_bin}
+if [ ! -x "$silo" ]; then
+    silo=$(command -v silo) || exit 0
+fi
+exec "$silo" hooks run {hook} "$@"
+"#,
+        marker = MARKER,
+        silo_bin = quote(silo_bin),
+        hook = hook,
+    )
+}
+
+/// Quote a word for sh, so it's passed on as is.
+fn quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+fn write_hook(path: &Path, content: &str) -> Result<(), String> {
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZERO_OID: &str = "0000000000000000000000000000000000000000";
+
+    fn protected() -> Vec<String> {
+        vec!["main".to_string()]
+    }
+
+    #[test]
+    fn test_pre_push_flags_protected_branches() {
+        let oid = "1111111111111111111111111111111111111111";
+        let input = format!(
+            "refs/heads/feature {oid} refs/heads/feature {ZERO_OID}\n\
+             refs/heads/main {oid} refs/heads/main {oid}\n\
+             (delete) {ZERO_OID} refs/heads/main {oid}\n"
+        );
+        assert_eq!(
+            pre_push(input.as_bytes(), &protected()).as_deref(),
+            Some("pushing directly to 'main', deleting 'main'")
+        );
+        let input = format!("refs/heads/main {oid} refs/heads/feature {ZERO_OID}\n");
+        assert_eq!(pre_push(input.as_bytes(), &protected()), None);
+    }
+
+    #[test]
+    fn test_pre_push_flags_deletions_in_sha256_repos() {
+        let oid = "1".repeat(64);
+        let zero = "0".repeat(64);
+        let input = format!("(delete) {zero} refs/heads/main {oid}\n");
+        assert_eq!(
+            pre_push(input.as_bytes(), &protected()).as_deref(),
+            Some("deleting 'main'")
+        );
+    }
+
+    #[test]
+    fn test_script_quotes_binary_path() {
+        let script = script("pre-commit", "/opt/it's/silo");
+        assert!(script.contains("silo='/opt/it'\\''s/silo'\n"));
+        assert!(script.contains(MARKER));
+        assert!(script.ends_with("exec \"$silo\" hooks run pre-commit \"$@\"\n"));
+    }
+}
@@ -0,0 +1,240 @@
+//! The `info` command: show where silo keeps things and which config it read.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::registry;
+use crate::silo::BUNDLE_DIR;
+
+/// Where the config came from, as chosen on the command line.
+pub enum ConfigSource<'a> {
+    /// The default hierarchy of config files
+    Default,
+    /// A single file given with --config-file
+    File(&'a Path),
+    /// No config files (--no-config)
+    Disabled,
+}
+
+/// A config file silo consults.
+#[derive(Debug, Serialize)]
+struct ConfigFile {
+    path: PathBuf,
+    exists: bool,
+}
+
+/// The resolved environment, as printed by `info --json`.
+#[derive(Debug, Serialize)]
+struct Info {
+    version: &'static str,
+    storage_dir: PathBuf,
+    storage_exists: bool,
+    /// Further storage roots from worktree_dirs, after the primary one
+    extra_storage_dirs: Vec<PathBuf>,
+    /// Config files in order of precedence, lowest first
+    config_files: Vec<ConfigFile>,
+    /// Whether --config-file replaced the default config locations
+    config_file_override: bool,
+    /// Whether --no-config disabled all config files
+    no_config: bool,
+    /// Relocated tracking directory; None when tracking data is kept in
+    /// the storage directory
+    tracking_dir: Option<PathBuf>,
+    process_tracking: bool,
+    registry: PathBuf,
+    /// Number of repositories in the registry
+    known_repos: usize,
+    bundle_dir: PathBuf,
+}
+
+pub fn run(config: &Config, source: ConfigSource, paths: bool, json: bool) -> Result<(), String> {
+    let info = collect(config, &source)?;
+
+    if json {
+        let json = serde_json::to_string_pretty(&info)
+            .map_err(|e| format!("Failed to serialize info: {}", e))?;
+        println!("{}", json);
+    } else if paths {
+        for line in path_lines(&info) {
+            println!("{}", line);
+        }
+    } else {
+        for line in human_lines(&info) {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn collect(config: &Config, source: &ConfigSource) -> Result<Info, String> {
+    let mut storage_dirs = config.get_worktree_dirs()?;
+    let storage_dir = storage_dirs.remove(0);
+    let config_paths = match source {
+        ConfigSource::Default => Config::default_paths()?,
+        ConfigSource::File(path) => vec![path.to_path_buf()],
+        ConfigSource::Disabled => Vec::new(),
+    };
+
+    Ok(Info {
+        version: env!("CARGO_PKG_VERSION"),
+        storage_exists: storage_dir.is_dir(),
+        extra_storage_dirs: storage_dirs,
+        config_files: config_paths
+            .into_iter()
+            .map(|path| ConfigFile {
+                exists: path.is_file(),
+                path,
+            })
+            .collect(),
+        config_file_override: matches!(source, ConfigSource::File(_)),
+        no_config: matches!(source, ConfigSource::Disabled),
+        tracking_dir: config.tracking_dir()?,
+        process_tracking: config.process_tracking(),
+        registry: registry::registry_path(&storage_dir),
+        known_repos: registry::load(&storage_dir).len(),
+        bundle_dir: storage_dir.join(BUNDLE_DIR),
+        storage_dir,
+    })
+}
+
+/// One `key=path` line per resolved path, for scripts. Only config files
+/// that exist are listed.
+fn path_lines(info: &Info) -> Vec<String> {
+    let mut lines = vec![format!("storage={}", info.storage_dir.display())];
+    lines.extend(
+        info.extra_storage_dirs
+            .iter()
+            .map(|dir| format!("storage={}", dir.display())),
+    );
+    lines.extend(
+        info.config_files
+            .iter()
+            .filter(|file| file.exists)
+            .map(|file| format!("config={}", file.path.display())),
+    );
+    if let Some(dir) = &info.tracking_dir {
+        lines.push(format!("tracking={}", dir.display()));
+    }
+    lines.push(format!("registry={}", info.registry.display()));
+    lines.push(format!("bundles={}", info.bundle_dir.display()));
+    lines
+}
+
+fn human_lines(info: &Info) -> Vec<String> {
+    let missing = |exists: bool| if exists { "" } else { " (not found)" };
+
+    let mut lines = vec![
+        format!("silo {}", info.version),
+        format!(
+            "Storage:  {}{}",
+            info.storage_dir.display(),
+            missing(info.storage_exists)
+        ),
+    ];
+    lines.extend(
+        info.extra_storage_dirs
+            .iter()
+            .map(|dir| format!("          {}{}", dir.display(), missing(dir.is_dir()))),
+    );
+    if info.no_config {
+        lines.push("Config:   none (--no-config)".to_string());
+    } else if info.config_file_override {
+        lines.push("Config (--config-file):".to_string());
+    } else {
+        lines.push("Config (later files override earlier ones):".to_string());
+    }
+    lines.extend(
+        info.config_files
+            .iter()
+            .map(|file| format!("  {}{}", file.path.display(), missing(file.exists))),
+    );
+    lines.push(match &info.tracking_dir {
+        Some(dir) => format!("Tracking: {}", dir.display()),
+        None => "Tracking: .tracking in each repository directory".to_string(),
+    });
+    if !info.process_tracking {
+        lines.push("Processes: not tracked (process_tracking = false)".to_string());
+    }
+    lines.push(format!(
+        "Registry: {} ({})",
+        info.registry.display(),
+        crate::messages::count(info.known_repos, "repository", "repositories")
+    ));
+    lines.push(format!("Bundles:  {}", info.bundle_dir.display()));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> Info {
+        Info {
+            version: "1.2.3",
+            storage_dir: PathBuf::from("/var/silo"),
+            storage_exists: true,
+            extra_storage_dirs: Vec::new(),
+            config_files: vec![
+                ConfigFile {
+                    path: PathBuf::from("/home/me/.config/silo.toml"),
+                    exists: false,
+                },
+                ConfigFile {
+                    path: PathBuf::from("/src/repo/.silo.toml"),
+                    exists: true,
+                },
+            ],
+            config_file_override: false,
+            no_config: false,
+            tracking_dir: None,
+            process_tracking: true,
+            registry: PathBuf::from("/var/silo/.repos"),
+            known_repos: 1,
+            bundle_dir: PathBuf::from("/var/silo/.bundles"),
+        }
+    }
+
+    #[test]
+    fn test_path_lines_skip_missing_config() {
+        assert_eq!(
+            path_lines(&info()),
+            vec![
+                "storage=/var/silo",
+                "config=/src/repo/.silo.toml",
+                "registry=/var/silo/.repos",
+                "bundles=/var/silo/.bundles",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_lines_list_every_storage_root() {
+        let info = Info {
+            extra_storage_dirs: vec![PathBuf::from("/mnt/big/silo")],
+            ..info()
+        };
+        assert_eq!(
+            path_lines(&info)[..2],
+            ["storage=/var/silo", "storage=/mnt/big/silo"]
+        );
+    }
+
+    #[test]
+    fn test_human_lines() {
+        assert_eq!(
+            human_lines(&info()),
+            vec![
+                "silo 1.2.3",
+                "Storage:  /var/silo",
+                "Config (later files override earlier ones):",
+                "  /home/me/.config/silo.toml (not found)",
+                "  /src/repo/.silo.toml",
+                "Tracking: .tracking in each repository directory",
+                "Registry: /var/silo/.repos (1 repository)",
+                "Bundles:  /var/silo/.bundles",
+            ]
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! The `last-run` command: show the most recent run in a silo.
+
+use crate::last_run;
+
+use super::{resolve_dash, resolve_silo};
+
+pub fn run(name: String) -> Result<(), String> {
+    let name = resolve_dash(&name)?;
+    let silo = resolve_silo(&name)?;
+
+    let run = last_run::load(&silo.storage_path)
+        .ok_or_else(|| format!("No runs recorded for '{}'", name))?;
+
+    let status = if run.succeeded() {
+        "ok".to_string()
+    } else {
+        format!("failed ({})", run.exit_description())
+    };
+    println!("command:  {}", run.command);
+    println!("status:   {}", status);
+    println!("duration: {:.1}s", run.duration.as_secs_f64());
+    Ok(())
+}
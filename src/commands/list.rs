@@ -1,11 +1,205 @@
 //! The `list` command: list silos for the current repo or all repos.
 
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
+use serde::Serialize;
+
+use crate::activity;
 use crate::color;
+use crate::config::Config;
+use crate::created;
+use crate::disk_usage;
 use crate::git;
+use crate::last_run::{self, LastRun};
+use crate::names;
+use crate::output::Verbosity;
+use crate::pager;
 use crate::process;
 use crate::silo;
+use crate::storage;
+use crate::template;
+use crate::timings;
+use crate::verify;
+
+/// Options controlling what `list` shows.
+#[derive(Debug, Default)]
+pub struct ListOptions {
+    /// List silos for all repositories
+    pub all: bool,
+    /// Only show repositories matching this filter (repo, org/repo, ...)
+    pub repo: Option<String>,
+    /// Print one aggregated row per repository instead of per-silo rows
+    pub summary: bool,
+    /// Only show silos owned by this user
+    pub user: Option<String>,
+    /// Print machine-readable JSON instead of a table
+    pub json: bool,
+    /// Show at most this many silos
+    pub limit: Option<usize>,
+    /// Which `limit`-sized page of silos to show, from 1
+    pub page: Option<usize>,
+    /// Send table output through a pager when stdout is a terminal
+    pub pager: bool,
+    /// Show each branch's description under its silo
+    pub long: bool,
+    /// Print each silo with this template instead of a table
+    pub format: Option<String>,
+    /// Only show silos with uncommitted changes
+    pub dirty: bool,
+    /// Only show silos without uncommitted changes
+    pub clean: bool,
+    /// Only show silos with running processes
+    pub active: bool,
+    /// Show ahead/behind against each branch's upstream
+    pub remote: bool,
+    /// Show each worktree's disk size
+    pub du: bool,
+    /// Only show silos whose branch is in the configured namespace
+    pub mine: bool,
+    /// Print the stable format for scripts in this version
+    pub porcelain: Option<Porcelain>,
+    /// Print every `--format` field as CSV, with a header row
+    pub csv: bool,
+    /// Skip line counts and uncommitted files (also set by `list.fast`)
+    pub fast: bool,
+}
+
+/// Versions of `list --porcelain`, the format for scripts and editor
+/// plugins. A version's output never changes: fields are only added, moved
+/// or given new meanings in a new version, which scripts ask for by name.
+///
+/// Output starts with a `# silo porcelain VERSION` line, followed by a line
+/// per silo in table order. Fields are separated by tabs; a backslash, tab
+/// or newline in a value is written as `\\`, `\t` or `\n`. A value that
+/// doesn't apply is empty. Color, the terminal, `display_names` and the set
+/// of other silos don't affect the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Porcelain {
+    /// id, name, repo, branch, path, ahead, behind, upstream_ahead,
+    /// upstream_behind, added, removed, staged, modified, untracked,
+    /// processes, state, verified, external, current, activity
+    V1,
+}
+
+/// The silos `--limit` and `--page` select, by position in list order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Window {
+    start: usize,
+    end: usize,
+    page: usize,
+}
+
+impl Window {
+    fn new(limit: Option<usize>, page: Option<usize>) -> Result<Self, String> {
+        let page = page.unwrap_or(1);
+        if page == 0 {
+            return Err("Invalid --page: pages are numbered from 1".to_string());
+        }
+        let Some(limit) = limit else {
+            return Ok(Self {
+                start: 0,
+                end: usize::MAX,
+                page,
+            });
+        };
+        if limit == 0 {
+            return Err("Invalid --limit: must be at least 1".to_string());
+        }
+        let start = (page - 1).saturating_mul(limit);
+        Ok(Self {
+            start,
+            end: start.saturating_add(limit),
+            page,
+        })
+    }
+
+    /// The part of a run of `len` silos, starting at position `offset` in
+    /// list order, that is in the window, as a range within the run.
+    fn slice(&self, offset: usize, len: usize) -> std::ops::Range<usize> {
+        let clamp = |pos: usize| pos.clamp(offset, offset + len) - offset;
+        clamp(self.start)..clamp(self.end)
+    }
+
+    /// A line saying which silos were shown, if not all of them were.
+    fn footer(&self, total: usize) -> Option<String> {
+        if self.start == 0 && self.end >= total {
+            return None;
+        }
+        if self.start >= total {
+            return Some(format!(
+                "No silos on page {} ({} in total)",
+                self.page, total
+            ));
+        }
+        let end = self.end.min(total);
+        let more = if end < total {
+            format!(", --page {} for more", self.page + 1)
+        } else {
+            String::new()
+        };
+        Some(format!(
+            "Showing silos {}-{} of {}{}",
+            self.start + 1,
+            end,
+            total,
+            more
+        ))
+    }
+}
+
+/// A silo as printed by `list --json`.
+#[derive(Serialize)]
+struct SiloJson {
+    id: String,
+    name: String,
+    repo: String,
+    branch: Option<String>,
+    path: PathBuf,
+    main_worktree: PathBuf,
+    owner: Option<String>,
+    /// Commits ahead of and behind the main branch, as in `base`
+    ahead: u32,
+    behind: u32,
+    /// How the branch compares to the main branch
+    base: git::Comparison,
+    /// How the branch compares to its upstream; null without one
+    upstream: Option<git::Comparison>,
+    added: u32,
+    removed: u32,
+    uncommitted: u32,
+    processes: usize,
+    /// Git operation stopped partway, e.g. "rebase" or "merge"
+    in_progress: Option<&'static str>,
+    /// A registered worktree outside the storage directory
+    external: bool,
+    /// Lock reason from `git worktree lock`, empty if none was given;
+    /// null if the worktree isn't locked
+    locked: Option<String>,
+    /// The branch description, set with `silo note`
+    description: Option<String>,
+    tests_passed: Option<bool>,
+    last_run: Option<LastRunJson>,
+    created: Option<CreatedJson>,
+}
+
+/// The last run of a silo as printed by `list --json`.
+#[derive(Serialize)]
+struct LastRunJson {
+    command: String,
+    exit_code: Option<i32>,
+    duration_ms: u128,
+}
+
+/// How a silo was created, as printed by `list --json`.
+#[derive(Serialize)]
+struct CreatedJson {
+    /// Seconds since the Unix epoch
+    at: u64,
+    by: Option<String>,
+    command: String,
+}
 
 /// Stats for a silo, used for sorting and display.
 struct SiloDisplayInfo {
@@ -17,26 +211,157 @@ struct SiloDisplayInfo {
     added: u32,
     removed: u32,
     uncommitted: git::UncommittedStats,
+    /// Files with uncommitted changes, each untracked file listed
+    files: Vec<git::ChangedFile>,
     process_count: usize,
+    /// The agent each agent process runs, e.g. "claude"
+    agents: Vec<String>,
+    in_progress: Option<git::InProgress>,
+    external: bool,
+    /// Lock reason from `git worktree lock`, empty if none was given
+    locked: Option<String>,
+    verified: Option<bool>,
+    last_run: Option<LastRun>,
+    /// (ahead, behind) against the branch's upstream; None without one
+    upstream: Option<(u32, u32)>,
+    /// Seconds since the Unix epoch, see [`activity::last_activity`]
+    last_activity: Option<u64>,
+    /// Only loaded for `--long`
+    description: Option<String>,
+    /// Bytes in the worktree, only measured for `--du`
+    disk_size: Option<u64>,
     is_current: bool,
 }
 
-pub fn run(all: bool, use_color: bool, quiet: bool) -> Result<(), String> {
-    // Auto-use --all if we're not in a git repository
+/// Aggregated stats for one repository, used by `--summary`.
+struct RepoSummary {
+    name: String,
+    path: PathBuf,
+    silo_count: usize,
+    dirty_count: usize,
+    total_ahead: u32,
+}
+
+pub fn run(
+    options: &ListOptions,
+    config: &Config,
+    use_color: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    use std::io::IsTerminal;
+
+    let window = Window::new(options.limit, options.page)?;
+    if options.mine && config.namespace().is_none() {
+        return Err(
+            "--mine needs a namespace: set namespace (e.g. \"alice/\") in your config".to_string(),
+        );
+    }
+    // Auto-use --all if we're not in a git repository.
+    // Repo filtering and summaries only make sense across repositories.
     let repo_root = git::try_get_repo_root();
-    let list_all = all || repo_root.is_none();
+    let list_all = options.all
+        || options.repo.is_some()
+        || options.user.is_some()
+        || options.summary
+        || repo_root.is_none();
+
+    if options.json {
+        let silos = if list_all {
+            collect_filtered(options, config)?
+        } else {
+            collect_for_repo(repo_root.as_deref().unwrap(), options, config)?
+        };
+        return print_json(&silos[window.slice(0, silos.len())]);
+    }
+
+    if let Some(version) = options.porcelain {
+        let silos = if list_all {
+            collect_filtered(options, config)?
+        } else {
+            collect_for_repo(repo_root.as_deref().unwrap(), options, config)?
+        };
+        print_porcelain(&silos, version, window);
+        return Ok(());
+    }
+
+    if options.csv {
+        let silos = if list_all {
+            collect_filtered(options, config)?
+        } else {
+            collect_for_repo(repo_root.as_deref().unwrap(), options, config)?
+        };
+        print_csv(&silos, options.du, window);
+        return Ok(());
+    }
+
+    if let Some(format) = &options.format {
+        let silos = if list_all {
+            collect_filtered(options, config)?
+        } else {
+            collect_for_repo(repo_root.as_deref().unwrap(), options, config)?
+        };
+        return print_formatted(&silos, format, window);
+    }
+
+    // Checked before paging redirects stdout
+    let is_tty = std::io::stdout().is_terminal();
+    let _pager = (is_tty && options.pager && !verbosity.is_quiet())
+        .then(pager::start)
+        .flatten();
+    let view = View {
+        window,
+        is_tty,
+        use_color,
+        long: options.long,
+        remote: options.remote,
+        du: options.du,
+        // The config only changes the default; --remote and --du need the
+        // full stats
+        fast: options.fast || (config.list_fast() && !options.remote && !options.du),
+    };
 
     if list_all {
-        run_all(use_color, quiet)
+        run_all(options, config, view, verbosity)
     } else {
-        run_repo(&repo_root.unwrap(), use_color, quiet)
+        run_repo(&repo_root.unwrap(), options, config, view, verbosity)
     }
 }
 
-fn run_repo(repo_root: &Path, use_color: bool, quiet: bool) -> Result<(), String> {
-    use std::io::IsTerminal;
+/// How `list` output is laid out.
+#[derive(Debug, Clone, Copy)]
+struct View {
+    window: Window,
+    /// Print an aligned table rather than lines for scripts
+    is_tty: bool,
+    use_color: bool,
+    /// Show branch descriptions
+    long: bool,
+    /// Show ahead/behind against each branch's upstream
+    remote: bool,
+    /// Show each worktree's disk size
+    du: bool,
+    /// Show only names, branches and commits ahead/behind
+    fast: bool,
+}
 
-    let silos = silo::collect_silos_for_repo(repo_root)?;
+fn run_repo(
+    repo_root: &Path,
+    options: &ListOptions,
+    config: &Config,
+    view: View,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let View {
+        window,
+        is_tty,
+        use_color,
+        long,
+        remote,
+        du,
+        fast,
+    } = view;
+    let collapse = config.untracked_collapse();
+    let silos = collect_for_repo(repo_root, options, config)?;
 
     if silos.is_empty() {
         return Ok(());
@@ -52,47 +377,39 @@ fn run_repo(repo_root: &Path, use_color: bool, quiet: bool) -> Result<(), String
     // Get current working directory to mark current worktree
     let current_dir = std::env::current_dir().ok();
 
-    // Quiet mode: only print silo names (directory names)
-    if quiet {
-        for s in &silos {
-            println!("{}", s.name);
+    // Always-qualified names include the repo even within a single repo
+    let names_phase = timings::phase("display names");
+    let display_names = names::generate_display_names(&silos, config.always_qualify_names());
+    drop(names_phase);
+
+    // Quiet mode: only print silo names
+    if verbosity.is_quiet() {
+        for name in &display_names[window.slice(0, display_names.len())] {
+            println!("{}", name);
         }
         return Ok(());
     }
 
     // Collect stats for all silos
-    let mut silo_stats: Vec<SiloDisplayInfo> = silos
-        .iter()
-        .map(|s| {
-            let branch = s.branch.as_deref().unwrap_or("(detached)").to_string();
-            let is_current = current_dir
-                .as_ref()
-                .map(|cwd| cwd.starts_with(&s.storage_path))
-                .unwrap_or(false);
-            let (ahead, behind) = git::get_ahead_behind(&s.storage_path, &branch, main_branch);
-            let (added, removed) = git::get_diff_stats(&s.storage_path, &branch, main_branch);
-            let uncommitted = git::get_uncommitted_stats(&s.storage_path);
-            let process_count = process::list_active(&s.storage_path).len();
-
-            SiloDisplayInfo {
-                display_name: s.name.clone(),
-                branch,
-                path: s.storage_path.clone(),
-                ahead,
-                behind,
-                added,
-                removed,
-                uncommitted,
-                process_count,
-                is_current,
-            }
-        })
-        .collect();
+    let stats_phase = timings::phase("silo stats");
+    let named: Vec<_> = silos.iter().zip(display_names).collect();
+    let mut silo_stats: Vec<SiloDisplayInfo> = map_parallel(&named, |(s, display_name)| {
+        display_info(
+            s,
+            display_name.clone(),
+            main_branch,
+            lock_reason(&worktrees, s),
+            current_dir.as_deref(),
+            Detail { long, du, fast },
+        )
+    });
+    drop(stats_phase);
 
     // Sort by ahead count descending (most commits first)
-    silo_stats.sort_by(|a, b| b.ahead.cmp(&a.ahead));
+    silo_stats.sort_by_key(|s| std::cmp::Reverse(s.ahead));
+    let total = silo_stats.len();
+    let silo_stats = &silo_stats[window.slice(0, total)];
 
-    let is_tty = std::io::stdout().is_terminal();
     let is_current_main = current_dir
         .as_ref()
         .zip(main_worktree)
@@ -117,16 +434,7 @@ fn run_repo(repo_root: &Path, use_color: bool, quiet: bool) -> Result<(), String
             .unwrap_or(6)
             .max(6); // minimum width for "BRANCH"
 
-        // Print header
-        println!(
-            "  {:<nw$}  {:<bw$}  {:>12}  {:>14}  UNCOMMITTED",
-            "NAME",
-            "BRANCH",
-            "COMMITS",
-            "LINES",
-            nw = name_width,
-            bw = branch_width
-        );
+        print_header(view, name_width, branch_width);
 
         // Print main worktree
         let marker = if is_current_main { "*" } else { " " };
@@ -140,7 +448,15 @@ fn run_repo(repo_root: &Path, use_color: bool, quiet: bool) -> Result<(), String
         );
 
         // Print silos with aligned columns
-        for silo in &silo_stats {
+        for silo in silo_stats {
+            if fast {
+                println!(
+                    "{}",
+                    fast_row(silo, true, use_color, name_width, branch_width)
+                );
+                print_description(silo.description.as_deref());
+                continue;
+            }
             let marker = if silo.is_current { "*" } else { " " };
             let commits = format!(
                 "{} {}",
@@ -152,10 +468,20 @@ fn run_repo(repo_root: &Path, use_color: bool, quiet: bool) -> Result<(), String
                 color::green_positive(silo.added, use_color),
                 color::red_negative(silo.removed, use_color)
             );
-            let uncommitted_str =
-                format_uncommitted_short(&silo.uncommitted, &silo.path, use_color);
-            let process_str = format_process_count(silo.process_count, use_color);
-            let suffix = format_suffix(&uncommitted_str, &process_str);
+            let uncommitted_str = format_uncommitted_short(&silo.files, collapse, use_color);
+            let process_str = format_processes(silo.process_count, &silo.agents, use_color);
+            let last_run_str = format_last_run_failure(silo.last_run.as_ref(), use_color);
+            let state_str = format_in_progress(silo.in_progress, use_color);
+            let external_str = format_external(silo.external, use_color);
+            let locked_str = format_locked(silo.locked.as_deref(), use_color);
+            let suffix = format_suffix(&[
+                &external_str,
+                &locked_str,
+                &state_str,
+                &uncommitted_str,
+                &process_str,
+                &last_run_str,
+            ]);
 
             // Calculate visible widths (without ANSI codes)
             let commits_visible = format!("+{} -{}", silo.ahead, silo.behind);
@@ -164,31 +490,40 @@ fn run_repo(repo_root: &Path, use_color: bool, quiet: bool) -> Result<(), String
             let lines_padding = 14_usize.saturating_sub(lines_visible.len());
 
             println!(
-                "{} {:<nw$}  {:<bw$}  {:>cp$}{}  {:>lp$}{}  {}",
+                "{} {:<nw$}  {:<bw$}  {:>cp$}{}{}  {:>lp$}{}  {}  {:>4}{}  {}",
                 marker,
                 silo.display_name,
                 silo.branch,
                 "",
                 commits,
+                format_upstream(silo.upstream, remote, use_color),
                 "",
                 lines,
+                format_verified(silo.verified, use_color),
+                format_activity(silo.last_activity),
+                format_disk_size(silo.disk_size),
                 suffix,
                 nw = name_width,
                 bw = branch_width,
                 cp = commits_padding,
                 lp = lines_padding,
             );
+            print_description(silo.description.as_deref());
         }
     } else {
         // Non-TTY: simple format without headers
         let marker = if is_current_main { "*" } else { " " };
         println!("{} {} ({})  (main)", marker, main_branch, main_branch);
 
-        for silo in &silo_stats {
+        for silo in silo_stats {
+            if fast {
+                println!("{}", fast_row(silo, false, use_color, 0, 0));
+                print_description(silo.description.as_deref());
+                continue;
+            }
             let marker = if silo.is_current { "*" } else { " " };
-            let uncommitted_str =
-                format_uncommitted_with_files(&silo.uncommitted, &silo.path, use_color);
-            let process_str = format_process_count(silo.process_count, use_color);
+            let uncommitted_str = format_uncommitted_with_files(&silo.files, collapse, use_color);
+            let process_str = format_processes(silo.process_count, &silo.agents, use_color);
 
             // Build suffix with proper separators
             // uncommitted_str already has leading ", " when non-empty
@@ -200,29 +535,54 @@ fn run_repo(repo_root: &Path, use_color: bool, quiet: bool) -> Result<(), String
             };
 
             println!(
-                "{} {} ({})  {} {} commits, {} {} lines{}",
+                "{} {} ({})  {} {} commits, {}{} {} lines{}{}{}{}{}{}{}",
                 marker,
                 silo.display_name,
                 silo.branch,
                 color::green_positive(silo.ahead, use_color),
                 color::red_negative(silo.behind, use_color),
+                format_upstream_long(silo.upstream, remote, use_color),
                 color::green_positive(silo.added, use_color),
                 color::red_negative(silo.removed, use_color),
-                suffix
+                format_disk_size_long(silo.disk_size),
+                suffix,
+                format_verified_long(silo.verified),
+                format_last_run_long(silo.last_run.as_ref(), use_color),
+                format_in_progress_long(silo.in_progress, use_color),
+                format_external_long(silo.external),
+                format_locked_long(silo.locked.as_deref())
             );
+            print_description(silo.description.as_deref());
         }
     }
 
+    if is_tty && let Some(footer) = window.footer(total) {
+        println!("{}", footer);
+    }
     Ok(())
 }
 
-fn run_all(use_color: bool, quiet: bool) -> Result<(), String> {
-    use std::io::IsTerminal;
-
-    let silos = silo::collect_all_silos()?;
+fn run_all(
+    options: &ListOptions,
+    config: &Config,
+    view: View,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let View {
+        window,
+        is_tty,
+        use_color,
+        long,
+        remote,
+        du,
+        fast,
+    } = view;
+    let collapse = config.untracked_collapse();
+
+    let silos = collect_filtered(options, config)?;
 
     if silos.is_empty() {
-        if !quiet {
+        if !verbosity.is_quiet() {
             println!("No silos found.");
         }
         return Ok(());
@@ -231,8 +591,8 @@ fn run_all(use_color: bool, quiet: bool) -> Result<(), String> {
     let current_dir = std::env::current_dir().ok();
 
     // Quiet mode: only print silo names
-    if quiet {
-        for s in &silos {
+    if verbosity.is_quiet() {
+        for s in &silos[window.slice(0, silos.len())] {
             println!("{}/{}", s.repo_name, s.name);
         }
         return Ok(());
@@ -254,7 +614,14 @@ fn run_all(use_color: bool, quiet: bool) -> Result<(), String> {
         name_a.cmp(name_b)
     });
 
-    let is_tty = std::io::stdout().is_terminal();
+    if options.summary {
+        let summaries: Vec<RepoSummary> = sorted_repos
+            .iter()
+            .filter_map(|repo_silos| summarize_repo(repo_silos))
+            .collect();
+        print_summary(&summaries, is_tty, use_color);
+        return Ok(());
+    }
 
     // Calculate global name and branch widths for TTY mode
     let (global_name_width, global_branch_width) = if is_tty {
@@ -291,19 +658,20 @@ fn run_all(use_color: bool, quiet: bool) -> Result<(), String> {
 
     // Print header for TTY
     if is_tty {
-        println!(
-            "  {:<nw$}  {:<bw$}  {:>12}  {:>14}  UNCOMMITTED",
-            "NAME",
-            "BRANCH",
-            "COMMITS",
-            "LINES",
-            nw = global_name_width,
-            bw = global_branch_width
-        );
+        print_header(view, global_name_width, global_branch_width);
     }
 
     let mut first_repo = true;
+    let mut offset = 0;
     for repo_silos in sorted_repos {
+        // Only repos with silos in the window are shown, and only those
+        // silos, but each repo's silos are sorted as a whole first
+        let shown = window.slice(offset, repo_silos.len());
+        offset += repo_silos.len();
+        if shown.is_empty() {
+            continue;
+        }
+
         // Add empty line between repositories
         if !first_repo {
             println!();
@@ -329,46 +697,11 @@ fn run_all(use_color: bool, quiet: bool) -> Result<(), String> {
             .map(|cwd| cwd.starts_with(main_worktree))
             .unwrap_or(false);
 
-        // Collect stats for all silos in this repo
-        let mut silo_stats: Vec<SiloDisplayInfo> = repo_silos
-            .iter()
-            .map(|s| {
-                let display_name = format!("{}/{}", repo_name, s.name);
-                let is_current = current_dir
-                    .as_ref()
-                    .map(|cwd| cwd.starts_with(&s.storage_path))
-                    .unwrap_or(false);
-                let branch_str = s.branch.as_deref().unwrap_or("(detached)");
-                let (ahead, behind) =
-                    git::get_ahead_behind(&s.storage_path, branch_str, main_branch);
-                let (added, removed) =
-                    git::get_diff_stats(&s.storage_path, branch_str, main_branch);
-                let uncommitted = git::get_uncommitted_stats(&s.storage_path);
-                let process_count = process::list_active(&s.storage_path).len();
-
-                SiloDisplayInfo {
-                    display_name,
-                    branch: branch_str.to_string(),
-                    path: s.storage_path.clone(),
-                    ahead,
-                    behind,
-                    added,
-                    removed,
-                    uncommitted,
-                    process_count,
-                    is_current,
-                }
-            })
-            .collect();
-
-        // Sort by ahead count descending (most commits first)
-        silo_stats.sort_by(|a, b| b.ahead.cmp(&a.ahead));
-
+        // The main worktree needs no stats, so it's shown while they're
+        // collected and a long list shows progress repo by repo
         let main_display_name = format!("{}/{}", repo_name, main_branch);
-
+        let marker = if is_current_main { "*" } else { " " };
         if is_tty {
-            // Print main worktree
-            let marker = if is_current_main { "*" } else { " " };
             println!(
                 "{} {:<nw$}  {:<bw$}  {}",
                 marker,
@@ -378,9 +711,51 @@ fn run_all(use_color: bool, quiet: bool) -> Result<(), String> {
                 nw = global_name_width,
                 bw = global_branch_width
             );
+        } else {
+            println!(
+                "{} {} ({})  {}",
+                marker,
+                main_display_name,
+                main_branch,
+                main_worktree.display()
+            );
+        }
+
+        // Collect stats for all silos in this repo
+        let stats_phase = timings::phase("silo stats");
+        let mut silo_stats: Vec<SiloDisplayInfo> = map_parallel(&repo_silos, |s| {
+            display_info(
+                s,
+                format!("{}/{}", repo_name, s.name),
+                main_branch,
+                lock_reason(&worktrees, s),
+                current_dir.as_deref(),
+                Detail { long, du, fast },
+            )
+        });
+        drop(stats_phase);
+
+        // Sort by ahead count descending (most commits first)
+        silo_stats.sort_by_key(|s| std::cmp::Reverse(s.ahead));
+        let silo_stats = &silo_stats[shown];
 
+        if is_tty {
             // Print silos with aligned columns
-            for silo in &silo_stats {
+            for silo in silo_stats {
+                if fast {
+                    println!(
+                        "{}",
+                        fast_row(
+                            silo,
+                            true,
+                            use_color,
+                            global_name_width,
+                            global_branch_width
+                        )
+                    );
+                    print_description(silo.description.as_deref());
+                    continue;
+                }
                 let marker = if silo.is_current { "*" } else { " " };
                 let commits = format!(
                     "{} {}",
@@ -392,10 +767,20 @@ fn run_all(use_color: bool, quiet: bool) -> Result<(), String> {
                     color::green_positive(silo.added, use_color),
                     color::red_negative(silo.removed, use_color)
                 );
-                let uncommitted_str =
-                    format_uncommitted_short(&silo.uncommitted, &silo.path, use_color);
-                let process_str = format_process_count(silo.process_count, use_color);
-                let suffix = format_suffix(&uncommitted_str, &process_str);
+                let uncommitted_str = format_uncommitted_short(&silo.files, collapse, use_color);
+                let process_str = format_processes(silo.process_count, &silo.agents, use_color);
+                let last_run_str = format_last_run_failure(silo.last_run.as_ref(), use_color);
+                let state_str = format_in_progress(silo.in_progress, use_color);
+                let external_str = format_external(silo.external, use_color);
+                let locked_str = format_locked(silo.locked.as_deref(), use_color);
+                let suffix = format_suffix(&[
+                    &external_str,
+                    &locked_str,
+                    &state_str,
+                    &uncommitted_str,
+                    &process_str,
+                    &last_run_str,
+                ]);
 
                 // Calculate visible widths (without ANSI codes)
                 let commits_visible = format!("+{} -{}", silo.ahead, silo.behind);
@@ -404,37 +789,38 @@ fn run_all(use_color: bool, quiet: bool) -> Result<(), String> {
                 let lines_padding = 14_usize.saturating_sub(lines_visible.len());
 
                 println!(
-                    "{} {:<nw$}  {:<bw$}  {:>cp$}{}  {:>lp$}{}  {}",
+                    "{} {:<nw$}  {:<bw$}  {:>cp$}{}{}  {:>lp$}{}  {}  {:>4}{}  {}",
                     marker,
                     silo.display_name,
                     silo.branch,
                     "",
                     commits,
+                    format_upstream(silo.upstream, remote, use_color),
                     "",
                     lines,
+                    format_verified(silo.verified, use_color),
+                    format_activity(silo.last_activity),
+                    format_disk_size(silo.disk_size),
                     suffix,
                     nw = global_name_width,
                     bw = global_branch_width,
                     cp = commits_padding,
                     lp = lines_padding,
                 );
+                print_description(silo.description.as_deref());
             }
         } else {
             // Non-TTY: simple format without headers
-            let marker = if is_current_main { "*" } else { " " };
-            println!(
-                "{} {} ({})  {}",
-                marker,
-                main_display_name,
-                main_branch,
-                main_worktree.display()
-            );
-
-            for silo in &silo_stats {
+            for silo in silo_stats {
+                if fast {
+                    println!("{}", fast_row(silo, false, use_color, 0, 0));
+                    print_description(silo.description.as_deref());
+                    continue;
+                }
                 let marker = if silo.is_current { "*" } else { " " };
                 let uncommitted_str =
-                    format_uncommitted_with_files(&silo.uncommitted, &silo.path, use_color);
-                let process_str = format_process_count(silo.process_count, use_color);
+                    format_uncommitted_with_files(&silo.files, collapse, use_color);
+                let process_str = format_processes(silo.process_count, &silo.agents, use_color);
 
                 // Build suffix with proper separators
                 // uncommitted_str already has leading ", " when non-empty
@@ -446,86 +832,856 @@ fn run_all(use_color: bool, quiet: bool) -> Result<(), String> {
                 };
 
                 println!(
-                    "{} {} ({})  {} {} commits, {} {} lines{}",
+                    "{} {} ({})  {} {} commits, {}{} {} lines{}{}{}{}{}{}{}",
                     marker,
                     silo.display_name,
                     silo.branch,
                     color::green_positive(silo.ahead, use_color),
                     color::red_negative(silo.behind, use_color),
+                    format_upstream_long(silo.upstream, remote, use_color),
                     color::green_positive(silo.added, use_color),
                     color::red_negative(silo.removed, use_color),
-                    suffix
+                    format_disk_size_long(silo.disk_size),
+                    suffix,
+                    format_verified_long(silo.verified),
+                    format_last_run_long(silo.last_run.as_ref(), use_color),
+                    format_in_progress_long(silo.in_progress, use_color),
+                    format_external_long(silo.external),
+                    format_locked_long(silo.locked.as_deref())
                 );
+                print_description(silo.description.as_deref());
+            }
+        }
+        // Show each repo as soon as it's done, also when piped
+        let _ = std::io::stdout().flush();
+    }
+
+    if is_tty && let Some(footer) = window.footer(silos.len()) {
+        println!("{}", footer);
+    }
+    Ok(())
+}
+
+/// Which of the optional stats `display_info` collects.
+#[derive(Debug, Clone, Copy)]
+struct Detail {
+    /// The branch description
+    long: bool,
+    /// The worktree's disk size
+    du: bool,
+    /// Skip line counts and uncommitted files
+    fast: bool,
+}
+
+/// Collect the stats shown for a silo.
+fn display_info(
+    s: &silo::Silo,
+    display_name: String,
+    main_branch: &str,
+    locked: Option<String>,
+    current_dir: Option<&Path>,
+    detail: Detail,
+) -> SiloDisplayInfo {
+    let Detail { long, du, fast } = detail;
+    let branch = s.branch.as_deref().unwrap_or("(detached)").to_string();
+    let is_current = current_dir.is_some_and(|cwd| cwd.starts_with(&s.storage_path));
+    let (ahead, behind) = git::get_ahead_behind(&s.storage_path, &branch, main_branch);
+    // On a big diff these two dominate the time `list` takes
+    let (added, removed) = if fast {
+        (0, 0)
+    } else {
+        git::get_diff_stats(&s.storage_path, &branch, main_branch)
+    };
+    // Uncommitted changes and upstream counts come from one `git status`
+    let status = if fast {
+        git::WorktreeStatus::default()
+    } else {
+        git::get_worktree_status(&s.storage_path)
+    };
+    let processes = process::list_active(&s.storage_path);
+
+    SiloDisplayInfo {
+        display_name,
+        branch,
+        path: s.storage_path.clone(),
+        ahead,
+        behind,
+        added,
+        removed,
+        uncommitted: status.uncommitted,
+        process_count: processes.len(),
+        agents: processes.into_iter().filter_map(|p| p.agent).collect(),
+        in_progress: git::get_in_progress(&s.storage_path),
+        external: s.is_external(),
+        locked,
+        verified: verify::load(&s.storage_path).map(|r| r.passed),
+        last_run: last_run::load(&s.storage_path),
+        upstream: status.upstream,
+        last_activity: activity::last_activity(&s.storage_path, &status.files),
+        files: status.files,
+        description: long.then(|| description(s)).flatten(),
+        disk_size: du.then(|| disk_usage::worktree_size(&s.storage_path)),
+        is_current,
+    }
+}
+
+/// Print the header of the table, with the columns `view` shows.
+fn print_header(view: View, name_width: usize, branch_width: usize) {
+    if view.fast {
+        println!(
+            "  {:<nw$}  {:<bw$}  {:>12}",
+            "NAME",
+            "BRANCH",
+            "COMMITS",
+            nw = name_width,
+            bw = branch_width
+        );
+        return;
+    }
+    println!(
+        "  {:<nw$}  {:<bw$}  {:>12}{}  {:>14}  {:<5}  {:>4}{}  UNCOMMITTED",
+        "NAME",
+        "BRANCH",
+        "COMMITS",
+        if view.remote {
+            format!("  {:>10}", "REMOTE")
+        } else {
+            String::new()
+        },
+        "LINES",
+        "TESTS",
+        "AGE",
+        if view.du {
+            format!("  {:>6}", "SIZE")
+        } else {
+            String::new()
+        },
+        nw = name_width,
+        bw = branch_width
+    );
+}
+
+/// A silo's row for `--fast`: its name, branch and commits ahead/behind.
+fn fast_row(
+    silo: &SiloDisplayInfo,
+    is_tty: bool,
+    use_color: bool,
+    name_width: usize,
+    branch_width: usize,
+) -> String {
+    let marker = if silo.is_current { "*" } else { " " };
+    let ahead = color::green_positive(silo.ahead, use_color);
+    let behind = color::red_negative(silo.behind, use_color);
+    if !is_tty {
+        return format!(
+            "{} {} ({})  {} {} commits",
+            marker, silo.display_name, silo.branch, ahead, behind
+        );
+    }
+    // Padded by the visible width, without ANSI codes
+    let commits_visible = format!("+{} -{}", silo.ahead, silo.behind);
+    format!(
+        "{} {:<nw$}  {:<bw$}  {:>cp$}{} {}",
+        marker,
+        silo.display_name,
+        silo.branch,
+        "",
+        ahead,
+        behind,
+        nw = name_width,
+        bw = branch_width,
+        cp = 12_usize.saturating_sub(commits_visible.len()),
+    )
+}
+
+/// Why a silo's worktree is locked, from its repository's `worktrees`.
+fn lock_reason(worktrees: &[git::Worktree], s: &silo::Silo) -> Option<String> {
+    worktrees
+        .iter()
+        .find(|wt| wt.path == s.storage_path)?
+        .locked
+        .clone()
+}
+
+/// Map `items` with `f` on a few threads, keeping their order. Stats run
+/// several git commands per silo, which mostly means waiting on git.
+pub(super) fn map_parallel<T: Sync, U: Send>(items: &[T], f: impl Fn(&T) -> U + Sync) -> Vec<U> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = items.len().div_ceil(threads).max(1);
+    let f = &f;
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<_>>()))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    })
+}
+
+/// Placeholders a `--format` template can use.
+const FORMAT_FIELDS: &[&str] = &[
+    "name",
+    "id",
+    "repo",
+    "branch",
+    "path",
+    "ahead",
+    "behind",
+    "upstream_ahead",
+    "upstream_behind",
+    "added",
+    "removed",
+    "staged",
+    "modified",
+    "untracked",
+    "processes",
+    "state",
+    "verified",
+    "external",
+    "current",
+    "age",
+    "size",
+    "description",
+];
+
+/// Print a line per silo from a `--format` template, in table order. The
+/// template can use `\t`, `\n` and `\\` escapes, since a tab is hard to type
+/// in a shell argument.
+fn print_formatted(silos: &[silo::Silo], format: &str, window: Window) -> Result<(), String> {
+    let format = unescape(format);
+    // Checked before any stats are collected
+    let blank: Vec<(&str, String)> = FORMAT_FIELDS.iter().map(|f| (*f, String::new())).collect();
+    template::expand(&format, &blank, "--format")?;
+
+    let long = format.contains("{description}");
+    let du = format.contains("{size}");
+    for (s, info) in &collect_rows(silos, long, du)[window.slice(0, silos.len())] {
+        println!(
+            "{}",
+            template::expand(&format, &format_values(s, info), "--format")?
+        );
+    }
+    Ok(())
+}
+
+/// Print a CSV header row of the `--format` fields, then a row per silo in
+/// table order. Sizes are only measured for `du`.
+fn print_csv(silos: &[silo::Silo], du: bool, window: Window) {
+    println!("{}", FORMAT_FIELDS.join(","));
+    for (s, info) in &collect_rows(silos, true, du)[window.slice(0, silos.len())] {
+        let fields: Vec<String> = format_values(s, info)
+            .iter()
+            .map(|(_, value)| csv_field(value))
+            .collect();
+        println!("{}", fields.join(","));
+    }
+}
+
+/// Quote a CSV field if it holds a comma, a double quote or a line break,
+/// doubling its double quotes (RFC 4180). A field that a spreadsheet would
+/// read as a formula, such as a description starting with `=`, is prefixed
+/// with `'` so it is shown as text instead of evaluated.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Print a line per silo in the `--porcelain` format `version`.
+fn print_porcelain(silos: &[silo::Silo], version: Porcelain, window: Window) {
+    println!("# silo porcelain {}", porcelain_name(version));
+    for (s, info) in &collect_rows(silos, false, false)[window.slice(0, silos.len())] {
+        let fields = match version {
+            Porcelain::V1 => porcelain_v1(s, info),
+        };
+        let fields: Vec<String> = fields.iter().map(|f| escape_field(f)).collect();
+        println!("{}", fields.join("\t"));
+    }
+}
+
+pub(crate) fn porcelain_name(version: Porcelain) -> &'static str {
+    match version {
+        Porcelain::V1 => "v1",
+    }
+}
+
+/// A silo's fields in `--porcelain=v1`. Never change these: add a version.
+fn porcelain_v1(s: &silo::Silo, info: &SiloDisplayInfo) -> Vec<String> {
+    let optional = |value: Option<u32>| value.map_or(String::new(), |v| v.to_string());
+    vec![
+        s.id(),
+        s.name.clone(),
+        s.repo_name.clone(),
+        s.branch.clone().unwrap_or_default(),
+        info.path.display().to_string(),
+        info.ahead.to_string(),
+        info.behind.to_string(),
+        optional(info.upstream.map(|(ahead, _)| ahead)),
+        optional(info.upstream.map(|(_, behind)| behind)),
+        info.added.to_string(),
+        info.removed.to_string(),
+        info.uncommitted.staged.to_string(),
+        info.uncommitted.modified.to_string(),
+        info.uncommitted.untracked.to_string(),
+        info.process_count.to_string(),
+        info.in_progress
+            .map_or(String::new(), |state| state.command().to_string()),
+        match info.verified {
+            Some(true) => "passed".to_string(),
+            Some(false) => "failed".to_string(),
+            None => String::new(),
+        },
+        info.external.to_string(),
+        info.is_current.to_string(),
+        info.last_activity
+            .map_or(String::new(), |at| at.to_string()),
+    ]
+}
+
+/// Escape the characters that would break a `--porcelain` line.
+pub(crate) fn escape_field(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+/// Stats for each silo, in table order: repos by name, then each repo's
+/// silos by commits ahead.
+fn collect_rows(silos: &[silo::Silo], long: bool, du: bool) -> Vec<(&silo::Silo, SiloDisplayInfo)> {
+    // Each repo's worktrees, listed once per repo; the first is the main
+    // worktree, whose branch silos are compared with
+    let mut repo_worktrees: HashMap<&Path, Vec<git::Worktree>> = HashMap::new();
+    for s in silos {
+        repo_worktrees
+            .entry(s.main_worktree.as_path())
+            .or_insert_with(|| git::list_worktrees(&s.main_worktree).unwrap_or_default());
+    }
+
+    let current_dir = std::env::current_dir().ok();
+    let stats_phase = timings::phase("silo stats");
+    let infos = map_parallel(silos, |s| {
+        let worktrees = &repo_worktrees[s.main_worktree.as_path()];
+        let main_branch = worktrees
+            .first()
+            .and_then(|wt| wt.branch.as_deref())
+            .unwrap_or("main");
+        display_info(
+            s,
+            s.name.clone(),
+            main_branch,
+            lock_reason(worktrees, s),
+            current_dir.as_deref(),
+            Detail {
+                long,
+                du,
+                fast: false,
+            },
+        )
+    });
+    drop(stats_phase);
+    let mut rows: Vec<_> = silos.iter().zip(infos).collect();
+
+    // Repos by name, then each repo's silos as in the table
+    rows.sort_by(|(a, a_info), (b, b_info)| {
+        (
+            &a.repo_name,
+            &a.main_worktree,
+            std::cmp::Reverse(a_info.ahead),
+        )
+            .cmp(&(
+                &b.repo_name,
+                &b.main_worktree,
+                std::cmp::Reverse(b_info.ahead),
+            ))
+    });
+    rows
+}
+
+/// The values of a silo's `--format` placeholders, in [`FORMAT_FIELDS`] order.
+fn format_values(s: &silo::Silo, info: &SiloDisplayInfo) -> Vec<(&'static str, String)> {
+    let values = [
+        info.display_name.clone(),
+        s.id(),
+        s.repo_name.clone(),
+        info.branch.clone(),
+        info.path.display().to_string(),
+        info.ahead.to_string(),
+        info.behind.to_string(),
+        info.upstream
+            .map_or(String::new(), |(ahead, _)| ahead.to_string()),
+        info.upstream
+            .map_or(String::new(), |(_, behind)| behind.to_string()),
+        info.added.to_string(),
+        info.removed.to_string(),
+        info.uncommitted.staged.to_string(),
+        info.uncommitted.modified.to_string(),
+        info.uncommitted.untracked.to_string(),
+        info.process_count.to_string(),
+        info.in_progress
+            .map_or(String::new(), |state| state.command().to_string()),
+        match info.verified {
+            Some(true) => "passed".to_string(),
+            Some(false) => "failed".to_string(),
+            None => String::new(),
+        },
+        info.external.to_string(),
+        info.is_current.to_string(),
+        info.last_activity
+            .map_or(String::new(), |at| created::format_age(activity::age(at))),
+        info.disk_size
+            .map_or(String::new(), disk_usage::format_size),
+        info.description
+            .as_deref()
+            .and_then(|d| d.lines().next())
+            .unwrap_or("")
+            .to_string(),
+    ];
+    FORMAT_FIELDS.iter().copied().zip(values).collect()
+}
+
+/// Replace `\t`, `\n` and `\\` in a `--format` template. Other backslashes
+/// are kept.
+fn unescape(format: &str) -> String {
+    let mut result = String::with_capacity(format.len());
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
             }
+            None => result.push('\\'),
         }
     }
+    result
+}
+
+/// Collect silos across all repositories, applying the `--repo` filter.
+fn collect_filtered(options: &ListOptions, config: &Config) -> Result<Vec<silo::Silo>, String> {
+    let mut silos = silo::collect_all_silos()?;
+    if let Some(filter) = &options.repo {
+        silos.retain(|s| names::repo_matches(&s.main_worktree, filter));
+    }
+    if let Some(user) = &options.user {
+        silos.retain(|s| storage::owner_name(&s.storage_path).as_deref() == Some(user));
+    }
+    retain_mine(&mut silos, options, config);
+    retain_state(&mut silos, options);
+    Ok(silos)
+}
+
+/// Collect the current repository's silos, applying the state filters.
+fn collect_for_repo(
+    repo_root: &Path,
+    options: &ListOptions,
+    config: &Config,
+) -> Result<Vec<silo::Silo>, String> {
+    let mut silos = silo::collect_silos_for_repo(repo_root)?;
+    retain_mine(&mut silos, options, config);
+    retain_state(&mut silos, options);
+    Ok(silos)
+}
+
+/// Keep only the silos on branches in the configured namespace, if
+/// `--mine` asks for them.
+fn retain_mine(silos: &mut Vec<silo::Silo>, options: &ListOptions, config: &Config) {
+    if let Some(namespace) = config.namespace().filter(|_| options.mine) {
+        silos.retain(|s| {
+            s.branch
+                .as_deref()
+                .is_some_and(|b| b.starts_with(&namespace))
+        });
+    }
+}
 
+/// Keep only the silos in the state `--dirty`, `--clean` and `--active`
+/// ask for.
+fn retain_state(silos: &mut Vec<silo::Silo>, options: &ListOptions) {
+    if !(options.dirty || options.clean || options.active) {
+        return;
+    }
+    let _phase = timings::phase("state filter");
+    let keep = map_parallel(silos, |s| {
+        let matches_changes = !(options.dirty || options.clean)
+            || git::get_uncommitted_stats(&s.storage_path).is_clean() == options.clean;
+        matches_changes && (!options.active || !process::list_active(&s.storage_path).is_empty())
+    });
+    let mut keep = keep.into_iter();
+    silos.retain(|_| keep.next().unwrap_or(false));
+}
+
+/// Print silos as a JSON array, one object per silo.
+fn print_json(silos: &[silo::Silo]) -> Result<(), String> {
+    let mut main_branches: std::collections::HashMap<PathBuf, String> =
+        std::collections::HashMap::new();
+    let mut repo_worktrees: HashMap<PathBuf, Vec<git::Worktree>> = HashMap::new();
+
+    let entries: Vec<SiloJson> = silos
+        .iter()
+        .map(|s| {
+            let main_branch = main_branches
+                .entry(s.main_worktree.clone())
+                .or_insert_with(|| git::get_main_branch(&s.main_worktree));
+            let branch = s.branch_name();
+            let base = git::compare_branches(&s.storage_path, branch, main_branch);
+            let (added, removed) = git::get_diff_stats(&s.storage_path, branch, main_branch);
+            // Uncommitted changes and the upstream come from one `git status`
+            let status = git::get_worktree_status(&s.storage_path);
+            let worktrees = repo_worktrees
+                .entry(s.main_worktree.clone())
+                .or_insert_with(|| git::list_worktrees(&s.main_worktree).unwrap_or_default());
+
+            SiloJson {
+                id: s.id(),
+                name: s.name.clone(),
+                repo: s.repo_name.clone(),
+                branch: s.branch.clone(),
+                path: s.storage_path.clone(),
+                main_worktree: s.main_worktree.clone(),
+                owner: storage::owner_name(&s.storage_path),
+                ahead: base.ahead,
+                behind: base.behind,
+                upstream: status.upstream_comparison(&s.storage_path, branch),
+                base,
+                added,
+                removed,
+                uncommitted: status.uncommitted.total(),
+                processes: process::list_active(&s.storage_path).len(),
+                in_progress: git::get_in_progress(&s.storage_path).map(git::InProgress::command),
+                external: s.is_external(),
+                locked: lock_reason(worktrees, s),
+                description: description(s),
+                tests_passed: verify::load(&s.storage_path).map(|r| r.passed),
+                last_run: last_run::load(&s.storage_path).map(|r| LastRunJson {
+                    command: r.command,
+                    exit_code: r.exit_code,
+                    duration_ms: r.duration.as_millis(),
+                }),
+                created: created::load(&s.storage_path).map(|c| CreatedJson {
+                    at: c.at,
+                    by: c.by,
+                    command: c.command,
+                }),
+            }
+        })
+        .collect();
+
+    let json = serde_json::to_string_pretty(&entries)
+        .map_err(|e| format!("Failed to serialize silos: {}", e))?;
+    println!("{}", json);
     Ok(())
 }
 
+/// The description of a silo's branch; detached silos have none.
+fn description(silo: &silo::Silo) -> Option<String> {
+    let branch = silo.branch.as_deref()?;
+    git::get_branch_description(&silo.storage_path, branch)
+}
+
+/// Print the first line of a branch description under its silo's row.
+fn print_description(description: Option<&str>) {
+    if let Some(line) = description.and_then(|d| d.lines().next()) {
+        println!("      {}", line);
+    }
+}
+
+/// Aggregate stats for the silos of a single repository.
+/// Returns None for an empty group.
+fn summarize_repo(repo_silos: &[&silo::Silo]) -> Option<RepoSummary> {
+    let first = repo_silos.first()?;
+
+    let main_branch = git::get_main_branch(&first.main_worktree);
+
+    let dirty_count = repo_silos
+        .iter()
+        .filter(|s| !git::get_uncommitted_stats(&s.storage_path).is_clean())
+        .count();
+    let total_ahead = repo_silos
+        .iter()
+        .map(|s| git::get_ahead_behind(&s.storage_path, s.branch_name(), &main_branch).0)
+        .sum();
+
+    Some(RepoSummary {
+        name: first.repo_name.clone(),
+        path: first.main_worktree.clone(),
+        silo_count: repo_silos.len(),
+        dirty_count,
+        total_ahead,
+    })
+}
+
+/// Print one row per repository for `--summary`.
+fn print_summary(summaries: &[RepoSummary], is_tty: bool, use_color: bool) {
+    if is_tty {
+        let name_width = summaries
+            .iter()
+            .map(|s| s.name.len())
+            .max()
+            .unwrap_or(4)
+            .max(4); // minimum width for "REPO"
+
+        println!(
+            "  {:<nw$}  {:>5}  {:>5}  {:>7}  PATH",
+            "REPO",
+            "SILOS",
+            "DIRTY",
+            "AHEAD",
+            nw = name_width
+        );
+        for summary in summaries {
+            let ahead_visible = format!("+{}", summary.total_ahead);
+            let ahead_padding = 7_usize.saturating_sub(ahead_visible.len());
+            println!(
+                "  {:<nw$}  {:>5}  {:>5}  {:>ap$}{}  {}",
+                summary.name,
+                summary.silo_count,
+                summary.dirty_count,
+                "",
+                color::green_positive(summary.total_ahead, use_color),
+                summary.path.display(),
+                nw = name_width,
+                ap = ahead_padding,
+            );
+        }
+    } else {
+        for summary in summaries {
+            println!(
+                "{}  {} silos, {} dirty, {} commits  {}",
+                summary.name,
+                summary.silo_count,
+                summary.dirty_count,
+                color::green_positive(summary.total_ahead, use_color),
+                summary.path.display()
+            );
+        }
+    }
+}
+
 /// Format uncommitted changes in short form for TTY output.
 fn format_uncommitted_short(
-    uncommitted: &git::UncommittedStats,
-    path: &Path,
+    files: &[git::ChangedFile],
+    collapse: usize,
     use_color: bool,
 ) -> String {
-    if uncommitted.is_clean() {
+    if files.is_empty() {
         return String::new();
     }
 
-    let files = git::get_uncommitted_files(path);
-    let total = uncommitted.total();
+    let shown = summarize_files(files, collapse);
 
-    // Limit displayed files to 3, with ellipsis for more
+    // Limit displayed entries to 3, with ellipsis for more
     const MAX_FILES: usize = 3;
-    let file_list = if files.len() <= MAX_FILES {
-        files.join(", ")
+    let file_list = if shown.len() <= MAX_FILES {
+        shown.join(", ")
     } else {
-        let shown: Vec<_> = files.iter().take(MAX_FILES).cloned().collect();
-        format!("{}, +{} more", shown.join(", "), files.len() - MAX_FILES)
+        format!(
+            "{}, +{} more",
+            shown[..MAX_FILES].join(", "),
+            shown.len() - MAX_FILES
+        )
     };
 
     format!(
         "{} files: {}",
-        color::yellow_uncommitted(total, use_color),
+        color::yellow_uncommitted(files.len() as u32, use_color),
         file_list
     )
 }
 
 /// Format uncommitted changes with file names.
 fn format_uncommitted_with_files(
-    uncommitted: &git::UncommittedStats,
-    path: &Path,
+    files: &[git::ChangedFile],
+    collapse: usize,
     use_color: bool,
 ) -> String {
-    if uncommitted.is_clean() {
+    if files.is_empty() {
         return String::new();
     }
 
-    let files = git::get_uncommitted_files(path);
-    let total = uncommitted.total();
+    let shown = summarize_files(files, collapse);
 
-    // Limit displayed files to 3, with ellipsis for more
+    // Limit displayed entries to 3, with ellipsis for more
     const MAX_FILES: usize = 3;
-    let file_list = if files.len() <= MAX_FILES {
-        files.join(", ")
+    let file_list = if shown.len() <= MAX_FILES {
+        shown.join(", ")
     } else {
-        let shown: Vec<_> = files.iter().take(MAX_FILES).cloned().collect();
         format!(
             "{}, ... and {} more",
-            shown.join(", "),
-            files.len() - MAX_FILES
+            shown[..MAX_FILES].join(", "),
+            shown.len() - MAX_FILES
         )
     };
 
     format!(
         ", {} uncommitted files: {}",
-        color::yellow_uncommitted(total, use_color),
+        color::yellow_uncommitted(files.len() as u32, use_color),
         file_list
     )
 }
 
+/// Name changed files for display, showing each directory that holds at
+/// least `collapse` untracked files as one `dir/ (N files)` entry instead of
+/// its files. The shallowest such directory is used, so a generated tree
+/// becomes a single entry. A `collapse` of 0 lists every file.
+fn summarize_files(files: &[git::ChangedFile], collapse: usize) -> Vec<String> {
+    fn dirs(path: &str) -> impl Iterator<Item = &str> {
+        path.match_indices('/').map(move |(i, _)| &path[..=i])
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    if collapse > 0 {
+        for file in files.iter().filter(|f| f.untracked) {
+            for dir in dirs(&file.path) {
+                *counts.entry(dir).or_default() += 1;
+            }
+        }
+    }
+
+    let mut shown = Vec::new();
+    let mut collapsed = HashSet::new();
+    for file in files {
+        let dir = file
+            .untracked
+            .then(|| dirs(&file.path).find(|dir| counts.get(dir).is_some_and(|&n| n >= collapse)))
+            .flatten();
+        match dir {
+            Some(dir) if collapsed.insert(dir) => {
+                shown.push(format!("{} ({} files)", dir, counts[dir]));
+            }
+            Some(_) => {}
+            None => shown.push(file.path.clone()),
+        }
+    }
+    shown
+}
+
+/// Format a verification result as a 5-wide TESTS cell.
+pub fn format_verified(verified: Option<bool>, use_color: bool) -> String {
+    match verified {
+        Some(passed) => format!("{}    ", color::check_mark(passed, use_color)),
+        None => " ".repeat(5),
+    }
+}
+
+/// Format ahead/behind against the upstream as a 10-wide REMOTE cell with
+/// its leading separator, or nothing without `--remote`.
+fn format_upstream(upstream: Option<(u32, u32)>, remote: bool, use_color: bool) -> String {
+    match upstream {
+        _ if !remote => String::new(),
+        Some((ahead, behind)) => {
+            let visible = format!("+{} -{}", ahead, behind);
+            format!(
+                "  {:>pad$}{} {}",
+                "",
+                color::green_positive(ahead, use_color),
+                color::red_negative(behind, use_color),
+                pad = 10_usize.saturating_sub(visible.len())
+            )
+        }
+        None => format!("  {:>10}", "-"),
+    }
+}
+
+/// Format ahead/behind against the upstream for non-TTY output, or nothing
+/// without `--remote`.
+fn format_upstream_long(upstream: Option<(u32, u32)>, remote: bool, use_color: bool) -> String {
+    match upstream {
+        _ if !remote => String::new(),
+        Some((ahead, behind)) => format!(
+            "{} {} vs upstream, ",
+            color::green_positive(ahead, use_color),
+            color::red_negative(behind, use_color)
+        ),
+        None => "no upstream, ".to_string(),
+    }
+}
+
+/// Format the time since a silo's last activity for the AGE column.
+fn format_activity(last_activity: Option<u64>) -> String {
+    last_activity.map_or_else(
+        || "-".to_string(),
+        |at| created::format_age(activity::age(at)),
+    )
+}
+
+/// Format a worktree's disk size for the SIZE column, or nothing without
+/// `--du`.
+fn format_disk_size(disk_size: Option<u64>) -> String {
+    disk_size.map_or(String::new(), |bytes| {
+        format!("  {:>6}", disk_usage::format_size(bytes))
+    })
+}
+
+/// Format a worktree's disk size for non-TTY output, or nothing without
+/// `--du`.
+fn format_disk_size_long(disk_size: Option<u64>) -> String {
+    disk_size.map_or(String::new(), |bytes| {
+        format!(", {} on disk", disk_usage::format_size(bytes))
+    })
+}
+
+/// Format a verification result for non-TTY output.
+fn format_verified_long(verified: Option<bool>) -> &'static str {
+    match verified {
+        Some(true) => ", tests passed",
+        Some(false) => ", tests failed",
+        None => "",
+    }
+}
+
+/// Format a failed last run for non-TTY output.
+fn format_last_run_long(run: Option<&LastRun>, use_color: bool) -> String {
+    let failure = format_last_run_failure(run, use_color);
+    if failure.is_empty() {
+        failure
+    } else {
+        format!(", {}", failure)
+    }
+}
+
 /// Format active process count for display.
+/// Format a silo's running processes: the agents at work by name, e.g.
+/// "claude", then a count of the other processes.
+fn format_processes(count: usize, agents: &[String], use_color: bool) -> String {
+    let mut names: Vec<&str> = agents.iter().map(String::as_str).collect();
+    names.sort_unstable();
+    names.dedup();
+    let names = names.into_iter().map(|name| {
+        if use_color {
+            format!("\x1b[35m{}\x1b[0m", name) // Magenta
+        } else {
+            name.to_string()
+        }
+    });
+    let others = format_process_count(count.saturating_sub(agents.len()), use_color);
+    names
+        .chain((!others.is_empty()).then_some(others))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 fn format_process_count(count: usize, use_color: bool) -> String {
     if count == 0 {
         return String::new();
@@ -541,12 +1697,206 @@ fn format_process_count(count: usize, use_color: bool) -> String {
     format!("{} {}", count_str, label)
 }
 
-/// Combine uncommitted and process strings with proper separators.
-fn format_suffix(uncommitted: &str, process: &str) -> String {
-    match (uncommitted.is_empty(), process.is_empty()) {
-        (true, true) => String::new(),
-        (true, false) => process.to_string(),
-        (false, true) => uncommitted.to_string(),
-        (false, false) => format!("{}, {}", uncommitted, process),
+/// Format a git operation stopped partway as a badge, e.g. "REBASING".
+fn format_in_progress(state: Option<git::InProgress>, use_color: bool) -> String {
+    match state {
+        Some(state) if use_color => format!("\x1b[1;33m{}\x1b[0m", state.badge()), // Bold yellow
+        Some(state) => state.badge().to_string(),
+        None => String::new(),
+    }
+}
+
+/// Mark a registered external worktree.
+fn format_external(external: bool, use_color: bool) -> String {
+    match external {
+        true if use_color => "\x1b[2mexternal\x1b[0m".to_string(), // Dim
+        true => "external".to_string(),
+        false => String::new(),
+    }
+}
+
+/// Mark a registered external worktree for non-TTY output.
+fn format_external_long(external: bool) -> &'static str {
+    if external { ", external" } else { "" }
+}
+
+/// Mark a worktree locked with `git worktree lock`.
+fn format_locked(locked: Option<&str>, use_color: bool) -> String {
+    match locked {
+        Some(_) if use_color => "\x1b[33mlocked\x1b[0m".to_string(), // Yellow
+        Some(_) => "locked".to_string(),
+        None => String::new(),
+    }
+}
+
+/// Mark a locked worktree for non-TTY output, with the reason if given.
+fn format_locked_long(locked: Option<&str>) -> String {
+    match locked {
+        Some("") => ", locked".to_string(),
+        Some(reason) => format!(", locked ({})", reason),
+        None => String::new(),
+    }
+}
+
+/// Format a git operation stopped partway for non-TTY output.
+fn format_in_progress_long(state: Option<git::InProgress>, use_color: bool) -> String {
+    let badge = format_in_progress(state, use_color);
+    if badge.is_empty() {
+        badge
+    } else {
+        format!(", {}", badge)
+    }
+}
+
+/// Combine non-empty suffix parts (uncommitted, processes, ...) with separators.
+fn format_suffix(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .filter(|p| !p.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Format a failed last run for display; successful runs show nothing.
+fn format_last_run_failure(run: Option<&LastRun>, use_color: bool) -> String {
+    match run {
+        Some(run) if !run.succeeded() => {
+            let text = format!("last run failed ({})", run.exit_description());
+            if use_color {
+                format!("\x1b[31m{}\x1b[0m", text) // Red
+            } else {
+                text
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changed(path: &str, untracked: bool) -> git::ChangedFile {
+        git::ChangedFile {
+            path: path.to_string(),
+            untracked,
+        }
+    }
+
+    #[test]
+    fn test_unescape_format() {
+        assert_eq!(unescape("{name}\\t{ahead}\\n"), "{name}\t{ahead}\n");
+        assert_eq!(unescape("a\\\\tb"), "a\\tb");
+        assert_eq!(unescape("keep \\d and \\"), "keep \\d and \\");
+    }
+
+    #[test]
+    fn test_escape_porcelain_field() {
+        assert_eq!(escape_field("plain"), "plain");
+        assert_eq!(escape_field("a\tb\nc"), "a\\tb\\nc");
+        // Escaped first, so `\t` in a value stays distinct from a tab
+        assert_eq!(escape_field("a\\tb"), "a\\\\tb");
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("feature"), "feature");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("Fix login, again"), "\"Fix login, again\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn test_csv_field_defuses_formulas() {
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-rf"), "'-rf");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("a=b"), "a=b");
+    }
+
+    #[test]
+    fn test_format_processes_names_agents() {
+        let agents = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(format_processes(0, &[], false), "");
+        assert_eq!(format_processes(2, &[], false), "2 processes");
+        // A background agent and the sandbox it runs are one agent at work
+        assert_eq!(
+            format_processes(2, &agents(&["claude", "claude"]), false),
+            "claude"
+        );
+        assert_eq!(
+            format_processes(2, &agents(&["claude"]), false),
+            "claude, 1 process"
+        );
+    }
+
+    #[test]
+    fn test_map_parallel_keeps_order() {
+        let items: Vec<usize> = (0..100).collect();
+        let doubled = map_parallel(&items, |n| n * 2);
+        assert_eq!(doubled, (0..100).map(|n| n * 2).collect::<Vec<_>>());
+        assert!(map_parallel(&[] as &[usize], |n| *n).is_empty());
+    }
+
+    #[test]
+    fn test_window_slices_runs_of_silos() {
+        let window = Window::new(Some(3), Some(2)).unwrap();
+        assert_eq!(window.slice(0, 10), 3..6);
+        // A repo of 4 silos after 2 others holds positions 2..6
+        assert_eq!(window.slice(2, 4), 1..4);
+        assert_eq!(window.slice(6, 4), 0..0);
+
+        let all = Window::new(None, None).unwrap();
+        assert_eq!(all.slice(5, 4), 0..4);
+        assert_eq!(all.footer(9), None);
+    }
+
+    #[test]
+    fn test_window_footer() {
+        let window = Window::new(Some(3), Some(2)).unwrap();
+        assert_eq!(
+            window.footer(10).as_deref(),
+            Some("Showing silos 4-6 of 10, --page 3 for more")
+        );
+        assert_eq!(window.footer(5).as_deref(), Some("Showing silos 4-5 of 5"));
+        assert_eq!(
+            window.footer(2).as_deref(),
+            Some("No silos on page 2 (2 in total)")
+        );
+        assert_eq!(Window::new(Some(3), None).unwrap().footer(3), None);
+    }
+
+    #[test]
+    fn test_window_rejects_zero() {
+        assert!(Window::new(Some(0), None).is_err());
+        assert!(Window::new(Some(5), Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_summarize_files_collapses_untracked_directories() {
+        let mut files = vec![changed("src/lib.rs", false)];
+        files.extend((0..4).map(|i| changed(&format!("build/out/{}.o", i), true)));
+        files.push(changed("build/log.txt", true));
+        files.push(changed("src/foo.rs", true));
+
+        assert_eq!(
+            summarize_files(&files, 3),
+            vec!["src/lib.rs", "build/ (5 files)", "src/foo.rs"]
+        );
+        assert_eq!(summarize_files(&files, 0).len(), files.len());
+    }
+
+    #[test]
+    fn test_summarize_files_ignores_tracked_changes() {
+        let files: Vec<_> = (0..3)
+            .map(|i| changed(&format!("src/{}.rs", i), false))
+            .collect();
+        assert_eq!(
+            summarize_files(&files, 2),
+            vec!["src/0.rs", "src/1.rs", "src/2.rs"]
+        );
     }
 }
@@ -1,22 +1,40 @@
 //! The `merge` command: merge a silo's branch into the main worktree's current branch.
 
+use crate::config::{AfterMerge, Config};
 use crate::git;
-use crate::silo;
+use crate::messages;
+use crate::metrics;
+use crate::output::Verbosity;
+use crate::preflight::{self, Operation, Plan};
+use crate::prompt;
+use crate::removal::RemovableSilo;
+use crate::silo::{self, Silo};
 
-use super::{resolve_dash, resolve_silo};
+use super::{reset, resolve_dash, resolve_silo};
 
-pub fn run(name: String, dry_run: bool, quiet: bool) -> Result<(), String> {
+pub fn run(
+    name: String,
+    config: &Config,
+    dry_run: bool,
+    force: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
     let name = resolve_dash(&name)?;
 
-    // Get current repo root and verify we're in the main worktree
-    let repo_root = git::get_repo_root()?;
     let cwd =
         std::env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?;
+    let in_silo = silo::is_silo_path(&cwd);
 
-    if silo::is_silo_path(&cwd) {
-        return Err("Must be run from the main worktree, not from a silo.".to_string());
+    if in_silo && config.merge_from_main_only() {
+        return Err(
+            "Must be run from the main worktree, not from a silo (merge_from_main_only is set)."
+                .to_string(),
+        );
     }
 
+    // The merge always happens in the main worktree, wherever it's run from
+    let repo_root = main_worktree(&git::get_repo_root()?)?;
+
     let silo = resolve_silo(&name)?;
 
     // Verify the silo belongs to the current repo
@@ -27,13 +45,48 @@ pub fn run(name: String, dry_run: bool, quiet: bool) -> Result<(), String> {
         ));
     }
 
+    // Don't merge into changes the user may have forgotten about, which
+    // from inside a silo they can't even see
+    preflight::check_clean(&repo_root, "merging")?;
+
+    let branch_name = silo.branch_name();
+    let target = git::list_worktrees(&repo_root)?
+        .into_iter()
+        .next()
+        .and_then(|wt| wt.branch)
+        .unwrap_or_else(|| "HEAD".to_string());
+    let plan = Plan::new(Operation::Merge, &repo_root, branch_name, &target);
+    let after_merge = config.for_dir(&silo.storage_path)?.after_merge();
+
     if dry_run {
-        println!("Would merge '{}' into current branch", silo.name);
+        println!("Would merge '{}' into '{}'", silo.name, target);
+        for line in plan.lines() {
+            println!("  {}", line);
+        }
+        if !plan.is_up_to_date() {
+            match after_merge {
+                AfterMerge::Keep => {}
+                AfterMerge::Reset => println!("Then would reset silo '{}'", silo.name),
+                AfterMerge::Remove => println!("Then would remove silo '{}'", silo.name),
+            }
+        }
         return Ok(());
     }
 
-    let branch_name = silo.branch_name();
-    if !quiet {
+    if !verbosity.is_quiet() {
+        for line in plan.lines() {
+            println!("{}", line);
+        }
+    }
+    if plan.is_up_to_date() {
+        return Ok(());
+    }
+    if plan.has_conflicts() && !force && !prompt::confirm("Merge anyway?") {
+        println!("{}", messages::ABORTED);
+        return Ok(());
+    }
+
+    if !verbosity.is_quiet() {
         println!("Merging '{}'...", silo.name);
         git::merge_branch_interactive(&repo_root, branch_name)?;
         println!("Merge complete.");
@@ -41,5 +94,63 @@ pub fn run(name: String, dry_run: bool, quiet: bool) -> Result<(), String> {
         git::merge_branch(&repo_root, branch_name)?;
     }
 
+    apply_after_merge(silo, after_merge, verbosity)
+}
+
+/// Do what after_merge says with the merged silo. Blockers such as
+/// uncommitted changes or running processes keep it as it is: the merge
+/// took only its commits, so they'd be lost.
+fn apply_after_merge(silo: Silo, policy: AfterMerge, verbosity: Verbosity) -> Result<(), String> {
+    let kept = |policy: &str| format!("Kept silo '{}' (after_merge = \"{}\")", silo.name, policy);
+    match policy {
+        AfterMerge::Keep => {}
+        AfterMerge::Reset => {
+            let blockers = reset::check_dirty(&silo);
+            if !blockers.is_empty() {
+                eprintln!("{}", messages::blocked(&silo.name, "reset"));
+                for blocker in &blockers {
+                    eprintln!("{}", messages::blocker_line(blocker));
+                }
+                eprintln!("{}", kept("reset"));
+                return Ok(());
+            }
+            let commit = git::get_head_commit(&silo.main_worktree)
+                .map_err(|e| format!("Failed to get main worktree commit: {}", e))?;
+            reset::reset_to(&silo, &commit, verbosity)?;
+            if !verbosity.is_quiet() {
+                println!(
+                    "Reset silo '{}' to commit {}",
+                    silo.name,
+                    messages::short_commit(&commit)
+                );
+            }
+        }
+        AfterMerge::Remove => {
+            let message = kept("remove");
+            match RemovableSilo::try_from(silo) {
+                Ok(removable) => {
+                    let name = removable.name().to_string();
+                    removable.remove(false, verbosity)?;
+                    metrics::record_removed("merge", 1);
+                    if !verbosity.is_quiet() {
+                        println!("{}", messages::removed(&name));
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    eprintln!("{}", message);
+                }
+            }
+        }
+    }
     Ok(())
 }
+
+/// Get the main worktree of the repository containing `worktree`.
+fn main_worktree(worktree: &std::path::Path) -> Result<std::path::PathBuf, String> {
+    git::list_worktrees(worktree)?
+        .into_iter()
+        .next()
+        .map(|wt| wt.path)
+        .ok_or_else(|| format!("Could not find main worktree for {}", worktree.display()))
+}
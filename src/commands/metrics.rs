@@ -0,0 +1,207 @@
+//! The `metrics` command: silo lifecycle metrics in the OpenMetrics text
+//! format, for scraping into dashboards (e.g. with node_exporter's
+//! textfile collector).
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::disk_usage;
+use crate::metrics::{self, Counters};
+use crate::process;
+use crate::silo;
+
+/// The state of storage when metrics were taken.
+#[derive(Debug, Default)]
+struct Snapshot {
+    /// Silos per repository (main worktree)
+    silos: BTreeMap<PathBuf, usize>,
+    /// Running processes silo started in silos
+    processes: usize,
+    /// Bytes used by each storage root; None when not measured
+    storage_bytes: Vec<(PathBuf, Option<u64>)>,
+    counters: Counters,
+}
+
+pub fn run(disk_usage: bool) -> Result<(), String> {
+    let roots = silo::get_silo_base_dirs()?;
+    let mut snapshot = Snapshot {
+        counters: metrics::load(&roots[0]),
+        ..Default::default()
+    };
+    for silo in silo::collect_all_silos()? {
+        if !silo.is_external() {
+            snapshot.processes += process::list_active(&silo.storage_path).len();
+        }
+        *snapshot.silos.entry(silo.main_worktree).or_default() += 1;
+    }
+    snapshot.storage_bytes = roots
+        .into_iter()
+        .map(|root| {
+            let bytes = disk_usage.then(|| disk_usage::dir_size(&root));
+            (root, bytes)
+        })
+        .collect();
+
+    print!("{}", format(&snapshot));
+    Ok(())
+}
+
+/// Escape a label value: backslash, double quote and newline.
+fn label(value: impl std::fmt::Display) -> String {
+    value
+        .to_string()
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+/// Start a metric family.
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+}
+
+fn format(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+
+    header(&mut out, "silo_silos", "gauge", "Silos, by repository.");
+    for (repo, count) in &snapshot.silos {
+        writeln!(
+            out,
+            "silo_silos{{repo=\"{}\"}} {}",
+            label(repo.display()),
+            count
+        )
+        .unwrap();
+    }
+
+    header(
+        &mut out,
+        "silo_tracked_processes",
+        "gauge",
+        "Running processes started in silos.",
+    );
+    writeln!(out, "silo_tracked_processes {}", snapshot.processes).unwrap();
+
+    if snapshot
+        .storage_bytes
+        .iter()
+        .any(|(_, bytes)| bytes.is_some())
+    {
+        header(
+            &mut out,
+            "silo_storage_bytes",
+            "gauge",
+            "Disk space used by each storage root.",
+        );
+        for (root, bytes) in &snapshot.storage_bytes {
+            if let Some(bytes) = bytes {
+                let root = label(root.display());
+                writeln!(out, "silo_storage_bytes{{root=\"{}\"}} {}", root, bytes).unwrap();
+            }
+        }
+    }
+
+    header(
+        &mut out,
+        "silo_removed",
+        "counter",
+        "Silos removed, by the command that removed them.",
+    );
+    for (by, count) in &snapshot.counters.removed {
+        writeln!(out, "silo_removed_total{{by=\"{}\"}} {}", label(by), count).unwrap();
+    }
+
+    header(
+        &mut out,
+        "silo_command_duration_seconds",
+        "summary",
+        "Time spent running silo commands.",
+    );
+    for totals in &snapshot.counters.commands {
+        let command = label(&totals.command);
+        writeln!(
+            out,
+            "silo_command_duration_seconds_count{{command=\"{}\"}} {}",
+            command, totals.count
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "silo_command_duration_seconds_sum{{command=\"{}\"}} {:.3}",
+            command,
+            totals.duration.as_secs_f64()
+        )
+        .unwrap();
+    }
+
+    header(
+        &mut out,
+        "silo_command_failures",
+        "counter",
+        "Silo commands that failed.",
+    );
+    for totals in &snapshot.counters.commands {
+        let command = label(&totals.command);
+        writeln!(
+            out,
+            "silo_command_failures_total{{command=\"{}\"}} {}",
+            command, totals.failures
+        )
+        .unwrap();
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::CommandTotals;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_openmetrics() {
+        let snapshot = Snapshot {
+            silos: BTreeMap::from([(PathBuf::from("/src/\"app\""), 2)]),
+            processes: 1,
+            storage_bytes: vec![(PathBuf::from("/silos"), Some(4096))],
+            counters: Counters {
+                commands: vec![CommandTotals {
+                    command: "list".to_string(),
+                    count: 3,
+                    failures: 1,
+                    duration: Duration::from_millis(1250),
+                }],
+                removed: vec![("gc".to_string(), 2)],
+            },
+        };
+        let out = format(&snapshot);
+        let samples: Vec<&str> = out.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(
+            samples,
+            vec![
+                r#"silo_silos{repo="/src/\"app\""} 2"#,
+                "silo_tracked_processes 1",
+                r#"silo_storage_bytes{root="/silos"} 4096"#,
+                r#"silo_removed_total{by="gc"} 2"#,
+                r#"silo_command_duration_seconds_count{command="list"} 3"#,
+                r#"silo_command_duration_seconds_sum{command="list"} 1.250"#,
+                r#"silo_command_failures_total{command="list"} 1"#,
+            ]
+        );
+        assert!(out.starts_with("# TYPE silo_silos gauge\n"));
+        assert!(out.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_disk_usage_is_optional() {
+        let out = format(&Snapshot {
+            storage_bytes: vec![(PathBuf::from("/silos"), None)],
+            ..Default::default()
+        });
+        assert!(!out.contains("silo_storage_bytes"));
+    }
+}
@@ -2,18 +2,38 @@
 //!
 //! Each subcommand is implemented in its own module for easier parallel development.
 
+pub mod agent;
 pub mod cd;
+pub mod code;
+pub mod complete;
+pub mod diff;
+pub mod doctor;
 pub mod exec;
+pub mod external;
 pub mod gc;
+pub mod hooks;
+pub mod info;
+pub mod last_run;
 pub mod list;
 pub mod merge;
+pub mod metrics;
 pub mod new;
+pub mod note;
+pub mod orphan_branches;
+pub mod policy;
 pub mod prune;
 pub mod rebase;
 pub mod reset;
+pub mod resolve;
+pub mod reuse;
 pub mod rm;
 pub mod sandbox;
 pub mod shell;
+pub mod silo_info;
+pub mod stats;
+pub mod test_matrix;
+pub mod verify;
+pub mod verify_storage;
 
 use crate::shell as shell_integration;
 
@@ -42,21 +62,33 @@ pub fn resolve_silo(name: &str) -> Result<crate::silo::Silo, String> {
     }
 
     let current_repo = crate::git::try_get_repo_root();
+    let resolve_phase = crate::timings::phase("resolve name");
     let result = crate::names::resolve_name(name, &silos, current_repo);
+    drop(resolve_phase);
 
+    found_or_error(name, &silos, &result).cloned()
+}
+
+/// The silo a resolution found, or the error to show for `name`.
+fn found_or_error<'a>(
+    name: &str,
+    silos: &[crate::silo::Silo],
+    result: &crate::names::ResolveResult<'a>,
+) -> Result<&'a crate::silo::Silo, String> {
     match result {
-        crate::names::ResolveResult::Found(silo) => Ok(silo.clone()),
+        crate::names::ResolveResult::Found(silo) => Ok(silo),
         crate::names::ResolveResult::NotFound => Err(format!("Silo not found: {}", name)),
         crate::names::ResolveResult::Ambiguous(matches) => {
             // Generate display names with repo prefix for clarity
-            let display_names = crate::names::generate_display_names(&silos, true);
+            let display_names = crate::names::generate_display_names(silos, true);
             let ambiguous: Vec<String> = matches
                 .iter()
                 .filter_map(|m| {
                     silos
                         .iter()
                         .position(|s| s == *m)
-                        .and_then(|idx| display_names.get(idx).cloned())
+                        .and_then(|idx| display_names.get(idx))
+                        .map(|display| crate::names::quote_display_name(display, m))
                 })
                 .collect();
             Err(format!(
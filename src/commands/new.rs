@@ -1,60 +1,380 @@
 //! The `new` command: create a new silo with a new branch.
 
-use crate::config::Config;
-use crate::git::{self, Verbosity};
+use crate::config::{Config, LOCAL_CONFIG_NAME};
+use crate::created::{self, Created};
+use crate::exit;
+use crate::git;
+use crate::messages;
+use crate::names;
+use crate::output::Verbosity;
+use crate::pending::Pending;
+use crate::registry;
+use crate::roots;
 use crate::runner;
+use crate::sandbox::DockerSandboxConfig;
 use crate::shell;
 use crate::silo;
+use crate::storage::{self, StorageLock};
+use std::path::{Path, PathBuf};
 
+use super::sandbox as sandbox_command;
+
+/// Options for `silo new`.
+pub struct NewOptions {
+    /// Run the command (an agent, only `claude` for now) in a Docker sandbox
+    pub sandbox: bool,
+    /// Move the main worktree's uncommitted changes into the new silo
+    pub take_changes: bool,
+    /// Storage root for this silo only, instead of the configured one
+    pub worktree_dir: Option<PathBuf>,
+}
+
+/// Create a silo and optionally run `command` in it. Returns the exit code
+/// to leave with: the command's own when it fails.
 pub fn run(
     branch: String,
     command: &[String],
+    options: &NewOptions,
     config: &Config,
     dry_run: bool,
-    quiet: bool,
-) -> Result<(), String> {
+    verbosity: Verbosity,
+) -> Result<i32, String> {
     let repo_info = git::get_repo_info()?;
     let repo_root = &repo_info.main_worktree;
-    let silo_path = silo::get_silo_path(&repo_info.name, repo_root, &branch)?;
+
+    // New branches go in the configured namespace, while branches a remote
+    // has already are started from there as named
+    let namespace = config.namespace();
+    let branch = match namespace.as_deref() {
+        Some(namespace) if git::find_upstream(repo_root, &branch).is_none() => {
+            names::namespaced_branch(&branch, Some(namespace))
+        }
+        _ => branch,
+    };
+    let name = names::strip_namespace(&branch, namespace.as_deref()).to_string();
+    let silo_path = match &options.worktree_dir {
+        Some(dir) => names::silo_storage_path(dir, &repo_info.name, repo_root, &name),
+        None => silo::get_silo_path(&repo_info.name, repo_root, &name)?,
+    };
+
+    if options.take_changes && changes_to_take(repo_root).is_empty() {
+        return Err(format!(
+            "No uncommitted changes to take in {}",
+            repo_root.display()
+        ));
+    }
+
+    // A branch only a remote has yet is started from there
+    let upstream = if git::branch_exists(repo_root, &branch) {
+        None
+    } else {
+        git::find_upstream(repo_root, &branch).map(|rb| rb.short_name())
+    };
+    if let Some(upstream) = upstream.as_deref().filter(|_| options.take_changes) {
+        // The changes were made against the main worktree's commit, not the remote's
+        return Err(format!(
+            "Can't take changes into '{}', which starts from {} rather than the current commit",
+            branch, upstream
+        ));
+    }
+
+    // Check the agent before creating anything
+    let sandbox_config = if options.sandbox {
+        Some(sandbox_config(&silo_path, command, config)?)
+    } else {
+        None
+    };
 
     if dry_run {
         println!("Would create silo at: {}", silo_path.display());
-        println!("Would create branch: {}", branch);
-        if !command.is_empty() {
+        match &upstream {
+            Some(upstream) => println!("Would create branch: {} (tracking {})", branch, upstream),
+            None => println!("Would create branch: {}", branch),
+        }
+        if options.take_changes {
+            println!(
+                "Would move uncommitted changes from: {}",
+                repo_root.display()
+            );
+        }
+        if let Some(sandbox_config) = &sandbox_config {
+            println!(
+                "Would run in sandbox: {}",
+                sandbox_config.to_command().join(" ")
+            );
+        } else if !command.is_empty() {
             println!("Would execute: {}", command.join(" "));
         }
-        return Ok(());
+        return Ok(exit::SUCCESS);
+    }
+
+    if let Some(dir) = &options.worktree_dir {
+        // Recorded first, so the silo is never left where nothing looks
+        roots::add(&silo::get_silo_base_dir()?, dir)?;
+    }
+    let pending = create(
+        &silo_path,
+        &branch,
+        upstream.as_deref(),
+        repo_root,
+        config,
+        verbosity,
+    )?;
+    if options.take_changes {
+        take_changes(repo_root, &silo_path, &branch, verbosity)?;
+    }
+    drop(pending);
+
+    // Track this silo as the last used
+    shell::write_directive("last", &name);
+
+    if let Some(sandbox_config) = sandbox_config {
+        // Tracked and recorded as the silo's last run, like `sandbox claude`
+        let status = sandbox_config.run(&silo_path)?;
+        if !status.success() {
+            return Ok(exit::status_code(status));
+        }
+        if !verbosity.is_quiet() {
+            eprintln!("[silo: {}]", branch);
+        }
+    } else if !command.is_empty() {
+        // Execute command in the new silo if provided
+        let silo = silo::Silo {
+            name: name.clone(),
+            branch: Some(branch.clone()),
+            main_worktree: repo_root.clone(),
+            storage_path: silo_path,
+            repo_name: repo_info.name,
+        };
+        let code = runner::run_command(command, &silo, config, runner::Terminal::Inherit, true)?;
+        if code != exit::SUCCESS {
+            return Ok(code);
+        }
+        if !verbosity.is_quiet() {
+            eprintln!("[silo: {}]", branch);
+        }
+    }
+
+    Ok(exit::SUCCESS)
+}
+
+/// Uncommitted files `--take-changes` would move. The main worktree's own
+/// silo config stays where it is.
+fn changes_to_take(repo_root: &Path) -> Vec<String> {
+    git::get_uncommitted_files(repo_root)
+        .into_iter()
+        .filter(|file| file != LOCAL_CONFIG_NAME)
+        .collect()
+}
+
+/// Move uncommitted changes (including untracked files) from the main
+/// worktree into the freshly created silo, which starts at the same commit
+/// (`run` refuses branches started from a remote).
+fn take_changes(
+    repo_root: &Path,
+    silo_path: &Path,
+    branch: &str,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let message = format!("silo: changes taken into {}", branch);
+    if !git::stash_push(repo_root, &message, &[LOCAL_CONFIG_NAME], verbosity)? {
+        // Never pop an older, unrelated stash entry
+        return Err(format!(
+            "No uncommitted changes to take in {}",
+            repo_root.display()
+        ));
     }
 
-    // Create parent directories if needed
-    if let Some(parent) = silo_path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create silo directory: {}", e))?;
+    // The stash is shared by all worktrees of the repository
+    if let Err(e) = git::stash_pop(silo_path, verbosity) {
+        return Err(format!(
+            "{}\nThe changes are kept in `git stash list` as '{}'",
+            e, message
+        ));
     }
 
-    let verbosity = if quiet {
-        Verbosity::Quiet
-    } else {
-        Verbosity::Verbose
+    if !verbosity.is_quiet() {
+        println!("Moved uncommitted changes from {}", repo_root.display());
+    }
+    Ok(())
+}
+
+/// In a partial clone, fetch the blobs the new silo's checkout needs in one
+/// batch. Otherwise the checkout fetches them as it goes, which can mean a
+/// storm of requests. Only the paths of a sparse checkout are fetched, as
+/// `git worktree add` copies its sparse-checkout settings. Checkout still
+/// fetches whatever is missing, so failures only warn.
+fn prefetch_blobs(repo_root: &Path, verbosity: Verbosity) {
+    let Some(remote) = git::promisor_remotes(repo_root).into_iter().next() else {
+        return;
     };
+    let paths = git::sparse_checkout_paths(repo_root).unwrap_or_default();
+    let result = git::missing_blobs(repo_root, "HEAD", &paths).and_then(|oids| {
+        if oids.is_empty() {
+            return Ok(());
+        }
+        if !verbosity.is_quiet() {
+            println!(
+                "Prefetching {} from {}...",
+                messages::count(oids.len(), "missing blob", "missing blobs"),
+                remote.name
+            );
+        }
+        git::fetch_objects(repo_root, &remote.name, &oids, verbosity)
+    });
+    if let Err(e) = result {
+        eprintln!("Warning: {}", e);
+    }
+}
+
+/// Register the repository for background `git maintenance` unless it is
+/// already. Every silo shares the repository's object store, so keeping
+/// its commit-graph and prefetched refs fresh speeds up all of them.
+fn start_maintenance(repo_root: &Path, verbosity: Verbosity) {
+    if git::is_maintenance_registered(repo_root) {
+        return;
+    }
+    match git::start_maintenance(repo_root, Verbosity::Quiet) {
+        Ok(()) if !verbosity.is_quiet() => {
+            println!("Registered {} for git maintenance", repo_root.display());
+        }
+        Ok(()) => {}
+        Err(e) => eprintln!("Warning: {} (set git_maintenance = false to skip)", e),
+    }
+}
 
-    if !quiet {
-        println!("Creating branch '{}'...", branch);
+/// Build the sandbox configuration for `new --sandbox -- AGENT ARGS...`.
+fn sandbox_config(
+    silo_path: &Path,
+    command: &[String],
+    config: &Config,
+) -> Result<DockerSandboxConfig, String> {
+    match command.split_first() {
+        Some((agent, args)) if agent == "claude" => {
+            sandbox_command::claude_config(silo_path, &[], args, config)
+        }
+        Some((agent, _)) => Err(format!(
+            "Cannot run '{}' in the sandbox (supported: claude)",
+            agent
+        )),
+        None => Err("--sandbox needs an agent to run, e.g. `-- claude -p \"...\"`".to_string()),
     }
-    git::create_worktree(&silo_path, &branch, repo_root, verbosity)?;
-    if !quiet {
+}
+
+/// Create the worktree and branch for a new silo at `silo_path`, starting
+/// at the remote branch `upstream` (e.g. "origin/feature") and tracking it
+/// if given.
+///
+/// `gc` and `prune` leave the silo alone until the returned guard is
+/// dropped, so hold on to it while finishing the silo off.
+pub fn create(
+    silo_path: &Path,
+    branch: &str,
+    upstream: Option<&str>,
+    repo_root: &Path,
+    config: &Config,
+    verbosity: Verbosity,
+) -> Result<Pending, String> {
+    // Create the storage directory with restrictive permissions on first use
+    let base_dir = match silo::get_containing_base_dir(silo_path) {
+        Some(dir) => dir,
+        None => silo::get_repo_base_dir(repo_root)?,
+    };
+    storage::ensure_base_dir(&base_dir, config.storage_mode()?)?;
+    let mut worktree_config = config.signing_git_config()?;
+    worktree_config.extend(config.status_git_config());
+    check_filesystem(&base_dir, silo_path, repo_root, verbosity)?;
+
+    if config.prefetch_blobs() {
+        prefetch_blobs(repo_root, verbosity);
+    }
+
+    if !verbosity.is_quiet() {
+        match upstream {
+            Some(upstream) => println!("Creating branch '{}' from {}...", branch, upstream),
+            None => println!("Creating branch '{}'...", branch),
+        }
+    }
+    let pending = {
+        // Serialize with other silo processes sharing this storage
+        // directory; gc removes empty directories under the same lock
+        let _lock = StorageLock::acquire(&base_dir)?;
+        let pending = Pending::start(silo_path)?;
+        if let Some(parent) = silo_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create silo directory: {}", e))?;
+        }
+        git::create_worktree(silo_path, branch, upstream, repo_root, verbosity)?;
+        // In the silo's own config, so other worktrees keep theirs
+        git::set_worktree_config(silo_path, &worktree_config)?;
+        // Lets gc find this repository again if it's moved
+        if let Err(e) = silo::get_silo_base_dir()
+            .and_then(|primary| registry::register_repo(&primary, repo_root))
+        {
+            eprintln!("Warning: {}", e);
+        }
+        pending
+    };
+    // The silo is usable without it, so only warn if it can't be written
+    if let Err(e) = created::record(silo_path, &Created::now()) {
+        eprintln!("Warning: {}", e);
+    }
+    if config.git_maintenance() {
+        start_maintenance(repo_root, verbosity);
+    }
+    if !verbosity.is_quiet() {
         println!("Created silo: {}", silo_path.display());
     }
 
-    // Track this silo as the last used
-    shell::write_directive("last", &branch);
+    Ok(pending)
+}
 
-    // Execute command in the new silo if provided
-    if !command.is_empty() {
-        runner::run_command(command, &silo_path, config)?;
-        if !quiet {
-            eprintln!("[silo: {}]", branch);
+/// Check the storage filesystem for properties that would break the new
+/// silo, and warn about ones that may cause surprises.
+fn check_filesystem(
+    base_dir: &Path,
+    silo_path: &Path,
+    repo_root: &Path,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let fs = storage::probe_filesystem(base_dir);
+
+    if fs.case_insensitive {
+        // On a case-insensitive volume `Feature` and `feature` are the same
+        // directory, so the new worktree would land inside the existing silo
+        let folded = silo_path.to_string_lossy().to_lowercase();
+        if let Some(existing) = silo::collect_silos_for_repo(repo_root)?
+            .into_iter()
+            .find(|s| s.storage_path.to_string_lossy().to_lowercase() == folded)
+        {
+            return Err(format!(
+                "Silo '{}' differs only in case from the new name, and the storage \
+                 filesystem is case-insensitive",
+                existing.name
+            ));
         }
+
+        if !verbosity.is_quiet() {
+            let files = git::list_tracked_files(repo_root).unwrap_or_default();
+            let collisions = storage::case_collisions(files.iter().map(String::as_str));
+            if !collisions.is_empty() {
+                eprintln!(
+                    "Warning: {} group(s) of tracked files differ only in case and will \
+                     overwrite each other on this case-insensitive filesystem:",
+                    collisions.len()
+                );
+                for group in &collisions {
+                    eprintln!("  {}", group.join(", "));
+                }
+            }
+        }
+    }
+
+    if fs.network && !verbosity.is_quiet() {
+        eprintln!(
+            "Warning: silo storage is on a network filesystem; git operations may be \
+             slow and storage locking may be unreliable"
+        );
     }
 
     Ok(())
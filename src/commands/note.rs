@@ -0,0 +1,59 @@
+//! The `note` command: describe what a silo's branch is for.
+//!
+//! The description is git's own branch description (`git branch
+//! --edit-description`), so git tools that read it, like `git
+//! request-pull` and `git format-patch --cover-from-description`, see the
+//! same text.
+
+use crate::git;
+use crate::output::Verbosity;
+
+use super::{resolve_dash, resolve_silo};
+
+/// What to do with a silo's branch description.
+pub enum NoteAction {
+    /// Print it
+    Show,
+    /// Replace it with the given text
+    Set(String),
+    /// Open it in git's editor
+    Edit,
+    /// Remove it
+    Clear,
+}
+
+pub fn run(name: String, action: NoteAction, verbosity: Verbosity) -> Result<(), String> {
+    let name = resolve_dash(&name)?;
+    let silo = resolve_silo(&name)?;
+    let branch = silo
+        .branch
+        .as_deref()
+        .ok_or_else(|| format!("Silo '{}' is not on a branch (detached HEAD)", name))?;
+
+    match action {
+        NoteAction::Show => match git::get_branch_description(&silo.storage_path, branch) {
+            Some(description) => println!("{}", description),
+            None if !verbosity.is_quiet() => {
+                eprintln!(
+                    "No description for '{}' (set one with `silo note {} TEXT`)",
+                    branch, name
+                );
+            }
+            None => {}
+        },
+        NoteAction::Set(text) => {
+            git::set_branch_description(&silo.storage_path, branch, &text)?;
+            if !verbosity.is_quiet() {
+                println!("Described '{}'", branch);
+            }
+        }
+        NoteAction::Edit => git::edit_branch_description(&silo.storage_path, branch)?,
+        NoteAction::Clear => {
+            git::set_branch_description(&silo.storage_path, branch, "")?;
+            if !verbosity.is_quiet() {
+                println!("Removed the description of '{}'", branch);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,223 @@
+//! The `orphan-branches` command: report local branches that no worktree
+//! has checked out and that track no upstream, often left behind by removed
+//! silos, and delete the merged ones. `gc` cleans up directories; this
+//! cleans up branches.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::activity;
+use crate::config::Config;
+use crate::created;
+use crate::git;
+use crate::messages;
+use crate::output::Verbosity;
+use crate::prompt;
+use crate::registry;
+use crate::silo;
+
+/// Options for `silo orphan-branches`.
+pub struct OrphanOptions {
+    /// Report branches in every known repository
+    pub all: bool,
+    /// Delete the orphan branches merged into the main branch
+    pub delete_merged: bool,
+    /// Print the report as JSON instead of prose
+    pub json: bool,
+}
+
+/// A branch no worktree has checked out and with no upstream.
+#[derive(Debug, Serialize)]
+struct OrphanBranch {
+    repo: PathBuf,
+    branch: String,
+    /// Whether the main branch contains the branch, so deleting it loses nothing
+    merged: bool,
+    /// Commits the branch has that the main branch doesn't
+    ahead: u32,
+    /// Commit time of the branch tip, in seconds since the Unix epoch
+    committed_at: Option<u64>,
+    deleted: bool,
+}
+
+pub fn run(
+    options: &OrphanOptions,
+    config: &Config,
+    dry_run: bool,
+    force: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let repos = if options.all {
+        known_repos()?
+    } else if let Some(root) = git::try_get_repo_root() {
+        vec![root]
+    } else {
+        return Err(
+            "Not in a git repository. Use --all to check every known repository.".to_string(),
+        );
+    };
+
+    let mut orphans = Vec::new();
+    for repo in &repos {
+        let protected = config.for_dir(repo)?.protected_branches();
+        orphans.extend(find_orphans(repo, &protected)?);
+    }
+
+    if options.delete_merged {
+        delete_merged(&mut orphans, options.json, dry_run, force, verbosity);
+    }
+
+    if options.json {
+        let json = serde_json::to_string_pretty(&orphans)
+            .map_err(|e| format!("Failed to serialize orphan branches: {}", e))?;
+        println!("{}", json);
+    } else if !verbosity.is_quiet() {
+        print_report(&orphans, options.delete_merged);
+    }
+    Ok(())
+}
+
+/// Repositories silo knows of: those it has created silos for, and those
+/// of the silos in storage. Repositories that no longer exist are skipped.
+pub(super) fn known_repos() -> Result<Vec<PathBuf>, String> {
+    let mut repos: Vec<PathBuf> = registry::load(&silo::get_silo_base_dir()?)
+        .into_iter()
+        .map(|repo| repo.path)
+        .chain(
+            silo::collect_all_silos()?
+                .into_iter()
+                .map(|s| s.main_worktree),
+        )
+        .filter(|path| path.is_dir())
+        .collect();
+    repos.sort();
+    repos.dedup();
+    Ok(repos)
+}
+
+/// The orphan branches of one repository. The main branch and protected
+/// branches are never orphans.
+fn find_orphans(repo: &Path, protected: &[String]) -> Result<Vec<OrphanBranch>, String> {
+    let worktrees = git::list_worktrees(repo)?;
+    let main_branch = git::get_main_branch(repo);
+    let orphans = git::list_local_branch_details(repo)
+        .into_iter()
+        .filter(|b| b.upstream.is_none())
+        .filter(|b| b.name != main_branch && !protected.contains(&b.name))
+        .filter(|b| {
+            !worktrees
+                .iter()
+                .any(|wt| wt.branch.as_deref() == Some(b.name.as_str()))
+        })
+        .map(|b| OrphanBranch {
+            repo: repo.to_path_buf(),
+            merged: git::is_branch_merged(repo, &b.name, &main_branch),
+            ahead: git::get_ahead_behind(repo, &b.name, &main_branch).0,
+            branch: b.name,
+            committed_at: b.committed_at,
+            deleted: false,
+        })
+        .collect();
+    Ok(orphans)
+}
+
+/// Delete the merged orphan branches, after confirmation unless `force`.
+fn delete_merged(
+    orphans: &mut [OrphanBranch],
+    json: bool,
+    dry_run: bool,
+    force: bool,
+    verbosity: Verbosity,
+) {
+    let merged = orphans.iter().filter(|o| o.merged).count();
+    if merged == 0 {
+        return;
+    }
+    let text = !json && !verbosity.is_quiet();
+    if dry_run {
+        if text {
+            for orphan in orphans.iter().filter(|o| o.merged) {
+                println!("Would delete branch '{}'", orphan.branch);
+            }
+        }
+        return;
+    }
+    let message = format!(
+        "Delete {}?",
+        messages::count(merged, "merged orphan branch", "merged orphan branches")
+    );
+    if !force && !prompt::confirm(&message) {
+        if text {
+            println!("{}", messages::ABORTED);
+        }
+        return;
+    }
+
+    for orphan in orphans.iter_mut().filter(|o| o.merged) {
+        // Reported below, and git's own output would break the JSON
+        match git::delete_branch(&orphan.repo, &orphan.branch, Verbosity::Quiet) {
+            Ok(()) => {
+                orphan.deleted = true;
+                if text {
+                    println!("Deleted branch '{}'", orphan.branch);
+                }
+            }
+            Err(e) => eprintln!(
+                "Warning: Failed to delete branch '{}': {}",
+                orphan.branch, e
+            ),
+        }
+    }
+}
+
+/// Print the orphan branches left, grouped by repository.
+fn print_report(orphans: &[OrphanBranch], deleting: bool) {
+    let left: Vec<&OrphanBranch> = orphans.iter().filter(|o| !o.deleted).collect();
+    if left.is_empty() {
+        if orphans.is_empty() {
+            println!("No orphan branches.");
+        }
+        return;
+    }
+    // Set apart from the lines about deleted branches
+    if deleting && orphans.iter().any(|o| o.merged) {
+        println!();
+    }
+
+    let width = left.iter().map(|o| o.branch.len()).max().unwrap_or(0);
+    let mut repo: Option<&Path> = None;
+    for orphan in &left {
+        if repo != Some(orphan.repo.as_path()) {
+            repo = Some(orphan.repo.as_path());
+            println!("{}:", orphan.repo.display());
+        }
+        let age = orphan
+            .committed_at
+            .map_or("-".to_string(), |at| created::format_age(activity::age(at)));
+        let state = if orphan.merged {
+            "merged".to_string()
+        } else {
+            format!("{} not merged", commits(orphan.ahead))
+        };
+        println!(
+            "  {:<width$}  {:>4}  {}",
+            orphan.branch,
+            age,
+            state,
+            width = width
+        );
+    }
+
+    let merged = left.iter().filter(|o| o.merged).count();
+    if merged > 0 && !deleting {
+        println!(
+            "\n{} merged. Delete with `silo orphan-branches --delete-merged`.",
+            messages::count(merged, "branch is", "branches are")
+        );
+    }
+}
+
+fn commits(n: u32) -> String {
+    messages::count(n as usize, "commit", "commits")
+}
@@ -0,0 +1,42 @@
+//! The `policy` command: show or change what silo may do to a silo's branch.
+
+use crate::output::Verbosity;
+use crate::policy::{self, Policy};
+use crate::silo;
+
+use super::{resolve_dash, resolve_silo};
+
+/// Show a silo's policy, or change it when `no_rebase` is given.
+pub fn run(name: String, no_rebase: Option<bool>, verbosity: Verbosity) -> Result<(), String> {
+    let name = resolve_dash(&name)?;
+    let silo = resolve_silo(&name)?;
+
+    let Some(no_rebase) = no_rebase else {
+        println!(
+            "rebase: {}",
+            if policy::load(&silo.storage_path).no_rebase {
+                "refused (keeps exact history)"
+            } else {
+                "allowed"
+            }
+        );
+        return Ok(());
+    };
+
+    if !silo::is_silo_path(&silo.storage_path) {
+        return Err(format!(
+            "Silo '{}' is an external worktree, which has no tracking directory to record a policy in",
+            name
+        ));
+    }
+    policy::store(&silo.storage_path, &Policy { no_rebase })?;
+
+    if !verbosity.is_quiet() {
+        if no_rebase {
+            println!("'{}' won't be rebased", name);
+        } else {
+            println!("'{}' may be rebased", name);
+        }
+    }
+    Ok(())
+}
@@ -1,12 +1,51 @@
 //! The `prune` command: remove silos with no uncommitted changes.
 
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::created;
 use crate::git;
+use crate::messages;
+use crate::metrics;
 use crate::names;
+use crate::output::Verbosity;
+use crate::pending;
 use crate::prompt;
 use crate::removal;
+use crate::removal_plan::RemovalPlan;
 use crate::silo;
 
-pub fn run(all: bool, dry_run: bool, force: bool, quiet: bool) -> Result<(), String> {
+/// Options for `silo prune`.
+pub struct PruneOptions {
+    /// Prune silos for all repositories
+    pub all: bool,
+    /// Only prune silos created longer ago than this
+    pub older_than: Option<Duration>,
+    /// Print the removal plan as JSON (with --dry-run)
+    pub json: bool,
+    /// Carry out this approved removal plan instead
+    pub plan_file: Option<PathBuf>,
+    /// Also remove worktrees locked with `git worktree lock` (--force twice)
+    pub unlock: bool,
+}
+
+pub fn run(
+    options: &PruneOptions,
+    config: &Config,
+    dry_run: bool,
+    force: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    if let Some(plan_file) = &options.plan_file {
+        return super::rm::execute_plan(plan_file, "prune", dry_run, verbosity);
+    }
+    if options.json && !dry_run {
+        return Err(messages::JSON_PLAN_NEEDS_DRY_RUN.to_string());
+    }
+    let PruneOptions {
+        all, older_than, ..
+    } = *options;
     let repo_root = git::try_get_repo_root();
 
     let prunable = if all {
@@ -19,8 +58,20 @@ pub fn run(all: bool, dry_run: bool, force: bool, quiet: bool) -> Result<(), Str
         );
     };
 
+    // Only silos known to be old enough; the age of others is unknown
+    let prunable: Vec<_> = match older_than {
+        Some(age) => prunable
+            .into_iter()
+            .filter(|s| created::load(&s.storage_path).is_some_and(|c| c.age() > age))
+            .collect(),
+        None => prunable,
+    };
+
     if prunable.is_empty() {
-        if !quiet {
+        if options.json {
+            return RemovalPlan::new("prune", force, vec![], vec![]).print();
+        }
+        if !verbosity.is_quiet() {
             println!("No clean silos to prune.");
         }
         return Ok(());
@@ -28,12 +79,24 @@ pub fn run(all: bool, dry_run: bool, force: bool, quiet: bool) -> Result<(), Str
 
     // Convert to RemovableSilo, partitioning into removable and blocked
     let (removable, blocked): (Vec<_>, Vec<_>) = if force {
-        // With force, all silos are removable
-        let removable: Vec<_> = prunable
-            .into_iter()
-            .map(removal::RemovableSilo::from_silo_unchecked)
-            .collect();
-        (removable, Vec::new())
+        // With force, all silos are removable except ones still being created
+        // and, unless --force was given twice, locked ones
+        let mut removable = Vec::new();
+        let mut blocked = Vec::new();
+        for silo in prunable {
+            if pending::is_pending(&silo.storage_path) {
+                blocked.push(removal::RemovalError {
+                    silo,
+                    blockers: vec![removal::RemovalBlocker::BeingCreated],
+                });
+                continue;
+            }
+            match removal::RemovableSilo::forced(silo, options.unlock) {
+                Ok(r) => removable.push(r),
+                Err(e) => blocked.push(e),
+            }
+        }
+        (removable, blocked)
     } else {
         // Without force, validate each silo
         let mut removable = Vec::new();
@@ -53,7 +116,8 @@ pub fn run(all: bool, dry_run: bool, force: bool, quiet: bool) -> Result<(), Str
         .map(|r| r.silo().clone())
         .chain(blocked.iter().map(|e| e.silo.clone()))
         .collect();
-    let display_names = names::generate_display_names(&all_silos, all);
+    let display_names =
+        names::generate_display_names(&all_silos, all || config.always_qualify_names());
     let display_name_map: std::collections::HashMap<_, _> = all_silos
         .iter()
         .zip(display_names.iter())
@@ -68,32 +132,53 @@ pub fn run(all: bool, dry_run: bool, force: bool, quiet: bool) -> Result<(), Str
             .unwrap_or_else(|| silo.name.clone())
     };
 
+    if dry_run && options.json {
+        let remove = removable
+            .iter()
+            .map(|r| r.planned(&get_display_name(r.silo())))
+            .collect::<Result<Vec<_>, String>>()?;
+        let blocked = blocked
+            .iter()
+            .map(|e| e.planned(&get_display_name(&e.silo)))
+            .collect();
+        return RemovalPlan::new("prune", force, remove, blocked).print();
+    }
+
     if dry_run {
         for error in &blocked {
-            println!("Would skip: {} (blocked)", get_display_name(&error.silo));
+            println!("{}", messages::would_skip(&get_display_name(&error.silo)));
         }
         for r in &removable {
             let display_name = get_display_name(r.silo());
-            println!("Would remove silo: {}", display_name);
+            println!("{}", messages::would_remove(&display_name));
             println!("  Path: {}", r.silo().storage_path.display());
+            r.print_dry_run_branch();
         }
-        println!("\n{} silo(s) would be pruned.", removable.len());
+        println!("\n{} would be pruned.", messages::silos(removable.len()));
         if !blocked.is_empty() {
-            println!("{} silo(s) skipped due to blockers.", blocked.len());
+            println!(
+                "{} skipped due to blockers.",
+                messages::silos(blocked.len())
+            );
         }
         return Ok(());
     }
 
     // Report skipped silos
-    if !blocked.is_empty() && !quiet {
-        eprintln!("Skipping {} silo(s) with blockers:", blocked.len());
+    if !blocked.is_empty() && !verbosity.is_quiet() {
+        eprintln!("{}", messages::skipping_blocked(blocked.len()));
         for error in &blocked {
-            eprintln!("  {}", get_display_name(&error.silo));
+            let reasons: Vec<String> = error.blockers.iter().map(|b| b.summary()).collect();
+            eprintln!(
+                "  {} ({})",
+                get_display_name(&error.silo),
+                reasons.join(", ")
+            );
         }
     }
 
     if removable.is_empty() {
-        if !quiet {
+        if !verbosity.is_quiet() {
             println!("No silos to prune (after excluding blocked silos).");
         }
         return Ok(());
@@ -101,12 +186,12 @@ pub fn run(all: bool, dry_run: bool, force: bool, quiet: bool) -> Result<(), Str
 
     // Batch confirmation
     if !force {
-        println!("Will prune {} silo(s):", removable.len());
+        println!("Will prune {}:", messages::silos(removable.len()));
         for r in &removable {
             println!("  {}", get_display_name(r.silo()));
         }
         if !prompt::confirm("Continue?") {
-            println!("Aborted.");
+            println!("{}", messages::ABORTED);
             return Ok(());
         }
     }
@@ -114,9 +199,10 @@ pub fn run(all: bool, dry_run: bool, force: bool, quiet: bool) -> Result<(), Str
     // Execute removals
     for r in removable {
         let display_name = get_display_name(r.silo());
-        r.remove(force, quiet)?;
-        if !quiet {
-            println!("Pruned: {}", display_name);
+        r.remove(force, verbosity)?;
+        metrics::record_removed("prune", 1);
+        if !verbosity.is_quiet() {
+            println!("{}", messages::removed(&display_name));
         }
     }
 
@@ -1,12 +1,25 @@
 //! The `rebase` command: rebase a silo's commits on top of the main branch.
 
-use crate::git;
+use crate::git::{self, RebaseOptions};
+use crate::messages;
+use crate::output::Verbosity;
+use crate::policy;
+use crate::preflight::{self, Operation, Plan};
+use crate::prompt;
 
 use super::{resolve_dash, resolve_silo};
 
-pub fn run(name: String, dry_run: bool, quiet: bool) -> Result<(), String> {
+pub fn run(
+    name: String,
+    options: RebaseOptions,
+    dry_run: bool,
+    force: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
     let name = resolve_dash(&name)?;
     let silo = resolve_silo(&name)?;
+    // Before anything else, since --force doesn't override it
+    policy::check_rebase(&name, &silo.storage_path)?;
 
     // Get main branch from the silo's main worktree
     let worktrees = git::list_worktrees(&silo.main_worktree)?;
@@ -15,17 +28,52 @@ pub fn run(name: String, dry_run: bool, quiet: bool) -> Result<(), String> {
         .and_then(|wt| wt.branch.as_deref())
         .ok_or("Could not determine main branch")?;
 
+    // git refuses to rebase a dirty worktree; say so before it gets that far
+    preflight::check_clean(&silo.storage_path, "rebasing")?;
+    let plan = Plan::new(
+        Operation::Rebase,
+        &silo.storage_path,
+        silo.branch_name(),
+        main_branch,
+    );
+
     if dry_run {
-        println!("Would rebase '{}' onto '{}'", silo.name, main_branch);
+        let flags = options.args();
+        if flags.is_empty() {
+            println!("Would rebase '{}' onto '{}'", silo.name, main_branch);
+        } else {
+            println!(
+                "Would rebase '{}' onto '{}' ({})",
+                silo.name,
+                main_branch,
+                flags.join(" ")
+            );
+        }
+        for line in plan.lines() {
+            println!("  {}", line);
+        }
+        return Ok(());
+    }
+
+    if !verbosity.is_quiet() {
+        for line in plan.lines() {
+            println!("{}", line);
+        }
+    }
+    if plan.is_up_to_date() {
+        return Ok(());
+    }
+    if plan.has_conflicts() && !force && !prompt::confirm("Rebase anyway?") {
+        println!("{}", messages::ABORTED);
         return Ok(());
     }
 
-    if !quiet {
+    if !verbosity.is_quiet() {
         println!("Rebasing '{}' onto '{}'...", silo.name, main_branch);
-        git::rebase_onto_interactive(&silo.storage_path, main_branch)?;
+        git::rebase_onto_interactive(&silo.storage_path, main_branch, options)?;
         println!("Rebase complete.");
     } else {
-        git::rebase_onto(&silo.storage_path, main_branch)?;
+        git::rebase_onto(&silo.storage_path, main_branch, options)?;
     }
 
     Ok(())
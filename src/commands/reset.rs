@@ -1,17 +1,37 @@
 //! The `reset` command: reset a silo to the main worktree's current commit.
 
-use crate::git::{self, Verbosity};
+use crate::git;
+use crate::messages;
+use crate::output::Verbosity;
+use crate::process;
 use crate::prompt;
-use crate::removal::RemovalBlocker;
+use crate::removal::{self, RemovalBlocker};
 use crate::silo::Silo;
 
 use super::{resolve_dash, resolve_silo};
 
-/// Check if a silo has uncommitted changes or unmerged commits.
+/// Check if a silo has uncommitted changes, unmerged commits, running
+/// processes or a git operation stopped partway, or is locked with
+/// `git worktree lock`.
 /// Returns a list of blockers if the silo is dirty.
-fn check_dirty(silo: &Silo) -> Vec<RemovalBlocker> {
+pub(super) fn check_dirty(silo: &Silo) -> Vec<RemovalBlocker> {
     let mut blockers = Vec::new();
 
+    // A locked worktree was set aside on purpose; leave it as it is
+    if let Some(reason) = removal::lock_reason(silo) {
+        blockers.push(RemovalBlocker::Locked(reason));
+    }
+
+    // Resetting under a running process or in the middle of a rebase leaves
+    // the silo in a state neither expects
+    if let Some(state) = git::get_in_progress(&silo.storage_path) {
+        blockers.push(RemovalBlocker::InProgress(state));
+    }
+    let processes = process::list_active(&silo.storage_path);
+    if !processes.is_empty() {
+        blockers.push(RemovalBlocker::ActiveProcesses(processes));
+    }
+
     // Check for uncommitted changes
     let uncommitted = git::get_uncommitted_stats(&silo.storage_path);
     if !uncommitted.is_clean() {
@@ -30,14 +50,10 @@ fn check_dirty(silo: &Silo) -> Vec<RemovalBlocker> {
 }
 
 fn get_main_branch(silo: &Silo) -> String {
-    git::list_worktrees(&silo.main_worktree)
-        .ok()
-        .and_then(|wts| wts.into_iter().next())
-        .and_then(|wt| wt.branch)
-        .unwrap_or_else(|| "main".to_string())
+    git::get_main_branch(&silo.main_worktree)
 }
 
-pub fn run(name: String, dry_run: bool, force: bool, quiet: bool) -> Result<(), String> {
+pub fn run(name: String, dry_run: bool, force: bool, verbosity: Verbosity) -> Result<(), String> {
     let name = resolve_dash(&name)?;
     let silo = resolve_silo(&name)?;
 
@@ -45,13 +61,13 @@ pub fn run(name: String, dry_run: bool, force: bool, quiet: bool) -> Result<(),
     let blockers = check_dirty(&silo);
 
     if !blockers.is_empty() && !force {
-        eprintln!("Silo '{}' has uncommitted work:", silo.name);
+        eprintln!("{}", messages::blocked(&silo.name, "reset"));
         for blocker in &blockers {
-            eprintln!("  - {}", blocker);
+            eprintln!("{}", messages::blocker_line(blocker));
         }
 
-        if !prompt::confirm("Reset anyway? All changes will be lost.") {
-            println!("Aborted.");
+        if !prompt::confirm(&messages::confirm_discard("Reset")) {
+            println!("{}", messages::ABORTED);
             return Ok(());
         }
     }
@@ -64,40 +80,46 @@ pub fn run(name: String, dry_run: bool, force: bool, quiet: bool) -> Result<(),
         println!(
             "Would reset silo '{}' to commit {}",
             silo.name,
-            &main_commit[..12.min(main_commit.len())]
+            messages::short_commit(&main_commit)
         );
         println!("  Path: {}", silo.storage_path.display());
         if !blockers.is_empty() {
             println!("  Would discard:");
             for blocker in &blockers {
-                println!("    - {}", blocker);
+                println!("  {}", messages::blocker_line(blocker));
             }
         }
         return Ok(());
     }
 
-    // Perform the reset and clean
-    let verbosity = if quiet {
-        Verbosity::Quiet
-    } else {
-        Verbosity::Verbose
-    };
-    git::reset_hard(&silo.storage_path, &main_commit, verbosity)
-        .map_err(|e| format!("Failed to reset silo: {}", e))?;
-    git::clean(&silo.storage_path, verbosity)
-        .map_err(|e| format!("Failed to clean silo: {}", e))?;
+    reset_to(&silo, &main_commit, verbosity)?;
 
-    if !quiet {
+    if !verbosity.is_quiet() {
         println!(
             "Reset silo '{}' to commit {}",
             silo.name,
-            &main_commit[..12.min(main_commit.len())]
+            messages::short_commit(&main_commit)
         );
     }
 
     Ok(())
 }
 
+/// Reset a silo to `commit`, discarding its changes and aborting a git
+/// operation stopped partway.
+pub(super) fn reset_to(silo: &Silo, commit: &str, verbosity: Verbosity) -> Result<(), String> {
+    // A reset alone would leave git thinking the operation is still going
+    if let Some(state) = git::get_in_progress(&silo.storage_path) {
+        git::abort_in_progress(&silo.storage_path, state, verbosity)
+            .map_err(|e| format!("Failed to reset silo: {}", e))?;
+    }
+
+    // Perform the reset and clean
+    git::reset_hard(&silo.storage_path, commit, verbosity)
+        .map_err(|e| format!("Failed to reset silo: {}", e))?;
+    git::clean(&silo.storage_path, verbosity).map_err(|e| format!("Failed to clean silo: {}", e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
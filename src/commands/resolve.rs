@@ -0,0 +1,263 @@
+//! The hidden `resolve` command: resolve a silo name without side effects,
+//! optionally explaining how the name was matched.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::git;
+use crate::names::{self, Resolution, ResolveResult, ResolveStep};
+use crate::silo::{self, Silo};
+
+use super::{found_or_error, resolve_dash};
+
+/// A silo considered during resolution, as printed by `resolve --json`.
+#[derive(Serialize)]
+struct SiloJson {
+    id: String,
+    qualified_name: String,
+    path: PathBuf,
+}
+
+impl SiloJson {
+    fn new(silo: &Silo) -> Self {
+        Self {
+            id: silo.id(),
+            qualified_name: names::qualified_name(silo),
+            path: silo.storage_path.clone(),
+        }
+    }
+
+    fn all(silos: &Option<Vec<&Silo>>) -> Option<Vec<Self>> {
+        silos
+            .as_ref()
+            .map(|silos| silos.iter().map(|s| Self::new(s)).collect())
+    }
+}
+
+/// How the input was parsed.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ParsedJson<'a> {
+    Id { hash: &'a str, name: &'a str },
+    Name { parts: &'a [String], quoted: bool },
+}
+
+/// A resolution and how it was reached, as printed by `resolve --json`.
+#[derive(Serialize)]
+struct ResolutionJson<'a> {
+    input: &'a str,
+    parsed: ParsedJson<'a>,
+    current_repo: Option<&'a Path>,
+    current_repo_matches: Option<Vec<SiloJson>>,
+    qualified_matches: Option<Vec<SiloJson>>,
+    /// "canonical_id", "current_repo" or "qualified_name"
+    decided_by: &'static str,
+    /// "found", "not_found" or "ambiguous"
+    result: &'static str,
+    silo: Option<SiloJson>,
+}
+
+/// Resolve `name` and print the silo's path. With `explain` or `json`, also
+/// print how the name was parsed and matched. Fails unless exactly one silo
+/// matches.
+pub fn run(name: String, explain: bool, json: bool) -> Result<(), String> {
+    let name = resolve_dash(&name)?;
+    let silos = silo::collect_all_silos()?;
+    let current_repo = git::try_get_repo_root();
+    let resolution = names::explain_name(&name, &silos, current_repo.clone());
+
+    if json {
+        let json =
+            serde_json::to_string_pretty(&to_json(&name, current_repo.as_deref(), &resolution))
+                .map_err(|e| format!("Failed to serialize resolution: {}", e))?;
+        println!("{}", json);
+    } else if explain {
+        for line in explain_lines(&name, current_repo.as_deref(), &resolution) {
+            println!("{}", line);
+        }
+    }
+
+    let silo = found_or_error(&name, &silos, &resolution.result)?;
+    if !json && !explain {
+        println!("{}", silo.storage_path.display());
+    }
+    Ok(())
+}
+
+fn to_json<'a>(
+    input: &'a str,
+    current_repo: Option<&'a Path>,
+    resolution: &'a Resolution,
+) -> ResolutionJson<'a> {
+    let parsed = match &resolution.id {
+        Some((hash, name)) => ParsedJson::Id { hash, name },
+        None => ParsedJson::Name {
+            parts: &resolution.parts,
+            quoted: resolution.quoted,
+        },
+    };
+    let (result, silo) = match &resolution.result {
+        ResolveResult::Found(silo) => ("found", Some(SiloJson::new(silo))),
+        ResolveResult::NotFound => ("not_found", None),
+        ResolveResult::Ambiguous(_) => ("ambiguous", None),
+    };
+    ResolutionJson {
+        input,
+        parsed,
+        current_repo,
+        current_repo_matches: SiloJson::all(&resolution.current_repo_matches),
+        qualified_matches: SiloJson::all(&resolution.qualified_matches),
+        decided_by: match resolution.step {
+            ResolveStep::CanonicalId => "canonical_id",
+            ResolveStep::CurrentRepo => "current_repo",
+            ResolveStep::QualifiedName => "qualified_name",
+        },
+        result,
+        silo,
+    }
+}
+
+/// A step-by-step account of the resolution, one line each.
+fn explain_lines(input: &str, current_repo: Option<&Path>, resolution: &Resolution) -> Vec<String> {
+    let mut lines = vec![format!("Input:     {}", input)];
+    lines.push(match (&resolution.id, resolution.parts.split_last()) {
+        (Some((hash, name)), _) => {
+            format!(
+                "Parsed:    canonical ID (repository hash {}, silo {})",
+                hash, name
+            )
+        }
+        (None, Some((name, []))) if resolution.quoted => {
+            format!("Parsed:    quoted silo {}", name)
+        }
+        (None, Some((name, []))) => format!("Parsed:    silo {}", name),
+        (None, Some((name, repo))) if resolution.quoted => {
+            format!(
+                "Parsed:    quoted silo {} in repository {}",
+                name,
+                repo.join("/")
+            )
+        }
+        (None, Some((name, repo))) => {
+            format!("Parsed:    silo {} in repository {}", name, repo.join("/"))
+        }
+        (None, None) => "Parsed:    empty name".to_string(),
+    });
+    lines.push(match current_repo {
+        Some(repo) => format!("Repo:      {}", repo.display()),
+        None => "Repo:      none (not in a repository)".to_string(),
+    });
+
+    if let Some(matches) = &resolution.current_repo_matches {
+        lines.push(format!(
+            "In repo:   {}",
+            match_summary(matches.len(), "falling back to qualified names")
+        ));
+        lines.extend(silo_lines(matches));
+    }
+    if let Some(matches) = &resolution.qualified_matches {
+        lines.push(format!(
+            "Qualified: {}",
+            match_summary(matches.len(), "ambiguous")
+        ));
+        lines.extend(silo_lines(matches));
+    }
+
+    lines.push(match &resolution.result {
+        ResolveResult::Found(silo) => format!(
+            "Result:    {} ({}) by {}",
+            silo.storage_path.display(),
+            silo.id(),
+            match resolution.step {
+                ResolveStep::CanonicalId => "canonical ID",
+                ResolveStep::CurrentRepo => "the only match in the current repository",
+                ResolveStep::QualifiedName => "the only qualified name match",
+            }
+        ),
+        ResolveResult::NotFound => "Result:    not found".to_string(),
+        ResolveResult::Ambiguous(matches) => {
+            format!("Result:    ambiguous ({} silos match)", matches.len())
+        }
+    });
+    lines
+}
+
+fn match_summary(count: usize, when_several: &str) -> String {
+    match count {
+        0 => "no matches".to_string(),
+        1 => "1 match".to_string(),
+        n => format!("{} matches, {}", n, when_several),
+    }
+}
+
+fn silo_lines(silos: &[&Silo]) -> Vec<String> {
+    silos
+        .iter()
+        .map(|silo| format!("  {} ({})", names::qualified_name(silo), silo.id()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silo(repo: &str, name: &str) -> Silo {
+        Silo {
+            name: name.to_string(),
+            branch: Some(name.to_string()),
+            main_worktree: PathBuf::from(repo),
+            storage_path: PathBuf::from(format!("/silos/{}", name)),
+            repo_name: "repo".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_explain_falls_back_to_qualified_names() {
+        let silos = vec![
+            silo("/src/a/repo", "feature"),
+            silo("/src/b/repo", "feature"),
+        ];
+        let current = Path::new("/src/other");
+        let resolution = names::explain_name("a/repo/feature", &silos, Some(current.into()));
+
+        let lines = explain_lines("a/repo/feature", Some(current), &resolution);
+        assert_eq!(
+            lines,
+            vec![
+                "Input:     a/repo/feature".to_string(),
+                "Parsed:    silo feature in repository a/repo".to_string(),
+                "Repo:      /src/other".to_string(),
+                "In repo:   no matches".to_string(),
+                "Qualified: 1 match".to_string(),
+                format!("  src/a/repo/feature ({})", silos[0].id()),
+                format!(
+                    "Result:    /silos/feature ({}) by the only qualified name match",
+                    silos[0].id()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_reports_canonical_id_and_ambiguity() {
+        let silos = vec![
+            silo("/src/a/repo", "feature"),
+            silo("/src/b/repo", "feature"),
+        ];
+
+        let id = silos[1].id();
+        let resolution = names::explain_name(&id, &silos, None);
+        let json = serde_json::to_value(to_json(&id, None, &resolution)).unwrap();
+        assert_eq!(json["parsed"]["kind"], "id");
+        assert_eq!(json["decided_by"], "canonical_id");
+        assert_eq!(json["silo"]["path"], "/silos/feature");
+        assert!(json["qualified_matches"].is_null());
+
+        let resolution = names::explain_name("feature", &silos, None);
+        let json = serde_json::to_value(to_json("feature", None, &resolution)).unwrap();
+        assert_eq!(json["result"], "ambiguous");
+        assert_eq!(json["qualified_matches"].as_array().unwrap().len(), 2);
+        assert!(json["silo"].is_null());
+    }
+}
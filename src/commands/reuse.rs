@@ -0,0 +1,85 @@
+//! The `reuse` command: start a silo from an idle worktree in the
+//! repository's pool instead of creating one, so churning through many
+//! short-lived silos doesn't pay for a full checkout each time.
+
+use crate::config::Config;
+use crate::created::{self, Created};
+use crate::git;
+use crate::names;
+use crate::output::Verbosity;
+use crate::pool;
+use crate::shell;
+use crate::silo;
+
+use super::new::{self, NewOptions};
+
+const NO_IDLE_SILO: &str = "No idle silo in the pool; creating one.";
+
+/// Start a silo on the new branch `branch` from the pool, or create it
+/// with `new` if the pool has no idle worktree or the branch isn't new.
+pub fn run(
+    branch: String,
+    config: &Config,
+    dry_run: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let repo_info = git::get_repo_info()?;
+    let repo_root = &repo_info.main_worktree;
+
+    let namespace = config.namespace();
+    let new_branch = names::namespaced_branch(&branch, namespace.as_deref());
+    let name = names::strip_namespace(&new_branch, namespace.as_deref()).to_string();
+    let silo_path = silo::get_silo_path(&repo_info.name, repo_root, &name)?;
+
+    // Pooled worktrees start new branches at HEAD; `new` knows what to do
+    // with existing and remote branches
+    let fresh = !git::branch_exists(repo_root, &new_branch)
+        && git::find_upstream(repo_root, &branch).is_none()
+        && !silo_path.exists();
+    let create = || {
+        let options = NewOptions {
+            sandbox: false,
+            take_changes: false,
+            worktree_dir: None,
+        };
+        // Without a command to run, there's no exit code but success
+        new::run(branch.clone(), &[], &options, config, dry_run, verbosity).map(|_| ())
+    };
+    if !fresh {
+        return create();
+    }
+    if pool::idle(repo_root)?.is_none() {
+        if !verbosity.is_quiet() {
+            eprintln!("{}", NO_IDLE_SILO);
+        }
+        return create();
+    }
+
+    if dry_run {
+        println!("Would reuse a pooled silo at: {}", silo_path.display());
+        println!("Would create branch: {}", new_branch);
+        return Ok(());
+    }
+
+    if !verbosity.is_quiet() {
+        println!("Creating branch '{}' in a pooled silo...", new_branch);
+    }
+    let Some(pending) = pool::take(repo_root, &silo_path, &new_branch, verbosity)? else {
+        // Taken by someone else since
+        if !verbosity.is_quiet() {
+            eprintln!("{}", NO_IDLE_SILO);
+        }
+        return create();
+    };
+    // The silo is usable without it, so only warn if it can't be written
+    if let Err(e) = created::record(&silo_path, &Created::now()) {
+        eprintln!("Warning: {}", e);
+    }
+    drop(pending);
+
+    shell::write_directive("last", &name);
+    if !verbosity.is_quiet() {
+        println!("Reused pooled silo: {}", silo_path.display());
+    }
+    Ok(())
+}
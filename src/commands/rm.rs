@@ -1,47 +1,141 @@
 //! The `rm` command: remove a silo.
 
+use std::path::{Path, PathBuf};
+
+use crate::messages;
+use crate::metrics;
+use crate::output::Verbosity;
 use crate::prompt;
 use crate::removal;
+use crate::removal_plan::RemovalPlan;
 
 use super::{resolve_dash, resolve_silo};
 
-pub fn run(name: String, dry_run: bool, force: bool, quiet: bool) -> Result<(), String> {
-    let name = resolve_dash(&name)?;
+/// Options for `silo rm`.
+pub struct RmOptions {
+    /// Print the removal plan as JSON (with --dry-run)
+    pub json: bool,
+    /// Carry out this approved removal plan instead of removing NAME
+    pub plan_file: Option<PathBuf>,
+    /// Also remove a worktree locked with `git worktree lock` (--force twice)
+    pub unlock: bool,
+}
+
+pub fn run(
+    name: Option<String>,
+    options: &RmOptions,
+    dry_run: bool,
+    force: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    if let Some(plan_file) = &options.plan_file {
+        return execute_plan(plan_file, "rm", dry_run, verbosity);
+    }
+    if options.json && !dry_run {
+        return Err(messages::JSON_PLAN_NEEDS_DRY_RUN.to_string());
+    }
+    let name = resolve_dash(&name.ok_or("Specify the silo to remove")?)?;
     let silo = resolve_silo(&name)?;
 
-    // Try to create a RemovableSilo, or use unchecked if force
-    let removable = if force {
-        removal::RemovableSilo::from_silo_unchecked(silo)
+    // Try to create a RemovableSilo, or skip the checks if force
+    let checked = if force {
+        removal::RemovableSilo::forced(silo, options.unlock)
     } else {
-        match removal::RemovableSilo::try_from(silo) {
-            Ok(r) => r,
-            Err(e) => {
-                eprintln!("{}", e);
-                eprintln!("Use --force to remove anyway.");
-                return Err("Silo removal blocked".to_string());
+        removal::RemovableSilo::try_from(silo)
+    };
+    let removable = match checked {
+        Ok(r) => r,
+        Err(e) if options.json => {
+            let blocked = e.planned(&e.silo.name);
+            return RemovalPlan::new("rm", force, vec![], vec![blocked]).print();
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            if !force {
+                eprintln!("{}", messages::force_hint("remove"));
             }
+            return Err("Silo removal blocked".to_string());
         }
     };
 
     if dry_run {
+        if options.json {
+            let planned = removable.planned(removable.name())?;
+            return RemovalPlan::new("rm", force, vec![planned], vec![]).print();
+        }
         removable.print_dry_run();
         return Ok(());
     }
 
     // Prompt for confirmation (unless force)
-    if !force {
-        let msg = format!("Remove silo '{}'?", removable.name());
-        if !prompt::confirm(&msg) {
-            println!("Aborted.");
-            return Ok(());
-        }
+    if !force && !prompt::confirm(&messages::confirm_remove(removable.name())) {
+        println!("{}", messages::ABORTED);
+        return Ok(());
     }
 
     let display = removable.name().to_string();
-    removable.remove(force, quiet)?;
-    if !quiet {
-        println!("Removed silo: {}", display);
+    removable.remove(force, verbosity)?;
+    metrics::record_removed("rm", 1);
+    if !verbosity.is_quiet() {
+        println!("{}", messages::removed(&display));
     }
 
     Ok(())
 }
+
+/// Carry out a removal plan made by `silo COMMAND --dry-run --json` and
+/// approved outside silo. Nothing is removed unless every silo in it is
+/// still as planned. The approval stands in for the confirmation prompt.
+pub fn execute_plan(
+    plan_file: &Path,
+    command: &str,
+    dry_run: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let plan = RemovalPlan::load(plan_file, command)?;
+    if !plan.is_intact() {
+        return Err(format!(
+            "{} was edited after it was made; its token doesn't match",
+            plan_file.display()
+        ));
+    }
+    let outdated = |reason: String| {
+        format!(
+            "{} no longer applies: {}\nMake a new plan with `silo {} --dry-run --json`.",
+            plan_file.display(),
+            reason,
+            command
+        )
+    };
+
+    // Plan the same removals again, as the silos are now
+    let mut removals = Vec::new();
+    let mut current = Vec::new();
+    for planned in &plan.remove {
+        let silo = resolve_silo(&planned.id).map_err(&outdated)?;
+        let removable = if plan.force {
+            removal::RemovableSilo::forced(silo, false)
+        } else {
+            removal::RemovableSilo::try_from(silo)
+        }
+        .map_err(|e| outdated(e.to_string()))?;
+        current.push(removable.planned(&planned.name)?);
+        removals.push(removable);
+    }
+    if RemovalPlan::new(command, plan.force, current, vec![]).token != plan.token {
+        return Err(outdated("its silos changed since it was made".to_string()));
+    }
+
+    for (planned, removable) in plan.remove.iter().zip(removals) {
+        if dry_run {
+            println!("{}", messages::would_remove(&planned.name));
+            continue;
+        }
+        removable.remove(plan.force, verbosity)?;
+        metrics::record_removed(command, 1);
+        if !verbosity.is_quiet() {
+            println!("{}", messages::removed(&planned.name));
+        }
+    }
+    Ok(())
+}
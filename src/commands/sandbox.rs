@@ -1,18 +1,140 @@
 //! Sandbox commands: run agents in Docker containers.
 
-use crate::sandbox;
+use std::path::{Path, PathBuf};
+
+use crate::config::{CommitSigning, Config};
+use crate::exit;
+use crate::git;
+use crate::output::Verbosity;
+use crate::sandbox::{self, MountSpec};
 use crate::silo;
+use crate::transcript;
 
 use super::resolve_silo;
+use super::verify;
+
+/// Options for `silo sandbox claude`.
+pub struct ClaudeOptions {
+    /// Extra silos (or `@main`) to expose read-only
+    pub mounts: Vec<String>,
+    /// Run verify_command after Claude exits
+    pub verify: bool,
+    /// Arguments to pass to Claude Code
+    pub args: Vec<String>,
+}
 
 /// Run Claude Code in a Docker sandbox.
-pub fn claude(silo_name: Option<String>, dry_run: bool, args: &[String]) -> Result<(), String> {
-    // Resolve workspace path
-    let workspace = match silo_name {
-        Some(name) => {
-            let silo_info = resolve_silo(&name)?;
-            silo_info.storage_path
+/// Returns the exit code to leave with: Claude's own if it fails.
+pub fn claude(
+    silo_name: Option<String>,
+    options: &ClaudeOptions,
+    config: &Config,
+    dry_run: bool,
+    verbosity: Verbosity,
+) -> Result<i32, String> {
+    let workspace = resolve_workspace(silo_name)?;
+    let sandbox_config = claude_config(
+        &workspace,
+        &options.mounts,
+        &options.args,
+        &config.for_dir(&workspace)?,
+    )?;
+
+    if dry_run {
+        sandbox_config.print();
+        return Ok(exit::SUCCESS);
+    }
+
+    let status = sandbox_config.run(&workspace)?;
+    // Verify even if the agent failed, so its partial work gets a result
+    let verified = if options.verify {
+        verify::verify_silo(&workspace, config, verbosity)
+    } else {
+        Ok(())
+    };
+
+    if !status.success() {
+        return Ok(exit::status_code(status));
+    }
+    verified.map(|()| exit::SUCCESS)
+}
+
+/// Build the sandbox configuration for running Claude in `workspace`,
+/// with `mounts` (silo names or `@main`) exposed read-only. When silos
+/// enforce commit signing, the signing key is mounted too, so the agent's
+/// commits can be signed.
+pub fn claude_config(
+    workspace: &Path,
+    mounts: &[String],
+    args: &[String],
+    config: &Config,
+) -> Result<sandbox::DockerSandboxConfig, String> {
+    let mut sandbox_config = sandbox::DockerSandboxConfig::claude(workspace, args.to_vec());
+    for spec in mounts {
+        let spec = MountSpec::parse(spec)?;
+        let host = resolve_mount_source(&spec.source, workspace)?;
+        sandbox_config.add_readonly_mount(&host, &spec.target);
+    }
+    if config.commit_signing() == Some(CommitSigning::Enforce) {
+        sandbox_config
+            .add_signing_mounts(config.signing_format(), config.signing_key()?.as_deref());
+    }
+    Ok(sandbox_config)
+}
+
+/// List or copy files the agent wrote to its scratch directories.
+pub fn artifacts(
+    silo_name: Option<String>,
+    copy_to: Option<&Path>,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    let workspace = resolve_workspace(silo_name)?;
+    let root = sandbox::scratch_root(&workspace);
+
+    if let Some(dest) = copy_to {
+        let count = sandbox::copy_artifacts(&root, dest)?;
+        if !verbosity.is_quiet() {
+            println!("Copied {} artifact(s) to {}", count, dest.display());
+        }
+        return Ok(());
+    }
+
+    for file in sandbox::list_artifacts(&root) {
+        println!("{}", root.join(file).display());
+    }
+    Ok(())
+}
+
+/// Show the most recent agent transcript, or list all of them.
+pub fn transcript(silo_name: Option<String>, list: bool, raw: bool) -> Result<(), String> {
+    let workspace = resolve_workspace(silo_name)?;
+    let files = transcript::list(&workspace);
+
+    if list {
+        for file in &files {
+            println!("{}", file.display());
         }
+        return Ok(());
+    }
+
+    let latest = files
+        .last()
+        .ok_or_else(|| format!("No transcripts recorded for {}", workspace.display()))?;
+    let contents = std::fs::read_to_string(latest)
+        .map_err(|e| format!("Failed to read transcript {}: {}", latest.display(), e))?;
+
+    if raw {
+        print!("{}", contents);
+    } else {
+        print!("{}", transcript::render(&contents));
+    }
+    Ok(())
+}
+
+/// Resolve the silo to work on: a named silo, or the current directory.
+fn resolve_workspace(silo_name: Option<String>) -> Result<PathBuf, String> {
+    match silo_name {
+        Some(name) => Ok(resolve_silo(&name)?.storage_path),
         None => {
             // Check if current directory is a silo
             let cwd = std::env::current_dir()
@@ -21,17 +143,17 @@ pub fn claude(silo_name: Option<String>, dry_run: bool, args: &[String]) -> Resu
             if !silo::is_silo_path(&cwd) {
                 return Err("Not in a silo. Specify a silo name or navigate to one.".to_string());
             }
-            cwd
+            Ok(cwd)
         }
-    };
-
-    let config = sandbox::DockerSandboxConfig::claude(&workspace, args.to_vec());
-
-    if dry_run {
-        config.print();
-    } else {
-        config.run(&workspace)?;
     }
+}
 
-    Ok(())
+/// Resolve a mount source to a host path.
+/// `@main` refers to the main worktree of the silo being sandboxed.
+fn resolve_mount_source(source: &str, workspace: &Path) -> Result<PathBuf, String> {
+    if source == sandbox::MAIN_WORKTREE_SOURCE {
+        return git::get_main_worktree_from_silo(workspace)
+            .ok_or_else(|| format!("Could not find main worktree for {}", workspace.display()));
+    }
+    Ok(resolve_silo(source)?.storage_path)
 }
@@ -1,18 +1,33 @@
-//! Shell integration commands: init and complete-args.
+//! Shell integration commands: init, complete-args and complete-debug.
 
-use crate::complete;
+use std::time::Instant;
+
+use crate::complete::{self, Kind};
+use crate::config::Config;
+use crate::git;
 use crate::shell::{self, ShellType};
+use crate::timings;
 
-/// Output shell integration script.
-pub fn init(shell_type: ShellType) -> Result<(), String> {
+/// Output shell integration script, with the zsh picker widget if
+/// `shell_widget_key` is set.
+pub fn init(shell_type: ShellType, config: &Config) -> Result<(), String> {
     // Get the path to the silo binary
     let silo_bin = std::env::current_exe()
         .map_err(|e| format!("Failed to get silo path: {}", e))?
         .display()
         .to_string();
 
+    let picker = config.shell_picker();
     let script = match shell_type {
-        ShellType::Zsh => shell::zsh::init_script(&silo_bin),
+        ShellType::Zsh => shell::zsh::init_script(
+            &silo_bin,
+            config.shell_widget_key().map(|key| shell::zsh::Widget {
+                key,
+                picker: &picker,
+            }),
+        ),
+        ShellType::Bash => shell::bash::init_script(&silo_bin),
+        ShellType::Elvish => shell::elvish::init_script(&silo_bin),
     };
 
     print!("{}", script);
@@ -20,8 +35,41 @@ pub fn init(shell_type: ShellType) -> Result<(), String> {
 }
 
 /// Generate completions for any position (for shell completion).
-pub fn complete_args(args: &[String]) {
-    for c in complete::generate(args) {
-        println!("{}", c.format_zsh());
+pub fn complete_args(args: &[String], format: complete::Format) {
+    print!("{}", complete::format(&complete::generate(args), format));
+}
+
+/// Answer a completion request like `complete-args` and describe it instead
+/// of printing the candidates, for finding out why completion is slow. The
+/// time each phase took is printed to stderr, as with `--timings`.
+pub fn complete_debug(args: &[String]) {
+    // Detected before timing starts, so it isn't counted twice
+    let repo_root = git::try_get_repo_root();
+    timings::enable();
+
+    let start = Instant::now();
+    let completions = complete::generate(args);
+    let elapsed = start.elapsed();
+
+    let count = |kind| completions.iter().filter(|c| c.kind == kind).count();
+    println!("Request:    silo {}", args.join(" "));
+    println!(
+        "Repository: {}",
+        repo_root.map_or(
+            "none (silos come from a storage scan)".to_string(),
+            |root| { format!("{} (silos come from its worktrees)", root.display()) }
+        )
+    );
+    let mut kinds = format!(
+        "{} commands, {} silos",
+        count(Kind::Command),
+        count(Kind::Silo)
+    );
+    for (kind, plural) in [(Kind::Branch, "branches"), (Kind::Flag, "flags")] {
+        if count(kind) > 0 {
+            kinds.push_str(&format!(", {} {}", count(kind), plural));
+        }
     }
+    println!("Candidates: {} ({})", completions.len(), kinds);
+    println!("Time:       {:.1}ms", elapsed.as_secs_f64() * 1000.0);
 }
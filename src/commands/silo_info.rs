@@ -0,0 +1,244 @@
+//! The `info NAME` command: everything about one silo in one place, which
+//! otherwise takes `list`, `diff` and `rm --dry-run` together.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::activity;
+use crate::commands::list::{self, Porcelain};
+use crate::created;
+use crate::git;
+use crate::messages;
+use crate::process;
+
+use super::{resolve_dash, resolve_silo};
+
+/// One silo, as printed by `info NAME --json`.
+#[derive(Debug, Serialize)]
+struct SiloInfo {
+    name: String,
+    id: String,
+    repo: String,
+    /// None on a detached HEAD
+    branch: Option<String>,
+    path: PathBuf,
+    main_worktree: PathBuf,
+    main_branch: String,
+    /// Commits ahead of and behind the main branch, as in `base`
+    ahead: u32,
+    behind: u32,
+    /// How the branch compares to the main branch
+    base: git::Comparison,
+    /// Lines added and removed by the branch's commits
+    added: u32,
+    removed: u32,
+    /// Whether the main branch contains the branch
+    merged: bool,
+    /// How the branch compares to its upstream; None without one
+    upstream: Option<git::Comparison>,
+    uncommitted: Vec<UncommittedFile>,
+    processes: Vec<Process>,
+}
+
+#[derive(Debug, Serialize)]
+struct UncommittedFile {
+    path: String,
+    untracked: bool,
+}
+
+/// A tracked process running in the silo.
+#[derive(Debug, Serialize)]
+struct Process {
+    pid: u32,
+    command: String,
+    agent: Option<String>,
+    /// Seconds since it was started, if known
+    running_for: Option<u64>,
+}
+
+pub fn run(name: &str, json: bool, porcelain: Option<Porcelain>) -> Result<(), String> {
+    let info = collect(name)?;
+    if json {
+        let json = serde_json::to_string_pretty(&info)
+            .map_err(|e| format!("Failed to serialize silo info: {}", e))?;
+        println!("{}", json);
+    } else if let Some(version) = porcelain {
+        println!("# silo info porcelain {}", list::porcelain_name(version));
+        let records = match version {
+            Porcelain::V1 => porcelain_v1(&info),
+        };
+        for fields in records {
+            let fields: Vec<String> = fields.iter().map(|f| list::escape_field(f)).collect();
+            println!("{}", fields.join("\t"));
+        }
+    } else {
+        for line in human_lines(&info) {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn collect(name: &str) -> Result<SiloInfo, String> {
+    let s = resolve_silo(&resolve_dash(name)?)?;
+    let main_branch = git::get_main_branch(&s.main_worktree);
+    let branch = s.branch_name().to_string();
+    let base = git::compare_branches(&s.storage_path, &branch, &main_branch);
+    let (added, removed) = git::get_diff_stats(&s.storage_path, &branch, &main_branch);
+    let status = git::get_worktree_status(&s.storage_path);
+
+    Ok(SiloInfo {
+        id: s.id(),
+        repo: s.repo_name.clone(),
+        merged: s.branch.is_some()
+            && git::is_branch_merged(&s.main_worktree, &branch, &main_branch),
+        ahead: base.ahead,
+        behind: base.behind,
+        base,
+        added,
+        removed,
+        upstream: status.upstream_comparison(&s.storage_path, &branch),
+        uncommitted: status
+            .files
+            .into_iter()
+            .map(|file| UncommittedFile {
+                path: file.path,
+                untracked: file.untracked,
+            })
+            .collect(),
+        processes: process::list_active(&s.storage_path)
+            .into_iter()
+            .map(|p| Process {
+                pid: p.pid,
+                command: p.command,
+                agent: p.agent,
+                running_for: p.started_at.map(|at| activity::age(at).as_secs()),
+            })
+            .collect(),
+        main_branch,
+        name: s.name,
+        branch: s.branch,
+        path: s.storage_path,
+        main_worktree: s.main_worktree,
+    })
+}
+
+/// The silo's records in `--porcelain=v1`, each a tag and its fields: one
+/// `silo` record, an `upstream` record if it has one, then a `file` record
+/// per uncommitted file and a `process` record per tracked process. Never
+/// change these: add a version.
+fn porcelain_v1(info: &SiloInfo) -> Vec<Vec<String>> {
+    let mut records = vec![vec![
+        "silo".to_string(),
+        info.id.clone(),
+        info.name.clone(),
+        info.repo.clone(),
+        info.branch.clone().unwrap_or_default(),
+        info.path.display().to_string(),
+        info.main_worktree.display().to_string(),
+        info.main_branch.clone(),
+        info.ahead.to_string(),
+        info.behind.to_string(),
+        info.base.merge_base.clone().unwrap_or_default(),
+        info.added.to_string(),
+        info.removed.to_string(),
+        info.merged.to_string(),
+    ]];
+    if let Some(upstream) = &info.upstream {
+        records.push(vec![
+            "upstream".to_string(),
+            upstream.branch.clone(),
+            upstream.ahead.to_string(),
+            upstream.behind.to_string(),
+            upstream.merge_base.clone().unwrap_or_default(),
+        ]);
+    }
+    for file in &info.uncommitted {
+        let kind = if file.untracked {
+            "untracked"
+        } else {
+            "changed"
+        };
+        records.push(vec![
+            "file".to_string(),
+            kind.to_string(),
+            file.path.clone(),
+        ]);
+    }
+    for p in &info.processes {
+        records.push(vec![
+            "process".to_string(),
+            p.pid.to_string(),
+            p.running_for.map_or(String::new(), |secs| secs.to_string()),
+            p.agent.clone().unwrap_or_default(),
+            p.command.clone(),
+        ]);
+    }
+    records
+}
+
+fn human_lines(info: &SiloInfo) -> Vec<String> {
+    let mut lines = vec![
+        format!("Silo:          {} ({})", info.name, info.id),
+        format!("Path:          {}", info.path.display()),
+        format!("Main worktree: {}", info.main_worktree.display()),
+        format!(
+            "Branch:        {}",
+            info.branch.as_deref().unwrap_or("(detached)")
+        ),
+    ];
+    let merged = if info.merged { "merged" } else { "not merged" };
+    lines.push(format!(
+        "Commits:       {} ahead, {} behind {}, +{} -{} lines, {}",
+        info.ahead, info.behind, info.main_branch, info.added, info.removed, merged
+    ));
+    if let Some(merge_base) = &info.base.merge_base {
+        lines.push(format!(
+            "Merge base:    {}",
+            messages::short_commit(merge_base)
+        ));
+    }
+    if let Some(upstream) = &info.upstream {
+        lines.push(format!(
+            "Upstream:      {} ahead, {} behind {}",
+            upstream.ahead, upstream.behind, upstream.branch
+        ));
+    }
+
+    if info.uncommitted.is_empty() {
+        lines.push("Uncommitted:   none".to_string());
+    } else {
+        lines.push(format!(
+            "Uncommitted:   {}",
+            messages::count(info.uncommitted.len(), "file", "files")
+        ));
+        for file in &info.uncommitted {
+            let untracked = if file.untracked { " (untracked)" } else { "" };
+            lines.push(format!("  {}{}", file.path, untracked));
+        }
+    }
+
+    if info.processes.is_empty() {
+        lines.push("Processes:     none".to_string());
+    } else {
+        lines.push(format!(
+            "Processes:     {}",
+            messages::count(info.processes.len(), "process", "processes")
+        ));
+        for p in &info.processes {
+            let running_for = p.running_for.map_or("-".to_string(), |secs| {
+                created::format_age(std::time::Duration::from_secs(secs))
+            });
+            let agent = p
+                .agent
+                .as_ref()
+                .map_or(String::new(), |a| format!(" [{}]", a));
+            lines.push(format!(
+                "  {:>7}  {:>4}  {}{}",
+                p.pid, running_for, p.command, agent
+            ));
+        }
+    }
+    lines
+}
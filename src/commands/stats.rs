@@ -0,0 +1,256 @@
+//! The `stats` command: totals across all silos, or per repository, to
+//! keep an eye on worktree sprawl. `list --summary` lists repositories;
+//! this adds up what's in them.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::activity;
+use crate::created;
+use crate::git;
+use crate::messages;
+use crate::names;
+use crate::process;
+use crate::silo::{self, Silo};
+
+use super::list::map_parallel;
+
+/// Options for `silo stats`.
+pub struct StatsOptions {
+    /// Only count repositories matching this filter (repo or org/repo)
+    pub repo: Option<String>,
+    /// Also print the totals of each repository
+    pub by_repo: bool,
+    pub json: bool,
+}
+
+/// Totals over a set of silos.
+#[derive(Debug, Default, Serialize)]
+struct Stats {
+    silos: usize,
+    /// Commits on silo branches that their main branch doesn't have
+    unmerged_commits: u32,
+    /// Lines added and removed by those commits
+    added: u32,
+    removed: u32,
+    /// Silos with uncommitted changes
+    dirty: usize,
+    /// Tracked processes running in the silos
+    processes: usize,
+    /// The silo created longest ago, of those with a creation record
+    oldest: Option<Oldest>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Oldest {
+    /// repo/name
+    name: String,
+    /// Seconds since the Unix epoch
+    created_at: u64,
+}
+
+/// One repository's totals.
+#[derive(Debug, Serialize)]
+struct RepoStats {
+    repo: String,
+    path: PathBuf,
+    #[serde(flatten)]
+    stats: Stats,
+}
+
+/// The report printed by `stats --json`.
+#[derive(Debug, Serialize)]
+struct Report {
+    total: Stats,
+    repos: Vec<RepoStats>,
+}
+
+/// What one silo contributes to the totals.
+struct SiloStats {
+    name: String,
+    ahead: u32,
+    added: u32,
+    removed: u32,
+    dirty: bool,
+    processes: usize,
+    created_at: Option<u64>,
+}
+
+impl Stats {
+    fn add(&mut self, silo: &SiloStats) {
+        self.silos += 1;
+        self.unmerged_commits += silo.ahead;
+        self.added += silo.added;
+        self.removed += silo.removed;
+        self.dirty += usize::from(silo.dirty);
+        self.processes += silo.processes;
+        if let Some(at) = silo.created_at
+            && self.oldest.as_ref().is_none_or(|o| at < o.created_at)
+        {
+            self.oldest = Some(Oldest {
+                name: silo.name.clone(),
+                created_at: at,
+            });
+        }
+    }
+
+    fn merge(&mut self, other: &Stats) {
+        self.silos += other.silos;
+        self.unmerged_commits += other.unmerged_commits;
+        self.added += other.added;
+        self.removed += other.removed;
+        self.dirty += other.dirty;
+        self.processes += other.processes;
+        if let Some(oldest) = &other.oldest
+            && self
+                .oldest
+                .as_ref()
+                .is_none_or(|o| oldest.created_at < o.created_at)
+        {
+            self.oldest = Some(oldest.clone());
+        }
+    }
+}
+
+pub fn run(options: &StatsOptions) -> Result<(), String> {
+    let mut silos = silo::collect_all_silos()?;
+    if let Some(filter) = &options.repo {
+        silos.retain(|s| names::repo_matches(&s.main_worktree, filter));
+    }
+    let report = collect(&silos);
+
+    if options.json {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize stats: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+    let indent = if options.by_repo { "  " } else { "" };
+    if options.by_repo {
+        for repo in &report.repos {
+            println!("{} ({}):", repo.repo, repo.path.display());
+            println!("  Silos:            {}", repo.stats.silos);
+            for line in stats_lines(&repo.stats) {
+                println!("  {}", line);
+            }
+            println!();
+        }
+        println!("Total:");
+    }
+    println!(
+        "{}Silos:            {} in {}",
+        indent,
+        report.total.silos,
+        messages::count(report.repos.len(), "repository", "repositories")
+    );
+    for line in stats_lines(&report.total) {
+        println!("{}{}", indent, line);
+    }
+    Ok(())
+}
+
+/// Add up the silos, per repository (sorted by name) and in total.
+fn collect(silos: &[Silo]) -> Report {
+    let mut main_branches = std::collections::HashMap::new();
+    for s in silos {
+        main_branches
+            .entry(s.main_worktree.clone())
+            .or_insert_with(|| git::get_main_branch(&s.main_worktree));
+    }
+    let per_silo = map_parallel(silos, |s| silo_stats(s, &main_branches[&s.main_worktree]));
+
+    let mut repos: Vec<RepoStats> = Vec::new();
+    for (s, stats) in silos.iter().zip(&per_silo) {
+        let index = match repos.iter().position(|r| r.path == s.main_worktree) {
+            Some(index) => index,
+            None => {
+                repos.push(RepoStats {
+                    repo: s.repo_name.clone(),
+                    path: s.main_worktree.clone(),
+                    stats: Stats::default(),
+                });
+                repos.len() - 1
+            }
+        };
+        repos[index].stats.add(stats);
+    }
+    repos.sort_by(|a, b| a.repo.cmp(&b.repo).then_with(|| a.path.cmp(&b.path)));
+
+    let mut total = Stats::default();
+    for repo in &repos {
+        total.merge(&repo.stats);
+    }
+    Report { total, repos }
+}
+
+fn silo_stats(s: &Silo, main_branch: &str) -> SiloStats {
+    let branch = s.branch_name();
+    let (ahead, _) = git::get_ahead_behind(&s.storage_path, branch, main_branch);
+    let (added, removed) = git::get_diff_stats(&s.storage_path, branch, main_branch);
+    SiloStats {
+        name: format!("{}/{}", s.repo_name, s.name),
+        ahead,
+        added,
+        removed,
+        dirty: !git::get_uncommitted_stats(&s.storage_path).is_clean(),
+        processes: process::list_active(&s.storage_path).len(),
+        created_at: created::load(&s.storage_path).map(|c| c.at),
+    }
+}
+
+fn stats_lines(stats: &Stats) -> Vec<String> {
+    let oldest = stats.oldest.as_ref().map_or("-".to_string(), |o| {
+        format!(
+            "{} ({})",
+            o.name,
+            created::format_age(activity::age(o.created_at))
+        )
+    });
+    vec![
+        format!("Unmerged commits: {}", stats.unmerged_commits),
+        format!("Lines:            +{} -{}", stats.added, stats.removed),
+        format!("Dirty silos:      {}", stats.dirty),
+        format!("Processes:        {}", stats.processes),
+        format!("Oldest silo:      {}", oldest),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn silo(name: &str, ahead: u32, dirty: bool, created_at: Option<u64>) -> SiloStats {
+        SiloStats {
+            name: name.to_string(),
+            ahead,
+            added: ahead * 10,
+            removed: ahead,
+            dirty,
+            processes: 1,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_stats_add_up_and_keep_oldest() {
+        let mut a = Stats::default();
+        a.add(&silo("repo/new", 2, true, Some(200)));
+        a.add(&silo("repo/unknown", 1, false, None));
+        a.add(&silo("repo/old", 0, false, Some(100)));
+        let mut b = Stats::default();
+        b.add(&silo("other/older", 3, true, Some(50)));
+
+        let mut total = Stats::default();
+        total.merge(&a);
+        total.merge(&b);
+
+        assert_eq!(a.oldest.as_ref().unwrap().name, "repo/old");
+        assert_eq!(total.silos, 4);
+        assert_eq!(total.unmerged_commits, 6);
+        assert_eq!((total.added, total.removed), (60, 6));
+        assert_eq!(total.dirty, 2);
+        assert_eq!(total.processes, 4);
+        assert_eq!(total.oldest.unwrap().name, "other/older");
+    }
+}
@@ -0,0 +1,282 @@
+//! The `test-matrix` command: run verify_command across several silos and
+//! show the results side by side, e.g. to find which agent's branch works.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::color;
+use crate::config::Config;
+use crate::git;
+use crate::output::Verbosity;
+use crate::silo::{self, Silo};
+use crate::verify::{self, VerifyResult};
+
+use super::{resolve_dash, resolve_silo};
+
+/// Options for `silo test-matrix`.
+pub struct MatrixOptions {
+    /// Number of silos verified at once
+    pub jobs: usize,
+    /// Print the results as JSON instead of a table
+    pub json: bool,
+}
+
+/// Outcome of verifying one silo.
+struct MatrixRow {
+    name: String,
+    path: PathBuf,
+    /// Err if the verify command couldn't be run
+    outcome: Result<VerifyResult, String>,
+    added: u32,
+    removed: u32,
+    /// Where the output went when it was captured
+    log: Option<PathBuf>,
+}
+
+impl MatrixRow {
+    fn passed(&self) -> bool {
+        self.outcome.as_ref().is_ok_and(|result| result.passed)
+    }
+}
+
+/// A silo's result, as printed by `test-matrix --json`.
+#[derive(Serialize)]
+struct RowJson<'a> {
+    silo: &'a str,
+    path: &'a Path,
+    passed: bool,
+    /// None if the command was killed by a signal or couldn't be run
+    exit_code: Option<i32>,
+    duration_ms: u128,
+    added: u32,
+    removed: u32,
+    error: Option<&'a str>,
+    log: Option<&'a Path>,
+}
+
+/// Verify the named silos, or every silo of the current repository, and
+/// print a table of the results. Fails if any silo fails verification.
+pub fn run(
+    names: &[String],
+    options: &MatrixOptions,
+    config: &Config,
+    use_color: bool,
+    verbosity: Verbosity,
+) -> Result<(), String> {
+    if options.jobs == 0 {
+        return Err("--jobs must be at least 1".to_string());
+    }
+    let silos = if names.is_empty() {
+        silo::collect_silos_for_repo(&git::get_repo_root()?)?
+    } else {
+        names
+            .iter()
+            .map(|name| resolve_silo(&resolve_dash(name)?))
+            .collect::<Result<Vec<_>, String>>()?
+    };
+    if silos.is_empty() {
+        println!("No silos found.");
+        return Ok(());
+    }
+
+    // Check every silo can be verified before running anything
+    let commands = silos
+        .iter()
+        .map(|s| {
+            config
+                .for_dir(&s.storage_path)?
+                .verify_command()
+                .map(<[String]>::to_vec)
+                .ok_or_else(|| {
+                    format!(
+                        "No verify_command configured for '{}'. Set verify_command in .silo.toml, e.g. [\"cargo\", \"test\"].",
+                        s.name
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // Output of runs side by side would interleave, and JSON needs stdout
+    let capture = options.jobs > 1 || options.json;
+    let rows = verify_all(&silos, &commands, options.jobs, capture, verbosity);
+
+    if options.json {
+        print_json(&rows)?;
+    } else {
+        print_table(&rows, use_color);
+    }
+
+    let failed = rows.iter().filter(|row| !row.passed()).count();
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} silos failed verification",
+            failed,
+            rows.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Verify each silo with its command, `jobs` at a time, keeping the order.
+fn verify_all(
+    silos: &[Silo],
+    commands: &[Vec<String>],
+    jobs: usize,
+    capture: bool,
+    verbosity: Verbosity,
+) -> Vec<MatrixRow> {
+    let next = AtomicUsize::new(0);
+    let mut rows: Vec<(usize, MatrixRow)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(silos.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(s) = silos.get(i) else {
+                            return done;
+                        };
+                        if !capture && !verbosity.is_quiet() {
+                            eprintln!("Verifying '{}'...", s.name);
+                        }
+                        let row = verify_one(s, &commands[i], capture);
+                        if !verbosity.is_quiet() {
+                            report_progress(&row);
+                        }
+                        done.push((i, row));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    rows.sort_by_key(|(i, _)| *i);
+    rows.into_iter().map(|(_, row)| row).collect()
+}
+
+/// Verify one silo and record the result, as `silo verify` does.
+fn verify_one(s: &Silo, command: &[String], capture: bool) -> MatrixRow {
+    let path = &s.storage_path;
+    // External worktrees have no tracking directory to record in
+    let tracked = silo::is_silo_path(path);
+    let log = (capture && tracked).then(|| verify::log_path(path));
+
+    let outcome = if capture {
+        verify::run_captured(command, path, log.as_deref())
+    } else {
+        verify::run(command, path)
+    }
+    .and_then(|result| {
+        if tracked {
+            verify::record(path, &result)?;
+        }
+        Ok(result)
+    });
+
+    let main_branch = git::get_main_branch(&s.main_worktree);
+    let (added, removed) = git::get_diff_stats(path, s.branch_name(), &main_branch);
+    MatrixRow {
+        name: s.name.clone(),
+        path: path.clone(),
+        outcome,
+        added,
+        removed,
+        log,
+    }
+}
+
+/// Print a line to stderr as a silo finishes.
+fn report_progress(row: &MatrixRow) {
+    match &row.outcome {
+        Ok(result) => eprintln!(
+            "{} {} ({})",
+            if result.passed { "✓" } else { "✗" },
+            row.name,
+            format_duration(result.duration)
+        ),
+        Err(e) => eprintln!("✗ {}: {}", row.name, e),
+    }
+}
+
+/// Print the results as an aligned table.
+fn print_table(rows: &[MatrixRow], use_color: bool) {
+    let name_width = rows.iter().map(|r| r.name.len()).max().unwrap_or(4).max(4); // minimum width for "NAME"
+
+    println!(
+        "{:<nw$}  {:<5}  {:>8}  {:>14}  NOTE",
+        "NAME",
+        "TESTS",
+        "TIME",
+        "LINES",
+        nw = name_width
+    );
+    for row in rows {
+        let lines_visible = format!("+{} -{}", row.added, row.removed);
+        let lines_padding = 14_usize.saturating_sub(lines_visible.len());
+        let (time, note) = match &row.outcome {
+            Ok(result) if result.passed => (format_duration(result.duration), String::new()),
+            Ok(result) => (
+                format_duration(result.duration),
+                match (&row.log, result.exit_code) {
+                    (Some(log), _) => format!("log: {}", log.display()),
+                    (None, Some(code)) => format!("exit code {}", code),
+                    (None, None) => "killed by a signal".to_string(),
+                },
+            ),
+            Err(e) => ("-".to_string(), e.clone()),
+        };
+
+        let line = format!(
+            "{:<nw$}  {}    {:>8}  {:>lp$}{} {}  {}",
+            row.name,
+            color::check_mark(row.passed(), use_color),
+            time,
+            "",
+            color::green_positive(row.added, use_color),
+            color::red_negative(row.removed, use_color),
+            note,
+            nw = name_width,
+            lp = lines_padding,
+        );
+        println!("{}", line.trim_end());
+    }
+}
+
+/// Print the results as JSON.
+fn print_json(rows: &[MatrixRow]) -> Result<(), String> {
+    let entries: Vec<RowJson> = rows
+        .iter()
+        .map(|row| {
+            let result = row.outcome.as_ref().ok();
+            RowJson {
+                silo: &row.name,
+                path: &row.path,
+                passed: row.passed(),
+                exit_code: result.and_then(|r| r.exit_code),
+                duration_ms: result.map_or(0, |r| r.duration.as_millis()),
+                added: row.added,
+                removed: row.removed,
+                error: row.outcome.as_ref().err().map(String::as_str),
+                log: row.log.as_deref(),
+            }
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&entries)
+        .map_err(|e| format!("Failed to serialize results: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1}s", duration.as_secs_f64())
+}
@@ -0,0 +1,44 @@
+//! The `verify` command: run the configured verify_command in a silo.
+
+use std::path::Path;
+
+use crate::config::Config;
+use crate::output::Verbosity;
+use crate::silo;
+use crate::verify;
+
+use super::{resolve_dash, resolve_silo};
+
+pub fn run(name: String, config: &Config, verbosity: Verbosity) -> Result<(), String> {
+    let name = resolve_dash(&name)?;
+    let silo = resolve_silo(&name)?;
+    verify_silo(&silo.storage_path, config, verbosity)
+}
+
+/// Run verify_command in a silo and record the result, using the config
+/// that applies in the silo. Returns an error if verification fails.
+pub fn verify_silo(silo_path: &Path, config: &Config, verbosity: Verbosity) -> Result<(), String> {
+    let config = config.for_dir(silo_path)?;
+    let command = config.verify_command().ok_or(
+        "No verify_command configured. Set verify_command in .silo.toml, e.g. [\"cargo\", \"test\"].",
+    )?;
+
+    let result = verify::run(command, silo_path)?;
+    // External worktrees have no tracking directory to record in
+    if silo::is_silo_path(silo_path) {
+        verify::record(silo_path, &result)?;
+    }
+
+    let secs = result.duration.as_secs_f64();
+    if !result.passed {
+        return Err(format!(
+            "Verification failed after {:.1}s (exit code {})",
+            secs,
+            result.exit_code.unwrap_or(1)
+        ));
+    }
+    if !verbosity.is_quiet() {
+        println!("✓ Verification passed in {:.1}s", secs);
+    }
+    Ok(())
+}
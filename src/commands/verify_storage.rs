@@ -0,0 +1,517 @@
+//! The `verify-storage` command: cross-check the storage directory against
+//! git's worktree records, tracking data and the repository registry.
+//!
+//! `gc` removes what is clearly garbage. This looks for places where the
+//! records silo and git keep about a silo disagree, e.g. after directories
+//! were moved or renamed by hand, and with `--fix` reconciles them where
+//! that can't lose work: git's records are repaired or pruned, but
+//! directories are only removed when they're empty or hold tracking data.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+use crate::messages;
+use crate::output::Verbosity;
+use crate::pending;
+use crate::process;
+use crate::registry;
+use crate::silo;
+use crate::storage::{self, StorageLock};
+
+/// An inconsistency between the storage directory and what git or silo
+/// recorded about it.
+#[derive(Debug, PartialEq, Eq)]
+enum Problem {
+    /// A worktree directory the repository's worktree records don't list
+    Unregistered {
+        path: PathBuf,
+        main_worktree: PathBuf,
+    },
+    /// The repository records the worktree under a path differing in case
+    CaseMismatch {
+        path: PathBuf,
+        recorded: PathBuf,
+        main_worktree: PathBuf,
+    },
+    /// The repository lists a worktree in the storage that doesn't exist
+    Missing {
+        path: PathBuf,
+        main_worktree: PathBuf,
+    },
+    /// A worktree whose repository no longer exists
+    Orphaned {
+        path: PathBuf,
+        main_worktree: PathBuf,
+    },
+    /// A worktree whose `.git` file doesn't point at a repository
+    BrokenGitFile { path: PathBuf },
+    /// A directory in a repo directory that holds no worktree
+    Stray { path: PathBuf },
+    /// Tracking data of a silo that doesn't exist
+    StaleTracking { path: PathBuf },
+    /// A registry entry for a repository that's gone, with no silos left
+    /// that `gc` could relink
+    StaleRegistryEntry { path: PathBuf },
+    /// A repository with silos that the registry doesn't know
+    NotRegistered { main_worktree: PathBuf },
+}
+
+impl Problem {
+    /// The path the problem is about.
+    fn path(&self) -> &Path {
+        match self {
+            Problem::Unregistered { path, .. }
+            | Problem::CaseMismatch { path, .. }
+            | Problem::Missing { path, .. }
+            | Problem::Orphaned { path, .. }
+            | Problem::BrokenGitFile { path }
+            | Problem::Stray { path }
+            | Problem::StaleTracking { path }
+            | Problem::StaleRegistryEntry { path } => path,
+            Problem::NotRegistered { main_worktree } => main_worktree,
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            Problem::Unregistered { main_worktree, .. } => {
+                format!("worktree that {} doesn't list", main_worktree.display())
+            }
+            Problem::CaseMismatch { recorded, .. } => {
+                format!("git records it as {}", recorded.display())
+            }
+            Problem::Missing { main_worktree, .. } => {
+                format!("listed by {} but doesn't exist", main_worktree.display())
+            }
+            Problem::Orphaned { main_worktree, .. } => {
+                format!("repository missing: {}", main_worktree.display())
+            }
+            Problem::BrokenGitFile { .. } => ".git file doesn't point at a repository".to_string(),
+            Problem::Stray { .. } => "directory without a worktree".to_string(),
+            Problem::StaleTracking { .. } => {
+                "tracking data of a silo that doesn't exist".to_string()
+            }
+            Problem::StaleRegistryEntry { .. } => {
+                "registered repository that no longer exists".to_string()
+            }
+            Problem::NotRegistered { .. } => {
+                "repository with silos missing from the registry".to_string()
+            }
+        }
+    }
+
+    /// Whether `--fix` can do something about the problem.
+    fn is_fixable(&self) -> bool {
+        match self {
+            Problem::Orphaned { .. } | Problem::BrokenGitFile { .. } => false,
+            Problem::Stray { path } => is_empty_dir(path),
+            _ => true,
+        }
+    }
+
+    /// Reconcile the records, returning what was done.
+    fn fix(&self, base_dir: &Path) -> Result<String, String> {
+        match self {
+            Problem::Unregistered {
+                path,
+                main_worktree,
+            } => {
+                repair(main_worktree, path)?;
+                Ok(format!("Repaired git's record of {}", path.display()))
+            }
+            Problem::CaseMismatch {
+                path,
+                recorded,
+                main_worktree,
+            } => {
+                repair(main_worktree, path)?;
+                // The tracking data went by the old name too
+                let (old, new) = (process::tracking_dir(recorded), process::tracking_dir(path));
+                if old.exists() && !new.exists() {
+                    fs::rename(&old, &new)
+                        .map_err(|e| format!("Failed to move tracking data: {}", e))?;
+                }
+                Ok(format!("Repaired git's record of {}", path.display()))
+            }
+            Problem::Missing {
+                path,
+                main_worktree,
+            } => {
+                git::prune_worktrees(main_worktree, Verbosity::Quiet)?;
+                if listed_paths(main_worktree).contains(path) {
+                    return Err("it is locked (`git worktree unlock` it first)".to_string());
+                }
+                Ok(format!("Pruned git's record of {}", path.display()))
+            }
+            Problem::Orphaned { .. } => {
+                Err("`silo gc` relinks or removes silos of missing repositories".to_string())
+            }
+            Problem::BrokenGitFile { .. } => {
+                Err("remove it by hand if it holds nothing you need".to_string())
+            }
+            Problem::Stray { path } => {
+                fs::remove_dir(path).map_err(|_| {
+                    "it isn't empty; remove it by hand if it holds nothing you need".to_string()
+                })?;
+                Ok(format!("Removed empty directory {}", path.display()))
+            }
+            Problem::StaleTracking { path } => {
+                fs::remove_dir_all(path)
+                    .map_err(|e| format!("Failed to remove tracking data: {}", e))?;
+                Ok(format!("Removed tracking data {}", path.display()))
+            }
+            Problem::StaleRegistryEntry { path } => {
+                registry::unregister(base_dir, path)?;
+                Ok(format!("Unregistered {}", path.display()))
+            }
+            Problem::NotRegistered { main_worktree } => {
+                registry::register_repo(base_dir, main_worktree)?;
+                Ok(format!("Registered {}", main_worktree.display()))
+            }
+        }
+    }
+}
+
+pub fn run(fix: bool, dry_run: bool, verbosity: Verbosity) -> Result<(), String> {
+    let base_dir = silo::get_silo_base_dir()?;
+    let problems = collect(&base_dir)?;
+
+    if problems.is_empty() {
+        if !verbosity.is_quiet() {
+            println!("No problems found in the storage directory.");
+        }
+        return Ok(());
+    }
+
+    if !verbosity.is_quiet() || dry_run {
+        println!("Found {}:", problems_count(problems.len()));
+        for problem in &problems {
+            println!("  {}", problem.path().display());
+            println!("    ({})", problem.description());
+        }
+    }
+
+    if !fix {
+        return Err(format!(
+            "verify-storage found {} (run with --fix to reconcile)",
+            problems_count(problems.len())
+        ));
+    }
+
+    if dry_run {
+        let fixable = problems.iter().filter(|p| p.is_fixable()).count();
+        println!("\nWould fix {}.", problems_count(fixable));
+        return Ok(());
+    }
+
+    let mut remaining = 0;
+    for problem in &problems {
+        match fix_locked(problem, &base_dir) {
+            Ok(done) => {
+                if !verbosity.is_quiet() {
+                    println!("{}", done);
+                }
+            }
+            Err(e) => {
+                eprintln!("Warning: Not fixed: {}: {}", problem.path().display(), e);
+                remaining += 1;
+            }
+        }
+    }
+
+    if remaining > 0 {
+        return Err(format!("{} left", problems_count(remaining)));
+    }
+    Ok(())
+}
+
+/// Point the repository's record of a worktree at `path`.
+fn repair(main_worktree: &Path, path: &Path) -> Result<(), String> {
+    git::repair_worktree(main_worktree, path, Verbosity::Quiet)?;
+    if !listed_paths(main_worktree).contains(&path.to_path_buf()) {
+        return Err(
+            "git has no record of it left to repair; move any work out of it and remove it"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Fix a problem under the storage lock `new` creates silos under,
+/// leaving alone anything a `silo new` started on since it was found.
+fn fix_locked(problem: &Problem, base_dir: &Path) -> Result<String, String> {
+    let path = problem.path();
+    let lock_dir = silo::get_containing_base_dir(path).unwrap_or_else(|| base_dir.to_path_buf());
+    let _lock = StorageLock::acquire(&lock_dir)?;
+    if pending::is_pending(path)
+        || pending::any_pending(path)
+        || pending::is_pending_tracking_dir(path)
+    {
+        return Err("a `silo new` is creating it now".to_string());
+    }
+    problem.fix(base_dir)
+}
+
+/// Collect every problem in the storage directories.
+fn collect(base_dir: &Path) -> Result<Vec<Problem>, String> {
+    let mut worktrees = Vec::new();
+    let mut strays = Vec::new();
+    for repo_dir in silo::repo_dirs()? {
+        if repo_dir.file_name() == Some(silo::BUNDLE_DIR.as_ref()) {
+            continue;
+        }
+        scan(
+            &repo_dir,
+            silo::MAX_NESTED_SILO_DEPTH,
+            &mut worktrees,
+            &mut strays,
+        );
+    }
+
+    // Like tracking data, only the current user's directories are ours to
+    // fix in a shared storage directory
+    let mut problems: Vec<Problem> = strays
+        .into_iter()
+        .filter(|path| !pending::any_pending(path) && storage::is_owned_by_current_user(path))
+        .map(|path| Problem::Stray { path })
+        .collect();
+
+    // Each repository's worktree records, and the ones matched by a directory
+    let mut listed: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    let mut matched: HashSet<PathBuf> = HashSet::new();
+    let mut with_silos: BTreeSet<PathBuf> = BTreeSet::new();
+    for path in worktrees {
+        if pending::is_pending(&path) {
+            continue;
+        }
+        let Some(main_worktree) = git::get_main_worktree_from_silo(&path) else {
+            problems.push(Problem::BrokenGitFile { path });
+            continue;
+        };
+        if !main_worktree.exists() {
+            problems.push(Problem::Orphaned {
+                path,
+                main_worktree,
+            });
+            continue;
+        }
+        with_silos.insert(main_worktree.clone());
+        let recorded = listed
+            .entry(main_worktree.clone())
+            .or_insert_with(|| listed_paths(&main_worktree));
+        if recorded.contains(&path) {
+            matched.insert(path);
+        } else if let Some(recorded) = recorded.iter().find(|r| differs_in_case(r, &path)) {
+            matched.insert(recorded.clone());
+            problems.push(Problem::CaseMismatch {
+                path,
+                recorded: recorded.clone(),
+                main_worktree,
+            });
+        } else if let Some(recorded) = recorded.iter().find(|r| same_file(r, &path)) {
+            // The same directory under another name, e.g. through a symlink
+            matched.insert(recorded.clone());
+        } else {
+            problems.push(Problem::Unregistered {
+                path,
+                main_worktree,
+            });
+        }
+    }
+
+    // Repositories whose silos are all gone may still list them
+    let known_repos = registry::load(base_dir);
+    let mut repos: BTreeSet<PathBuf> = with_silos.clone();
+    repos.extend(known_repos.iter().map(|r| r.path.clone()));
+    if let Some(repo_root) = git::try_get_repo_root()
+        && !silo::is_silo_path(&repo_root)
+    {
+        repos.insert(repo_root);
+    }
+    // Pruning records changes the repository, so only the current user's
+    let owned_repos = repos
+        .iter()
+        .filter(|p| p.exists() && storage::is_owned_by_current_user(p));
+    for main_worktree in owned_repos {
+        let recorded = listed
+            .entry(main_worktree.clone())
+            .or_insert_with(|| listed_paths(main_worktree));
+        for path in recorded.iter() {
+            if matched.contains(path)
+                || !silo::is_silo_path(path)
+                || path.exists()
+                || pending::is_pending(path)
+            {
+                continue;
+            }
+            problems.push(Problem::Missing {
+                path: path.clone(),
+                main_worktree: main_worktree.clone(),
+            });
+        }
+    }
+
+    // Tracking data under a case-renamed silo's old name moves with it
+    let renamed: HashSet<PathBuf> = problems
+        .iter()
+        .filter_map(|p| match p {
+            Problem::CaseMismatch { recorded, .. } => Some(process::tracking_dir(recorded)),
+            _ => None,
+        })
+        .collect();
+    problems.extend(
+        silo::collect_stale_tracking_dirs()?
+            .into_iter()
+            .filter(|p| storage::is_owned_by_current_user(p) && !renamed.contains(p))
+            .map(|path| Problem::StaleTracking { path }),
+    );
+
+    // gc needs the entries of missing repositories that still have silos
+    let orphaned_repos: HashSet<PathBuf> = problems
+        .iter()
+        .filter_map(|p| match p {
+            Problem::Orphaned { main_worktree, .. } => Some(main_worktree.clone()),
+            _ => None,
+        })
+        .collect();
+    problems.extend(
+        known_repos
+            .iter()
+            .filter(|r| !r.path.exists() && !orphaned_repos.contains(&r.path))
+            .map(|r| Problem::StaleRegistryEntry {
+                path: r.path.clone(),
+            }),
+    );
+    problems.extend(
+        with_silos
+            .into_iter()
+            .filter(|main| {
+                !known_repos.iter().any(|r| &r.path == main) && git::get_origin_url(main).is_some()
+            })
+            .map(|main_worktree| Problem::NotRegistered { main_worktree }),
+    );
+
+    Ok(problems)
+}
+
+/// Find the worktrees in a directory of the storage directory. Branch names
+/// with slashes nest worktrees in directories of their own, so directories
+/// without a `.git` are searched too, up to `depth` levels down and only
+/// if they hold nothing but directories, as `silo list` does; those with no
+/// worktree below them are strays. Hidden entries (tracking data) are skipped.
+fn scan(dir: &Path, depth: usize, worktrees: &mut Vec<PathBuf>, strays: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') || !path.is_dir() {
+            continue;
+        }
+        if path.join(".git").exists() {
+            worktrees.push(path);
+            continue;
+        }
+        if depth == 0 || !silo::holds_only_dirs(&path) {
+            strays.push(path);
+            continue;
+        }
+        let mut nested = Vec::new();
+        let mut nested_strays = Vec::new();
+        scan(&path, depth - 1, &mut nested, &mut nested_strays);
+        if nested.is_empty() {
+            strays.push(path);
+        } else {
+            worktrees.extend(nested);
+            strays.extend(nested_strays);
+        }
+    }
+}
+
+/// The paths of a repository's linked worktrees, as git records them.
+fn listed_paths(main_worktree: &Path) -> Vec<PathBuf> {
+    git::list_worktrees(main_worktree)
+        .map(|worktrees| worktrees.into_iter().skip(1).map(|wt| wt.path).collect())
+        .unwrap_or_default()
+}
+
+/// Whether two paths are spelled the same except for case.
+fn differs_in_case(a: &Path, b: &Path) -> bool {
+    a != b && a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+}
+
+/// Whether two existing paths are the same directory.
+fn same_file(a: &Path, b: &Path) -> bool {
+    matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+}
+
+/// "1 problem" / "2 problems"
+fn problems_count(n: usize) -> String {
+    messages::count(n, "problem", "problems")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_finds_nested_worktrees_and_strays() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        for worktree in ["feature", "user/fix"] {
+            fs::create_dir_all(repo.join(worktree)).unwrap();
+            fs::write(repo.join(worktree).join(".git"), "gitdir: /x\n").unwrap();
+        }
+        fs::create_dir_all(repo.join("user/leftover")).unwrap();
+        fs::create_dir_all(repo.join("empty/deeper")).unwrap();
+        fs::create_dir_all(repo.join(".tracking/feature")).unwrap();
+        // A build directory isn't walked, even with a worktree inside
+        fs::create_dir_all(repo.join("build/vendored")).unwrap();
+        fs::write(repo.join("build/vendored/.git"), "gitdir: /x\n").unwrap();
+        fs::write(repo.join("build/out.o"), "").unwrap();
+
+        let mut worktrees = Vec::new();
+        let mut strays = Vec::new();
+        scan(repo, 8, &mut worktrees, &mut strays);
+        worktrees.sort();
+        strays.sort();
+
+        assert_eq!(worktrees, vec![repo.join("feature"), repo.join("user/fix")]);
+        assert_eq!(
+            strays,
+            vec![
+                repo.join("build"),
+                repo.join("empty"),
+                repo.join("user/leftover")
+            ]
+        );
+
+        let mut worktrees = Vec::new();
+        let mut strays = Vec::new();
+        scan(repo, 0, &mut worktrees, &mut strays);
+        assert_eq!(worktrees, vec![repo.join("feature")]);
+        assert!(strays.contains(&repo.join("user")));
+    }
+
+    #[test]
+    fn test_differs_in_case() {
+        assert!(differs_in_case(
+            Path::new("/s/repo/Feature"),
+            Path::new("/s/repo/feature")
+        ));
+        assert!(!differs_in_case(
+            Path::new("/s/repo/feature"),
+            Path::new("/s/repo/feature")
+        ));
+        assert!(!differs_in_case(
+            Path::new("/s/repo/feature"),
+            Path::new("/s/repo/other")
+        ));
+    }
+}
@@ -3,54 +3,171 @@
 //! Generates completion candidates based on current command-line position.
 //! Returns data that can be formatted for any shell.
 
-use crate::{Cli, git, names, silo};
+use crate::{Cli, git, messages, names, process, silo, timings};
 use clap::CommandFactory;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Output format for `shell complete-args`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// `GROUP<tab>VALUE[:DESCRIPTION]` lines for the zsh `_silo` function,
+    /// where GROUP is the kind, or `silo/REPO` for silos listed by repository
+    #[default]
+    Zsh,
+    /// `VALUE[<tab>DESCRIPTION]` lines, as fish's `complete -a` expects
+    Fish,
+    /// Bare values, one per line, for `COMPREPLY`
+    Bash,
+    /// A JSON array of `{kind, value, description}` objects
+    Json,
+}
+
+/// What a completion candidate is, so shells can list groups separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Command,
+    Silo,
+    Flag,
+    /// A remote branch `new` can start a silo from
+    Branch,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Command => "command",
+            Kind::Silo => "silo",
+            Kind::Flag => "flag",
+            Kind::Branch => "branch",
+        }
+    }
+}
 
 /// A completion candidate.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Completion {
+    pub kind: Kind,
     pub value: String,
     pub description: Option<String>,
+    /// Repository the candidate belongs to, set for silos listed from
+    /// outside a repository so shells can list each repository separately
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
 }
 
 impl Completion {
-    fn new(value: impl Into<String>) -> Self {
+    fn command(value: impl Into<String>, desc: impl Into<String>) -> Self {
         Self {
+            kind: Kind::Command,
             value: value.into(),
-            description: None,
+            description: Some(desc.into()),
+            group: None,
         }
     }
 
-    fn with_desc(value: impl Into<String>, desc: impl Into<String>) -> Self {
+    fn flag(value: impl Into<String>, desc: Option<String>) -> Self {
         Self {
+            kind: Kind::Flag,
             value: value.into(),
-            description: Some(desc.into()),
+            description: desc,
+            group: None,
+        }
+    }
+
+    fn silo(value: impl Into<String>) -> Self {
+        Self {
+            kind: Kind::Silo,
+            value: value.into(),
+            description: None,
+            group: None,
         }
     }
 
-    /// Format for zsh completion (value:description with escaped colons).
+    /// Format for the zsh `_silo` function: the group, a tab, then
+    /// `value:description` for `_describe`. Colons in the value and
+    /// description are escaped so names like `repo:branch` aren't split.
     pub fn format_zsh(&self) -> String {
+        let group = match &self.group {
+            Some(group) => format!("{}/{}", self.kind.as_str(), group),
+            None => self.kind.as_str().to_string(),
+        };
+        let value = self.value.replace(':', "\\:");
+        match &self.description {
+            Some(desc) => format!("{}\t{}:{}", group, value, desc.replace(':', "\\:")),
+            None => format!("{}\t{}", group, value),
+        }
+    }
+
+    /// Format for fish (value, then a tab and the description).
+    pub fn format_fish(&self) -> String {
         match &self.description {
-            Some(desc) => format!("{}:{}", self.value, desc.replace(':', "\\:")),
+            Some(desc) => format!("{}\t{}", self.value, desc),
             None => self.value.clone(),
         }
     }
 }
 
+/// Format completions for a shell: one candidate per line, or a JSON array.
+pub fn format(completions: &[Completion], format: Format) -> String {
+    let lines: Vec<String> = match format {
+        Format::Zsh => completions.iter().map(Completion::format_zsh).collect(),
+        Format::Fish => completions.iter().map(Completion::format_fish).collect(),
+        Format::Bash => completions.iter().map(|c| c.value.clone()).collect(),
+        Format::Json => {
+            return serde_json::to_string(completions).unwrap_or_else(|_| "[]".to_string()) + "\n";
+        }
+    };
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
 /// Generate completions for the given arguments (words after 'silo').
 pub fn generate(args: &[String]) -> Vec<Completion> {
     let cli = Cli::command();
-    complete_command(&cli, args)
+    complete_command(&cli, args, args)
+}
+
+/// Whether `path` names a command or subcommand, e.g. `["sandbox", "claude"]`.
+pub fn is_command(path: &[String]) -> bool {
+    let mut cmd = Cli::command();
+    for name in path {
+        let Some(sub) = cmd
+            .get_subcommands()
+            .find(|c| c.get_name() == name || c.get_visible_aliases().any(|a| a == name))
+            .cloned()
+        else {
+            return false;
+        };
+        cmd = sub;
+    }
+    true
+}
+
+/// Candidates for the word after the command `path` that start with `word`,
+/// as the shell would offer them.
+pub fn candidates(path: &[String], word: &str) -> Vec<Completion> {
+    let mut args = path.to_vec();
+    args.push(word.to_string());
+    generate(&args)
+        .into_iter()
+        .filter(|c| c.value.starts_with(word))
+        .collect()
 }
 
 /// Walk the command tree recursively to find completions for current position.
-fn complete_command(cmd: &clap::Command, args: &[String]) -> Vec<Completion> {
+/// `all` is every word after 'silo', `args` the words after `cmd`.
+fn complete_command(cmd: &clap::Command, all: &[String], args: &[String]) -> Vec<Completion> {
     // No args: complete subcommands at current level
     if args.is_empty() {
         return subcommands(cmd);
     }
 
     let first = &args[0];
+    if args.len() == 1 && first.starts_with('-') {
+        return flags(&all[..all.len() - 1]);
+    }
 
     // Find matching subcommand
     let subcmd = cmd
@@ -60,11 +177,17 @@ fn complete_command(cmd: &clap::Command, args: &[String]) -> Vec<Completion> {
     match subcmd {
         Some(sub) if sub.has_subcommands() => {
             // Has nested subcommands: recurse
-            complete_command(sub, &args[1..])
+            complete_command(sub, all, &args[1..])
         }
-        Some(_) => {
-            // Leaf command: check for special argument completion
-            complete_leaf(first, &args[1..])
+        Some(sub) => {
+            // Leaf command: its flags, or special argument completion
+            let remaining = &args[1..];
+            match remaining.split_last() {
+                Some((word, before)) if word.starts_with('-') && takes_flags(sub, before) => {
+                    flags(&all[..all.len() - remaining.len()])
+                }
+                _ => complete_leaf(first, remaining),
+            }
         }
         None => {
             // Unknown/partial: offer subcommands for filtering
@@ -83,26 +206,81 @@ fn subcommands(cmd: &clap::Command) -> Vec<Completion> {
         }
 
         let desc = sub.get_about().map(|s| s.to_string()).unwrap_or_default();
-        out.push(Completion::with_desc(sub.get_name(), &desc));
+        out.push(Completion::command(sub.get_name(), &desc));
 
         for alias in sub.get_visible_aliases() {
-            out.push(Completion::with_desc(alias, format!("{} (alias)", desc)));
+            out.push(Completion::command(alias, format!("{} (alias)", desc)));
         }
     }
 
     out
 }
 
+/// Flag completions for the command `path` (e.g. `["list"]`), including
+/// global flags and `--help`.
+fn flags(path: &[String]) -> Vec<Completion> {
+    // Building propagates global flags to subcommands and adds --help
+    let mut cmd = Cli::command();
+    cmd.build();
+    for name in path {
+        let Some(sub) = cmd
+            .get_subcommands()
+            .find(|c| c.get_name() == name || c.get_visible_aliases().any(|a| a == name))
+            .cloned()
+        else {
+            return vec![];
+        };
+        cmd = sub;
+    }
+
+    let mut out = Vec::new();
+    for arg in cmd.get_arguments() {
+        if arg.is_hide_set() || arg.is_positional() {
+            continue;
+        }
+        let desc = arg.get_help().map(|help| help.to_string());
+        if let Some(long) = arg.get_long() {
+            out.push(Completion::flag(format!("--{}", long), desc.clone()));
+        }
+        if let Some(short) = arg.get_short() {
+            out.push(Completion::flag(format!("-{}", short), desc));
+        }
+    }
+    out
+}
+
+/// Whether a word after a leaf command can still be one of its flags, given
+/// the words before it: not after `--`, or once a trailing command (as for
+/// `exec NAME COMMAND...`) has started.
+fn takes_flags(cmd: &clap::Command, before: &[String]) -> bool {
+    if before.iter().any(|word| word == "--") {
+        return false;
+    }
+    let given = positional_count(before);
+    !cmd.get_positionals()
+        .enumerate()
+        .any(|(i, arg)| arg.is_trailing_var_arg_set() && given > i)
+}
+
+/// How many of `words` are positional arguments rather than flags.
+fn positional_count(words: &[String]) -> usize {
+    words.iter().filter(|word| !word.starts_with('-')).count()
+}
+
 /// Complete arguments for leaf commands (no subcommands).
 fn complete_leaf(cmd_name: &str, remaining: &[String]) -> Vec<Completion> {
-    // Check if we're completing the first positional arg.
-    // remaining is empty when there's no partial word yet,
-    // or has one element (possibly empty) when user is typing.
-    let completing_first_arg = remaining.is_empty() || remaining.len() == 1;
+    // Check if we're completing the first positional arg. The last word is
+    // the one being completed (possibly empty); flags before it don't count.
+    let completing_first_arg = remaining
+        .split_last()
+        .is_none_or(|(_, before)| positional_count(before) == 0);
 
     match cmd_name {
+        "new" if completing_first_arg => remote_branches(),
         // Commands that take a silo name as first arg
-        "new" | "rm" | "cd" | "exec" | "run" | "rebase" | "merge" | "reset" | "claude" => {
+        "rm" | "cd" | "code" | "note" | "policy" | "exec" | "verify" | "last-run" | "run"
+        | "rebase" | "merge" | "reset" | "claude" | "artifacts" | "transcript" | "review"
+        | "compare" | "test-matrix" | "info" => {
             if completing_first_arg {
                 silo_names()
             } else {
@@ -114,33 +292,91 @@ fn complete_leaf(cmd_name: &str, remaining: &[String]) -> Vec<Completion> {
     }
 }
 
+/// Branches of the current repository's remotes that have no local branch
+/// yet, which `new` starts a silo from. Described by where they're from.
+fn remote_branches() -> Vec<Completion> {
+    let Some(root) = git::try_get_repo_root() else {
+        return vec![];
+    };
+    let _phase = timings::phase("list branches");
+    let local: HashSet<String> = git::list_local_branches(&root).into_iter().collect();
+    let mut out: Vec<Completion> = Vec::new();
+    for rb in git::list_remote_branches(&root) {
+        if local.contains(&rb.branch) {
+            continue;
+        }
+        match out.iter_mut().find(|c| c.value == rb.branch) {
+            Some(existing) => {
+                let desc = existing.description.get_or_insert_default();
+                desc.push_str(", ");
+                desc.push_str(&rb.short_name());
+            }
+            None => out.push(Completion {
+                kind: Kind::Branch,
+                value: rb.branch.clone(),
+                description: Some(rb.short_name()),
+                group: None,
+            }),
+        }
+    }
+    out
+}
+
 /// Get silo name completions based on current directory context.
 fn silo_names() -> Vec<Completion> {
-    let repo_root = git::try_get_repo_root();
+    let repo_root = {
+        let _phase = timings::phase("detect repo");
+        git::try_get_repo_root()
+    };
 
     if let Some(ref root) = repo_root {
         // Inside a repo: list branches for this repo
-        if let Ok(worktrees) = git::list_worktrees(root) {
+        let worktrees = {
+            let _phase = timings::phase("list worktrees");
+            git::list_worktrees(root)
+        };
+        if let Ok(worktrees) = worktrees {
+            let _phase = timings::phase("silo status");
+            let main_branch = worktrees.first().and_then(|wt| wt.branch.clone());
             return worktrees
                 .iter()
                 .enumerate()
                 .filter_map(|(i, wt)| {
                     let is_main = i == 0;
                     let is_silo = silo::is_silo_path(&wt.path);
-                    if is_main || is_silo {
-                        wt.branch.as_ref().map(Completion::new)
-                    } else {
-                        None
-                    }
+                    let branch = wt.branch.as_ref().filter(|_| is_main || is_silo)?;
+                    Some(Completion {
+                        description: status(&wt.path, branch, main_branch.as_deref()),
+                        ..Completion::silo(branch)
+                    })
                 })
                 .collect();
         }
     }
 
-    // Not in a repo: list all silos with display names (always include repo prefix)
+    // Not in a repo: list all silos with display names (always include repo
+    // prefix), grouped by repository
     if let Ok(silos) = silo::collect_all_silos() {
+        let _phase = timings::phase("silo status");
         let display_names = names::generate_display_names(&silos, true);
-        let mut names: Vec<_> = display_names.into_iter().map(Completion::new).collect();
+        let mut main_branches: HashMap<&Path, Option<String>> = HashMap::new();
+        let mut names: Vec<_> = silos
+            .iter()
+            .zip(display_names)
+            .map(|(s, name)| {
+                let main_branch = main_branches
+                    .entry(&s.main_worktree)
+                    .or_insert_with(|| git::get_current_branch(&s.main_worktree));
+                Completion {
+                    description: s
+                        .branch
+                        .as_deref()
+                        .and_then(|branch| status(&s.storage_path, branch, main_branch.as_deref())),
+                    group: Some(s.repo_name.clone()),
+                    ..Completion::silo(names::quote_display_name(&name, s))
+                }
+            })
+            .collect();
         names.sort_by(|a, b| a.value.cmp(&b.value));
         return names;
     }
@@ -148,6 +384,27 @@ fn silo_names() -> Vec<Completion> {
     vec![]
 }
 
+/// Describe a silo's state next to its name, as `list` shows it: commits
+/// ahead of and behind the main branch, uncommitted changes and running
+/// processes. None for a clean silo level with the main branch.
+fn status(path: &Path, branch: &str, main_branch: Option<&str>) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(main_branch) = main_branch.filter(|main| *main != branch) {
+        let (ahead, behind) = git::get_ahead_behind(path, branch, main_branch);
+        if ahead > 0 || behind > 0 {
+            parts.push(format!("+{} -{}", ahead, behind));
+        }
+    }
+    if !git::get_uncommitted_stats(path).is_clean() {
+        parts.push("dirty".to_string());
+    }
+    let processes = process::list_active(path).len();
+    if processes > 0 {
+        parts.push(messages::count(processes, "process", "processes"));
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,6 +423,21 @@ mod tests {
         assert!(v.contains(&"sandbox"));
     }
 
+    #[test]
+    fn candidates_filter_by_word() {
+        let completions = candidates(&["shell".into()], "in");
+        assert_eq!(values(&completions), vec!["init"]);
+        assert!(candidates(&[], "zz").is_empty());
+    }
+
+    #[test]
+    fn is_command_walks_subcommands() {
+        assert!(is_command(&[]));
+        assert!(is_command(&["sandbox".into(), "claude".into()]));
+        assert!(is_command(&["ls".into()]));
+        assert!(!is_command(&["sandbox".into(), "bogus".into()]));
+    }
+
     #[test]
     fn shell_returns_init() {
         let completions = generate(&["shell".into()]);
@@ -180,6 +452,8 @@ mod tests {
         let completions = generate(&["shell".into(), "init".into()]);
         let v = values(&completions);
         assert!(v.contains(&"zsh"));
+        assert!(v.contains(&"bash"));
+        assert!(v.contains(&"elvish"));
     }
 
     #[test]
@@ -226,11 +500,58 @@ mod tests {
 
     #[test]
     fn list_returns_nothing() {
-        // No flag completions
+        // Flags are only offered for a word starting with '-'
         let completions = generate(&["list".into()]);
         assert!(completions.is_empty());
     }
 
+    #[test]
+    fn dash_returns_flags_of_command() {
+        let completions = generate(&["list".into(), "--".into()]);
+        let v = values(&completions);
+        assert!(v.contains(&"--all"));
+        assert!(v.contains(&"-a"));
+        // Global flags and --help apply to every command
+        assert!(v.contains(&"--dry-run"));
+        assert!(v.contains(&"--help"));
+        // Hidden flags aren't offered
+        assert!(!v.contains(&"--timings"));
+        assert!(completions.iter().all(|c| c.kind == Kind::Flag));
+        let all = completions.iter().find(|c| c.value == "--all").unwrap();
+        assert_eq!(
+            all.description.as_deref(),
+            Some("List silos for all repositories")
+        );
+    }
+
+    #[test]
+    fn dash_after_arguments_returns_flags() {
+        let completions = generate(&["prune".into(), "--all".into(), "-".into()]);
+        assert!(values(&completions).contains(&"--older-than"));
+
+        let completions = generate(&["shell".into(), "-".into()]);
+        assert!(values(&completions).contains(&"--help"));
+        let completions = generate(&["-".into()]);
+        assert!(values(&completions).contains(&"--quiet"));
+    }
+
+    #[test]
+    fn no_flags_inside_trailing_command() {
+        let args = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        let completions = generate(&args(&["exec", "feature", "--"]));
+        assert!(values(&completions).contains(&"--tty"));
+        let completions = generate(&args(&["exec", "feature", "cargo", "--"]));
+        assert!(completions.is_empty(), "got: {:?}", completions);
+        let completions = generate(&args(&["new", "feature", "--", "-"]));
+        assert!(completions.is_empty(), "got: {:?}", completions);
+    }
+
+    #[test]
+    fn flags_before_silo_name_are_skipped() {
+        let completions = generate(&["rm".into(), "--force".into(), "".into()]);
+        assert!(completions.iter().all(|c| c.kind == Kind::Silo));
+    }
+
     #[test]
     fn partial_command_returns_commands() {
         // Partial "sh" should still return all commands for shell to filter
@@ -241,13 +562,69 @@ mod tests {
 
     #[test]
     fn format_zsh_escapes_colons() {
-        let c = Completion::with_desc("test", "desc:with:colons");
-        assert_eq!(c.format_zsh(), "test:desc\\:with\\:colons");
+        let c = Completion::command("test", "desc:with:colons");
+        assert_eq!(c.format_zsh(), "command\ttest:desc\\:with\\:colons");
     }
 
     #[test]
     fn format_zsh_no_description() {
-        let c = Completion::new("branch-name");
-        assert_eq!(c.format_zsh(), "branch-name");
+        let c = Completion::silo("branch-name");
+        assert_eq!(c.format_zsh(), "silo\tbranch-name");
+    }
+
+    #[test]
+    fn format_zsh_escapes_colons_in_names() {
+        let c = Completion::silo("1a2b3c4d:feature");
+        assert_eq!(c.format_zsh(), "silo\t1a2b3c4d\\:feature");
+    }
+
+    #[test]
+    fn format_zsh_keeps_spaces_in_names() {
+        let c = Completion::silo("my repo/feature");
+        assert_eq!(c.format_zsh(), "silo\tmy repo/feature");
+    }
+
+    #[test]
+    fn format_zsh_names_repository_group() {
+        let c = Completion {
+            group: Some("my-repo".to_string()),
+            ..Completion::silo("my-repo/feature")
+        };
+        assert_eq!(c.format_zsh(), "silo/my-repo\tmy-repo/feature");
+        assert_eq!(c.format_fish(), "my-repo/feature");
+    }
+
+    #[test]
+    fn format_fish_and_bash() {
+        let completions = [
+            Completion::command("cd", "Change directory"),
+            Completion::silo("feature"),
+        ];
+        assert_eq!(
+            format(&completions, Format::Fish),
+            "cd\tChange directory\nfeature\n"
+        );
+        assert_eq!(format(&completions, Format::Bash), "cd\nfeature\n");
+    }
+
+    #[test]
+    fn format_json() {
+        let completions = [Completion::silo("feature")];
+        assert_eq!(
+            format(&completions, Format::Json),
+            "[{\"kind\":\"silo\",\"value\":\"feature\",\"description\":null}]\n"
+        );
+    }
+
+    #[test]
+    fn format_empty_prints_nothing() {
+        assert_eq!(format(&[], Format::Zsh), "");
+        assert_eq!(format(&[], Format::Json), "[]\n");
+    }
+
+    #[test]
+    fn commands_and_silos_are_grouped() {
+        let completions = generate(&[]);
+        assert!(completions.iter().all(|c| c.kind == Kind::Command));
     }
 }
@@ -3,26 +3,226 @@ use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use tracing::warn;
 
+use crate::storage::Layout;
+
 const USER_CONFIG_PATH: &str = ".config/silo.toml";
-const LOCAL_CONFIG_NAME: &str = ".silo.toml";
+pub const LOCAL_CONFIG_NAME: &str = ".silo.toml";
 const DEFAULT_WORKTREE_DIR: &str = ".local/var/silo";
 
 /// Known top-level config keys
 const KNOWN_KEYS: &[&str] = &[
     "worktree_dir",
+    "worktree_dirs",
+    "storage_routes",
     "warn_shell_integration",
+    "shell_widget_key",
+    "shell_picker",
     "extra_command_args",
+    "display_names",
+    "untracked_collapse",
+    "storage_mode",
+    "review_command",
+    "editor_command",
+    "verify_command",
+    "git_config",
+    "merge_from_main_only",
+    "protected_branches",
+    "protected_branch_policy",
+    "after_merge",
+    "pool_size",
+    "git_maintenance",
+    "prefetch_blobs",
+    "process_tracking",
+    "busy_indicator",
+    "tracking_dir",
+    "commit_signing",
+    "signing_format",
+    "signing_key",
+    "fsmonitor",
+    "untracked_cache",
+    "namespace",
+    "agent_email",
+    "list",
 ];
 
+/// Default permissions for a newly created storage directory.
+const DEFAULT_STORAGE_MODE: u32 = 0o700;
+
+/// How silo display names are generated.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayNames {
+    /// Shortest unique name (branch, then repo/branch, ...)
+    #[default]
+    Minimal,
+    /// Always include at least the repo name (repo/branch)
+    AlwaysQualified,
+}
+
+/// What the hooks from `silo hooks install` do about commits and pushes
+/// made directly to a protected branch.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProtectedBranchPolicy {
+    /// Print a warning and let it through
+    #[default]
+    Warn,
+    /// Refuse it (git's --no-verify still bypasses the hook)
+    Block,
+}
+
+/// What `merge` does with the merged silo afterwards.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AfterMerge {
+    /// Leave it as it is
+    #[default]
+    Keep,
+    /// Reset it to the merge result, for reuse
+    Reset,
+    /// Remove it, and its branch
+    Remove,
+}
+
+/// Whether commits made in new silos are signed.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommitSigning {
+    /// Sign every commit and tag, with signing_format and signing_key
+    Enforce,
+    /// Never sign, whatever the user's git config says
+    Disable,
+}
+
+/// How commits are signed, as git's `gpg.format`.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SigningFormat {
+    /// GnuPG (git's default)
+    #[default]
+    Openpgp,
+    /// An SSH key
+    Ssh,
+    /// X.509 certificates via gpgsm
+    X509,
+}
+
+impl SigningFormat {
+    /// The value of `gpg.format` for this format.
+    fn git_value(self) -> &'static str {
+        match self {
+            SigningFormat::Openpgp => "openpgp",
+            SigningFormat::Ssh => "ssh",
+            SigningFormat::X509 => "x509",
+        }
+    }
+}
+
+/// How a config was loaded, so it can be resolved again for another
+/// directory.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Origin {
+    /// The default hierarchy, resolved relative to a directory
+    #[default]
+    Hierarchy,
+    /// A single file given with --config-file
+    File,
+    /// No config files at all (--no-config)
+    Disabled,
+}
+
+/// Settings for `silo list`, in a `[list]` table.
+#[derive(Debug, Default, Deserialize, Clone)]
+pub struct ListConfig {
+    /// Skip line counts and uncommitted files, as with `list --fast` (default: false)
+    pub fast: Option<bool>,
+}
+
 #[derive(Debug, Default, Deserialize, Clone)]
 pub struct Config {
     pub worktree_dir: Option<String>,
+    /// Several storage roots, the first being the primary one. Takes
+    /// precedence over worktree_dir.
+    pub worktree_dirs: Option<Vec<String>>,
+    /// Which storage root a repository's silos go to: keys are repository
+    /// filters as for `list --repo`, values are roots from worktree_dirs.
+    #[serde(default)]
+    pub storage_routes: HashMap<String, String>,
     /// Whether to warn when shell integration is not enabled (default: true)
     pub warn_shell_integration: Option<bool>,
+    /// Key bound to the silo picker widget by `shell init`, e.g. "^G" (default: none)
+    pub shell_widget_key: Option<String>,
+    /// Command the picker widget reads silo names from on stdin and prints
+    /// the chosen one with (default: ["fzf"])
+    pub shell_picker: Option<Vec<String>>,
     /// Extra arguments to inject into commands based on command prefix.
     /// Keys are command prefixes (e.g., "git", "git diff"), values are args to insert.
     #[serde(default)]
     pub extra_command_args: HashMap<String, Vec<String>>,
+    /// Display name style: "minimal" (default) or "always-qualified"
+    pub display_names: Option<DisplayNames>,
+    /// `list` shows a directory holding at least this many untracked files
+    /// as one entry, e.g. "build/ (312 files)"; 0 never collapses (default: 10)
+    pub untracked_collapse: Option<usize>,
+    /// Octal permissions for a newly created storage directory (default: "0700")
+    pub storage_mode: Option<String>,
+    /// Command that reads a diff on stdin and prints a review (for `agent review --pipe`)
+    pub review_command: Option<Vec<String>>,
+    /// Editor that opens a `.code-workspace` file given as its last
+    /// argument, for `silo code` (default: ["code"])
+    pub editor_command: Option<Vec<String>>,
+    /// Command that checks a silo's changes, e.g. ["cargo", "test"] (for `silo verify`)
+    pub verify_command: Option<Vec<String>>,
+    /// Options passed as `-c key=value` to every git command silo runs
+    #[serde(default)]
+    pub git_config: Vec<String>,
+    /// Only allow `merge` from the main worktree, not from inside a silo (default: false)
+    pub merge_from_main_only: Option<bool>,
+    /// Branches the hooks from `silo hooks install` guard (default: ["main", "master"])
+    pub protected_branches: Option<Vec<String>>,
+    /// Whether those hooks "warn" about (default) or "block" direct commits
+    /// and pushes to protected branches
+    pub protected_branch_policy: Option<ProtectedBranchPolicy>,
+    /// What `merge` does with the merged silo: "keep", "reset" or "remove"
+    /// (default: keep)
+    pub after_merge: Option<AfterMerge>,
+    /// Idle silos `gc` keeps ready for `silo reuse` (default: 0)
+    pub pool_size: Option<usize>,
+    /// Register repositories for `git maintenance` when creating silos (default: false)
+    pub git_maintenance: Option<bool>,
+    /// In a partial clone, fetch a new silo's missing blobs in one batch
+    /// before checking it out (default: false)
+    pub prefetch_blobs: Option<bool>,
+    /// Whether to record PID files for processes started in silos (default: true)
+    pub process_tracking: Option<bool>,
+    /// Mark the tmux window a command runs in as busy while it runs (default: true)
+    pub busy_indicator: Option<bool>,
+    /// Directory for per-silo tracking data (default: `.tracking` in each repo directory)
+    pub tracking_dir: Option<String>,
+    /// Sign commits made in new silos ("enforce") or never sign them
+    /// ("disable"), set in each silo's own git config (default: git's config)
+    pub commit_signing: Option<CommitSigning>,
+    /// Signature format when commit_signing = "enforce" (default: openpgp)
+    pub signing_format: Option<SigningFormat>,
+    /// Key commits are signed with when commit_signing = "enforce": a GnuPG
+    /// key ID, or the path of an SSH key (default: git's user.signingKey)
+    pub signing_key: Option<String>,
+    /// Turn git's file system monitor (`core.fsmonitor`) on or off in new
+    /// silos, so `git status` needn't scan the whole worktree (default: git's config)
+    pub fsmonitor: Option<bool>,
+    /// Turn git's untracked cache (`core.untrackedCache`) on or off in new
+    /// silos (default: git's config)
+    pub untracked_cache: Option<bool>,
+    /// Prefix for the branches `new` creates, e.g. "alice/", so the
+    /// branches of many people and agents in one repository stay apart
+    pub namespace: Option<String>,
+    /// Email for agents' commits, the `{agent_email}` placeholder in
+    /// extra_command_args (default: git's user.email)
+    pub agent_email: Option<String>,
+    #[serde(default)]
+    pub list: ListConfig,
+    #[serde(skip)]
+    origin: Origin,
 }
 
 impl Config {
@@ -33,43 +233,73 @@ impl Config {
     /// 2. Main worktree config (if in a silo, the original repo's .silo.toml)
     /// 3. Current directory config (.silo.toml)
     pub fn load() -> Result<Self, String> {
-        let mut config = Self::load_user()?;
+        Self::load_paths(&Self::default_paths()?)
+    }
 
-        if let Ok(cwd) = std::env::current_dir() {
-            // If we're in a silo worktree, also check the main worktree for config
-            if let Some(main_wt) = crate::git::get_main_worktree_from_silo(&cwd) {
-                let main_config = Self::load_local(&main_wt)?;
-                config = config.merge(main_config);
-            }
+    /// Built-in defaults only, ignoring every config file (--no-config).
+    pub fn disabled() -> Self {
+        Config {
+            origin: Origin::Disabled,
+            ..Default::default()
+        }
+    }
 
-            // Current directory config has highest priority
-            let local_config = Self::load_local(&cwd)?;
-            config = config.merge(local_config);
+    /// Resolve this config for commands run in `dir`, typically a silo
+    /// targeted from elsewhere: the hierarchy is loaded as if silo had been
+    /// invoked there, so the silo's own `.silo.toml` applies. A config given
+    /// with --config-file, or --no-config, applies everywhere and is
+    /// returned unchanged.
+    pub fn for_dir(&self, dir: &Path) -> Result<Self, String> {
+        match self.origin {
+            Origin::Hierarchy => Self::load_paths(&Self::paths_for(dir)?),
+            Origin::File | Origin::Disabled => Ok(self.clone()),
         }
+    }
 
-        Ok(config)
+    fn load_paths(paths: &[PathBuf]) -> Result<Self, String> {
+        paths.iter().try_fold(Config::default(), |config, path| {
+            Ok(config.merge(Self::load_from_path(path)?))
+        })
     }
 
-    /// Load config exclusively from a specific file (ignores default locations).
-    /// Unlike load_from_path, this returns an error if the file doesn't exist.
-    pub fn load_file(path: &Path) -> Result<Self, String> {
-        if !path.exists() {
-            return Err(format!("Config file not found: {}", path.display()));
+    /// The config files `load` consults, lowest precedence first.
+    /// Files that don't exist are listed too; loading skips them.
+    pub fn default_paths() -> Result<Vec<PathBuf>, String> {
+        match std::env::current_dir() {
+            Ok(cwd) => Self::paths_for(&cwd),
+            Err(_) => Ok(vec![Self::user_path()?]),
         }
-        Self::load_from_path(path)
     }
 
-    /// Load user config from ~/.config/silo.toml
-    fn load_user() -> Result<Self, String> {
+    /// The config files that apply in `dir`, lowest precedence first.
+    fn paths_for(dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let mut paths = vec![Self::user_path()?];
+
+        // If `dir` is a silo worktree, also check the main worktree for config
+        if let Some(main_wt) = crate::git::get_main_worktree_from_silo(dir) {
+            paths.push(main_wt.join(LOCAL_CONFIG_NAME));
+        }
+
+        // The directory's own config has highest priority
+        paths.push(dir.join(LOCAL_CONFIG_NAME));
+        Ok(paths)
+    }
+
+    fn user_path() -> Result<PathBuf, String> {
         let home = std::env::var("HOME").map_err(|_| "HOME environment variable not set")?;
-        let config_path = PathBuf::from(&home).join(USER_CONFIG_PATH);
-        Self::load_from_path(&config_path)
+        Ok(PathBuf::from(home).join(USER_CONFIG_PATH))
     }
 
-    /// Load local config from path/.silo.toml (returns default if not exists)
-    fn load_local(path: &Path) -> Result<Self, String> {
-        let config_path = path.join(LOCAL_CONFIG_NAME);
-        Self::load_from_path(&config_path)
+    /// Load config exclusively from a specific file (ignores default locations).
+    /// Unlike load_from_path, this returns an error if the file doesn't exist.
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Err(format!("Config file not found: {}", path.display()));
+        }
+        Ok(Config {
+            origin: Origin::File,
+            ..Self::load_from_path(path)?
+        })
     }
 
     /// Load config from a specific path (returns default if not exists)
@@ -100,17 +330,57 @@ impl Config {
     }
 
     /// Merge another config into this one (other takes precedence for set values).
-    /// For extra_command_args, entries from both configs are combined (not overridden).
+    /// For extra_command_args, git_config and storage_routes, entries from both
+    /// configs are combined (not overridden).
     fn merge(self, other: Self) -> Self {
         let mut extra_command_args = self.extra_command_args;
         for (key, args) in other.extra_command_args {
             extra_command_args.entry(key).or_default().extend(args);
         }
+        // Later `-c` options win in git, so the overriding config goes last
+        let mut git_config = self.git_config;
+        git_config.extend(other.git_config);
+        let mut storage_routes = self.storage_routes;
+        storage_routes.extend(other.storage_routes);
 
         Config {
             worktree_dir: other.worktree_dir.or(self.worktree_dir),
+            worktree_dirs: other.worktree_dirs.or(self.worktree_dirs),
+            storage_routes,
             warn_shell_integration: other.warn_shell_integration.or(self.warn_shell_integration),
+            shell_widget_key: other.shell_widget_key.or(self.shell_widget_key),
+            shell_picker: other.shell_picker.or(self.shell_picker),
             extra_command_args,
+            display_names: other.display_names.or(self.display_names),
+            untracked_collapse: other.untracked_collapse.or(self.untracked_collapse),
+            storage_mode: other.storage_mode.or(self.storage_mode),
+            review_command: other.review_command.or(self.review_command),
+            editor_command: other.editor_command.or(self.editor_command),
+            verify_command: other.verify_command.or(self.verify_command),
+            git_config,
+            merge_from_main_only: other.merge_from_main_only.or(self.merge_from_main_only),
+            protected_branches: other.protected_branches.or(self.protected_branches),
+            protected_branch_policy: other
+                .protected_branch_policy
+                .or(self.protected_branch_policy),
+            after_merge: other.after_merge.or(self.after_merge),
+            pool_size: other.pool_size.or(self.pool_size),
+            git_maintenance: other.git_maintenance.or(self.git_maintenance),
+            prefetch_blobs: other.prefetch_blobs.or(self.prefetch_blobs),
+            process_tracking: other.process_tracking.or(self.process_tracking),
+            busy_indicator: other.busy_indicator.or(self.busy_indicator),
+            tracking_dir: other.tracking_dir.or(self.tracking_dir),
+            commit_signing: other.commit_signing.or(self.commit_signing),
+            signing_format: other.signing_format.or(self.signing_format),
+            signing_key: other.signing_key.or(self.signing_key),
+            fsmonitor: other.fsmonitor.or(self.fsmonitor),
+            untracked_cache: other.untracked_cache.or(self.untracked_cache),
+            namespace: other.namespace.or(self.namespace),
+            agent_email: other.agent_email.or(self.agent_email),
+            list: ListConfig {
+                fast: other.list.fast.or(self.list.fast),
+            },
+            origin: self.origin,
         }
     }
 
@@ -124,26 +394,275 @@ impl Config {
         self.warn_shell_integration.unwrap_or(true)
     }
 
-    /// Get the worktree directory, expanding ~ to $HOME
-    pub fn get_worktree_dir(&self) -> Result<PathBuf, String> {
-        let home = std::env::var("HOME").map_err(|_| "HOME environment variable not set")?;
+    /// Key to bind the silo picker widget to, if the widget is wanted.
+    pub fn shell_widget_key(&self) -> Option<&str> {
+        self.shell_widget_key
+            .as_deref()
+            .filter(|key| !key.is_empty())
+    }
+
+    /// Command the picker widget chooses a silo with (default: fzf).
+    pub fn shell_picker(&self) -> Vec<String> {
+        self.shell_picker
+            .clone()
+            .filter(|cmd| !cmd.is_empty())
+            .unwrap_or_else(|| vec!["fzf".to_string()])
+    }
 
-        let path = self.worktree_dir.as_deref().unwrap_or(DEFAULT_WORKTREE_DIR);
-
-        // Expand ~ to home directory
-        let expanded = if let Some(suffix) = path.strip_prefix("~/") {
-            format!("{}/{}", home, suffix)
-        } else if path == "~" {
-            home
-        } else if path.starts_with('/') {
-            path.to_string()
-        } else {
-            // Relative path treated as relative to home
-            format!("{}/{}", home, path)
+    /// How many untracked files make `list` collapse their directory
+    /// (default: 10, 0 never collapses).
+    pub fn untracked_collapse(&self) -> usize {
+        self.untracked_collapse.unwrap_or(10)
+    }
+
+    /// Whether `merge` must be run from the main worktree (default: false)
+    pub fn merge_from_main_only(&self) -> bool {
+        self.merge_from_main_only.unwrap_or(false)
+    }
+
+    /// Branches the installed git hooks guard (default: main and master).
+    pub fn protected_branches(&self) -> Vec<String> {
+        self.protected_branches
+            .clone()
+            .unwrap_or_else(|| vec!["main".to_string(), "master".to_string()])
+    }
+
+    /// What the installed git hooks do about direct commits to protected
+    /// branches (default: warn).
+    pub fn protected_branch_policy(&self) -> ProtectedBranchPolicy {
+        self.protected_branch_policy.unwrap_or_default()
+    }
+
+    /// What `merge` does with the merged silo (default: keep).
+    pub fn after_merge(&self) -> AfterMerge {
+        self.after_merge.unwrap_or_default()
+    }
+
+    /// How many idle silos `gc` keeps in each repository's pool for `silo
+    /// reuse` (default: 0, no pool).
+    pub fn pool_size(&self) -> usize {
+        self.pool_size.unwrap_or(0)
+    }
+
+    /// Whether `list` skips line counts and uncommitted files by default
+    /// (default: false).
+    pub fn list_fast(&self) -> bool {
+        self.list.fast.unwrap_or(false)
+    }
+
+    /// Whether `new` registers the repository for background git maintenance
+    /// (default: false)
+    pub fn git_maintenance(&self) -> bool {
+        self.git_maintenance.unwrap_or(false)
+    }
+
+    /// Whether `new` fetches the blobs a partial clone is missing in one
+    /// batch before checking out (default: false)
+    pub fn prefetch_blobs(&self) -> bool {
+        self.prefetch_blobs.unwrap_or(false)
+    }
+
+    /// Whether to record PID files for processes started in silos (default: true)
+    pub fn process_tracking(&self) -> bool {
+        self.process_tracking.unwrap_or(true)
+    }
+
+    /// Whether to mark the tmux window a command runs in as busy (default: true)
+    pub fn busy_indicator(&self) -> bool {
+        self.busy_indicator.unwrap_or(true)
+    }
+
+    /// Whether commits in new silos are signed, if configured.
+    pub fn commit_signing(&self) -> Option<CommitSigning> {
+        self.commit_signing
+    }
+
+    /// Signature format for enforced commit signing (default: openpgp).
+    pub fn signing_format(&self) -> SigningFormat {
+        self.signing_format.unwrap_or_default()
+    }
+
+    /// Key for enforced commit signing, if configured. SSH keys are paths,
+    /// with ~ expanded to $HOME.
+    pub fn signing_key(&self) -> Result<Option<String>, String> {
+        let Some(key) = self.signing_key.as_deref().filter(|key| !key.is_empty()) else {
+            return Ok(None);
+        };
+        if self.signing_format() == SigningFormat::Ssh && key.starts_with('~') {
+            return Ok(Some(expand_home(key)?.display().to_string()));
+        }
+        Ok(Some(key.to_string()))
+    }
+
+    /// Git options that give a new silo the configured commit signing, to be
+    /// set in its worktree's own config.
+    pub fn signing_git_config(&self) -> Result<Vec<(&'static str, String)>, String> {
+        let options = match self.commit_signing {
+            None => vec![],
+            Some(CommitSigning::Disable) => vec![
+                ("commit.gpgSign", "false".to_string()),
+                ("tag.gpgSign", "false".to_string()),
+            ],
+            Some(CommitSigning::Enforce) => {
+                let mut options = vec![
+                    ("commit.gpgSign", "true".to_string()),
+                    ("tag.gpgSign", "true".to_string()),
+                    ("gpg.format", self.signing_format().git_value().to_string()),
+                ];
+                if let Some(key) = self.signing_key()? {
+                    options.push(("user.signingKey", key));
+                }
+                options
+            }
         };
+        Ok(options)
+    }
 
-        Ok(PathBuf::from(expanded))
+    /// Git options that speed up `git status` in a new silo, as configured
+    /// with fsmonitor and untracked_cache, to be set in its worktree's own
+    /// config.
+    pub fn status_git_config(&self) -> Vec<(&'static str, String)> {
+        let mut options = Vec::new();
+        if let Some(enabled) = self.fsmonitor {
+            options.push(("core.fsmonitor", enabled.to_string()));
+        }
+        if let Some(enabled) = self.untracked_cache {
+            options.push(("core.untrackedCache", enabled.to_string()));
+        }
+        options
     }
+
+    /// The configured branch namespace, always ending in "/". None if unset
+    /// or empty.
+    pub fn namespace(&self) -> Option<String> {
+        let namespace = self.namespace.as_deref()?.trim_end_matches('/');
+        (!namespace.is_empty()).then(|| format!("{}/", namespace))
+    }
+
+    /// The configured email for agents' commits. None if unset or empty.
+    pub fn agent_email(&self) -> Option<&str> {
+        self.agent_email
+            .as_deref()
+            .filter(|email| !email.is_empty())
+    }
+
+    /// Get the configured tracking directory, expanding ~ to $HOME.
+    /// None keeps tracking data next to the silos.
+    pub fn tracking_dir(&self) -> Result<Option<PathBuf>, String> {
+        self.tracking_dir.as_deref().map(expand_home).transpose()
+    }
+
+    /// Whether display names should always include the repo name.
+    pub fn always_qualify_names(&self) -> bool {
+        self.display_names.unwrap_or_default() == DisplayNames::AlwaysQualified
+    }
+
+    /// Get the permissions to apply when creating the storage directory.
+    pub fn storage_mode(&self) -> Result<u32, String> {
+        let Some(mode) = self.storage_mode.as_deref() else {
+            return Ok(DEFAULT_STORAGE_MODE);
+        };
+        let digits = mode.strip_prefix("0o").unwrap_or(mode);
+        u32::from_str_radix(digits, 8)
+            .ok()
+            .filter(|m| *m <= 0o7777)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid storage_mode '{}': expected octal like \"0700\"",
+                    mode
+                )
+            })
+    }
+
+    /// Get the command used to review a silo's diff, if configured.
+    pub fn review_command(&self) -> Option<&[String]> {
+        self.review_command.as_deref().filter(|cmd| !cmd.is_empty())
+    }
+
+    /// Editor `silo code` opens workspaces with (default: VS Code).
+    pub fn editor_command(&self) -> Vec<String> {
+        self.editor_command
+            .clone()
+            .filter(|cmd| !cmd.is_empty())
+            .unwrap_or_else(|| vec!["code".to_string()])
+    }
+
+    /// Get the command used to verify a silo's changes, if configured.
+    pub fn verify_command(&self) -> Option<&[String]> {
+        self.verify_command.as_deref().filter(|cmd| !cmd.is_empty())
+    }
+
+    /// Get the `key=value` options applied to every git command silo runs.
+    pub fn git_config(&self) -> Result<&[String], String> {
+        for option in &self.git_config {
+            let key = option
+                .split_once('=')
+                .map_or(option.as_str(), |(key, _)| key);
+            if !key.contains('.') || key.starts_with('.') || key.ends_with('.') {
+                return Err(format!(
+                    "Invalid git_config option '{}': expected \"section.key=value\"",
+                    option
+                ));
+            }
+        }
+        Ok(&self.git_config)
+    }
+
+    /// Get the worktree directory, expanding ~ to $HOME. With several
+    /// storage roots, this is the primary one.
+    pub fn get_worktree_dir(&self) -> Result<PathBuf, String> {
+        match self.worktree_dirs.as_deref() {
+            Some([first, ..]) => expand_home(first),
+            _ => expand_home(self.worktree_dir.as_deref().unwrap_or(DEFAULT_WORKTREE_DIR)),
+        }
+    }
+
+    /// Get every storage root, primary first, expanding ~ to $HOME.
+    pub fn get_worktree_dirs(&self) -> Result<Vec<PathBuf>, String> {
+        match self.worktree_dirs.as_deref() {
+            Some([]) => Err("Invalid worktree_dirs: expected at least one directory".to_string()),
+            Some(dirs) => dirs.iter().map(|dir| expand_home(dir)).collect(),
+            None => Ok(vec![self.get_worktree_dir()?]),
+        }
+    }
+
+    /// Get the storage roots and the routing of repositories to them.
+    pub fn storage_layout(&self) -> Result<Layout, String> {
+        let roots = self.get_worktree_dirs()?;
+        let routes = self
+            .storage_routes
+            .iter()
+            .map(|(filter, root)| {
+                let path = expand_home(root)?;
+                if !roots.contains(&path) {
+                    return Err(format!(
+                        "Invalid storage_routes entry '{}': {} is not in worktree_dirs",
+                        filter, root
+                    ));
+                }
+                Ok((filter.clone(), path))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Layout::new(roots, routes))
+    }
+}
+
+/// Expand ~ to $HOME. Relative paths are relative to $HOME too.
+fn expand_home(path: &str) -> Result<PathBuf, String> {
+    let home = std::env::var("HOME").map_err(|_| "HOME environment variable not set")?;
+
+    let expanded = if let Some(suffix) = path.strip_prefix("~/") {
+        format!("{}/{}", home, suffix)
+    } else if path == "~" {
+        home
+    } else if path.starts_with('/') {
+        path.to_string()
+    } else {
+        // Relative path treated as relative to home
+        format!("{}/{}", home, path)
+    };
+
+    Ok(PathBuf::from(expanded))
 }
 
 #[cfg(test)]
@@ -156,11 +675,13 @@ mod tests {
             worktree_dir: Some("/base/dir".to_string()),
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let other = Config {
             worktree_dir: Some("/other/dir".to_string()),
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let merged = base.merge(other);
         assert_eq!(merged.worktree_dir, Some("/other/dir".to_string()));
@@ -172,27 +693,65 @@ mod tests {
             worktree_dir: Some("/base/dir".to_string()),
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let other = Config {
             worktree_dir: None,
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let merged = base.merge(other);
         assert_eq!(merged.worktree_dir, Some("/base/dir".to_string()));
     }
 
+    #[test]
+    fn test_storage_layout_from_worktree_dirs() {
+        let config: Config = toml::from_str(
+            r#"
+            worktree_dir = "/ignored"
+            worktree_dirs = ["/fast", "/big"]
+            [storage_routes]
+            "org/mono" = "/big"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.get_worktree_dir().unwrap(), PathBuf::from("/fast"));
+        let layout = config.storage_layout().unwrap();
+        assert_eq!(
+            layout.roots(),
+            [PathBuf::from("/fast"), PathBuf::from("/big")]
+        );
+        assert_eq!(
+            layout.root_for(Path::new("/src/org/mono")),
+            Path::new("/big")
+        );
+        assert_eq!(layout.root_for(Path::new("/src/app")), Path::new("/fast"));
+
+        let config: Config = toml::from_str(
+            r#"
+            worktree_dirs = ["/fast"]
+            storage_routes = { "mono" = "/elsewhere" }
+            "#,
+        )
+        .unwrap();
+        let err = config.storage_layout().unwrap_err();
+        assert!(err.contains("not in worktree_dirs"), "{}", err);
+    }
+
     #[test]
     fn test_merge_both_none() {
         let base = Config {
             worktree_dir: None,
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let other = Config {
             worktree_dir: None,
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let merged = base.merge(other);
         assert_eq!(merged.worktree_dir, None);
@@ -204,6 +763,7 @@ mod tests {
             worktree_dir: Some("/absolute/path/to/silos".to_string()),
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let result = config.get_worktree_dir();
         assert!(result.is_ok());
@@ -216,6 +776,7 @@ mod tests {
             worktree_dir: Some("~/my/silos".to_string()),
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let result = config.get_worktree_dir();
         assert!(result.is_ok());
@@ -231,6 +792,7 @@ mod tests {
             worktree_dir: Some("relative/path".to_string()),
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let result = config.get_worktree_dir();
         assert!(result.is_ok());
@@ -245,6 +807,7 @@ mod tests {
             worktree_dir: None,
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let result = config.get_worktree_dir();
         assert!(result.is_ok());
@@ -268,6 +831,7 @@ mod tests {
             worktree_dir: Some("/test".to_string()),
             warn_shell_integration: Some(false),
             extra_command_args: extra_args,
+            ..Default::default()
         };
         let cloned = config.clone();
         assert_eq!(config.worktree_dir, cloned.worktree_dir);
@@ -281,6 +845,7 @@ mod tests {
             worktree_dir: Some("/test".to_string()),
             warn_shell_integration: None,
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let debug = format!("{:?}", config);
         assert!(debug.contains("/test"));
@@ -298,6 +863,7 @@ mod tests {
             worktree_dir: None,
             warn_shell_integration: Some(false),
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         assert!(!config.warn_shell_integration());
 
@@ -305,6 +871,7 @@ mod tests {
             worktree_dir: None,
             warn_shell_integration: Some(true),
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         assert!(config.warn_shell_integration());
     }
@@ -315,11 +882,13 @@ mod tests {
             worktree_dir: None,
             warn_shell_integration: Some(true),
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let other = Config {
             worktree_dir: None,
             warn_shell_integration: Some(false),
             extra_command_args: HashMap::new(),
+            ..Default::default()
         };
         let merged = base.merge(other);
         assert_eq!(merged.warn_shell_integration, Some(false));
@@ -357,11 +926,13 @@ git = ["-c", "color.ui=always"]
             worktree_dir: None,
             warn_shell_integration: None,
             extra_command_args: base_args,
+            ..Default::default()
         };
         let other = Config {
             worktree_dir: None,
             warn_shell_integration: None,
             extra_command_args: other_args,
+            ..Default::default()
         };
 
         let merged = base.merge(other);
@@ -387,4 +958,239 @@ git = ["-c", "color.ui=always"]
             Some(&vec!["--silent".to_string()])
         );
     }
+
+    #[test]
+    fn test_parse_display_names() {
+        let config: Config = toml::from_str("display_names = \"always-qualified\"").unwrap();
+        assert_eq!(config.display_names, Some(DisplayNames::AlwaysQualified));
+        assert!(config.always_qualify_names());
+
+        let config: Config = toml::from_str("display_names = \"minimal\"").unwrap();
+        assert!(!config.always_qualify_names());
+    }
+
+    #[test]
+    fn test_storage_mode_default() {
+        assert_eq!(Config::default().storage_mode().unwrap(), 0o700);
+    }
+
+    #[test]
+    fn test_storage_mode_parses_octal() {
+        let config = Config {
+            storage_mode: Some("0770".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.storage_mode().unwrap(), 0o770);
+
+        let config = Config {
+            storage_mode: Some("0o750".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.storage_mode().unwrap(), 0o750);
+    }
+
+    #[test]
+    fn test_storage_mode_rejects_invalid() {
+        let config = Config {
+            storage_mode: Some("rwx".to_string()),
+            ..Default::default()
+        };
+        assert!(config.storage_mode().is_err());
+
+        let config = Config {
+            storage_mode: Some("99999".to_string()),
+            ..Default::default()
+        };
+        assert!(config.storage_mode().is_err());
+    }
+
+    #[test]
+    fn test_always_qualify_names_defaults_to_false() {
+        assert!(!Config::default().always_qualify_names());
+    }
+
+    #[test]
+    fn test_merge_display_names() {
+        let base = Config {
+            display_names: Some(DisplayNames::AlwaysQualified),
+            ..Default::default()
+        };
+        let merged = base.merge(Config::default());
+        assert!(merged.always_qualify_names());
+    }
+
+    #[test]
+    fn test_list_fast() {
+        let config: Config = toml::from_str("[list]\nfast = true").unwrap();
+        assert!(config.list_fast());
+        assert!(!Config::default().list_fast());
+
+        // A later file that doesn't set it keeps the earlier setting
+        let merged = config.merge(toml::from_str("[list]").unwrap());
+        assert!(merged.list_fast());
+        let merged = merged.merge(toml::from_str("list.fast = false").unwrap());
+        assert!(!merged.list_fast());
+    }
+
+    #[test]
+    fn test_review_command() {
+        let config: Config =
+            toml::from_str(r#"review_command = ["claude", "-p", "review this diff"]"#).unwrap();
+        assert_eq!(
+            config.review_command(),
+            Some(
+                &[
+                    "claude".to_string(),
+                    "-p".to_string(),
+                    "review this diff".to_string()
+                ][..]
+            )
+        );
+
+        let config: Config = toml::from_str("review_command = []").unwrap();
+        assert_eq!(config.review_command(), None);
+    }
+
+    #[test]
+    fn test_merge_git_config_combines_in_order() {
+        let base = Config {
+            git_config: vec!["core.fsmonitor=true".to_string()],
+            ..Default::default()
+        };
+        let other = Config {
+            git_config: vec!["core.fsmonitor=false".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            base.merge(other).git_config().unwrap(),
+            ["core.fsmonitor=true", "core.fsmonitor=false"]
+        );
+    }
+
+    #[test]
+    fn test_git_config_rejects_keys_without_section() {
+        let config: Config = toml::from_str(r#"git_config = ["nosection=1"]"#).unwrap();
+        assert!(config.git_config().is_err());
+
+        let config: Config = toml::from_str(r#"git_config = ["core.bare"]"#).unwrap();
+        assert!(config.git_config().is_ok());
+    }
+
+    #[test]
+    fn test_merge_verify_command() {
+        let base = Config {
+            verify_command: Some(vec!["make".to_string(), "test".to_string()]),
+            ..Default::default()
+        };
+        let other = Config {
+            verify_command: Some(vec!["cargo".to_string(), "test".to_string()]),
+            ..Default::default()
+        };
+        let merged = base.merge(other);
+        assert_eq!(
+            merged.verify_command(),
+            Some(&["cargo".to_string(), "test".to_string()][..])
+        );
+    }
+
+    #[test]
+    fn test_tracking_options() {
+        let config = Config::default();
+        assert!(config.process_tracking());
+        assert_eq!(config.tracking_dir(), Ok(None));
+
+        let config: Config =
+            toml::from_str("process_tracking = false\ntracking_dir = \"/fast/silo\"").unwrap();
+        assert!(!config.process_tracking());
+        assert_eq!(config.tracking_dir(), Ok(Some(PathBuf::from("/fast/silo"))));
+    }
+
+    #[test]
+    fn test_for_dir_uses_config_of_dir() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join(LOCAL_CONFIG_NAME), "verify_command = [\"make\"]\n").unwrap();
+
+        let config = Config::default().for_dir(dir).unwrap();
+        assert_eq!(config.verify_command(), Some(&["make".to_string()][..]));
+
+        assert_eq!(
+            Config::disabled().for_dir(dir).unwrap().verify_command(),
+            None
+        );
+
+        // A --config-file config applies everywhere
+        let file = Config::load_file(&dir.join(LOCAL_CONFIG_NAME)).unwrap();
+        let other = dir.join("other");
+        std::fs::create_dir_all(&other).unwrap();
+        std::fs::write(other.join(LOCAL_CONFIG_NAME), "verify_command = [\"ls\"]\n").unwrap();
+        let config = file.for_dir(&other).unwrap();
+        assert_eq!(config.verify_command(), Some(&["make".to_string()][..]));
+    }
+
+    #[test]
+    fn test_signing_git_config() {
+        assert!(Config::default().signing_git_config().unwrap().is_empty());
+
+        let config: Config = toml::from_str(r#"commit_signing = "disable""#).unwrap();
+        assert_eq!(
+            config.signing_git_config().unwrap(),
+            [
+                ("commit.gpgSign", "false".to_string()),
+                ("tag.gpgSign", "false".to_string())
+            ]
+        );
+
+        let config: Config = toml::from_str(
+            r#"
+            commit_signing = "enforce"
+            signing_format = "ssh"
+            signing_key = "/keys/id_ed25519"
+            "#,
+        )
+        .unwrap();
+        let options = config.signing_git_config().unwrap();
+        assert!(options.contains(&("commit.gpgSign", "true".to_string())));
+        assert!(options.contains(&("gpg.format", "ssh".to_string())));
+        assert!(options.contains(&("user.signingKey", "/keys/id_ed25519".to_string())));
+    }
+
+    #[test]
+    fn test_status_git_config() {
+        assert!(Config::default().status_git_config().is_empty());
+
+        let config: Config = toml::from_str("fsmonitor = true\nuntracked_cache = false").unwrap();
+        assert_eq!(
+            config.status_git_config(),
+            [
+                ("core.fsmonitor", "true".to_string()),
+                ("core.untrackedCache", "false".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_namespace_ends_with_slash() {
+        assert_eq!(Config::default().namespace(), None);
+        for value in ["alice", "alice/", "alice//"] {
+            let config: Config = toml::from_str(&format!("namespace = \"{}\"", value)).unwrap();
+            assert_eq!(config.namespace().as_deref(), Some("alice/"));
+        }
+        let config: Config = toml::from_str(r#"namespace = """#).unwrap();
+        assert_eq!(config.namespace(), None);
+    }
+
+    #[test]
+    fn test_signing_key_expands_home_for_ssh_only() {
+        let home = std::env::var("HOME").unwrap();
+        let config: Config =
+            toml::from_str("signing_format = \"ssh\"\nsigning_key = \"~/.ssh/id\"").unwrap();
+        assert_eq!(
+            config.signing_key().unwrap(),
+            Some(format!("{}/.ssh/id", home))
+        );
+
+        let config: Config = toml::from_str(r#"signing_key = "~ABCD""#).unwrap();
+        assert_eq!(config.signing_key().unwrap(), Some("~ABCD".to_string()));
+    }
 }
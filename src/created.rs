@@ -0,0 +1,184 @@
+//! When, by whom and with which command a silo was created.
+//!
+//! Recorded in the silo's tracking directory by `new` (and agent spawns),
+//! since none of it can be recovered from git afterwards. `list --json`
+//! shows it and `prune --older-than` uses the silo's age.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::metadata::{self, Record};
+use crate::process;
+use crate::storage;
+
+/// Creation metadata of a silo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Created {
+    /// Creation time in seconds since the Unix epoch
+    pub at: u64,
+    /// Login name of the user who created the silo
+    pub by: Option<String>,
+    /// The silo command line that created it
+    pub command: String,
+}
+
+impl Created {
+    /// Creation metadata for a silo created now by this process.
+    pub fn now() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        Created {
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            by: storage::current_user_name(),
+            command: args.join(" "),
+        }
+    }
+
+    /// How long ago the silo was created.
+    pub fn age(&self) -> Duration {
+        let created = UNIX_EPOCH + Duration::from_secs(self.at);
+        SystemTime::now()
+            .duration_since(created)
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+/// Get the path of the file recording a silo's creation.
+pub fn record_path(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("created")
+}
+
+/// Record a silo's creation metadata.
+pub fn record(silo_path: &Path, created: &Created) -> Result<(), String> {
+    let mut record = Record::new();
+    record
+        .set("at", created.at)
+        .set("command", &created.command);
+    if let Some(by) = &created.by {
+        record.set("by", by);
+    }
+    metadata::store(&record_path(silo_path), &record)
+        .map_err(|e| format!("Failed to write creation metadata: {}", e))
+}
+
+/// Load a silo's creation metadata, if it was recorded.
+pub fn load(silo_path: &Path) -> Option<Created> {
+    parse(&metadata::load(&record_path(silo_path))?)
+}
+
+/// Read creation metadata from its record.
+fn parse(record: &Record) -> Option<Created> {
+    Some(Created {
+        at: record.get_parsed("at")?,
+        by: record.get("by").map(str::to_string),
+        command: record.get("command").unwrap_or_default().to_string(),
+    })
+}
+
+/// Parse an age such as "30m", "12h", "7d" or "2w".
+pub fn parse_age(value: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid age '{}': expected a number with a unit (m, h, d or w), e.g. 7d",
+            value
+        )
+    };
+    let Some((split, _)) = value.char_indices().last() else {
+        return Err(invalid());
+    };
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    Ok(Duration::from_secs(number.saturating_mul(unit_secs)))
+}
+
+/// Format an age as its largest whole unit of those [`parse_age`] takes,
+/// e.g. "5m", "3h", "2d" or "3w". Less than a minute is "now".
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => "now".to_string(),
+        60..3_600 => format!("{}m", secs / 60),
+        3_600..86_400 => format!("{}h", secs / 3_600),
+        86_400..604_800 => format!("{}d", secs / 86_400),
+        _ => format!("{}w", secs / 604_800),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(59)), "now");
+        assert_eq!(format_age(Duration::from_secs(5 * 60)), "5m");
+        assert_eq!(format_age(Duration::from_secs(3 * 3_600 + 59 * 60)), "3h");
+        assert_eq!(format_age(Duration::from_secs(2 * 86_400)), "2d");
+        assert_eq!(format_age(Duration::from_secs(22 * 86_400)), "3w");
+        assert_eq!(
+            parse_age(&format_age(Duration::from_secs(14 * 86_400))).unwrap(),
+            Duration::from_secs(14 * 86_400)
+        );
+    }
+
+    #[test]
+    fn test_record_path() {
+        assert_eq!(
+            record_path(Path::new("/var/silo/repo-abc/feature")),
+            PathBuf::from("/var/silo/repo-abc/.tracking/feature/created")
+        );
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let base = tempfile::tempdir().unwrap();
+        let silo = base.path().join("feature");
+        let created = Created {
+            at: 1_700_000_000,
+            by: Some("alice".to_string()),
+            command: "silo new feature".to_string(),
+        };
+
+        record(&silo, &created).unwrap();
+        assert_eq!(load(&silo), Some(created));
+    }
+
+    #[test]
+    fn test_parse_requires_time() {
+        let record = |content| Record::parse(content).unwrap();
+        assert_eq!(parse(&record("command=silo new x\n")), None);
+        let created = parse(&record("at=5\ncommand=silo new x\n")).unwrap();
+        assert_eq!(created.by, None);
+    }
+
+    #[test]
+    fn test_age() {
+        let created = Created {
+            at: 0,
+            by: None,
+            command: String::new(),
+        };
+        assert!(created.age() > Duration::from_secs(365 * 24 * 60 * 60));
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_age("7d"), Ok(Duration::from_secs(7 * 24 * 60 * 60)));
+        assert_eq!(parse_age("2w"), Ok(Duration::from_secs(14 * 24 * 60 * 60)));
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("").is_err());
+        assert!(parse_age("1y").is_err());
+        assert!(parse_age("1é").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! Disk usage of storage roots and silo worktrees, for `metrics
+//! --disk-usage` and `list --du`.
+
+use std::fs::{self, DirEntry};
+use std::path::Path;
+
+/// Total size of the files under `dir`, not following symlinks.
+pub fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries.flatten().map(|entry| entry_size(&entry)).sum()
+}
+
+/// Size of a worktree's own files, such as build artifacts. Its `.git` is
+/// left out: in a silo it points at repository data shared with the main
+/// worktree.
+pub fn worktree_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name() != ".git")
+        .map(|entry| entry_size(&entry))
+        .sum()
+}
+
+/// Size of a file, or of everything under a directory.
+fn entry_size(entry: &DirEntry) -> u64 {
+    match entry.metadata() {
+        Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Format a size in bytes with a binary unit, e.g. "340K" or "1.2G".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || size >= 10.0 {
+        format!("{:.0}{}", size, UNITS[unit])
+    } else {
+        format!("{:.1}{}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worktree_size_leaves_out_git() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "12345").unwrap();
+        fs::create_dir(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join("target/out"), "123").unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git/index"), "1234567890").unwrap();
+
+        assert_eq!(worktree_size(dir.path()), 8);
+        assert_eq!(dir_size(dir.path()), 18);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0B");
+        assert_eq!(format_size(1023), "1023B");
+        assert_eq!(format_size(1536), "1.5K");
+        assert_eq!(format_size(340 * 1024), "340K");
+        assert_eq!(format_size(1288490189), "1.2G");
+    }
+}
@@ -3,6 +3,8 @@
 //! This module provides a unified error type for all silo operations,
 //! replacing the scattered `Result<T, String>` pattern with proper error types.
 
+use std::sync::{Mutex, PoisonError};
+
 use thiserror::Error;
 
 /// Main error type for silo operations.
@@ -36,6 +38,10 @@ pub enum SiloError {
     #[error("Command failed: {0}")]
     Command(String),
 
+    /// A network operation failed in a way that may succeed if retried
+    #[error("Transient failure: {0} (retrying may help, see --retries)")]
+    Transient(String),
+
     /// User aborted the operation
     #[error("Aborted")]
     Aborted,
@@ -48,6 +54,34 @@ pub enum SiloError {
 /// Convenience type alias for Results using SiloError.
 pub type Result<T> = std::result::Result<T, SiloError>;
 
+/// Transient failures raised so far, as displayed. Commands return their
+/// errors as strings with context added, so main can't match on the variant;
+/// it looks for these instead (see [`is_transient`]).
+static TRANSIENT_FAILURES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+impl SiloError {
+    /// A transient failure, remembered so that the exit code still reflects
+    /// it after the error has been turned into a string.
+    pub fn transient(message: String) -> Self {
+        let err = SiloError::Transient(message);
+        TRANSIENT_FAILURES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(err.to_string());
+        err
+    }
+}
+
+/// Whether a command's final error message carries a failure raised with
+/// [`SiloError::transient`]. Text that merely looks like one doesn't count.
+pub fn is_transient(message: &str) -> bool {
+    TRANSIENT_FAILURES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .any(|failure| message.contains(failure.as_str()))
+}
+
 impl From<String> for SiloError {
     fn from(s: String) -> Self {
         SiloError::Other(s)
@@ -99,6 +133,25 @@ mod tests {
         assert!(display.contains("feature"));
     }
 
+    #[test]
+    fn test_transient_display() {
+        let err = SiloError::Transient("Could not resolve host: example.com".to_string());
+        assert_eq!(
+            format!("{}", err),
+            "Transient failure: Could not resolve host: example.com (retrying may help, see --retries)"
+        );
+    }
+
+    #[test]
+    fn test_is_transient_only_for_raised_failures() {
+        let lookalike = "Failed to run hook: Transient failure: printed by a script";
+        assert!(!is_transient(lookalike));
+
+        let err = SiloError::transient("Connection refused".to_string());
+        assert!(is_transient(&format!("Failed to fetch: {}", err)));
+        assert!(!is_transient(lookalike));
+    }
+
     #[test]
     fn test_io_error_from() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
@@ -3,12 +3,30 @@
 //! These codes follow common Unix conventions and provide semantic meaning
 //! for different failure modes.
 
+use std::process::ExitStatus;
+
+/// Success
+pub const SUCCESS: i32 = 0;
+
 /// General error
 pub const ERROR: i32 = 1;
 
 /// Resource not found (silo, repository, etc.)
 pub const NOT_FOUND: i32 = 2;
 
+/// `gc` left something it found in place (dry run, declined, or failed)
+pub const ITEMS_REMAIN: i32 = 3;
+
+/// A network operation failed transiently; running again may succeed
+/// (EX_TEMPFAIL from sysexits.h)
+pub const TRANSIENT: i32 = 75;
+
+/// The code to exit with for a command that finished with `status`:
+/// its exit code, or [`ERROR`] if a signal killed it.
+pub fn status_code(status: ExitStatus) -> i32 {
+    status.code().unwrap_or(ERROR)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -27,4 +45,17 @@ mod tests {
     fn test_error_and_not_found_are_different() {
         assert_ne!(ERROR, NOT_FOUND);
     }
+
+    #[test]
+    fn test_items_remain_is_distinct() {
+        assert_ne!(ITEMS_REMAIN, ERROR);
+        assert_ne!(ITEMS_REMAIN, NOT_FOUND);
+    }
+
+    #[test]
+    fn test_transient_is_distinct() {
+        for code in [ERROR, NOT_FOUND, ITEMS_REMAIN] {
+            assert_ne!(TRANSIENT, code);
+        }
+    }
 }
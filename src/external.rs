@@ -0,0 +1,112 @@
+//! External worktrees: git worktrees outside the storage directory that
+//! were registered with `silo external add`.
+//!
+//! Stored as `{storage}/.external`, one worktree path per line, as git lists
+//! it. Registered worktrees are treated as silos by `cd`, `list` and `exec`,
+//! but silo didn't create them, so `prune` and `rm` leave them alone and
+//! nothing is written to a tracking directory for them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+use crate::metadata;
+
+/// Name of the external worktree list in the storage directory.
+const EXTERNAL_NAME: &str = ".external";
+
+/// Get the path of the external worktree list in a storage directory.
+pub fn external_path(base_dir: &Path) -> PathBuf {
+    base_dir.join(EXTERNAL_NAME)
+}
+
+/// Load the registered external worktrees. A missing list is empty.
+pub fn load(base_dir: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(external_path(base_dir))
+        .map(|content| content.lines().map(PathBuf::from).collect())
+        .unwrap_or_default()
+}
+
+fn save(base_dir: &Path, paths: &[PathBuf]) -> Result<(), String> {
+    fs::create_dir_all(base_dir)
+        .map_err(|e| format!("Failed to create {}: {}", base_dir.display(), e))?;
+    let content: String = paths
+        .iter()
+        .map(|path| format!("{}\n", path.display()))
+        .collect();
+    metadata::write_atomic(&external_path(base_dir), content)
+        .map_err(|e| format!("Failed to write external worktree list: {}", e))
+}
+
+/// Register a worktree, given as `worktree_path` returns it. Returns
+/// whether it was newly added.
+pub fn add(base_dir: &Path, path: &Path) -> Result<bool, String> {
+    let mut paths = load(base_dir);
+    if paths.iter().any(|p| p == path) {
+        return Ok(false);
+    }
+    paths.push(path.to_path_buf());
+    save(base_dir, &paths)?;
+    Ok(true)
+}
+
+/// Unregister an external worktree. The worktree itself is left as is.
+/// Returns whether it was registered.
+pub fn remove(base_dir: &Path, path: &Path) -> Result<bool, String> {
+    let mut paths = load(base_dir);
+    let canonical = path.canonicalize().ok();
+    let before = paths.len();
+    // The worktree may be gone already, so match the path as given too
+    paths.retain(|p| p != path && Some(p) != canonical.as_ref());
+    if paths.len() == before {
+        return Ok(false);
+    }
+    save(base_dir, &paths)?;
+    Ok(true)
+}
+
+/// Check that `path` is a linked worktree and get its path as git lists
+/// it, which is how it's recorded.
+pub fn worktree_path(path: &Path) -> Result<PathBuf, String> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("Cannot access {}: {}", path.display(), e))?;
+    let main = git::get_main_worktree_from_silo(&canonical).ok_or_else(|| {
+        format!(
+            "{} is not a linked git worktree (see `git worktree add`)",
+            path.display()
+        )
+    })?;
+    git::list_worktrees(&main)?
+        .into_iter()
+        .skip(1)
+        .map(|wt| wt.path)
+        .find(|wt| wt.canonicalize().ok().as_ref() == Some(&canonical))
+        .ok_or_else(|| {
+            format!(
+                "{} is not listed by `git worktree list` in {}",
+                path.display(),
+                main.display()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load_and_remove() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().join("silo");
+        let paths = vec![PathBuf::from("/old/a"), PathBuf::from("/old/b")];
+
+        save(&base, &paths).unwrap();
+        assert_eq!(load(&base), paths);
+        assert!(!add(&base, Path::new("/old/b")).unwrap());
+
+        assert!(remove(&base, Path::new("/old/a")).unwrap());
+        assert!(!remove(&base, Path::new("/old/a")).unwrap());
+        assert_eq!(load(&base), vec![PathBuf::from("/old/b")]);
+    }
+}
@@ -1,23 +1,69 @@
 use crate::error::{Result, SiloError};
+use crate::output::Verbosity;
+use crate::timings;
+use serde::Serialize;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use tracing::{debug, warn};
-
-/// Controls whether git operations print their output
-#[derive(Clone, Copy, Default, PartialEq, Eq)]
-pub enum Verbosity {
-    /// Suppress git output (default)
-    #[default]
-    Quiet,
-    /// Print git stdout and stderr
-    Verbose,
-}
+use std::process::{Command, Output};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{debug, trace, warn};
+
+/// Environment variables that point git at a specific repository or index.
+/// They are cleared for commands run in an explicit directory, so a value
+/// inherited from the caller (e.g. a git hook) can't redirect an operation
+/// away from the silo. Everything else, such as GIT_SSH_COMMAND,
+/// GIT_ASKPASS and GIT_CONFIG_*, is passed through unchanged.
+const REPO_ENV_VARS: &[&str] = &[
+    "GIT_DIR",
+    "GIT_WORK_TREE",
+    "GIT_COMMON_DIR",
+    "GIT_INDEX_FILE",
+    "GIT_OBJECT_DIRECTORY",
+    "GIT_ALTERNATE_OBJECT_DIRECTORIES",
+    "GIT_NAMESPACE",
+    "GIT_PREFIX",
+];
+
+/// `key=value` options passed with `-c` to every git command (`git_config`)
+static CONFIG_OPTIONS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// How often to retry git commands that fail transiently (`--retries`)
+static RETRIES: OnceLock<u32> = OnceLock::new();
+
+/// Longest wait between retries, in seconds.
+const MAX_BACKOFF_SECS: u64 = 30;
+
+/// Git error output, lowercased, that points at a flaky network rather than
+/// a real problem. Authentication failures and missing repositories are
+/// deliberately absent: retrying doesn't fix them.
+const TRANSIENT_MARKERS: &[&str] = &[
+    "could not resolve host",
+    "temporary failure in name resolution",
+    "connection timed out",
+    "operation timed out",
+    "connection refused",
+    "couldn't connect to server",
+    "connection reset",
+    "the remote end hung up unexpectedly",
+    "early eof",
+    "rpc failed",
+    "gnutls_handshake() failed",
+    "ssl_connect: ",
+];
+
+/// Git subcommands that are retried after a transient failure (`--retries`).
+/// Others can fail halfway, e.g. `worktree add` after creating the branch,
+/// so running them again would fail for a different reason.
+const RETRY_SAFE_SUBCOMMANDS: &[&str] = &["fetch", "ls-remote"];
 
 #[derive(Debug, Clone)]
 pub struct Worktree {
     pub path: PathBuf,
     pub branch: Option<String>,
+    /// Why the worktree is locked with `git worktree lock`, empty if no
+    /// reason was given; None if it isn't locked
+    pub locked: Option<String>,
 }
 
 impl Worktree {
@@ -42,10 +88,105 @@ pub struct RepoInfo {
     pub name: String,
 }
 
-/// Create a git command with working directory set
-fn git_command(repo_root: &Path) -> Command {
+/// Running git commands with their duration recorded for `--timings`
+trait TimedOutput {
+    fn timed_output(&mut self) -> std::io::Result<Output>;
+    /// Like `timed_output`, with `input` written to the command's stdin.
+    fn timed_output_with_input(&mut self, input: &[u8]) -> std::io::Result<Output>;
+}
+
+impl TimedOutput for Command {
+    fn timed_output(&mut self) -> std::io::Result<Output> {
+        let start = Instant::now();
+        let output = self.output();
+        timings::record_git(start.elapsed());
+        output
+    }
+
+    fn timed_output_with_input(&mut self, input: &[u8]) -> std::io::Result<Output> {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        let start = Instant::now();
+        let mut child = self
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // Written from another thread so a command that fills its output
+        // pipes before reading all input can't deadlock
+        let input = input.to_vec();
+        let writer = std::thread::spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output();
+        let _ = writer.join();
+        timings::record_git(start.elapsed());
+        output
+    }
+}
+
+/// Set the options passed with `-c` to every git command silo runs.
+/// Only the first call has an effect; main calls it once after loading config.
+pub fn set_config_options(options: Vec<String>) {
+    let _ = CONFIG_OPTIONS.set(options);
+}
+
+/// Set how often git commands that fail transiently are retried, with
+/// exponential backoff. Only the first call has an effect; main calls it
+/// once at startup.
+pub fn set_retries(retries: u32) {
+    let _ = RETRIES.set(retries);
+}
+
+/// Whether git's error output points at a failure that may go away when
+/// the command is retried.
+fn is_transient_failure(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    TRANSIENT_MARKERS
+        .iter()
+        .any(|marker| stderr.contains(marker))
+}
+
+/// Whether `cmd` may be run again after a transient failure: it runs one
+/// of [`RETRY_SAFE_SUBCOMMANDS`].
+fn is_retry_safe(cmd: &Command) -> bool {
+    let mut args = cmd.get_args();
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("-c" | "-C") => {
+                args.next();
+            }
+            Some(arg) if arg.starts_with('-') => {}
+            Some(subcommand) => return RETRY_SAFE_SUBCOMMANDS.contains(&subcommand),
+            None => return false,
+        }
+    }
+    false
+}
+
+/// How long to wait before retry number `attempt` (starting at 1).
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs((1u64 << (attempt - 1).min(5)).min(MAX_BACKOFF_SECS))
+}
+
+/// Create a git command with the configured `-c` options
+fn base_command() -> Command {
     let mut cmd = Command::new("git");
+    for option in CONFIG_OPTIONS.get().into_iter().flatten() {
+        cmd.arg("-c").arg(option);
+    }
+    cmd
+}
+
+/// Create a git command with working directory set.
+/// Running in the worktree itself (rather than via GIT_DIR) lets git resolve
+/// conditional includes (`includeIf "gitdir:..."`, `onbranch:`) for it.
+fn git_command(repo_root: &Path) -> Command {
+    let mut cmd = base_command();
     cmd.current_dir(repo_root);
+    for var in REPO_ENV_VARS {
+        cmd.env_remove(var);
+    }
     cmd
 }
 
@@ -58,8 +199,20 @@ fn format_args(cmd: &Command) -> String {
 }
 
 /// Run a git command and return stdout on success, or formatted error on failure.
-/// If `verbosity` is `Verbose`, prints stdout and stderr.
-fn run_git(mut cmd: Command, error_context: &str, verbosity: Verbosity) -> Result<String> {
+/// Read-only queries pass `Verbosity::Quiet`; commands that change things pass
+/// the user's level, and print git's stdout and stderr unless it is quiet.
+/// Git's stderr is always logged at trace level (`-vv`).
+fn run_git(cmd: Command, error_context: &str, verbosity: Verbosity) -> Result<String> {
+    run_git_with_input(cmd, None, error_context, verbosity)
+}
+
+/// Like `run_git`, with `input` (if any) written to git's stdin.
+fn run_git_with_input(
+    mut cmd: Command,
+    input: Option<&[u8]>,
+    error_context: &str,
+    verbosity: Verbosity,
+) -> Result<String> {
     let cwd = cmd
         .get_current_dir()
         .map(Path::to_string_lossy)
@@ -67,11 +220,41 @@ fn run_git(mut cmd: Command, error_context: &str, verbosity: Verbosity) -> Resul
     let args = format_args(&cmd);
     debug!(cwd = %cwd, "git {}", args);
 
-    let output = cmd.output()?;
+    let retries = if is_retry_safe(&cmd) {
+        RETRIES.get().copied().unwrap_or(0)
+    } else {
+        0
+    };
+    let mut attempt = 0;
+    let output = loop {
+        let output = match input {
+            Some(input) => cmd.timed_output_with_input(input)?,
+            None => cmd.timed_output()?,
+        };
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if output.status.success() || attempt >= retries || !is_transient_failure(&stderr) {
+            break output;
+        }
+        attempt += 1;
+        let delay = backoff(attempt);
+        eprintln!(
+            "Warning: {}: {} (retry {} of {} in {}s)",
+            error_context,
+            stderr.trim(),
+            attempt,
+            retries,
+            delay.as_secs()
+        );
+        std::thread::sleep(delay);
+    };
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
 
-    if verbosity == Verbosity::Verbose {
+    if !stderr.trim().is_empty() {
+        trace!(stderr = %stderr.trim(), "git {}", args);
+    }
+
+    if verbosity.shows_git_output() {
         // Print stdout if non-empty
         if !stdout.trim().is_empty() {
             print!("{}", stdout);
@@ -88,11 +271,12 @@ fn run_git(mut cmd: Command, error_context: &str, verbosity: Verbosity) -> Resul
             stderr = %stderr.trim(),
             "{}", error_context
         );
-        return Err(SiloError::Git(format!(
-            "{}: {}",
-            error_context,
-            stderr.trim()
-        )));
+        let message = format!("{}: {}", error_context, stderr.trim());
+        return Err(if is_transient_failure(&stderr) {
+            SiloError::transient(message)
+        } else {
+            SiloError::Git(message)
+        });
     }
 
     Ok(stdout.into_owned())
@@ -108,11 +292,14 @@ fn run_git_interactive(mut cmd: Command, error_context: &str) -> Result<()> {
     let args = format_args(&cmd);
     debug!(cwd = %cwd, "git {}", args);
 
+    let start = Instant::now();
     let status = cmd
         .stdin(std::process::Stdio::inherit())
         .stdout(std::process::Stdio::inherit())
         .stderr(std::process::Stdio::inherit())
-        .status()?;
+        .status();
+    timings::record_git(start.elapsed());
+    let status = status?;
 
     if !status.success() {
         warn!(exit_code = ?status.code(), "{}", error_context);
@@ -140,9 +327,9 @@ pub fn get_repo_root() -> Result<PathBuf> {
 /// Returns None if not in a git repository
 pub fn try_get_repo_root() -> Option<PathBuf> {
     debug!("git rev-parse --show-toplevel");
-    let output = Command::new("git")
+    let output = base_command()
         .args(["rev-parse", "--show-toplevel"])
-        .output()
+        .timed_output()
         .ok()?;
 
     if !output.status.success() {
@@ -159,9 +346,9 @@ pub fn try_get_repo_root() -> Option<PathBuf> {
 pub fn get_repo_name(repo_root: Option<&Path>) -> Result<String> {
     // Try to get from origin URL first
     debug!("git remote get-url origin");
-    let output = Command::new("git")
+    let output = base_command()
         .args(["remote", "get-url", "origin"])
-        .output()?;
+        .timed_output()?;
 
     if output.status.success() {
         let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -187,6 +374,20 @@ pub fn get_repo_name(repo_root: Option<&Path>) -> Result<String> {
     })
 }
 
+/// Get the URL of a repository's origin remote, if it has one
+#[must_use]
+pub fn get_origin_url(repo_root: &Path) -> Option<String> {
+    let output = git_command(repo_root)
+        .args(["remote", "get-url", "origin"])
+        .timed_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!url.is_empty()).then_some(url)
+}
+
 fn extract_repo_name_from_url(url: &str) -> Option<String> {
     // Handle URLs like:
     // git@github.com:user/repo.git
@@ -202,29 +403,174 @@ fn extract_repo_name_from_url(url: &str) -> Option<String> {
 }
 
 /// Create a new worktree with a new branch
+/// Create a worktree with a new branch, starting at `upstream` (e.g.
+/// "origin/feature") and tracking it if given, else at HEAD
 pub fn create_worktree(
     path: &Path,
     branch: &str,
+    upstream: Option<&str>,
     repo_root: &Path,
     verbosity: Verbosity,
 ) -> Result<()> {
     let mut cmd = git_command(repo_root);
-    cmd.args(["worktree", "add", "-b", branch]).arg(path);
+    cmd.args(["worktree", "add"]);
+    if upstream.is_some() {
+        cmd.arg("--track");
+    }
+    cmd.args(["-b", branch]).arg(path);
+    cmd.args(upstream);
+    run_git(cmd, "Failed to create worktree", verbosity)?;
+    Ok(())
+}
+
+/// Create a worktree with a detached HEAD at the main worktree's HEAD
+pub fn create_detached_worktree(path: &Path, repo_root: &Path, verbosity: Verbosity) -> Result<()> {
+    let mut cmd = git_command(repo_root);
+    cmd.args(["worktree", "add", "--detach"]).arg(path);
     run_git(cmd, "Failed to create worktree", verbosity)?;
     Ok(())
 }
 
+/// Move a worktree to `to` (`git worktree move`)
+pub fn move_worktree(repo_root: &Path, from: &Path, to: &Path, verbosity: Verbosity) -> Result<()> {
+    let mut cmd = git_command(repo_root);
+    cmd.args(["worktree", "move"]).arg(from).arg(to);
+    run_git(cmd, "Failed to move worktree", verbosity)?;
+    Ok(())
+}
+
+/// Create `branch` at a worktree's HEAD and check it out there
+pub fn switch_new_branch(worktree_path: &Path, branch: &str, verbosity: Verbosity) -> Result<()> {
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["switch", "-c", branch]);
+    run_git(cmd, "Failed to create branch", verbosity)?;
+    Ok(())
+}
+
+/// A branch of a remote, from its remote-tracking ref
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteBranch {
+    pub remote: String,
+    pub branch: String,
+}
+
+impl RemoteBranch {
+    /// The remote-tracking ref's short name, e.g. "origin/feature"
+    pub fn short_name(&self) -> String {
+        format!("{}/{}", self.remote, self.branch)
+    }
+}
+
+/// List the branches of all remotes, as last fetched. Remote names are
+/// taken to be the first path component, as they rarely contain slashes.
+#[must_use]
+pub fn list_remote_branches(repo_root: &Path) -> Vec<RemoteBranch> {
+    list_refs(repo_root, "refs/remotes")
+        .iter()
+        .filter_map(|name| {
+            let (remote, branch) = name.split_once('/')?;
+            // refs/remotes/origin/HEAD points at another branch
+            (branch != "HEAD").then(|| RemoteBranch {
+                remote: remote.to_string(),
+                branch: branch.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// List local branch names
+#[must_use]
+pub fn list_local_branches(repo_root: &Path) -> Vec<String> {
+    list_refs(repo_root, "refs/heads")
+}
+
+/// A local branch with its upstream and the time of its last commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalBranch {
+    pub name: String,
+    /// Upstream ref, e.g. "refs/remotes/origin/feature"; None without one
+    pub upstream: Option<String>,
+    /// Commit time of the branch tip, in seconds since the Unix epoch
+    pub committed_at: Option<u64>,
+}
+
+/// List local branches with their upstreams and last commit times
+#[must_use]
+pub fn list_local_branch_details(repo_root: &Path) -> Vec<LocalBranch> {
+    let mut cmd = git_command(repo_root);
+    cmd.args([
+        "for-each-ref",
+        "--format=%(refname:strip=2)%09%(upstream)%09%(committerdate:unix)",
+        "refs/heads",
+    ]);
+    run_git(cmd, "Failed to list branches", Verbosity::Quiet)
+        .map(|output| parse_branch_details(&output))
+        .unwrap_or_default()
+}
+
+/// Parse `name<TAB>upstream<TAB>time` lines from `git for-each-ref`
+fn parse_branch_details(output: &str) -> Vec<LocalBranch> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let name = fields.next().filter(|name| !name.is_empty())?;
+            let upstream = fields.next().filter(|upstream| !upstream.is_empty());
+            Some(LocalBranch {
+                name: name.to_string(),
+                upstream: upstream.map(str::to_string),
+                committed_at: fields.next().and_then(|time| time.parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// Names of the refs under `prefix`, with the prefix stripped
+fn list_refs(repo_root: &Path, prefix: &str) -> Vec<String> {
+    let mut cmd = git_command(repo_root);
+    cmd.args(["for-each-ref", "--format=%(refname)", prefix]);
+    let Ok(output) = run_git(cmd, "Failed to list refs", Verbosity::Quiet) else {
+        return Vec::new();
+    };
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix(prefix)?.strip_prefix('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The remote branch a new local `branch` should start from: the one of
+/// that name on origin, or on the only remote that has it
+#[must_use]
+pub fn find_upstream(repo_root: &Path, branch: &str) -> Option<RemoteBranch> {
+    let matches: Vec<RemoteBranch> = list_remote_branches(repo_root)
+        .into_iter()
+        .filter(|rb| rb.branch == branch)
+        .collect();
+    match matches.iter().find(|rb| rb.remote == "origin") {
+        Some(origin) => Some(origin.clone()),
+        None if matches.len() == 1 => matches.into_iter().next(),
+        None => None,
+    }
+}
+
 /// Remove a worktree
-/// If force is true, removes even if there are uncommitted changes
+/// If force is true, removes even if there are uncommitted changes.
+/// If locked is true, also removes a worktree locked with `git worktree lock`,
+/// which git only does with --force given twice.
 pub fn remove_worktree(
     path: &Path,
     repo_root: &Path,
     force: bool,
+    locked: bool,
     verbosity: Verbosity,
 ) -> Result<()> {
     let mut cmd = git_command(repo_root);
     cmd.args(["worktree", "remove"]);
-    if force {
+    if force || locked {
+        cmd.arg("--force");
+    }
+    if locked {
         cmd.arg("--force");
     }
     cmd.arg(path);
@@ -246,25 +592,62 @@ pub fn list_worktrees(repo_root: &Path) -> Result<Vec<Worktree>> {
 /// and behind is commits in base not in branch
 #[must_use]
 pub fn get_ahead_behind(worktree_path: &Path, branch: &str, base_branch: &str) -> (u32, u32) {
+    count_ahead_behind(worktree_path, base_branch, branch).unwrap_or((0, 0))
+}
+
+/// Count commits in `branch` not in `base`, and in `base` not in `branch`.
+fn count_ahead_behind(worktree_path: &Path, base: &str, branch: &str) -> Option<(u32, u32)> {
     let output = git_command(worktree_path)
         .args(["rev-list", "--left-right", "--count"])
-        .arg(format!("{}...{}", base_branch, branch))
-        .output();
+        .arg(format!("{}...{}", base, branch))
+        .timed_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (behind, ahead) = stdout.trim().split_once('\t')?;
+    Some((ahead.parse().unwrap_or(0), behind.parse().unwrap_or(0)))
+}
 
-    match output {
-        Ok(out) if out.status.success() => {
-            let stdout = String::from_utf8_lossy(&out.stdout);
-            let parts: Vec<&str> = stdout.trim().split('\t').collect();
-            if parts.len() == 2 {
-                let behind = parts[0].parse().unwrap_or(0);
-                let ahead = parts[1].parse().unwrap_or(0);
-                (ahead, behind)
-            } else {
-                (0, 0)
-            }
-        }
-        _ => (0, 0),
+/// How a branch compares to the branch it's measured against: the main
+/// branch or its upstream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Comparison {
+    /// The branch compared against, e.g. "main" or "origin/feature"
+    pub branch: String,
+    /// Commits the branch has that the other doesn't
+    pub ahead: u32,
+    /// Commits the other branch has that the branch doesn't
+    pub behind: u32,
+    /// The best common ancestor of both; None if they share no history
+    pub merge_base: Option<String>,
+}
+
+/// Compare `branch` to `base_branch`.
+#[must_use]
+pub fn compare_branches(worktree_path: &Path, branch: &str, base_branch: &str) -> Comparison {
+    let (ahead, behind) = get_ahead_behind(worktree_path, branch, base_branch);
+    Comparison {
+        branch: base_branch.to_string(),
+        ahead,
+        behind,
+        merge_base: get_merge_base(worktree_path, branch, base_branch),
+    }
+}
+
+/// The best common ancestor of two commits, as a full SHA.
+#[must_use]
+pub fn get_merge_base(worktree_path: &Path, a: &str, b: &str) -> Option<String> {
+    let output = git_command(worktree_path)
+        .args(["merge-base", a, b])
+        .timed_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
     }
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!sha.is_empty()).then_some(sha)
 }
 
 /// Get the total lines added and removed between two branches
@@ -274,7 +657,7 @@ pub fn get_diff_stats(worktree_path: &Path, branch: &str, base_branch: &str) ->
     let output = git_command(worktree_path)
         .args(["diff", "--numstat"])
         .arg(format!("{}...{}", base_branch, branch))
-        .output();
+        .timed_output();
 
     match output {
         Ok(out) if out.status.success() => {
@@ -298,7 +681,9 @@ pub fn get_diff_stats(worktree_path: &Path, branch: &str, base_branch: &str) ->
 /// Check if a worktree has no uncommitted changes
 #[must_use]
 pub fn is_worktree_clean(path: &Path) -> bool {
-    let output = git_command(path).args(["status", "--porcelain"]).output();
+    let output = git_command(path)
+        .args(["status", "--porcelain"])
+        .timed_output();
 
     match output {
         Ok(out) if out.status.success() => {
@@ -308,6 +693,77 @@ pub fn is_worktree_clean(path: &Path) -> bool {
     }
 }
 
+/// A git operation that stopped partway in a worktree, e.g. on conflicts,
+/// and needs someone to continue or abort it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InProgress {
+    Rebase,
+    Merge,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl InProgress {
+    /// Badge shown by `list`, e.g. "REBASING"
+    pub fn badge(self) -> &'static str {
+        match self {
+            InProgress::Rebase => "REBASING",
+            InProgress::Merge => "MERGING",
+            InProgress::CherryPick => "CHERRY-PICKING",
+            InProgress::Revert => "REVERTING",
+            InProgress::Bisect => "BISECTING",
+        }
+    }
+
+    /// Arguments that abort the operation, e.g. ["rebase", "--abort"]
+    pub fn abort_args(self) -> [&'static str; 2] {
+        match self {
+            InProgress::Bisect => ["bisect", "reset"],
+            _ => [self.command(), "--abort"],
+        }
+    }
+
+    /// Name of the git command, e.g. "cherry-pick"
+    pub fn command(self) -> &'static str {
+        match self {
+            InProgress::Rebase => "rebase",
+            InProgress::Merge => "merge",
+            InProgress::CherryPick => "cherry-pick",
+            InProgress::Revert => "revert",
+            InProgress::Bisect => "bisect",
+        }
+    }
+}
+
+/// Detect a rebase, merge, cherry-pick, revert or bisect in progress in a
+/// worktree from the state files git leaves in its git directory. Reads the
+/// files directly instead of running git, so it's cheap enough for `list`.
+pub fn get_in_progress(worktree: &Path) -> Option<InProgress> {
+    let dot_git = worktree.join(".git");
+    let git_dir = if dot_git.is_dir() {
+        dot_git
+    } else {
+        // Relative gitdirs are relative to the worktree
+        worktree.join(get_gitdir_from_silo(worktree)?)
+    };
+    in_progress_in(&git_dir)
+}
+
+fn in_progress_in(git_dir: &Path) -> Option<InProgress> {
+    [
+        ("rebase-merge", InProgress::Rebase),
+        ("rebase-apply", InProgress::Rebase),
+        ("MERGE_HEAD", InProgress::Merge),
+        ("CHERRY_PICK_HEAD", InProgress::CherryPick),
+        ("REVERT_HEAD", InProgress::Revert),
+        ("BISECT_LOG", InProgress::Bisect),
+    ]
+    .into_iter()
+    .find(|(file, _)| git_dir.join(file).exists())
+    .map(|(_, state)| state)
+}
+
 /// Stats about uncommitted changes in a worktree
 #[derive(Debug, Default, Clone, Copy)]
 pub struct UncommittedStats {
@@ -329,10 +785,25 @@ impl UncommittedStats {
     }
 }
 
-/// Get stats about uncommitted changes in a worktree
+/// Get stats about uncommitted changes in a worktree. Untracked files are
+/// counted one by one, as in [`get_worktree_status`], not by directory.
 #[must_use]
 pub fn get_uncommitted_stats(path: &Path) -> UncommittedStats {
-    let output = git_command(path).args(["status", "--porcelain"]).output();
+    uncommitted_stats_with(path, "--untracked-files=all")
+}
+
+/// Get stats about staged and modified tracked files in a worktree. Untracked
+/// files aren't looked for (and counted as 0), which in a large worktree is
+/// most of the cost of `git status`.
+#[must_use]
+pub fn get_tracked_stats(path: &Path) -> UncommittedStats {
+    uncommitted_stats_with(path, "--untracked-files=no")
+}
+
+fn uncommitted_stats_with(path: &Path, untracked_files: &str) -> UncommittedStats {
+    let output = git_command(path)
+        .args(["status", "--porcelain", untracked_files])
+        .timed_output();
 
     let mut stats = UncommittedStats::default();
 
@@ -371,32 +842,144 @@ pub fn get_uncommitted_stats(path: &Path) -> UncommittedStats {
 /// Get list of uncommitted file names in a worktree
 #[must_use]
 pub fn get_uncommitted_files(path: &Path) -> Vec<String> {
-    let output = git_command(path).args(["status", "--porcelain"]).output();
+    get_changed_files_with(path, "--untracked-files=normal")
+        .into_iter()
+        .map(|file| file.path)
+        .collect()
+}
+
+/// A file with uncommitted changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedFile {
+    pub path: String,
+    pub untracked: bool,
+}
 
-    let mut files = Vec::new();
+fn get_changed_files_with(path: &Path, untracked_files: &str) -> Vec<ChangedFile> {
+    let output = git_command(path)
+        .args(["status", "--porcelain", untracked_files])
+        .timed_output();
 
     let Ok(out) = output else {
-        return files;
+        return Vec::new();
     };
     if !out.status.success() {
-        return files;
+        return Vec::new();
     }
 
-    let stdout = String::from_utf8_lossy(&out.stdout);
-    for line in stdout.lines() {
-        // Porcelain format: XY filename
-        // where X is index status, Y is worktree status
-        if line.len() < 3 {
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(parse_status_line)
+        .collect()
+}
+
+/// Parse a porcelain status line: `XY filename`, where X is the index
+/// status and Y the worktree status.
+fn parse_status_line(line: &str) -> Option<ChangedFile> {
+    let filename = line.get(3..).filter(|name| !name.is_empty())?;
+    // Handle renamed files (old -> new format)
+    let filename = filename.split(" -> ").last().unwrap_or(filename);
+    Some(ChangedFile {
+        path: filename.to_string(),
+        untracked: line.starts_with("??"),
+    })
+}
+
+/// What `list` needs from a worktree's `git status`
+#[derive(Debug, Default)]
+pub struct WorktreeStatus {
+    /// (ahead, behind) against the branch's upstream; None without one
+    pub upstream: Option<(u32, u32)>,
+    /// The upstream's name, e.g. "origin/feature"
+    pub upstream_branch: Option<String>,
+    /// Untracked files are counted one by one, as listed in `files`
+    pub uncommitted: UncommittedStats,
+    pub files: Vec<ChangedFile>,
+}
+
+impl WorktreeStatus {
+    /// How `branch` compares to its upstream, reusing the counts from
+    /// `git status`; None without an upstream.
+    #[must_use]
+    pub fn upstream_comparison(&self, worktree_path: &Path, branch: &str) -> Option<Comparison> {
+        let upstream = self.upstream_branch.as_deref()?;
+        let (ahead, behind) = self.upstream?;
+        Some(Comparison {
+            branch: upstream.to_string(),
+            ahead,
+            behind,
+            merge_base: get_merge_base(worktree_path, branch, upstream),
+        })
+    }
+}
+
+/// Get a worktree's changes and upstream ahead/behind from a single
+/// `git status --porcelain=v2 --branch` call, listing each untracked file.
+/// Ignored files are left out.
+#[must_use]
+pub fn get_worktree_status(path: &Path) -> WorktreeStatus {
+    let output = git_command(path)
+        .args([
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "--untracked-files=all",
+        ])
+        .timed_output();
+    match output {
+        Ok(out) if out.status.success() => parse_status_v2(&String::from_utf8_lossy(&out.stdout)),
+        _ => WorktreeStatus::default(),
+    }
+}
+
+/// Parse `git status --porcelain=v2 --branch` output.
+fn parse_status_v2(output: &str) -> WorktreeStatus {
+    let mut status = WorktreeStatus::default();
+    for line in output.lines() {
+        if let Some(upstream) = line.strip_prefix("# branch.upstream ") {
+            status.upstream_branch = Some(upstream.to_string());
+            continue;
+        }
+        if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            status.upstream = ab.split_once(' ').and_then(|(ahead, behind)| {
+                Some((
+                    ahead.strip_prefix('+')?.parse().ok()?,
+                    behind.strip_prefix('-')?.parse().ok()?,
+                ))
+            });
             continue;
         }
-        // Skip the status chars and space
-        let filename = &line[3..];
-        // Handle renamed files (old -> new format)
-        let filename = filename.split(" -> ").last().unwrap_or(filename);
-        files.push(filename.to_string());
-    }
 
-    files
+        // Entries are `1 XY <6 fields> path`, `2 XY <7 fields> path\torig`
+        // (renames), `u XY <8 fields> path` (unmerged) and `? path`
+        let (fields, untracked) = match line.split_once(' ') {
+            Some(("1", _)) => (9, false),
+            Some(("2", _)) => (10, false),
+            Some(("u", _)) => (11, false),
+            Some(("?", _)) => (2, true),
+            _ => continue,
+        };
+        let Some(path) = line.splitn(fields, ' ').nth(fields - 1) else {
+            continue;
+        };
+        let path = path.split('\t').next().unwrap_or(path);
+
+        if untracked {
+            status.uncommitted.untracked += 1;
+        } else {
+            let xy = line.as_bytes().get(2..4).unwrap_or(b"..");
+            if xy[0] != b'.' {
+                status.uncommitted.staged += 1;
+            } else if xy[1] != b'.' {
+                status.uncommitted.modified += 1;
+            }
+        }
+        status.files.push(ChangedFile {
+            path: path.to_string(),
+            untracked,
+        });
+    }
+    status
 }
 
 /// Check if a branch has been merged into the main branch
@@ -405,7 +988,7 @@ pub fn get_uncommitted_files(path: &Path) -> Vec<String> {
 pub fn is_branch_merged(repo_root: &Path, branch: &str, main_branch: &str) -> bool {
     let output = git_command(repo_root)
         .args(["merge-base", "--is-ancestor", branch, main_branch])
-        .output();
+        .timed_output();
 
     match output {
         Ok(out) => out.status.success(),
@@ -413,6 +996,99 @@ pub fn is_branch_merged(repo_root: &Path, branch: &str, main_branch: &str) -> bo
     }
 }
 
+/// Count commits on a branch's upstream that are in neither the branch nor
+/// the main branch, i.e. work that only exists on the remote.
+/// Returns 0 if the branch has no upstream.
+#[must_use]
+pub fn count_upstream_only_commits(repo_root: &Path, branch: &str, main_branch: &str) -> u32 {
+    let output = git_command(repo_root)
+        .args(["rev-list", "--count"])
+        .arg(format!("{}@{{upstream}}", branch))
+        .arg(format!("^{}", branch))
+        .arg(format!("^{}", main_branch))
+        .timed_output();
+
+    match output {
+        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout)
+            .trim()
+            .parse()
+            .unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Predict which files would conflict when merging `branch` into `base`,
+/// without touching any worktree (`git merge-tree --write-tree`, git 2.38+).
+/// Returns None if the check could not be run.
+#[must_use]
+pub fn merge_tree_conflicts(repo_root: &Path, base: &str, branch: &str) -> Option<Vec<String>> {
+    let output = git_command(repo_root)
+        .args(["merge-tree", "--write-tree", "--name-only", "--no-messages"])
+        .args([base, branch])
+        .timed_output()
+        .ok()?;
+
+    // Exit code 1 means the merge has conflicts; anything else is an error
+    match output.status.code() {
+        Some(0) => Some(Vec::new()),
+        Some(1) => Some(parse_merge_tree_conflicts(&String::from_utf8_lossy(
+            &output.stdout,
+        ))),
+        _ => None,
+    }
+}
+
+/// Parse the conflicted files from `git merge-tree --name-only` output: the
+/// tree id on the first line, then one conflicted path per line
+fn parse_merge_tree_conflicts(output: &str) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for line in output.lines().skip(1).take_while(|line| !line.is_empty()) {
+        if !files.iter().any(|file| file == line) {
+            files.push(line.to_string());
+        }
+    }
+    files
+}
+
+/// Count the commits in a worktree's HEAD that no remote-tracking branch
+/// contains. Returns None if git can't open the worktree's repository data.
+#[must_use]
+pub fn count_unpushed_commits(worktree_path: &Path) -> Option<u32> {
+    let output = git_command(worktree_path)
+        .args(["rev-list", "--count", "HEAD", "--not", "--remotes"])
+        .timed_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Save the commits in a worktree's HEAD that no remote-tracking branch
+/// contains to a bundle file, restorable with `git clone` or `git fetch`.
+pub fn create_unpushed_bundle(
+    worktree_path: &Path,
+    bundle_path: &Path,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["bundle", "create"])
+        .arg(bundle_path)
+        .args(["HEAD", "--not", "--remotes"]);
+    run_git(cmd, "Failed to create bundle", verbosity)?;
+    Ok(())
+}
+
+/// Check if a local branch exists
+#[must_use]
+pub fn branch_exists(repo_root: &Path, branch: &str) -> bool {
+    git_command(repo_root)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("refs/heads/{}", branch))
+        .timed_output()
+        .is_ok_and(|out| out.status.success())
+}
+
 /// Delete a branch
 pub fn delete_branch(repo_root: &Path, branch: &str, verbosity: Verbosity) -> Result<()> {
     let mut cmd = git_command(repo_root);
@@ -421,18 +1097,240 @@ pub fn delete_branch(repo_root: &Path, branch: &str, verbosity: Verbosity) -> Re
     Ok(())
 }
 
+/// Stash a worktree's uncommitted changes, including untracked files,
+/// except for the `excluded` paths.
+/// Returns false if there was nothing to stash (no stash entry was created).
+pub fn stash_push(
+    worktree_path: &Path,
+    message: &str,
+    excluded: &[&str],
+    verbosity: Verbosity,
+) -> Result<bool> {
+    let before = stash_head(worktree_path);
+
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["stash", "push", "--include-untracked", "-m", message, "--"]);
+    for path in excluded {
+        cmd.arg(format!(":(exclude){}", path));
+    }
+    run_git(cmd, "Failed to stash changes", verbosity)?;
+
+    let after = stash_head(worktree_path);
+    Ok(after.is_some() && after != before)
+}
+
+/// Get the commit of the most recent stash entry, if any
+fn stash_head(worktree_path: &Path) -> Option<String> {
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["rev-parse", "--quiet", "--verify", "refs/stash"]);
+    run_git(cmd, "Failed to read stash", Verbosity::Quiet)
+        .ok()
+        .map(|out| out.trim().to_string())
+}
+
+/// Apply the most recent stash to a worktree (restoring what was staged)
+/// and drop it. The stash is kept if it doesn't apply cleanly.
+pub fn stash_pop(worktree_path: &Path, verbosity: Verbosity) -> Result<()> {
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["stash", "pop", "--index"]);
+    run_git(cmd, "Failed to apply stashed changes", verbosity)?;
+    Ok(())
+}
+
+/// A remote that lazily supplies objects missing from a partial clone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromisorRemote {
+    pub name: String,
+    /// The clone's object filter, e.g. "blob:none"
+    pub filter: Option<String>,
+}
+
+/// Get the promisor remotes of a partial clone (`git clone --filter=...`).
+/// Empty for a full clone.
+#[must_use]
+pub fn promisor_remotes(repo_root: &Path) -> Vec<PromisorRemote> {
+    let Ok(output) = git_command(repo_root)
+        .args([
+            "config",
+            "--get-regexp",
+            r"^remote\..*\.(promisor|partialclonefilter)$",
+        ])
+        .timed_output()
+    else {
+        return Vec::new();
+    };
+    parse_promisor_remotes(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_promisor_remotes(config: &str) -> Vec<PromisorRemote> {
+    let mut remotes: Vec<PromisorRemote> = Vec::new();
+    let mut filters = Vec::new();
+    for line in config.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some(key) = key.strip_prefix("remote.") else {
+            continue;
+        };
+        if let Some(name) = key.strip_suffix(".promisor") {
+            if value == "true" {
+                remotes.push(PromisorRemote {
+                    name: name.to_string(),
+                    filter: None,
+                });
+            }
+        } else if let Some(name) = key.strip_suffix(".partialclonefilter") {
+            filters.push((name.to_string(), value.to_string()));
+        }
+    }
+    for (name, filter) in filters {
+        if let Some(remote) = remotes.iter_mut().find(|r| r.name == name) {
+            remote.filter = Some(filter);
+        }
+    }
+    remotes
+}
+
+/// Get pathspecs for what a cone-mode sparse checkout includes: the
+/// directories `git sparse-checkout list` shows plus the files at the top
+/// level, which cone mode always includes. None if the worktree isn't a
+/// cone-mode sparse checkout.
+#[must_use]
+pub fn sparse_checkout_paths(worktree_path: &Path) -> Option<Vec<String>> {
+    let is_set = |key: &str| {
+        git_command(worktree_path)
+            .args(["config", "--bool", key])
+            .timed_output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "true")
+    };
+    if !is_set("core.sparseCheckout") || !is_set("core.sparseCheckoutCone") {
+        return None;
+    }
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["sparse-checkout", "list"]);
+    let output = run_git(
+        cmd,
+        "Failed to list sparse-checkout paths",
+        Verbosity::Quiet,
+    )
+    .ok()?;
+    Some(
+        std::iter::once(":(glob)*".to_string())
+            .chain(output.lines().map(String::from))
+            .collect(),
+    )
+}
+
+/// Get the blobs of `commit` under `paths` (all when empty) that are missing
+/// from a partial clone.
+pub fn missing_blobs(repo_root: &Path, commit: &str, paths: &[String]) -> Result<Vec<String>> {
+    let mut cmd = git_command(repo_root);
+    cmd.args(["rev-list", "--objects", "--missing=print", commit, "--"])
+        .args(paths);
+    let output = run_git(cmd, "Failed to list missing objects", Verbosity::Quiet)?;
+    Ok(output
+        .lines()
+        .filter_map(|line| line.strip_prefix('?'))
+        .map(String::from)
+        .collect())
+}
+
+/// Fetch objects from a promisor remote in one batch, the way git fetches
+/// them on demand, instead of one request per missing object.
+pub fn fetch_objects(
+    repo_root: &Path,
+    remote: &str,
+    oids: &[String],
+    verbosity: Verbosity,
+) -> Result<()> {
+    let mut cmd = git_command(repo_root);
+    cmd.args([
+        "-c",
+        "fetch.negotiationAlgorithm=noop",
+        "fetch",
+        remote,
+        "--no-tags",
+        "--no-write-fetch-head",
+        "--recurse-submodules=no",
+        "--filter=blob:none",
+        "--stdin",
+    ]);
+    let input: String = oids.iter().map(|oid| format!("{}\n", oid)).collect();
+    run_git_with_input(
+        cmd,
+        Some(input.as_bytes()),
+        "Failed to fetch missing objects",
+        verbosity,
+    )?;
+    Ok(())
+}
+
+/// Whether a repository is registered for background maintenance
+/// (`maintenance.repo` in the global git config).
+#[must_use]
+pub fn is_maintenance_registered(repo_root: &Path) -> bool {
+    let Ok(output) = git_command(repo_root)
+        .args(["config", "--global", "--get-all", "maintenance.repo"])
+        .timed_output()
+    else {
+        return false;
+    };
+    let canonical = repo_root.canonicalize().ok();
+    String::from_utf8_lossy(&output.stdout).lines().any(|line| {
+        let path = Path::new(line);
+        path == repo_root || path.canonicalize().ok() == canonical
+    })
+}
+
+/// Register a repository for background maintenance (commit-graph,
+/// prefetch, ...) and schedule it with the system scheduler.
+pub fn start_maintenance(repo_root: &Path, verbosity: Verbosity) -> Result<()> {
+    let mut cmd = git_command(repo_root);
+    cmd.args(["maintenance", "start"]);
+    run_git(cmd, "Failed to start git maintenance", verbosity)?;
+    Ok(())
+}
+
+/// Options passed through to `git rebase`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RebaseOptions {
+    /// Move branches pointing into the rebased commits along with them
+    pub update_refs: bool,
+    /// Recreate merge commits instead of flattening them
+    pub rebase_merges: bool,
+}
+
+impl RebaseOptions {
+    /// The `git rebase` flags for these options
+    #[must_use]
+    pub fn args(&self) -> Vec<&'static str> {
+        let mut args = Vec::new();
+        if self.update_refs {
+            args.push("--update-refs");
+        }
+        if self.rebase_merges {
+            args.push("--rebase-merges");
+        }
+        args
+    }
+}
+
 /// Rebase the current branch onto another branch (quiet mode)
-pub fn rebase_onto(worktree_path: &Path, base_branch: &str) -> Result<()> {
+pub fn rebase_onto(worktree_path: &Path, base_branch: &str, options: RebaseOptions) -> Result<()> {
     let mut cmd = git_command(worktree_path);
-    cmd.args(["rebase", base_branch]);
+    cmd.arg("rebase").args(options.args()).arg(base_branch);
     run_git(cmd, "Failed to rebase", Verbosity::Quiet)?;
     Ok(())
 }
 
 /// Rebase the current branch onto another branch with interactive output
-pub fn rebase_onto_interactive(worktree_path: &Path, base_branch: &str) -> Result<()> {
+pub fn rebase_onto_interactive(
+    worktree_path: &Path,
+    base_branch: &str,
+    options: RebaseOptions,
+) -> Result<()> {
     let mut cmd = git_command(worktree_path);
-    cmd.args(["rebase", base_branch]);
+    cmd.arg("rebase").args(options.args()).arg(base_branch);
     run_git_interactive(cmd, "Failed to rebase")
 }
 
@@ -467,6 +1365,102 @@ pub fn cleanup_branch(
     was_merged
 }
 
+/// Get the main branch of a repository (the branch checked out in its
+/// main worktree), falling back to "main"
+#[must_use]
+pub fn get_main_branch(main_worktree: &Path) -> String {
+    list_worktrees(main_worktree)
+        .ok()
+        .and_then(|wts| wts.into_iter().next())
+        .and_then(|wt| wt.branch)
+        .unwrap_or_else(|| "main".to_string())
+}
+
+/// Get one-line summaries of the commits in branch that are not in base,
+/// oldest first
+pub fn get_commit_summaries(
+    worktree_path: &Path,
+    branch: &str,
+    base_branch: &str,
+) -> Result<Vec<String>> {
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["log", "--oneline", "--reverse"])
+        .arg(format!("{}..{}", base_branch, branch));
+    let output = run_git(cmd, "Failed to list commits", Verbosity::Quiet)?;
+    Ok(output.lines().map(str::to_string).collect())
+}
+
+/// Get the diff of a worktree against its merge base with base_branch.
+/// Includes committed and uncommitted changes to tracked files.
+/// If `stat` is true, returns a diffstat instead of the full diff.
+pub fn get_diff_from_base(worktree_path: &Path, base_branch: &str, stat: bool) -> Result<String> {
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["merge-base", base_branch, "HEAD"]);
+    let merge_base = run_git(cmd, "Failed to find merge base", Verbosity::Quiet)?;
+
+    let mut cmd = git_command(worktree_path);
+    cmd.arg("diff");
+    if stat {
+        cmd.arg("--stat");
+    }
+    cmd.arg(merge_base.trim());
+    run_git(cmd, "Failed to get diff", Verbosity::Quiet)
+}
+
+/// Get a tree of a worktree's files as they are on disk: committed,
+/// uncommitted and untracked ones that aren't ignored. The files are staged
+/// in a copy of the worktree's index, so its own index is left alone.
+pub fn snapshot_tree(worktree_path: &Path) -> Result<String> {
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["rev-parse", "--git-path", "index"]);
+    let index = worktree_path.join(run_git(cmd, "Failed to find index", Verbosity::Quiet)?.trim());
+    // Next to the index, in the git dir, where other users can't plant a
+    // symlink for the copy to follow as they could in a shared temp dir
+    let copy = index.with_file_name(format!("index.silo-snapshot-{}", std::process::id()));
+    // Starting from the index saves rehashing unchanged files; a worktree
+    // without one just starts empty
+    if index.exists() {
+        std::fs::copy(&index, &copy)?;
+    } else {
+        // Left over from a run that was killed
+        let _ = std::fs::remove_file(&copy);
+    }
+
+    let snapshot = || -> Result<String> {
+        let mut cmd = git_command(worktree_path);
+        cmd.env("GIT_INDEX_FILE", &copy).args(["add", "--all"]);
+        run_git(cmd, "Failed to stage files", Verbosity::Quiet)?;
+        let mut cmd = git_command(worktree_path);
+        cmd.env("GIT_INDEX_FILE", &copy).arg("write-tree");
+        Ok(run_git(cmd, "Failed to write tree", Verbosity::Quiet)?
+            .trim()
+            .to_string())
+    };
+    let tree = snapshot();
+    let _ = std::fs::remove_file(&copy);
+    tree
+}
+
+/// Get the diff between two commits or trees, or a diffstat if `stat`
+pub fn diff_between(
+    worktree_path: &Path,
+    from: &str,
+    to: &str,
+    stat: bool,
+    color: bool,
+) -> Result<String> {
+    let mut cmd = git_command(worktree_path);
+    cmd.arg("diff");
+    if stat {
+        cmd.arg("--stat");
+    }
+    if color {
+        cmd.arg("--color=always");
+    }
+    cmd.args([from, to]);
+    run_git(cmd, "Failed to get diff", Verbosity::Quiet)
+}
+
 /// Get the current HEAD commit hash of a worktree
 pub fn get_head_commit(worktree_path: &Path) -> Result<String> {
     let mut cmd = git_command(worktree_path);
@@ -475,6 +1469,131 @@ pub fn get_head_commit(worktree_path: &Path) -> Result<String> {
     Ok(output.trim().to_string())
 }
 
+/// Get the committer time of a worktree's HEAD in seconds since the Unix
+/// epoch, or None if there are no commits
+#[must_use]
+pub fn get_last_commit_time(worktree_path: &Path) -> Option<u64> {
+    let output = git_command(worktree_path)
+        .args(["log", "-1", "--format=%ct", "HEAD"])
+        .timed_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Get the branch checked out in a worktree, or None on a detached HEAD
+#[must_use]
+pub fn get_current_branch(worktree_path: &Path) -> Option<String> {
+    // Fails on a detached HEAD, which isn't worth a warning
+    let output = git_command(worktree_path)
+        .args(["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .timed_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!branch.is_empty()).then_some(branch)
+}
+
+/// Get the email git records as the author of commits made in
+/// `worktree_path` (`user.email`), if set.
+#[must_use]
+pub fn get_user_email(worktree_path: &Path) -> Option<String> {
+    let output = git_command(worktree_path)
+        .args(["config", "--get", "user.email"])
+        .timed_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let email = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!email.is_empty()).then_some(email)
+}
+
+/// Get a branch's description, as set with `git branch --edit-description`
+#[must_use]
+pub fn get_branch_description(repo_root: &Path, branch: &str) -> Option<String> {
+    // Fails when there's no description
+    let output = git_command(repo_root)
+        .args(["config", "--get", &format!("branch.{}.description", branch)])
+        .timed_output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let description = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!description.is_empty()).then_some(description)
+}
+
+/// Set a branch's description, or remove it when `description` is empty
+pub fn set_branch_description(repo_root: &Path, branch: &str, description: &str) -> Result<()> {
+    let key = format!("branch.{}.description", branch);
+    let mut cmd = git_command(repo_root);
+    if description.trim().is_empty() {
+        if get_branch_description(repo_root, branch).is_none() {
+            return Ok(());
+        }
+        cmd.args(["config", "--unset-all", &key]);
+    } else {
+        // Git ends descriptions it writes with a newline too
+        cmd.args(["config", &key, &format!("{}\n", description.trim_end())]);
+    }
+    run_git(cmd, "Failed to set branch description", Verbosity::Quiet)?;
+    Ok(())
+}
+
+/// Set git config options for one worktree only (`git config --worktree`),
+/// enabling per-worktree config for the repository first
+pub fn set_worktree_config(worktree_path: &Path, options: &[(&str, String)]) -> Result<()> {
+    if options.is_empty() {
+        return Ok(());
+    }
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["config", "extensions.worktreeConfig", "true"]);
+    run_git(
+        cmd,
+        "Failed to enable per-worktree config",
+        Verbosity::Quiet,
+    )?;
+    for (key, value) in options {
+        let mut cmd = git_command(worktree_path);
+        cmd.args(["config", "--worktree", key, value]);
+        run_git(cmd, "Failed to set worktree config", Verbosity::Quiet)?;
+    }
+    Ok(())
+}
+
+/// Edit a branch's description in the user's editor
+pub fn edit_branch_description(worktree_path: &Path, branch: &str) -> Result<()> {
+    let mut cmd = git_command(worktree_path);
+    cmd.args(["branch", "--edit-description", branch]);
+    run_git_interactive(cmd, "Failed to edit branch description")
+}
+
+/// Get the directory git runs hooks from, honoring core.hooksPath
+pub fn get_hooks_dir(repo_root: &Path) -> Result<PathBuf> {
+    let mut cmd = git_command(repo_root);
+    cmd.args(["rev-parse", "--git-path", "hooks"]);
+    let output = run_git(cmd, "Failed to find hooks directory", Verbosity::Quiet)?;
+    // Relative paths are relative to the directory git ran in
+    Ok(repo_root.join(output.trim()))
+}
+
+/// List the files tracked at HEAD, relative to the repository root
+pub fn list_tracked_files(repo_root: &Path) -> Result<Vec<String>> {
+    let mut cmd = git_command(repo_root);
+    cmd.args(["ls-tree", "-r", "-z", "--name-only", "HEAD"]);
+    let output = run_git(cmd, "Failed to list tracked files", Verbosity::Quiet)?;
+    Ok(output
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
 /// Reset a worktree to a specific commit (hard reset)
 pub fn reset_hard(worktree_path: &Path, commit: &str, verbosity: Verbosity) -> Result<()> {
     let mut cmd = git_command(worktree_path);
@@ -483,6 +1602,23 @@ pub fn reset_hard(worktree_path: &Path, commit: &str, verbosity: Verbosity) -> R
     Ok(())
 }
 
+/// Abort a rebase, merge, cherry-pick or revert, or end a bisect, that
+/// stopped partway in a worktree
+pub fn abort_in_progress(
+    worktree_path: &Path,
+    state: InProgress,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let mut cmd = git_command(worktree_path);
+    cmd.args(state.abort_args());
+    run_git(
+        cmd,
+        &format!("Failed to abort {}", state.command()),
+        verbosity,
+    )?;
+    Ok(())
+}
+
 /// Clean untracked files and directories from a worktree
 pub fn clean(worktree_path: &Path, verbosity: Verbosity) -> Result<()> {
     let mut cmd = git_command(worktree_path);
@@ -491,14 +1627,39 @@ pub fn clean(worktree_path: &Path, verbosity: Verbosity) -> Result<()> {
     Ok(())
 }
 
+/// Get the worktree admin directory a silo's .git file points to,
+/// e.g. /main/repo/.git/worktrees/branch-name
+pub fn get_gitdir_from_silo(silo_path: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(silo_path.join(".git")).ok()?;
+    let gitdir = content.strip_prefix("gitdir: ")?.trim();
+    Some(PathBuf::from(gitdir))
+}
+
+/// Point a worktree that was left behind by a moved repository back at
+/// the repository's new location (`git worktree repair`)
+pub fn repair_worktree(
+    main_worktree: &Path,
+    worktree_path: &Path,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let mut cmd = git_command(main_worktree);
+    cmd.args(["worktree", "repair"]).arg(worktree_path);
+    run_git(cmd, "Failed to repair worktree", verbosity)?;
+    Ok(())
+}
+
+/// Drop git's records of worktrees whose directory is gone (`git worktree
+/// prune`)
+pub fn prune_worktrees(repo_root: &Path, verbosity: Verbosity) -> Result<()> {
+    let mut cmd = git_command(repo_root);
+    cmd.args(["worktree", "prune"]);
+    run_git(cmd, "Failed to prune worktrees", verbosity)?;
+    Ok(())
+}
+
 /// Get the main worktree path from a silo worktree by reading its .git file
 pub fn get_main_worktree_from_silo(silo_path: &Path) -> Option<PathBuf> {
-    let git_file = silo_path.join(".git");
-    let content = std::fs::read_to_string(&git_file).ok()?;
-
-    // .git file contains: gitdir: /path/to/.git/worktrees/branch-name
-    let gitdir = content.strip_prefix("gitdir: ")?.trim();
-    let gitdir_path = PathBuf::from(gitdir);
+    let gitdir_path = get_gitdir_from_silo(silo_path)?;
 
     // Go up from .git/worktrees/branch-name to .git, then get parent
     // Structure: /main/repo/.git/worktrees/branch -> /main/repo/.git -> /main/repo
@@ -513,6 +1674,7 @@ fn parse_worktree_list(output: &str) -> Vec<Worktree> {
     let mut current_path: Option<PathBuf> = None;
     let mut has_head = false;
     let mut current_branch: Option<String> = None;
+    let mut current_locked: Option<String> = None;
 
     for line in output.lines() {
         if let Some(path) = line.strip_prefix("worktree ") {
@@ -523,17 +1685,23 @@ fn parse_worktree_list(output: &str) -> Vec<Worktree> {
                 worktrees.push(Worktree {
                     path,
                     branch: current_branch.take(),
+                    locked: current_locked.take(),
                 });
             }
             current_path = Some(PathBuf::from(path));
             has_head = false;
             current_branch = None;
+            current_locked = None;
         } else if line.starts_with("HEAD ") {
             has_head = true;
         } else if let Some(branch) = line.strip_prefix("branch ") {
             // Branch is in format refs/heads/branch-name
             let branch_name = branch.strip_prefix("refs/heads/").unwrap_or(branch);
             current_branch = Some(branch_name.to_string());
+        } else if line == "locked" {
+            current_locked = Some(String::new());
+        } else if let Some(reason) = line.strip_prefix("locked ") {
+            current_locked = Some(reason.to_string());
         }
     }
 
@@ -544,6 +1712,7 @@ fn parse_worktree_list(output: &str) -> Vec<Worktree> {
         worktrees.push(Worktree {
             path,
             branch: current_branch,
+            locked: current_locked,
         });
     }
 
@@ -554,6 +1723,75 @@ fn parse_worktree_list(output: &str) -> Vec<Worktree> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_git_command_clears_repo_env() {
+        let cmd = git_command(Path::new("/tmp"));
+        let cleared: Vec<_> = cmd
+            .get_envs()
+            .filter(|(_, value)| value.is_none())
+            .map(|(key, _)| key.to_string_lossy().into_owned())
+            .collect();
+        assert!(cleared.contains(&"GIT_DIR".to_string()));
+        assert!(cleared.contains(&"GIT_INDEX_FILE".to_string()));
+        assert!(!cleared.contains(&"GIT_SSH_COMMAND".to_string()));
+    }
+
+    #[test]
+    fn test_parse_promisor_remotes() {
+        let remotes = parse_promisor_remotes(
+            "remote.origin.promisor true\n\
+             remote.origin.partialclonefilter blob:none\n\
+             remote.old.promisor false\n",
+        );
+        assert_eq!(
+            remotes,
+            vec![PromisorRemote {
+                name: "origin".to_string(),
+                filter: Some("blob:none".to_string()),
+            }]
+        );
+        assert!(parse_promisor_remotes("").is_empty());
+    }
+
+    #[test]
+    fn test_is_transient_failure() {
+        assert!(is_transient_failure(
+            "fatal: unable to access 'https://host/r.git/': Could not resolve host: host"
+        ));
+        assert!(is_transient_failure(
+            "error: RPC failed; curl 56 GnuTLS recv error\nfatal: early EOF"
+        ));
+        assert!(!is_transient_failure(
+            "git@host: Permission denied (publickey).\nfatal: Could not read from remote repository."
+        ));
+        assert!(!is_transient_failure("fatal: invalid reference: main"));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_limit() {
+        let delays: Vec<u64> = (1..=8).map(|n| backoff(n).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30, 30]);
+    }
+
+    #[test]
+    fn test_is_retry_safe() {
+        let mut fetch = base_command();
+        fetch.args(["-c", "fetch.negotiationAlgorithm=noop", "fetch", "origin"]);
+        assert!(is_retry_safe(&fetch));
+
+        let mut add = base_command();
+        add.args(["worktree", "add", "-b", "fetch", "/tmp/fetch"]);
+        assert!(!is_retry_safe(&add));
+    }
+
+    #[test]
+    fn test_network_failure_is_transient() {
+        let mut cmd = git_command(&std::env::temp_dir());
+        cmd.args(["ls-remote", "http://127.0.0.1:1/repo.git"]);
+        let err = run_git(cmd, "Failed to reach remote", Verbosity::Quiet).unwrap_err();
+        assert!(matches!(err, SiloError::Transient(_)), "{:?}", err);
+    }
+
     #[test]
     fn test_extract_repo_name_from_https_url() {
         assert_eq!(
@@ -620,4 +1858,137 @@ mod tests {
         assert_eq!(worktrees.len(), 1);
         assert_eq!(worktrees[0].branch, None);
     }
+
+    #[test]
+    fn test_parse_worktree_list_locked() {
+        let output = "worktree /path/to/repo\nHEAD abc123\nbranch refs/heads/main\n\n\
+                      worktree /path/to/a\nHEAD def456\nbranch refs/heads/a\nlocked\n\n\
+                      worktree /path/to/b\nHEAD def456\nbranch refs/heads/b\nlocked on a USB disk\n";
+        let worktrees = parse_worktree_list(output);
+        assert_eq!(worktrees.len(), 3);
+        assert_eq!(worktrees[0].locked, None);
+        assert_eq!(worktrees[1].locked.as_deref(), Some(""));
+        assert_eq!(worktrees[2].locked.as_deref(), Some("on a USB disk"));
+    }
+
+    #[test]
+    fn test_parse_status_line() {
+        let file = |path: &str, untracked| {
+            Some(ChangedFile {
+                path: path.to_string(),
+                untracked,
+            })
+        };
+        assert_eq!(
+            parse_status_line(" M src/lib.rs"),
+            file("src/lib.rs", false)
+        );
+        assert_eq!(
+            parse_status_line("R  old.rs -> new.rs"),
+            file("new.rs", false)
+        );
+        assert_eq!(
+            parse_status_line("?? build/out.o"),
+            file("build/out.o", true)
+        );
+        assert_eq!(parse_status_line("?? "), None);
+    }
+
+    #[test]
+    fn test_parse_branch_details() {
+        let branches = parse_branch_details(
+            "main\trefs/remotes/origin/main\t1700000000\nold/fix\t\t1600000000\n",
+        );
+        assert_eq!(
+            branches,
+            [
+                LocalBranch {
+                    name: "main".to_string(),
+                    upstream: Some("refs/remotes/origin/main".to_string()),
+                    committed_at: Some(1700000000),
+                },
+                LocalBranch {
+                    name: "old/fix".to_string(),
+                    upstream: None,
+                    committed_at: Some(1600000000),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_status_v2() {
+        let output = "\
+# branch.oid 1234567890abcdef1234567890abcdef12345678
+# branch.head feature
+# branch.upstream origin/feature
+# branch.ab +2 -1
+1 .M N... 100644 100644 100644 aaaa bbbb src/lib.rs
+1 A. N... 000000 100644 100644 0000 cccc docs/new file.md
+2 R. N... 100644 100644 100644 dddd dddd R100 new.rs\told.rs
+u UU N... 100644 100644 100644 100644 eeee ffff 0000 conflict.rs
+? build/out.o
+";
+        let status = parse_status_v2(output);
+        assert_eq!(status.upstream, Some((2, 1)));
+        assert_eq!(status.upstream_branch.as_deref(), Some("origin/feature"));
+        assert_eq!(
+            (
+                status.uncommitted.staged,
+                status.uncommitted.modified,
+                status.uncommitted.untracked
+            ),
+            (3, 1, 1)
+        );
+        let paths: Vec<&str> = status.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "src/lib.rs",
+                "docs/new file.md",
+                "new.rs",
+                "conflict.rs",
+                "build/out.o"
+            ]
+        );
+        assert!(status.files[4].untracked && !status.files[0].untracked);
+
+        // No upstream
+        assert_eq!(parse_status_v2("# branch.head feature\n").upstream, None);
+    }
+
+    #[test]
+    fn test_rebase_options_args() {
+        assert!(RebaseOptions::default().args().is_empty());
+        let options = RebaseOptions {
+            update_refs: true,
+            rebase_merges: true,
+        };
+        assert_eq!(options.args(), vec!["--update-refs", "--rebase-merges"]);
+    }
+
+    #[test]
+    fn test_parse_merge_tree_conflicts() {
+        let output = "a7365a44\nsrc/lib.rs\nREADME.md\nsrc/lib.rs\n";
+        assert_eq!(
+            parse_merge_tree_conflicts(output),
+            vec!["src/lib.rs", "README.md"]
+        );
+        assert!(parse_merge_tree_conflicts("a7365a44\n").is_empty());
+    }
+
+    #[test]
+    fn test_in_progress_from_state_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let git_dir = dir.path();
+        assert_eq!(in_progress_in(git_dir), None);
+
+        std::fs::write(git_dir.join("BISECT_LOG"), "").unwrap();
+        assert_eq!(in_progress_in(git_dir), Some(InProgress::Bisect));
+
+        // A rebase stopped on a conflict takes precedence
+        std::fs::create_dir(git_dir.join("rebase-merge")).unwrap();
+        assert_eq!(in_progress_in(git_dir), Some(InProgress::Rebase));
+        assert_eq!(InProgress::Rebase.badge(), "REBASING");
+    }
 }
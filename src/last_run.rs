@@ -0,0 +1,112 @@
+//! The most recent command run in a silo via `exec` or a sandboxed agent.
+//!
+//! Stored in the silo's tracking directory so `list` can flag silos whose
+//! last run failed, without having to find the terminal it ran in.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::metadata::{self, Record};
+use crate::process;
+
+/// Outcome of the most recent run in a silo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastRun {
+    pub command: String,
+    /// Exit code, or None if the process was killed by a signal
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+}
+
+impl LastRun {
+    /// Whether the run exited successfully.
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// Short description of a failed run's exit, e.g. "exit 2" or "killed".
+    pub fn exit_description(&self) -> String {
+        match self.exit_code {
+            Some(code) => format!("exit {}", code),
+            None => "killed".to_string(),
+        }
+    }
+}
+
+/// Get the path of the file recording a silo's last run.
+pub fn record_path(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("last-run")
+}
+
+/// Get the path of the full output of a silo's last `exec --capture` run.
+pub fn log_path(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("last-run.log")
+}
+
+/// Record the last run for a silo, replacing any previous one.
+pub fn record(silo_path: &Path, run: &LastRun) -> Result<(), String> {
+    let mut record = Record::new();
+    record
+        .set("command", &run.command)
+        .set("duration_ms", run.duration.as_millis());
+    if let Some(code) = run.exit_code {
+        record.set("exit_code", code);
+    }
+    metadata::store(&record_path(silo_path), &record)
+        .map_err(|e| format!("Failed to write last run: {}", e))
+}
+
+/// Load the last recorded run for a silo, if any.
+pub fn load(silo_path: &Path) -> Option<LastRun> {
+    parse(&metadata::load(&record_path(silo_path))?)
+}
+
+/// Read a run from its record.
+fn parse(record: &Record) -> Option<LastRun> {
+    Some(LastRun {
+        command: record.get("command")?.to_string(),
+        exit_code: record.get_parsed("exit_code"),
+        duration: Duration::from_millis(record.get_parsed("duration_ms").unwrap_or(0)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_path() {
+        assert_eq!(
+            record_path(Path::new("/var/silo/repo-abc/feature")),
+            PathBuf::from("/var/silo/repo-abc/.tracking/feature/last-run")
+        );
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let base = tempfile::tempdir().unwrap();
+        let silo = base.path().join("feature");
+        let run = LastRun {
+            command: "cargo test".to_string(),
+            exit_code: Some(101),
+            duration: Duration::from_millis(2500),
+        };
+
+        record(&silo, &run).unwrap();
+        assert_eq!(load(&silo), Some(run));
+    }
+
+    #[test]
+    fn test_killed_run_has_no_exit_code() {
+        let record = Record::parse("command=sleep 100\nduration_ms=10\n").unwrap();
+        let run = parse(&record).unwrap();
+        assert_eq!(run.exit_code, None);
+        assert!(!run.succeeded());
+        assert_eq!(run.exit_description(), "killed");
+    }
+
+    #[test]
+    fn test_parse_requires_command() {
+        assert_eq!(parse(&Record::parse("exit_code=0\n").unwrap()), None);
+    }
+}
@@ -1,7 +1,8 @@
 use clap::{
-    CommandFactory, FromArgMatches, Parser, Subcommand,
+    ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand,
     builder::styling::{AnsiColor, Effects, Styles},
 };
+use output::Verbosity;
 use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
 const STYLES: Styles = Styles::styled()
@@ -10,21 +11,48 @@ const STYLES: Styles = Styles::styled()
     .literal(AnsiColor::Green.on_default().effects(Effects::BOLD))
     .placeholder(AnsiColor::Cyan.on_default());
 
+mod activity;
+mod agent;
+mod busy;
 mod color;
 mod commands;
 mod complete;
 mod config;
+mod created;
+mod disk_usage;
 mod error;
 mod exit;
+mod external;
 mod git;
+mod last_run;
+mod messages;
+mod metadata;
+mod metrics;
 mod names;
+mod notes;
+mod output;
+mod pager;
+mod pending;
+mod policy;
+mod pool;
+mod preflight;
 mod process;
 mod prompt;
+#[cfg(unix)]
+mod pty;
+mod registry;
 mod removal;
+mod removal_plan;
+mod roots;
 mod runner;
 mod sandbox;
 mod shell;
 mod silo;
+mod storage;
+mod template;
+mod timings;
+mod transcript;
+mod verify;
 
 #[derive(Parser)]
 #[command(name = "silo", styles = STYLES)]
@@ -37,21 +65,33 @@ struct Cli {
     #[arg(short = 'n', long, global = true)]
     dry_run: bool,
 
-    /// Skip confirmation prompts
-    #[arg(short, long, global = true)]
-    force: bool,
+    /// Skip confirmation prompts; give twice to also remove locked worktrees
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    force: u8,
 
     /// Suppress non-error output
     #[arg(short, long, global = true)]
     quiet: bool,
 
-    /// Enable verbose output (debug-level logging)
-    #[arg(short = 'v', long, global = true)]
-    verbose: bool,
+    /// Increase output: -v for debug logging, -vv for trace logging and full git output
+    #[arg(short = 'v', long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Print how long each phase of the command took (to stderr)
+    #[arg(long, global = true, hide = true)]
+    timings: bool,
 
     /// Use a specific config file (ignores default config locations)
     #[arg(short = 'c', long, global = true, value_name = "FILE")]
     config_file: Option<std::path::PathBuf>,
+
+    /// Ignore all config files and use built-in defaults
+    #[arg(long, global = true, conflicts_with = "config_file")]
+    no_config: bool,
+
+    /// Retry git fetches that fail transiently up to N times, with backoff
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    retries: u32,
 }
 
 #[derive(Subcommand)]
@@ -60,68 +100,384 @@ enum Commands {
     New {
         /// Branch name to create
         branch: String,
+        /// Run the command (an agent, e.g. `-- claude -p "..."`) in a Docker sandbox
+        #[arg(long)]
+        sandbox: bool,
+        /// Move the main worktree's uncommitted changes (including untracked files) into the new silo
+        #[arg(long)]
+        take_changes: bool,
+        /// Store this silo under DIR instead of the configured storage directory
+        /// (remembered, so list, rm and gc find it)
+        #[arg(long, value_name = "DIR", value_parser = roots::parse_dir)]
+        worktree_dir: Option<std::path::PathBuf>,
         /// Command to run in the new silo
         #[arg(trailing_var_arg = true)]
         command: Vec<String>,
     },
+    /// Create a new silo from the repository's pool of idle silos
+    ///
+    /// Hands out a silo `gc` created ahead of time (see pool_size) instead
+    /// of checking out a new worktree. Creates the silo like `new` when the
+    /// pool is empty or the branch already exists.
+    Reuse {
+        /// Branch name to create
+        branch: String,
+    },
     /// List silos for the current repo
-    #[command(visible_alias = "ls")]
+    #[command(
+        visible_alias = "ls",
+        after_help = "--format placeholders: {name} {id} {repo} {branch} {path} {ahead} {behind}\n\
+        {upstream_ahead} {upstream_behind} {added} {removed} {staged} {modified} {untracked}\n\
+        {processes} {state} {verified} {external} {current} {age} {size} {description}.\n\
+        \\t, \\n and \\\\ are replaced by a tab, a newline and a backslash."
+    )]
     List {
         /// List silos for all repositories
         #[arg(short, long)]
         all: bool,
+        /// Only list repositories matching REPO (repo or org/repo; implies --all)
+        #[arg(long, value_name = "REPO")]
+        repo: Option<String>,
+        /// Print one aggregated row per repository (implies --all)
+        #[arg(long)]
+        summary: bool,
+        /// Only list silos owned by USER (for shared storage directories)
+        #[arg(long, value_name = "USER")]
+        user: Option<String>,
+        /// Print machine-readable JSON (includes canonical silo IDs)
+        #[arg(long, conflicts_with = "summary")]
+        json: bool,
+        /// Show at most N silos
+        #[arg(long, value_name = "N", conflicts_with = "summary")]
+        limit: Option<usize>,
+        /// Show the PAGE-th group of --limit silos, from 1
+        #[arg(long, value_name = "PAGE", requires = "limit")]
+        page: Option<usize>,
+        /// Don't send long output through $SILO_PAGER or $PAGER (default: less)
+        #[arg(long)]
+        no_pager: bool,
+        /// Show each branch's description (set with `silo note`) under its silo
+        #[arg(short, long, conflicts_with = "summary")]
+        long: bool,
+        /// Print a line per silo from TEMPLATE, e.g. '{name}\t{branch}\t{ahead}'
+        #[arg(
+            long,
+            value_name = "TEMPLATE",
+            conflicts_with_all = ["summary", "json", "long"]
+        )]
+        format: Option<String>,
+        /// Only list silos with uncommitted changes
+        #[arg(long, conflicts_with = "clean")]
+        dirty: bool,
+        /// Only list silos without uncommitted changes
+        #[arg(long)]
+        clean: bool,
+        /// Only list silos with running processes
+        #[arg(long)]
+        active: bool,
+        /// Also show commits ahead of and behind each branch's upstream
+        /// (unpushed and unpulled work)
+        #[arg(long, conflicts_with_all = ["summary", "json"])]
+        remote: bool,
+        /// Also show each silo's disk size, not counting the repository
+        /// data it shares (slower: reads every file's size)
+        #[arg(long, conflicts_with_all = ["summary", "json"])]
+        du: bool,
+        /// Only list silos on branches in the configured namespace
+        #[arg(long)]
+        mine: bool,
+        /// Print tab-separated fields that stay the same across releases,
+        /// for scripts; VERSION defaults to v1 (see the README)
+        #[arg(
+            long,
+            value_name = "VERSION",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "v1",
+            conflicts_with_all = ["summary", "json", "long", "format", "remote", "du"]
+        )]
+        porcelain: Option<commands::list::Porcelain>,
+        /// Print a CSV header row and a row per silo with every --format
+        /// field, e.g. for a spreadsheet (size only with --du)
+        #[arg(
+            long,
+            conflicts_with_all = ["summary", "json", "long", "format", "porcelain", "remote"]
+        )]
+        csv: bool,
+        /// Only show names, branches and commits ahead/behind, skipping
+        /// line counts and uncommitted files (faster on big diffs)
+        #[arg(
+            long,
+            conflicts_with_all = ["summary", "json", "format", "porcelain", "csv", "remote", "du"]
+        )]
+        fast: bool,
     },
     /// Remove a silo
     ///
     /// If the branch has been merged into the main worktree, it will be deleted.
     /// Otherwise, the branch is preserved.
-    #[command(after_help = "NAME can be a branch, repo/branch, or org/repo/branch")]
+    #[command(after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID")]
     Rm {
+        /// With --dry-run, print the removal plan as JSON for approval
+        #[arg(long)]
+        json: bool,
+        /// Carry out a removal plan printed by --dry-run --json, if the
+        /// silo is still as planned
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["name", "json"])]
+        plan_file: Option<std::path::PathBuf>,
         /// Silo to remove (branch, repo/branch, or org/repo/branch)
-        name: String,
+        #[arg(required_unless_present = "plan_file")]
+        name: Option<String>,
     },
     /// Navigate to a silo directory
     #[command(
-        after_help = "NAME can be a branch, repo/branch, or org/repo/branch.\n\
-        With no arguments, returns to the main worktree."
+        after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID.\n\
+        With no arguments, returns to the main worktree.\n\n\
+        With --spawn, starts $SHELL in the silo with SILO_CURRENT, SILO_PATH,\n\
+        SILO_BRANCH, SILO_REPO and SILO_MAIN_WORKTREE set, for shells without\n\
+        shell integration. Exit it to return."
     )]
     Cd {
+        /// Start a new shell in the silo instead of changing directory
+        #[arg(long)]
+        spawn: bool,
         /// Silo to navigate to (branch, repo/branch, or org/repo/branch)
         name: Option<String>,
     },
+    /// Open a silo in VS Code with its main worktree alongside, read-only
+    ///
+    /// Writes a multi-root `.code-workspace` file to the silo's tracking
+    /// directory and opens it with editor_command (default: code), so
+    /// editors that read workspace files, like Cursor, work too.
+    #[command(
+        after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID.\n\
+        Use '-' for the last used silo."
+    )]
+    Code {
+        /// Print the workspace file's path instead of opening it
+        #[arg(long)]
+        print: bool,
+        /// Silo to open
+        name: String,
+    },
     /// Run a command in a silo directory
     #[command(
         visible_alias = "run",
-        after_help = "NAME can be a branch, repo/branch, or org/repo/branch"
+        after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID"
     )]
     Exec {
+        /// Run verify_command after the command succeeds
+        #[arg(long)]
+        verify: bool,
+        /// Run the command on a new pseudo-terminal, so it behaves as in an
+        /// interactive shell even when silo's output is piped
+        #[arg(long, conflicts_with = "no_tty")]
+        tty: bool,
+        /// Run the command without a terminal (stdin from /dev/null, output
+        /// through pipes), as in a script
+        #[arg(long)]
+        no_tty: bool,
+        /// Capture the output instead of passing it through and print the
+        /// result as JSON, with the first KB kibibytes of each stream
+        #[arg(
+            long,
+            value_name = "KB",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "64",
+            value_parser = commands::exec::parse_capture_limit,
+            conflicts_with_all = ["verify", "tty", "no_tty"]
+        )]
+        capture: Option<usize>,
+        /// Don't record a PID file or the last run, e.g. for quick commands
+        /// run by prompt helpers
+        #[arg(long, conflicts_with = "verify")]
+        no_track: bool,
         /// Silo to run in (branch, repo/branch, or org/repo/branch)
         name: String,
         /// Command and arguments to execute
         #[arg(trailing_var_arg = true, required = true)]
         command: Vec<String>,
     },
+    /// Show the most recent command run in a silo
+    #[command(
+        after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID.\n\
+        Use '-' for the last used silo.\n\
+        Runs are recorded by `silo exec`, `silo new NAME CMD` and sandboxed agents."
+    )]
+    LastRun {
+        /// Silo to inspect
+        name: String,
+    },
+    /// Show or set the description of a silo's branch
+    #[command(
+        after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID.\n\
+        Use '-' for the last used silo.\n\
+        This is git's branch description (`git branch --edit-description`),\n\
+        shown by `silo list --long`."
+    )]
+    Note {
+        /// Open the description in git's editor
+        #[arg(short, long, conflicts_with_all = ["clear", "text"])]
+        edit: bool,
+        /// Remove the description
+        #[arg(long, conflicts_with = "text")]
+        clear: bool,
+        /// Silo whose branch to describe
+        name: String,
+        /// New description (omit to print the current one)
+        text: Option<String>,
+    },
+    /// Show or set what silo may do to a silo's branch
+    #[command(
+        after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID.\n\
+        Use '-' for the last used silo.\n\
+        A silo marked --no-rebase keeps its exact history (e.g. signed commits\n\
+        pending review): `silo rebase` refuses it, even with --force."
+    )]
+    Policy {
+        /// Refuse to rebase the silo's branch
+        #[arg(long, conflicts_with = "allow_rebase")]
+        no_rebase: bool,
+        /// Allow rebasing the silo's branch again (the default)
+        #[arg(long)]
+        allow_rebase: bool,
+        /// Silo whose policy to show or set
+        name: String,
+    },
+    /// Run verify_command in a silo and record the result
+    #[command(
+        after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID.\n\
+        Use '-' for the last used silo.\n\
+        The result is shown in the TESTS column of `silo list`."
+    )]
+    Verify {
+        /// Silo to verify
+        name: String,
+    },
+    /// Run verify_command in several silos and compare the results
+    #[command(
+        after_help = "NAMES can be branches, repo/branch, org/repo/branch, or silo IDs.\n\
+        Without names, verifies every silo of the current repository.\n\
+        With --jobs above 1 or --json, each silo's output goes to a log in its\n\
+        tracking directory instead of the terminal. Results are recorded as by\n\
+        `silo verify`; the command fails if any silo fails."
+    )]
+    TestMatrix {
+        /// Number of silos to verify at once
+        #[arg(short, long, value_name = "N", default_value_t = 1)]
+        jobs: usize,
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
+        /// Silos to verify
+        names: Vec<String>,
+    },
     /// Remove silos with no uncommitted changes
     Prune {
         /// Prune silos for all repositories
         #[arg(short, long)]
         all: bool,
+        /// Only prune silos created longer ago than this (e.g. 12h, 7d, 2w).
+        /// Silos without a recorded creation time are kept.
+        #[arg(long, value_name = "AGE", value_parser = created::parse_age)]
+        older_than: Option<std::time::Duration>,
+        /// With --dry-run, print the removal plan as JSON for approval
+        #[arg(long)]
+        json: bool,
+        /// Carry out a removal plan printed by --dry-run --json, if its
+        /// silos are still as planned
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["all", "older_than", "json"])]
+        plan_file: Option<std::path::PathBuf>,
     },
     /// Remove orphaned silos and empty directories
     ///
     /// Cleans up silos whose main worktree no longer exists (e.g., test repos
     /// created in /tmp that were cleaned up) and empty repo directories.
-    Gc,
+    #[command(
+        after_help = "Exits with status 3 if anything found is left in place (dry run, declined,\n\
+        or failed to remove), so it can run unattended, e.g. from cron."
+    )]
+    Gc {
+        /// Remove without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Print what was found and removed as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Report local branches no worktree has checked out and with no upstream
+    ///
+    /// Such branches are often left behind by removed silos. The main branch
+    /// and protected_branches are never reported. Merged ones can be deleted
+    /// with --delete-merged; unmerged ones are only reported.
+    OrphanBranches {
+        /// Check every known repository, not just the current one
+        #[arg(short, long)]
+        all: bool,
+        /// Delete the orphan branches merged into the main branch
+        #[arg(long)]
+        delete_merged: bool,
+        /// Print the branches found (and whether each was deleted) as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Cross-check the storage directory against git's worktree records
+    ///
+    /// Reports worktree directories git doesn't list, worktrees git lists
+    /// that don't exist, paths git records in a different case, stray
+    /// directories, stale tracking data and registry entries. With --fix,
+    /// repairs or prunes git's records and removes only empty directories
+    /// and tracking data. Exits non-zero if problems remain.
+    VerifyStorage {
+        /// Reconcile what can be reconciled without losing work
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Compare the work in two silos, e.g. two agents' solutions
+    #[command(
+        after_help = "A and B can be a branch, repo/branch, org/repo/branch, or a silo ID,\n\
+        and must belong to the same repository. Without --uncommitted, their\n\
+        HEAD commits are compared."
+    )]
+    Diff {
+        /// Show the changes from silo A to silo B
+        #[arg(long, num_args = 2, value_names = ["A", "B"], required = true)]
+        between: Vec<String>,
+        /// Compare the silos' files on disk, including uncommitted changes
+        /// and untracked files
+        #[arg(long)]
+        uncommitted: bool,
+        /// Print a diffstat instead of the diff
+        #[arg(long)]
+        stat: bool,
+        /// Don't send the diff through $SILO_PAGER or $PAGER (default: less)
+        #[arg(long)]
+        no_pager: bool,
+    },
     /// Rebase a silo's commits on top of the main branch
-    #[command(after_help = "NAME can be a branch, repo/branch, or org/repo/branch")]
+    ///
+    /// The silo must have no uncommitted changes. Prints the plan first; use
+    /// --force to skip confirmation when conflicts are expected.
+    #[command(after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID")]
     Rebase {
         /// Silo to rebase (branch, repo/branch, or org/repo/branch)
         name: String,
+        /// Move other branches pointing into the rebased commits along with
+        /// them (git rebase --update-refs), for stacked branches in one silo
+        #[arg(long)]
+        update_refs: bool,
+        /// Recreate merge commits instead of flattening them
+        /// (git rebase --rebase-merges)
+        #[arg(long)]
+        rebase_merges: bool,
     },
     /// Merge a silo's branch into the main worktree's current branch
-    #[command(
-        after_help = "NAME can be a branch, repo/branch, or org/repo/branch.\nMust be run from the main worktree."
-    )]
+    ///
+    /// The main worktree must have no uncommitted changes. Prints the plan
+    /// first; use --force to skip confirmation when conflicts are expected.
+    /// Afterwards the silo is kept, reset or removed as after_merge says.
+    #[command(after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID")]
     Merge {
         /// Silo to merge (branch, repo/branch, or org/repo/branch)
         name: String,
@@ -131,11 +487,109 @@ enum Commands {
     /// Discards all changes in the silo and resets it to match the current
     /// HEAD commit of the main worktree. Use --force to skip confirmation
     /// when the silo has uncommitted changes or unmerged commits.
-    #[command(after_help = "NAME can be a branch, repo/branch, or org/repo/branch")]
+    #[command(after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID")]
     Reset {
         /// Silo to reset (branch, repo/branch, or org/repo/branch)
         name: String,
     },
+    /// Check the silo environment for problems
+    ///
+    /// Reports storage directory permissions, filesystem features and shell
+    /// integration status. Exits non-zero if a check fails.
+    Doctor,
+    /// Show the storage directory, config files and version silo uses, or
+    /// everything about one silo
+    ///
+    /// Config files are listed in order of precedence, later ones
+    /// overriding earlier ones, including those that don't exist. With NAME,
+    /// shows the silo's paths, branch, commits ahead and behind the main
+    /// branch and its upstream, uncommitted files and tracked processes.
+    #[command(after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID")]
+    Info {
+        /// Silo to show (branch, repo/branch, or org/repo/branch)
+        name: Option<String>,
+        /// Print only the resolved paths, one `key=path` line each
+        #[arg(long, conflicts_with = "name")]
+        paths: bool,
+        /// Print everything as JSON
+        #[arg(long, conflicts_with = "paths")]
+        json: bool,
+        /// Print the silo's details in a stable tab-separated format for
+        /// scripts; VERSION defaults to v1 (see the README)
+        #[arg(
+            long,
+            value_name = "VERSION",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "v1",
+            requires = "name",
+            conflicts_with_all = ["paths", "json"]
+        )]
+        porcelain: Option<commands::list::Porcelain>,
+    },
+    /// Print silo metrics in the OpenMetrics text format
+    ///
+    /// Silo counts per repository, tracked processes and disk usage per
+    /// storage root are measured on the spot; commands run, their durations
+    /// and silos removed by rm, prune and gc are running totals.
+    Metrics {
+        /// Skip measuring disk usage, which walks every silo
+        #[arg(long)]
+        no_disk_usage: bool,
+    },
+    /// Show totals across all silos: unmerged commits, changed lines, dirty
+    /// silos, running processes and the oldest silo
+    Stats {
+        /// Only count repositories matching REPO (repo or org/repo)
+        #[arg(long)]
+        repo: Option<String>,
+        /// Also show the totals of each repository
+        #[arg(long)]
+        by_repo: bool,
+        /// Print the totals as JSON, with each repository's
+        #[arg(long, conflicts_with = "by_repo")]
+        json: bool,
+    },
+    /// Print completion candidates, for editors and launchers
+    ///
+    /// Offers what the shell would for the word after COMMAND: subcommands,
+    /// or silo names for commands that take one (relative to the current
+    /// repository when run inside one). One value per line by default.
+    Complete {
+        /// Command the word is an argument of, e.g. "rm" or "sandbox claude"
+        /// (default: complete top-level commands)
+        #[arg(long = "for", value_name = "COMMAND")]
+        for_command: Option<String>,
+        /// Only list candidates starting with WORD
+        #[arg(long, value_name = "WORD", default_value = "")]
+        word: String,
+        /// Output format (bash: bare values; json: kind, value, description)
+        #[arg(long, value_enum, default_value = "bash")]
+        format: complete::Format,
+    },
+    /// Print the path of the silo a name resolves to, without side effects
+    #[command(hide = true)]
+    Resolve {
+        /// Silo name, repo/name, canonical ID, or - for the last used silo
+        name: String,
+        /// Show how the name was parsed and matched
+        #[arg(long)]
+        explain: bool,
+        /// Print the resolution and how it was reached as JSON
+        #[arg(long, conflicts_with = "explain")]
+        json: bool,
+    },
+    /// Git hooks that steer commits away from protected branches
+    #[command(
+        after_help = "The hooks go in the repository's hooks directory (core.hooksPath if set)\n\
+        and are shared by all its worktrees. They warn about, or with\n\
+        protected_branch_policy = \"block\" refuse, commits and pushes made\n\
+        directly to protected_branches (default: main and master)."
+    )]
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCommands,
+    },
     /// Shell integration commands
     Shell {
         #[command(subcommand)]
@@ -146,6 +600,54 @@ enum Commands {
         #[command(subcommand)]
         command: SandboxCommands,
     },
+    /// Run sandboxed agents in the background, each in a fresh silo
+    Agent {
+        #[command(subcommand)]
+        command: AgentCommands,
+    },
+    /// Address git worktrees outside the storage directory like silos
+    #[command(
+        after_help = "Registered worktrees work with cd, list and exec, and are shown as\n\
+        external. prune never touches them and rm refuses without --force."
+    )]
+    External {
+        #[command(subcommand)]
+        command: ExternalCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExternalCommands {
+    /// Register an existing linked worktree (it is not moved)
+    Add {
+        /// Path of the worktree
+        path: std::path::PathBuf,
+    },
+    /// Unregister a worktree, leaving it in place
+    #[command(alias = "remove")]
+    Rm {
+        /// Path of the worktree
+        path: std::path::PathBuf,
+    },
+    /// List registered worktrees
+    List,
+}
+
+#[derive(Subcommand)]
+enum HooksCommands {
+    /// Install pre-commit and pre-push hooks (--force moves existing ones aside)
+    Install,
+    /// Remove the hooks silo installed, restoring any it moved aside
+    Uninstall,
+    /// Run a hook (called by the installed hooks)
+    #[command(hide = true)]
+    Run {
+        /// Hook name, e.g. pre-commit
+        hook: String,
+        /// Arguments git passed to the hook
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -158,41 +660,176 @@ enum ShellCommands {
     /// Generate completions for any position (for shell completion)
     #[command(hide = true)]
     CompleteArgs {
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: complete::Format,
         /// Current command line words (after 'silo')
         #[arg(trailing_var_arg = true)]
         args: Vec<String>,
     },
+    /// Simulate a completion request and show candidate counts and timings
+    #[command(hide = true)]
+    CompleteDebug {
+        /// Command line words to complete (after 'silo')
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
 }
 
 #[derive(Subcommand)]
 enum SandboxCommands {
+    /// Show the agent transcript recorded for a silo
+    #[command(
+        after_help = "SILO can be a branch, repo/branch, org/repo/branch, or a silo ID.\n\
+        If not specified, uses current directory if it's a silo.\n\
+        Shows the most recent sandbox session unless --list is given."
+    )]
+    Transcript {
+        /// Silo to inspect (omit to use current directory)
+        silo: Option<String>,
+
+        /// List all recorded transcript files instead
+        #[arg(long, conflicts_with = "raw")]
+        list: bool,
+
+        /// Print the raw JSONL transcript
+        #[arg(long)]
+        raw: bool,
+    },
+    /// List or copy files written to a silo's sandbox scratch directory
+    #[command(
+        after_help = "SILO can be a branch, repo/branch, org/repo/branch, or a silo ID.\n\
+        If not specified, uses current directory if it's a silo.\n\
+        Agents can write to /scratch inside the sandbox; each run gets its own directory."
+    )]
+    Artifacts {
+        /// Silo to inspect (omit to use current directory)
+        silo: Option<String>,
+
+        /// Copy all artifacts into DIR instead of listing them
+        #[arg(long, value_name = "DIR")]
+        copy: Option<std::path::PathBuf>,
+    },
     /// Run Claude Code in a Docker sandbox
     #[command(
-        after_help = "SILO can be a branch, repo/branch, or org/repo/branch.\nIf not specified, uses current directory if it's a silo."
+        after_help = "SILO can be a branch, repo/branch, org/repo/branch, or a silo ID.\nIf not specified, uses current directory if it's a silo."
     )]
     Claude {
         /// Silo to run in (omit to use current directory)
         silo: Option<String>,
 
+        /// Mount another silo read-only (SILO:/container/path[:ro]; use @main for the main worktree)
+        #[arg(long = "mount-silo", value_name = "SPEC")]
+        mount_silo: Vec<String>,
+
+        /// Run verify_command after Claude exits
+        #[arg(long)]
+        verify: bool,
+
         /// Arguments to pass to Claude Code (after --)
         #[arg(last = true)]
         args: Vec<String>,
     },
 }
 
+#[derive(Subcommand)]
+enum AgentCommands {
+    /// Create a silo and start a sandboxed agent in it in the background
+    #[command(after_help = "The silo is named agent-N unless --name is given.\n\
+        The prompt and the agent's output log are kept with the silo's tracking data;\n\
+        the running agent shows up in `silo list` like any other process.")]
+    Spawn {
+        /// Branch name for the new silo
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Prompt for the agent
+        #[arg(long, group = "prompt_source")]
+        prompt: Option<String>,
+
+        /// Read the prompt from FILE
+        #[arg(long, value_name = "FILE", group = "prompt_source")]
+        prompt_file: Option<std::path::PathBuf>,
+
+        /// Mount another silo read-only (SILO:/container/path[:ro]; use @main for the main worktree)
+        #[arg(long = "mount-silo", value_name = "SPEC")]
+        mount_silo: Vec<String>,
+
+        /// Arguments to pass to Claude Code (after --)
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Start several agents with the same prompt, each in a fresh silo
+    #[command(
+        after_help = "Silos are named agent-N, or PREFIX-1 .. PREFIX-N with --name PREFIX.\n\
+        Use `silo agent compare` afterwards to pick the best candidate."
+    )]
+    Matrix {
+        /// Number of agents to start
+        #[arg(long, default_value_t = 3)]
+        count: u32,
+
+        /// Branch name prefix for the new silos
+        #[arg(long, value_name = "PREFIX")]
+        name: Option<String>,
+
+        /// Prompt for the agents
+        #[arg(long, group = "prompt_source")]
+        prompt: Option<String>,
+
+        /// Read the prompt from FILE
+        #[arg(long, value_name = "FILE", group = "prompt_source")]
+        prompt_file: Option<std::path::PathBuf>,
+
+        /// Mount another silo read-only (SILO:/container/path[:ro]; use @main for the main worktree)
+        #[arg(long = "mount-silo", value_name = "SPEC")]
+        mount_silo: Vec<String>,
+
+        /// Arguments to pass to Claude Code (after --)
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Compare the results of agent silos side by side
+    #[command(
+        after_help = "NAMES can be branches, repo/branch, org/repo/branch, or silo IDs.\n\
+        Without names, compares all agent silos of the current repository."
+    )]
+    Compare {
+        /// Silos to compare
+        names: Vec<String>,
+    },
+    /// Summarize what an agent changed in a silo
+    #[command(
+        after_help = "NAME can be a branch, repo/branch, org/repo/branch, or a silo ID.\n\
+        Use '-' for the last used silo.\n\
+        With --pipe, the diff is piped into review_command from the config\n\
+        and the output is appended to the silo's notes."
+    )]
+    Review {
+        /// Silo name
+        name: String,
+
+        /// Pipe the diff into the configured review_command
+        #[arg(long)]
+        pipe: bool,
+    },
+}
+
 fn main() {
-    let cli = Cli::command().get_matches();
-    let cli = Cli::from_arg_matches(&cli).expect("clap argument parsing invariant");
+    let start = std::time::Instant::now();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).expect("clap argument parsing invariant");
+    if cli.timings {
+        timings::enable();
+    }
+
+    let verbosity = Verbosity::from_flags(cli.quiet, cli.verbose);
+    let force = cli.force > 0;
 
     // Initialize tracing with appropriate filter level
-    // RUST_LOG env var takes precedence, otherwise use --verbose flag
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        if cli.verbose {
-            EnvFilter::new("debug")
-        } else {
-            EnvFilter::new("warn")
-        }
-    });
+    // RUST_LOG env var takes precedence, otherwise use the verbosity level
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(verbosity.log_filter()));
 
     tracing_subscriber::registry()
         .with(fmt::layer().with_target(false).without_time())
@@ -209,10 +846,14 @@ fn main() {
     };
 
     let use_color = color::should_use_color(false);
+    git::set_retries(cli.retries);
+    let config_phase = timings::phase("config");
     let config = match &cli.config_file {
+        _ if cli.no_config => Ok(config::Config::disabled()),
         Some(path) => config::Config::load_file(path),
         None => config::Config::load(),
     };
+    drop(config_phase);
     let config = match config {
         Ok(c) => c,
         Err(e) => {
@@ -220,42 +861,402 @@ fn main() {
             std::process::exit(exit::ERROR);
         }
     };
+    match config.git_config() {
+        Ok(options) => git::set_config_options(options.to_vec()),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exit::ERROR);
+        }
+    }
+    match config.tracking_dir() {
+        Ok(dir) => process::configure(config.process_tracking(), dir),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exit::ERROR);
+        }
+    }
+    match config.storage_layout() {
+        Ok(layout) => {
+            let mut extra = roots::load(layout.primary());
+            // Known before the command runs, so the new silo is in storage
+            if let Commands::New {
+                worktree_dir: Some(dir),
+                ..
+            } = &command
+            {
+                extra.push(dir.clone());
+            }
+            silo::set_layout(layout.with_extra_roots(extra))
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exit::ERROR);
+        }
+    }
 
+    let command_phase = timings::phase("command");
+    // `exec --no-track` is meant for prompts and status lines, which run it
+    // constantly: it records nothing, not even metrics
+    let record_metrics = !matches!(command, Commands::Exec { no_track: true, .. });
+    // Commands that run something leave with its exit code
+    let mut exit_code = exit::SUCCESS;
     let result = match command {
-        Commands::New { branch, command } => {
-            commands::new::run(branch, &command, &config, cli.dry_run, cli.quiet)
+        Commands::New {
+            branch,
+            sandbox,
+            take_changes,
+            worktree_dir,
+            command,
+        } => {
+            let options = commands::new::NewOptions {
+                sandbox,
+                take_changes,
+                worktree_dir,
+            };
+            commands::new::run(branch, &command, &options, &config, cli.dry_run, verbosity)
+                .map(|code| exit_code = code)
+        }
+        Commands::Reuse { branch } => commands::reuse::run(branch, &config, cli.dry_run, verbosity),
+        Commands::List {
+            all,
+            repo,
+            summary,
+            user,
+            json,
+            limit,
+            page,
+            no_pager,
+            long,
+            format,
+            dirty,
+            clean,
+            active,
+            remote,
+            du,
+            mine,
+            porcelain,
+            csv,
+            fast,
+        } => {
+            let options = commands::list::ListOptions {
+                all,
+                repo,
+                summary,
+                user,
+                json,
+                limit,
+                page,
+                pager: !no_pager,
+                long,
+                format,
+                dirty,
+                clean,
+                active,
+                remote,
+                du,
+                mine,
+                porcelain,
+                csv,
+                fast,
+            };
+            commands::list::run(&options, &config, use_color, verbosity)
         }
-        Commands::List { all } => commands::list::run(all, use_color, cli.quiet),
-        Commands::Rm { name } => commands::rm::run(name, cli.dry_run, cli.force, cli.quiet),
-        Commands::Cd { name } => commands::cd::run(name, &config),
-        Commands::Exec { name, command } => commands::exec::run(name, &command, &config, cli.quiet),
-        Commands::Prune { all } => commands::prune::run(all, cli.dry_run, cli.force, cli.quiet),
-        Commands::Gc => commands::gc::run(cli.dry_run, cli.force, cli.quiet),
-        Commands::Rebase { name } => commands::rebase::run(name, cli.dry_run, cli.quiet),
-        Commands::Merge { name } => commands::merge::run(name, cli.dry_run, cli.quiet),
-        Commands::Reset { name } => commands::reset::run(name, cli.dry_run, cli.force, cli.quiet),
+        Commands::Rm {
+            json,
+            plan_file,
+            name,
+        } => commands::rm::run(
+            name,
+            &commands::rm::RmOptions {
+                json,
+                plan_file,
+                unlock: cli.force > 1,
+            },
+            cli.dry_run,
+            force,
+            verbosity,
+        ),
+        Commands::Cd { spawn, name } => {
+            commands::cd::run(name, spawn, &config, verbosity).map(|code| exit_code = code)
+        }
+        Commands::Code { print, name } => commands::code::run(name, print, &config, verbosity),
+        Commands::Exec {
+            verify,
+            tty,
+            no_tty,
+            capture,
+            no_track,
+            name,
+            command,
+        } => {
+            let terminal = match (tty, no_tty) {
+                (true, _) => runner::Terminal::Pty,
+                (_, true) => runner::Terminal::None,
+                _ => runner::Terminal::Inherit,
+            };
+            match capture {
+                Some(limit_kb) => {
+                    commands::exec::capture(name, &command, limit_kb, !no_track, &config)
+                }
+                None => commands::exec::run(
+                    name, &command, verify, terminal, !no_track, &config, verbosity,
+                ),
+            }
+            .map(|code| exit_code = code)
+        }
+        Commands::LastRun { name } => commands::last_run::run(name),
+        Commands::Policy {
+            no_rebase,
+            allow_rebase,
+            name,
+        } => {
+            let no_rebase = (no_rebase || allow_rebase).then_some(no_rebase);
+            commands::policy::run(name, no_rebase, verbosity)
+        }
+        Commands::Note {
+            edit,
+            clear,
+            name,
+            text,
+        } => {
+            let action = match (text, edit, clear) {
+                (Some(text), _, _) => commands::note::NoteAction::Set(text),
+                (None, true, _) => commands::note::NoteAction::Edit,
+                (None, _, true) => commands::note::NoteAction::Clear,
+                (None, false, false) => commands::note::NoteAction::Show,
+            };
+            commands::note::run(name, action, verbosity)
+        }
+        Commands::Verify { name } => commands::verify::run(name, &config, verbosity),
+        Commands::TestMatrix { jobs, json, names } => commands::test_matrix::run(
+            &names,
+            &commands::test_matrix::MatrixOptions { jobs, json },
+            &config,
+            use_color,
+            verbosity,
+        ),
+        Commands::Prune {
+            all,
+            older_than,
+            json,
+            plan_file,
+        } => commands::prune::run(
+            &commands::prune::PruneOptions {
+                all,
+                older_than,
+                json,
+                plan_file,
+                unlock: cli.force > 1,
+            },
+            &config,
+            cli.dry_run,
+            force,
+            verbosity,
+        ),
+        Commands::Gc { yes, json } => commands::gc::run(
+            &commands::gc::GcOptions { json, yes },
+            &config,
+            cli.dry_run,
+            force,
+            verbosity,
+        )
+        .map(|code| exit_code = code),
+        Commands::OrphanBranches {
+            all,
+            delete_merged,
+            json,
+        } => commands::orphan_branches::run(
+            &commands::orphan_branches::OrphanOptions {
+                all,
+                delete_merged,
+                json,
+            },
+            &config,
+            cli.dry_run,
+            force,
+            verbosity,
+        ),
+        Commands::VerifyStorage { fix } => {
+            commands::verify_storage::run(fix, cli.dry_run, verbosity)
+        }
+        Commands::Diff {
+            between,
+            uncommitted,
+            stat,
+            no_pager,
+        } => commands::diff::between(
+            &between[0],
+            &between[1],
+            &commands::diff::DiffOptions {
+                uncommitted,
+                stat,
+                pager: !no_pager,
+            },
+            use_color,
+            verbosity,
+        ),
+        Commands::Rebase {
+            name,
+            update_refs,
+            rebase_merges,
+        } => commands::rebase::run(
+            name,
+            git::RebaseOptions {
+                update_refs,
+                rebase_merges,
+            },
+            cli.dry_run,
+            force,
+            verbosity,
+        ),
+        Commands::Merge { name } => {
+            commands::merge::run(name, &config, cli.dry_run, force, verbosity)
+        }
+        Commands::Reset { name } => commands::reset::run(name, cli.dry_run, force, verbosity),
+        Commands::Doctor => commands::doctor::run(&config, verbosity),
+        Commands::Info {
+            name: Some(name),
+            json,
+            porcelain,
+            ..
+        } => commands::silo_info::run(&name, json, porcelain),
+        Commands::Info { paths, json, .. } => {
+            let source = match &cli.config_file {
+                _ if cli.no_config => commands::info::ConfigSource::Disabled,
+                Some(path) => commands::info::ConfigSource::File(path),
+                None => commands::info::ConfigSource::Default,
+            };
+            commands::info::run(&config, source, paths, json)
+        }
+        Commands::Complete {
+            for_command,
+            word,
+            format,
+        } => commands::complete::run(for_command.as_deref(), &word, format),
+        Commands::Metrics { no_disk_usage } => commands::metrics::run(!no_disk_usage),
+        Commands::Stats {
+            repo,
+            by_repo,
+            json,
+        } => commands::stats::run(&commands::stats::StatsOptions {
+            repo,
+            by_repo,
+            json,
+        }),
+        Commands::Resolve {
+            name,
+            explain,
+            json,
+        } => commands::resolve::run(name, explain, json),
+        Commands::Hooks { command } => match command {
+            HooksCommands::Install => commands::hooks::install(cli.dry_run, force, verbosity),
+            HooksCommands::Uninstall => commands::hooks::uninstall(cli.dry_run, verbosity),
+            HooksCommands::Run { hook, args: _ } => commands::hooks::run(&hook, &config),
+        },
         Commands::Shell { command } => match command {
-            ShellCommands::Init { shell } => commands::shell::init(shell),
-            ShellCommands::CompleteArgs { args } => {
-                commands::shell::complete_args(&args);
+            ShellCommands::Init { shell } => commands::shell::init(shell, &config),
+            ShellCommands::CompleteArgs { format, args } => {
+                commands::shell::complete_args(&args, format);
+                Ok(())
+            }
+            ShellCommands::CompleteDebug { args } => {
+                commands::shell::complete_debug(&args);
                 Ok(())
             }
         },
+        Commands::External { command } => match command {
+            ExternalCommands::Add { path } => commands::external::add(&path, verbosity),
+            ExternalCommands::Rm { path } => commands::external::remove(&path, verbosity),
+            ExternalCommands::List => commands::external::list(),
+        },
+        Commands::Agent { command } => match command {
+            AgentCommands::Spawn {
+                name,
+                prompt,
+                prompt_file,
+                mount_silo,
+                args,
+            } => {
+                let options = commands::agent::SpawnOptions {
+                    name,
+                    prompt,
+                    prompt_file,
+                    mounts: mount_silo,
+                    args,
+                };
+                commands::agent::spawn(&options, &config, cli.dry_run, verbosity)
+            }
+            AgentCommands::Matrix {
+                count,
+                name,
+                prompt,
+                prompt_file,
+                mount_silo,
+                args,
+            } => {
+                let options = commands::agent::SpawnOptions {
+                    name,
+                    prompt,
+                    prompt_file,
+                    mounts: mount_silo,
+                    args,
+                };
+                commands::agent::matrix(&options, count, &config, cli.dry_run, verbosity)
+            }
+            AgentCommands::Compare { names } => commands::agent::compare(&names, use_color),
+            AgentCommands::Review { name, pipe } => commands::agent::review(name, pipe, &config),
+        },
         Commands::Sandbox { command } => match command {
-            SandboxCommands::Claude { silo, args } => {
-                commands::sandbox::claude(silo, cli.dry_run, &args)
+            SandboxCommands::Claude {
+                silo,
+                mount_silo,
+                verify,
+                args,
+            } => {
+                let options = commands::sandbox::ClaudeOptions {
+                    mounts: mount_silo,
+                    verify,
+                    args,
+                };
+                commands::sandbox::claude(silo, &options, &config, cli.dry_run, verbosity)
+                    .map(|code| exit_code = code)
+            }
+            SandboxCommands::Transcript { silo, list, raw } => {
+                commands::sandbox::transcript(silo, list, raw)
+            }
+            SandboxCommands::Artifacts { silo, copy } => {
+                commands::sandbox::artifacts(silo, copy.as_deref(), verbosity)
             }
         },
     };
 
+    drop(command_phase);
+    timings::report(start.elapsed());
+
+    // Completion runs on every keypress, hooks on every commit and metrics
+    // would count itself
+    match matches.subcommand_name() {
+        Some("shell" | "complete" | "hooks" | "metrics") | None => {}
+        Some(_) if !record_metrics => {}
+        Some(name) => metrics::record_command(
+            name,
+            start.elapsed(),
+            result.is_ok() && exit_code == exit::SUCCESS,
+        ),
+    }
+
     if let Err(e) = result {
         eprintln!("Error: {}", e);
         // Use specific exit codes for different error types
         let exit_code = match e.as_str() {
+            s if error::is_transient(s) => exit::TRANSIENT,
             s if s.starts_with("Not in a git repository") => exit::NOT_FOUND,
             s if s.contains("not found") || s.contains("Not found") => exit::NOT_FOUND,
             _ => exit::ERROR,
         };
         std::process::exit(exit_code);
     }
+    if exit_code != exit::SUCCESS {
+        std::process::exit(exit_code);
+    }
 }
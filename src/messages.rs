@@ -0,0 +1,137 @@
+//! User-facing messages shared across commands.
+//!
+//! Commands that talk about the same thing (removing a silo, work that would
+//! be lost, an aborted prompt, a number of directories) build their output
+//! here, so the wording stays consistent between `rm`, `prune`, `gc` and
+//! `reset`, can be checked in one place, and could later be localized.
+
+use std::fmt::Display;
+
+/// Printed when the user declines a confirmation prompt.
+pub const ABORTED: &str = "Aborted.";
+
+/// Error for `rm`/`prune --json` without a dry run.
+pub const JSON_PLAN_NEEDS_DRY_RUN: &str =
+    "--json prints the removal plan without removing anything, so it needs --dry-run";
+
+/// Format a count with the singular or plural noun, e.g. "1 silo", "2 silos".
+pub fn count(n: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", n, if n == 1 { singular } else { plural })
+}
+
+/// Join items into prose, e.g. "a", "a and b", "a, b and c".
+pub fn and_list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// "1 silo" / "2 silos"
+pub fn silos(n: usize) -> String {
+    count(n, "silo", "silos")
+}
+
+/// Header listing why an operation on a silo is blocked, e.g.
+/// "Silo 'feature' cannot be removed:". Followed by one line per blocker.
+pub fn blocked(name: &str, done: &str) -> String {
+    format!("Silo '{}' cannot be {}:", name, done)
+}
+
+/// One line per blocker below a [`blocked`] header.
+pub fn blocker_line(blocker: impl Display) -> String {
+    format!("  - {}", blocker)
+}
+
+/// Hint shown after blockers when `--force` would override them.
+pub fn force_hint(action: &str) -> String {
+    format!("Use --force to {} anyway.", action)
+}
+
+/// Prompt for overriding blockers interactively.
+pub fn confirm_discard(action: &str) -> String {
+    format!("{} anyway? All changes will be lost.", action)
+}
+
+/// Prompt for removing a single silo.
+pub fn confirm_remove(name: &str) -> String {
+    format!("Remove silo '{}'?", name)
+}
+
+/// Printed after a silo was removed.
+pub fn removed(name: &str) -> String {
+    format!("Removed silo: {}", name)
+}
+
+/// Printed for each silo a dry run would remove.
+pub fn would_remove(name: &str) -> String {
+    format!("Would remove silo: {}", name)
+}
+
+/// Printed for each silo a dry run would skip because of blockers.
+pub fn would_skip(name: &str) -> String {
+    format!("Would skip silo: {} (blocked)", name)
+}
+
+/// Header before the names of silos skipped because of blockers.
+pub fn skipping_blocked(n: usize) -> String {
+    format!("Skipping {} with blockers:", silos(n))
+}
+
+/// Why a merged branch is kept: its upstream has commits that exist only on
+/// the remote, e.g. "upstream has 2 commits not in the local branch".
+pub fn upstream_only(n: u32) -> String {
+    format!(
+        "upstream has {} not in the local branch",
+        count(n as usize, "commit", "commits")
+    )
+}
+
+/// Short form of a commit hash for messages.
+pub fn short_commit(commit: &str) -> &str {
+    &commit[..12.min(commit.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_pluralizes() {
+        assert_eq!(silos(0), "0 silos");
+        assert_eq!(silos(1), "1 silo");
+        assert_eq!(count(1, "directory", "directories"), "1 directory");
+        assert_eq!(count(3, "directory", "directories"), "3 directories");
+    }
+
+    #[test]
+    fn test_and_list() {
+        let items = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(and_list(&[]), "");
+        assert_eq!(and_list(&items(&["a"])), "a");
+        assert_eq!(and_list(&items(&["a", "b"])), "a and b");
+        assert_eq!(and_list(&items(&["a", "b", "c"])), "a, b and c");
+    }
+
+    #[test]
+    fn test_blocked_messages() {
+        assert_eq!(blocked("feat", "reset"), "Silo 'feat' cannot be reset:");
+        assert_eq!(
+            blocker_line("Unmerged commits: 2"),
+            "  - Unmerged commits: 2"
+        );
+        assert_eq!(force_hint("remove"), "Use --force to remove anyway.");
+        assert_eq!(skipping_blocked(1), "Skipping 1 silo with blockers:");
+        assert_eq!(
+            upstream_only(1),
+            "upstream has 1 commit not in the local branch"
+        );
+    }
+
+    #[test]
+    fn test_short_commit() {
+        assert_eq!(short_commit("0123456789abcdef"), "0123456789ab");
+        assert_eq!(short_commit("abc"), "abc");
+    }
+}
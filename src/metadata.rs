@@ -0,0 +1,195 @@
+//! Atomic, corruption-tolerant metadata files.
+//!
+//! Everything silo records about a silo in its tracking directory (PID
+//! files, the last run, verify results, creation metadata, notes), and the
+//! repository registry, is written through here: to a temporary file next
+//! to the target, then renamed over it. Readers see the old or the new
+//! content, never a half-written file, even if silo is killed mid-write.
+//!
+//! Records are `key=value` lines, each ending in a newline. A record that
+//! doesn't parse, e.g. one truncated by a crash in an older version of silo,
+//! is ignored with a warning instead of being half-read.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tracing::warn;
+
+/// Write `contents` to `path` atomically: the file either keeps its old
+/// contents or has all of the new ones. The parent directory must exist.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let temp = temp_path(path);
+    let result = write_synced(&temp, contents.as_ref()).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Temporary file for writing `path`, unique to this write: threads of one
+/// process writing the same file don't share it. Starts with a dot so
+/// directory scans that look for silo names or PIDs skip it.
+fn temp_path(path: &Path) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{}.tmp-{}-{}", name, std::process::id(), n))
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents)?;
+    // Make sure the data is on disk before the rename makes it visible
+    file.sync_all()
+}
+
+/// A metadata record: an ordered list of `key=value` fields.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Record {
+    fields: Vec<(String, String)>,
+}
+
+impl Record {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a field, replacing an earlier value. Newlines in the value are
+    /// replaced by spaces, since each field is one line.
+    pub fn set(&mut self, key: &str, value: impl fmt::Display) -> &mut Self {
+        let value = value.to_string().replace('\n', " ");
+        match self.fields.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.fields.push((key.to_string(), value)),
+        }
+        self
+    }
+
+    /// Get the fields in order, as `(key, value)` pairs.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Get a field's value.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Get a field's value parsed as `T`. None if missing or malformed.
+    pub fn get_parsed<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key)?.parse().ok()
+    }
+
+    /// Parse a record. None if it's empty, doesn't end with a newline or has
+    /// a line without `=`: all signs of a truncated or foreign file.
+    pub fn parse(content: &str) -> Option<Self> {
+        if !content.ends_with('\n') {
+            return None;
+        }
+        let mut record = Record::new();
+        for line in content.lines() {
+            let (key, value) = line.split_once('=')?;
+            record.set(key, value);
+        }
+        Some(record)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.fields {
+            writeln!(f, "{}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a record atomically, creating its directory.
+pub fn store(path: &Path, record: &Record) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(path, record.to_string())
+}
+
+/// Load a record. None if the file doesn't exist; corrupted records are
+/// ignored with a warning.
+pub fn load(path: &Path) -> Option<Record> {
+    let content = fs::read(path).ok()?;
+    let record = String::from_utf8(content)
+        .ok()
+        .and_then(|content| Record::parse(&content));
+    if record.is_none() {
+        warn!(file = %path.display(), "Ignoring corrupted metadata");
+    }
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_round_trip() {
+        let mut record = Record::new();
+        record
+            .set("command", "sh -c 'a\nb'")
+            .set("exit_code", 3)
+            .set("exit_code", 4);
+        let content = record.to_string();
+        assert_eq!(content, "command=sh -c 'a b'\nexit_code=4\n");
+
+        let parsed = Record::parse(&content).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.get_parsed::<i32>("exit_code"), Some(4));
+        assert_eq!(parsed.get_parsed::<i32>("command"), None);
+        assert_eq!(parsed.get("missing"), None);
+    }
+
+    #[test]
+    fn test_values_may_contain_equals() {
+        let record = Record::parse("command=env A=1 make\n").unwrap();
+        assert_eq!(record.get("command"), Some("env A=1 make"));
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_records() {
+        assert_eq!(Record::parse(""), None);
+        assert_eq!(Record::parse("command=make\nexit_co"), None);
+        assert_eq!(Record::parse("command=make\ngarbage\n"), None);
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("nested/record");
+        let mut record = Record::new();
+        record.set("passed", true);
+
+        store(&path, &record).unwrap();
+        assert_eq!(load(&path), Some(record));
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        fs::write(&path, "passed=tr").unwrap();
+        assert_eq!(load(&path), None);
+        assert_eq!(load(&dir.join("missing")), None);
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+
+        write_atomic(&path, "old contents").unwrap();
+        write_atomic(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+}
@@ -0,0 +1,156 @@
+//! Counters behind `silo metrics`: commands run and silos removed.
+//!
+//! Stored as `{storage}/.metrics`, a metadata record of running totals that
+//! every silo process adds to under the storage lock. Gauges such as silo
+//! counts and disk usage are computed when metrics are read, so only
+//! things that happened need recording. Recording is best effort: a
+//! failure is logged and never fails the command that was measured.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::metadata::{self, Record};
+use crate::silo;
+use crate::storage::StorageLock;
+
+/// Name of the counters file in the storage directory.
+const METRICS_NAME: &str = ".metrics";
+
+/// Get the path of the counters file in a storage directory.
+pub fn metrics_path(base_dir: &Path) -> PathBuf {
+    base_dir.join(METRICS_NAME)
+}
+
+/// Totals for one command, e.g. `list`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandTotals {
+    pub command: String,
+    pub count: u64,
+    pub failures: u64,
+    pub duration: Duration,
+}
+
+/// Everything recorded so far.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Counters {
+    pub commands: Vec<CommandTotals>,
+    /// Silos removed, by the command that removed them
+    pub removed: Vec<(String, u64)>,
+}
+
+/// Load the counters recorded in a storage directory.
+pub fn load(base_dir: &Path) -> Counters {
+    metadata::load(&metrics_path(base_dir))
+        .map(|record| parse(&record))
+        .unwrap_or_default()
+}
+
+fn parse(record: &Record) -> Counters {
+    let mut counters = Counters::default();
+    for (key, _) in record.fields() {
+        if let Some(command) = key
+            .strip_prefix("command.")
+            .and_then(|k| k.strip_suffix(".count"))
+        {
+            let get = |field: &str| {
+                record
+                    .get_parsed::<u64>(&format!("command.{}.{}", command, field))
+                    .unwrap_or(0)
+            };
+            counters.commands.push(CommandTotals {
+                command: command.to_string(),
+                count: get("count"),
+                failures: get("failures"),
+                duration: Duration::from_millis(get("ms")),
+            });
+        } else if let Some(by) = key.strip_prefix("removed.") {
+            let removed = record.get_parsed(key).unwrap_or(0);
+            counters.removed.push((by.to_string(), removed));
+        }
+    }
+    counters
+}
+
+/// Add `amount` to a counter.
+fn add(record: &mut Record, key: &str, amount: u64) {
+    let total = record.get_parsed::<u64>(key).unwrap_or(0) + amount;
+    record.set(key, total);
+}
+
+/// Update the counters in the primary storage directory. Skipped when it
+/// doesn't exist yet: nothing worth counting has happened.
+fn update(change: impl FnOnce(&mut Record)) {
+    let result = silo::get_silo_base_dir().and_then(|base_dir| {
+        if !base_dir.is_dir() {
+            return Ok(());
+        }
+        let _lock = StorageLock::acquire(&base_dir)?;
+        let path = metrics_path(&base_dir);
+        let mut record = metadata::load(&path).unwrap_or_default();
+        change(&mut record);
+        metadata::store(&path, &record).map_err(|e| format!("Failed to write metrics: {}", e))
+    });
+    if let Err(e) = result {
+        debug!("Not recording metrics: {}", e);
+    }
+}
+
+/// Record one run of `command` and how long it took.
+pub fn record_command(command: &str, duration: Duration, success: bool) {
+    update(|record| {
+        add(record, &format!("command.{}.count", command), 1);
+        add(
+            record,
+            &format!("command.{}.ms", command),
+            duration.as_millis() as u64,
+        );
+        if !success {
+            add(record, &format!("command.{}.failures", command), 1);
+        }
+    });
+}
+
+/// Record that `by` (rm, prune or gc) removed `count` silos.
+pub fn record_removed(by: &str, count: usize) {
+    if count > 0 {
+        update(|record| add(record, &format!("removed.{}", by), count as u64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_counters() {
+        let mut record = Record::new();
+        add(&mut record, "command.list.count", 2);
+        add(&mut record, "command.list.ms", 1500);
+        add(&mut record, "removed.gc", 1);
+        add(&mut record, "removed.gc", 2);
+        add(&mut record, "command.rm.count", 1);
+        add(&mut record, "command.rm.failures", 1);
+
+        let counters = parse(&record);
+        assert_eq!(
+            counters.commands,
+            vec![
+                CommandTotals {
+                    command: "list".to_string(),
+                    count: 2,
+                    failures: 0,
+                    duration: Duration::from_millis(1500),
+                },
+                CommandTotals {
+                    command: "rm".to_string(),
+                    count: 1,
+                    failures: 1,
+                    duration: Duration::ZERO,
+                },
+            ]
+        );
+        assert_eq!(counters.removed, vec![("gc".to_string(), 3)]);
+    }
+}
@@ -4,6 +4,7 @@
 //! - Unique storage path generation (with hash suffix for collision avoidance)
 //! - Minimal display name generation (shortest unique name)
 //! - Name resolution from user input
+//! - Canonical silo IDs (`{repo-hash}:{name}`) that never change with the silo set
 
 use crate::silo::Silo;
 use sha2::{Digest, Sha256};
@@ -18,6 +19,37 @@ pub enum ResolveResult<'a> {
     Ambiguous(Vec<&'a Silo>),
 }
 
+/// The step of name resolution that decided the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolveStep {
+    /// The input is a canonical ID, which matches exactly or not at all
+    CanonicalId,
+    /// The current repository has a silo with exactly the name, or only one
+    /// silo matching it
+    CurrentRepo,
+    /// The input was matched against every silo's fully-qualified name
+    QualifiedName,
+}
+
+/// A name resolution and how it was reached, for `silo resolve --explain`.
+#[derive(Debug)]
+pub struct Resolution<'a> {
+    /// `(hash, name)` if the input was parsed as a canonical ID
+    pub id: Option<(String, String)>,
+    /// The input's `/`-separated parts, silo name last. A quoted name is
+    /// one part, slashes and all
+    pub parts: Vec<String>,
+    /// Whether the input was quoted as `[QUALIFIER]:NAME`
+    pub quoted: bool,
+    /// Silos with the name in the current repository, if in a repository
+    /// and the input isn't an ID
+    pub current_repo_matches: Option<Vec<&'a Silo>>,
+    /// Silos whose qualified name matched, if that step ran
+    pub qualified_matches: Option<Vec<&'a Silo>>,
+    pub step: ResolveStep,
+    pub result: ResolveResult<'a>,
+}
+
 /// Generate a short hash from a path for unique directory naming.
 /// Returns first 8 characters of SHA-256 hash.
 /// Uses raw bytes from the OS path representation to avoid lossy conversion.
@@ -53,6 +85,63 @@ pub fn silo_storage_path(
     base_dir.join(storage_name).join(branch)
 }
 
+/// The branch for `name` in `namespace` (e.g. "alice/"): the name with the
+/// namespace prefixed, unless it already has it.
+pub fn namespaced_branch(name: &str, namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) if !name.starts_with(namespace) => format!("{}{}", namespace, name),
+        _ => name.to_string(),
+    }
+}
+
+/// The silo name for `branch`: the branch without `namespace`, so display
+/// names don't repeat it for every silo.
+pub fn strip_namespace<'a>(branch: &'a str, namespace: Option<&str>) -> &'a str {
+    namespace
+        .and_then(|namespace| branch.strip_prefix(namespace))
+        .filter(|name| !name.is_empty())
+        .unwrap_or(branch)
+}
+
+/// Separator between the repo hash and the silo name in a canonical ID.
+const ID_SEPARATOR: char = ':';
+
+/// Generate the canonical ID for a silo.
+/// Format: `{hash}:{name}` where hash is the main worktree's path hash.
+/// Unlike display names, the ID does not depend on which other silos exist.
+pub fn silo_id(main_worktree: &Path, name: &str) -> String {
+    format!("{}{}{}", path_hash(main_worktree), ID_SEPARATOR, name)
+}
+
+/// Split a canonical silo ID into (hash, name).
+/// Returns None if the input is not shaped like an ID.
+fn parse_silo_id(input: &str) -> Option<(&str, &str)> {
+    let (hash, name) = input.split_once(ID_SEPARATOR)?;
+    let is_hash = hash.len() == 8 && hash.bytes().all(|b| b.is_ascii_hexdigit());
+    (is_hash && !name.is_empty()).then_some((hash, name))
+}
+
+/// Split a quoted name, `[QUALIFIER]:NAME`, into the repository qualifier
+/// and the silo name. Branch names can't contain `:`, so everything after it
+/// is the name, slashes included.
+fn parse_quoted_name(input: &str) -> Option<(&str, &str)> {
+    let (qualifier, name) = input.split_once(ID_SEPARATOR)?;
+    (!name.is_empty()).then_some((qualifier, name))
+}
+
+/// Quote a display name whose silo name has slashes, so it can't be read as
+/// a repository qualifier: "repo/feature/x" becomes "repo:feature/x" and
+/// "feature/x" becomes ":feature/x". Other display names are unchanged.
+pub fn quote_display_name(display: &str, silo: &Silo) -> String {
+    if !silo.name.contains('/') {
+        return display.to_string();
+    }
+    let qualifier = display
+        .strip_suffix(silo.name.as_str())
+        .map_or("", |q| q.strip_suffix('/').unwrap_or(q));
+    format!("{}{}{}", qualifier, ID_SEPARATOR, silo.name)
+}
+
 /// Extract path components from a path, bottom-up (child first).
 /// For `/a/b/c/repo`, returns `["repo", "c", "b", "a"]`.
 /// Skips components that are not valid UTF-8.
@@ -72,6 +161,30 @@ fn path_components(path: &Path) -> Vec<String> {
     components
 }
 
+/// Check if a repository path matches a user-provided repo filter.
+/// The filter is compared against trailing path components, so `repo`,
+/// `org/repo` and `/full/path/org/repo` all match `/full/path/org/repo`.
+pub fn repo_matches(repo_path: &Path, filter: &str) -> bool {
+    let wanted: Vec<&str> = filter
+        .trim_matches('/')
+        .split('/')
+        .filter(|p| !p.is_empty())
+        .collect();
+    if wanted.is_empty() {
+        return false;
+    }
+
+    let components = path_components(repo_path);
+    if wanted.len() > components.len() {
+        return false;
+    }
+    wanted
+        .iter()
+        .rev()
+        .zip(components.iter())
+        .all(|(w, c)| *w == c)
+}
+
 /// Generate minimal display names for a set of silos.
 /// Returns a vector of display names in the same order as input silos.
 ///
@@ -165,6 +278,12 @@ fn build_display_name(name: &str, repo_components: &[String], depth: usize) -> S
     format!("{}/{}", path_parts.join("/"), name)
 }
 
+/// Build the longest display name for a silo (all repo path components).
+pub fn qualified_name(silo: &Silo) -> String {
+    let components = path_components(&silo.main_worktree);
+    build_display_name(&silo.name, &components, components.len())
+}
+
 /// Resolve a user-provided name to a silo.
 /// If `current_repo` is Some, prioritize matches from that repo.
 ///
@@ -172,61 +291,121 @@ fn build_display_name(name: &str, repo_components: &[String], depth: usize) -> S
 /// - Just a silo name: "feature"
 /// - Repo + name: "repoA/feature"
 /// - Parent + repo + name: "org/repoA/feature"
+/// - Canonical ID: "1a2b3c4d:feature"
+/// - Quoted name: ":feature/x" or "repoA:feature/x", for names with slashes
+///
+/// An unquoted name with slashes matches both ways: "feature/x" is the silo
+/// "feature/x" as well as the silo "x" in a repository "feature". In the
+/// current repository an exact name wins.
 pub fn resolve_name<'a>(
     input: &str,
     silos: &'a [Silo],
     current_repo: Option<PathBuf>,
 ) -> ResolveResult<'a> {
-    if silos.is_empty() {
-        return ResolveResult::NotFound;
+    explain_name(input, silos, current_repo).result
+}
+
+/// Resolve a user-provided name like `resolve_name`, recording each step.
+pub fn explain_name<'a>(
+    input: &str,
+    silos: &'a [Silo],
+    current_repo: Option<PathBuf>,
+) -> Resolution<'a> {
+    let quoted = parse_silo_id(input)
+        .is_none()
+        .then(|| parse_quoted_name(input))
+        .flatten();
+    let parts: Vec<&str> = match quoted {
+        Some(("", name)) => vec![name],
+        Some((qualifier, name)) => qualifier.split('/').chain([name]).collect(),
+        None => input.split('/').collect(),
+    };
+    let mut resolution = Resolution {
+        id: None,
+        parts: parts.iter().map(|p| p.to_string()).collect(),
+        quoted: quoted.is_some(),
+        current_repo_matches: None,
+        qualified_matches: None,
+        step: ResolveStep::QualifiedName,
+        result: ResolveResult::NotFound,
+    };
+
+    // Canonical IDs are exact and bypass display-name matching
+    if let Some((hash, name)) = parse_silo_id(input) {
+        resolution.id = Some((hash.to_string(), name.to_string()));
+        resolution.step = ResolveStep::CanonicalId;
+        resolution.result = silos
+            .iter()
+            .find(|s| s.name == name && path_hash(&s.main_worktree) == hash)
+            .map_or(ResolveResult::NotFound, ResolveResult::Found);
+        return resolution;
     }
 
-    let parts: Vec<&str> = input.split('/').collect();
-    let silo_name = parts.last().copied().unwrap_or(input);
+    let silo_name = quoted.map_or(input, |(_, name)| name);
 
     // If we're in a repo, first try to find a match there
     if let Some(ref repo_path) = current_repo {
         let repo_matches: Vec<&Silo> = silos
             .iter()
-            .filter(|s| &s.main_worktree == repo_path && s.name == silo_name)
+            .filter(|s| &s.main_worktree == repo_path && matches_input(s, input, quoted))
             .collect();
 
-        if repo_matches.len() == 1 {
-            return ResolveResult::Found(repo_matches[0]);
+        // "feature/x" is the silo of that branch before it's the silo "x" of
+        // a repository called "feature"
+        let exact = repo_matches.iter().find(|s| s.name == silo_name);
+        let unique = exact
+            .or((repo_matches.len() == 1).then(|| &repo_matches[0]))
+            .copied();
+        resolution.current_repo_matches = Some(repo_matches);
+        if let Some(silo) = unique {
+            resolution.step = ResolveStep::CurrentRepo;
+            resolution.result = ResolveResult::Found(silo);
+            return resolution;
         }
     }
 
-    // Generate display names for all silos (minimal names for resolution)
-    let display_names = generate_display_names(silos, false);
-
-    // Find silos whose display name matches the input (or ends with the input)
+    // Match against fully-qualified names rather than the current minimal
+    // display names, so a name that was once shown (e.g. "repoA/feature")
+    // keeps resolving after the set of silos shrinks.
     let matches: Vec<&Silo> = silos
         .iter()
-        .enumerate()
-        .filter(|(i, _)| {
-            let display = &display_names[*i];
-            // Exact match or the display name ends with the provided parts
-            display == input || matches_suffix(display, &parts)
-        })
-        .map(|(_, silo)| silo)
+        .filter(|s| matches_input(s, input, quoted))
         .collect();
 
-    match matches.len() {
+    resolution.result = match matches.len() {
         0 => ResolveResult::NotFound,
         1 => ResolveResult::Found(matches[0]),
-        _ => ResolveResult::Ambiguous(matches),
+        _ => ResolveResult::Ambiguous(matches.clone()),
+    };
+    resolution.qualified_matches = Some(matches);
+    resolution
+}
+
+/// Check if the input names the silo: its name, optionally qualified by
+/// trailing components of its repository path. The name is matched whole,
+/// so "x" doesn't match the silo "feature/x" but "repo/feature/x" does.
+fn matches_input(silo: &Silo, input: &str, quoted: Option<(&str, &str)>) -> bool {
+    match quoted {
+        Some((qualifier, name)) => silo.name == name && matches_suffix(silo, qualifier),
+        None => {
+            input == silo.name
+                || input
+                    .strip_suffix(silo.name.as_str())
+                    .and_then(|qualifier| qualifier.strip_suffix('/'))
+                    .is_some_and(|qualifier| matches_suffix(silo, qualifier))
+        }
     }
 }
 
-/// Check if display name matches the given parts as a suffix.
-/// "org/repo/name" matches ["name"], ["repo", "name"], ["org", "repo", "name"]
-fn matches_suffix(display: &str, parts: &[&str]) -> bool {
-    let display_parts: Vec<&str> = display.split('/').collect();
-    if parts.len() > display_parts.len() {
-        return false;
+/// Check if the silo's repository path ends with the `/`-separated qualifier.
+/// "/org/repo" matches "", "repo" and "org/repo"
+fn matches_suffix(silo: &Silo, qualifier: &str) -> bool {
+    if qualifier.is_empty() {
+        return true;
     }
-    let start = display_parts.len() - parts.len();
-    display_parts[start..] == *parts
+    let components = path_components(&silo.main_worktree);
+    let parts: Vec<&str> = qualifier.rsplit('/').collect();
+    parts.len() <= components.len() && parts.iter().zip(&components).all(|(p, c)| p == c)
 }
 
 #[cfg(test)]
@@ -265,6 +444,18 @@ mod tests {
         assert_ne!(hash1, hash2);
     }
 
+    #[test]
+    fn test_namespaced_branch() {
+        assert_eq!(namespaced_branch("fix", Some("alice/")), "alice/fix");
+        assert_eq!(namespaced_branch("alice/fix", Some("alice/")), "alice/fix");
+        assert_eq!(namespaced_branch("fix", None), "fix");
+
+        assert_eq!(strip_namespace("alice/fix", Some("alice/")), "fix");
+        assert_eq!(strip_namespace("bob/fix", Some("alice/")), "bob/fix");
+        assert_eq!(strip_namespace("alice/", Some("alice/")), "alice/");
+        assert_eq!(strip_namespace("alice/fix", None), "alice/fix");
+    }
+
     #[test]
     fn test_silo_storage_path() {
         let path = silo_storage_path(
@@ -288,6 +479,63 @@ mod tests {
         assert_eq!(components, vec!["repo", "c", "b", "a"]);
     }
 
+    #[test]
+    fn test_silo_id_format() {
+        let id = silo_id(Path::new("/projects/repoA"), "feature");
+        let hash = path_hash(Path::new("/projects/repoA"));
+        assert_eq!(id, format!("{}:feature", hash));
+    }
+
+    #[test]
+    fn test_parse_silo_id() {
+        assert_eq!(
+            parse_silo_id("1a2b3c4d:feature/x"),
+            Some(("1a2b3c4d", "feature/x"))
+        );
+        assert_eq!(parse_silo_id("feature"), None);
+        assert_eq!(parse_silo_id("nothex!!:feature"), None);
+        assert_eq!(parse_silo_id("1a2b3c4d:"), None);
+    }
+
+    #[test]
+    fn test_resolve_by_silo_id() {
+        let silos = vec![
+            make_silo("/projects/repoA", "repoA", "feature"),
+            make_silo("/projects/repoB", "repoB", "feature"),
+        ];
+        let id = silo_id(Path::new("/projects/repoB"), "feature");
+        let result = resolve_name(&id, &silos, Some(PathBuf::from("/projects/repoA")));
+        assert!(
+            matches!(result, ResolveResult::Found(s) if s.main_worktree == Path::new("/projects/repoB"))
+        );
+
+        let missing = silo_id(Path::new("/projects/repoC"), "feature");
+        assert!(matches!(
+            resolve_name(&missing, &silos, None),
+            ResolveResult::NotFound
+        ));
+    }
+
+    #[test]
+    fn test_repo_matches_name_only() {
+        assert!(repo_matches(Path::new("/org/repo"), "repo"));
+        assert!(!repo_matches(Path::new("/org/repo"), "other"));
+    }
+
+    #[test]
+    fn test_repo_matches_qualified() {
+        assert!(repo_matches(Path::new("/work/org/repo"), "org/repo"));
+        assert!(!repo_matches(Path::new("/work/org2/repo"), "org/repo"));
+        assert!(repo_matches(Path::new("/work/org/repo"), "/work/org/repo"));
+    }
+
+    #[test]
+    fn test_repo_matches_rejects_partial_component_and_empty() {
+        assert!(!repo_matches(Path::new("/org/myrepo"), "repo"));
+        assert!(!repo_matches(Path::new("/org/repo"), ""));
+        assert!(!repo_matches(Path::new("/repo"), "a/b/repo"));
+    }
+
     #[test]
     fn test_display_names_unique_branches() {
         let silos = vec![
@@ -386,7 +634,7 @@ mod tests {
         let current = PathBuf::from("/projects/repoA");
         let result = resolve_name("feature", &silos, Some(current));
         assert!(
-            matches!(result, ResolveResult::Found(s) if s.main_worktree == PathBuf::from("/projects/repoA"))
+            matches!(result, ResolveResult::Found(s) if s.main_worktree == Path::new("/projects/repoA"))
         );
     }
 
@@ -398,7 +646,7 @@ mod tests {
         ];
         let result = resolve_name("repoB/feature", &silos, None);
         assert!(
-            matches!(result, ResolveResult::Found(s) if s.main_worktree == PathBuf::from("/projects/repoB"))
+            matches!(result, ResolveResult::Found(s) if s.main_worktree == Path::new("/projects/repoB"))
         );
     }
 
@@ -419,6 +667,25 @@ mod tests {
         assert!(matches!(result, ResolveResult::NotFound));
     }
 
+    #[test]
+    fn test_resolve_qualified_name_after_set_shrinks() {
+        // Only one "feature" left, so its minimal display name is just "feature",
+        // but the previously shown "repoA/feature" must still resolve.
+        let silos = vec![make_silo("/projects/repoA", "repoA", "feature")];
+        assert!(matches!(
+            resolve_name("repoA/feature", &silos, None),
+            ResolveResult::Found(_)
+        ));
+        assert!(matches!(
+            resolve_name("projects/repoA/feature", &silos, None),
+            ResolveResult::Found(_)
+        ));
+        assert!(matches!(
+            resolve_name("repoB/feature", &silos, None),
+            ResolveResult::NotFound
+        ));
+    }
+
     #[test]
     fn test_resolve_deeply_qualified() {
         let silos = vec![
@@ -427,8 +694,92 @@ mod tests {
         ];
         let result = resolve_name("org2/repo/feature", &silos, None);
         assert!(
-            matches!(result, ResolveResult::Found(s) if s.main_worktree == PathBuf::from("/org2/repo"))
+            matches!(result, ResolveResult::Found(s) if s.main_worktree == Path::new("/org2/repo"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_slashed_name_prefers_exact_name_in_current_repo() {
+        // "feature/x" also reads as the silo "x" of a repository "feature"
+        let silos = vec![
+            make_silo("/projects/feature", "feature", "x"),
+            make_silo("/projects/repoA", "repoA", "feature/x"),
+            make_silo("/projects/repoA", "repoA", "x"),
+        ];
+        let current = Some(PathBuf::from("/projects/repoA"));
+        assert!(matches!(
+            resolve_name("feature/x", &silos, current.clone()),
+            ResolveResult::Found(s) if s.name == "feature/x"
+        ));
+        assert!(matches!(
+            resolve_name("feature/x", &silos, None),
+            ResolveResult::Ambiguous(v) if v.len() == 2
+        ));
+        // The last part alone names only the silo "x"
+        assert!(matches!(
+            resolve_name("x", &silos, current),
+            ResolveResult::Found(s) if s.name == "x" && s.repo_name == "repoA"
+        ));
+    }
+
+    #[test]
+    fn test_resolve_qualified_slashed_name() {
+        let silos = vec![
+            make_silo("/projects/repoA", "repoA", "feature/x"),
+            make_silo("/projects/repoB", "repoB", "feature/x"),
+        ];
+        let result = resolve_name("repoB/feature/x", &silos, None);
+        assert!(
+            matches!(result, ResolveResult::Found(s) if s.main_worktree == Path::new("/projects/repoB"))
+        );
+        // A qualifier names the repository, not another current-repo silo
+        let result = resolve_name(
+            "repoB/feature/x",
+            &silos,
+            Some(PathBuf::from("/projects/repoA")),
+        );
+        assert!(
+            matches!(result, ResolveResult::Found(s) if s.main_worktree == Path::new("/projects/repoB"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_quoted_name() {
+        let silos = vec![
+            make_silo("/projects/feature", "feature", "x"),
+            make_silo("/projects/repoA", "repoA", "feature/x"),
+        ];
+        let resolution = explain_name(":feature/x", &silos, None);
+        assert!(resolution.quoted);
+        assert_eq!(resolution.parts, vec!["feature/x"]);
+        assert!(matches!(
+            resolution.result,
+            ResolveResult::Found(s) if s.repo_name == "repoA"
+        ));
+        assert!(matches!(
+            resolve_name("projects/repoA:feature/x", &silos, None),
+            ResolveResult::Found(s) if s.repo_name == "repoA"
+        ));
+        assert!(matches!(
+            resolve_name("feature:x", &silos, None),
+            ResolveResult::Found(s) if s.repo_name == "feature"
+        ));
+        assert!(matches!(
+            resolve_name("repoB:feature/x", &silos, None),
+            ResolveResult::NotFound
+        ));
+    }
+
+    #[test]
+    fn test_quote_display_name() {
+        let slashed = make_silo("/projects/repoA", "repoA", "feature/x");
+        let plain = make_silo("/projects/repoA", "repoA", "feature");
+        assert_eq!(
+            quote_display_name("repoA/feature/x", &slashed),
+            "repoA:feature/x"
         );
+        assert_eq!(quote_display_name("feature/x", &slashed), ":feature/x");
+        assert_eq!(quote_display_name("repoA/feature", &plain), "repoA/feature");
     }
 
     #[test]
@@ -0,0 +1,76 @@
+//! Free-form markdown notes kept per silo in its tracking directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::metadata;
+use crate::process;
+use crate::storage::FileLock;
+
+/// Get the path of a silo's notes file.
+pub fn notes_path(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("notes.md")
+}
+
+/// Append a section with a heading to a silo's notes.
+pub fn append(silo_path: &Path, heading: &str, body: &str) -> Result<PathBuf, String> {
+    let path = notes_path(silo_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create tracking directory: {}", e))?;
+    }
+
+    // Held across the read and the rewrite, so concurrent appends (e.g.
+    // reviews of several silos finishing at once) don't drop each other
+    let lock_path = path.with_file_name(".notes.md.lock");
+    let _lock = FileLock::acquire(&lock_path)
+        .map_err(|e| format!("Failed to lock notes {}: {}", path.display(), e))?;
+
+    let mut notes = match fs::read_to_string(&path) {
+        Ok(notes) => notes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read notes {}: {}", path.display(), e)),
+    };
+    notes.push_str(&format!("## {}\n\n{}\n\n", heading, body.trim_end()));
+    // Rewritten whole, so an interrupted write can't leave half a section
+    metadata::write_atomic(&path, notes).map_err(|e| format!("Failed to write notes: {}", e))?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_adds_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let silo = dir.path().join("feature");
+
+        append(&silo, "First", "one\n").unwrap();
+        let path = append(&silo, "Second", "two").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## First\n\none\n\n## Second\n\ntwo\n\n"
+        );
+    }
+
+    #[test]
+    fn test_concurrent_appends_keep_every_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let silo = dir.path().join("feature");
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let silo = &silo;
+                scope.spawn(move || append(silo, &format!("Review {}", i), "ok").unwrap());
+            }
+        });
+
+        let notes = fs::read_to_string(notes_path(&silo)).unwrap();
+        for i in 0..8 {
+            assert!(notes.contains(&format!("## Review {}\n", i)), "{}", notes);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Output policy shared by all commands.
+//!
+//! The global `-q` and `-v` flags select a single level that decides both what
+//! silo prints itself and how much of git's output is shown:
+//! - quiet (`-q`): errors only
+//! - normal: progress messages, plus git's output for commands that change
+//!   the repository (creating worktrees, deleting branches, ...)
+//! - verbose (`-v`): also debug logging, including every git command run
+//! - debug (`-vv`): also trace logging, including git's stderr for every
+//!   command, even read-only queries
+
+/// How much output silo produces.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only errors
+    Quiet,
+    /// Progress messages and output of git commands that change things
+    #[default]
+    Normal,
+    /// Debug logging
+    Verbose,
+    /// Trace logging and full git stderr
+    Debug,
+}
+
+impl Verbosity {
+    /// Determine the level from the `-q` flag and the number of `-v` flags.
+    /// `-q` wins over `-v`.
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+
+    /// Whether normal output should be suppressed.
+    pub fn is_quiet(self) -> bool {
+        self == Verbosity::Quiet
+    }
+
+    /// Whether git's own output is shown for commands that change things.
+    pub fn shows_git_output(self) -> bool {
+        self >= Verbosity::Normal
+    }
+
+    /// Tracing filter used when RUST_LOG is not set.
+    pub fn log_filter(self) -> &'static str {
+        match self {
+            Verbosity::Quiet => "error",
+            Verbosity::Normal => "warn",
+            Verbosity::Verbose => "debug",
+            Verbosity::Debug => "trace",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_flags() {
+        assert_eq!(Verbosity::from_flags(false, 0), Verbosity::Normal);
+        assert_eq!(Verbosity::from_flags(false, 1), Verbosity::Verbose);
+        assert_eq!(Verbosity::from_flags(false, 2), Verbosity::Debug);
+        assert_eq!(Verbosity::from_flags(false, 5), Verbosity::Debug);
+        assert_eq!(Verbosity::from_flags(true, 2), Verbosity::Quiet);
+    }
+
+    #[test]
+    fn test_levels_are_ordered() {
+        assert!(Verbosity::Quiet.is_quiet());
+        assert!(!Verbosity::Quiet.shows_git_output());
+        assert!(Verbosity::Normal.shows_git_output());
+        assert!(Verbosity::Debug.shows_git_output());
+        assert_eq!(Verbosity::Debug.log_filter(), "trace");
+    }
+}
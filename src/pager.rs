@@ -0,0 +1,93 @@
+//! Paging long output, as git does: stdout is sent through `$SILO_PAGER`
+//! or `$PAGER` (default `less`) while a [`Pager`] is alive.
+//!
+//! `less` gets `LESS=FRX` unless the user set `LESS`, so output that fits on
+//! one screen is printed as is and colors pass through.
+
+#[cfg(unix)]
+use std::io::Write;
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+#[cfg(unix)]
+use std::process::{Child, Command, Stdio};
+
+/// Default pager command.
+#[cfg(unix)]
+const DEFAULT_PAGER: &str = "less";
+
+/// The pager command to use, if paging isn't turned off with an empty
+/// pager or `cat`.
+#[cfg(unix)]
+fn pager_command() -> Option<String> {
+    let pager = ["SILO_PAGER", "PAGER"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let pager = pager.trim();
+    (!pager.is_empty() && pager != "cat").then(|| pager.to_string())
+}
+
+/// A running pager that stdout is redirected to. Dropping it restores
+/// stdout and waits for the user to quit the pager.
+#[cfg(unix)]
+pub struct Pager {
+    child: Child,
+    /// The original stdout, restored on drop
+    stdout: OwnedFd,
+}
+
+/// Start paging stdout. Call only when stdout is a terminal. Returns None
+/// if paging is turned off or the pager can't be started, in which case
+/// output goes to the terminal as usual.
+#[cfg(unix)]
+pub fn start() -> Option<Pager> {
+    let command = pager_command()?;
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", &command]).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        cmd.env("LESS", "FRX");
+    }
+    let child = cmd.spawn().ok()?;
+    let pipe = child.stdin.as_ref()?.as_raw_fd();
+
+    let _ = std::io::stdout().flush();
+    // SAFETY: dup and dup2 on valid descriptors; the duplicate is owned
+    let stdout = unsafe {
+        let saved = libc::dup(libc::STDOUT_FILENO);
+        if saved == -1 {
+            return None;
+        }
+        if libc::dup2(pipe, libc::STDOUT_FILENO) == -1 {
+            libc::close(saved);
+            return None;
+        }
+        // Quitting the pager early should end silo quietly, as with git,
+        // instead of failing on the next write
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        OwnedFd::from_raw_fd(saved)
+    };
+    Some(Pager { child, stdout })
+}
+
+#[cfg(unix)]
+impl Drop for Pager {
+    fn drop(&mut self) {
+        let _ = std::io::stdout().flush();
+        // SAFETY: both descriptors are valid; this closes our copy of the
+        // pipe so the pager sees the end of its input
+        unsafe {
+            libc::dup2(self.stdout.as_raw_fd(), libc::STDOUT_FILENO);
+        }
+        drop(self.child.stdin.take());
+        let _ = self.child.wait();
+    }
+}
+
+/// Paging is only supported on Unix.
+#[cfg(not(unix))]
+pub struct Pager;
+
+#[cfg(not(unix))]
+pub fn start() -> Option<Pager> {
+    None
+}
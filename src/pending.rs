@@ -0,0 +1,104 @@
+//! Silos that a `new` (or agent spawn) is still creating.
+//!
+//! Between creating the silo's directories and moving changes into it, a
+//! half-made silo can look like an empty repo directory, stale tracking data
+//! or a clean silo, all of which `gc` and `prune` remove. So `new` leaves a
+//! marker with its PID in the silo's tracking directory while it works, and
+//! removal skips silos whose marker belongs to a running process. Markers of
+//! processes that died (e.g. a `new` that was killed) are ignored.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::{self, Record};
+use crate::process;
+
+/// Name of the marker in the silo's tracking directory.
+const MARKER_NAME: &str = "creating";
+
+/// Marks a silo as being created until dropped.
+#[derive(Debug)]
+pub struct Pending {
+    marker: PathBuf,
+}
+
+impl Pending {
+    /// Mark a silo as being created by this process. Write this under the
+    /// storage lock, before creating any of the silo's directories.
+    pub fn start(silo_path: &Path) -> Result<Self, String> {
+        let marker = marker_path(silo_path);
+        let mut record = Record::new();
+        record.set("pid", std::process::id());
+        metadata::store(&marker, &record)
+            .map_err(|e| format!("Failed to mark silo as being created: {}", e))?;
+        Ok(Self { marker })
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.marker);
+    }
+}
+
+/// Get the path of the marker of a silo being created.
+fn marker_path(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join(MARKER_NAME)
+}
+
+/// Whether a running process is still creating the silo at `silo_path`.
+pub fn is_pending(silo_path: &Path) -> bool {
+    is_pending_tracking_dir(&process::tracking_dir(silo_path))
+}
+
+/// Whether a tracking directory belongs to a silo that a running process is
+/// still creating. Such a directory exists before the silo does.
+pub fn is_pending_tracking_dir(tracking_dir: &Path) -> bool {
+    metadata::load(&tracking_dir.join(MARKER_NAME))
+        .and_then(|record| record.get_parsed::<u32>("pid"))
+        .is_some_and(process::is_running)
+}
+
+/// Whether a running process is creating any silo in a repo directory of
+/// the storage directory.
+pub fn any_pending(repo_silo_dir: &Path) -> bool {
+    fs::read_dir(process::repo_tracking_dir(repo_silo_dir)).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| is_pending_tracking_dir(&entry.path()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo_dir = dir.path().join("repo-abc123");
+        let silo_path = repo_dir.join("feature");
+
+        assert!(!is_pending(&silo_path));
+        let pending = Pending::start(&silo_path).unwrap();
+        assert!(is_pending(&silo_path));
+        assert!(any_pending(&repo_dir));
+        drop(pending);
+        assert!(!is_pending(&silo_path));
+        assert!(!any_pending(&repo_dir));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_marker_of_dead_process_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let silo_path = dir.path().join("repo-abc123").join("feature");
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        child.wait().unwrap();
+
+        let mut record = Record::new();
+        record.set("pid", child.id());
+        metadata::store(&marker_path(&silo_path), &record).unwrap();
+        assert!(!is_pending(&silo_path));
+    }
+}
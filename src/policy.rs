@@ -0,0 +1,85 @@
+//! Per-silo policies limiting what silo does to a silo's branch.
+//!
+//! Set with `silo policy` and stored in the silo's tracking directory. A
+//! silo marked no-rebase keeps its exact history (e.g. signed commits
+//! pending review): `rebase` refuses to rewrite it, `--force` or not.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::{self, Record};
+use crate::process;
+
+/// What silo may do to a silo's branch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Policy {
+    /// Refuse to rebase the branch
+    pub no_rebase: bool,
+}
+
+/// Get the path of the file recording a silo's policy.
+pub fn policy_path(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("policy")
+}
+
+/// Record a silo's policy. The default policy is recorded by removing it.
+pub fn store(silo_path: &Path, policy: &Policy) -> Result<(), String> {
+    let path = policy_path(silo_path);
+    if *policy == Policy::default() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove policy: {}", e))
+            }
+            _ => Ok(()),
+        };
+    }
+
+    let mut record = Record::new();
+    record.set("no_rebase", policy.no_rebase);
+    metadata::store(&path, &record).map_err(|e| format!("Failed to write policy: {}", e))
+}
+
+/// Load a silo's policy; the default if none was recorded.
+pub fn load(silo_path: &Path) -> Policy {
+    metadata::load(&policy_path(silo_path))
+        .map(|record| Policy {
+            no_rebase: record.get_parsed("no_rebase").unwrap_or(false),
+        })
+        .unwrap_or_default()
+}
+
+/// Fail if the policy of silo `name` forbids rebasing it.
+pub fn check_rebase(name: &str, silo_path: &Path) -> Result<(), String> {
+    if !load(silo_path).no_rebase {
+        return Ok(());
+    }
+    Err(format!(
+        "Silo '{}' must keep its exact history, so it isn't rebased. \
+         Merge instead, or allow rebasing with `silo policy {} --allow-rebase`.",
+        name, name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let silo_path = dir.path().join("repo-abc123").join("feature");
+
+        assert_eq!(load(&silo_path), Policy::default());
+        assert!(check_rebase("feature", &silo_path).is_ok());
+
+        store(&silo_path, &Policy { no_rebase: true }).unwrap();
+        assert!(load(&silo_path).no_rebase);
+        let err = check_rebase("feature", &silo_path).unwrap_err();
+        assert!(err.contains("--allow-rebase"), "got: {}", err);
+
+        store(&silo_path, &Policy::default()).unwrap();
+        assert!(!policy_path(&silo_path).exists());
+        // Storing the default again is fine
+        store(&silo_path, &Policy::default()).unwrap();
+    }
+}
@@ -0,0 +1,164 @@
+//! Silo pools: idle worktrees created ahead of time, so `silo reuse` can
+//! hand one out instead of paying for `git worktree add` and a full
+//! checkout every time.
+//!
+//! A repository's pool lives in `.pool` in its storage directory, one
+//! detached worktree per entry. Silo scans skip hidden directories and
+//! [`silo::is_silo_path`] leaves pool worktrees out, so they're never listed
+//! or resolved as silos. `gc` tops each pool up to the repository's
+//! `pool_size`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::git;
+use crate::names;
+use crate::output::Verbosity;
+use crate::pending::Pending;
+use crate::registry;
+use crate::silo;
+use crate::storage::{self, StorageLock};
+
+/// Directory in a repository's storage directory holding its pool.
+pub const POOL_DIR: &str = ".pool";
+
+/// The pool directory of the repository at `repo_root`.
+pub fn pool_dir(repo_root: &Path) -> Result<PathBuf, String> {
+    let repo_name = git::get_repo_name(Some(repo_root))?;
+    Ok(silo::get_repo_base_dir(repo_root)?
+        .join(names::repo_storage_name(&repo_name, repo_root))
+        .join(POOL_DIR))
+}
+
+/// Whether `path` is a worktree in a pool.
+pub fn is_pool_path(path: &Path) -> bool {
+    path.parent()
+        .and_then(Path::file_name)
+        .is_some_and(|name| name == POOL_DIR)
+}
+
+/// Whether a repo directory of the storage directory has a pool with
+/// worktrees in it, which `gc` mustn't take for an empty directory.
+pub fn has_worktrees(repo_silo_dir: &Path) -> bool {
+    fs::read_dir(repo_silo_dir.join(POOL_DIR)).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// The pool worktrees of the repository at `repo_root`.
+pub fn worktrees(repo_root: &Path) -> Result<Vec<PathBuf>, String> {
+    Ok(git::list_worktrees(repo_root)?
+        .into_iter()
+        .map(|wt| wt.path)
+        .filter(|path| is_pool_path(path))
+        .collect())
+}
+
+/// A pool worktree of the repository at `repo_root` that's ready to hand
+/// out: one with no changes left in it.
+pub fn idle(repo_root: &Path) -> Result<Option<PathBuf>, String> {
+    Ok(worktrees(repo_root)?
+        .into_iter()
+        .find(|path| git::is_worktree_clean(path)))
+}
+
+/// Create pool worktrees for the repository until it has `size`. Returns
+/// how many were created.
+pub fn fill(
+    repo_root: &Path,
+    size: usize,
+    config: &Config,
+    verbosity: Verbosity,
+) -> Result<usize, String> {
+    let existing = worktrees(repo_root)?;
+    if existing.len() >= size {
+        return Ok(0);
+    }
+    let dir = pool_dir(repo_root)?;
+    let base_dir = silo::get_repo_base_dir(repo_root)?;
+    // A pool may be the first thing in a new storage directory
+    storage::ensure_base_dir(&base_dir, config.storage_mode()?)?;
+    let mut worktree_config = config.signing_git_config()?;
+    worktree_config.extend(config.status_git_config());
+
+    let _lock = StorageLock::acquire(&base_dir)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create pool directory: {}", e))?;
+    let mut created = 0;
+    for n in 1.. {
+        if existing.len() + created == size {
+            break;
+        }
+        let path = dir.join(n.to_string());
+        if path.exists() {
+            continue;
+        }
+        git::create_detached_worktree(&path, repo_root, verbosity)?;
+        // Moves with the worktree when it's handed out
+        git::set_worktree_config(&path, &worktree_config)?;
+        created += 1;
+    }
+    Ok(created)
+}
+
+/// Hand out an idle pool worktree as a silo at `silo_path` on the new
+/// branch `branch`, starting at the main worktree's HEAD. Returns None,
+/// leaving everything as it was, if the pool has no clean worktree.
+///
+/// Like [`crate::commands::new::create`], `gc` and `prune` leave the silo
+/// alone until the returned guard is dropped.
+pub fn take(
+    repo_root: &Path,
+    silo_path: &Path,
+    branch: &str,
+    verbosity: Verbosity,
+) -> Result<Option<Pending>, String> {
+    // Held from choosing a worktree until it's moved out of the pool, so
+    // two silos never get the same one
+    let _lock = StorageLock::acquire(&silo::get_repo_base_dir(repo_root)?)?;
+    let Some(pooled) = idle(repo_root)? else {
+        return Ok(None);
+    };
+
+    // The pool was filled at an older HEAD; only what changed since is
+    // checked out again. Still detached and clean, so it stays idle if
+    // anything below fails.
+    let head = git::get_head_commit(repo_root)
+        .map_err(|e| format!("Failed to get main worktree commit: {}", e))?;
+    git::reset_hard(&pooled, &head, Verbosity::Quiet)?;
+
+    let pending = Pending::start(silo_path)?;
+    if let Some(parent) = silo_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create silo directory: {}", e))?;
+    }
+    git::move_worktree(repo_root, &pooled, silo_path, verbosity)?;
+    if let Err(e) = git::switch_new_branch(silo_path, branch, verbosity) {
+        // Back to the pool as it was
+        if let Err(back) = git::move_worktree(repo_root, silo_path, &pooled, Verbosity::Quiet) {
+            eprintln!(
+                "Warning: Failed to return {} to the pool: {}",
+                silo_path.display(),
+                back
+            );
+        }
+        return Err(e.into());
+    }
+    // Lets gc find this repository again if it's moved
+    if let Err(e) =
+        silo::get_silo_base_dir().and_then(|primary| registry::register_repo(&primary, repo_root))
+    {
+        eprintln!("Warning: {}", e);
+    }
+    Ok(Some(pending))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_pool_path() {
+        assert!(is_pool_path(Path::new("/silos/repo-abcd1234/.pool/1")));
+        assert!(!is_pool_path(Path::new("/silos/repo-abcd1234/feature")));
+        assert!(!is_pool_path(Path::new("/silos/repo-abcd1234/.pool")));
+    }
+}
@@ -0,0 +1,234 @@
+//! Pre-flight checks for `merge` and `rebase`.
+//!
+//! Before either command touches a worktree, silo works out what git is about
+//! to do: whether the worktree that will change is clean, whether a merge is a
+//! fast-forward, and which files `git merge-tree` expects to conflict. The
+//! result is printed as a plan, so conflicts don't come as a surprise halfway
+//! through.
+
+use std::path::Path;
+
+use crate::git;
+use crate::messages;
+
+/// The operation a plan describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// Merge `branch` into `onto`
+    Merge,
+    /// Rebase `branch` onto `onto`
+    Rebase,
+}
+
+/// What a merge or rebase is about to do.
+#[derive(Debug)]
+pub struct Plan {
+    operation: Operation,
+    /// Branch whose commits are merged or rebased
+    branch: String,
+    /// Branch they are merged into or rebased onto
+    onto: String,
+    /// Commits in `branch` that are not in `onto`
+    ahead: u32,
+    /// Commits in `onto` that are not in `branch`
+    behind: u32,
+    /// Files expected to conflict; None if they could not be predicted
+    conflicts: Option<Vec<String>>,
+}
+
+impl Plan {
+    /// Work out the plan for `operation`, running git in `worktree`.
+    pub fn new(operation: Operation, worktree: &Path, branch: &str, onto: &str) -> Self {
+        let (ahead, behind) = git::get_ahead_behind(worktree, branch, onto);
+        // Only diverged branches can conflict
+        let conflicts = if ahead > 0 && behind > 0 {
+            git::merge_tree_conflicts(worktree, onto, branch)
+        } else {
+            Some(Vec::new())
+        };
+        Plan {
+            operation,
+            branch: branch.to_string(),
+            onto: onto.to_string(),
+            ahead,
+            behind,
+            conflicts,
+        }
+    }
+
+    /// Whether there is nothing to do.
+    pub fn is_up_to_date(&self) -> bool {
+        match self.operation {
+            Operation::Merge => self.ahead == 0,
+            Operation::Rebase => self.behind == 0,
+        }
+    }
+
+    /// Whether git is expected to stop with conflicts.
+    pub fn has_conflicts(&self) -> bool {
+        self.conflicts
+            .as_ref()
+            .is_some_and(|files| !files.is_empty())
+    }
+
+    /// Describe the plan, one line per fact.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![self.summary()];
+        if self.is_up_to_date() || self.ahead == 0 || self.behind == 0 {
+            return lines;
+        }
+        match &self.conflicts {
+            Some(files) if files.is_empty() => lines.push("No conflicts expected".to_string()),
+            Some(files) => {
+                lines.push(format!(
+                    "Conflicts expected in {}:",
+                    messages::count(files.len(), "file", "files")
+                ));
+                lines.extend(files.iter().map(messages::blocker_line));
+            }
+            None => lines.push(
+                "Could not check for conflicts (git merge-tree needs git 2.38 or later)"
+                    .to_string(),
+            ),
+        }
+        lines
+    }
+
+    fn summary(&self) -> String {
+        let commits = |n: u32| messages::count(n as usize, "commit", "commits");
+        let new_commits = |n: u32| messages::count(n as usize, "new commit", "new commits");
+        match self.operation {
+            Operation::Merge if self.ahead == 0 => {
+                format!(
+                    "Nothing to merge: '{}' already contains '{}'",
+                    self.onto, self.branch
+                )
+            }
+            Operation::Merge if self.behind == 0 => {
+                format!("Fast-forward '{}' by {}", self.onto, commits(self.ahead))
+            }
+            Operation::Merge => format!(
+                "Merge {} from '{}' into '{}' ('{}' has {})",
+                commits(self.ahead),
+                self.branch,
+                self.onto,
+                self.onto,
+                new_commits(self.behind)
+            ),
+            Operation::Rebase if self.behind == 0 => {
+                format!(
+                    "Nothing to rebase: '{}' is up to date with '{}'",
+                    self.branch, self.onto
+                )
+            }
+            Operation::Rebase if self.ahead == 0 => {
+                format!("Fast-forward '{}' to '{}'", self.branch, self.onto)
+            }
+            Operation::Rebase => format!(
+                "Rebase {} of '{}' onto '{}' ('{}' has {})",
+                commits(self.ahead),
+                self.branch,
+                self.onto,
+                self.onto,
+                new_commits(self.behind)
+            ),
+        }
+    }
+}
+
+/// Fail with an actionable message if `worktree` has a git operation
+/// stopped partway, or staged or modified tracked files. Untracked files
+/// don't get in the way of a merge or rebase.
+pub fn check_clean(worktree: &Path, doing: &str) -> Result<(), String> {
+    if let Some(state) = git::get_in_progress(worktree) {
+        return Err(format!(
+            "{} has a {} in progress. Finish it or run `git {}` before {}.",
+            worktree.display(),
+            state.command(),
+            state.abort_args().join(" "),
+            doing
+        ));
+    }
+    let stats = git::get_tracked_stats(worktree);
+    if stats.staged > 0 || stats.modified > 0 {
+        return Err(format!(
+            "{} has uncommitted changes ({} staged, {} modified). \
+             Commit or stash them before {}.",
+            worktree.display(),
+            stats.staged,
+            stats.modified,
+            doing
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(operation: Operation, ahead: u32, behind: u32, conflicts: Option<&[&str]>) -> Plan {
+        Plan {
+            operation,
+            branch: "feature".to_string(),
+            onto: "main".to_string(),
+            ahead,
+            behind,
+            conflicts: conflicts.map(|files| files.iter().map(|f| f.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn test_merge_fast_forward() {
+        let plan = plan(Operation::Merge, 2, 0, Some(&[]));
+        assert!(!plan.is_up_to_date());
+        assert_eq!(plan.lines(), vec!["Fast-forward 'main' by 2 commits"]);
+    }
+
+    #[test]
+    fn test_merge_up_to_date() {
+        let plan = plan(Operation::Merge, 0, 3, Some(&[]));
+        assert!(plan.is_up_to_date());
+        assert_eq!(
+            plan.lines(),
+            vec!["Nothing to merge: 'main' already contains 'feature'"]
+        );
+    }
+
+    #[test]
+    fn test_merge_with_conflicts() {
+        let plan = plan(Operation::Merge, 1, 2, Some(&["src/lib.rs"]));
+        assert!(plan.has_conflicts());
+        assert_eq!(
+            plan.lines(),
+            vec![
+                "Merge 1 commit from 'feature' into 'main' ('main' has 2 new commits)",
+                "Conflicts expected in 1 file:",
+                "  - src/lib.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rebase_plans() {
+        let up_to_date = plan(Operation::Rebase, 2, 0, Some(&[]));
+        assert!(up_to_date.is_up_to_date());
+
+        let clean = plan(Operation::Rebase, 2, 1, Some(&[]));
+        assert!(!clean.has_conflicts());
+        assert_eq!(
+            clean.lines(),
+            vec![
+                "Rebase 2 commits of 'feature' onto 'main' ('main' has 1 new commit)",
+                "No conflicts expected",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_conflicts_are_not_blocking() {
+        let plan = plan(Operation::Merge, 1, 1, None);
+        assert!(!plan.has_conflicts());
+        assert!(plan.lines()[1].starts_with("Could not check for conflicts"));
+    }
+}
@@ -3,23 +3,92 @@
 //! This module handles tracking of processes started in silos so that:
 //! - The `list` command can show active process counts
 //! - The `rm` command can warn before deleting silos with active processes
+//!
+//! It also decides where each silo's tracking directory lives, which other
+//! modules keep their per-silo data in. The `process_tracking` and
+//! `tracking_dir` config options turn off PID files or move tracking data
+//! out of the storage directory (`configure`).
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+use crate::metadata::{self, Record};
+use crate::silo;
+
+/// Tracking settings from config (`configure`)
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+#[derive(Debug, Default)]
+struct Settings {
+    /// Don't record PID files for processes started in silos
+    disabled: bool,
+    /// Directory holding tracking data instead of the storage directory
+    root: Option<PathBuf>,
+}
+
+/// Apply the `process_tracking` and `tracking_dir` config options.
+/// Called once at startup, before any tracking data is read or written.
+pub fn configure(process_tracking: bool, root: Option<PathBuf>) {
+    let _ = SETTINGS.set(Settings {
+        disabled: !process_tracking,
+        root,
+    });
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+/// Whether processes started in silos are tracked.
+pub fn is_enabled() -> bool {
+    !settings().disabled
+}
+
+/// The relocated tracking directory from config, if any.
+pub fn tracking_root() -> Option<&'static Path> {
+    settings().root.as_deref()
+}
 
 /// Information about a tracked process.
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
     pub pid: u32,
     pub command: String,
+    /// The agent the process runs, e.g. "claude", for sandbox and
+    /// background agent runs
+    pub agent: Option<String>,
+    /// When the process was registered, in seconds since the Unix epoch
+    pub started_at: Option<u64>,
 }
 
 /// Get the tracking directory for a silo (parallel to worktree).
 /// Maps: ~/.local/var/silo/repo-hash/branch -> ~/.local/var/silo/repo-hash/.tracking/branch
+/// or, with `tracking_dir` configured, to {tracking_dir}/repo-hash/branch.
+/// A branch like `feature/x` keeps its whole name, so its data is apart
+/// from the silo of `x` and from `feature/x` in other repos.
 pub fn tracking_dir(silo_path: &Path) -> PathBuf {
+    if let Some((repo_silo_dir, name)) = silo::split_storage_path(silo_path) {
+        return repo_tracking_dir(&repo_silo_dir).join(name);
+    }
+    // Outside storage, e.g. registered external worktrees
     let parent = silo_path.parent().unwrap_or(silo_path);
     let branch = silo_path.file_name().unwrap_or_default();
-    parent.join(".tracking").join(branch)
+    repo_tracking_dir(parent).join(branch)
+}
+
+/// Get the directory holding the tracking directories of all silos in a
+/// repo directory of the storage directory.
+pub fn repo_tracking_dir(repo_silo_dir: &Path) -> PathBuf {
+    repo_tracking_dir_in(tracking_root(), repo_silo_dir)
+}
+
+fn repo_tracking_dir_in(root: Option<&Path>, repo_silo_dir: &Path) -> PathBuf {
+    match root {
+        Some(root) => root.join(repo_silo_dir.file_name().unwrap_or_default()),
+        None => repo_silo_dir.join(".tracking"),
+    }
 }
 
 /// Get the pids directory inside the tracking directory.
@@ -27,16 +96,24 @@ pub fn pids_dir(silo_path: &Path) -> PathBuf {
     tracking_dir(silo_path).join("pids")
 }
 
-/// Register a process for tracking (creates PID file).
-pub fn register(silo_path: &Path, pid: u32, command: &str) -> Result<(), String> {
-    let dir = pids_dir(silo_path);
-    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create tracking directory: {}", e))?;
-
-    let pid_file = dir.join(pid.to_string());
-    let content = format!("command={}\n", command);
-    fs::write(&pid_file, content).map_err(|e| format!("Failed to write PID file: {}", e))?;
-
-    Ok(())
+/// Register a process for tracking (creates PID file), noting the agent
+/// it runs, if any. Does nothing when process tracking is disabled.
+pub fn register(
+    silo_path: &Path,
+    pid: u32,
+    command: &str,
+    agent: Option<&str>,
+) -> Result<(), String> {
+    if !is_enabled() {
+        return Ok(());
+    }
+    let mut record = Record::new();
+    record.set("command", command);
+    if let Some(agent) = agent {
+        record.set("agent", agent);
+    }
+    metadata::store(&pids_dir(silo_path).join(pid.to_string()), &record)
+        .map_err(|e| format!("Failed to write PID file: {}", e))
 }
 
 /// Unregister a process (removes PID file).
@@ -50,9 +127,10 @@ pub fn unregister(silo_path: &Path, pid: u32) -> Result<(), String> {
 
 /// Get all active processes for a silo.
 /// Reads PID files, checks if each process is still running, prunes dead entries.
+/// Always empty when process tracking is disabled.
 pub fn list_active(silo_path: &Path) -> Vec<ProcessInfo> {
     let dir = pids_dir(silo_path);
-    if !dir.exists() {
+    if !is_enabled() || !dir.exists() {
         return Vec::new();
     }
 
@@ -74,8 +152,20 @@ pub fn list_active(silo_path: &Path) -> Vec<ProcessInfo> {
         };
 
         if is_running(pid) {
-            let command = read_command(&path).unwrap_or_default();
-            active.push(ProcessInfo { pid, command });
+            let record = metadata::load(&path);
+            let field = |key| record.as_ref()?.get(key).map(str::to_string);
+            active.push(ProcessInfo {
+                pid,
+                command: field("command").unwrap_or_default(),
+                agent: field("agent"),
+                // The PID file is written when the process starts
+                started_at: entry
+                    .metadata()
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+                    .map(|age| age.as_secs()),
+            });
         } else {
             // Prune stale PID file
             let _ = fs::remove_file(&path);
@@ -85,26 +175,24 @@ pub fn list_active(silo_path: &Path) -> Vec<ProcessInfo> {
     active
 }
 
-/// Read the command from a PID file.
-fn read_command(pid_file: &Path) -> Option<String> {
-    let content = fs::read_to_string(pid_file).ok()?;
-    for line in content.lines() {
-        if let Some(cmd) = line.strip_prefix("command=") {
-            return Some(cmd.to_string());
-        }
-    }
-    None
-}
-
 /// Check if a process is still running.
 #[cfg(unix)]
-fn is_running(pid: u32) -> bool {
+pub fn is_running(pid: u32) -> bool {
     // kill(pid, 0) checks if process exists without sending a signal
-    unsafe { libc::kill(pid as i32, 0) == 0 }
+    let result = unsafe { libc::kill(pid as i32, 0) };
+    kill_probe_found(result, std::io::Error::last_os_error().raw_os_error())
+}
+
+/// Whether a `kill(pid, 0)` that returned `result` (with `errno` on failure)
+/// found the process. EPERM means it exists but belongs to another user,
+/// as on shared storage.
+#[cfg(unix)]
+fn kill_probe_found(result: i32, errno: Option<i32>) -> bool {
+    result == 0 || errno == Some(libc::EPERM)
 }
 
 #[cfg(not(unix))]
-fn is_running(_pid: u32) -> bool {
+pub fn is_running(_pid: u32) -> bool {
     // On non-Unix platforms, assume the process is running
     // This is a conservative approach that may leave stale entries
     true
@@ -124,15 +212,15 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
-    fn temp_silo_path(suffix: &str) -> PathBuf {
-        let mut path = std::env::temp_dir();
-        path.push(format!(
-            "silo-test-{}-{:?}-{}",
-            std::process::id(),
-            std::thread::current().id(),
-            suffix
-        ));
-        path
+    #[test]
+    #[cfg(unix)]
+    fn test_is_running_for_other_users_processes() {
+        assert!(kill_probe_found(0, None));
+        assert!(kill_probe_found(-1, Some(libc::EPERM)));
+        assert!(!kill_probe_found(-1, Some(libc::ESRCH)));
+        // init is owned by root: signalling it is EPERM for everyone else
+        assert!(is_running(1));
+        assert!(is_running(std::process::id()));
     }
 
     #[test]
@@ -145,6 +233,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_repo_tracking_dir_relocated() {
+        let repo_dir = PathBuf::from("/var/silo/repo-abc123");
+        assert_eq!(
+            repo_tracking_dir_in(None, &repo_dir),
+            PathBuf::from("/var/silo/repo-abc123/.tracking")
+        );
+        assert_eq!(
+            repo_tracking_dir_in(Some(Path::new("/tmp/silo-tracking")), &repo_dir),
+            PathBuf::from("/tmp/silo-tracking/repo-abc123")
+        );
+    }
+
     #[test]
     fn test_pids_dir() {
         let silo = PathBuf::from("/var/silo/repo-abc123/feature");
@@ -157,39 +258,41 @@ mod tests {
 
     #[test]
     fn test_register_and_list() {
-        let silo = temp_silo_path("register");
+        let dir = tempfile::tempdir().unwrap();
+        let silo = dir.path().join("register");
         let pid = std::process::id(); // Use our own PID (known to be running)
 
         // Register
-        register(&silo, pid, "test command").unwrap();
+        register(&silo, pid, "test command", None).unwrap();
 
         // List should find it
         let active = list_active(&silo);
         assert_eq!(active.len(), 1);
         assert_eq!(active[0].pid, pid);
         assert_eq!(active[0].command, "test command");
+        assert_eq!(active[0].agent, None);
 
-        // Cleanup
-        cleanup_tracking(&silo).unwrap();
+        register(&silo, pid, "docker sandbox run claude", Some("claude")).unwrap();
+        assert_eq!(list_active(&silo)[0].agent.as_deref(), Some("claude"));
     }
 
     #[test]
     fn test_unregister() {
-        let silo = temp_silo_path("unregister");
+        let dir = tempfile::tempdir().unwrap();
+        let silo = dir.path().join("unregister");
         let pid = std::process::id();
 
-        register(&silo, pid, "test").unwrap();
+        register(&silo, pid, "test", None).unwrap();
         assert_eq!(list_active(&silo).len(), 1);
 
         unregister(&silo, pid).unwrap();
         assert_eq!(list_active(&silo).len(), 0);
-
-        cleanup_tracking(&silo).unwrap();
     }
 
     #[test]
     fn test_list_prunes_dead_pids() {
-        let silo = temp_silo_path("prune");
+        let temp = tempfile::tempdir().unwrap();
+        let silo = temp.path().join("prune");
 
         // Create a PID file for a non-existent process
         let dir = pids_dir(&silo);
@@ -200,14 +303,13 @@ mod tests {
         let active = list_active(&silo);
         assert!(active.is_empty());
         assert!(!dir.join("999999").exists());
-
-        cleanup_tracking(&silo).unwrap();
     }
 
     #[test]
     fn test_cleanup_tracking() {
-        let silo = temp_silo_path("cleanup");
-        register(&silo, 12345, "test").unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let silo = temp.path().join("cleanup");
+        register(&silo, 12345, "test", None).unwrap();
 
         let dir = tracking_dir(&silo);
         assert!(dir.exists());
@@ -0,0 +1,320 @@
+//! Terminal handling for commands silo runs: on a pseudo-terminal
+//! (`exec --tty`) or with no terminal at all (`exec --no-tty`).
+//!
+//! Programs check `isatty` to decide whether to be interactive, use colors
+//! or buffer their output. On a PTY they behave as in a normal shell even
+//! when silo's own output is piped; with no terminal they behave as in a
+//! script even when silo runs in one.
+
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::thread;
+
+/// How long relay loops wait for input before checking whether to stop.
+const POLL_INTERVAL_MS: libc::c_int = 100;
+
+/// Process group SIGINT and SIGTERM are passed on to while a command runs
+/// without a terminal; 0 when none is.
+static FORWARD_TO: AtomicI32 = AtomicI32::new(0);
+
+/// Set on SIGWINCH while a command runs on a PTY, until the PTY is resized.
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+/// Start `cmd` on a new pseudo-terminal and relay between it and silo's own
+/// stdin/stdout until the command exits. `on_spawn` gets the command's PID.
+pub fn run_on_pty(cmd: &mut Command, on_spawn: impl FnOnce(u32)) -> io::Result<ExitStatus> {
+    let (master, slave) = open_pty()?;
+
+    cmd.stdin(Stdio::from(slave.try_clone()?))
+        .stdout(Stdio::from(slave.try_clone()?))
+        .stderr(Stdio::from(slave));
+    // SAFETY: only async-signal-safe calls between fork and exec
+    unsafe {
+        cmd.pre_exec(|| {
+            // A new session whose controlling terminal is the PTY, so the
+            // command gets job control and terminal signals
+            if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let mut child = cmd.spawn()?;
+    on_spawn(child.id());
+    // Close our copies of the slave side so reads see the end of output
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    let _raw = RawMode::enable(libc::STDIN_FILENO);
+    // Resized along with silo's terminal, which tells the command
+    let _resize = SignalHandler::install(libc::SIGWINCH, note_resize).ok();
+    let stop = Arc::new(AtomicBool::new(false));
+    let input = {
+        let master = master.try_clone()?;
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || relay_input(&master, &stop))
+    };
+
+    let result = relay_output(&master, &mut child);
+    stop.store(true, Ordering::Relaxed);
+    let _ = input.join();
+    result
+}
+
+/// Start `cmd` without a terminal: no controlling terminal, stdin from
+/// /dev/null and output relayed through pipes. `on_spawn` gets the
+/// command's PID. Being in a session of its own, the command doesn't get
+/// the terminal's Ctrl-C; SIGINT and SIGTERM sent to silo are passed on to
+/// its process group instead.
+pub fn run_without_tty(cmd: &mut Command, on_spawn: impl FnOnce(u32)) -> io::Result<ExitStatus> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // SAFETY: setsid is async-signal-safe
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let mut child = cmd.spawn()?;
+    on_spawn(child.id());
+    // setsid made the command the leader of its own process group
+    FORWARD_TO.store(child.id() as i32, Ordering::Relaxed);
+    let forwarding = [libc::SIGINT, libc::SIGTERM]
+        .into_iter()
+        .filter_map(|signal| SignalHandler::install(signal, forward_signal).ok())
+        .collect::<Vec<_>>();
+
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let out = thread::spawn(move || io::copy(&mut stdout, &mut io::stdout()));
+    let err = thread::spawn(move || io::copy(&mut stderr, &mut io::stderr()));
+    let status = child.wait();
+    drop(forwarding);
+    FORWARD_TO.store(0, Ordering::Relaxed);
+    let _ = out.join();
+    let _ = err.join();
+    status
+}
+
+/// Pass a signal on to the process group in [`FORWARD_TO`].
+extern "C" fn forward_signal(signal: libc::c_int) {
+    let pgid = FORWARD_TO.load(Ordering::Relaxed);
+    if pgid > 0 {
+        // SAFETY: kill is async-signal-safe
+        unsafe {
+            libc::kill(-pgid, signal);
+        }
+    }
+}
+
+/// Note that silo's terminal changed size, for [`relay_output`] to pass on.
+extern "C" fn note_resize(_signal: libc::c_int) {
+    RESIZED.store(true, Ordering::Relaxed);
+}
+
+/// A signal handler, installed until dropped, when the previous one is
+/// restored.
+struct SignalHandler {
+    signal: libc::c_int,
+    previous: libc::sigaction,
+}
+
+impl SignalHandler {
+    fn install(signal: libc::c_int, handler: extern "C" fn(libc::c_int)) -> io::Result<Self> {
+        // SAFETY: sigaction is plain data; the handler only makes
+        // async-signal-safe calls
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handler as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            let mut previous: libc::sigaction = std::mem::zeroed();
+            if libc::sigaction(signal, &action, &mut previous) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(SignalHandler { signal, previous })
+        }
+    }
+}
+
+impl Drop for SignalHandler {
+    fn drop(&mut self) {
+        // SAFETY: restores the action sigaction returned for this signal
+        unsafe {
+            libc::sigaction(self.signal, &self.previous, std::ptr::null_mut());
+        }
+    }
+}
+
+/// Open a PTY pair sized like silo's own terminal, if it has one.
+fn open_pty() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut master: libc::c_int = -1;
+    let mut slave: libc::c_int = -1;
+    let mut size = window_size();
+    let size_ptr = size
+        .as_mut()
+        .map_or(std::ptr::null_mut(), |s| s as *mut libc::winsize);
+    // SAFETY: all pointers are valid or null, as openpty allows
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            size_ptr,
+        )
+    };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: openpty succeeded, so both are open descriptors we now own
+    unsafe { Ok((OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave))) }
+}
+
+/// Size of the terminal on stdout or stdin, if either is one.
+fn window_size() -> Option<libc::winsize> {
+    [libc::STDOUT_FILENO, libc::STDIN_FILENO]
+        .into_iter()
+        .find_map(|fd| {
+            // SAFETY: winsize is plain data; TIOCGWINSZ fills it in
+            let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+            let result = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) };
+            (result == 0 && size.ws_col > 0).then_some(size)
+        })
+}
+
+/// Give the PTY the size of silo's own terminal. The kernel sends the
+/// command SIGWINCH when that changes its size.
+fn resize(master: &OwnedFd) {
+    if let Some(size) = window_size() {
+        // SAFETY: size is a valid winsize for TIOCSWINSZ to read
+        unsafe {
+            libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &size);
+        }
+    }
+}
+
+/// Wait up to POLL_INTERVAL_MS for `fd` to become readable.
+fn wait_readable(fd: libc::c_int) -> bool {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: one valid pollfd
+    unsafe { libc::poll(&mut pollfd, 1, POLL_INTERVAL_MS) > 0 }
+}
+
+/// Copy silo's stdin to the PTY until `stop` is set or stdin ends.
+///
+/// Reads the descriptor itself: a buffered reader could hold input that
+/// `poll` no longer reports, leaving it stuck until the next key.
+fn relay_input(master: &OwnedFd, stop: &AtomicBool) {
+    let mut master = std::fs::File::from(master.try_clone().expect("duplicate PTY"));
+    let mut buf = [0u8; 4096];
+    while !stop.load(Ordering::Relaxed) {
+        if !wait_readable(libc::STDIN_FILENO) {
+            continue;
+        }
+        // SAFETY: reads at most buf.len() bytes into buf
+        let n = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+        if n < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+            continue;
+        }
+        if n <= 0 {
+            // Pass end of input on like a terminal does (Ctrl-D)
+            let _ = master.write_all(&[4]);
+            return;
+        }
+        if master.write_all(&buf[..n as usize]).is_err() {
+            return;
+        }
+    }
+}
+
+/// Copy the PTY's output to silo's stdout until the command exits and its
+/// output has been drained. Returns the command's exit status.
+fn relay_output(master: &OwnedFd, child: &mut Child) -> io::Result<ExitStatus> {
+    let mut reader = std::fs::File::from(master.try_clone()?);
+    let mut stdout = io::stdout();
+    let mut buf = [0u8; 4096];
+    loop {
+        if RESIZED.swap(false, Ordering::Relaxed) {
+            resize(master);
+        }
+        if wait_readable(master.as_raw_fd()) {
+            match reader.read(&mut buf) {
+                // EIO: every process holding the slave side has exited
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    stdout.write_all(&buf[..n])?;
+                    stdout.flush()?;
+                }
+            }
+        } else if child.try_wait()?.is_some() {
+            // Exited with nothing left to read; background processes it
+            // started may still hold the terminal open
+            break;
+        }
+    }
+    child.wait()
+}
+
+/// Puts a terminal in raw mode, so keys reach the command unprocessed,
+/// and restores its settings when dropped.
+struct RawMode {
+    fd: libc::c_int,
+    original: libc::termios,
+}
+
+impl RawMode {
+    /// Enable raw mode if `fd` is a terminal.
+    fn enable(fd: libc::c_int) -> Option<Self> {
+        // SAFETY: termios is plain data; tcgetattr fills it in
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+            return None;
+        }
+        let mut raw = original;
+        // SAFETY: raw is a valid termios copied from the terminal
+        unsafe {
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(fd, libc::TCSANOW, &raw) != 0 {
+                return None;
+            }
+        }
+        Some(RawMode { fd, original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: restores settings read from the same terminal
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_without_tty_sees_no_terminal() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "test ! -t 0 && test ! -t 1 && test ! -t 2"]);
+        let mut pid = 0;
+        assert!(run_without_tty(&mut cmd, |p| pid = p).unwrap().success());
+        assert_ne!(pid, 0);
+    }
+}
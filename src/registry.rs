@@ -0,0 +1,150 @@
+//! Registry of known repositories: where each main worktree was seen and
+//! the URL of its origin remote.
+//!
+//! Stored as `{storage}/.repos`, one tab-separated `url<TAB>path` line per
+//! repository. When a repository is moved, its silos are orphaned: their
+//! `.git` files still point at the old location. A repository with the same
+//! origin at a new location tells `gc` where the silos' data went, so it can
+//! relink them instead of deleting them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+use crate::metadata;
+
+/// Name of the registry file in the storage directory.
+const REGISTRY_NAME: &str = ".repos";
+
+/// A repository silo has seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownRepo {
+    pub remote_url: String,
+    pub path: PathBuf,
+}
+
+/// Get the path of the registry in a storage directory.
+pub fn registry_path(base_dir: &Path) -> PathBuf {
+    base_dir.join(REGISTRY_NAME)
+}
+
+/// Load the known repositories. A missing registry is empty.
+pub fn load(base_dir: &Path) -> Vec<KnownRepo> {
+    fs::read_to_string(registry_path(base_dir))
+        .map(|content| parse(&content))
+        .unwrap_or_default()
+}
+
+fn parse(content: &str) -> Vec<KnownRepo> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (url, path) = line.split_once('\t')?;
+            Some(KnownRepo {
+                remote_url: url.to_string(),
+                path: PathBuf::from(path),
+            })
+        })
+        .collect()
+}
+
+/// Record a repository's location and origin URL, replacing what was
+/// recorded for the same path before.
+pub fn register(base_dir: &Path, path: &Path, remote_url: &str) -> Result<(), String> {
+    let mut repos = load(base_dir);
+    let repo = KnownRepo {
+        remote_url: remote_url.to_string(),
+        path: path.to_path_buf(),
+    };
+    if repos.contains(&repo) {
+        return Ok(());
+    }
+    repos.retain(|r| r.path != path);
+    repos.push(repo);
+    save(base_dir, &repos)
+}
+
+fn save(base_dir: &Path, repos: &[KnownRepo]) -> Result<(), String> {
+    let content: String = repos
+        .iter()
+        .map(|r| format!("{}\t{}\n", r.remote_url, r.path.display()))
+        .collect();
+    metadata::write_atomic(&registry_path(base_dir), content)
+        .map_err(|e| format!("Failed to write repository registry: {}", e))
+}
+
+/// Forget what was recorded for the repository at `path`.
+pub fn unregister(base_dir: &Path, path: &Path) -> Result<(), String> {
+    let mut repos = load(base_dir);
+    repos.retain(|r| r.path != path);
+    save(base_dir, &repos)
+}
+
+/// Record a repository under the URL of its origin remote. Repositories
+/// without an origin can't be recognized after a move, so they're skipped.
+pub fn register_repo(base_dir: &Path, repo_root: &Path) -> Result<(), String> {
+    match git::get_origin_url(repo_root) {
+        Some(url) => register(base_dir, repo_root, &url),
+        None => Ok(()),
+    }
+}
+
+/// Other known locations of the repository that was at `old_path`: those
+/// with the same origin URL, most recently registered first.
+pub fn other_locations(repos: &[KnownRepo], old_path: &Path) -> Vec<PathBuf> {
+    let Some(old) = repos.iter().find(|r| r.path == old_path) else {
+        return Vec::new();
+    };
+    repos
+        .iter()
+        .rev()
+        .filter(|r| r.remote_url == old.remote_url && r.path != old_path)
+        .map(|r| r.path.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(url: &str, path: &str) -> KnownRepo {
+        KnownRepo {
+            remote_url: url.to_string(),
+            path: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn test_parse_skips_malformed_lines() {
+        let repos = parse("git@host:a.git\t/src/a\ngarbage\n");
+        assert_eq!(repos, vec![repo("git@host:a.git", "/src/a")]);
+    }
+
+    #[test]
+    fn test_register_replaces_same_path() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path();
+
+        register(base, Path::new("/src/a"), "url-a").unwrap();
+        register(base, Path::new("/src/b"), "url-b").unwrap();
+        register(base, Path::new("/src/a"), "url-a2").unwrap();
+        assert_eq!(
+            load(base),
+            vec![repo("url-b", "/src/b"), repo("url-a2", "/src/a")]
+        );
+    }
+
+    #[test]
+    fn test_other_locations() {
+        let repos = vec![
+            repo("url-a", "/old/a"),
+            repo("url-b", "/src/b"),
+            repo("url-a", "/new/a"),
+        ];
+        assert_eq!(
+            other_locations(&repos, Path::new("/old/a")),
+            vec![PathBuf::from("/new/a")]
+        );
+        assert!(other_locations(&repos, Path::new("/unknown")).is_empty());
+    }
+}
@@ -2,12 +2,20 @@
 //!
 //! This module provides the `RemovableSilo` pattern which ensures that silos
 //! are validated before removal. Use `TryFrom<Silo>` to validate, or
-//! `RemovableSilo::from_silo_unchecked` to skip validation (for --force).
+//! `RemovableSilo::forced` to skip validation (for --force).
 
-use crate::git::{self, Verbosity};
+use crate::external;
+use crate::git;
+use crate::messages;
+use crate::output::Verbosity;
+use crate::pending;
 use crate::process;
-use crate::silo::Silo;
+use crate::removal_plan::{BlockedSilo, BranchAction, PlannedRemoval};
+use crate::shell;
+use crate::silo::{self, Silo};
+use crate::storage::{self, StorageLock};
 use std::fmt;
+use std::path::Path;
 
 /// Reasons why a silo cannot be removed without --force.
 #[derive(Debug, Clone)]
@@ -18,6 +26,17 @@ pub enum RemovalBlocker {
     ActiveProcesses(Vec<process::ProcessInfo>),
     /// Silo has commits not merged into main branch
     UnmergedCommits(u32),
+    /// Silo belongs to another user (shared storage directory)
+    OwnedByOtherUser(String),
+    /// A rebase, merge, cherry-pick, revert or bisect stopped partway
+    InProgress(git::InProgress),
+    /// A registered external worktree, which silo didn't create
+    External,
+    /// A running `silo new` is still creating it; --force doesn't override
+    BeingCreated,
+    /// Locked with `git worktree lock`, with the given reason (may be empty);
+    /// only --force given twice overrides it, as with `git worktree remove`
+    Locked(String),
 }
 
 impl fmt::Display for RemovalBlocker {
@@ -53,6 +72,49 @@ impl fmt::Display for RemovalBlocker {
                     count
                 )
             }
+            RemovalBlocker::OwnedByOtherUser(owner) => {
+                write!(f, "Owned by another user: {}", owner)
+            }
+            RemovalBlocker::InProgress(state) => {
+                write!(
+                    f,
+                    "{} in progress (finish it or run `git {}`)",
+                    state.badge(),
+                    state.abort_args().join(" ")
+                )
+            }
+            RemovalBlocker::External => write!(
+                f,
+                "External worktree: not created by silo (`silo external rm` unregisters it)"
+            ),
+            RemovalBlocker::BeingCreated => write!(f, "Still being created by `silo new`"),
+            RemovalBlocker::Locked(reason) => {
+                write!(f, "Locked with `git worktree lock`")?;
+                if !reason.is_empty() {
+                    write!(f, ": {}", reason)?;
+                }
+                write!(f, " (run `git worktree unlock` or pass --force twice)")
+            }
+        }
+    }
+}
+
+impl RemovalBlocker {
+    /// Short form for listing skipped silos, e.g. "3 unmerged commits".
+    pub fn summary(&self) -> String {
+        match self {
+            RemovalBlocker::UncommittedChanges(_) => "uncommitted changes".to_string(),
+            RemovalBlocker::ActiveProcesses(processes) => {
+                messages::count(processes.len(), "active process", "active processes")
+            }
+            RemovalBlocker::UnmergedCommits(count) => {
+                messages::count(*count as usize, "unmerged commit", "unmerged commits")
+            }
+            RemovalBlocker::OwnedByOtherUser(owner) => format!("owned by {}", owner),
+            RemovalBlocker::InProgress(state) => state.badge().to_string(),
+            RemovalBlocker::External => "external".to_string(),
+            RemovalBlocker::BeingCreated => "being created".to_string(),
+            RemovalBlocker::Locked(_) => "locked".to_string(),
         }
     }
 }
@@ -68,9 +130,9 @@ pub struct RemovalError {
 
 impl fmt::Display for RemovalError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Silo '{}' cannot be removed:", self.silo.name)?;
+        write!(f, "{}", messages::blocked(&self.silo.name, "removed"))?;
         for blocker in &self.blockers {
-            write!(f, "\n  - {}", blocker)?;
+            write!(f, "\n{}", messages::blocker_line(blocker))?;
         }
         Ok(())
     }
@@ -78,15 +140,32 @@ impl fmt::Display for RemovalError {
 
 impl std::error::Error for RemovalError {}
 
+impl RemovalError {
+    /// Describe the blocked silo for a removal plan, under display name `name`.
+    pub fn planned(&self, name: &str) -> BlockedSilo {
+        BlockedSilo {
+            name: name.to_string(),
+            id: self.silo.id(),
+            path: self.silo.storage_path.clone(),
+            blockers: self.blockers.iter().map(RemovalBlocker::summary).collect(),
+        }
+    }
+}
+
 /// A silo that has been validated for removal.
 ///
 /// Created via `TryFrom<Silo>` which validates the silo can be removed,
-/// or `RemovableSilo::from_silo_unchecked` to skip validation (for --force).
+/// or `RemovableSilo::forced` to skip validation (for --force).
 pub struct RemovableSilo {
     silo: Silo,
     main_branch: String,
+    /// Whether the worktree is locked; removing it then takes `--force` twice
+    locked: bool,
     /// Whether the branch would be deleted (if merged into main)
     would_delete_branch: bool,
+    /// Commits on the branch's upstream that exist only on the remote.
+    /// The branch is preserved when this is non-zero, even if merged.
+    upstream_only_commits: u32,
 }
 
 impl TryFrom<Silo> for RemovableSilo {
@@ -96,6 +175,25 @@ impl TryFrom<Silo> for RemovableSilo {
     fn try_from(silo: Silo) -> Result<Self, Self::Error> {
         let mut blockers = Vec::new();
 
+        if pending::is_pending(&silo.storage_path) {
+            blockers.push(RemovalBlocker::BeingCreated);
+        }
+
+        // Registered worktrees outside the storage are the user's own
+        if silo.is_external() {
+            blockers.push(RemovalBlocker::External);
+        }
+
+        if let Some(reason) = lock_reason(&silo) {
+            blockers.push(RemovalBlocker::Locked(reason));
+        }
+
+        // Never remove another user's silo from shared storage without --force
+        if !storage::is_owned_by_current_user(&silo.storage_path) {
+            let owner = storage::owner_name(&silo.storage_path).unwrap_or_default();
+            blockers.push(RemovalBlocker::OwnedByOtherUser(owner));
+        }
+
         // Check for uncommitted changes
         let uncommitted = git::get_uncommitted_stats(&silo.storage_path);
         if !uncommitted.is_clean() {
@@ -108,6 +206,11 @@ impl TryFrom<Silo> for RemovableSilo {
             blockers.push(RemovalBlocker::ActiveProcesses(processes));
         }
 
+        // A stopped rebase or a bisect is work in progress, even when clean
+        if let Some(state) = git::get_in_progress(&silo.storage_path) {
+            blockers.push(RemovalBlocker::InProgress(state));
+        }
+
         // Check for unmerged commits (commits ahead of main)
         let main_branch = Self::get_main_branch(&silo);
         let branch_name = silo.branch_name();
@@ -120,30 +223,48 @@ impl TryFrom<Silo> for RemovableSilo {
             return Err(RemovalError { silo, blockers });
         }
 
-        // Pre-compute removal state
-        let would_delete_branch =
-            git::is_branch_merged(&silo.main_worktree, branch_name, &main_branch);
-
-        Ok(Self {
-            silo,
-            main_branch,
-            would_delete_branch,
-        })
+        Ok(Self::with_branch_state(silo, main_branch, false))
     }
 }
 
 impl RemovableSilo {
     /// Create a RemovableSilo without validation (for --force flag).
-    pub fn from_silo_unchecked(silo: Silo) -> Self {
+    ///
+    /// A worktree locked with `git worktree lock` is still refused unless
+    /// `unlock` is set (--force given twice), as git itself does.
+    #[allow(clippy::result_large_err)]
+    pub fn forced(silo: Silo, unlock: bool) -> Result<Self, RemovalError> {
+        let locked = match lock_reason(&silo) {
+            Some(reason) if !unlock => {
+                return Err(RemovalError {
+                    silo,
+                    blockers: vec![RemovalBlocker::Locked(reason)],
+                });
+            }
+            reason => reason.is_some(),
+        };
         let main_branch = Self::get_main_branch(&silo);
+        Ok(Self::with_branch_state(silo, main_branch, locked))
+    }
+
+    /// Pre-compute what removal will do with the silo's branch.
+    fn with_branch_state(silo: Silo, main_branch: String, locked: bool) -> Self {
         let branch_name = silo.branch_name();
-        let would_delete_branch =
-            git::is_branch_merged(&silo.main_worktree, branch_name, &main_branch);
+        let merged = git::is_branch_merged(&silo.main_worktree, branch_name, &main_branch);
+        // A merged branch may still be the only local ref to work pushed from
+        // elsewhere (e.g. after a reclone), so check its upstream too
+        let upstream_only_commits = if merged {
+            git::count_upstream_only_commits(&silo.main_worktree, branch_name, &main_branch)
+        } else {
+            0
+        };
 
         Self {
             silo,
             main_branch,
-            would_delete_branch,
+            locked,
+            would_delete_branch: merged && upstream_only_commits == 0,
+            upstream_only_commits,
         }
     }
 
@@ -165,17 +286,53 @@ impl RemovableSilo {
         &self.silo
     }
 
+    /// Describe this removal for a removal plan, under display name `name`.
+    pub fn planned(&self, name: &str) -> Result<PlannedRemoval, String> {
+        let (branch_action, reason) = if self.would_delete_branch {
+            (
+                BranchAction::Delete,
+                format!("merged into {}", self.main_branch),
+            )
+        } else if self.upstream_only_commits > 0 {
+            (
+                BranchAction::Preserve,
+                messages::upstream_only(self.upstream_only_commits),
+            )
+        } else {
+            (BranchAction::Preserve, "not merged".to_string())
+        };
+        Ok(PlannedRemoval {
+            name: name.to_string(),
+            id: self.silo.id(),
+            path: self.silo.storage_path.clone(),
+            branch: self.silo.branch_name().to_string(),
+            head: git::get_head_commit(&self.silo.storage_path)?,
+            branch_action,
+            reason,
+        })
+    }
+
     /// Print what would happen in a dry run.
     pub fn print_dry_run(&self) {
-        println!("Would remove silo: {}", self.silo.name);
+        println!("{}", messages::would_remove(&self.silo.name));
         println!("  Path: {}", self.silo.storage_path.display());
+        self.print_dry_run_branch();
+    }
 
+    /// Print what a dry run would do with the silo's branch.
+    pub fn print_dry_run_branch(&self) {
         let branch_name = self.silo.branch_name();
         if self.would_delete_branch {
             println!(
                 "  Would delete branch '{}' (merged into {})",
                 branch_name, self.main_branch
             );
+        } else if self.upstream_only_commits > 0 {
+            println!(
+                "  Would preserve branch '{}' ({})",
+                branch_name,
+                messages::upstream_only(self.upstream_only_commits)
+            );
         } else {
             println!("  Would preserve branch '{}' (not merged)", branch_name);
         }
@@ -184,28 +341,52 @@ impl RemovableSilo {
     /// Execute the removal.
     ///
     /// If `force` is true, removes even if there are uncommitted changes.
-    /// If `quiet` is true, suppresses normal output (errors still shown).
-    pub fn remove(self, force: bool, quiet: bool) -> Result<(), String> {
-        let verbosity = if quiet {
-            Verbosity::Quiet
-        } else {
-            Verbosity::Verbose
-        };
+    /// At `Verbosity::Quiet`, suppresses normal output (errors still shown).
+    pub fn remove(self, force: bool, verbosity: Verbosity) -> Result<(), String> {
+        // Serialize with other silo processes sharing this storage directory
+        let base_dir = silo::get_silo_base_dir()?;
+        let lock_dir = silo::get_containing_base_dir(&self.silo.storage_path);
+        let _lock = StorageLock::acquire(lock_dir.as_deref().unwrap_or(&base_dir))?;
+        let is_external = lock_dir.is_none();
+        // Checked again under the lock: it may have started since validation
+        if pending::is_pending(&self.silo.storage_path) {
+            return Err(format!(
+                "{}\n{}",
+                messages::blocked(&self.silo.name, "removed"),
+                messages::blocker_line(RemovalBlocker::BeingCreated)
+            ));
+        }
 
+        leave_if_inside(&self.silo);
         git::remove_worktree(
             &self.silo.storage_path,
             &self.silo.main_worktree,
             force,
+            self.locked,
             verbosity,
         )?;
 
-        // Clean up process tracking
-        if let Err(e) = process::cleanup_tracking(&self.silo.storage_path) {
+        // External worktrees have no tracking data, just a registration
+        let cleanup = if is_external {
+            external::remove(&base_dir, &self.silo.storage_path).map(|_| ())
+        } else {
+            process::cleanup_tracking(&self.silo.storage_path)
+        };
+        if let Err(e) = cleanup {
             eprintln!("Warning: {}", e);
         }
 
-        // Clean up branch if merged
         let branch_name = self.silo.branch_name();
+        if self.upstream_only_commits > 0 {
+            eprintln!(
+                "Warning: Preserved branch '{}' ({})",
+                branch_name,
+                messages::upstream_only(self.upstream_only_commits)
+            );
+            return Ok(());
+        }
+
+        // Clean up branch if merged
         let was_merged = git::cleanup_branch(
             &self.silo.main_worktree,
             branch_name,
@@ -214,7 +395,7 @@ impl RemovableSilo {
         );
 
         // Only print "preserved" message - git already outputs deletion info
-        if !quiet && !was_merged {
+        if !verbosity.is_quiet() && !was_merged {
             println!("Preserved branch '{}' (not merged)", branch_name);
         }
 
@@ -222,6 +403,51 @@ impl RemovableSilo {
     }
 }
 
+/// Why the silo's worktree is locked with `git worktree lock` (empty if no
+/// reason was given), or None if it isn't locked.
+pub fn lock_reason(silo: &Silo) -> Option<String> {
+    git::list_worktrees(&silo.main_worktree)
+        .ok()?
+        .into_iter()
+        .find(|wt| wt.path == silo.storage_path)?
+        .locked
+}
+
+/// Move out of a silo about to be removed when run from inside it, so the
+/// shell isn't left in a deleted directory: this process changes to the
+/// main worktree (some platforms can't delete the current directory), and
+/// the shell wrapper is told to follow. Without shell integration the user
+/// is told to leave themselves.
+fn leave_if_inside(silo: &Silo) {
+    let Ok(cwd) = std::env::current_dir() else {
+        return;
+    };
+    if !is_inside(&cwd, &silo.storage_path) {
+        return;
+    }
+    let main = &silo.main_worktree;
+    if let Err(e) = std::env::set_current_dir(main) {
+        eprintln!("Warning: Failed to leave silo '{}': {}", silo.name, e);
+    }
+    if shell::is_enabled() {
+        shell::write_directive("cd", &main.display().to_string());
+    } else {
+        eprintln!(
+            "Warning: Your shell is in silo '{}', which is being removed. \
+             Run `cd {}` to leave it.",
+            silo.name,
+            main.display()
+        );
+    }
+}
+
+/// Whether `dir` is `silo_path` or inside it, either as given or with
+/// symlinks resolved (the current directory always has them resolved).
+fn is_inside(dir: &Path, silo_path: &Path) -> bool {
+    dir.starts_with(silo_path)
+        || std::fs::canonicalize(silo_path).is_ok_and(|resolved| dir.starts_with(resolved))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -259,6 +485,42 @@ mod tests {
         assert!(display.contains("not in main branch"));
     }
 
+    #[test]
+    fn test_owned_by_other_user_blocker_display() {
+        let blocker = RemovalBlocker::OwnedByOtherUser("alice".to_string());
+        assert_eq!(format!("{}", blocker), "Owned by another user: alice");
+    }
+
+    #[test]
+    fn test_in_progress_blocker() {
+        let blocker = RemovalBlocker::InProgress(git::InProgress::Bisect);
+        assert_eq!(
+            format!("{}", blocker),
+            "BISECTING in progress (finish it or run `git bisect reset`)"
+        );
+        assert_eq!(blocker.summary(), "BISECTING");
+        assert_eq!(RemovalBlocker::External.summary(), "external");
+        assert_eq!(
+            RemovalBlocker::UnmergedCommits(1).summary(),
+            "1 unmerged commit"
+        );
+    }
+
+    #[test]
+    fn test_locked_blocker() {
+        let blocker = RemovalBlocker::Locked("on a USB drive".to_string());
+        assert_eq!(
+            format!("{}", blocker),
+            "Locked with `git worktree lock`: on a USB drive \
+             (run `git worktree unlock` or pass --force twice)"
+        );
+        assert_eq!(blocker.summary(), "locked");
+        assert_eq!(
+            format!("{}", RemovalBlocker::Locked(String::new())),
+            "Locked with `git worktree lock` (run `git worktree unlock` or pass --force twice)"
+        );
+    }
+
     #[test]
     fn test_removal_error_display() {
         let silo = make_test_silo("test-branch");
@@ -272,9 +534,21 @@ mod tests {
     }
 
     #[test]
-    fn test_from_silo_unchecked_preserves_name() {
+    fn test_forced_preserves_name() {
         let silo = make_test_silo("my-feature");
-        let removable = RemovableSilo::from_silo_unchecked(silo);
+        let removable = RemovableSilo::forced(silo, false).unwrap();
         assert_eq!(removable.name(), "my-feature");
     }
+
+    #[test]
+    fn test_is_inside() {
+        let silo = Path::new("/silos/repo-abcd1234/feature");
+        assert!(is_inside(silo, silo));
+        assert!(is_inside(&silo.join("src/bin"), silo));
+        assert!(!is_inside(
+            Path::new("/silos/repo-abcd1234/feature-2"),
+            silo
+        ));
+        assert!(!is_inside(Path::new("/silos/repo-abcd1234"), silo));
+    }
 }
@@ -0,0 +1,179 @@
+//! Removal plans: what `rm` or `prune` would remove, as JSON to be reviewed
+//! outside silo (e.g. by a CI bot or a chat-ops approval) before it's done.
+//!
+//! `--dry-run --json` prints a plan whose token fingerprints the silos it
+//! removes: their paths, HEAD commits and what happens to their branches.
+//! `--plan-file` carries out an approved plan only while a fresh plan for
+//! the same silos has the same token, so nothing that changed since the
+//! review is removed. The token detects change, not forgery: it isn't
+//! signed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A removal plan, as printed by `--dry-run --json` and read by `--plan-file`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovalPlan {
+    /// The command that made the plan, "rm" or "prune"
+    pub command: String,
+    /// Whether blockers were overridden with --force
+    pub force: bool,
+    pub remove: Vec<PlannedRemoval>,
+    /// Silos left alone because of blockers; not covered by the token
+    #[serde(default)]
+    pub blocked: Vec<BlockedSilo>,
+    pub token: String,
+}
+
+/// A silo the plan removes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedRemoval {
+    pub name: String,
+    /// Canonical silo ID, which `--plan-file` resolves the silo by
+    pub id: String,
+    pub path: PathBuf,
+    pub branch: String,
+    /// Commit checked out in the silo
+    pub head: String,
+    pub branch_action: BranchAction,
+    /// Why the branch is deleted or preserved, e.g. "merged into main"
+    pub reason: String,
+}
+
+/// What removing a silo does with its branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BranchAction {
+    Delete,
+    Preserve,
+}
+
+/// A silo the plan leaves alone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockedSilo {
+    pub name: String,
+    pub id: String,
+    pub path: PathBuf,
+    /// Short reasons, e.g. "uncommitted changes"
+    pub blockers: Vec<String>,
+}
+
+impl RemovalPlan {
+    pub fn new(
+        command: &str,
+        force: bool,
+        remove: Vec<PlannedRemoval>,
+        blocked: Vec<BlockedSilo>,
+    ) -> Self {
+        let token = token(command, force, &remove);
+        RemovalPlan {
+            command: command.to_string(),
+            force,
+            remove,
+            blocked,
+            token,
+        }
+    }
+
+    /// Whether the token still matches what the plan removes, i.e. the plan
+    /// wasn't edited after it was made.
+    pub fn is_intact(&self) -> bool {
+        token(&self.command, self.force, &self.remove) == self.token
+    }
+
+    /// Print the plan as JSON.
+    pub fn print(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize removal plan: {}", e))?;
+        println!("{}", json);
+        Ok(())
+    }
+
+    /// Read a plan printed by `--dry-run --json` for `command`.
+    pub fn load(path: &Path, command: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read plan {}: {}", path.display(), e))?;
+        let plan: RemovalPlan = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid removal plan {}: {}", path.display(), e))?;
+        if plan.command != command {
+            return Err(format!(
+                "{} is a plan for `silo {}`, not `silo {}`",
+                path.display(),
+                plan.command,
+                command
+            ));
+        }
+        Ok(plan)
+    }
+}
+
+/// Fingerprint what a plan removes. Display names are left out: they
+/// depend on which other silos exist.
+fn token(command: &str, force: bool, remove: &[PlannedRemoval]) -> String {
+    let silos: Vec<_> = remove
+        .iter()
+        .map(|r| (&r.id, &r.path, &r.branch, &r.head, r.branch_action))
+        .collect();
+    let bytes = serde_json::to_vec(&(command, force, silos)).unwrap_or_default();
+    let hash = Sha256::digest(&bytes);
+    hash[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned(head: &str) -> PlannedRemoval {
+        PlannedRemoval {
+            name: "feature".to_string(),
+            id: "abcd1234:feature".to_string(),
+            path: PathBuf::from("/silos/repo-abcd1234/feature"),
+            branch: "feature".to_string(),
+            head: head.to_string(),
+            branch_action: BranchAction::Delete,
+            reason: "merged into main".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_token_changes_with_what_is_removed() {
+        let plan = RemovalPlan::new("prune", false, vec![planned("aaa")], vec![]);
+        assert_eq!(plan.token.len(), 16);
+        assert_eq!(
+            plan.token,
+            RemovalPlan::new("prune", false, vec![planned("aaa")], vec![]).token
+        );
+
+        let moved = RemovalPlan::new("prune", false, vec![planned("bbb")], vec![]);
+        assert_ne!(plan.token, moved.token);
+        let forced = RemovalPlan::new("prune", true, vec![planned("aaa")], vec![]);
+        assert_ne!(plan.token, forced.token);
+
+        assert!(plan.is_intact());
+        let mut edited = plan.clone();
+        edited.remove[0].path = PathBuf::from("/home/user/work");
+        assert!(!edited.is_intact());
+
+        let mut renamed = planned("aaa");
+        renamed.name = "repo/feature".to_string();
+        assert_eq!(
+            plan.token,
+            RemovalPlan::new("prune", false, vec![renamed], vec![]).token
+        );
+    }
+
+    #[test]
+    fn test_load_checks_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plan.json");
+        let plan = RemovalPlan::new("rm", false, vec![planned("aaa")], vec![]);
+        fs::write(&path, serde_json::to_string(&plan).unwrap()).unwrap();
+
+        assert_eq!(RemovalPlan::load(&path, "rm").unwrap(), plan);
+        let err = RemovalPlan::load(&path, "prune").unwrap_err();
+        assert!(err.contains("plan for `silo rm`"), "got: {}", err);
+    }
+}
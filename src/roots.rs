@@ -0,0 +1,77 @@
+//! One-off storage roots: directories given to `silo new --worktree-dir`
+//! for a single silo, e.g. a scratch disk for one huge checkout.
+//!
+//! Stored as `{storage}/.roots`, one directory per line. They're added to
+//! the configured roots at startup, so `list`, `rm` and `gc` scan them like
+//! any other root, but new silos only go there when asked to.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::metadata;
+
+/// Name of the one-off root list in the storage directory.
+const ROOTS_NAME: &str = ".roots";
+
+/// Get the path of the one-off root list in a storage directory.
+pub fn roots_path(base_dir: &Path) -> PathBuf {
+    base_dir.join(ROOTS_NAME)
+}
+
+/// Load the recorded one-off roots. A missing list is empty.
+pub fn load(base_dir: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(roots_path(base_dir))
+        .map(|content| {
+            content
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Record a one-off root. Returns whether it was newly added.
+pub fn add(base_dir: &Path, root: &Path) -> Result<bool, String> {
+    let mut roots = load(base_dir);
+    if roots.iter().any(|r| r == root) {
+        return Ok(false);
+    }
+    roots.push(root.to_path_buf());
+
+    fs::create_dir_all(base_dir)
+        .map_err(|e| format!("Failed to create {}: {}", base_dir.display(), e))?;
+    let content: String = roots
+        .iter()
+        .map(|root| format!("{}\n", root.display()))
+        .collect();
+    metadata::write_atomic(&roots_path(base_dir), content)
+        .map_err(|e| format!("Failed to write storage root list: {}", e))?;
+    Ok(true)
+}
+
+/// Parse a root given on the command line. Relative paths are relative to
+/// the current directory (the shell has already expanded `~`).
+pub fn parse_dir(dir: &str) -> Result<PathBuf, String> {
+    std::path::absolute(dir).map_err(|e| format!("Invalid directory '{}': {}", dir, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_records_each_root_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("storage");
+
+        assert!(load(&base).is_empty());
+        assert!(add(&base, Path::new("/scratch/silo")).unwrap());
+        assert!(!add(&base, Path::new("/scratch/silo")).unwrap());
+        assert!(add(&base, Path::new("/mnt/big")).unwrap());
+        assert_eq!(
+            load(&base),
+            vec![PathBuf::from("/scratch/silo"), PathBuf::from("/mnt/big")]
+        );
+    }
+}
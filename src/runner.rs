@@ -1,61 +1,572 @@
-//! Command execution with extra argument injection.
+//! Running commands for silos: the shared process executor and extra
+//! argument injection.
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use tracing::debug;
 
+use crate::busy;
 use crate::config::Config;
+use crate::exit;
+use crate::git;
+use crate::last_run::{self, LastRun};
+use crate::names;
 use crate::process;
+#[cfg(unix)]
+use crate::pty;
+use crate::silo::{self, Silo};
+use crate::template;
 
-/// Run a command in a specific directory with config-based argument injection.
+/// Run a command in a silo with config-based argument injection.
 ///
 /// Applies extra arguments from config based on command prefix matching,
-/// then executes the command. The process is tracked while running.
-/// Exits the process if the command fails.
-pub fn run_command(command: &[String], dir: &Path, config: &Config) -> Result<(), String> {
-    let command = apply_extra_args(command, config.extra_command_args());
-    run_command_in_dir(&command, dir)
+/// expanding placeholders for the silo, then executes the command in the
+/// silo. Config is resolved for the silo, so its own `.silo.toml` applies
+/// even when silo is invoked from elsewhere. Unless `track` is false, the
+/// process is tracked and its outcome recorded as the silo's last run. Its
+/// tmux window is marked busy while it runs.
+/// Returns the command's exit code, for silo to exit with.
+pub fn run_command(
+    command: &[String],
+    silo: &Silo,
+    config: &Config,
+    terminal: Terminal,
+    track: bool,
+) -> Result<i32, String> {
+    let executor = silo_executor(command, silo, config, track)?.terminal(terminal);
+    let _busy = start_busy(silo, config, track)?;
+    let completed = executor.run()?;
+    Ok(exit::status_code(completed.status))
 }
 
-/// Run a command in a specific directory, inheriting stdin/stdout/stderr.
-/// Tracks the process while running so other commands can see it.
-/// Exits the process if the command fails.
-fn run_command_in_dir(command: &[String], dir: &Path) -> Result<(), String> {
-    let (cmd, args) = command.split_first().ok_or("No command specified")?;
+/// Run a command in a silo like [`run_command`], but capture its output
+/// instead of passing it through (`exec --capture`). The full output goes
+/// to `log_path`, if given; the first `limit` bytes of each stream are
+/// returned. Doesn't exit on failure: the caller reports the exit code.
+pub fn capture_command(
+    command: &[String],
+    silo: &Silo,
+    config: &Config,
+    log_path: Option<&Path>,
+    limit: usize,
+    track: bool,
+) -> Result<Captured, String> {
+    let executor = silo_executor(command, silo, config, track)?;
+    let _busy = start_busy(silo, config, track)?;
+    executor.capture(log_path, limit)
+}
+
+/// Mark the silo's tmux window busy for the run, unless it is untracked:
+/// quick commands run by prompt helpers shouldn't make the status line
+/// flicker on every prompt.
+fn start_busy(silo: &Silo, config: &Config, track: bool) -> Result<Option<busy::Busy>, String> {
+    if !track {
+        return Ok(None);
+    }
+    Ok(busy::start(
+        &silo.name,
+        &config.for_dir(&silo.storage_path)?,
+    ))
+}
+
+/// An executor for `command` in a silo, with extra arguments applied and,
+/// if `track`, tracked and recorded as the silo's last run. Untracked runs
+/// write nothing to the tracking directory, for quick commands like prompt
+/// helpers that would only churn it (and clobber the last real run).
+fn silo_executor(
+    command: &[String],
+    silo: &Silo,
+    config: &Config,
+    track: bool,
+) -> Result<Executor, String> {
+    let config = config.for_dir(&silo.storage_path)?;
+    // Only the arguments this command gets are expanded, so placeholders in
+    // other commands' arguments can't fail it or cost it a lookup
+    let extra_args = matching_extra_args(command, config.extra_command_args());
+    let extra_args = if extra_args.is_empty() {
+        extra_args
+    } else {
+        Placeholders::for_silo(silo, &config)?.expand_all(&extra_args)?
+    };
+    let command = apply_extra_args(command, &extra_args);
+
+    let executor = Executor::new(&command)
+        .current_dir(&silo.storage_path)
+        .silo_env(&silo.storage_path);
+    if !track {
+        return Ok(executor);
+    }
+    Ok(executor
+        .track(&silo.storage_path)
+        .record_last_run(command.join(" ")))
+}
+
+/// Values that `{name}` placeholders in extra_command_args expand to.
+struct Placeholders {
+    values: Vec<(&'static str, String)>,
+}
+
+impl Placeholders {
+    /// Placeholders for `silo`. `{agent_email}` comes from the config, or
+    /// else from git's user.email in the silo.
+    fn for_silo(silo: &Silo, config: &Config) -> Result<Self, String> {
+        // Shared by every silo of the repo; hidden so silo scans skip it
+        let shared_target_dir = repo_storage_dir(silo)?.join(".shared").join("target");
+        let agent_email = match config.agent_email() {
+            Some(email) => Some(email.to_string()),
+            None => git::get_user_email(&silo.storage_path),
+        };
+        Ok(Self::new(silo, &shared_target_dir, agent_email))
+    }
+
+    fn new(silo: &Silo, shared_target_dir: &Path, agent_email: Option<String>) -> Self {
+        let path = |p: &Path| p.to_string_lossy().into_owned();
+        let mut values = vec![
+            ("silo", silo.name.clone()),
+            ("branch", silo.branch_name().to_string()),
+            ("repo", silo.repo_name.clone()),
+            ("silo_path", path(&silo.storage_path)),
+            ("repo_root", path(&silo.main_worktree)),
+            (
+                "tracking_dir",
+                path(&process::tracking_dir(&silo.storage_path)),
+            ),
+            ("shared_target_dir", path(shared_target_dir)),
+        ];
+        if let Some(email) = agent_email {
+            values.push(("agent_email", email));
+        }
+        Self { values }
+    }
+
+    fn expand_all(
+        &self,
+        extra_args: &HashMap<String, Vec<String>>,
+    ) -> Result<HashMap<String, Vec<String>>, String> {
+        extra_args
+            .iter()
+            .map(|(prefix, args)| {
+                let args = args
+                    .iter()
+                    .map(|arg| self.expand(arg))
+                    .collect::<Result<_, _>>()?;
+                Ok((prefix.clone(), args))
+            })
+            .collect()
+    }
+
+    /// Replace each `{name}` in `arg`. Braces that don't enclose a
+    /// placeholder-like name (e.g. `{}`) are kept as they are.
+    fn expand(&self, arg: &str) -> Result<String, String> {
+        if arg.contains("{agent_email}") && !self.values.iter().any(|(n, _)| *n == "agent_email") {
+            return Err(
+                "extra_command_args uses {agent_email}, but neither agent_email \
+                 nor git's user.email is set"
+                    .to_string(),
+            );
+        }
+        template::expand(arg, &self.values, "extra_command_args")
+    }
+}
+
+/// The directory in storage holding the silos of `silo`'s repository, e.g.
+/// `{root}/repo-1a2b3c4d`, even for a nested silo like `feature/x`. An
+/// external worktree gets the one its repository's new silos go in.
+fn repo_storage_dir(silo: &Silo) -> Result<PathBuf, String> {
+    if let Some((repo_dir, _)) = silo::split_storage_path(&silo.storage_path) {
+        return Ok(repo_dir);
+    }
+    let base = silo::get_repo_base_dir(&silo.main_worktree)?;
+    Ok(base.join(names::repo_storage_name(
+        &silo.repo_name,
+        &silo.main_worktree,
+    )))
+}
+
+/// What a command run by an [`Executor`] gets as its terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Terminal {
+    /// Inherit silo's stdin, stdout and stderr
+    #[default]
+    Inherit,
+    /// A new pseudo-terminal relayed to silo's stdin and stdout (`--tty`)
+    Pty,
+    /// No terminal: stdin from /dev/null, output through pipes (`--no-tty`)
+    None,
+}
+
+/// Outcome of a command run to completion by an [`Executor`].
+pub struct Completed {
+    pub status: ExitStatus,
+    pub duration: Duration,
+}
+
+/// Output of a stream captured by [`Executor::capture`].
+#[derive(Debug, Default)]
+pub struct CapturedStream {
+    /// The first bytes written, up to the capture limit
+    pub head: Vec<u8>,
+    /// Total number of bytes written
+    pub bytes: u64,
+}
+
+impl CapturedStream {
+    /// Whether more was written than `head` holds.
+    pub fn truncated(&self) -> bool {
+        self.bytes > self.head.len() as u64
+    }
+}
+
+/// Outcome of a command run by [`Executor::capture`].
+pub struct Captured {
+    pub completed: Completed,
+    pub stdout: CapturedStream,
+    pub stderr: CapturedStream,
+}
+
+/// Runs a child process on behalf of a silo.
+///
+/// Every command silo starts for the user (`exec`, `new`, `verify`,
+/// sandboxes and background agents) goes through here, so process tracking
+/// and last-run recording behave the same everywhere.
+pub struct Executor {
+    command: Vec<String>,
+    /// What the command is called in error messages, e.g. "verify command"
+    label: &'static str,
+    dir: Option<PathBuf>,
+    /// Silo whose tracking directory lists the process while it runs
+    tracked_in: Option<PathBuf>,
+    /// Description recorded as the tracked silo's last run
+    last_run: Option<String>,
+    /// Agent the process runs, noted in its PID file
+    agent: Option<String>,
+    terminal: Terminal,
+    /// Environment changes; None removes the variable
+    env: Vec<(String, Option<String>)>,
+}
+
+impl Executor {
+    pub fn new(command: &[String]) -> Self {
+        Self {
+            command: command.to_vec(),
+            label: "command",
+            dir: None,
+            tracked_in: None,
+            last_run: None,
+            agent: None,
+            terminal: Terminal::Inherit,
+            env: Vec::new(),
+        }
+    }
+
+    /// Name the command in error messages.
+    pub fn label(mut self, label: &'static str) -> Self {
+        self.label = label;
+        self
+    }
+
+    /// Run the command in `dir` instead of the current directory.
+    pub fn current_dir(mut self, dir: &Path) -> Self {
+        self.dir = Some(dir.to_path_buf());
+        self
+    }
+
+    /// Register the process in the silo's tracking directory so other
+    /// commands can see it while it runs. External worktrees have no
+    /// tracking directory, so nothing is recorded for them.
+    pub fn track(mut self, silo_path: &Path) -> Self {
+        if silo::is_silo_path(silo_path) {
+            self.tracked_in = Some(silo_path.to_path_buf());
+        }
+        self
+    }
+
+    /// Tell the command it runs in the silo at `silo_path`, by setting
+    /// `SILO=1`, `SILO_NAME`, `SILO_PATH` and `SILO_MAIN_WORKTREE`, so build
+    /// scripts, hooks and agents can adapt to running in a silo.
+    /// `SILO_NAME` is the full name, e.g. `feature/x` rather than `x`.
+    pub fn silo_env(self, silo_path: &Path) -> Self {
+        let name = silo::silo_name(silo_path).unwrap_or_default();
+        let executor = self
+            .env("SILO", "1")
+            .env("SILO_NAME", name)
+            .env("SILO_PATH", silo_path.to_string_lossy());
+        match git::get_main_worktree_from_silo(silo_path) {
+            Some(main) => executor.env("SILO_MAIN_WORKTREE", main.to_string_lossy()),
+            None => executor,
+        }
+    }
+
+    /// Record the outcome as the tracked silo's last run, under
+    /// `description`. Only applies together with [`Executor::track`].
+    pub fn record_last_run(mut self, description: impl Into<String>) -> Self {
+        self.last_run = Some(description.into());
+        self
+    }
+
+    /// Note in the tracked process's PID file that it runs agent `name`
+    /// (e.g. "claude"), so `list` can show which silos have agents at work.
+    pub fn agent(mut self, name: &str) -> Self {
+        self.agent = Some(name.to_string());
+        self
+    }
+
+    /// Choose what the command gets as its terminal. Only applies to
+    /// [`Executor::run`].
+    pub fn terminal(mut self, terminal: Terminal) -> Self {
+        self.terminal = terminal;
+        self
+    }
+
+    /// Set an environment variable for the command.
+    pub fn env(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.env.push((key.to_string(), Some(value.into())));
+        self
+    }
+
+    /// Remove an environment variable from the command's environment.
+    pub fn env_remove(mut self, key: &str) -> Self {
+        self.env.push((key.to_string(), None));
+        self
+    }
+
+    fn build(&self) -> Result<Command, String> {
+        let (program, args) = self
+            .command
+            .split_first()
+            .ok_or_else(|| format!("No {} specified", self.label))?;
+
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        if let Some(dir) = &self.dir {
+            cmd.current_dir(dir);
+        }
+        for (key, value) in &self.env {
+            match value {
+                Some(value) => cmd.env(key, value),
+                None => cmd.env_remove(key),
+            };
+        }
+        Ok(cmd)
+    }
 
-    let command_str = command.join(" ");
-    debug!(command = %command_str, dir = %dir.display(), "Running command");
+    fn register(&self, pid: u32) {
+        if let Some(silo_path) = &self.tracked_in
+            && let Err(e) = process::register(
+                silo_path,
+                pid,
+                &self.command.join(" "),
+                self.agent.as_deref(),
+            )
+        {
+            eprintln!("Warning: Failed to register process: {}", e);
+        }
+    }
 
-    let mut child = std::process::Command::new(cmd)
-        .args(args)
-        .current_dir(dir)
-        .stdin(std::process::Stdio::inherit())
-        .stdout(std::process::Stdio::inherit())
-        .stderr(std::process::Stdio::inherit())
-        .spawn()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
+    /// Run the command to completion, with the terminal chosen by
+    /// [`Executor::terminal`].
+    pub fn run(self) -> Result<Completed, String> {
+        let mut cmd = self.build()?;
+        debug!(command = %self.command.join(" "), dir = ?self.dir, "Running command");
+
+        let start = Instant::now();
+        let mut pid = 0;
+        let status = match self.terminal {
+            Terminal::Inherit => {
+                let mut child = cmd
+                    .stdin(Stdio::inherit())
+                    .stdout(Stdio::inherit())
+                    .stderr(Stdio::inherit())
+                    .spawn()
+                    .map_err(|e| format!("Failed to run {}: {}", self.label, e))?;
+                pid = child.id();
+                self.register(pid);
+                child.wait()
+            }
+            #[cfg(unix)]
+            Terminal::Pty => pty::run_on_pty(&mut cmd, |p| {
+                pid = p;
+                self.register(p);
+            }),
+            #[cfg(unix)]
+            Terminal::None => pty::run_without_tty(&mut cmd, |p| {
+                pid = p;
+                self.register(p);
+            }),
+            #[cfg(not(unix))]
+            Terminal::Pty | Terminal::None => {
+                return Err("--tty and --no-tty are only supported on Unix".to_string());
+            }
+        }
+        .map_err(|e| format!("Failed to run {}: {}", self.label, e))?;
+        Ok(self.finish(pid, status, start.elapsed()))
+    }
 
-    let pid = child.id();
+    /// Run the command to completion with stdin from /dev/null, capturing
+    /// stdout and stderr. Everything is written to `log_path`, interleaved
+    /// as it arrives, and the first `limit` bytes of each stream are kept.
+    pub fn capture(self, log_path: Option<&Path>, limit: usize) -> Result<Captured, String> {
+        let mut cmd = self.build()?;
+        debug!(command = %self.command.join(" "), dir = ?self.dir, "Capturing command");
+
+        let log = match log_path {
+            Some(path) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+                }
+                let file = File::create(path)
+                    .map_err(|e| format!("Failed to create log {}: {}", path.display(), e))?;
+                Some(Arc::new(Mutex::new(file)))
+            }
+            None => None,
+        };
+
+        let start = Instant::now();
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", self.label, e))?;
+        let pid = child.id();
+        self.register(pid);
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let out = {
+            let log = log.clone();
+            thread::spawn(move || capture_stream(stdout, log, limit))
+        };
+        let err = thread::spawn(move || capture_stream(stderr, log, limit));
+        let status = child.wait();
+        // The readers finish once the command and anything it left running
+        // in the background close the pipes
+        let stdout = out.join().unwrap_or_default();
+        let stderr = err.join().unwrap_or_default();
+        let status = status.map_err(|e| format!("Failed to run {}: {}", self.label, e))?;
+
+        Ok(Captured {
+            completed: self.finish(pid, status, start.elapsed()),
+            stdout,
+            stderr,
+        })
+    }
 
-    // Register the process for tracking
-    if let Err(e) = process::register(dir, pid, &command_str) {
-        eprintln!("Warning: Failed to register process: {}", e);
+    /// Unregister a finished command and record its outcome.
+    fn finish(self, pid: u32, status: ExitStatus, duration: Duration) -> Completed {
+        if let Some(silo_path) = &self.tracked_in {
+            if let Err(e) = process::unregister(silo_path, pid) {
+                eprintln!("Warning: Failed to unregister process: {}", e);
+            }
+
+            if let Some(description) = self.last_run {
+                let run = LastRun {
+                    command: description,
+                    exit_code: status.code(),
+                    duration,
+                };
+                if let Err(e) = last_run::record(silo_path, &run) {
+                    eprintln!("Warning: Failed to record run: {}", e);
+                }
+            }
+        }
+
+        Completed { status, duration }
     }
 
-    let status = child
-        .wait()
-        .map_err(|e| format!("Failed to wait for command: {}", e))?;
+    /// Start the command in the background, detached from the terminal,
+    /// with its output going to `log_path`. A tracked process stays
+    /// registered until it exits. Returns the PID of the started process.
+    pub fn spawn_detached(self, log_path: &Path) -> Result<u32, String> {
+        let mut cmd = self.build()?;
+
+        let log = File::create(log_path)
+            .map_err(|e| format!("Failed to create log {}: {}", log_path.display(), e))?;
+        let log_err = log
+            .try_clone()
+            .map_err(|e| format!("Failed to create log {}: {}", log_path.display(), e))?;
+
+        debug!(command = %self.command.join(" "), dir = ?self.dir, "Spawning detached command");
+
+        cmd.stdin(Stdio::null()).stdout(log).stderr(log_err);
 
-    // Unregister the process
-    if let Err(e) = process::unregister(dir, pid) {
-        eprintln!("Warning: Failed to unregister process: {}", e);
+        // Detach from our process group so terminal signals don't reach it
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+
+        let child = cmd
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", self.label, e))?;
+
+        let pid = child.id();
+        self.register(pid);
+        Ok(pid)
     }
+}
 
-    if !status.success() {
-        std::process::exit(status.code().unwrap_or(1));
+/// Read a stream to the end, copying it to `log` and keeping its first
+/// `limit` bytes. Log write errors are ignored: the captured head is still
+/// worth returning.
+fn capture_stream(
+    mut stream: impl Read,
+    log: Option<Arc<Mutex<File>>>,
+    limit: usize,
+) -> CapturedStream {
+    let mut captured = CapturedStream::default();
+    let mut buf = [0; 8192];
+    loop {
+        let n = match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        };
+        if let Some(log) = &log
+            && let Ok(mut file) = log.lock()
+        {
+            let _ = file.write_all(&buf[..n]);
+        }
+        let room = limit.saturating_sub(captured.head.len());
+        captured.head.extend_from_slice(&buf[..n.min(room)]);
+        captured.bytes += n as u64;
     }
-    Ok(())
+    captured
+}
+
+/// The entries of `extra_args` whose prefix `command` starts with.
+fn matching_extra_args(
+    command: &[String],
+    extra_args: &HashMap<String, Vec<String>>,
+) -> HashMap<String, Vec<String>> {
+    extra_args
+        .iter()
+        .filter(|(prefix, _)| prefix_len(prefix, command).is_some())
+        .map(|(prefix, args)| (prefix.clone(), args.clone()))
+        .collect()
+}
+
+/// The number of words in `prefix` if `command` starts with them, else None.
+fn prefix_len(prefix: &str, command: &[String]) -> Option<usize> {
+    let prefix_words: Vec<&str> = prefix.split_whitespace().collect();
+    if prefix_words.is_empty() || command.len() < prefix_words.len() {
+        return None;
+    }
+    prefix_words
+        .iter()
+        .zip(command)
+        .all(|(p, c)| *p == c)
+        .then_some(prefix_words.len())
 }
 
 /// Apply extra arguments to a command based on matching prefixes.
@@ -81,23 +592,7 @@ fn apply_extra_args(command: &[String], extra_args: &HashMap<String, Vec<String>
     // Collect matching prefixes with their word counts
     let mut matches: Vec<(usize, &Vec<String>)> = extra_args
         .iter()
-        .filter_map(|(prefix, args)| {
-            let prefix_words: Vec<&str> = prefix.split_whitespace().collect();
-            if prefix_words.is_empty() {
-                return None;
-            }
-            // Check if command starts with this prefix
-            if command.len() >= prefix_words.len()
-                && prefix_words
-                    .iter()
-                    .zip(command.iter())
-                    .all(|(p, c)| *p == c)
-            {
-                Some((prefix_words.len(), args))
-            } else {
-                None
-            }
-        })
+        .filter_map(|(prefix, args)| Some((prefix_len(prefix, command)?, args)))
         .collect();
 
     if matches.is_empty() {
@@ -137,6 +632,102 @@ fn apply_extra_args(command: &[String], extra_args: &HashMap<String, Vec<String>
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_executor_empty_command_uses_label() {
+        let err = Executor::new(&[]).label("verify command").run().err();
+        assert_eq!(err.as_deref(), Some("No verify command specified"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_capture_keeps_head_and_logs_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("capture.log");
+        let command =
+            ["sh", "-c", "printf 'hello world'; printf oops >&2; exit 3"].map(String::from);
+
+        let captured = Executor::new(&command).capture(Some(&log), 5).unwrap();
+
+        assert_eq!(captured.completed.status.code(), Some(3));
+        assert_eq!(captured.stdout.head, b"hello");
+        assert_eq!(captured.stdout.bytes, 11);
+        assert!(captured.stdout.truncated());
+        assert_eq!(captured.stderr.head, b"oops");
+        assert!(!captured.stderr.truncated());
+        let logged = fs::read_to_string(&log).unwrap();
+        assert!(logged.contains("hello world") && logged.contains("oops"));
+    }
+
+    fn test_silo() -> Silo {
+        Silo {
+            name: "feature".to_string(),
+            branch: Some("feature".to_string()),
+            main_worktree: PathBuf::from("/src/repo"),
+            storage_path: PathBuf::from("/silos/repo-abc/feature"),
+            repo_name: "repo".to_string(),
+        }
+    }
+
+    fn test_placeholders() -> Placeholders {
+        Placeholders::new(
+            &test_silo(),
+            Path::new("/silos/repo-abc/.shared/target"),
+            Some("agent@example.com".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let placeholders = test_placeholders();
+        assert_eq!(
+            placeholders.expand("{shared_target_dir}").unwrap(),
+            "/silos/repo-abc/.shared/target"
+        );
+        assert_eq!(
+            placeholders.expand("user.email={silo}@{repo}").unwrap(),
+            "user.email=feature@repo"
+        );
+        assert_eq!(
+            placeholders.expand("{tracking_dir}/log").unwrap(),
+            "/silos/repo-abc/.tracking/feature/log"
+        );
+    }
+
+    #[test]
+    fn test_expand_keeps_non_placeholder_braces() {
+        let placeholders = test_placeholders();
+        assert_eq!(placeholders.expand("{}").unwrap(), "{}");
+        assert_eq!(placeholders.expand("{a b}").unwrap(), "{a b}");
+        assert_eq!(placeholders.expand("{{silo}").unwrap(), "{feature");
+        assert_eq!(placeholders.expand("trailing{").unwrap(), "trailing{");
+    }
+
+    #[test]
+    fn test_expand_agent_email() {
+        assert_eq!(
+            test_placeholders()
+                .expand("user.email={agent_email}")
+                .unwrap(),
+            "user.email=agent@example.com"
+        );
+        let placeholders = Placeholders::new(&test_silo(), Path::new("/shared"), None);
+        let err = placeholders.expand("{agent_email}").unwrap_err();
+        assert!(err.contains("user.email is set"), "got: {}", err);
+    }
+
+    #[test]
+    fn test_matching_extra_args_skips_other_prefixes() {
+        let command = vec!["git".to_string(), "status".to_string()];
+        let mut extra_args = HashMap::new();
+        extra_args.insert("git".to_string(), vec!["-c".to_string()]);
+        extra_args.insert("git log".to_string(), vec!["--oneline".to_string()]);
+        extra_args.insert("cargo".to_string(), vec!["{unknown}".to_string()]);
+
+        let matching = matching_extra_args(&command, &extra_args);
+        assert_eq!(matching.len(), 1);
+        assert!(matching.contains_key("git"));
+    }
+
     #[test]
     fn test_apply_extra_args_no_match() {
         let command = vec!["cargo".to_string(), "build".to_string()];
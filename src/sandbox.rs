@@ -1,8 +1,67 @@
 //! Sandbox configuration for running agents in isolated Docker containers.
 
+use crate::config::SigningFormat;
 use crate::process;
-use std::path::Path;
-use std::process::{Command, Stdio};
+use crate::runner::Executor;
+use crate::transcript;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+
+/// Path inside the container where the per-run scratch directory is mounted.
+pub const SCRATCH_CONTAINER_PATH: &str = "/scratch";
+
+/// Path inside the container where Claude writes its session transcripts.
+pub const TRANSCRIPT_CONTAINER_PATH: &str = "/home/agent/.claude/projects";
+
+/// Source name that refers to the main worktree in a mount spec.
+pub const MAIN_WORKTREE_SOURCE: &str = "@main";
+
+/// A request to mount another silo (or the main worktree) into the sandbox.
+/// Parsed from `SOURCE:CONTAINER_PATH[:ro]`; mounts are always read-only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountSpec {
+    /// Silo name, or `@main` for the main worktree
+    pub source: String,
+    /// Absolute path inside the container
+    pub target: String,
+}
+
+impl MountSpec {
+    /// Parse a `SOURCE:CONTAINER_PATH[:ro]` mount spec. It is split from the
+    /// right, so sources with colons of their own (silo IDs, quoted names)
+    /// are kept whole.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (rest, mode) = match spec.rsplit_once(':') {
+            Some((rest, mode)) if !mode.starts_with('/') => (rest, Some(mode)),
+            _ => (spec, None),
+        };
+        let (source, target) = match rest.rsplit_once(':') {
+            Some((source, target)) if !source.is_empty() && target.starts_with('/') => {
+                (source, target)
+            }
+            _ => {
+                return Err(format!(
+                    "Invalid mount '{}': expected SILO:/container/path[:ro]",
+                    spec
+                ));
+            }
+        };
+        if let Some(mode) = mode
+            && mode != "ro"
+        {
+            return Err(format!(
+                "Invalid mount mode '{}' in '{}': extra silos can only be mounted read-only",
+                mode, spec
+            ));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            target: target.to_string(),
+        })
+    }
+}
 
 /// Configuration for running an agent in a Docker sandbox.
 pub struct DockerSandboxConfig {
@@ -14,6 +73,10 @@ pub struct DockerSandboxConfig {
     pub credentials_mode: String,
     /// Volume mounts: (host_path, container_path)
     pub mounts: Vec<(String, String)>,
+    /// Writable per-run scratch directory, mounted at `/scratch`
+    pub scratch: Option<PathBuf>,
+    /// Writable directory where the agent's session transcripts are kept
+    pub transcript_dir: Option<PathBuf>,
     /// Additional arguments to pass to the agent
     pub args: Vec<String>,
 }
@@ -48,6 +111,8 @@ impl DockerSandboxConfig {
                     "/home/agent/.config/gcloud/application_default_credentials.json".to_string(),
                 ),
             ],
+            scratch: Some(new_scratch_run_dir(workspace)),
+            transcript_dir: Some(transcript::transcripts_dir(workspace)),
             args,
         }
     }
@@ -70,53 +135,139 @@ impl DockerSandboxConfig {
             }
         }
 
+        if let Some(scratch) = &self.scratch {
+            cmd.push("-v".to_string());
+            cmd.push(format!("{}:{}", scratch.display(), SCRATCH_CONTAINER_PATH));
+        }
+
+        if let Some(dir) = &self.transcript_dir {
+            cmd.push("-v".to_string());
+            cmd.push(format!("{}:{}", dir.display(), TRANSCRIPT_CONTAINER_PATH));
+        }
+
         cmd.push(self.runner.clone());
         cmd.extend(self.args.clone());
 
         cmd
     }
 
+    /// Add a read-only mount of a host directory into the container.
+    pub fn add_readonly_mount(&mut self, host: &Path, container: &str) {
+        self.mounts
+            .push((host.display().to_string(), container.to_string()));
+    }
+
+    /// Mount what signing commits in the container needs: an SSH key at
+    /// its own path, which the silo's `user.signingKey` names, or the
+    /// GnuPG home. Mounts are read-only.
+    pub fn add_signing_mounts(&mut self, format: SigningFormat, key: Option<&str>) {
+        match (format, key) {
+            (SigningFormat::Ssh, Some(key)) => {
+                self.mounts.push((key.to_string(), key.to_string()));
+            }
+            // Without a key, git signs with one from the SSH agent
+            (SigningFormat::Ssh, None) => {}
+            (SigningFormat::Openpgp | SigningFormat::X509, _) => {
+                let home = std::env::var("HOME").unwrap_or_default();
+                self.mounts
+                    .push((format!("{}/.gnupg", home), "/home/agent/.gnupg".to_string()));
+            }
+        }
+    }
+
     /// Print the docker command to stdout (for --dry-run).
     pub fn print(&self) {
         println!("{}", self.to_command().join(" "));
     }
 
-    /// Execute the docker sandbox command.
-    /// Tracks the process while running so other commands can see it.
-    pub fn run(&self, silo_path: &Path) -> Result<(), String> {
-        let cmd_parts = self.to_command();
-        let (program, args) = cmd_parts.split_first().ok_or("Empty command")?;
+    /// Execute the docker sandbox command and return its exit status.
+    /// Tracks the process while running so other commands can see it, and
+    /// records its outcome as the silo's last run.
+    pub fn run(&self, silo_path: &Path) -> Result<ExitStatus, String> {
+        if let Some(scratch) = &self.scratch {
+            fs::create_dir_all(scratch)
+                .map_err(|e| format!("Failed to create scratch directory: {}", e))?;
+        }
+        if let Some(dir) = &self.transcript_dir {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create transcript directory: {}", e))?;
+        }
 
-        let mut child = Command::new(program)
-            .args(args)
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .spawn()
-            .map_err(|e| format!("Failed to run docker: {}", e))?;
+        let completed = Executor::new(&self.to_command())
+            .label("docker")
+            .track(silo_path)
+            .agent(&self.runner)
+            .silo_env(silo_path)
+            .record_last_run(self.run_description())
+            .run()?;
+        Ok(completed.status)
+    }
 
-        let pid = child.id();
-        let command_str = cmd_parts.join(" ");
+    /// Short description of the agent invocation, e.g. "sandbox claude -c".
+    fn run_description(&self) -> String {
+        std::iter::once(format!("sandbox {}", self.runner))
+            .chain(self.args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
 
-        // Register the process for tracking
-        if let Err(e) = process::register(silo_path, pid, &command_str) {
-            eprintln!("Warning: Failed to register process: {}", e);
-        }
+/// Get the directory holding all scratch runs for a silo.
+pub fn scratch_root(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("scratch")
+}
 
-        let status = child
-            .wait()
-            .map_err(|e| format!("Failed to wait for docker: {}", e))?;
+/// Get a fresh scratch directory path for a new sandbox run. Named by the
+/// start time down to the nanosecond and the PID, so sandboxes started in
+/// the same second don't write their artifacts into one directory.
+fn new_scratch_run_dir(silo_path: &Path) -> PathBuf {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    scratch_root(silo_path).join(format!(
+        "run-{}.{:09}-{}",
+        now.as_secs(),
+        now.subsec_nanos(),
+        std::process::id()
+    ))
+}
 
-        // Unregister the process
-        if let Err(e) = process::unregister(silo_path, pid) {
-            eprintln!("Warning: Failed to unregister process: {}", e);
+/// List files written to scratch directories, relative to `root`, sorted.
+pub fn list_artifacts(root: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, root: &Path, out: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, root, out);
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                out.push(relative.to_path_buf());
+            }
         }
+    }
+
+    let mut files = Vec::new();
+    walk(root, root, &mut files);
+    files.sort();
+    files
+}
 
-        if !status.success() {
-            std::process::exit(status.code().unwrap_or(1));
+/// Copy artifacts from `root` into `dest`, preserving relative paths.
+/// Returns the number of files copied.
+pub fn copy_artifacts(root: &Path, dest: &Path) -> Result<usize, String> {
+    let files = list_artifacts(root);
+    for file in &files {
+        let target = dest.join(file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
         }
-        Ok(())
+        fs::copy(root.join(file), &target)
+            .map_err(|e| format!("Failed to copy {}: {}", file.display(), e))?;
     }
+    Ok(files.len())
 }
 
 #[cfg(test)]
@@ -152,6 +303,8 @@ mod tests {
             workspace: workspace.clone(),
             credentials_mode: "none".to_string(),
             mounts: vec![], // Empty mounts for predictable test
+            scratch: None,
+            transcript_dir: None,
             args: vec![],
         };
 
@@ -165,6 +318,124 @@ mod tests {
         assert_eq!(cmd[6], "claude");
     }
 
+    #[test]
+    fn test_claude_config_has_scratch_under_tracking() {
+        let workspace = PathBuf::from("/var/silo/repo-abc/feature");
+        let config = DockerSandboxConfig::claude(&workspace, vec![]);
+        let scratch = config.scratch.unwrap();
+        assert!(scratch.starts_with("/var/silo/repo-abc/.tracking/feature/scratch"));
+    }
+
+    #[test]
+    fn test_to_command_mounts_scratch_writable() {
+        let config = DockerSandboxConfig {
+            runner: "claude".to_string(),
+            workspace: PathBuf::from("/test/workspace"),
+            credentials_mode: "none".to_string(),
+            mounts: vec![],
+            scratch: Some(PathBuf::from("/tmp/scratch/run-1")),
+            transcript_dir: None,
+            args: vec![],
+        };
+        let cmd = config.to_command();
+        assert!(cmd.contains(&"/tmp/scratch/run-1:/scratch".to_string()));
+    }
+
+    #[test]
+    fn test_to_command_mounts_transcript_dir() {
+        let workspace = PathBuf::from("/var/silo/repo-abc/feature");
+        let config = DockerSandboxConfig::claude(&workspace, vec![]);
+        let cmd = config.to_command();
+        assert!(cmd.contains(&format!(
+            "/var/silo/repo-abc/.tracking/feature/transcripts:{}",
+            TRANSCRIPT_CONTAINER_PATH
+        )));
+    }
+
+    #[test]
+    fn test_list_and_copy_artifacts() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("scratch");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(root.join("run-1/reports")).unwrap();
+        fs::write(root.join("run-1/reports/summary.md"), "ok").unwrap();
+        fs::write(root.join("run-1/notes.txt"), "notes").unwrap();
+
+        let files = list_artifacts(&root);
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("run-1/notes.txt"),
+                PathBuf::from("run-1/reports/summary.md")
+            ]
+        );
+
+        assert_eq!(copy_artifacts(&root, &dest).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(dest.join("run-1/reports/summary.md")).unwrap(),
+            "ok"
+        );
+    }
+
+    #[test]
+    fn test_list_artifacts_missing_root() {
+        assert!(list_artifacts(Path::new("/nonexistent/scratch")).is_empty());
+    }
+
+    #[test]
+    fn test_mount_spec_parse() {
+        let spec = MountSpec::parse("@main:/workspace-main:ro").unwrap();
+        assert_eq!(spec.source, "@main");
+        assert_eq!(spec.target, "/workspace-main");
+
+        let spec = MountSpec::parse("feature:/other").unwrap();
+        assert_eq!(spec.source, "feature");
+        assert_eq!(spec.target, "/other");
+    }
+
+    #[test]
+    fn test_new_scratch_run_dirs_are_unique() {
+        let silo = Path::new("/tmp/silo-scratch-test/feature");
+        assert_ne!(new_scratch_run_dir(silo), new_scratch_run_dir(silo));
+    }
+
+    #[test]
+    fn test_mount_spec_parse_source_with_colons() {
+        let spec = MountSpec::parse("3f2a9c1d:feature:/other:ro").unwrap();
+        assert_eq!(spec.source, "3f2a9c1d:feature");
+        assert_eq!(spec.target, "/other");
+
+        let spec = MountSpec::parse("[api]:feature/x:/other").unwrap();
+        assert_eq!(spec.source, "[api]:feature/x");
+        assert_eq!(spec.target, "/other");
+    }
+
+    #[test]
+    fn test_mount_spec_rejects_writable_and_malformed() {
+        assert!(MountSpec::parse("@main:/workspace:rw").is_err());
+        assert!(MountSpec::parse("@main").is_err());
+        assert!(MountSpec::parse("@main:relative").is_err());
+        assert!(MountSpec::parse(":/path").is_err());
+    }
+
+    #[test]
+    fn test_add_readonly_mount_in_command() {
+        let dir = std::env::temp_dir();
+        let mut config = DockerSandboxConfig {
+            runner: "claude".to_string(),
+            workspace: PathBuf::from("/test/workspace"),
+            credentials_mode: "none".to_string(),
+            mounts: vec![],
+            scratch: None,
+            transcript_dir: None,
+            args: vec![],
+        };
+        config.add_readonly_mount(&dir, "/workspace-main");
+
+        let cmd = config.to_command();
+        assert!(cmd.contains(&format!("{}:/workspace-main:ro", dir.display())));
+    }
+
     #[test]
     fn test_to_command_with_args() {
         let workspace = PathBuf::from("/test/workspace");
@@ -173,6 +444,8 @@ mod tests {
             workspace,
             credentials_mode: "none".to_string(),
             mounts: vec![],
+            scratch: None,
+            transcript_dir: None,
             args: vec!["-c".to_string(), "hello".to_string()],
         };
 
@@ -0,0 +1,107 @@
+pub fn init_script(silo_bin: &str) -> String {
+    format!(
+        r##"# Silo shell integration for bash
+# Add to ~/.bashrc:
+#   eval "$(silo shell init bash)"
+
+# Store path to real silo binary (before we shadow it with a function)
+__silo_bin="{silo_bin}"
+
+# Create session-scoped directive file for communication between binary and shell
+export SILO_DIRECTIVE_FILE=$(mktemp)
+# Where the binary writes directives if the directive file can't be written:
+# a private directory, so other users can't plant directives in it
+export SILO_RECOVERY_DIR=$(mktemp -d)
+__silo_recovery_file="$SILO_RECOVERY_DIR/directive"
+trap 'rm -rf "$SILO_DIRECTIVE_FILE" "$SILO_RECOVERY_DIR"' EXIT
+
+silo() {{
+    # Clear directive file before each command, recreating it if it was
+    # removed along with its directory (tmp cleanup)
+    if ! {{ : > "$SILO_DIRECTIVE_FILE"; }} 2>/dev/null; then
+        export SILO_DIRECTIVE_FILE=$(mktemp)
+    fi
+    rm -f "$__silo_recovery_file"
+
+    # Run the silo binary
+    "$__silo_bin" "$@"
+    local exit_code=$?
+
+    # Process directives from file
+    local file key value
+    for file in "$SILO_DIRECTIVE_FILE" "$__silo_recovery_file"; do
+        [[ -f "$file" ]] || continue
+        while IFS='=' read -r key value; do
+            case "$key" in
+                cd) builtin cd -- "$value" ;;
+                last)
+                    # Save current silo as "last" (for cd -), then update current
+                    export SILO_LAST="$SILO_CURRENT"
+                    export SILO_CURRENT="$value"
+                    ;;
+            esac
+        done < "$file"
+    done
+    rm -f "$__silo_recovery_file"
+
+    # Commands may have created or removed silos
+    __silo_completion_cache=()
+    __silo_completion_time=()
+
+    return $exit_code
+}}
+
+# Completions
+# Results are cached per directory and preceding words for a few seconds
+# (bash 5 and later), since listing silos runs git. Override with
+# SILO_COMPLETION_TTL (seconds).
+if (( BASH_VERSINFO[0] >= 5 )); then
+    declare -gA __silo_completion_cache=() __silo_completion_time=()
+fi
+: "${{SILO_COMPLETION_TTL:=5}}"
+
+_silo() {{
+    # Split the line ourselves: bash splits words at colons too, and silo
+    # IDs contain one
+    local line="${{COMP_LINE:0:COMP_POINT}}"
+    local -a words
+    read -ra words <<< "$line"
+    [[ -z "$line" || "$line" == *[[:space:]] ]] && words+=("")
+    local count=${{#words[@]}}
+    local cur="${{words[count-1]}}"
+
+    # The word being completed doesn't change the candidates, except that
+    # flags are offered for one starting with '-'
+    local key="$PWD ${{words[*]:1:count-2}}"
+    [[ "$cur" == -* ]] && key+=" -"
+    local output cached_at=
+    [[ -n "$EPOCHSECONDS" ]] && cached_at="${{__silo_completion_time[$key]}}"
+    if [[ -n "$cached_at" ]] && (( EPOCHSECONDS - cached_at < SILO_COMPLETION_TTL )); then
+        output="${{__silo_completion_cache[$key]}}"
+    else
+        # Pass all words after 'silo' to the completion command
+        output="$("$__silo_bin" shell complete-args --format bash -- "${{words[@]:1}}" 2>/dev/null)"
+        if [[ -n "$EPOCHSECONDS" ]]; then
+            __silo_completion_cache[$key]="$output"
+            __silo_completion_time[$key]="$EPOCHSECONDS"
+        fi
+    fi
+
+    # Bash replaces only the part of the word after its last colon
+    local prefix=
+    if [[ "$cur" == *:* && "$COMP_WORDBREAKS" == *:* ]]; then
+        prefix="${{cur%"${{cur##*:}}"}}"
+    fi
+
+    COMPREPLY=()
+    local candidate
+    while IFS= read -r candidate; do
+        [[ -n "$candidate" && "$candidate" == "$cur"* ]] || continue
+        printf -v candidate '%q' "${{candidate#"$prefix"}}"
+        COMPREPLY+=("$candidate")
+    done <<< "$output"
+}}
+complete -F _silo silo
+"##
+    )
+}
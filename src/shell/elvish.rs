@@ -0,0 +1,80 @@
+//! Shell integration for elvish: a `silo` function that runs the binary and
+//! applies its directives (`cd`, `last`), and an argument completer.
+
+pub fn init_script(silo_bin: &str) -> String {
+    format!(
+        r##"# Silo shell integration for elvish
+# Add to ~/.config/elvish/rc.elv:
+#   eval (silo shell init elvish | slurp)
+
+use path
+use str
+
+# Path to the real silo binary (before we shadow it with a function)
+var __silo_bin = {silo_bin}
+
+# Create session-scoped directive file for communication between binary and shell
+set-env SILO_DIRECTIVE_FILE (e:mktemp)
+# Where the binary writes directives if the directive file can't be written:
+# a private directory, so other users can't plant directives in it
+set-env SILO_RECOVERY_DIR (e:mktemp -d)
+var __silo_recovery_file = $E:SILO_RECOVERY_DIR/directive
+
+fn silo {{|@args|
+    # Clear directive file before each command, recreating it if it was
+    # removed along with its directory (tmp cleanup)
+    try {{ print '' > $E:SILO_DIRECTIVE_FILE }} catch {{ set-env SILO_DIRECTIVE_FILE (e:mktemp) }}
+    e:rm -f $__silo_recovery_file
+
+    # Run the silo binary, keeping its failure to rethrow once directives
+    # are processed
+    var err = $nil
+    try {{ (external $__silo_bin) $@args }} catch e {{ set err = $e }}
+
+    # Process directives from file
+    for file [$E:SILO_DIRECTIVE_FILE $__silo_recovery_file] {{
+        if (not (path:is-regular $file)) {{ continue }}
+        for line [(from-lines < $file)] {{
+            if (not (str:contains $line '=')) {{ continue }}
+            var key value = (str:split &max=2 '=' $line)
+            if (eq $key cd) {{
+                cd $value
+            }} elif (eq $key last) {{
+                # Save current silo as "last" (for cd -), then update current
+                set-env SILO_LAST $E:SILO_CURRENT
+                set-env SILO_CURRENT $value
+            }}
+        }}
+    }}
+    e:rm -f $__silo_recovery_file
+
+    if (not-eq $err $nil) {{ fail $err }}
+}}
+edit:add-var silo~ $silo~
+
+# Completions: elvish filters the candidates by the word being completed
+set edit:completion:arg-completer[silo] = {{|@words|
+    try {{
+        (external $__silo_bin) shell complete-args --format bash -- (all $words[1..]) 2>/dev/null | from-lines
+    }} catch {{ }}
+}}
+"##,
+        silo_bin = quote(silo_bin),
+    )
+}
+
+/// Quote a word for elvish, so it's passed on as is.
+fn quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_path_is_quoted() {
+        let script = init_script("/opt/it's/silo");
+        assert!(script.contains("var __silo_bin = '/opt/it''s/silo'\n"));
+    }
+}
@@ -1,10 +1,13 @@
 use clap::Subcommand;
 use std::fs::OpenOptions;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::config::Config;
 
+pub mod bash;
+pub mod elvish;
 pub mod zsh;
 
 /// Supported shell types for integration
@@ -12,27 +15,67 @@ pub mod zsh;
 pub enum ShellType {
     /// Zsh shell integration
     Zsh,
+    /// Bash shell integration
+    Bash,
+    /// Elvish shell integration
+    Elvish,
 }
 
 /// Environment variable for the directive file path
 pub const DIRECTIVE_FILE_ENV: &str = "SILO_DIRECTIVE_FILE";
 
+/// Environment variable for the wrapper's private directory (made with
+/// `mktemp -d`) that holds the recovery file
+pub const RECOVERY_DIR_ENV: &str = "SILO_RECOVERY_DIR";
+
 /// Environment variable for the last used silo (previous location for `cd -`)
 pub const LAST_ENV: &str = "SILO_LAST";
 
 /// Write a directive to the specified path (if provided).
 /// Directives are written as `key=value\n` lines.
-fn write_directive_to_path(path: Option<PathBuf>, key: &str, value: &str) {
+/// Returns false if a path was given but could not be written.
+fn write_directive_to_path(path: Option<PathBuf>, key: &str, value: &str) -> bool {
     let Some(path) = path else {
-        return;
+        return true;
     };
 
     let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) else {
-        return;
+        return false;
     };
 
-    // Silently ignore write errors - directive file is optional
-    let _ = writeln!(file, "{}={}", key, value);
+    writeln!(file, "{}={}", key, value).is_ok()
+}
+
+/// File the shell wrapper also reads directives from, for when the directive
+/// file can't be written (e.g. its temp directory was cleaned up). It lives
+/// in a directory private to the wrapper, whose unguessable name only the
+/// wrapper's environment holds, so other users can't plant directives there.
+fn recovery_path() -> Option<PathBuf> {
+    let dir = std::env::var_os(RECOVERY_DIR_ENV)?;
+    Some(PathBuf::from(dir).join("directive"))
+}
+
+/// Recreate the recovery file's directory if it was cleaned up too,
+/// readable only by the current user.
+fn ensure_private_dir(dir: &Path) {
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    let _ = builder.create(dir);
+}
+
+/// Check whether a directive file exists and can be appended to, or could be
+/// created. Does not create it.
+pub fn is_directive_file_writable(path: &Path) -> bool {
+    if path.exists() {
+        OpenOptions::new().append(true).open(path).is_ok()
+    } else {
+        path.parent()
+            .is_some_and(|dir| dir.is_dir() && crate::storage::is_writable(dir))
+    }
 }
 
 /// Check if shell integration is enabled.
@@ -58,6 +101,15 @@ hint: Shell integration is not enabled. The `cd` command printed the
         # For zsh (~/.zshrc):
         eval \"$(silo shell init zsh)\"
 
+        # For bash (~/.bashrc):
+        eval \"$(silo shell init bash)\"
+
+        # For elvish (~/.config/elvish/rc.elv):
+        eval (silo shell init elvish | slurp)
+
+      Where that isn't possible, `silo cd NAME --spawn` starts a new
+      shell in the silo instead.
+
       To disable this warning, add to ~/.config/silo.toml:
 
         warn_shell_integration = false
@@ -67,50 +119,105 @@ hint: Shell integration is not enabled. The `cd` command printed the
 
 /// Write a directive to the directive file (if configured).
 /// Directives are written as `key=value\n` lines.
-/// If SILO_DIRECTIVE_FILE is not set, this is a no-op.
+/// If SILO_DIRECTIVE_FILE is not set, this is a no-op. If it can't be
+/// written, the directive goes to the recovery file instead, and a warning
+/// is printed if that fails too.
 pub fn write_directive(key: &str, value: &str) {
     let path = std::env::var_os(DIRECTIVE_FILE_ENV).map(PathBuf::from);
-    write_directive_to_path(path, key, value);
+    if let Some(lost) = write_directive_with_recovery(path, recovery_path(), key, value) {
+        warn_directive_file_lost(&lost);
+    }
+}
+
+/// Write a directive, falling back to `recovery` if `path` can't be written.
+/// Returns the directive file path if neither could be written.
+fn write_directive_with_recovery(
+    path: Option<PathBuf>,
+    recovery: Option<PathBuf>,
+    key: &str,
+    value: &str,
+) -> Option<PathBuf> {
+    let path = path?;
+    if write_directive_to_path(Some(path.clone()), key, value) {
+        return None;
+    }
+    if let Some(dir) = recovery.as_deref().and_then(Path::parent) {
+        ensure_private_dir(dir);
+    }
+    let recovered = recovery.is_some() && write_directive_to_path(recovery, key, value);
+    (!recovered).then_some(path)
+}
+
+/// Warn (once per process) that directives could not be delivered.
+fn warn_directive_file_lost(path: &Path) {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+    if WARNED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    eprintln!(
+        "Warning: Cannot write shell directive file {}; `cd` and `-` won't work in this shell.\n\
+         Run `eval \"$(silo shell init zsh)\"` or open a new shell to restore shell integration.",
+        path.display()
+    );
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
-    use std::path::PathBuf;
-
-    fn temp_file(suffix: &str) -> PathBuf {
-        let mut path = std::env::temp_dir();
-        path.push(format!(
-            "silo-test-{}-{:?}-{}",
-            std::process::id(),
-            std::thread::current().id(),
-            suffix
-        ));
-        path
-    }
 
     #[test]
     fn test_write_directive_format() {
-        let path = temp_file("format");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("directive");
         write_directive_to_path(Some(path.clone()), "cd", "/some/path");
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(content, "cd=/some/path\n");
-
-        fs::remove_file(&path).ok();
     }
 
     #[test]
     fn test_write_directive_appends() {
-        let path = temp_file("appends");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("directive");
         write_directive_to_path(Some(path.clone()), "cd", "/path/one");
         write_directive_to_path(Some(path.clone()), "last", "feature-branch");
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(content, "cd=/path/one\nlast=feature-branch\n");
+    }
+
+    #[test]
+    fn test_write_directive_falls_back_to_recovery_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing-dir/directive");
+        // Its directory was cleaned up as well
+        let recovery = dir.path().join("recovery/directive");
+
+        let lost = write_directive_with_recovery(
+            Some(missing.clone()),
+            Some(recovery.clone()),
+            "cd",
+            "/p",
+        );
+
+        assert_eq!(lost, None);
+        assert!(!missing.exists());
+        assert_eq!(fs::read_to_string(&recovery).unwrap(), "cd=/p\n");
+    }
+
+    #[test]
+    fn test_write_directive_reports_lost_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("lost-dir/directive");
+        // Only the recovery file's own directory is recreated
+        let also_missing = dir.path().join("gone/recovery/directive");
+
+        let lost =
+            write_directive_with_recovery(Some(missing.clone()), Some(also_missing), "cd", "/p");
 
-        fs::remove_file(&path).ok();
+        assert_eq!(lost, Some(missing.clone()));
+        assert!(!is_directive_file_writable(&missing));
     }
 
     #[test]
@@ -1,4 +1,20 @@
-pub fn init_script(silo_bin: &str) -> String {
+/// A key binding for the silo picker (`shell_widget_key`).
+pub struct Widget<'a> {
+    /// Key sequence as `bindkey` takes it, e.g. "^G"
+    pub key: &'a str,
+    /// Command that reads silo names on stdin and prints the chosen one
+    pub picker: &'a [String],
+}
+
+pub fn init_script(silo_bin: &str, widget: Option<Widget>) -> String {
+    let mut script = base_script(silo_bin);
+    if let Some(widget) = widget {
+        script.push_str(&widget_script(&widget));
+    }
+    script
+}
+
+fn base_script(silo_bin: &str) -> String {
     format!(
         r##"# Silo shell integration for zsh
 # Add to ~/.zshrc:
@@ -9,41 +25,160 @@ __silo_bin="{silo_bin}"
 
 # Create session-scoped directive file for communication between binary and shell
 export SILO_DIRECTIVE_FILE=$(mktemp)
-trap "rm -f '$SILO_DIRECTIVE_FILE'" EXIT
+# Where the binary writes directives if the directive file can't be written:
+# a private directory, so other users can't plant directives in it
+export SILO_RECOVERY_DIR=$(mktemp -d)
+__silo_recovery_file="$SILO_RECOVERY_DIR/directive"
+trap 'rm -rf "$SILO_DIRECTIVE_FILE" "$SILO_RECOVERY_DIR"' EXIT
 
 silo() {{
-    # Clear directive file before each command
-    : > "$SILO_DIRECTIVE_FILE"
+    # Clear directive file before each command, recreating it if it was
+    # removed along with its directory (tmp cleanup)
+    if ! {{ : > "$SILO_DIRECTIVE_FILE"; }} 2>/dev/null; then
+        export SILO_DIRECTIVE_FILE=$(mktemp)
+    fi
+    rm -f "$__silo_recovery_file"
 
     # Run the silo binary
     "$__silo_bin" "$@"
     local exit_code=$?
 
     # Process directives from file
-    while IFS='=' read -r key value; do
-        case "$key" in
-            cd) builtin cd "$value" ;;
-            last)
-                # Save current silo as "last" (for cd -), then update current
-                export SILO_LAST="$SILO_CURRENT"
-                export SILO_CURRENT="$value"
-                ;;
-        esac
-    done < "$SILO_DIRECTIVE_FILE"
+    local file
+    for file in "$SILO_DIRECTIVE_FILE" "$__silo_recovery_file"; do
+        [[ -f "$file" ]] || continue
+        while IFS='=' read -r key value; do
+            case "$key" in
+                cd) builtin cd "$value" ;;
+                last)
+                    # Save current silo as "last" (for cd -), then update current
+                    export SILO_LAST="$SILO_CURRENT"
+                    export SILO_CURRENT="$value"
+                    ;;
+            esac
+        done < "$file"
+    done
+    rm -f "$__silo_recovery_file"
+
+    # Commands may have created or removed silos
+    __silo_completion_cache=()
+    __silo_completion_time=()
 
     return $exit_code
 }}
 
 # Completions
+# Results are cached per directory and preceding words for a few seconds,
+# since listing silos runs git. Override with SILO_COMPLETION_TTL (seconds).
+zmodload -F zsh/datetime p:EPOCHSECONDS 2>/dev/null
+typeset -gA __silo_completion_cache __silo_completion_time
+: ${{SILO_COMPLETION_TTL:=5}}
+
 _silo() {{
-    local -a completions
+    local -a lines commands silos branches flags repo_items
+    local -A repo_silos
+    local output line repo
+    # The word being completed doesn't change the candidates, except that
+    # flags are offered for one starting with '-'
+    local key="$PWD ${{words[2,CURRENT-1]}}"
+    [[ "${{words[CURRENT]}}" == -* ]] && key+=" -"
+    local cached_at="${{__silo_completion_time[$key]}}"
+
+    if [[ -n "$EPOCHSECONDS" && -n "$cached_at" ]] &&
+        (( EPOCHSECONDS - cached_at < SILO_COMPLETION_TTL )); then
+        output="${{__silo_completion_cache[$key]}}"
+    else
+        # Pass all words after 'silo' to the completion command
+        output="$("$__silo_bin" shell complete-args --format zsh -- "${{words[@]:1}}" 2>/dev/null)"
+        __silo_completion_cache[$key]="$output"
+        __silo_completion_time[$key]="${{EPOCHSECONDS:-0}}"
+    fi
+
+    # Each line is GROUP<tab>VALUE[:DESCRIPTION]; outside a repository,
+    # silos come in one group per repository (silo/REPO)
+    lines=("${{(@f)output}}")
+    for line in "${{lines[@]}}"; do
+        case "${{line%%$'\t'*}}" in
+            command) commands+=("${{line#*$'\t'}}") ;;
+            silo) silos+=("${{line#*$'\t'}}") ;;
+            silo/*)
+                repo="${{${{line%%$'\t'*}}#silo/}}"
+                repo_silos[$repo]+="${{line#*$'\t'}}"$'\n'
+                ;;
+            branch) branches+=("${{line#*$'\t'}}") ;;
+            flag) flags+=("${{line#*$'\t'}}") ;;
+        esac
+    done
 
-    # Pass all words after 'silo' to the completion command
-    # words[2,-1] gets all elements from position 2 to the end
-    completions=("${{(@f)$("$__silo_bin" shell complete-args -- "${{words[@]:1}}" 2>/dev/null)}}")
-    [[ -n "$completions" ]] && _describe 'completion' completions
+    local ret=1
+    (( $#commands )) && _describe -t commands 'command' commands && ret=0
+    (( $#silos )) && _describe -t silos 'silo' silos && ret=0
+    for repo in ${{(ok)repo_silos}}; do
+        repo_items=("${{(@f)${{repo_silos[$repo]%$'\n'}}}}")
+        _describe -t "silos-${{repo//[^[:alnum:]_.-]/_}}" "silo in $repo" repo_items && ret=0
+    done
+    (( $#branches )) && _describe -t branches 'remote branch' branches && ret=0
+    (( $#flags )) && _describe -t options 'option' flags && ret=0
+    return ret
 }}
 compdef _silo silo
 "##
     )
 }
+
+/// A zle widget that picks a silo and cds into it, bound to `widget.key`.
+fn widget_script(widget: &Widget) -> String {
+    let picker: Vec<String> = widget.picker.iter().map(|word| quote(word)).collect();
+    format!(
+        r##"
+# Picker widget (shell_widget_key): choose a silo and cd into it
+__silo_pick() {{
+    local choice
+    choice="$("$__silo_bin" shell complete-args --format bash -- cd '' 2>/dev/null | {picker})"
+    if [[ -n "$choice" ]]; then
+        silo cd "$choice" >/dev/null
+        # Let prompt hooks (vcs_info, ...) see the new directory
+        local hook
+        for hook in $precmd_functions; do
+            "$hook"
+        done
+    fi
+    zle reset-prompt
+}}
+zle -N __silo_pick
+bindkey {key} __silo_pick
+"##,
+        picker = picker.join(" "),
+        key = quote(widget.key),
+    )
+}
+
+/// Quote a word for zsh, so it's passed on as is.
+fn quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_widget_is_opt_in() {
+        let script = init_script("/bin/silo", None);
+        assert!(!script.contains("bindkey"));
+    }
+
+    #[test]
+    fn test_widget_binds_key_to_picker() {
+        let picker = vec!["fzf".to_string(), "--prompt=silo's> ".to_string()];
+        let script = init_script(
+            "/bin/silo",
+            Some(Widget {
+                key: "^G",
+                picker: &picker,
+            }),
+        );
+        assert!(script.contains("bindkey '^G' __silo_pick\n"));
+        assert!(script.contains("cd '' 2>/dev/null | 'fzf' '--prompt=silo'\\''s> ')"));
+    }
+}
@@ -1,10 +1,19 @@
-use crate::config::Config;
+use crate::external;
 use crate::git;
 use crate::names;
+use crate::pending;
+use crate::pool;
+use crate::process;
+use crate::storage::Layout;
+use crate::timings;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tracing::warn;
 
+/// Storage roots from the config loaded at startup (`set_layout`)
+static LAYOUT: OnceLock<Layout> = OnceLock::new();
+
 /// Information about a silo (isolated git worktree)
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Silo {
@@ -25,75 +34,193 @@ impl Silo {
     pub fn branch_name(&self) -> &str {
         self.branch.as_deref().unwrap_or(&self.name)
     }
+
+    /// Get the canonical ID of this silo (stable regardless of other silos).
+    pub fn id(&self) -> String {
+        names::silo_id(&self.main_worktree, &self.name)
+    }
+
+    /// Whether this is a registered external worktree rather than a silo
+    /// in the storage directory.
+    pub fn is_external(&self) -> bool {
+        !is_silo_path(&self.storage_path)
+    }
+}
+
+/// Use `layout`, resolved from the config loaded at startup, for the
+/// storage roots of all silos. Called once, before any silo is looked up.
+pub fn set_layout(layout: Layout) {
+    let _ = LAYOUT.set(layout);
 }
 
-/// Get the base directory for all silos
-/// Uses ~/.config/silo.toml if present, otherwise defaults to ~/.local/var/silo/
+/// Get the storage layout, as set at startup. Never loads config itself,
+/// so --config-file and --no-config apply everywhere.
+fn layout() -> Result<&'static Layout, String> {
+    LAYOUT
+        .get()
+        .ok_or_else(|| "Storage directory not configured".to_string())
+}
+
+/// Get the primary storage directory, which holds the repository registry
+/// and the external worktree list.
 pub fn get_silo_base_dir() -> Result<PathBuf, String> {
-    let config = Config::load()?;
-    config.get_worktree_dir()
+    Ok(layout()?.primary().to_path_buf())
+}
+
+/// Get every storage directory silos may live in, primary first.
+pub fn get_silo_base_dirs() -> Result<Vec<PathBuf>, String> {
+    Ok(layout()?.roots().to_vec())
 }
 
+/// Get the storage directory new silos of the repository at `repo_path`
+/// are created in.
+pub fn get_repo_base_dir(repo_path: &Path) -> Result<PathBuf, String> {
+    Ok(layout()?.root_for(repo_path).to_path_buf())
+}
+
+/// Get the storage directory containing `path`, if any.
+pub fn get_containing_base_dir(path: &Path) -> Option<PathBuf> {
+    let canon_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    layout().ok()?.roots().iter().find_map(|base| {
+        // Canonicalize both paths to handle symlinks (e.g., /var -> /private/var on macOS)
+        let canon_base = base.canonicalize().unwrap_or_else(|_| base.clone());
+        // A silo that isn't created yet can't be canonicalized
+        (canon_path.starts_with(&canon_base) || path.starts_with(base)).then(|| base.clone())
+    })
+}
+
+/// Split a path in a storage directory into its repo directory and the path
+/// below that: `{root}/repo-1a2b3c4d/feature/x` into `{root}/repo-1a2b3c4d`
+/// and `feature/x`. None outside storage and for a repo directory itself.
+pub fn split_storage_path(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let base = get_containing_base_dir(path)?;
+    let relative = path
+        .strip_prefix(&base)
+        .ok()
+        .map(Path::to_path_buf)
+        .or_else(|| {
+            let canon_path = path.canonicalize().ok()?;
+            canon_path
+                .strip_prefix(base.canonicalize().ok()?)
+                .ok()
+                .map(Path::to_path_buf)
+        })?;
+    let mut components = relative.components();
+    let repo_silo_dir = base.join(components.next()?);
+    let rest = components.as_path();
+    (!rest.as_os_str().is_empty()).then(|| (repo_silo_dir, rest.to_path_buf()))
+}
+
+/// The name of the silo at `path`: its path below the repository's storage
+/// directory, so the silo of `feature/x` is named `feature/x` rather than
+/// `x`. Worktrees outside storage are named by their directory. None if the
+/// name isn't valid UTF-8.
+pub fn silo_name(path: &Path) -> Option<String> {
+    let Some((_, name)) = split_storage_path(path) else {
+        return path.file_name()?.to_str().map(String::from);
+    };
+    let parts = name
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("/"))
+}
+
+/// Directory in the storage directory where `gc` saves the unpushed commits
+/// of orphaned silos it removes.
+pub const BUNDLE_DIR: &str = ".bundles";
+
 /// Get the full path for a specific silo
 /// Format: ~/.local/var/silo/{repo-name}-{hash}/{branch-name}
 pub fn get_silo_path(repo_name: &str, repo_path: &Path, branch: &str) -> Result<PathBuf, String> {
-    let base = get_silo_base_dir()?;
+    let base = get_repo_base_dir(repo_path)?;
     Ok(names::silo_storage_path(
         &base, repo_name, repo_path, branch,
     ))
 }
 
-/// Check if a path is within the silo base directory
+/// Check if a path is within one of the storage directories. Idle pool
+/// worktrees there aren't silos.
 #[must_use]
 pub fn is_silo_path(path: &Path) -> bool {
-    if let Ok(base) = get_silo_base_dir() {
-        // Canonicalize both paths to handle symlinks (e.g., /var -> /private/var on macOS)
-        let canon_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-        let canon_base = base.canonicalize().unwrap_or(base);
-        canon_path.starts_with(&canon_base)
-    } else {
-        false
-    }
+    get_containing_base_dir(path).is_some() && !pool::is_pool_path(path)
 }
 
-/// Collect all silos across all repositories
-pub fn collect_all_silos() -> Result<Vec<Silo>, String> {
-    let base_dir = get_silo_base_dir()?;
-
-    if !base_dir.exists() {
-        return Ok(Vec::new());
+/// The repository directories in every storage directory that exists.
+pub fn repo_dirs() -> Result<Vec<PathBuf>, String> {
+    let mut dirs = Vec::new();
+    for base_dir in layout()?.roots() {
+        if !base_dir.exists() {
+            continue;
+        }
+        let entries = std::fs::read_dir(base_dir)
+            .map_err(|e| format!("Failed to read silo directory: {}", e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+            let repo_silo_dir = entry.path();
+            if repo_silo_dir.is_dir() {
+                dirs.push(repo_silo_dir);
+            }
+        }
     }
+    Ok(dirs)
+}
 
-    let mut silos = Vec::new();
-    let mut seen_paths: HashSet<PathBuf> = HashSet::new();
+/// How many directories deep `find_main_worktree` looks for a silo, i.e.
+/// the most slashes in a branch name it finds silos for.
+pub const MAX_NESTED_SILO_DEPTH: usize = 8;
 
-    let entries = std::fs::read_dir(&base_dir)
-        .map_err(|e| format!("Failed to read silo directory: {}", e))?;
+/// The main worktree of the silos in a repo directory of the storage
+/// directory, from the first silo found. Hidden entries (like .DS_Store or
+/// the pool) are skipped.
+fn find_main_worktree(dir: &Path) -> Option<PathBuf> {
+    find_main_worktree_in(dir, MAX_NESTED_SILO_DEPTH)
+}
 
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-        let repo_silo_dir = entry.path();
+/// Look for a silo in `dir`, then in its subdirectories that hold the silos
+/// of branches like `feature/x`. Those hold nothing but directories, so a
+/// leftover directory full of build output isn't walked.
+fn find_main_worktree_in(dir: &Path, depth: usize) -> Option<PathBuf> {
+    let dirs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    if let Some(main) = dirs
+        .iter()
+        .filter(|path| path.join(".git").exists())
+        .find_map(|path| git::get_main_worktree_from_silo(path))
+    {
+        return Some(main);
+    }
+    if depth == 0 {
+        return None;
+    }
+    dirs.iter()
+        .filter(|path| !path.join(".git").exists() && holds_only_dirs(path))
+        .find_map(|path| find_main_worktree_in(path, depth - 1))
+}
 
-        if !repo_silo_dir.is_dir() {
-            continue;
-        }
+/// Whether `dir` has no entries but directories (hidden ones aside).
+pub fn holds_only_dirs(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .filter_map(|e| e.ok())
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .all(|entry| entry.path().is_dir())
+    })
+}
 
-        // Find the first valid silo to get the main worktree location
-        // Skip hidden files (like .DS_Store) and non-directories
-        let first_silo = std::fs::read_dir(&repo_silo_dir)
-            .ok()
-            .and_then(|entries| {
-                entries.filter_map(|e| e.ok()).find(|entry| {
-                    let name = entry.file_name();
-                    let name_str = name.to_string_lossy();
-                    !name_str.starts_with('.') && entry.path().is_dir()
-                })
-            })
-            .map(|e| e.path());
+/// Collect all silos across all repositories
+pub fn collect_all_silos() -> Result<Vec<Silo>, String> {
+    let _phase = timings::phase("scan storage");
+    let mut silos = Vec::new();
+    let mut seen_paths: HashSet<PathBuf> = HashSet::new();
 
-        let main_worktree_path = first_silo
-            .as_ref()
-            .and_then(|silo| git::get_main_worktree_from_silo(silo));
+    for repo_silo_dir in repo_dirs()? {
+        let main_worktree_path = find_main_worktree(&repo_silo_dir);
 
         let Some(main_path) = main_worktree_path else {
             continue;
@@ -120,7 +247,7 @@ pub fn collect_all_silos() -> Result<Vec<Silo>, String> {
                 }
 
                 // Get silo name, skip if not valid UTF-8
-                let Some(silo_name) = wt.name() else {
+                let Some(silo_name) = silo_name(&wt.path) else {
                     warn!(
                         path = %wt.path.display(),
                         "Skipping silo with non-UTF-8 directory name"
@@ -142,12 +269,48 @@ pub fn collect_all_silos() -> Result<Vec<Silo>, String> {
         }
     }
 
+    for silo in collect_external_silos(&get_silo_base_dir()?) {
+        if seen_paths.insert(silo.storage_path.clone()) {
+            silos.push(silo);
+        }
+    }
+
+    // Directory iteration order is filesystem-dependent; sort so that display
+    // names and output are deterministic.
+    silos.sort_by(|a, b| (&a.main_worktree, &a.name).cmp(&(&b.main_worktree, &b.name)));
+
     Ok(silos)
 }
 
-/// Collect all silos for a specific repository.
+/// Registered external worktrees that still exist, as silos.
+fn collect_external_silos(base_dir: &Path) -> Vec<Silo> {
+    external::load(base_dir)
+        .into_iter()
+        .filter_map(|path| {
+            let main_path = git::get_main_worktree_from_silo(&path)?;
+            let repo_name = main_path.file_name()?.to_str()?.to_string();
+            let wt = git::list_worktrees(&main_path)
+                .ok()?
+                .into_iter()
+                .skip(1)
+                .find(|wt| wt.path == path)?;
+            Some(Silo {
+                name: wt.name()?.to_string(),
+                branch: wt.branch,
+                main_worktree: main_path,
+                storage_path: wt.path,
+                repo_name,
+            })
+        })
+        .collect()
+}
+
+/// Collect all silos for a specific repository, including its registered
+/// external worktrees.
 pub fn collect_silos_for_repo(repo_root: &Path) -> Result<Vec<Silo>, String> {
+    let _phase = timings::phase("scan repo silos");
     let worktrees = git::list_worktrees(repo_root)?;
+    let externals = external::load(&get_silo_base_dir()?);
 
     let repo_name = repo_root
         .file_name()
@@ -158,9 +321,9 @@ pub fn collect_silos_for_repo(repo_root: &Path) -> Result<Vec<Silo>, String> {
     let silos = worktrees
         .into_iter()
         .skip(1) // Skip main worktree
-        .filter(|wt| is_silo_path(&wt.path))
+        .filter(|wt| is_silo_path(&wt.path) || externals.contains(&wt.path))
         .filter_map(|wt| {
-            let name = wt.name().map(|s| s.to_string())?;
+            let name = silo_name(&wt.path)?;
             Some(Silo {
                 name,
                 branch: wt.branch,
@@ -193,7 +356,7 @@ pub fn collect_prunable_repo(repo_root: &Path) -> Result<Vec<Silo>, String> {
         }
 
         // Skip silos with non-UTF-8 names
-        let Some(silo_name) = wt.name() else {
+        let Some(silo_name) = silo_name(&wt.path) else {
             continue;
         };
 
@@ -214,41 +377,10 @@ pub fn collect_prunable_repo(repo_root: &Path) -> Result<Vec<Silo>, String> {
 /// Collect silos that can be pruned (have no uncommitted changes) across all repos.
 /// Returns Silo for each clean silo.
 pub fn collect_prunable_all() -> Result<Vec<Silo>, String> {
-    let base_dir = get_silo_base_dir()?;
-
-    if !base_dir.exists() {
-        return Ok(Vec::new());
-    }
-
     let mut to_prune = Vec::new();
 
-    let entries = std::fs::read_dir(&base_dir)
-        .map_err(|e| format!("Failed to read silo directory: {}", e))?;
-
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-        let repo_silo_dir = entry.path();
-
-        if !repo_silo_dir.is_dir() {
-            continue;
-        }
-
-        // Find the first valid silo to get the main worktree location
-        // Skip hidden files (like .DS_Store) and non-directories
-        let first_silo = std::fs::read_dir(&repo_silo_dir)
-            .ok()
-            .and_then(|entries| {
-                entries.filter_map(|e| e.ok()).find(|entry| {
-                    let name = entry.file_name();
-                    let name_str = name.to_string_lossy();
-                    !name_str.starts_with('.') && entry.path().is_dir()
-                })
-            })
-            .map(|e| e.path());
-
-        let main_worktree_path = first_silo
-            .as_ref()
-            .and_then(|silo| git::get_main_worktree_from_silo(silo));
+    for repo_silo_dir in repo_dirs()? {
+        let main_worktree_path = find_main_worktree(&repo_silo_dir);
 
         let Some(ref main_path) = main_worktree_path else {
             continue;
@@ -274,7 +406,7 @@ pub fn collect_prunable_all() -> Result<Vec<Silo>, String> {
             }
 
             // Skip silos with non-UTF-8 names
-            let Some(silo_name) = wt.name() else {
+            let Some(silo_name) = silo_name(&wt.path) else {
                 continue;
             };
 
@@ -308,25 +440,9 @@ pub struct OrphanedSilo {
 /// These are silos whose main worktree directory no longer exists,
 /// making them impossible to manage via git worktree commands.
 pub fn collect_orphaned_silos() -> Result<Vec<OrphanedSilo>, String> {
-    let base_dir = get_silo_base_dir()?;
-
-    if !base_dir.exists() {
-        return Ok(Vec::new());
-    }
-
     let mut orphaned = Vec::new();
 
-    let entries = std::fs::read_dir(&base_dir)
-        .map_err(|e| format!("Failed to read silo directory: {}", e))?;
-
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-        let repo_silo_dir = entry.path();
-
-        if !repo_silo_dir.is_dir() {
-            continue;
-        }
-
+    for repo_silo_dir in repo_dirs()? {
         // Check each silo in this repo directory
         let Ok(silo_entries) = std::fs::read_dir(&repo_silo_dir) else {
             continue;
@@ -361,41 +477,85 @@ pub fn collect_orphaned_silos() -> Result<Vec<OrphanedSilo>, String> {
     Ok(orphaned)
 }
 
-/// Collect empty repo directories in the silo base directory.
-/// These are directories that once held silos but are now empty
-/// (except possibly for hidden files like .DS_Store).
-pub fn collect_empty_repo_dirs() -> Result<Vec<PathBuf>, String> {
-    let base_dir = get_silo_base_dir()?;
-
-    if !base_dir.exists() {
-        return Ok(Vec::new());
+/// Collect tracking directories whose silo no longer exists.
+/// These hold PID files and sandbox scratch data for silos that were
+/// removed outside of silo (e.g. with `rm -rf`).
+pub fn collect_stale_tracking_dirs() -> Result<Vec<PathBuf>, String> {
+    let roots = get_silo_base_dirs()?;
+    let mut stale = Vec::new();
+
+    // With a relocated tracking directory, repo directories may be gone from
+    // storage while their tracking data remains
+    let repo_silo_dirs: Vec<PathBuf> = match process::tracking_root() {
+        Some(scan_dir) => {
+            if !scan_dir.exists() {
+                return Ok(Vec::new());
+            }
+            let entries = std::fs::read_dir(scan_dir)
+                .map_err(|e| format!("Failed to read tracking directory: {}", e))?;
+            let mut dirs = Vec::new();
+            for entry in entries {
+                let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+                // Tracking data is keyed by repo directory name only, so it
+                // belongs to whichever root has that directory
+                let name = entry.file_name();
+                let root = roots
+                    .iter()
+                    .find(|root| root.join(&name).is_dir())
+                    .unwrap_or(&roots[0]);
+                dirs.push(root.join(name));
+            }
+            dirs
+        }
+        None => repo_dirs()?,
+    };
+
+    for repo_silo_dir in repo_silo_dirs {
+        collect_stale_in(
+            &process::repo_tracking_dir(&repo_silo_dir),
+            &repo_silo_dir,
+            &mut stale,
+        );
     }
 
-    let mut empty_dirs = Vec::new();
+    stale.sort();
+    Ok(stale)
+}
 
-    let entries = std::fs::read_dir(&base_dir)
-        .map_err(|e| format!("Failed to read silo directory: {}", e))?;
+/// Collect the tracking directories in `tracking_dir` with no silo in
+/// `silo_dir`. The silo of `feature/x` keeps its data in `feature/x`, so a
+/// directory that holds silos rather than being one is searched in turn.
+fn collect_stale_in(tracking_dir: &Path, silo_dir: &Path, stale: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(tracking_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let tracking_path = entry.path();
+        if !tracking_path.is_dir() || pending::is_pending_tracking_dir(&tracking_path) {
+            continue;
+        }
+        let silo_path = silo_dir.join(entry.file_name());
+        if !silo_path.exists() {
+            stale.push(tracking_path);
+        } else if !silo_path.join(".git").exists() {
+            collect_stale_in(&tracking_path, &silo_path, stale);
+        }
+    }
+}
 
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-        let repo_silo_dir = entry.path();
+/// Collect empty repo directories in the storage directories.
+/// These are directories that once held silos but are now empty
+/// (except possibly for hidden files like .DS_Store).
+pub fn collect_empty_repo_dirs() -> Result<Vec<PathBuf>, String> {
+    let mut empty_dirs = Vec::new();
 
-        if !repo_silo_dir.is_dir() {
+    for repo_silo_dir in repo_dirs()? {
+        // Bundles saved by gc live alongside the repo directories
+        if repo_silo_dir.file_name() == Some(BUNDLE_DIR.as_ref()) {
             continue;
         }
 
-        // Check if directory has any non-hidden entries
-        let has_visible_entries = std::fs::read_dir(&repo_silo_dir)
-            .map(|entries| {
-                entries.filter_map(|e| e.ok()).any(|entry| {
-                    let name = entry.file_name();
-                    let name_str = name.to_string_lossy();
-                    !name_str.starts_with('.')
-                })
-            })
-            .unwrap_or(false);
-
-        if !has_visible_entries {
+        if is_empty_repo_dir(&repo_silo_dir) {
             empty_dirs.push(repo_silo_dir);
         }
     }
@@ -403,6 +563,23 @@ pub fn collect_empty_repo_dirs() -> Result<Vec<PathBuf>, String> {
     Ok(empty_dirs)
 }
 
+/// Whether a repo directory of the storage directory holds no silos (only
+/// hidden entries), no pool and no silo being created.
+pub fn is_empty_repo_dir(repo_silo_dir: &Path) -> bool {
+    let has_visible_entries = std::fs::read_dir(repo_silo_dir)
+        .map(|entries| {
+            entries.filter_map(|e| e.ok()).any(|entry| {
+                let name = entry.file_name();
+                let name_str = name.to_string_lossy();
+                !name_str.starts_with('.')
+            })
+        })
+        .unwrap_or(false);
+    !has_visible_entries
+        && !pending::any_pending(repo_silo_dir)
+        && !pool::has_worktrees(repo_silo_dir)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -417,6 +594,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_find_main_worktree_in_nested_silos_only() {
+        let repo_dir = tempfile::tempdir().unwrap();
+        let fake_silo = |path: &Path, main: &str| {
+            std::fs::create_dir_all(path).unwrap();
+            std::fs::write(
+                path.join(".git"),
+                format!("gitdir: {}/.git/worktrees/x\n", main),
+            )
+            .unwrap();
+        };
+
+        // A leftover directory with files isn't searched
+        let leftover = repo_dir.path().join("leftover");
+        fake_silo(&leftover.join("node_modules/pkg"), "/src/other");
+        std::fs::write(leftover.join("build.log"), "").unwrap();
+        assert_eq!(find_main_worktree(repo_dir.path()), None);
+
+        fake_silo(&repo_dir.path().join("feature/x"), "/src/app");
+        assert_eq!(
+            find_main_worktree(repo_dir.path()),
+            Some(PathBuf::from("/src/app"))
+        );
+    }
+
     #[test]
     fn test_silo_branch_name_with_branch() {
         let silo = make_silo("feature", Some("feature-branch"));
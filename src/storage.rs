@@ -0,0 +1,526 @@
+//! Storage directory management: initialization, ownership and locking.
+//!
+//! Several users may point `worktree_dir` at the same directory on a team
+//! server. This module lets commands:
+//! - Create the storage directory with restrictive permissions
+//! - Probe the storage filesystem for features silo relies on, and for
+//!   properties that change how worktrees behave on it (case-insensitive
+//!   volumes, network mounts)
+//! - Determine which user owns a silo (from the directory's owner)
+//! - Serialize create/remove operations with an advisory lock on the
+//!   storage directory, so concurrent runs don't interleave
+//! - Spread silos over several storage roots (`worktree_dirs`), routing
+//!   repositories to roots with `storage_routes`
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use crate::names;
+
+/// The storage roots silos live in and which repositories go where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// Never empty. The first root is the primary one: it holds the
+    /// repository registry and the external worktree list, and gets the
+    /// silos of repositories no route matches.
+    roots: Vec<PathBuf>,
+    /// `(repo filter, root)` pairs, most specific filter first
+    routes: Vec<(String, PathBuf)>,
+}
+
+impl Layout {
+    /// A layout with several roots, the first being the primary one.
+    /// Routes match repositories like `list --repo`; when several match,
+    /// the one naming the most path components wins.
+    pub fn new(roots: Vec<PathBuf>, mut routes: Vec<(String, PathBuf)>) -> Self {
+        assert!(!roots.is_empty(), "a storage layout needs a root");
+        let components = |filter: &str| filter.split('/').filter(|p| !p.is_empty()).count();
+        routes.sort_by(|(a, _), (b, _)| components(b).cmp(&components(a)).then(a.cmp(b)));
+        Self { roots, routes }
+    }
+
+    /// Add roots that hold existing silos but get no new ones unless asked,
+    /// such as one-off `new --worktree-dir` roots. Known roots are skipped.
+    pub fn with_extra_roots(mut self, extra: impl IntoIterator<Item = PathBuf>) -> Self {
+        for root in extra {
+            if !self.roots.contains(&root) {
+                self.roots.push(root);
+            }
+        }
+        self
+    }
+
+    /// The primary root.
+    pub fn primary(&self) -> &Path {
+        &self.roots[0]
+    }
+
+    /// All roots, primary first.
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// The root new silos of the repository at `repo_path` go to.
+    pub fn root_for(&self, repo_path: &Path) -> &Path {
+        self.routes
+            .iter()
+            .find(|(filter, _)| names::repo_matches(repo_path, filter))
+            .map_or(self.primary(), |(_, root)| root)
+    }
+}
+
+/// Name of the lock file inside the storage base directory.
+const LOCK_FILE_NAME: &str = ".silo.lock";
+
+/// Create the storage base directory with the given permissions.
+/// An existing directory is left untouched, so users can loosen
+/// permissions deliberately (e.g. for shared storage).
+pub fn ensure_base_dir(base_dir: &Path, mode: u32) -> Result<(), String> {
+    if base_dir.exists() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(base_dir).map_err(|e| format!("Failed to create silo directory: {}", e))?;
+    set_mode(base_dir, mode)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| format!("Failed to set permissions on {}: {}", path.display(), e))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<(), String> {
+    Ok(())
+}
+
+/// Get the permission bits of a path, or None if it can't be read.
+#[cfg(unix)]
+pub fn mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .ok()
+        .map(|m| m.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+pub fn mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Check whether files can be created in a directory.
+pub fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".silo-probe-{}", std::process::id()));
+    let ok = fs::write(&probe, b"").is_ok();
+    let _ = fs::remove_file(&probe);
+    ok
+}
+
+/// Check whether a directory's filesystem supports symbolic links.
+#[cfg(unix)]
+pub fn supports_symlinks(dir: &Path) -> bool {
+    let link = dir.join(format!(".silo-symlink-probe-{}", std::process::id()));
+    let ok = std::os::unix::fs::symlink(".", &link).is_ok();
+    let _ = fs::remove_file(&link);
+    ok
+}
+
+#[cfg(not(unix))]
+pub fn supports_symlinks(_dir: &Path) -> bool {
+    false
+}
+
+/// Properties of a storage filesystem that affect how silos behave on it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FsProperties {
+    /// File names differing only in case refer to the same file
+    pub case_insensitive: bool,
+    /// The filesystem is a network mount (NFS, SMB, ...)
+    pub network: bool,
+}
+
+/// Probe the filesystem holding a directory. The directory must exist and
+/// be writable for the case-sensitivity probe to succeed.
+pub fn probe_filesystem(dir: &Path) -> FsProperties {
+    FsProperties {
+        case_insensitive: is_case_insensitive(dir),
+        network: is_network_filesystem(dir),
+    }
+}
+
+/// Check whether a directory's filesystem ignores case in file names.
+pub fn is_case_insensitive(dir: &Path) -> bool {
+    let probe = dir.join(format!(".silo-case-probe-{}", std::process::id()));
+    if fs::write(&probe, b"").is_err() {
+        return false;
+    }
+    let upper = dir.join(format!(".SILO-CASE-PROBE-{}", std::process::id()));
+    let insensitive = upper.exists();
+    let _ = fs::remove_file(&probe);
+    insensitive
+}
+
+/// Check whether a directory is on a network filesystem.
+#[cfg(target_os = "linux")]
+// f_type is i64 or u32 depending on the target, so the cast isn't always a no-op
+#[allow(clippy::unnecessary_cast)]
+pub fn is_network_filesystem(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    const NETWORK_MAGICS: &[i64] = &[
+        0x6969,      // NFS
+        0x517B,      // SMB
+        0xFF53_4D42, // CIFS
+        0xFE53_4D42, // SMB2
+        0x00C3_6400, // Ceph
+        0x5346_414F, // AFS
+        0x0102_1997, // 9p (VM and WSL shares)
+    ];
+
+    let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: path is a valid C string and buf is a properly sized statfs struct
+    let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut buf) } != 0 {
+        return false;
+    }
+    NETWORK_MAGICS.contains(&(buf.f_type as i64))
+}
+
+/// Check whether a directory is on a network filesystem.
+#[cfg(target_os = "macos")]
+pub fn is_network_filesystem(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: path is a valid C string and buf is a properly sized statfs struct
+    let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut buf) } != 0 {
+        return false;
+    }
+    // SAFETY: f_fstypename is a NUL-terminated fixed-size buffer
+    let fs_type = unsafe { std::ffi::CStr::from_ptr(buf.f_fstypename.as_ptr()) };
+    matches!(fs_type.to_bytes(), b"nfs" | b"smbfs" | b"afpfs" | b"webdav")
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn is_network_filesystem(_dir: &Path) -> bool {
+    false
+}
+
+/// Check whether two existing paths are on the same filesystem.
+/// Returns None if either path can't be read.
+#[cfg(unix)]
+pub fn same_filesystem(a: &Path, b: &Path) -> Option<bool> {
+    use std::os::unix::fs::MetadataExt;
+    Some(fs::metadata(a).ok()?.dev() == fs::metadata(b).ok()?.dev())
+}
+
+#[cfg(not(unix))]
+pub fn same_filesystem(_a: &Path, _b: &Path) -> Option<bool> {
+    None
+}
+
+/// Find groups of paths that differ only in case, and would therefore
+/// overwrite each other on a case-insensitive filesystem.
+/// Groups and the paths within them are sorted.
+pub fn case_collisions<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<Vec<&'a str>> {
+    let mut by_folded: HashMap<String, Vec<&str>> = HashMap::new();
+    for path in paths {
+        by_folded.entry(path.to_lowercase()).or_default().push(path);
+    }
+
+    let mut groups: Vec<Vec<&str>> = by_folded
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_unstable();
+            group
+        })
+        .collect();
+    groups.sort();
+    groups
+}
+
+/// An exclusive advisory lock on the storage directory.
+/// The lock is released when this value is dropped.
+pub struct StorageLock {
+    _lock: FileLock,
+}
+
+impl StorageLock {
+    /// Acquire the storage lock, blocking until it is available.
+    /// Creates the storage directory if it doesn't exist yet.
+    pub fn acquire(base_dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(base_dir)
+            .map_err(|e| format!("Failed to create silo directory: {}", e))?;
+
+        let lock = FileLock::acquire(&base_dir.join(LOCK_FILE_NAME))
+            .map_err(|e| format!("Failed to lock storage: {}", e))?;
+        Ok(Self { _lock: lock })
+    }
+}
+
+/// An exclusive advisory lock held on a lock file, created if missing.
+/// The lock is released when this value is dropped.
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Acquire the lock, blocking until it is available. The parent
+    /// directory must exist.
+    pub fn acquire(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+
+        lock_exclusive(&file)?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        unlock(&self.file);
+    }
+}
+
+#[cfg(unix)]
+fn lock_exclusive(file: &File) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    // SAFETY: the file descriptor is valid for the lifetime of `file`
+    let result = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn unlock(file: &File) {
+    use std::os::unix::io::AsRawFd;
+    // SAFETY: the file descriptor is valid for the lifetime of `file`
+    unsafe {
+        libc::flock(file.as_raw_fd(), libc::LOCK_UN);
+    }
+}
+
+#[cfg(not(unix))]
+fn lock_exclusive(_file: &File) -> std::io::Result<()> {
+    // Advisory locking is only supported on Unix
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn unlock(_file: &File) {}
+
+/// Get the uid of the user running silo.
+#[cfg(unix)]
+pub fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
+
+/// Get the uid that owns a path, or None if it can't be read.
+#[cfg(unix)]
+pub fn owner_uid(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| m.uid())
+}
+
+/// Check whether a path is owned by the current user.
+/// Paths whose owner can't be determined are treated as owned.
+#[cfg(unix)]
+pub fn is_owned_by_current_user(path: &Path) -> bool {
+    owner_uid(path).is_none_or(|uid| uid == current_uid())
+}
+
+#[cfg(not(unix))]
+pub fn is_owned_by_current_user(_path: &Path) -> bool {
+    true
+}
+
+/// Get the login name of the user owning a path.
+/// Falls back to the numeric uid if the user has no passwd entry.
+#[cfg(unix)]
+pub fn owner_name(path: &Path) -> Option<String> {
+    owner_uid(path).map(|uid| user_name(uid).unwrap_or_else(|| uid.to_string()))
+}
+
+#[cfg(not(unix))]
+pub fn owner_name(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Get the login name of the user running silo.
+#[cfg(unix)]
+pub fn current_user_name() -> Option<String> {
+    let uid = current_uid();
+    Some(user_name(uid).unwrap_or_else(|| uid.to_string()))
+}
+
+#[cfg(not(unix))]
+pub fn current_user_name() -> Option<String> {
+    std::env::var("USERNAME").ok()
+}
+
+/// Look up a user's login name by uid.
+#[cfg(unix)]
+fn user_name(uid: u32) -> Option<String> {
+    // SAFETY: getpwuid returns a pointer into static storage or null. We copy
+    // the name out immediately; silo is single-threaded here.
+    unsafe {
+        let pw = libc::getpwuid(uid);
+        if pw.is_null() || (*pw).pw_name.is_null() {
+            return None;
+        }
+        std::ffi::CStr::from_ptr((*pw).pw_name)
+            .to_str()
+            .ok()
+            .map(str::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_layout_routes_most_specific_match() {
+        let layout = Layout::new(
+            vec![PathBuf::from("/fast"), PathBuf::from("/big")],
+            vec![
+                ("repo".to_string(), PathBuf::from("/big")),
+                ("org/repo".to_string(), PathBuf::from("/fast")),
+                ("mono".to_string(), PathBuf::from("/big")),
+            ],
+        );
+        assert_eq!(layout.primary(), Path::new("/fast"));
+        assert_eq!(
+            layout.root_for(Path::new("/src/org/repo")),
+            Path::new("/fast")
+        );
+        assert_eq!(
+            layout.root_for(Path::new("/src/other/repo")),
+            Path::new("/big")
+        );
+        assert_eq!(layout.root_for(Path::new("/src/mono")), Path::new("/big"));
+        assert_eq!(
+            layout.root_for(Path::new("/src/unrouted")),
+            Path::new("/fast")
+        );
+    }
+
+    #[test]
+    fn test_extra_roots_get_no_new_silos() {
+        let layout = Layout::new(vec![PathBuf::from("/fast")], Vec::new())
+            .with_extra_roots([PathBuf::from("/scratch"), PathBuf::from("/fast")]);
+        assert_eq!(
+            layout.roots(),
+            [PathBuf::from("/fast"), PathBuf::from("/scratch")]
+        );
+        assert_eq!(layout.root_for(Path::new("/src/repo")), Path::new("/fast"));
+    }
+
+    #[test]
+    fn test_ensure_base_dir_sets_mode() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().join("silo");
+        ensure_base_dir(&base, 0o700).unwrap();
+        assert_eq!(mode(&base), Some(0o700));
+    }
+
+    #[test]
+    fn test_ensure_base_dir_keeps_existing_mode() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().join("silo");
+        fs::create_dir_all(&base).unwrap();
+        let before = mode(&base);
+        ensure_base_dir(&base, 0o700).unwrap();
+        assert_eq!(mode(&base), before);
+    }
+
+    #[test]
+    fn test_probes_on_temp_dir() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().join("silo");
+        fs::create_dir_all(&base).unwrap();
+        assert!(is_writable(&base));
+        assert!(supports_symlinks(&base));
+        // Linux temp dirs are case-sensitive local filesystems
+        #[cfg(target_os = "linux")]
+        assert_eq!(probe_filesystem(&base), FsProperties::default());
+        // Probes clean up after themselves
+        assert_eq!(fs::read_dir(&base).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_case_collisions() {
+        let paths = [
+            "README.md",
+            "src/Foo.rs",
+            "readme.md",
+            "src/foo.rs",
+            "src/bar.rs",
+        ];
+        assert_eq!(
+            case_collisions(paths),
+            vec![
+                vec!["README.md", "readme.md"],
+                vec!["src/Foo.rs", "src/foo.rs"]
+            ]
+        );
+        assert!(case_collisions(["a", "b"]).is_empty());
+    }
+
+    #[test]
+    fn test_same_filesystem_with_itself() {
+        let dir = std::env::temp_dir();
+        assert_eq!(same_filesystem(&dir, &dir), Some(true));
+        assert_eq!(same_filesystem(&dir, Path::new("/nonexistent/silo")), None);
+    }
+
+    #[test]
+    fn test_lock_creates_directory_and_lock_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().join("silo");
+        {
+            let _lock = StorageLock::acquire(&base).unwrap();
+            assert!(base.join(LOCK_FILE_NAME).exists());
+        }
+    }
+
+    #[test]
+    fn test_lock_can_be_reacquired_after_drop() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().join("silo");
+        drop(StorageLock::acquire(&base).unwrap());
+        drop(StorageLock::acquire(&base).unwrap());
+    }
+
+    #[test]
+    fn test_temp_dir_is_owned_by_current_user() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().join("silo");
+        fs::create_dir_all(&base).unwrap();
+        assert!(is_owned_by_current_user(&base));
+        assert!(owner_name(&base).is_some());
+    }
+
+    #[test]
+    fn test_missing_path_counts_as_owned() {
+        assert!(is_owned_by_current_user(Path::new(
+            "/nonexistent/silo/storage/path"
+        )));
+    }
+}
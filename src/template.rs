@@ -0,0 +1,71 @@
+//! `{name}` placeholders in user-supplied text, as in extra_command_args and
+//! `list --format` templates.
+
+/// Replace each `{name}` in `text` with its value in `values`. Braces that
+/// don't enclose a placeholder-like name (e.g. `{}`) are kept as they are.
+/// `what` names the text in the error for an unknown placeholder.
+pub fn expand(text: &str, values: &[(&str, String)], what: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+
+        if name_len == 0 || !after[name_len..].starts_with('}') {
+            result.push('{');
+            rest = after;
+            continue;
+        }
+
+        let name = &after[..name_len];
+        let value = values
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v)
+            .ok_or_else(|| {
+                let known: Vec<&str> = values.iter().map(|(n, _)| *n).collect();
+                format!(
+                    "Unknown placeholder in {}: {{{}}} (expected one of: {})",
+                    what,
+                    name,
+                    known.join(", ")
+                )
+            })?;
+        result.push_str(value);
+        rest = &after[name_len + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Vec<(&'static str, String)> {
+        vec![("name", "feature".to_string()), ("ahead", "3".to_string())]
+    }
+
+    #[test]
+    fn test_expand_replaces_each_placeholder() {
+        assert_eq!(
+            expand("{name}: +{ahead} ({name})", &values(), "test").unwrap(),
+            "feature: +3 (feature)"
+        );
+        assert_eq!(expand("plain", &values(), "test").unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_expand_names_text_in_error() {
+        let err = expand("{behind}", &values(), "--format").unwrap_err();
+        assert_eq!(
+            err,
+            "Unknown placeholder in --format: {behind} (expected one of: name, ahead)"
+        );
+    }
+}
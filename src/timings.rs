@@ -0,0 +1,118 @@
+//! Per-phase timing diagnostics for the hidden `--timings` flag.
+//!
+//! Commands mark phases with `let _phase = timings::phase("name");`, which
+//! records the time until the guard is dropped. Every git subprocess is
+//! also counted, so time spent in the git layer shows up on its own.
+//! Nothing is recorded unless `enable` was called.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PHASES: Mutex<Vec<(&str, Duration)>> = Mutex::new(Vec::new());
+static GIT: Mutex<(u32, Duration)> = Mutex::new((0, Duration::ZERO));
+
+/// Start recording timings for this process.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A phase being timed; its duration is recorded when dropped.
+pub struct Phase {
+    name: &'static str,
+    start: Option<Instant>,
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        if let Some(start) = self.start
+            && let Ok(mut phases) = PHASES.lock()
+        {
+            phases.push((self.name, start.elapsed()));
+        }
+    }
+}
+
+/// Start timing a phase. Phases are reported in the order they finish.
+pub fn phase(name: &'static str) -> Phase {
+    Phase {
+        name,
+        start: is_enabled().then(Instant::now),
+    }
+}
+
+/// Record the duration of one git subprocess.
+pub fn record_git(elapsed: Duration) {
+    if is_enabled()
+        && let Ok(mut git) = GIT.lock()
+    {
+        git.0 += 1;
+        git.1 += elapsed;
+    }
+}
+
+/// Print the recorded timings to stderr, if enabled.
+pub fn report(total: Duration) {
+    if !is_enabled() {
+        return;
+    }
+    let phases = PHASES.lock().map(|p| p.clone()).unwrap_or_default();
+    let (git_calls, git_time) = GIT.lock().map(|g| *g).unwrap_or_default();
+    eprint!("{}", format_report(&phases, git_calls, git_time, total));
+}
+
+fn format_report(
+    phases: &[(&str, Duration)],
+    git_calls: u32,
+    git_time: Duration,
+    total: Duration,
+) -> String {
+    let git_label = format!("git ({} calls)", git_calls);
+    let rows: Vec<(&str, Duration)> = phases
+        .iter()
+        .copied()
+        .chain([(git_label.as_str(), git_time), ("total", total)])
+        .collect();
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    let mut out = String::from("timings:\n");
+    for (name, duration) in rows {
+        out.push_str(&format!(
+            "  {:<width$}  {:>9.1}ms\n",
+            name,
+            duration.as_secs_f64() * 1000.0,
+            width = width
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_report() {
+        let report = format_report(
+            &[("config", Duration::from_micros(1500))],
+            3,
+            Duration::from_millis(12),
+            Duration::from_millis(20),
+        );
+        assert_eq!(
+            report,
+            "timings:\n  config               1.5ms\n  git (3 calls)       12.0ms\n  total               20.0ms\n"
+        );
+    }
+
+    #[test]
+    fn test_phase_without_enable_records_nothing() {
+        let phase = phase("ignored");
+        assert!(phase.start.is_none());
+    }
+}
@@ -0,0 +1,124 @@
+//! Agent session transcripts recorded during sandbox runs.
+//!
+//! Claude Code writes each session as a JSONL file under
+//! `~/.claude/projects/{project}/{session}.jsonl`. The sandbox mounts the
+//! silo's transcript directory at that location, so transcripts survive the
+//! container and can be reviewed after the branch's changes land.
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::process;
+
+/// Get the directory holding a silo's recorded transcripts.
+pub fn transcripts_dir(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("transcripts")
+}
+
+/// List a silo's transcript files, oldest first.
+pub fn list(silo_path: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, out: &mut Vec<(SystemTime, PathBuf)>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, out);
+            } else if path.extension().is_some_and(|ext| ext == "jsonl") {
+                let modified = entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                out.push((modified, path));
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(&transcripts_dir(silo_path), &mut files);
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Render a JSONL transcript as readable text.
+/// Lines that aren't user or assistant messages are skipped.
+pub fn render(contents: &str) -> String {
+    let mut out = String::new();
+    for line in contents.lines() {
+        let Ok(entry) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let Some(role) = entry["type"].as_str() else {
+            continue;
+        };
+        if role != "user" && role != "assistant" {
+            continue;
+        }
+        for text in message_parts(&entry["message"]["content"]) {
+            out.push_str(&format!("{}: {}\n", role, text));
+        }
+    }
+    out
+}
+
+/// Extract the displayable parts of a message's content.
+/// Content is either a plain string or a list of typed blocks.
+fn message_parts(content: &Value) -> Vec<String> {
+    if let Some(text) = content.as_str() {
+        return vec![text.to_string()];
+    }
+
+    let Some(blocks) = content.as_array() else {
+        return Vec::new();
+    };
+    blocks
+        .iter()
+        .filter_map(|block| match block["type"].as_str()? {
+            "text" => block["text"].as_str().map(str::to_string),
+            "tool_use" => Some(format!(
+                "[tool: {}]",
+                block["name"].as_str().unwrap_or("unknown")
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcripts_dir_under_tracking() {
+        let dir = transcripts_dir(Path::new("/var/silo/repo-abc/feature"));
+        assert_eq!(
+            dir,
+            PathBuf::from("/var/silo/repo-abc/.tracking/feature/transcripts")
+        );
+    }
+
+    #[test]
+    fn test_render_messages_and_tools() {
+        let contents = [
+            r#"{"type":"summary","summary":"ignored"}"#,
+            r#"{"type":"user","message":{"role":"user","content":"fix the bug"}}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Looking at it"},{"type":"tool_use","name":"Bash","input":{}}]}}"#,
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","content":"ok"}]}}"#,
+            "not json",
+        ]
+        .join("\n");
+
+        assert_eq!(
+            render(&contents),
+            "user: fix the bug\nassistant: Looking at it\nassistant: [tool: Bash]\n"
+        );
+    }
+
+    #[test]
+    fn test_list_missing_dir_is_empty() {
+        assert!(list(Path::new("/nonexistent/repo-abc/feature")).is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+//! Verification runs: the configured `verify_command` run inside a silo.
+//!
+//! The outcome of the most recent run is stored in the silo's tracking
+//! directory so `list` and `agent compare` can show whether a silo's
+//! changes pass without re-running the tests.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::metadata::{self, Record};
+use crate::process;
+use crate::runner::{Completed, Executor};
+
+/// Outcome of a verification run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyResult {
+    pub passed: bool,
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+}
+
+/// Get the path of the file recording a silo's last verification result.
+pub fn result_path(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("verify")
+}
+
+/// Get the path of the log of a silo's last verification run whose output
+/// was captured rather than shown.
+pub fn log_path(silo_path: &Path) -> PathBuf {
+    process::tracking_dir(silo_path).join("verify.log")
+}
+
+/// Run the verify command in a silo, inheriting stdin/stdout/stderr.
+/// The process is tracked while running.
+pub fn run(command: &[String], silo_path: &Path) -> Result<VerifyResult, String> {
+    let completed = executor(command, silo_path).run()?;
+    Ok(VerifyResult::from(completed))
+}
+
+/// Run the verify command in a silo like [`run`], but write its output to
+/// `log_path` (if given) instead of the terminal, so several silos can be
+/// verified at once.
+pub fn run_captured(
+    command: &[String],
+    silo_path: &Path,
+    log_path: Option<&Path>,
+) -> Result<VerifyResult, String> {
+    let captured = executor(command, silo_path).capture(log_path, 0)?;
+    Ok(VerifyResult::from(captured.completed))
+}
+
+fn executor(command: &[String], silo_path: &Path) -> Executor {
+    Executor::new(command)
+        .label("verify command")
+        .current_dir(silo_path)
+        .track(silo_path)
+        .silo_env(silo_path)
+}
+
+impl From<Completed> for VerifyResult {
+    fn from(completed: Completed) -> Self {
+        VerifyResult {
+            passed: completed.status.success(),
+            exit_code: completed.status.code(),
+            duration: completed.duration,
+        }
+    }
+}
+
+/// Record a verification result for a silo, replacing any previous one.
+pub fn record(silo_path: &Path, result: &VerifyResult) -> Result<(), String> {
+    let mut record = Record::new();
+    record
+        .set("passed", result.passed)
+        .set("duration_ms", result.duration.as_millis());
+    if let Some(code) = result.exit_code {
+        record.set("exit_code", code);
+    }
+    metadata::store(&result_path(silo_path), &record)
+        .map_err(|e| format!("Failed to write verify result: {}", e))
+}
+
+/// Load the last recorded verification result for a silo, if any.
+pub fn load(silo_path: &Path) -> Option<VerifyResult> {
+    parse(&metadata::load(&result_path(silo_path))?)
+}
+
+/// Read a verification result from its record.
+fn parse(record: &Record) -> Option<VerifyResult> {
+    Some(VerifyResult {
+        passed: record.get_parsed("passed")?,
+        exit_code: record.get_parsed("exit_code"),
+        duration: Duration::from_millis(record.get_parsed("duration_ms").unwrap_or(0)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_result_path() {
+        assert_eq!(
+            result_path(Path::new("/var/silo/repo-abc/feature")),
+            PathBuf::from("/var/silo/repo-abc/.tracking/feature/verify")
+        );
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let silo = dir.path().join("feature");
+        let result = VerifyResult {
+            passed: false,
+            exit_code: Some(101),
+            duration: Duration::from_millis(1500),
+        };
+
+        record(&silo, &result).unwrap();
+        assert_eq!(load(&silo), Some(result));
+    }
+
+    #[test]
+    fn test_load_missing_or_garbage() {
+        assert_eq!(load(Path::new("/nonexistent/repo-abc/feature")), None);
+        assert_eq!(parse(&Record::parse("passed=maybe\n").unwrap()), None);
+    }
+
+    #[test]
+    fn test_run_reports_exit_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let silo = dir.path().join("feature");
+        fs::create_dir_all(&silo).unwrap();
+
+        let ok = run(&["true".to_string()], &silo).unwrap();
+        assert!(ok.passed);
+
+        let failed = run(&["false".to_string()], &silo).unwrap();
+        assert!(!failed.passed);
+        assert_eq!(failed.exit_code, Some(1));
+
+        let log = dir.path().join("verify.log");
+        let command = ["sh", "-c", "echo checked; exit 3"].map(String::from);
+        let captured = run_captured(&command, &silo, Some(&log)).unwrap();
+        assert_eq!(captured.exit_code, Some(3));
+        assert_eq!(fs::read_to_string(&log).unwrap(), "checked\n");
+    }
+}
@@ -7,6 +7,7 @@ mod common;
 
 use common::TestEnv;
 use std::fs;
+use std::path::Path;
 use std::process::Command;
 
 // =============================================================================
@@ -23,6 +24,38 @@ fn test_new_creates_silo() {
     env.assert_silo_exists("test-branch");
 }
 
+#[test]
+fn test_new_starts_from_remote_branch() {
+    let env = TestEnv::builder().with_remote().build();
+    env.git(&["push", "origin", "HEAD:refs/heads/remote-only"]);
+    env.create_commit_on_remote("remote-only", "remote.txt", "remote", "On remote");
+    env.git(&["fetch", "origin"]);
+
+    // Offered for completion, unlike branches that exist locally
+    let output = env.run_silo(&["complete", "--for", "new", "--format", "json"]);
+    let json: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    let values: Vec<&str> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["value"].as_str().unwrap())
+        .collect();
+    assert_eq!(values, vec!["remote-only"]);
+    assert_eq!(json[0]["description"], "origin/remote-only");
+
+    let output = env.run_silo(&["new", "remote-only"]);
+
+    TestEnv::assert_success(&output);
+    assert!(
+        TestEnv::stdout(&output).contains("from origin/remote-only"),
+        "got: {}",
+        TestEnv::stdout(&output)
+    );
+    assert!(env.silo_path("remote-only").join("remote.txt").exists());
+    let upstream = env.git_in_silo("remote-only", &["rev-parse", "--abbrev-ref", "@{u}"]);
+    assert_eq!(TestEnv::stdout(&upstream).trim(), "origin/remote-only");
+}
+
 #[test]
 fn test_new_fails_for_existing_silo() {
     let env = TestEnv::new();
@@ -33,681 +66,4924 @@ fn test_new_fails_for_existing_silo() {
     TestEnv::assert_failure(&output);
 }
 
-// =============================================================================
-// LIST COMMAND TESTS
-// =============================================================================
-
 #[test]
-fn test_list_shows_silos() {
-    let env = TestEnv::new();
-    env.create_silos(&["branch1", "branch2"]);
+fn test_new_applies_git_config_options() {
+    use std::os::unix::fs::PermissionsExt;
 
-    let output = env.run_silo(&["list"]);
+    let env = TestEnv::new();
+    // `git worktree add` runs post-checkout, so a hooksPath option shows up
+    let hooks = env.silo_dir.path().join(".hooks");
+    let marker = env.silo_dir.path().join("hook-ran");
+    fs::create_dir_all(&hooks).unwrap();
+    let hook = hooks.join("post-checkout");
+    fs::write(&hook, format!("#!/bin/sh\ntouch '{}'\n", marker.display())).unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    env.append_config(&format!(
+        r#"git_config = ["core.hooksPath={}"]"#,
+        hooks.display()
+    ));
+
+    let output = env.run_silo(&["new", "configured"]);
 
     TestEnv::assert_success(&output);
-    let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("branch1"), "Output should contain branch1");
-    assert!(stdout.contains("branch2"), "Output should contain branch2");
+    assert!(marker.exists());
 }
 
 #[test]
-fn test_list_empty_repo() {
+fn test_new_rejects_invalid_git_config_option() {
     let env = TestEnv::new();
+    env.append_config(r#"git_config = ["nosection=1"]"#);
 
-    let output = env.run_silo(&["list"]);
+    let output = env.run_silo(&["new", "configured"]);
 
-    TestEnv::assert_success(&output);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("Invalid git_config option"));
+    env.assert_silo_not_exists("configured");
 }
 
 #[test]
-fn test_list_all_across_repos() {
+fn test_new_ignores_inherited_index_file() {
     let env = TestEnv::new();
-    env.create_silo("test-silo");
+    let stray_index = env.silo_dir.path().join("stray-index");
+    let stray_index = stray_index.to_str().unwrap();
 
-    let output = env.run_silo(&["list", "--all"]);
+    let output = env.run_silo_with_env(&["new", "hooked"], &[("GIT_INDEX_FILE", stray_index)]);
 
     TestEnv::assert_success(&output);
-    let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("test-silo"));
+    assert!(!Path::new(stray_index).exists());
+    let status = env.git_in_silo("hooked", &["status", "--porcelain"]);
+    assert!(TestEnv::stdout(&status).trim().is_empty());
 }
 
-// =============================================================================
-// RM COMMAND TESTS
-// =============================================================================
-
 #[test]
-fn test_rm_removes_silo() {
+fn test_new_take_changes_moves_uncommitted_work() {
     let env = TestEnv::new();
-    env.create_silo("to-remove");
-    env.assert_silo_exists("to-remove");
+    let repo = env.repo_dir.path();
+    fs::write(repo.join("README.md"), "# Changed\n").unwrap();
+    fs::write(repo.join("staged.txt"), "staged").unwrap();
+    env.git(&["add", "staged.txt"]);
+    fs::write(repo.join("untracked.txt"), "untracked").unwrap();
 
-    let output = env.run_silo(&["rm", "to-remove", "--force"]);
+    let output = env.run_silo(&["new", "rescue", "--take-changes"]);
 
     TestEnv::assert_success(&output);
-    env.assert_silo_not_exists("to-remove");
+    let silo = env.silo_path("rescue");
+    assert_eq!(
+        fs::read_to_string(silo.join("README.md")).unwrap(),
+        "# Changed\n"
+    );
+    assert!(silo.join("untracked.txt").exists());
+    let status = TestEnv::stdout(&env.git_in_silo("rescue", &["status", "--porcelain"]));
+    assert!(status.contains("A  staged.txt"), "got: {}", status);
+
+    // Only the main worktree's own silo config stays behind
+    let main_status = TestEnv::stdout(&env.git(&["status", "--porcelain"]));
+    assert_eq!(main_status.trim(), "?? .silo.toml");
+    assert!(!silo.join(".silo.toml").exists());
+    let stashes = TestEnv::stdout(&env.git(&["stash", "list"]));
+    assert!(stashes.trim().is_empty(), "got: {}", stashes);
 }
 
 #[test]
-fn test_rm_nonexistent_fails() {
+fn test_new_take_changes_requires_changes() {
     let env = TestEnv::new();
 
-    let output = env.run_silo(&["rm", "nonexistent", "--force"]);
+    let output = env.run_silo(&["new", "rescue", "--take-changes"]);
 
     TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("No uncommitted changes to take"));
+    env.assert_silo_not_exists("rescue");
 }
 
 #[test]
-fn test_rm_with_uncommitted_changes_requires_force() {
-    let env = TestEnv::new();
-    env.create_silo("dirty-silo");
-    env.create_uncommitted_file("dirty-silo", "dirty.txt", "uncommitted");
+fn test_new_take_changes_refuses_remote_branch() {
+    let env = TestEnv::builder().with_remote().build();
+    env.git(&["push", "origin", "HEAD:refs/heads/remote-only"]);
+    env.create_commit_on_remote("remote-only", "README.md", "# Remote\n", "On remote");
+    env.git(&["fetch", "origin"]);
+    fs::write(env.repo_dir.path().join("README.md"), "# Changed\n").unwrap();
 
-    // Should fail without --force
-    let output = env.run_silo(&["rm", "dirty-silo"]);
-    TestEnv::assert_failure(&output);
-    env.assert_silo_exists("dirty-silo");
+    let output = env.run_silo(&["new", "remote-only", "--take-changes"]);
 
-    // Should succeed with --force
-    let output = env.run_silo(&["rm", "dirty-silo", "--force"]);
-    TestEnv::assert_success(&output);
-    env.assert_silo_not_exists("dirty-silo");
+    TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("starts from origin/remote-only"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+    env.assert_silo_not_exists("remote-only");
+    let status = TestEnv::stdout(&env.git(&["status", "--porcelain"]));
+    assert!(status.contains(" M README.md"), "got: {}", status);
 }
 
 #[test]
-fn test_rm_dry_run_does_not_remove() {
+fn test_new_sandbox_runs_agent_in_new_silo() {
     let env = TestEnv::new();
-    env.create_silo("keep-me");
-    env.assert_silo_exists("keep-me");
 
-    let output = env.run_silo(&["rm", "keep-me", "--dry-run", "--force"]);
+    let output = env.run_silo_with_fake_docker(&[
+        "new",
+        "agent-task",
+        "--sandbox",
+        "--",
+        "claude",
+        "-p",
+        "fix it",
+    ]);
 
     TestEnv::assert_success(&output);
-    env.assert_silo_exists("keep-me"); // Should still exist
+    env.assert_silo_exists("agent-task");
     let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("Would remove"));
+    let workspace = format!("-w {}", env.silo_path("agent-task").display());
+    assert!(stdout.contains("docker sandbox run"), "got: {}", stdout);
+    assert!(stdout.contains(&workspace), "got: {}", stdout);
+    assert!(stdout.contains("claude -p fix it"), "got: {}", stdout);
+
+    let output = env.run_silo(&["last-run", "agent-task"]);
+    assert!(TestEnv::stdout(&output).contains("command:  sandbox claude -p fix it"));
+}
+
+#[test]
+fn test_new_sandbox_rejects_unknown_agent_before_creating() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo_with_fake_docker(&["new", "agent-task", "--sandbox", "--", "vim"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("Cannot run 'vim' in the sandbox"));
+    env.assert_silo_not_exists("agent-task");
 }
 
 // =============================================================================
-// CD COMMAND TESTS
+// LIST COMMAND TESTS
 // =============================================================================
 
 #[test]
-fn test_cd_outputs_directive() {
+fn test_list_shows_silos() {
     let env = TestEnv::new();
-    env.create_silo("cd-test");
+    env.create_silos(&["branch1", "branch2"]);
 
-    let directive_file = env.silo_dir.path().join("directive");
-    let output = env.run_silo_with_env(
-        &["cd", "cd-test"],
-        &[("SILO_DIRECTIVE_FILE", directive_file.to_str().unwrap())],
-    );
+    let output = env.run_silo(&["list"]);
 
     TestEnv::assert_success(&output);
-    let directive = fs::read_to_string(&directive_file).expect("Should have written directive");
-    assert!(directive.contains("cd="));
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("branch1"), "Output should contain branch1");
+    assert!(stdout.contains("branch2"), "Output should contain branch2");
 }
 
 #[test]
-fn test_cd_nonexistent_fails() {
+fn test_list_collapses_untracked_directories() {
     let env = TestEnv::new();
+    env.create_silo("generated");
+    for i in 0..12 {
+        env.create_uncommitted_file("generated", &format!("build/{}.o", i), "");
+    }
+    env.create_uncommitted_file("generated", "notes.txt", "");
 
-    let output = env.run_silo(&["cd", "nonexistent"]);
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(
+        stdout.contains("13 uncommitted files: build/ (12 files), notes.txt"),
+        "got: {}",
+        stdout
+    );
 
-    TestEnv::assert_failure(&output);
+    env.append_config("untracked_collapse = 0");
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(stdout.contains("build/0.o"), "got: {}", stdout);
+    assert!(!stdout.contains("(12 files)"), "got: {}", stdout);
 }
 
 #[test]
-fn test_cd_no_args_returns_main_worktree() {
+fn test_untracked_files_counted_alike_everywhere() {
     let env = TestEnv::new();
-    env.create_silo("feature-branch");
+    env.create_silo("generated");
+    env.create_uncommitted_file("generated", "build/a.o", "");
+    env.create_uncommitted_file("generated", "build/b.o", "");
 
-    let directive_file = env.silo_dir.path().join("directive");
-    let output = env.run_silo_with_env(
-        &["cd"],
-        &[("SILO_DIRECTIVE_FILE", directive_file.to_str().unwrap())],
-    );
+    let output = env.run_silo(&["list", "--format", "{untracked}"]);
+    assert_eq!(TestEnv::stdout(&output).trim(), "2");
 
+    // agent compare counts through the per-silo stats `rm` and `reset` use
+    let output = env.run_silo(&["agent", "compare", "generated"]);
     TestEnv::assert_success(&output);
-    let directive = fs::read_to_string(&directive_file).expect("Should have written directive");
-    assert!(directive.contains("cd="));
-
-    let repo_path = env.repo_dir.path().to_string_lossy();
-    assert!(directive.contains(repo_path.as_ref()));
+    let stdout = TestEnv::stdout(&output);
+    let row = stdout.lines().find(|l| l.starts_with("generated")).unwrap();
+    assert_eq!(row.split_whitespace().last(), Some("~2"), "got: {}", stdout);
 }
 
 #[test]
-fn test_cd_no_args_not_in_repo_fails() {
+fn test_list_limit_and_page() {
     let env = TestEnv::new();
+    env.create_silos(&["one", "two", "three"]);
+
+    let names = |args: &[&str]| -> Vec<String> {
+        let output = env.run_silo(args);
+        TestEnv::assert_success(&output);
+        TestEnv::stdout(&output).lines().map(String::from).collect()
+    };
+    let all = names(&["list", "-q"]);
+    assert_eq!(all.len(), 3);
+
+    assert_eq!(names(&["list", "-q", "--limit", "2"]), all[..2]);
+    assert_eq!(
+        names(&["list", "-q", "--limit", "2", "--page", "2"]),
+        all[2..]
+    );
+    assert!(names(&["list", "-q", "--limit", "2", "--page", "3"]).is_empty());
 
-    let output = Command::new(TestEnv::silo_bin())
-        .args(["cd"])
-        .current_dir(&env.silo_dir) // silo_dir is not a git repo
-        .output()
-        .expect("Failed to run silo cd");
+    // The main worktree row is always shown
+    let lines = names(&["list", "--all", "--limit", "1", "--page", "2"]);
+    assert_eq!(lines.len(), 2, "got: {:?}", lines);
 
-    TestEnv::assert_failure(&output);
-    let stderr = TestEnv::stderr(&output);
-    assert!(stderr.contains("Not in a git repository"));
+    TestEnv::assert_failure(&env.run_silo(&["list", "--page", "2"]));
+    TestEnv::assert_failure(&env.run_silo(&["list", "--limit", "0"]));
 }
 
-// =============================================================================
-// EXEC COMMAND TESTS
-// =============================================================================
-
 #[test]
-fn test_exec_runs_command_in_silo() {
+fn test_list_csv() {
     let env = TestEnv::new();
-    env.create_silo("exec-test");
+    env.create_silos(&["feature", "other"]);
+    env.create_commit_in_silo("feature", "a.txt", "a", "Add a");
+    env.git_in_silo(
+        "feature",
+        &["config", "branch.feature.description", "Fix login, again"],
+    );
 
-    let output = env.run_silo(&["exec", "exec-test", "pwd"]);
+    let output = env.run_silo(&["list", "--csv"]);
 
     TestEnv::assert_success(&output);
     let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("exec-test"));
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "got: {}", stdout);
+    let header: Vec<&str> = lines[0].split(',').collect();
+    assert_eq!(&header[..3], ["name", "id", "repo"]);
+    assert!(header.contains(&"description"));
+    let feature = lines.iter().find(|l| l.starts_with("feature,")).unwrap();
+    assert!(
+        feature.ends_with(",\"Fix login, again\""),
+        "got: {}",
+        feature
+    );
+    let ahead = header.iter().position(|f| *f == "ahead").unwrap();
+    assert_eq!(feature.split(',').nth(ahead), Some("1"));
+
+    TestEnv::assert_failure(&env.run_silo(&["list", "--csv", "--json"]));
 }
 
 #[test]
-fn test_exec_with_args() {
+fn test_list_porcelain_v1() {
     let env = TestEnv::new();
-    env.create_silo("exec-args");
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "a.txt", "a", "Add a");
+    env.create_uncommitted_file("feature", "b.txt", "b");
 
-    let output = env.run_silo(&["exec", "exec-args", "echo", "hello", "world"]);
+    let output = env.run_silo(&["list", "--porcelain"]);
 
     TestEnv::assert_success(&output);
     let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("hello world"));
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "# silo porcelain v1");
+    assert_eq!(lines.len(), 2, "got: {}", stdout);
+    let fields: Vec<&str> = lines[1].split('\t').collect();
+    assert_eq!(fields.len(), 20, "got: {}", lines[1]);
+    assert!(fields[0].ends_with(":feature"), "got: {}", fields[0]);
+    let repo_name = env.repo_dir.path().file_name().unwrap().to_str().unwrap();
+    assert_eq!(&fields[1..4], ["feature", repo_name, "feature"]);
+    assert_eq!(fields[4], env.silo_path("feature").to_string_lossy());
+    // ahead, behind, no upstream, then uncommitted counts
+    assert_eq!(&fields[5..9], ["1", "0", "", ""]);
+    assert_eq!(&fields[11..14], ["0", "0", "1"]);
+    assert_eq!(&fields[17..19], ["false", "false"]);
+    assert!(fields[19].parse::<u64>().is_ok(), "got: {}", fields[19]);
+
+    let output = env.run_silo(&["list", "--porcelain=v1"]);
+    assert_eq!(TestEnv::stdout(&output), stdout);
+    let output = env.run_silo(&["list", "--porcelain=v9"]);
+    TestEnv::assert_failure(&output);
 }
 
 #[test]
-fn test_exec_nonexistent_silo_fails() {
+fn test_list_format_template() {
     let env = TestEnv::new();
+    env.create_silos(&["quiet", "busy"]);
+    env.create_commit_in_silo("busy", "a.txt", "a", "Add a");
+    env.create_uncommitted_file("busy", "b.txt", "b");
 
-    let output = env.run_silo(&["exec", "nonexistent", "pwd"]);
+    let output = env.run_silo(&[
+        "list",
+        "--format",
+        "{name}\\t{ahead}\\t{untracked}\\t{verified}|",
+    ]);
+
+    TestEnv::assert_success(&output);
+    // Sorted as in the table, most commits ahead first
+    assert_eq!(TestEnv::stdout(&output), "busy\t1\t1\t|\nquiet\t0\t0\t|\n");
 
+    let output = env.run_silo(&["list", "--format", "{name} {nope}"]);
     TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("Unknown placeholder in --format: {nope}"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
 }
 
 #[test]
-fn test_exec_failing_command_returns_error() {
-    let env = TestEnv::new();
-    env.create_silo("exec-fail");
+fn test_list_remote_shows_upstream_ahead_behind() {
+    let env = TestEnv::builder().with_remote().build();
+    env.create_silos(&["pushed", "local"]);
+    env.push_silo("pushed");
+    env.create_commit_in_silo("pushed", "a.txt", "a", "Unpushed work");
+    env.create_commit_on_remote("pushed", "b.txt", "b", "Pushed elsewhere");
+    TestEnv::assert_success(&env.git_in_silo("pushed", &["fetch", "origin"]));
+
+    let output = env.run_silo(&["list", "--remote"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let pushed = stdout.lines().find(|l| l.contains("pushed (")).unwrap();
+    assert!(pushed.contains("+1 -1 vs upstream"), "got: {}", pushed);
+    let local = stdout.lines().find(|l| l.contains("local (")).unwrap();
+    assert!(local.contains("no upstream"), "got: {}", local);
+
+    // Not looked up without --remote
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(!stdout.contains("upstream"), "got: {}", stdout);
+
+    let output = env.run_silo(&[
+        "list",
+        "--format",
+        "{name}:{upstream_ahead}:{upstream_behind}",
+    ]);
+    let mut lines: Vec<String> = TestEnv::stdout(&output).lines().map(String::from).collect();
+    lines.sort();
+    assert_eq!(lines, ["local::", "pushed:1:1"]);
+}
 
-    let output = env.run_silo(&["exec", "exec-fail", "false"]);
+#[test]
+fn test_list_json_compares_to_main_and_upstream() {
+    let env = TestEnv::builder().with_remote().build();
+    env.create_silos(&["pushed", "local"]);
+    env.push_silo("pushed");
+    env.create_commit_in_silo("pushed", "a.txt", "a", "Unpushed work");
+    env.create_commit_on_remote("pushed", "b.txt", "b", "Pushed elsewhere");
+    TestEnv::assert_success(&env.git_in_silo("pushed", &["fetch", "origin"]));
+    let main_head = TestEnv::stdout(&env.git(&["rev-parse", "HEAD"]))
+        .trim()
+        .to_string();
+
+    let output = env.run_silo(&["list", "--json"]);
 
-    TestEnv::assert_failure(&output);
+    TestEnv::assert_success(&output);
+    let silos: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    let silo = |name: &str| {
+        silos
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["name"] == name)
+            .unwrap()
+            .clone()
+    };
+    let pushed = silo("pushed");
+    assert_eq!(pushed["base"]["branch"], "master");
+    assert_eq!(pushed["base"]["ahead"], 1);
+    assert_eq!(pushed["base"]["merge_base"], main_head.as_str());
+    assert_eq!(pushed["ahead"], pushed["base"]["ahead"]);
+    assert_eq!(pushed["upstream"]["branch"], "origin/pushed");
+    assert_eq!(pushed["upstream"]["ahead"], 1);
+    assert_eq!(pushed["upstream"]["behind"], 1);
+    assert_eq!(pushed["upstream"]["merge_base"], main_head.as_str());
+    assert!(silo("local")["upstream"].is_null());
 }
 
-// =============================================================================
-// PRUNE COMMAND TESTS
-// =============================================================================
-
 #[test]
-fn test_prune_removes_clean_silos() {
+fn test_list_shows_last_activity_age() {
     let env = TestEnv::new();
-    env.create_silos(&["clean1", "clean2"]);
+    env.create_silos(&["stale", "fresh"]);
+
+    // Only an old commit dates the stale silo
+    let output = Command::new("git")
+        .args(["commit", "--allow-empty", "-m", "Old work"])
+        .current_dir(env.silo_path("stale"))
+        .env("GIT_AUTHOR_DATE", "2020-01-01T00:00:00Z")
+        .env("GIT_COMMITTER_DATE", "2020-01-01T00:00:00Z")
+        .output()
+        .unwrap();
+    TestEnv::assert_success(&output);
+    fs::remove_file(env.tracking_path("stale").join("created")).unwrap();
+
+    let ages = || {
+        let output = env.run_silo(&["list", "--format", "{name}={age}"]);
+        TestEnv::assert_success(&output);
+        TestEnv::stdout(&output)
+    };
+    let listed = ages();
+    assert!(listed.contains("fresh=now\n"), "got: {}", listed);
+    let stale = listed
+        .lines()
+        .find_map(|line| line.strip_prefix("stale="))
+        .unwrap();
+    assert!(
+        stale.ends_with('w') && stale.len() > 3,
+        "expected years in weeks, got: {}",
+        stale
+    );
 
-    let output = env.run_silo(&["prune", "--force"]);
+    // Editing a file counts as activity
+    env.create_uncommitted_file("stale", "wip.txt", "wip");
+    assert!(ages().contains("stale=now\n"), "got: {}", ages());
+}
+
+#[test]
+fn test_list_du_shows_worktree_size() {
+    let env = TestEnv::new();
+    env.create_silos(&["small", "built"]);
+    let target = env.silo_path("built").join("target");
+    fs::create_dir(&target).unwrap();
+    fs::write(target.join("app"), vec![0u8; 3 * 1024 * 1024]).unwrap();
 
+    let output = env.run_silo(&["list", "--du"]);
     TestEnv::assert_success(&output);
-    env.assert_silo_not_exists("clean1");
-    env.assert_silo_not_exists("clean2");
+    let stdout = TestEnv::stdout(&output);
+    let built = stdout.lines().find(|line| line.contains("built")).unwrap();
+    assert!(built.contains(", 3.0M on disk"), "got: {}", built);
+    let small = stdout.lines().find(|line| line.contains("small")).unwrap();
+    assert!(small.contains("B on disk"), "got: {}", small);
+
+    let output = env.run_silo(&["list", "--format", "{name}={size}"]);
+    assert!(TestEnv::stdout(&output).contains("built=3.0M\n"));
+    // Not measured unless asked for
+    let output = env.run_silo(&["list"]);
+    assert!(!TestEnv::stdout(&output).contains("on disk"));
 }
 
 #[test]
-fn test_prune_preserves_dirty_silos() {
+fn test_list_fast_skips_lines_and_uncommitted_files() {
     let env = TestEnv::new();
-    env.create_silos(&["clean", "dirty"]);
-    env.create_uncommitted_file("dirty", "file.txt", "content");
-
-    let output = env.run_silo(&["prune", "--force"]);
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "a.txt", "a\n", "Add a");
+    env.create_uncommitted_file("feature", "scratch.txt", "");
 
+    let output = env.run_silo(&["list", "--fast"]);
     TestEnv::assert_success(&output);
-    env.assert_silo_not_exists("clean");
-    env.assert_silo_exists("dirty");
+    let stdout = TestEnv::stdout(&output);
+    let line = stdout.lines().find(|l| l.contains("feature")).unwrap();
+    assert_eq!(line, "  feature (feature)  +1 -0 commits");
+
+    // The config makes it the default, which --du overrides
+    env.append_config("[list]\nfast = true");
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(!stdout.contains("lines"), "got: {}", stdout);
+    let stdout = TestEnv::stdout(&env.run_silo(&["list", "--du"]));
+    assert!(stdout.contains("+1 -0 lines"), "got: {}", stdout);
+    assert!(stdout.contains("scratch.txt"), "got: {}", stdout);
 }
 
 #[test]
-fn test_prune_with_force_removes_silos_with_commits() {
+#[cfg(unix)]
+fn test_list_state_filters() {
     let env = TestEnv::new();
-    env.create_silos(&["clean", "has-commits"]);
-    env.create_commit_in_silo("has-commits", "new.txt", "content", "Add new file");
-
-    // With --force, even silos with unmerged commits are removed
-    let output = env.run_silo(&["prune", "--force"]);
+    env.create_silos(&["edited", "idle", "busy"]);
+    env.create_uncommitted_file("edited", "wip.txt", "wip");
+    let _process = env.start_process("busy", &["sleep", "30"]);
+
+    let names = |args: &[&str]| -> Vec<String> {
+        let output = env.run_silo(args);
+        TestEnv::assert_success(&output);
+        let mut names: Vec<String> = TestEnv::stdout(&output).lines().map(String::from).collect();
+        names.sort();
+        names
+    };
+    assert_eq!(names(&["list", "-q", "--dirty"]), ["edited"]);
+    assert_eq!(names(&["list", "-q", "--clean"]), ["busy", "idle"]);
+    assert_eq!(names(&["list", "-q", "--active"]), ["busy"]);
+    assert!(names(&["list", "-q", "--dirty", "--active"]).is_empty());
+    assert_eq!(
+        names(&["list", "--format", "{name}", "--all", "--clean", "--active"]),
+        ["busy"]
+    );
 
-    TestEnv::assert_success(&output);
-    env.assert_silo_not_exists("clean");
-    env.assert_silo_not_exists("has-commits");
+    TestEnv::assert_failure(&env.run_silo(&["list", "--dirty", "--clean"]));
 }
 
 #[test]
-fn test_prune_skips_silos_with_unmerged_commits() {
+fn test_list_empty_repo() {
     let env = TestEnv::new();
-    env.create_silos(&["clean", "has-commits"]);
-    env.create_commit_in_silo("has-commits", "new.txt", "content", "Add new file");
 
-    // Without --force, silos with unmerged commits should be blocked
-    // Since we're non-interactive, it will abort asking for confirmation
-    let output = env.run_silo(&["prune"]);
+    let output = env.run_silo(&["list"]);
 
     TestEnv::assert_success(&output);
-    // "has-commits" should be skipped and reported as blocked
-    let stderr = TestEnv::stderr(&output);
-    assert!(
-        stderr.contains("Skipping") || stderr.contains("blocked"),
-        "Should report skipped silos"
-    );
-    // Both should still exist since confirmation was denied
-    env.assert_silo_exists("clean");
-    env.assert_silo_exists("has-commits");
 }
 
 #[test]
-fn test_prune_dry_run() {
+fn test_list_all_across_repos() {
     let env = TestEnv::new();
-    env.create_silo("clean");
+    env.create_silo("test-silo");
 
-    let output = env.run_silo(&["prune", "--dry-run"]);
+    let output = env.run_silo(&["list", "--all"]);
 
     TestEnv::assert_success(&output);
-    env.assert_silo_exists("clean"); // Should still exist
     let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("Would remove"));
+    assert!(stdout.contains("test-silo"));
 }
 
 #[test]
-fn test_prune_empty_repo() {
+fn test_list_repo_filter_matches_current_repo() {
     let env = TestEnv::new();
+    env.create_silo("filtered");
+    let repo_name = env
+        .repo_dir
+        .path()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+
+    let output = env.run_silo(&["list", "--all", "--repo", &repo_name]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("filtered"));
 
-    let output = env.run_silo(&["prune", "--force"]);
-
+    let output = env.run_silo(&["list", "--all", "--repo", "no-such-repo"]);
     TestEnv::assert_success(&output);
+    assert!(!TestEnv::stdout(&output).contains("filtered"));
 }
 
 #[test]
-fn test_prune_all() {
+fn test_list_summary_aggregates_per_repo() {
     let env = TestEnv::new();
-    env.create_silo("clean");
+    env.create_silos(&["one", "two"]);
+    env.create_uncommitted_file("one", "dirty.txt", "content");
+    env.create_commit_in_silo("two", "new.txt", "content", "Add new file");
 
-    let output = env.run_silo(&["prune", "--all", "--force"]);
+    let output = env.run_silo(&["list", "--summary"]);
 
     TestEnv::assert_success(&output);
-    env.assert_silo_not_exists("clean");
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("2 silos, 1 dirty, +1 commits"),
+        "unexpected summary: {}",
+        stdout
+    );
 }
 
-// =============================================================================
-// REBASE COMMAND TESTS
-// =============================================================================
-
 #[test]
-fn test_rebase_success() {
+fn test_list_json_includes_canonical_id() {
     let env = TestEnv::new();
-    env.create_silo("feature");
-
-    // Create a commit in the silo
-    env.create_commit_in_silo("feature", "feature.txt", "feature content", "Add feature");
-
-    // Create a commit in main that doesn't conflict
-    env.create_commit("main.txt", "main content", "Add to main");
+    env.create_silo("json-silo");
 
-    let output = env.run_silo(&["rebase", "feature"]);
+    let output = env.run_silo(&["list", "--json"]);
 
     TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("\"name\": \"json-silo\""),
+        "got: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains(":json-silo\""),
+        "should contain id: {}",
+        stdout
+    );
 }
 
 #[test]
-fn test_rebase_nonexistent_fails() {
+fn test_list_flags_rebase_and_bisect_in_progress() {
     let env = TestEnv::new();
+    env.create_silos(&["conflicted", "bisecting", "idle"]);
+    env.create_commit_in_silo("conflicted", "file.txt", "silo\n", "Silo change");
+    env.create_commit("file.txt", "main\n", "Main change");
+    let main_branch = TestEnv::stdout(&env.git(&["branch", "--show-current"]));
+    let output = env.git_in_silo("conflicted", &["rebase", main_branch.trim()]);
+    TestEnv::assert_failure(&output);
+    TestEnv::assert_success(&env.git_in_silo("bisecting", &["bisect", "start"]));
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    let line = |name: &str| {
+        stdout
+            .lines()
+            .find(|l| l.contains(&format!(" {} (", name)))
+            .unwrap_or_default()
+            .to_string()
+    };
+    assert!(line("conflicted").contains("REBASING"), "got: {}", stdout);
+    assert!(line("bisecting").contains("BISECTING"), "got: {}", stdout);
+    assert!(!line("idle").contains("ING"), "got: {}", stdout);
+
+    let output = env.run_silo(&["list", "--json"]);
+    let silos: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    let state = |name: &str| {
+        silos
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["name"] == name)
+            .unwrap()["in_progress"]
+            .clone()
+    };
+    assert_eq!(state("conflicted"), "rebase");
+    assert_eq!(state("bisecting"), "bisect");
+    assert!(state("idle").is_null());
+}
 
-    let output = env.run_silo(&["rebase", "nonexistent"]);
+#[test]
+fn test_exec_accepts_canonical_id() {
+    let env = TestEnv::new();
+    env.create_silo("by-id");
 
-    TestEnv::assert_failure(&output);
+    let output = env.run_silo(&["list", "--json"]);
+    let stdout = TestEnv::stdout(&output);
+    let id = stdout
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("\"id\": \""))
+        .and_then(|rest| rest.strip_suffix("\","))
+        .expect("id field in JSON output")
+        .to_string();
+
+    let output = env.run_silo(&["exec", &id, "pwd"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("by-id"));
 }
 
 #[test]
-fn test_rebase_dry_run() {
+fn test_list_always_qualified_display_names() {
     let env = TestEnv::new();
-    env.create_silo("feature");
-    env.create_commit_in_silo("feature", "feature.txt", "content", "Feature");
-    env.create_commit("main.txt", "content", "Main");
+    env.create_silo("qualified");
+    let config_path = env.repo_dir.path().join(".silo.toml");
+    let config = fs::read_to_string(&config_path).unwrap();
+    fs::write(
+        &config_path,
+        format!("{}\ndisplay_names = \"always-qualified\"\n", config),
+    )
+    .unwrap();
 
-    let output = env.run_silo(&["rebase", "feature", "--dry-run"]);
+    let output = env.run_silo(&["list", "--quiet"]);
 
     TestEnv::assert_success(&output);
     let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("Would rebase"));
-}
+    assert!(
+        stdout.trim().ends_with("/qualified"),
+        "name should include repo: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_list_user_filter() {
+    let env = TestEnv::new();
+    env.create_silo("mine");
+    let whoami = Command::new("id").arg("-un").output().unwrap();
+    let user = String::from_utf8_lossy(&whoami.stdout).trim().to_string();
+
+    let output = env.run_silo(&["list", "--user", &user]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("mine"));
+
+    let output = env.run_silo(&["list", "--user", "no-such-user-xyz"]);
+    TestEnv::assert_success(&output);
+    assert!(!TestEnv::stdout(&output).contains("mine"));
+}
+
+// =============================================================================
+// RM COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_rm_removes_silo() {
+    let env = TestEnv::new();
+    env.create_silo("to-remove");
+    env.assert_silo_exists("to-remove");
+
+    let output = env.run_silo(&["rm", "to-remove", "--force"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("to-remove");
+}
+
+#[test]
+fn test_metrics_counts_silos_commands_and_removals() {
+    let env = TestEnv::new();
+    env.create_silo("kept");
+    env.create_silo("gone");
+    TestEnv::assert_success(&env.run_silo(&["rm", "gone", "--force"]));
+    TestEnv::assert_failure(&env.run_silo(&["rm", "nonexistent", "--force"]));
+
+    let output = env.run_silo(&["metrics"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("silo_silos{repo=\""), "got: {}", stdout);
+    assert!(
+        stdout.contains("silo_storage_bytes{root=\""),
+        "got: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("silo_removed_total{by=\"rm\"} 1\n"),
+        "got: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("silo_command_duration_seconds_count{command=\"rm\"} 2\n"),
+        "got: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("silo_command_failures_total{command=\"rm\"} 1\n"),
+        "got: {}",
+        stdout
+    );
+    assert!(!stdout.contains("command=\"metrics\""), "got: {}", stdout);
+    assert!(stdout.ends_with("# EOF\n"));
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["metrics", "--no-disk-usage"]));
+    assert!(!stdout.contains("silo_storage_bytes"), "got: {}", stdout);
+}
+
+#[test]
+fn test_metrics_counts_commands_that_exit_with_the_command_status() {
+    let env = TestEnv::new();
+    env.create_silo("runs");
+    let output = env.run_silo(&["exec", "runs", "sh", "-c", "exit 7"]);
+    assert_eq!(output.status.code(), Some(7));
+    TestEnv::assert_success(&env.run_silo(&["exec", "--no-track", "runs", "true"]));
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["metrics", "--no-disk-usage"]));
+
+    assert!(
+        stdout.contains("silo_command_duration_seconds_count{command=\"exec\"} 1\n"),
+        "got: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("silo_command_failures_total{command=\"exec\"} 1\n"),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_stats_adds_up_silos() {
+    let env = TestEnv::new();
+    env.create_silos(&["clean", "busy"]);
+    env.create_commit_in_silo("busy", "a.txt", "a\nb\n", "Add a");
+    env.create_commit_in_silo("busy", "b.txt", "b\n", "Add b");
+    fs::write(env.silo_path("busy").join("wip.txt"), "wip").unwrap();
+
+    let output = env.run_silo(&["stats"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("Silos:            2 in 1 repository"),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("Unmerged commits: 2"), "got: {}", stdout);
+    assert!(
+        stdout.contains("Lines:            +3 -0"),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("Dirty silos:      1"), "got: {}", stdout);
+
+    let output = env.run_silo(&["stats", "--json"]);
+    let stats: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert_eq!(stats["total"]["silos"], 2);
+    assert_eq!(stats["repos"][0]["unmerged_commits"], 2);
+    assert!(stats["total"]["oldest"]["name"].as_str().is_some());
+
+    let output = env.run_silo(&["stats", "--repo", "no-such-repo"]);
+    assert!(TestEnv::stdout(&output).contains("Silos:            0 in 0 repositories"));
+}
+
+#[test]
+fn test_rm_nonexistent_fails() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["rm", "nonexistent", "--force"]);
+
+    TestEnv::assert_failure(&output);
+}
+
+#[test]
+fn test_rm_with_uncommitted_changes_requires_force() {
+    let env = TestEnv::new();
+    env.create_silo("dirty-silo");
+    env.create_uncommitted_file("dirty-silo", "dirty.txt", "uncommitted");
+
+    // Should fail without --force
+    let output = env.run_silo(&["rm", "dirty-silo"]);
+    TestEnv::assert_failure(&output);
+    env.assert_silo_exists("dirty-silo");
+
+    // Should succeed with --force
+    let output = env.run_silo(&["rm", "dirty-silo", "--force"]);
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("dirty-silo");
+}
+
+#[test]
+fn test_rm_with_active_process_requires_force() {
+    let env = TestEnv::new();
+    env.create_silo("busy");
+    let process = env.start_process("busy", &["sleep", "30"]);
+    assert_eq!(process.registered_pids().len(), 1);
+
+    let output = env.run_silo(&["rm", "busy"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("Active processes"));
+    env.assert_silo_exists("busy");
+}
+
+#[test]
+fn test_builder_seeds_repo_files_and_config() {
+    let env = TestEnv::builder()
+        .file("src/lib.rs", "// seeded\n")
+        .config("display_names = \"always-qualified\"")
+        .build();
+    env.create_silo("seeded");
+
+    assert!(env.silo_path("seeded").join("src/lib.rs").exists());
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(stdout.contains("/seeded"), "got: {}", stdout);
+}
+
+#[test]
+fn test_rm_dry_run_does_not_remove() {
+    let env = TestEnv::new();
+    env.create_silo("keep-me");
+    env.assert_silo_exists("keep-me");
+
+    let output = env.run_silo(&["rm", "keep-me", "--dry-run", "--force"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_exists("keep-me"); // Should still exist
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Would remove"));
+}
+
+/// Set up a merged silo branch whose upstream has a commit that only exists
+/// on the remote, as if pushed from another clone.
+fn env_with_remote_only_work(name: &str) -> TestEnv {
+    let env = TestEnv::builder().with_remote().build();
+    env.create_silo(name);
+    env.push_silo(name);
+    env.create_commit_on_remote(name, "remote.txt", "remote work", "Remote work");
+    TestEnv::assert_success(&env.git(&["fetch", "origin"]));
+    env
+}
+
+fn local_branch_exists(env: &TestEnv, branch: &str) -> bool {
+    let refname = format!("refs/heads/{}", branch);
+    env.git(&["rev-parse", "--verify", "--quiet", &refname])
+        .status
+        .success()
+}
+
+#[test]
+fn test_rm_preserves_branch_with_remote_only_commits() {
+    let env = env_with_remote_only_work("pushed");
+
+    let output = env.run_silo(&["rm", "pushed", "--force"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("pushed");
+    assert!(local_branch_exists(&env, "pushed"));
+    let stderr = TestEnv::stderr(&output);
+    assert!(
+        stderr.contains("upstream has 1 commit not in the local branch"),
+        "got: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_rm_deletes_merged_branch_with_up_to_date_upstream() {
+    let env = TestEnv::builder().with_remote().build();
+    env.create_silo("pushed");
+    env.push_silo("pushed");
+
+    TestEnv::assert_success(&env.run_silo(&["rm", "pushed", "--force"]));
+
+    assert!(!local_branch_exists(&env, "pushed"));
+}
+
+#[test]
+fn test_prune_dry_run_reports_remote_only_commits() {
+    let env = env_with_remote_only_work("pushed");
+
+    let output = env.run_silo(&["prune", "--dry-run"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("Would preserve branch 'pushed' (upstream has 1 commit"),
+        "got: {}",
+        stdout
+    );
+
+    TestEnv::assert_success(&env.run_silo(&["prune", "--force"]));
+    assert!(local_branch_exists(&env, "pushed"));
+}
+
+// =============================================================================
+// CD COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_cd_outputs_directive() {
+    let env = TestEnv::new();
+    env.create_silo("cd-test");
+
+    let directive_file = env.silo_dir.path().join("directive");
+    let output = env.run_silo_with_env(
+        &["cd", "cd-test"],
+        &[("SILO_DIRECTIVE_FILE", directive_file.to_str().unwrap())],
+    );
+
+    TestEnv::assert_success(&output);
+    let directive = fs::read_to_string(&directive_file).expect("Should have written directive");
+    assert!(directive.contains("cd="));
+}
+
+#[test]
+fn test_rm_from_inside_silo_moves_shell_to_main_worktree() {
+    let env = TestEnv::new();
+    env.create_silos(&["feature", "other"]);
+    let directive_file = env.silo_dir.path().join("directive");
+
+    let output = env
+        .silo_command(&["rm", "--force", "feature"])
+        .current_dir(env.silo_path("feature"))
+        .env("SILO_DIRECTIVE_FILE", &directive_file)
+        .output()
+        .unwrap();
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("feature");
+    let directive = fs::read_to_string(&directive_file).unwrap();
+    let cd = directive
+        .lines()
+        .find_map(|l| l.strip_prefix("cd="))
+        .unwrap();
+    assert_eq!(
+        fs::canonicalize(cd).unwrap(),
+        fs::canonicalize(env.repo_dir.path()).unwrap()
+    );
+
+    // Without shell integration the user is told to leave
+    let output = env.run_silo_in("other", &["rm", "--force", "other"]);
+    TestEnv::assert_success(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("Your shell is in silo 'other'"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+
+    // Removing another silo from inside one leaves the shell alone
+    env.create_silos(&["here", "there"]);
+    let output = env.run_silo_in("here", &["rm", "--force", "there"]);
+    TestEnv::assert_success(&output);
+    assert!(!TestEnv::stderr(&output).contains("Your shell is in silo"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_cd_writes_recovery_file_when_directive_file_is_gone() {
+    let env = TestEnv::new();
+    env.create_silo("cd-test");
+
+    // The temp directories of both the directive and the recovery file
+    // were cleaned up
+    let directive_file = env.silo_dir.path().join("gone/directive");
+    let recovery_dir = env.silo_dir.path().join("recovery");
+    let output = env.run_silo_with_env(
+        &["cd", "cd-test"],
+        &[
+            ("SILO_DIRECTIVE_FILE", directive_file.to_str().unwrap()),
+            ("SILO_RECOVERY_DIR", recovery_dir.to_str().unwrap()),
+        ],
+    );
+
+    TestEnv::assert_success(&output);
+    let recovery = recovery_dir.join("directive");
+    let directive = fs::read_to_string(&recovery).expect("Should have written recovery file");
+    assert!(directive.contains("cd="), "got: {}", directive);
+    assert!(TestEnv::stderr(&output).is_empty());
+    // Recreated private to the user
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(&recovery_dir).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+}
+
+#[test]
+fn test_cd_warns_when_directives_cannot_be_written() {
+    let env = TestEnv::new();
+    env.create_silo("cd-test");
+
+    let directive_file = env.silo_dir.path().join("gone/directive");
+    let output = env.run_silo_with_env(
+        &["cd", "cd-test"],
+        &[
+            ("SILO_DIRECTIVE_FILE", directive_file.to_str().unwrap()),
+            ("SILO_RECOVERY_DIR", "/nonexistent/silo-recovery"),
+        ],
+    );
+
+    TestEnv::assert_success(&output);
+    let stderr = TestEnv::stderr(&output);
+    assert!(
+        stderr.contains("Cannot write shell directive file"),
+        "got: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_doctor_reports_unwritable_directive_file() {
+    let env = TestEnv::new();
+
+    let directive_file = env.silo_dir.path().join("gone/directive");
+    let output = env.run_silo_with_env(
+        &["doctor"],
+        &[("SILO_DIRECTIVE_FILE", directive_file.to_str().unwrap())],
+    );
+
+    TestEnv::assert_failure(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("is not writable"), "got: {}", stdout);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_cd_spawn_starts_shell_in_silo() {
+    let env = TestEnv::new();
+    env.create_silo("spawned");
+
+    // A "shell" that reports where it runs and exits with a status of its own
+    env.fake_command(
+        "fake-shell",
+        "echo \"$PWD|$SILO_CURRENT|$SILO_BRANCH|${SILO_DIRECTIVE_FILE-unset}\"\n\
+         ls \"$SILO_PATH\" > /dev/null || exit 9\n\
+         exit 7",
+    );
+    let shell = env.fake_bin_dir().join("fake-shell");
+    let directive_file = env.silo_dir.path().join("directive");
+    let output = env.run_silo_with_env(
+        &["cd", "--spawn", "spawned"],
+        &[
+            ("SHELL", shell.to_str().unwrap()),
+            ("SILO_DIRECTIVE_FILE", directive_file.to_str().unwrap()),
+        ],
+    );
+
+    assert_eq!(output.status.code(), Some(7));
+    let silo_path = env.silo_path("spawned").canonicalize().unwrap();
+    assert_eq!(
+        TestEnv::stdout(&output).trim(),
+        format!("{}|spawned|spawned|unset", silo_path.display())
+    );
+    assert!(TestEnv::stderr(&output).contains("Starting"));
+    // The calling shell isn't asked to change directory
+    assert!(!directive_file.exists());
+}
+
+#[test]
+fn test_cd_nonexistent_fails() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["cd", "nonexistent"]);
+
+    TestEnv::assert_failure(&output);
+}
+
+#[test]
+fn test_cd_no_args_returns_main_worktree() {
+    let env = TestEnv::new();
+    env.create_silo("feature-branch");
+
+    let directive_file = env.silo_dir.path().join("directive");
+    let output = env.run_silo_with_env(
+        &["cd"],
+        &[("SILO_DIRECTIVE_FILE", directive_file.to_str().unwrap())],
+    );
+
+    TestEnv::assert_success(&output);
+    let directive = fs::read_to_string(&directive_file).expect("Should have written directive");
+    assert!(directive.contains("cd="));
+
+    let repo_path = env.repo_dir.path().to_string_lossy();
+    assert!(directive.contains(repo_path.as_ref()));
+}
+
+#[test]
+fn test_resolve_prints_path_without_side_effects() {
+    let env = TestEnv::new();
+    env.create_silo("resolved");
+
+    let directive_file = env.silo_dir.path().join("directive");
+    let output = env.run_silo_with_env(
+        &["resolve", "resolved"],
+        &[("SILO_DIRECTIVE_FILE", directive_file.to_str().unwrap())],
+    );
+
+    TestEnv::assert_success(&output);
+    assert_eq!(
+        TestEnv::stdout(&output).trim(),
+        env.silo_path("resolved").display().to_string()
+    );
+    assert!(!directive_file.exists());
+}
+
+#[test]
+fn test_resolve_explain_shows_decision() {
+    let env = TestEnv::new();
+    env.create_silo("explained");
+
+    let output = env.run_silo(&["resolve", "explained", "--explain"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("In repo:   1 match"), "got: {}", stdout);
+    assert!(
+        stdout.contains("by the only match in the current repository"),
+        "got: {}",
+        stdout
+    );
+
+    let output = env.run_silo(&["resolve", "missing", "--json"]);
+    TestEnv::assert_failure(&output);
+    let json: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert_eq!(json["result"], "not_found");
+    assert_eq!(json["decided_by"], "qualified_name");
+    assert!(TestEnv::stderr(&output).contains("Silo not found: missing"));
+}
+
+#[test]
+fn test_cd_no_args_not_in_repo_fails() {
+    let env = TestEnv::new();
+
+    let output = Command::new(env.silo_bin())
+        .args(["cd"])
+        .current_dir(&env.silo_dir) // silo_dir is not a git repo
+        .output()
+        .expect("Failed to run silo cd");
+
+    TestEnv::assert_failure(&output);
+    let stderr = TestEnv::stderr(&output);
+    assert!(stderr.contains("Not in a git repository"));
+}
+
+// =============================================================================
+// EXEC COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_exec_runs_command_in_silo() {
+    let env = TestEnv::new();
+    env.create_silo("exec-test");
+
+    let output = env.run_silo(&["exec", "exec-test", "pwd"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("exec-test"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_exec_marks_tmux_window_busy() {
+    let env = TestEnv::new();
+    env.create_silo("busy");
+    let log = env.silo_dir.path().join("tmux.log");
+    env.fake_command("tmux", &format!("echo \"$*\" >> '{}'", log.display()));
+    let path = format!(
+        "{}:{}",
+        env.fake_bin_dir().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let tmux_env = [
+        ("PATH", path.as_str()),
+        ("TMUX", "/tmp/tmux-1000/default,1,0"),
+        ("TMUX_PANE", "%3"),
+    ];
+
+    // Cleared even when the command fails
+    let output = env.run_silo_with_env(&["exec", "busy", "sh", "-c", "exit 3"], &tmux_env);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        "set-option -w -t %3 @silo_busy busy\nset-option -w -u -t %3 @silo_busy\n"
+    );
+
+    fs::remove_file(&log).unwrap();
+    env.append_config("busy_indicator = false");
+    TestEnv::assert_success(&env.run_silo_with_env(&["exec", "busy", "true"], &tmux_env));
+    assert!(!log.exists());
+}
+
+#[test]
+fn test_exec_sets_silo_environment() {
+    let env = TestEnv::new();
+    env.create_silo("stamped");
+
+    let output = env.run_silo(&[
+        "exec",
+        "stamped",
+        "sh",
+        "-c",
+        "echo \"$SILO|$SILO_NAME|$SILO_PATH|$SILO_MAIN_WORKTREE\"",
+    ]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let fields: Vec<&str> = stdout.trim().split('|').collect();
+    assert_eq!(&fields[..2], ["1", "stamped"]);
+    assert_eq!(
+        Path::new(fields[2]).canonicalize().unwrap(),
+        env.silo_path("stamped").canonicalize().unwrap()
+    );
+    assert_eq!(
+        Path::new(fields[3]).canonicalize().unwrap(),
+        env.repo_dir.path().canonicalize().unwrap()
+    );
+}
+
+#[test]
+fn test_exec_sets_full_silo_name_for_nested_branches() {
+    let env = TestEnv::new();
+    env.create_silo("feature/x");
+
+    let output = env.run_silo(&["exec", "feature/x", "sh", "-c", "echo \"$SILO_NAME\""]);
+
+    TestEnv::assert_success(&output);
+    assert_eq!(TestEnv::stdout(&output).trim(), "feature/x");
+}
+
+#[test]
+fn test_code_opens_workspace_with_main_worktree() {
+    let env = TestEnv::new();
+    env.create_silo("editing");
+    env.append_config(r#"editor_command = ["sh", "-c", "echo \"$1\" > opened.txt", "sh"]"#);
+
+    let output = env.run_silo(&["code", "editing"]);
+
+    TestEnv::assert_success(&output);
+    let silo = env.silo_path("editing");
+    let workspace = fs::read_to_string(silo.join("opened.txt")).unwrap();
+    let workspace = workspace.trim();
+    assert!(
+        workspace.ends_with("silo.code-workspace"),
+        "got: {}",
+        workspace
+    );
+    assert_eq!(
+        TestEnv::stdout(&env.run_silo(&["code", "--print", "editing"])).trim(),
+        workspace
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(workspace).unwrap()).unwrap();
+    let main = env.repo_dir.path().canonicalize().unwrap();
+    let paths: Vec<&str> = json["folders"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(paths.len(), 2);
+    assert!(paths[0].ends_with("/editing"), "got: {:?}", paths);
+    assert_eq!(Path::new(paths[1]).canonicalize().unwrap(), main);
+}
+
+#[test]
+fn test_exec_tty_runs_command_on_terminal() {
+    let env = TestEnv::new();
+    env.create_silo("tty");
+    let script = "test -t 0 && test -t 1 && test -t 2 && echo on-tty; exit 4";
+
+    let output = env.run_silo(&["exec", "--tty", "tty", "sh", "-c", script]);
+
+    assert_eq!(output.status.code(), Some(4));
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("on-tty"), "got: {}", stdout);
+    let run = TestEnv::stdout(&env.run_silo(&["last-run", "tty"]));
+    assert!(run.contains("failed (exit 4)"), "got: {}", run);
+}
+
+#[test]
+fn test_exec_no_tty_runs_command_without_terminal() {
+    let env = TestEnv::new();
+    env.create_silo("no-tty");
+    let script = "test ! -t 0 && test ! -t 1 && echo no-tty && echo err >&2";
+
+    let output = env.run_silo(&["exec", "--no-tty", "no-tty", "sh", "-c", script]);
+
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("no-tty"));
+    assert!(TestEnv::stderr(&output).contains("err"));
+}
+
+#[test]
+fn test_exec_no_tty_passes_interrupt_on_to_command() {
+    let env = TestEnv::new();
+    env.create_silo("interrupted");
+    let ready = env.silo_path("interrupted").join("ready");
+    let script = "trap 'echo interrupted; exit 3' INT; touch ready; \
+                  while true; do sleep 0.1; done";
+    let child = env
+        .silo_command(&["exec", "--no-tty", "interrupted", "sh", "-c", script])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    for _ in 0..100 {
+        if ready.exists() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    assert!(ready.exists(), "command never started");
+
+    let kill = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(kill.success());
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(TestEnv::stdout(&output).contains("interrupted"));
+}
+
+#[test]
+fn test_exec_capture_prints_json_result() {
+    let env = TestEnv::new();
+    env.create_silo("captured");
+    let script = "printf 0123456789%.0s $(seq 200); echo oops >&2; exit 3";
+
+    let output = env.run_silo(&["exec", "--capture=1", "captured", "sh", "-c", script]);
+
+    assert_eq!(output.status.code(), Some(3));
+    let result: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert_eq!(result["silo"], "captured");
+    assert_eq!(result["exit_code"], 3);
+    assert!(result["duration_ms"].is_u64() && result["total_ms"].is_u64());
+    assert_eq!(result["stdout"]["bytes"], 2000);
+    assert_eq!(result["stdout"]["text"].as_str().unwrap().len(), 1024);
+    assert_eq!(result["stdout"]["truncated"], true);
+    assert_eq!(result["stderr"]["text"], "oops\n");
+    assert_eq!(result["stderr"]["truncated"], false);
+
+    let log = fs::read_to_string(result["log"].as_str().unwrap()).unwrap();
+    assert_eq!(log.len(), 2005, "log: {}", log);
+    let run = TestEnv::stdout(&env.run_silo(&["last-run", "captured"]));
+    assert!(run.contains("failed (exit 3)"), "got: {}", run);
+}
+
+#[test]
+fn test_exec_capture_rejects_out_of_range_limit() {
+    let env = TestEnv::new();
+    env.create_silo("captured");
+    let limit = format!("--capture={}", usize::MAX / 1024 + 1);
+
+    let output = env.run_silo(&["exec", &limit, "captured", "true"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("Invalid capture limit"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+}
+
+#[test]
+fn test_exec_no_track_records_nothing() {
+    let env = TestEnv::new();
+    env.create_silo("quick");
+    let pids = env
+        .silo_path("quick")
+        .parent()
+        .unwrap()
+        .join(".tracking/quick/pids");
+    // The PID file is written just after the command starts
+    let count_pid_files = r#"for i in 1 2 3 4 5 6 7 8 9 10; do
+            [ -n "$(ls "$0" 2>/dev/null)" ] && break; sleep 0.1
+        done; ls "$0" 2>/dev/null | wc -l"#;
+    let pids = pids.to_str().unwrap();
+
+    let output = env.run_silo(&[
+        "exec",
+        "--no-track",
+        "quick",
+        "sh",
+        "-c",
+        count_pid_files,
+        pids,
+    ]);
+    TestEnv::assert_success(&output);
+    assert_eq!(TestEnv::stdout(&output).trim(), "0");
+    TestEnv::assert_failure(&env.run_silo(&["last-run", "quick"]));
+
+    let output = env.run_silo(&["exec", "--no-track", "--capture", "quick", "true"]);
+    let result: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert!(result["log"].is_null());
+    TestEnv::assert_failure(&env.run_silo(&["last-run", "quick"]));
+
+    // Tracked as usual without the flag
+    let output = env.run_silo(&["exec", "quick", "sh", "-c", count_pid_files, pids]);
+    assert_eq!(TestEnv::stdout(&output).trim(), "1");
+    TestEnv::assert_success(&env.run_silo(&["last-run", "quick"]));
+}
+
+#[test]
+fn test_exec_no_track_conflicts_with_verify() {
+    let env = TestEnv::new();
+    env.create_silo("quick");
+
+    let output = env.run_silo(&["exec", "--no-track", "--verify", "quick", "true"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("cannot be used with"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+}
+
+#[test]
+fn test_exec_tty_conflicts_with_no_tty() {
+    let env = TestEnv::new();
+    env.create_silo("both");
+
+    let output = env.run_silo(&["exec", "--tty", "--no-tty", "both", "true"]);
+
+    TestEnv::assert_failure(&output);
+}
+
+#[test]
+fn test_exec_with_args() {
+    let env = TestEnv::new();
+    env.create_silo("exec-args");
+
+    let output = env.run_silo(&["exec", "exec-args", "echo", "hello", "world"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("hello world"));
+}
+
+#[test]
+fn test_exec_expands_placeholders_in_extra_args() {
+    let env = TestEnv::builder()
+        .config("[extra_command_args]")
+        .config("echo = [\"--silo={silo}\", \"{shared_target_dir}\"]")
+        .build();
+    env.create_silo("placeholders");
+
+    let output = env.run_silo(&["exec", "placeholders", "echo", "done"]);
+
+    TestEnv::assert_success(&output);
+    let shared = env
+        .silo_path("placeholders")
+        .parent()
+        .unwrap()
+        .join(".shared/target");
+    assert_eq!(
+        TestEnv::stdout(&output).trim(),
+        format!("--silo=placeholders {} done", shared.display())
+    );
+}
+
+#[test]
+fn test_exec_extra_args_share_target_dir_across_nested_silos() {
+    let env = TestEnv::builder()
+        .config("agent_email = \"agent@example.com\"")
+        .config("[extra_command_args]")
+        .config("echo = [\"{shared_target_dir}\", \"{agent_email}\"]")
+        .config("cat = [\"{nope}\"]")
+        .build();
+    env.create_silos(&["top", "feature/x"]);
+
+    let top = env.run_silo(&["exec", "top", "echo"]);
+    let nested = env.run_silo(&["exec", "feature/x", "echo"]);
+
+    TestEnv::assert_success(&top);
+    TestEnv::assert_success(&nested);
+    let shared = env
+        .silo_path("top")
+        .parent()
+        .unwrap()
+        .join(".shared/target");
+    let expected = format!("{} agent@example.com", shared.display());
+    assert_eq!(TestEnv::stdout(&top).trim(), expected);
+    assert_eq!(TestEnv::stdout(&nested).trim(), expected);
+}
+
+#[test]
+fn test_exec_agent_email_defaults_to_git_user_email() {
+    let env = TestEnv::builder()
+        .config("[extra_command_args]")
+        .config("echo = [\"{agent_email}\"]")
+        .build();
+    env.create_silo("emailed");
+    TestEnv::assert_success(
+        &env.git_in_silo("emailed", &["config", "user.email", "someone@example.com"]),
+    );
+
+    let output = env.run_silo(&["exec", "emailed", "echo"]);
+
+    TestEnv::assert_success(&output);
+    assert_eq!(TestEnv::stdout(&output).trim(), "someone@example.com");
+}
+
+#[test]
+fn test_exec_rejects_unknown_placeholder() {
+    let env = TestEnv::builder()
+        .config("[extra_command_args]")
+        .config("echo = [\"{nope}\"]")
+        .build();
+    env.create_silo("unknown");
+
+    let output = env.run_silo(&["exec", "unknown", "echo", "done"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("Unknown placeholder in extra_command_args: {nope}"));
+}
+
+#[test]
+fn test_exec_nonexistent_silo_fails() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["exec", "nonexistent", "pwd"]);
+
+    TestEnv::assert_failure(&output);
+}
+
+#[test]
+fn test_exec_failing_command_returns_error() {
+    let env = TestEnv::new();
+    env.create_silo("exec-fail");
+
+    let output = env.run_silo(&["exec", "exec-fail", "false"]);
+
+    TestEnv::assert_failure(&output);
+}
+
+#[test]
+fn test_exec_verify_records_result() {
+    let env = TestEnv::new();
+    env.append_config(r#"verify_command = ["test", "-f", "built.txt"]"#);
+    env.create_silo("verified");
+
+    let output = env.run_silo(&["exec", "--verify", "verified", "touch", "built.txt"]);
+
+    TestEnv::assert_success(&output);
+    let output = env.run_silo(&["list"]);
+    assert!(TestEnv::stdout(&output).contains("tests passed"));
+}
+
+#[test]
+fn test_exec_uses_config_of_target_silo() {
+    let env = TestEnv::new();
+    env.append_config(r#"verify_command = ["false"]"#);
+    env.create_silo("own-config");
+    std::fs::write(
+        env.silo_path("own-config").join(".silo.toml"),
+        "verify_command = [\"true\"]\n[extra_command_args]\necho = [\"from-silo\"]\n",
+    )
+    .unwrap();
+
+    // Invoked from the main worktree, the silo's own config still applies
+    let output = env.run_silo(&["exec", "--verify", "own-config", "echo", "done"]);
+
+    TestEnv::assert_success(&output);
+    assert_eq!(
+        TestEnv::stdout(&output).lines().next(),
+        Some("from-silo done")
+    );
+}
+
+// =============================================================================
+// LAST-RUN COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_last_run_records_failed_exec() {
+    let env = TestEnv::new();
+    env.create_silo("runner");
+
+    let output = env.run_silo(&["exec", "runner", "sh", "-c", "exit 3"]);
+    TestEnv::assert_failure(&output);
+
+    let output = env.run_silo(&["last-run", "runner"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("command:  sh -c exit 3"), "got: {}", stdout);
+    assert!(
+        stdout.contains("status:   failed (exit 3)"),
+        "got: {}",
+        stdout
+    );
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(
+        stdout.contains("last run failed (exit 3)"),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_tracking_dir_relocates_tracking_data() {
+    let env = TestEnv::new();
+    let tracking = env.repo_dir.path().join(".git/silo-tracking");
+    env.append_config(&format!("tracking_dir = \"{}\"", tracking.display()));
+    env.create_silo("runner");
+
+    let output = env.run_silo(&["exec", "runner", "sh", "-c", "exit 3"]);
+    TestEnv::assert_failure(&output);
+
+    let repo_dir = env.silo_path("runner").parent().unwrap().to_path_buf();
+    let relocated = tracking.join(repo_dir.file_name().unwrap()).join("runner");
+    assert!(relocated.join("last-run").exists());
+    assert!(relocated.join("created").exists());
+    assert!(!repo_dir.join(".tracking").exists());
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(
+        stdout.contains("last run failed (exit 3)"),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_process_tracking_disabled_skips_pid_files() {
+    let env = TestEnv::builder()
+        .config("process_tracking = false")
+        .build();
+    env.create_silo("runner");
+
+    let output = env.run_silo(&["exec", "runner", "true"]);
+
+    TestEnv::assert_success(&output);
+    assert!(!env.tracking_path("runner").join("pids").exists());
+    // Other tracking data is still recorded
+    assert!(env.tracking_path("runner").join("last-run").exists());
+}
+
+#[test]
+fn test_last_run_records_successful_exec() {
+    let env = TestEnv::new();
+    env.create_silo("runner");
+
+    TestEnv::assert_success(&env.run_silo(&["exec", "runner", "true"]));
+
+    let output = env.run_silo(&["last-run", "runner"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("status:   ok"));
+    assert!(!TestEnv::stdout(&env.run_silo(&["list"])).contains("last run failed"));
+}
+
+#[test]
+fn test_last_run_without_runs_fails() {
+    let env = TestEnv::new();
+    env.create_silo("idle");
+
+    let output = env.run_silo(&["last-run", "idle"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("No runs recorded"));
+}
+
+#[test]
+fn test_last_run_ignores_truncated_record() {
+    let env = TestEnv::new();
+    env.create_silo("idle");
+    let record = env.tracking_path("idle").join("last-run");
+    fs::create_dir_all(record.parent().unwrap()).unwrap();
+    fs::write(&record, "command=make\nexit_co").unwrap();
+
+    let output = env.run_silo(&["last-run", "idle"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("No runs recorded"));
+
+    // The next run replaces it
+    TestEnv::assert_success(&env.run_silo(&["exec", "idle", "true"]));
+    TestEnv::assert_success(&env.run_silo(&["last-run", "idle"]));
+}
+
+#[test]
+fn test_last_run_records_sandbox_run() {
+    let env = TestEnv::new();
+    env.create_silo("sandboxed");
+
+    let output = env.run_silo_with_fake_docker(&["sandbox", "claude", "sandboxed", "--", "-c"]);
+    TestEnv::assert_success(&output);
+
+    let output = env.run_silo(&["last-run", "sandboxed"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("command:  sandbox claude -c"));
+}
+
+// =============================================================================
+// VERIFY COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_verify_records_pass_and_fail() {
+    let env = TestEnv::new();
+    env.append_config(r#"verify_command = ["test", "-f", "ok.txt"]"#);
+    env.create_silo("passing");
+    env.create_silo("failing");
+    env.create_uncommitted_file("passing", "ok.txt", "");
+
+    let output = env.run_silo(&["verify", "passing"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Verification passed"));
+
+    let output = env.run_silo(&["verify", "failing"]);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("Verification failed"));
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    let passing = stdout.lines().find(|l| l.contains("passing")).unwrap();
+    let failing = stdout.lines().find(|l| l.contains("failing")).unwrap();
+    assert!(passing.ends_with("tests passed"), "got: {}", passing);
+    assert!(failing.ends_with("tests failed"), "got: {}", failing);
+
+    let verify_file = env
+        .silo_path("failing")
+        .parent()
+        .unwrap()
+        .join(".tracking/failing/verify");
+    let recorded = fs::read_to_string(verify_file).unwrap();
+    assert!(recorded.contains("passed=false"));
+    assert!(recorded.contains("exit_code=1"));
+}
+
+#[test]
+fn test_verify_requires_config() {
+    let env = TestEnv::new();
+    env.create_silo("unverified");
+
+    let output = env.run_silo(&["verify", "unverified"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("No verify_command configured"));
+}
+
+#[test]
+fn test_test_matrix_verifies_each_silo() {
+    let env = TestEnv::new();
+    env.append_config(r#"verify_command = ["sh", "-c", "echo checking; test -f ok.txt"]"#);
+    env.create_silos(&["good", "bad"]);
+    env.create_uncommitted_file("good", "ok.txt", "");
+
+    let output = env.run_silo(&["test-matrix", "--jobs", "2"]);
+    TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("1 of 2 silos failed verification"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+    let stdout = TestEnv::stdout(&output);
+    // Captured in logs rather than printed
+    assert!(!stdout.contains("checking"), "got: {}", stdout);
+    let bad = stdout.lines().find(|l| l.starts_with("bad")).unwrap();
+    assert!(bad.contains("log: "), "got: {}", bad);
+    let log = fs::read_to_string(env.tracking_path("bad").join("verify.log")).unwrap();
+    assert_eq!(log, "checking\n");
+
+    let output = env.run_silo(&["test-matrix", "--json", "good"]);
+    TestEnv::assert_success(&output);
+    let results: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert_eq!(results[0]["silo"], "good");
+    assert_eq!(results[0]["passed"], true);
+    assert_eq!(results.as_array().unwrap().len(), 1);
+
+    // Recorded as by `silo verify`
+    let recorded = fs::read_to_string(env.tracking_path("bad").join("verify")).unwrap();
+    assert!(recorded.contains("passed=false"));
+}
+
+#[test]
+fn test_list_json_includes_test_result() {
+    let env = TestEnv::new();
+    env.append_config(r#"verify_command = ["true"]"#);
+    env.create_silo("verified");
+    env.create_silo("unverified");
+    TestEnv::assert_success(&env.run_silo(&["verify", "verified"]));
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list", "--json"]));
+    let silos: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let by_name = |name: &str| {
+        silos
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["name"] == name)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(by_name("verified")["tests_passed"], true);
+    assert!(by_name("unverified")["tests_passed"].is_null());
+}
+
+#[test]
+fn test_list_shows_locked_worktrees() {
+    let env = TestEnv::new();
+    env.create_silo("locked");
+    env.create_silo("unlocked");
+    let path = env.silo_path("locked");
+    let output = env.git(&[
+        "worktree",
+        "lock",
+        "--reason",
+        "agent at work",
+        path.to_str().unwrap(),
+    ]);
+    TestEnv::assert_success(&output);
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    let line = |name: &str| {
+        stdout
+            .lines()
+            .find(|l| l.contains(&format!(" {} ", name)))
+            .unwrap()
+            .to_string()
+    };
+    assert!(
+        line("locked").ends_with(", locked (agent at work)"),
+        "got: {}",
+        stdout
+    );
+    assert!(!line("unlocked").contains(", locked"), "got: {}", stdout);
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list", "--json"]));
+    let silos: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let locked = |name: &str| {
+        silos
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["name"] == name)
+            .unwrap()["locked"]
+            .clone()
+    };
+    assert_eq!(locked("locked"), "agent at work");
+    assert!(locked("unlocked").is_null());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_list_names_running_agents() {
+    let env = TestEnv::new();
+    env.create_silo("agent");
+    env.create_silo("build");
+    env.fake_command("docker", "exec sleep 30");
+
+    let _agent = env.start_tracked("agent", &["sandbox", "claude", "agent"]);
+    let _build = env.start_process("build", &["sleep", "30"]);
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    let line = |name: &str| {
+        stdout
+            .lines()
+            .find(|l| l.contains(&format!(" {} ", name)))
+            .unwrap()
+            .to_string()
+    };
+    assert!(line("agent").ends_with(", claude"), "got: {}", stdout);
+    assert!(line("build").ends_with(", 1 process"), "got: {}", stdout);
+}
+
+#[test]
+fn test_list_json_includes_creation_metadata() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list", "--json"]));
+    let silos: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let created = &silos[0]["created"];
+
+    assert!(created["at"].as_u64().unwrap() > 0);
+    assert!(created["by"].is_string());
+    assert!(
+        created["command"]
+            .as_str()
+            .unwrap()
+            .ends_with("new feature"),
+        "got: {}",
+        created
+    );
+}
+
+// =============================================================================
+// PRUNE COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_prune_removes_clean_silos() {
+    let env = TestEnv::new();
+    env.create_silos(&["clean1", "clean2"]);
+
+    let output = env.run_silo(&["prune", "--force"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("clean1");
+    env.assert_silo_not_exists("clean2");
+    // Same wording as `rm`
+    assert!(TestEnv::stdout(&output).contains("Removed silo: clean1"));
+}
+
+#[test]
+fn test_prune_preserves_dirty_silos() {
+    let env = TestEnv::new();
+    env.create_silos(&["clean", "dirty"]);
+    env.create_uncommitted_file("dirty", "file.txt", "content");
+
+    let output = env.run_silo(&["prune", "--force"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("clean");
+    env.assert_silo_exists("dirty");
+}
+
+#[test]
+fn test_prune_json_plan_is_carried_out_with_plan_file() {
+    let env = TestEnv::new();
+    env.create_silos(&["done", "ahead"]);
+    env.create_commit_in_silo("ahead", "a.txt", "a", "Unmerged work");
+
+    let output = env.run_silo(&["prune", "--json"]);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("needs --dry-run"));
+
+    let output = env.run_silo(&["prune", "--dry-run", "--json"]);
+    TestEnv::assert_success(&output);
+    let plan: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert_eq!(plan["command"], "prune");
+    assert_eq!(plan["remove"][0]["name"], "done");
+    assert_eq!(plan["remove"][0]["branch_action"], "delete");
+    assert_eq!(plan["blocked"][0]["name"], "ahead");
+    assert_eq!(plan["blocked"][0]["blockers"][0], "1 unmerged commit");
+    env.assert_silo_exists("done");
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let plan_file = dir.path().join("plan.json");
+    fs::write(&plan_file, TestEnv::stdout(&output)).unwrap();
+    let plan_arg = plan_file.to_str().unwrap();
+
+    // An edited plan isn't carried out
+    let mut edited = plan.clone();
+    edited["remove"][0]["head"] = "0000000".into();
+    let edited_file = dir.path().join("edited.json");
+    fs::write(&edited_file, edited.to_string()).unwrap();
+    let output = env.run_silo(&["prune", "--plan-file", edited_file.to_str().unwrap()]);
+    TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("was edited"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+    env.assert_silo_exists("done");
+
+    // No prompt: the approval of the plan is the confirmation
+    let output = env.run_silo(&["prune", "--plan-file", plan_arg]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Removed silo: done"));
+    env.assert_silo_not_exists("done");
+    env.assert_silo_exists("ahead");
+
+    let output = env.run_silo(&["rm", "--plan-file", plan_arg]);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("plan for `silo prune`"));
+}
+
+#[test]
+fn test_rm_plan_file_refuses_silo_changed_since_planning() {
+    let env = TestEnv::new();
+    env.create_silo("moving");
+
+    let output = env.run_silo(&["rm", "moving", "--dry-run", "--force", "--json"]);
+    TestEnv::assert_success(&output);
+    let dir = tempfile::TempDir::new().unwrap();
+    let plan_file = dir.path().join("plan.json");
+    fs::write(&plan_file, TestEnv::stdout(&output)).unwrap();
+
+    // A commit made after the plan was approved
+    env.create_commit_in_silo("moving", "late.txt", "late", "Late work");
+
+    let output = env.run_silo(&["rm", "--plan-file", plan_file.to_str().unwrap()]);
+    TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("changed since it was made"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+    env.assert_silo_exists("moving");
+}
+
+#[test]
+fn test_prune_skips_silos_with_operation_in_progress() {
+    let env = TestEnv::new();
+    env.create_silos(&["clean", "bisecting"]);
+    TestEnv::assert_success(&env.git_in_silo("bisecting", &["bisect", "start"]));
+
+    let output = env.run_silo(&["prune"]);
+
+    TestEnv::assert_success(&output);
+    let stderr = TestEnv::stderr(&output);
+    assert!(stderr.contains("bisecting (BISECTING)"), "got: {}", stderr);
+    env.assert_silo_exists("bisecting");
+}
+
+#[test]
+fn test_prune_with_force_removes_silos_with_commits() {
+    let env = TestEnv::new();
+    env.create_silos(&["clean", "has-commits"]);
+    env.create_commit_in_silo("has-commits", "new.txt", "content", "Add new file");
+
+    // With --force, even silos with unmerged commits are removed
+    let output = env.run_silo(&["prune", "--force"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("clean");
+    env.assert_silo_not_exists("has-commits");
+}
+
+#[test]
+fn test_prune_force_skips_silos_being_created() {
+    let env = TestEnv::new();
+    env.create_silos(&["clean", "starting"]);
+    let repo_dir = env.silo_path("starting").parent().unwrap().to_path_buf();
+    mark_being_created(&repo_dir.join(".tracking/starting"));
+
+    let output = env.run_silo(&["prune", "--all", "--force"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("clean");
+    env.assert_silo_exists("starting");
+    let stderr = TestEnv::stderr(&output);
+    assert!(stderr.contains("(being created)"), "got: {}", stderr);
+}
+
+#[test]
+fn test_prune_skips_locked_silos_unless_forced_twice() {
+    let env = TestEnv::new();
+    env.create_silos(&["clean", "locked"]);
+    let path = env.silo_path("locked");
+    let path_arg = path.to_str().unwrap();
+    env.git(&["worktree", "lock", "--reason", "on a USB drive", path_arg]);
+
+    let output = env.run_silo(&["prune", "--all", "--force"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("clean");
+    env.assert_silo_exists("locked");
+    let stderr = TestEnv::stderr(&output);
+    assert!(stderr.contains("locked (locked)"), "got: {}", stderr);
+
+    TestEnv::assert_success(&env.run_silo(&["prune", "--all", "-ff"]));
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_rm_refuses_locked_silo_unless_forced_twice() {
+    let env = TestEnv::new();
+    env.create_silo("locked");
+    env.git(&[
+        "worktree",
+        "lock",
+        env.silo_path("locked").to_str().unwrap(),
+    ]);
+
+    let output = env.run_silo(&["rm", "locked", "--force"]);
+
+    TestEnv::assert_failure(&output);
+    let stderr = TestEnv::stderr(&output);
+    assert!(
+        stderr.contains("Locked with `git worktree lock`"),
+        "got: {}",
+        stderr
+    );
+    env.assert_silo_exists("locked");
+
+    TestEnv::assert_success(&env.run_silo(&["rm", "locked", "--force", "--force"]));
+    env.assert_silo_not_exists("locked");
+}
+
+#[test]
+fn test_prune_skips_silos_with_unmerged_commits() {
+    let env = TestEnv::new();
+    env.create_silos(&["clean", "has-commits"]);
+    env.create_commit_in_silo("has-commits", "new.txt", "content", "Add new file");
+
+    // Without --force, silos with unmerged commits should be blocked
+    // Since we're non-interactive, it will abort asking for confirmation
+    let output = env.run_silo(&["prune"]);
+
+    TestEnv::assert_success(&output);
+    // "has-commits" should be skipped and reported as blocked
+    let stderr = TestEnv::stderr(&output);
+    assert!(
+        stderr.contains("Skipping") || stderr.contains("blocked"),
+        "Should report skipped silos"
+    );
+    // Both should still exist since confirmation was denied
+    env.assert_silo_exists("clean");
+    env.assert_silo_exists("has-commits");
+}
+
+#[test]
+fn test_prune_older_than_only_removes_old_silos() {
+    let env = TestEnv::new();
+    env.create_silos(&["old", "recent", "unknown"]);
+    let created = env.tracking_path("old").join("created");
+    std::fs::write(&created, "at=1\ncommand=silo new old\n").unwrap();
+    std::fs::remove_file(env.tracking_path("unknown").join("created")).unwrap();
+
+    let output = env.run_silo(&["prune", "--force", "--older-than", "7d"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("old");
+    env.assert_silo_exists("recent");
+    // Without a recorded creation time the age is unknown, so it's kept
+    env.assert_silo_exists("unknown");
+}
+
+#[test]
+fn test_prune_older_than_rejects_invalid_age() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["prune", "--older-than", "soon"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("Invalid age 'soon'"));
+}
+
+#[test]
+fn test_prune_dry_run() {
+    let env = TestEnv::new();
+    env.create_silo("clean");
+
+    let output = env.run_silo(&["prune", "--dry-run"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_exists("clean"); // Should still exist
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Would remove"));
+}
+
+#[test]
+fn test_prune_empty_repo() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["prune", "--force"]);
+
+    TestEnv::assert_success(&output);
+}
+
+#[test]
+fn test_prune_all() {
+    let env = TestEnv::new();
+    env.create_silo("clean");
+
+    let output = env.run_silo(&["prune", "--all", "--force"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_not_exists("clean");
+}
+
+// =============================================================================
+// REBASE COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_rebase_success() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    // Create a commit in the silo
+    env.create_commit_in_silo("feature", "feature.txt", "feature content", "Add feature");
+
+    // Create a commit in main that doesn't conflict
+    env.create_commit("main.txt", "main content", "Add to main");
+
+    let output = env.run_silo(&["rebase", "feature"]);
+
+    TestEnv::assert_success(&output);
+}
+
+#[test]
+fn test_diff_between_silos() {
+    let env = TestEnv::new();
+    env.create_silos(&["agent-1", "agent-2", "same"]);
+    env.create_commit_in_silo("agent-1", "answer.txt", "one\n", "First try");
+    env.create_commit_in_silo("agent-2", "answer.txt", "two\n", "Second try");
+    env.create_uncommitted_file("agent-2", "notes.txt", "wip\n");
+
+    let output = env.run_silo(&["diff", "--between", "agent-1", "agent-2"]);
+    TestEnv::assert_success(&output);
+    let diff = TestEnv::stdout(&output);
+    assert!(diff.contains("-one\n+two"), "got: {}", diff);
+    assert!(!diff.contains("notes.txt"), "got: {}", diff);
+
+    let output = env.run_silo(&["diff", "--between", "agent-1", "agent-2", "--uncommitted"]);
+    TestEnv::assert_success(&output);
+    let diff = TestEnv::stdout(&output);
+    assert!(diff.contains("+wip"), "got: {}", diff);
+    // The silo's own index is left alone
+    let status = env.git_in_silo("agent-2", &["status", "--porcelain"]);
+    assert_eq!(TestEnv::stdout(&status), "?? notes.txt\n");
+
+    let output = env.run_silo(&["diff", "--between", "agent-1", "agent-2", "--stat"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("answer.txt | 2 +-"));
+
+    let output = env.run_silo(&["diff", "--between", "same", "same"]);
+    TestEnv::assert_success(&output);
+    assert_eq!(TestEnv::stdout(&output), "");
+    assert!(TestEnv::stderr(&output).contains("No differences"));
+}
+
+#[test]
+fn test_rebase_refused_by_no_rebase_policy() {
+    let env = TestEnv::new();
+    env.create_silo("signed");
+    env.create_commit_in_silo("signed", "feature.txt", "content", "Feature");
+    env.create_commit("main.txt", "content", "Main");
+    let head = |env: &TestEnv| TestEnv::stdout(&env.git_in_silo("signed", &["rev-parse", "HEAD"]));
+    let before = head(&env);
+
+    TestEnv::assert_success(&env.run_silo(&["policy", "signed", "--no-rebase"]));
+    let output = env.run_silo(&["policy", "signed"]);
+    assert!(TestEnv::stdout(&output).contains("rebase: refused"));
+
+    // Neither --force nor --update-refs (stacked branches) gets around it
+    for args in [
+        &["rebase", "signed"][..],
+        &["--force", "rebase", "signed"],
+        &["rebase", "--update-refs", "signed"],
+    ] {
+        let output = env.run_silo(args);
+        TestEnv::assert_failure(&output);
+        assert!(
+            TestEnv::stderr(&output).contains("must keep its exact history"),
+            "got: {}",
+            TestEnv::stderr(&output)
+        );
+    }
+    assert_eq!(head(&env), before);
+
+    TestEnv::assert_success(&env.run_silo(&["policy", "signed", "--allow-rebase"]));
+    TestEnv::assert_success(&env.run_silo(&["rebase", "signed"]));
+    assert_ne!(head(&env), before);
+}
+
+#[test]
+fn test_rebase_nonexistent_fails() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["rebase", "nonexistent"]);
+
+    TestEnv::assert_failure(&output);
+}
+
+#[test]
+fn test_rebase_dry_run() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "feature.txt", "content", "Feature");
+    env.create_commit("main.txt", "content", "Main");
+
+    let output = env.run_silo(&["rebase", "feature", "--dry-run"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Would rebase"));
+    assert!(stdout.contains("No conflicts expected"));
+}
+
+#[test]
+fn test_rebase_update_refs_moves_stacked_branches() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "part1.txt", "part 1", "Part 1");
+    TestEnv::assert_success(&env.git_in_silo("feature", &["branch", "feature-part1"]));
+    env.create_commit_in_silo("feature", "part2.txt", "part 2", "Part 2");
+    env.create_commit("main.txt", "main", "Main");
+
+    let output = env.run_silo(&["rebase", "feature", "--update-refs"]);
+
+    TestEnv::assert_success(&output);
+    // The stacked branch now sits on top of main, below the rebased tip
+    let main = TestEnv::stdout(&env.git(&["rev-parse", "HEAD"]));
+    let part1_parent =
+        TestEnv::stdout(&env.git_in_silo("feature", &["rev-parse", "feature-part1^"]));
+    assert_eq!(part1_parent.trim(), main.trim());
+    TestEnv::assert_success(&env.git_in_silo(
+        "feature",
+        &["merge-base", "--is-ancestor", "feature-part1", "feature"],
+    ));
+}
+
+#[test]
+fn test_rebase_dry_run_shows_passthrough_options() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    let output = env.run_silo(&[
+        "rebase",
+        "feature",
+        "--update-refs",
+        "--rebase-merges",
+        "--dry-run",
+    ]);
+
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("(--update-refs --rebase-merges)"));
+}
+
+#[test]
+fn test_rebase_dry_run_lists_expected_conflicts() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "shared.txt", "feature", "Feature");
+    env.create_commit("shared.txt", "main", "Main");
+
+    let output = env.run_silo(&["rebase", "feature", "--dry-run"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Conflicts expected in 1 file:"));
+    assert!(stdout.contains("- shared.txt"));
+}
+
+#[test]
+fn test_rebase_with_uncommitted_changes_fails() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "feature.txt", "content", "Feature");
+    env.create_commit("main.txt", "content", "Main");
+    std::fs::write(env.silo_path("feature").join("feature.txt"), "edited").unwrap();
+
+    let output = env.run_silo(&["rebase", "feature"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("Commit or stash them before rebasing"));
+}
+
+#[test]
+fn test_rebase_with_operation_in_progress_fails() {
+    let env = TestEnv::new();
+    env.create_silo("bisecting");
+    TestEnv::assert_success(&env.git_in_silo("bisecting", &["bisect", "start"]));
+
+    let output = env.run_silo(&["rebase", "bisecting"]);
+
+    TestEnv::assert_failure(&output);
+    let stderr = TestEnv::stderr(&output);
+    assert!(
+        stderr.contains("has a bisect in progress") && stderr.contains("git bisect reset"),
+        "got: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_rebase_with_expected_conflicts_aborts_without_force() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "shared.txt", "feature", "Feature");
+    env.create_commit("shared.txt", "main", "Main");
+
+    // Not a terminal, so the confirmation prompt is declined
+    let output = env.run_silo(&["rebase", "feature"]);
+
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Aborted."));
+    let status = env.git_in_silo("feature", &["status"]);
+    assert!(!TestEnv::stdout(&status).contains("rebase in progress"));
+}
+
+// =============================================================================
+// MERGE COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_merge_success() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    // Create a commit in the silo
+    env.create_commit_in_silo("feature", "feature.txt", "feature content", "Add feature");
+
+    let output = env.run_silo(&["merge", "feature"]);
+
+    TestEnv::assert_success(&output);
+
+    // Verify the file is now in the main repo
+    let feature_file = env.repo_dir.path().join("feature.txt");
+    assert!(feature_file.exists(), "Merged file should exist in main");
+}
+
+#[test]
+fn test_merge_after_merge_remove() {
+    let env = TestEnv::new();
+    env.append_config(r#"after_merge = "remove""#);
+    env.create_silo("done");
+    env.create_commit_in_silo("done", "done.txt", "done", "Add done");
+    env.create_silo("dirty");
+    env.create_commit_in_silo("dirty", "dirty.txt", "dirty", "Add dirty");
+    fs::write(env.silo_path("dirty").join("wip.txt"), "wip").unwrap();
+
+    let output = env.run_silo(&["merge", "done"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Removed silo: done"));
+    env.assert_silo_not_exists("done");
+    assert!(!local_branch_exists(&env, "done"));
+
+    // Uncommitted changes weren't merged, so the silo stays
+    let output = env.run_silo(&["merge", "dirty"]);
+    TestEnv::assert_success(&output);
+    assert!(env.repo_dir.path().join("dirty.txt").exists());
+    assert!(env.silo_path("dirty").join("wip.txt").exists());
+    assert!(
+        TestEnv::stderr(&output).contains("Kept silo 'dirty' (after_merge = \"remove\")"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+}
+
+#[test]
+fn test_merge_after_merge_reset() {
+    let env = TestEnv::new();
+    env.append_config(r#"after_merge = "reset""#);
+    env.create_silo("reused");
+    env.create_commit_in_silo("reused", "a.txt", "a", "Add a");
+
+    let output = env.run_silo(&["merge", "reused", "--dry-run"]);
+    assert!(TestEnv::stdout(&output).contains("Then would reset silo 'reused'"));
+
+    let output = env.run_silo(&["merge", "reused"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Reset silo 'reused'"));
+    let head = |output: std::process::Output| TestEnv::stdout(&output).trim().to_string();
+    assert_eq!(
+        head(env.git_in_silo("reused", &["rev-parse", "HEAD"])),
+        head(env.git(&["rev-parse", "HEAD"]))
+    );
+}
+
+#[test]
+fn test_merge_nonexistent_fails() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["merge", "nonexistent"]);
+
+    TestEnv::assert_failure(&output);
+}
+
+#[test]
+fn test_merge_dry_run() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "feature.txt", "content", "Feature");
+
+    let output = env.run_silo(&["merge", "feature", "--dry-run"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Would merge"));
+    assert!(stdout.contains("Fast-forward") && stdout.contains("by 1 commit"));
+
+    // File should not exist (dry run)
+    let feature_file = env.repo_dir.path().join("feature.txt");
+    assert!(!feature_file.exists());
+}
+
+#[test]
+fn test_merge_up_to_date_does_nothing() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    let output = env.run_silo(&["merge", "feature"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Nothing to merge"));
+    assert!(!stdout.contains("Merging"));
+}
+
+#[test]
+fn test_merge_with_expected_conflicts_aborts_without_force() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "shared.txt", "feature", "Feature");
+    env.create_commit("shared.txt", "main", "Main");
+
+    let output = env.run_silo(&["merge", "feature"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Conflicts expected in 1 file:"));
+    assert!(stdout.contains("Aborted."));
+    assert_eq!(
+        std::fs::read_to_string(env.repo_dir.path().join("shared.txt")).unwrap(),
+        "main"
+    );
+}
+
+#[test]
+fn test_merge_with_expected_conflicts_runs_with_force() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "shared.txt", "feature", "Feature");
+    env.create_commit("shared.txt", "main", "Main");
+
+    let output = env.run_silo(&["--force", "merge", "feature"]);
+
+    // git stops with the conflict that was predicted
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stdout(&output).contains("Merging 'feature'"));
+}
+
+#[test]
+fn test_merge_from_silo_fails_when_main_only() {
+    let env = TestEnv::new();
+    env.append_config("merge_from_main_only = true");
+    env.create_silo("feature");
+
+    // Try to run merge from inside the silo (should fail - must run from main)
+    let output = env.run_silo_in("feature", &["merge", "feature"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("merge_from_main_only"));
+}
+
+#[test]
+fn test_merge_from_silo_merges_into_main() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "feature.txt", "feature content", "Add feature");
+
+    let output = env.run_silo_in("feature", &["merge", "feature"]);
+
+    TestEnv::assert_success(&output);
+    let feature_file = env.repo_dir.path().join("feature.txt");
+    assert!(feature_file.exists(), "Merged file should exist in main");
+}
+
+#[test]
+fn test_merge_from_silo_fails_with_dirty_main() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "feature.txt", "feature content", "Add feature");
+    std::fs::write(env.repo_dir.path().join("README.md"), "local edit").unwrap();
+
+    let output = env.run_silo_in("feature", &["merge", "feature"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("uncommitted changes"));
+    assert!(!env.repo_dir.path().join("feature.txt").exists());
+}
+
+// =============================================================================
+// RESET COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_reset_success() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    // Create a commit in the silo
+    env.create_commit_in_silo("feature", "feature.txt", "feature content", "Add feature");
+
+    // Create a commit in main
+    env.create_commit("main.txt", "main content", "Update main");
+
+    // Reset the silo to main's HEAD
+    let output = env.run_silo(&["reset", "feature", "--force"]);
+
+    TestEnv::assert_success(&output);
+
+    // The silo should now have main.txt (from main's current commit)
+    let main_file = env.silo_path("feature").join("main.txt");
+    assert!(main_file.exists(), "Silo should have main.txt after reset");
+
+    // The feature.txt should be gone (it was only in the silo's commits)
+    let feature_file = env.silo_path("feature").join("feature.txt");
+    assert!(
+        !feature_file.exists(),
+        "feature.txt should be gone after reset"
+    );
+}
+
+#[test]
+fn test_reset_nonexistent_fails() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["reset", "nonexistent", "--force"]);
+
+    TestEnv::assert_failure(&output);
+}
+
+#[test]
+fn test_reset_dry_run() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "feature.txt", "content", "Feature");
+
+    let output = env.run_silo(&["reset", "feature", "--dry-run", "--force"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Would reset"));
+
+    // File should still exist (dry run)
+    let feature_file = env.silo_path("feature").join("feature.txt");
+    assert!(
+        feature_file.exists(),
+        "File should still exist after dry run"
+    );
+}
+
+#[test]
+fn test_reset_with_uncommitted_changes_requires_confirmation() {
+    let env = TestEnv::new();
+    env.create_silo("dirty-silo");
+    env.create_uncommitted_file("dirty-silo", "dirty.txt", "uncommitted");
+
+    // Without --force, should ask for confirmation (fails in non-interactive)
+    let output = env.run_silo(&["reset", "dirty-silo"]);
+    // In non-interactive mode, confirmation defaults to no
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("Aborted"),
+        "Should abort without confirmation"
+    );
+
+    // File should still exist
+    let dirty_file = env.silo_path("dirty-silo").join("dirty.txt");
+    assert!(dirty_file.exists(), "dirty.txt should still exist");
+}
+
+#[test]
+fn test_reset_with_force_discards_uncommitted_changes() {
+    let env = TestEnv::new();
+    env.create_silo("dirty-silo");
+    env.create_uncommitted_file("dirty-silo", "dirty.txt", "uncommitted");
+
+    // With --force, should reset without asking
+    let output = env.run_silo(&["reset", "dirty-silo", "--force"]);
+
+    TestEnv::assert_success(&output);
+
+    // dirty.txt should be gone
+    let dirty_file = env.silo_path("dirty-silo").join("dirty.txt");
+    assert!(
+        !dirty_file.exists(),
+        "dirty.txt should be removed after reset"
+    );
+}
+
+#[test]
+fn test_reset_in_progress_requires_confirmation_and_aborts_it() {
+    let env = TestEnv::new();
+    env.create_silo("bisecting");
+    TestEnv::assert_success(&env.git_in_silo("bisecting", &["bisect", "start"]));
+
+    let output = env.run_silo(&["reset", "bisecting"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Aborted"));
+    assert!(
+        TestEnv::stderr(&output).contains("BISECTING in progress"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+
+    let output = env.run_silo(&["reset", "bisecting", "--force"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(!stdout.contains("BISECTING"), "got: {}", stdout);
+}
+
+#[test]
+fn test_reset_with_unmerged_commits_requires_confirmation() {
+    let env = TestEnv::new();
+    env.create_silo("has-commits");
+    env.create_commit_in_silo("has-commits", "new.txt", "content", "Add new file");
+
+    // Without --force, should ask for confirmation (fails in non-interactive)
+    let output = env.run_silo(&["reset", "has-commits"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("Aborted"),
+        "Should abort without confirmation"
+    );
+
+    // File should still exist
+    let new_file = env.silo_path("has-commits").join("new.txt");
+    assert!(new_file.exists(), "new.txt should still exist");
+}
+
+#[test]
+fn test_reset_with_force_discards_unmerged_commits() {
+    let env = TestEnv::new();
+    env.create_silo("has-commits");
+    env.create_commit_in_silo("has-commits", "new.txt", "content", "Add new file");
+
+    // With --force, should reset without asking
+    let output = env.run_silo(&["reset", "has-commits", "--force"]);
+
+    TestEnv::assert_success(&output);
+
+    // new.txt should be gone
+    let new_file = env.silo_path("has-commits").join("new.txt");
+    assert!(!new_file.exists(), "new.txt should be removed after reset");
+}
+
+#[test]
+fn test_reset_quiet_flag() {
+    let env = TestEnv::new();
+    env.create_silo("quiet-reset");
+    env.create_commit("main.txt", "content", "Add main file");
+
+    let output = env.run_silo(&["reset", "quiet-reset", "--force", "--quiet"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    // Output should be minimal
+    assert!(
+        stdout.trim().is_empty(),
+        "Quiet reset should produce no stdout"
+    );
+}
+
+// =============================================================================
+// EDGE CASE TESTS
+// =============================================================================
+
+#[test]
+fn test_silo_name_with_slashes() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["new", "feature/sub-feature"]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_exists("feature/sub-feature");
+}
+
+#[test]
+fn test_resolve_and_complete_names_with_slashes() {
+    let env = TestEnv::new();
+    env.create_silos(&["feature/x", "x"]);
+    let repo = env
+        .repo_dir
+        .path()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+
+    for (input, silo) in [
+        ("feature/x", "feature/x"),
+        (":feature/x", "feature/x"),
+        ("x", "x"),
+    ] {
+        let output = env.run_silo(&["resolve", input]);
+        TestEnv::assert_success(&output);
+        assert_eq!(
+            TestEnv::stdout(&output).trim(),
+            env.silo_path(silo).display().to_string(),
+            "resolving {}",
+            input
+        );
+    }
+
+    // Outside the repository, completion quotes the slashed name so the
+    // candidate resolves as it was offered
+    let config = env.repo_dir.path().join(".silo.toml");
+    let outside = tempfile::TempDir::new().unwrap();
+    let run_outside = |args: &[&str]| {
+        let mut all = vec!["-c", config.to_str().unwrap()];
+        all.extend_from_slice(args);
+        env.silo_command(&all)
+            .current_dir(outside.path())
+            .output()
+            .unwrap()
+    };
+    let output = run_outside(&["shell", "complete-args", "--", "cd", ""]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains(&format!("{repo}\\:feature/x")),
+        "got: {}",
+        stdout
+    );
+
+    let output = run_outside(&["resolve", &format!("{repo}:feature/x")]);
+    TestEnv::assert_success(&output);
+    assert_eq!(
+        TestEnv::stdout(&output).trim(),
+        env.silo_path("feature/x").display().to_string()
+    );
+}
+
+#[test]
+fn test_namespace_prefixes_new_branches() {
+    let env = TestEnv::new();
+    env.append_config("namespace = \"alice/\"");
+    env.create_silo("fix");
+    // Naming the namespace doesn't add it twice
+    env.create_silo("alice/docs");
+
+    let output = env.git_in_silo("fix", &["branch", "--show-current"]);
+    assert_eq!(TestEnv::stdout(&output).trim(), "alice/fix");
+    let output = env.git_in_silo("docs", &["branch", "--show-current"]);
+    assert_eq!(TestEnv::stdout(&output).trim(), "alice/docs");
+
+    // Display names hide the namespace
+    let output = env.run_silo(&["list", "-q"]);
+    TestEnv::assert_success(&output);
+    let mut names: Vec<_> = TestEnv::stdout(&output)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    assert_eq!(names, ["docs", "fix"]);
+
+    // A silo made before the namespace was set isn't listed as mine
+    let shared = env.silo_path("fix").with_file_name("shared");
+    env.git(&["worktree", "add", "-b", "shared", &shared.to_string_lossy()]);
+    let output = env.run_silo(&["list", "-q"]);
+    assert!(TestEnv::stdout(&output).contains("shared"));
+
+    let output = env.run_silo(&["list", "--mine", "-q"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(!stdout.contains("shared"), "got: {}", stdout);
+    assert_eq!(stdout.lines().count(), 2, "got: {}", stdout);
+}
+
+#[test]
+fn test_list_mine_requires_namespace() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["list", "--mine"]);
+
+    assert!(!output.status.success());
+    assert!(TestEnv::stderr(&output).contains("--mine needs a namespace"));
+}
+
+#[test]
+fn test_quiet_flag_suppresses_output() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["new", "quiet-test", "--quiet"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    // Output should be minimal/empty
+    assert!(stdout.trim().is_empty() || stdout.len() < 50);
+}
+
+#[test]
+fn test_verbose_flag_logs_git_commands() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["list", "-v"]);
+
+    TestEnv::assert_success(&output);
+    // Log lines go to stdout
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("git worktree list"), "got: {}", stdout);
+}
+
+#[test]
+fn test_double_verbose_flag_traces_git_stderr() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["new", "traced", "-vv"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("TRACE") && stdout.contains("Preparing worktree"),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_timings_flag_reports_phases() {
+    let env = TestEnv::new();
+    env.create_silo("timed");
+
+    let output = env.run_silo(&["list", "--timings"]);
+
+    TestEnv::assert_success(&output);
+    let stderr = TestEnv::stderr(&output);
+    assert!(stderr.contains("timings:"), "got: {}", stderr);
+    assert!(stderr.contains("silo stats"), "got: {}", stderr);
+    assert!(stderr.contains("git ("), "got: {}", stderr);
+    assert!(!TestEnv::stdout(&output).contains("timings:"));
+}
+
+#[test]
+fn test_quiet_flag_wins_over_verbose() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["new", "quiet-verbose", "-q", "-v"]);
+
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stderr(&output).trim().is_empty());
+    assert!(TestEnv::stdout(&output).trim().is_empty());
+}
+
+// =============================================================================
+// SANDBOX COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_sandbox_mount_main_readonly_dry_run() {
+    let env = TestEnv::new();
+    env.create_silo("sandboxed");
+
+    let output = env.run_silo(&[
+        "sandbox",
+        "claude",
+        "sandboxed",
+        "--mount-silo",
+        "@main:/workspace-main:ro",
+        "--dry-run",
+    ]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let expected = format!("{}:/workspace-main:ro", env.repo_dir.path().display());
+    assert!(stdout.contains(&expected), "got: {}", stdout);
+}
+
+#[test]
+fn test_commit_signing_is_set_per_silo_and_mounted_in_sandbox() {
+    let env = TestEnv::new();
+    let key = env.silo_dir.path().join("signing-key");
+    fs::write(&key, "not a real key").unwrap();
+    env.append_config("commit_signing = \"enforce\"");
+    env.append_config("signing_format = \"ssh\"");
+    env.append_config(&format!("signing_key = \"{}\"", key.display()));
+    env.create_silo("signed");
+
+    let get = |args: &[&str]| {
+        TestEnv::stdout(&env.git_in_silo("signed", args))
+            .trim()
+            .to_string()
+    };
+    assert_eq!(
+        get(&["config", "--worktree", "--get", "commit.gpgSign"]),
+        "true"
+    );
+    assert_eq!(get(&["config", "--get", "gpg.format"]), "ssh");
+    assert_eq!(
+        get(&["config", "--get", "user.signingKey"]),
+        key.display().to_string()
+    );
+    // The main worktree keeps the user's own setting
+    let output = env.git(&["config", "--get", "commit.gpgSign"]);
+    assert_eq!(TestEnv::stdout(&output), "");
+
+    // The agent sees the key at the path the silo's config names
+    let output = env.run_silo(&["sandbox", "claude", "signed", "--dry-run"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let expected = format!("{}:{}:ro", key.display(), key.display());
+    assert!(stdout.contains(&expected), "got: {}", stdout);
+}
+
+#[test]
+fn test_commit_signing_disable_overrides_user_config() {
+    let env = TestEnv::new();
+    TestEnv::assert_success(&env.git(&["config", "commit.gpgSign", "true"]));
+    env.append_config("commit_signing = \"disable\"");
+    env.create_silo("unsigned");
+
+    // Commits in the silo succeed without a signing key
+    let output = env.git_in_silo("unsigned", &["commit", "--allow-empty", "-m", "Unsigned"]);
+    TestEnv::assert_success(&output);
+    let output = env.git_in_silo("unsigned", &["log", "-1", "--format=%G?"]);
+    assert_eq!(TestEnv::stdout(&output).trim(), "N");
+}
+
+#[test]
+fn test_status_caches_are_set_per_silo() {
+    let env = TestEnv::new();
+    env.append_config("fsmonitor = true\nuntracked_cache = true");
+    env.create_silo("large");
+
+    for key in ["core.fsmonitor", "core.untrackedCache"] {
+        let output = env.git_in_silo("large", &["config", "--worktree", "--get", key]);
+        assert_eq!(TestEnv::stdout(&output).trim(), "true", "{}", key);
+        // The main worktree keeps git's settings
+        let output = env.git(&["config", "--worktree", "--get", key]);
+        assert!(TestEnv::stdout(&output).trim().is_empty(), "{}", key);
+    }
+
+    // Status still sees untracked files
+    env.create_uncommitted_file("large", "new.txt", "new");
+    let output = env.run_silo(&["list", "--dirty", "-q"]);
+    assert_eq!(TestEnv::stdout(&output).trim(), "large");
+}
+
+#[test]
+fn test_sandbox_mount_rejects_writable() {
+    let env = TestEnv::new();
+    env.create_silo("sandboxed");
+
+    let output = env.run_silo(&[
+        "sandbox",
+        "claude",
+        "sandboxed",
+        "--mount-silo",
+        "@main:/workspace-main:rw",
+        "--dry-run",
+    ]);
+
+    TestEnv::assert_failure(&output);
+}
+
+#[test]
+fn test_sandbox_artifacts_lists_and_copies_scratch_files() {
+    let env = TestEnv::new();
+    env.create_silo("sandboxed");
+
+    let silo_path = env.silo_path("sandboxed");
+    let run_dir = silo_path
+        .parent()
+        .unwrap()
+        .join(".tracking/sandboxed/scratch/run-1");
+    std::fs::create_dir_all(run_dir.join("reports")).unwrap();
+    std::fs::write(run_dir.join("reports/summary.md"), "done").unwrap();
+
+    let output = env.run_silo(&["sandbox", "artifacts", "sandboxed"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("run-1/reports/summary.md"),
+        "got: {}",
+        stdout
+    );
+
+    let dest = env.repo_dir.path().join("artifacts-out");
+    let output = env.run_silo(&[
+        "sandbox",
+        "artifacts",
+        "sandboxed",
+        "--copy",
+        dest.to_str().unwrap(),
+    ]);
+    TestEnv::assert_success(&output);
+    assert!(dest.join("run-1/reports/summary.md").exists());
+}
+
+#[test]
+fn test_sandbox_transcript_shows_latest_session() {
+    let env = TestEnv::new();
+    env.create_silo("sandboxed");
+
+    let silo_path = env.silo_path("sandboxed");
+    let project_dir = silo_path
+        .parent()
+        .unwrap()
+        .join(".tracking/sandboxed/transcripts/-workspace");
+    std::fs::create_dir_all(&project_dir).unwrap();
+    std::fs::write(
+        project_dir.join("session.jsonl"),
+        r#"{"type":"user","message":{"role":"user","content":"add a test"}}"#,
+    )
+    .unwrap();
+
+    let output = env.run_silo(&["sandbox", "transcript", "sandboxed"]);
+    TestEnv::assert_success(&output);
+    assert_eq!(TestEnv::stdout(&output), "user: add a test\n");
+
+    let output = env.run_silo(&["sandbox", "transcript", "sandboxed", "--list"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("session.jsonl"));
+}
+
+#[test]
+fn test_sandbox_transcript_missing_fails() {
+    let env = TestEnv::new();
+    env.create_silo("sandboxed");
+
+    let output = env.run_silo(&["sandbox", "transcript", "sandboxed"]);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("No transcripts recorded"));
+}
+
+// =============================================================================
+// AGENT COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_agent_spawn_creates_named_silo_and_logs() {
+    let env = TestEnv::new();
+    env.create_silo("agent-1");
+
+    let output = env.run_silo_with_fake_docker(&["agent", "spawn", "--prompt", "fix the tests"]);
+
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Started agent in 'agent-2'"));
+    env.assert_silo_exists("agent-2");
+
+    let tracking = env
+        .silo_path("agent-2")
+        .parent()
+        .unwrap()
+        .join(".tracking/agent-2/agent");
+    assert_eq!(
+        fs::read_to_string(tracking.join("prompt.md")).unwrap(),
+        "fix the tests"
+    );
+    let log = TestEnv::wait_for_file(&tracking.join("agent.log"));
+    assert!(log.contains("docker sandbox run"), "got: {}", log);
+    assert!(log.contains("-p fix the tests"), "got: {}", log);
+}
+
+#[test]
+fn test_agent_spawn_verifies_after_run() {
+    let env = TestEnv::new();
+    env.append_config(r#"verify_command = ["true"]"#);
+
+    let output = env.run_silo_with_fake_docker(&["agent", "spawn", "--prompt", "x"]);
+    TestEnv::assert_success(&output);
+
+    let verify_file = env
+        .silo_path("agent-1")
+        .parent()
+        .unwrap()
+        .join(".tracking/agent-1/verify");
+    let recorded = TestEnv::wait_for_file(&verify_file);
+    assert!(recorded.contains("passed=true"), "got: {}", recorded);
+
+    let last_run = TestEnv::wait_for_file(&verify_file.with_file_name("last-run"));
+    assert!(
+        last_run.contains("command=sandbox claude -p x"),
+        "got: {}",
+        last_run
+    );
+}
+
+#[test]
+fn test_agent_spawn_prompt_file_dry_run() {
+    let env = TestEnv::new();
+    let prompt_file = env.repo_dir.path().join("prompt.md");
+    fs::write(&prompt_file, "write docs").unwrap();
+
+    let output = env.run_silo(&[
+        "agent",
+        "spawn",
+        "--name",
+        "docs",
+        "--prompt-file",
+        prompt_file.to_str().unwrap(),
+        "--dry-run",
+    ]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Would create branch: docs"));
+    assert!(stdout.contains("-p write docs"));
+    env.assert_silo_not_exists("docs");
+}
+
+#[test]
+fn test_agent_spawn_requires_prompt() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["agent", "spawn", "--dry-run"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("No prompt given"));
+}
+
+#[test]
+fn test_agent_matrix_spawns_count_silos() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo_with_fake_docker(&[
+        "agent",
+        "matrix",
+        "--count",
+        "2",
+        "--prompt",
+        "refactor the parser",
+    ]);
+
+    TestEnv::assert_success(&output);
+    env.assert_silo_exists("agent-1");
+    env.assert_silo_exists("agent-2");
+    env.assert_silo_not_exists("agent-3");
+    assert!(TestEnv::stdout(&output).contains("silo agent compare agent-1 agent-2"));
+}
+
+#[test]
+fn test_agent_matrix_prefix_dry_run() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&[
+        "agent",
+        "matrix",
+        "--count",
+        "2",
+        "--name",
+        "try",
+        "--prompt",
+        "x",
+        "--dry-run",
+    ]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Would create branch: try-1"));
+    assert!(stdout.contains("Would create branch: try-2"));
+    env.assert_silo_not_exists("try-1");
+}
+
+#[test]
+fn test_agent_compare_shows_agent_silos() {
+    let env = TestEnv::new();
+    env.create_silo("manual");
+
+    let output =
+        env.run_silo_with_fake_docker(&["agent", "matrix", "--count", "2", "--prompt", "x"]);
+    TestEnv::assert_success(&output);
+    env.create_commit_in_silo("agent-2", "better.txt", "a\nb\n", "Better");
+
+    let output = env.run_silo(&["agent", "compare"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("NAME"), "got: {}", stdout);
+    assert!(stdout.contains("agent-1"), "got: {}", stdout);
+    assert!(stdout.contains("+2 -0"), "got: {}", stdout);
+    assert!(!stdout.contains("manual"), "got: {}", stdout);
+
+    let output = env.run_silo(&["agent", "compare", "manual", "agent-1"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("manual"));
+}
+
+#[test]
+fn test_agent_review_summarizes_changes() {
+    let env = TestEnv::new();
+    env.create_silo("reviewed");
+    env.create_commit_in_silo("reviewed", "feature.txt", "new\n", "Add feature");
+    env.create_uncommitted_file("reviewed", "scratch.txt", "wip");
+
+    let output = env.run_silo(&["agent", "review", "reviewed"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Commits since"), "got: {}", stdout);
+    assert!(stdout.contains("Add feature"), "got: {}", stdout);
+    assert!(stdout.contains("feature.txt |"), "got: {}", stdout);
+    assert!(stdout.contains("Uncommitted files (1):"), "got: {}", stdout);
+    assert!(stdout.contains("scratch.txt"), "got: {}", stdout);
+}
+
+#[test]
+fn test_agent_review_pipe_saves_to_notes() {
+    let env = TestEnv::new();
+    env.append_config(r#"review_command = ["sh", "-c", "grep -q feature.txt && echo LGTM"]"#);
+
+    env.create_silo("reviewed");
+    env.create_commit_in_silo("reviewed", "feature.txt", "new\n", "Add feature");
+
+    let output = env.run_silo(&["agent", "review", "reviewed", "--pipe"]);
+
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("LGTM"));
+    let notes = env
+        .silo_path("reviewed")
+        .parent()
+        .unwrap()
+        .join(".tracking/reviewed/notes.md");
+    let notes = fs::read_to_string(notes).unwrap();
+    assert!(notes.starts_with("## Review of "), "got: {}", notes);
+    assert!(notes.contains("LGTM"));
+}
+
+#[test]
+fn test_agent_review_pipe_requires_config() {
+    let env = TestEnv::new();
+    env.create_silo("reviewed");
+
+    let output = env.run_silo(&["agent", "review", "reviewed", "--pipe"]);
+
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("No review_command configured"));
+}
+
+// =============================================================================
+// SHELL COMPLETION TESTS
+// =============================================================================
+
+#[test]
+fn test_complete_args_groups_commands_and_silos() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    let output = env.run_silo(&["shell", "complete-args", "--", "cd", ""]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.lines().any(|l| l == "silo\tfeature"),
+        "got: {}",
+        stdout
+    );
+    assert!(!stdout.contains("command\t"), "got: {}", stdout);
+
+    let output = env.run_silo(&["shell", "complete-args", "--", ""]);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.lines().any(|l| l.starts_with("command\tcd:")),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_complete_describes_silo_status() {
+    let env = TestEnv::new();
+    env.create_silos(&["busy", "idle"]);
+    env.create_commit_in_silo("busy", "new.txt", "content", "Add new file");
+    env.create_uncommitted_file("busy", "wip.txt", "wip");
+
+    let output = env.run_silo(&["complete", "--for", "cd", "--format", "json"]);
+
+    TestEnv::assert_success(&output);
+    let json: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    let description = |name: &str| {
+        json.as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["value"] == name)
+            .map(|c| c["description"].clone())
+            .unwrap_or_else(|| panic!("{} not completed, got: {}", name, json))
+    };
+    assert_eq!(description("busy"), "+1 -0, dirty");
+    assert_eq!(description("idle"), serde_json::Value::Null);
+
+    // zsh shows it next to the name
+    let output = env.run_silo(&["shell", "complete-args", "--", "cd", ""]);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.lines().any(|l| l == "silo\tbusy:+1 -0, dirty"),
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_complete_args_groups_silos_by_repo_outside_repo() {
+    let env = TestEnv::new();
+    env.create_silos(&["feature", "fix"]);
+    let repo = env
+        .repo_dir
+        .path()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+
+    // The storage is configured in the repository, so pass its config along
+    let config = env.repo_dir.path().join(".silo.toml");
+    let outside = tempfile::TempDir::new().unwrap();
+    let output = env
+        .silo_command(&[
+            "-c",
+            config.to_str().unwrap(),
+            "shell",
+            "complete-args",
+            "--",
+            "cd",
+            "",
+        ])
+        .current_dir(outside.path())
+        .output()
+        .unwrap();
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            format!("silo/{repo}\t{repo}/feature"),
+            format!("silo/{repo}\t{repo}/fix"),
+        ],
+        "got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_complete_for_command_filters_by_word() {
+    let env = TestEnv::new();
+    env.create_silos(&["feature", "fix", "other"]);
+
+    let output = env.run_silo(&["complete", "--for", "rm", "--word", "f"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let mut values: Vec<&str> = stdout.lines().collect();
+    values.sort();
+    assert_eq!(values, vec!["feature", "fix"]);
+
+    let output = env.run_silo(&["complete", "--for", "sandbox", "--format", "json"]);
+    let json: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert!(
+        json.as_array()
+            .unwrap()
+            .iter()
+            .any(|c| c["kind"] == "command" && c["value"] == "claude"),
+        "got: {}",
+        json
+    );
+
+    let output = env.run_silo(&["complete", "--for", "nope"]);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("Unknown command"));
+}
+
+#[test]
+fn test_complete_args_formats() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    let args = |format| ["shell", "complete-args", "--format", format, "--", "cd", ""];
+
+    let stdout = TestEnv::stdout(&env.run_silo(&args("bash")));
+    assert!(stdout.lines().any(|l| l == "feature"), "got: {}", stdout);
+
+    let output = env.run_silo(&args("json"));
+    TestEnv::assert_success(&output);
+    let json: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert!(
+        json.as_array()
+            .unwrap()
+            .iter()
+            .any(|c| c["kind"] == "silo" && c["value"] == "feature"),
+        "got: {}",
+        json
+    );
+
+    TestEnv::assert_failure(&env.run_silo(&args("powershell")));
+}
+
+#[test]
+fn test_shell_init_bash_wraps_cd_and_completes() {
+    let env = TestEnv::new();
+    env.create_silos(&["first", "second"]);
+    let script = r#"
+eval "$("$SILO" shell init bash)"
+silo cd first >/dev/null && pwd
+silo cd second >/dev/null && silo cd - >/dev/null && pwd
+COMP_LINE="silo cd sec"; COMP_POINT=${#COMP_LINE}; _silo
+echo "completed: ${COMPREPLY[*]}"
+"#;
+
+    let output = Command::new("bash")
+        .args(["--norc", "--noprofile", "-c", script])
+        .env("SILO", env.silo_bin())
+        .env_remove("SILO_DIRECTIVE_FILE")
+        .current_dir(env.repo_dir.path())
+        .output()
+        .expect("Failed to run bash");
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "got: {}", stdout);
+    assert!(lines[0].ends_with("/first"), "got: {}", stdout);
+    assert!(lines[1].ends_with("/first"), "got: {}", stdout);
+    assert_eq!(lines[2], "completed: second");
+}
+
+#[test]
+fn test_shell_init_elvish_wraps_binary_and_completes() {
+    let env = TestEnv::new();
+    env.create_silos(&["first", "second"]);
+
+    let output = env.run_silo(&["shell", "init", "elvish"]);
+
+    TestEnv::assert_success(&output);
+    let script = TestEnv::stdout(&output);
+    let bin = env.silo_bin();
+    assert!(
+        script.contains(&format!("var __silo_bin = '{}'\n", bin.display())),
+        "got: {}",
+        script
+    );
+    assert!(
+        script.contains("edit:add-var silo~ $silo~"),
+        "got: {}",
+        script
+    );
+    assert!(
+        script.contains("shell complete-args --format bash -- (all $words[1..])"),
+        "got: {}",
+        script
+    );
+
+    // What the completer runs for `silo cd sec<Tab>`; elvish does the filtering
+    let output = env.run_silo(&[
+        "shell",
+        "complete-args",
+        "--format",
+        "bash",
+        "--",
+        "cd",
+        "sec",
+    ]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.lines().any(|l| l == "second"), "got: {}", stdout);
+}
+
+#[test]
+fn test_shell_init_binds_picker_widget_when_configured() {
+    let env = TestEnv::new();
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["shell", "init", "zsh"]));
+    assert!(!stdout.contains("bindkey"), "got: {}", stdout);
+
+    env.append_config(r#"shell_widget_key = "^G""#);
+    env.append_config(r#"shell_picker = ["sk", "--height=40%"]"#);
+    let output = env.run_silo(&["shell", "init", "zsh"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("bindkey '^G' __silo_pick"),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("| 'sk' '--height=40%')"), "got: {}", stdout);
+    assert!(!TestEnv::stderr(&output).contains("Unknown"));
+}
+
+#[test]
+fn test_complete_debug_reports_counts_and_phases() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    let output = env.run_silo(&["shell", "complete-debug", "--", "cd", ""]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Request:    silo cd "), "got: {}", stdout);
+    assert!(stdout.contains("(0 commands, 2 silos)"), "got: {}", stdout);
+    let stderr = TestEnv::stderr(&output);
+    assert!(stderr.contains("detect repo"), "got: {}", stderr);
+    assert!(stderr.contains("list worktrees"), "got: {}", stderr);
+
+    // Outside a repository, completion scans the storage directory instead
+    let outside = tempfile::TempDir::new().unwrap();
+    let output = env
+        .silo_command(&["shell", "complete-debug", "--", "cd", ""])
+        .current_dir(outside.path())
+        .output()
+        .unwrap();
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Repository: none"));
+    assert!(TestEnv::stderr(&output).contains("scan storage"));
+}
+
+// =============================================================================
+// DOCTOR COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_new_creates_storage_with_restrictive_mode() {
+    use std::os::unix::fs::PermissionsExt;
+    let env = TestEnv::new();
+    let storage = env.silo_dir.path().join("nested-storage");
+    fs::write(
+        env.repo_dir.path().join(".silo.toml"),
+        format!("worktree_dir = \"{}\"", storage.display()),
+    )
+    .unwrap();
+
+    TestEnv::assert_success(&env.run_silo(&["new", "perm-test"]));
+
+    let mode = fs::metadata(&storage).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode, 0o700);
+}
+
+#[test]
+fn test_doctor_reports_storage() {
+    let env = TestEnv::new();
+    env.create_silo("doctor-silo");
+
+    let output = env.run_silo(&["doctor"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Storage directory"), "got: {}", stdout);
+    assert!(stdout.contains("symlinks"), "got: {}", stdout);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_doctor_reports_filesystem_properties() {
+    let env = TestEnv::new();
+    env.create_silo("fs-silo");
+
+    let output = env.run_silo(&["doctor"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("case-sensitive"), "got: {}", stdout);
+    assert!(
+        stdout.contains("repository's filesystem"),
+        "got: {}",
+        stdout
+    );
+}
 
 // =============================================================================
-// MERGE COMMAND TESTS
+// INFO COMMAND TESTS
 // =============================================================================
 
 #[test]
-fn test_merge_success() {
+fn test_info_paths() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["info", "--paths"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        format!("storage={}", env.silo_dir.path().display())
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.starts_with("config=") && l.ends_with(".silo.toml")),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("registry="), "got: {}", stdout);
+}
+
+#[test]
+fn test_info_json_lists_main_worktree_config_in_silo() {
+    let env = TestEnv::new();
+    env.create_silo("info-silo");
+
+    let output = env.run_silo_in("info-silo", &["info", "--json"]);
+
+    TestEnv::assert_success(&output);
+    let info: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(info["storage_exists"], true);
+    let files = info["config_files"].as_array().unwrap();
+    // User config, main worktree config, then the silo's own
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[1]["exists"], true);
+    assert_eq!(files[2]["exists"], false);
+}
+
+#[test]
+fn test_info_with_config_file_lists_only_that_file() {
+    let env = TestEnv::new();
+    let config = env.repo_dir.path().join("custom.toml");
+    fs::write(&config, "").unwrap();
+
+    let output = env.run_silo(&["--config-file", config.to_str().unwrap(), "info"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("Config (--config-file):"),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("custom.toml"), "got: {}", stdout);
+    assert!(!stdout.contains(".silo.toml"), "got: {}", stdout);
+}
+
+#[test]
+fn test_info_name_shows_silo_details() {
     let env = TestEnv::new();
     env.create_silo("feature");
+    env.create_commit_in_silo("feature", "a.txt", "a\nb\n", "Add a");
+    fs::write(env.silo_path("feature").join("wip.txt"), "wip").unwrap();
+    let _sleep = env.start_process("feature", &["sleep", "30"]);
 
-    // Create a commit in the silo
-    env.create_commit_in_silo("feature", "feature.txt", "feature content", "Add feature");
+    let output = env.run_silo(&["info", "feature"]);
 
-    let output = env.run_silo(&["merge", "feature"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("Silo:          feature ("),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("Branch:        feature"), "got: {}", stdout);
+    assert!(
+        stdout.contains("1 ahead, 0 behind master, +2 -0 lines, not merged"),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("  wip.txt (untracked)"), "got: {}", stdout);
+    assert!(
+        stdout.contains("Processes:     1 process"),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("sleep 30"), "got: {}", stdout);
+}
+
+#[test]
+fn test_info_name_json() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+
+    let output = env.run_silo(&["info", "feature", "--json"]);
 
     TestEnv::assert_success(&output);
+    let info: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert_eq!(info["branch"], "feature");
+    assert_eq!(info["ahead"], 0);
+    assert_eq!(info["merged"], true);
+    assert_eq!(info["uncommitted"].as_array().unwrap().len(), 0);
+    assert_eq!(
+        info["path"].as_str().unwrap(),
+        env.silo_path("feature").to_str().unwrap()
+    );
 
-    // Verify the file is now in the main repo
-    let feature_file = env.repo_dir.path().join("feature.txt");
-    assert!(feature_file.exists(), "Merged file should exist in main");
+    TestEnv::assert_failure(&env.run_silo(&["info", "feature", "--paths"]));
+}
+
+#[test]
+fn test_info_name_porcelain_v1() {
+    let env = TestEnv::new();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "a.txt", "a\nb\n", "Add a");
+    fs::write(env.silo_path("feature").join("wip.txt"), "wip").unwrap();
+    let _sleep = env.start_process("feature", &["sleep", "30"]);
+
+    let output = env.run_silo(&["info", "feature", "--porcelain"]);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "# silo info porcelain v1");
+    assert_eq!(lines.len(), 4, "got: {}", stdout);
+
+    let silo: Vec<&str> = lines[1].split('\t').collect();
+    assert_eq!(silo.len(), 14, "got: {}", lines[1]);
+    assert_eq!(silo[0], "silo");
+    assert!(silo[1].ends_with(":feature"), "got: {}", silo[1]);
+    assert_eq!(silo[2], "feature");
+    assert_eq!(silo[4], "feature");
+    assert_eq!(silo[5], env.silo_path("feature").to_string_lossy());
+    assert_eq!(&silo[7..10], ["master", "1", "0"]);
+    assert_eq!(silo[10].len(), 40, "got: {}", silo[10]);
+    assert_eq!(&silo[11..14], ["2", "0", "false"]);
+
+    assert_eq!(lines[2], "file\tuntracked\twip.txt");
+    let process: Vec<&str> = lines[3].split('\t').collect();
+    assert_eq!(process[0], "process");
+    assert!(process[1].parse::<u32>().is_ok(), "got: {}", lines[3]);
+    assert_eq!(process[4], "sleep 30");
+
+    let output = env.run_silo(&["info", "feature", "--porcelain=v1"]);
+    assert_eq!(TestEnv::stdout(&output), stdout);
+    TestEnv::assert_failure(&env.run_silo(&["info", "--porcelain"]));
+    TestEnv::assert_failure(&env.run_silo(&["info", "feature", "--porcelain", "--json"]));
+}
+
+#[test]
+fn test_config_file_sets_storage_for_every_command() {
+    let env = TestEnv::new();
+    let alt = tempfile::TempDir::new().unwrap();
+    let storage = alt.path().join("storage");
+    let config = alt.path().join("alt.toml");
+    fs::write(
+        &config,
+        format!("worktree_dir = \"{}\"\n", storage.display()),
+    )
+    .unwrap();
+    let config = config.to_str().unwrap();
+
+    let output = env.run_silo(&["-c", config, "new", "alt-silo"]);
+    TestEnv::assert_success(&output);
+    assert!(fs::read_dir(&storage).unwrap().next().is_some());
+
+    let listed = |args: &[&str]| TestEnv::stdout(&env.run_silo(args)).contains("alt-silo");
+    assert!(listed(&["-c", config, "list"]));
+    assert!(!listed(&["list"]));
+    assert!(listed(&[
+        "-c",
+        config,
+        "shell",
+        "complete-args",
+        "--",
+        "cd",
+        ""
+    ]));
+
+    let output = env.run_silo(&["-c", config, "rm", "alt-silo", "--force"]);
+    TestEnv::assert_success(&output);
+    assert!(!listed(&["-c", config, "list"]));
+
+    // gc only cleans up the storage directory the config points at
+    let empty = storage.join("empty-1234abcd");
+    fs::create_dir_all(&empty).unwrap();
+    TestEnv::assert_success(&env.run_silo(&["gc", "--force"]));
+    assert!(empty.exists());
+    TestEnv::assert_success(&env.run_silo(&["-c", config, "gc", "--force"]));
+    assert!(!empty.exists());
+}
+
+#[test]
+fn test_new_prefetches_blobs_of_partial_clone() {
+    let env = TestEnv::builder()
+        .with_remote()
+        .file("src/lib.rs", "pub fn lib() {}\n")
+        .build();
+    let remote = env.remote_dir.as_ref().unwrap().path();
+    let git = |dir: &Path, args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    git(remote, &["config", "uploadpack.allowFilter", "true"]);
+    git(remote, &["config", "uploadpack.allowAnySHA1InWant", "true"]);
+
+    let parent = tempfile::TempDir::new().unwrap();
+    let url = format!("file://{}", remote.display());
+    git(
+        parent.path(),
+        &[
+            "clone",
+            "-q",
+            "--no-checkout",
+            "--filter=blob:none",
+            &url,
+            "clone",
+        ],
+    );
+    let clone = parent.path().join("clone");
+    fs::write(
+        clone.join(".silo.toml"),
+        format!(
+            "worktree_dir = \"{}\"\nprefetch_blobs = true\n",
+            env.silo_dir.path().display()
+        ),
+    )
+    .unwrap();
+    let run = |args: &[&str]| env.silo_command(args).current_dir(&clone).output().unwrap();
+
+    let stdout = TestEnv::stdout(&run(&["doctor"]));
+    assert!(
+        stdout.contains("partial clone (origin, blob:none)"),
+        "got: {}",
+        stdout
+    );
+
+    let output = run(&["new", "lazy"]);
+    TestEnv::assert_success(&output);
+    assert!(
+        TestEnv::stdout(&output).contains("Prefetching 2 missing blobs from origin"),
+        "got: {}",
+        TestEnv::stdout(&output)
+    );
+    let missing = git(
+        &clone,
+        &["rev-list", "--objects", "--missing=print", "HEAD"],
+    );
+    assert!(!missing.lines().any(|l| l.starts_with('?')), "{}", missing);
+}
+
+#[test]
+fn test_unreachable_remote_exits_with_transient_code() {
+    let env = TestEnv::builder()
+        .with_remote()
+        .file("src/lib.rs", "pub fn lib() {}\n")
+        .build();
+    let remote = env.remote_dir.as_ref().unwrap().path();
+    let git = |dir: &Path, args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    };
+    git(remote, &["config", "uploadpack.allowFilter", "true"]);
+
+    let parent = tempfile::TempDir::new().unwrap();
+    let url = format!("file://{}", remote.display());
+    git(
+        parent.path(),
+        &[
+            "clone",
+            "-q",
+            "--no-checkout",
+            "--filter=blob:none",
+            &url,
+            "clone",
+        ],
+    );
+    let clone = parent.path().join("clone");
+    fs::write(
+        clone.join(".silo.toml"),
+        format!(
+            "worktree_dir = \"{}\"\nprefetch_blobs = true\n",
+            env.silo_dir.path().display()
+        ),
+    )
+    .unwrap();
+    // The new silo's blobs have to be fetched, from nowhere
+    git(
+        &clone,
+        &["remote", "set-url", "origin", "http://127.0.0.1:1/repo.git"],
+    );
+
+    let output = env
+        .silo_command(&["--retries", "1", "new", "offline"])
+        .current_dir(&clone)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(75), "{:?}", output);
+    let stderr = TestEnv::stderr(&output);
+    // The prefetch is retried; the checkout, which fails after creating the
+    // branch, isn't
+    assert_eq!(
+        stderr.matches("(retry 1 of 1 in 1s)").count(),
+        1,
+        "got: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains("Failed to fetch missing objects"),
+        "got: {}",
+        stderr
+    );
+    assert!(stderr.contains("Transient failure:"), "got: {}", stderr);
+    assert!(!stderr.contains("already exists"), "got: {}", stderr);
+}
+
+#[test]
+fn test_new_registers_git_maintenance_when_configured() {
+    let env = TestEnv::new();
+    let global = env.silo_dir.path().join("gitconfig");
+    fs::write(&global, "").unwrap();
+    // Stand-ins for the scheduler `git maintenance start` installs into
+    env.fake_command("crontab", "[ \"$1\" = -l ] || cat > /dev/null");
+    env.fake_command("systemctl", "exit 1");
+    let path = format!(
+        "{}:{}",
+        env.fake_bin_dir().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let run = |args: &[&str]| {
+        env.run_silo_with_env(
+            args,
+            &[
+                ("PATH", &path),
+                ("GIT_CONFIG_GLOBAL", global.to_str().unwrap()),
+            ],
+        )
+    };
+
+    TestEnv::assert_success(&run(&["new", "unmaintained"]));
+    assert!(!fs::read_to_string(&global).unwrap().contains("maintenance"));
+
+    env.append_config("git_maintenance = true");
+    let output = run(&["new", "maintained"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Registered"));
+    let repo = env.repo_dir.path().canonicalize().unwrap();
+    assert!(
+        fs::read_to_string(&global)
+            .unwrap()
+            .contains(&format!("repo = {}", repo.display()))
+    );
+
+    // Already registered: nothing to do
+    let output = run(&["new", "again"]);
+    TestEnv::assert_success(&output);
+    assert!(!TestEnv::stdout(&output).contains("Registered"));
+}
+
+#[test]
+fn test_worktree_dirs_routes_repos_and_unions_roots() {
+    let env = TestEnv::new();
+    let alt = tempfile::TempDir::new().unwrap();
+    let fast = alt.path().join("fast");
+    let big = alt.path().join("big");
+    let repo_name = env.repo_dir.path().file_name().unwrap().to_str().unwrap();
+    let roots = format!(
+        "worktree_dirs = [\"{}\", \"{}\"]\n",
+        fast.display(),
+        big.display()
+    );
+    let unrouted = alt.path().join("unrouted.toml");
+    fs::write(&unrouted, &roots).unwrap();
+    let routed = alt.path().join("routed.toml");
+    fs::write(
+        &routed,
+        format!(
+            "{}[storage_routes]\n\"{}\" = \"{}\"\n",
+            roots,
+            repo_name,
+            big.display()
+        ),
+    )
+    .unwrap();
+    let unrouted = unrouted.to_str().unwrap();
+    let routed = routed.to_str().unwrap();
+
+    TestEnv::assert_success(&env.run_silo(&["-c", unrouted, "new", "on-fast"]));
+    TestEnv::assert_success(&env.run_silo(&["-c", routed, "new", "on-big"]));
+    let in_root = |root: &Path, name: &str| {
+        fs::read_dir(root)
+            .unwrap()
+            .any(|entry| entry.unwrap().path().join(name).is_dir())
+    };
+    assert!(in_root(&fast, "on-fast"));
+    assert!(in_root(&big, "on-big"));
+
+    // Every root is searched, whichever one a repository is routed to
+    let stdout = TestEnv::stdout(&env.run_silo(&["-c", unrouted, "list"]));
+    assert!(
+        stdout.contains("on-fast") && stdout.contains("on-big"),
+        "got: {}",
+        stdout
+    );
+
+    TestEnv::assert_success(&env.run_silo(&["-c", unrouted, "rm", "on-big", "--force"]));
+    assert!(!in_root(&big, "on-big"));
+
+    let empty = big.join("empty-1234abcd");
+    fs::create_dir_all(&empty).unwrap();
+    TestEnv::assert_success(&env.run_silo(&["-c", unrouted, "gc", "--force"]));
+    assert!(!empty.exists());
+}
+
+#[test]
+fn test_storage_route_must_name_a_root() {
+    let env = TestEnv::new();
+    let alt = tempfile::TempDir::new().unwrap();
+    let config = alt.path().join("bad.toml");
+    fs::write(
+        &config,
+        format!(
+            "worktree_dirs = [\"{}\"]\nstorage_routes = {{ repo = \"/elsewhere\" }}\n",
+            alt.path().join("fast").display()
+        ),
+    )
+    .unwrap();
+
+    let output = env.run_silo(&["-c", config.to_str().unwrap(), "list"]);
+
+    assert!(!output.status.success());
+    assert!(
+        TestEnv::stderr(&output).contains("not in worktree_dirs"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+}
+
+#[test]
+fn test_new_worktree_dir_stores_one_silo_elsewhere() {
+    let env = TestEnv::new();
+    let scratch = tempfile::TempDir::new().unwrap();
+    let scratch_dir = scratch.path().to_str().unwrap();
+
+    let output = env.run_silo(&["new", "huge", "--worktree-dir", scratch_dir]);
+
+    TestEnv::assert_success(&output);
+    let silo = fs::read_dir(scratch.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path().join("huge"))
+        .find(|path| path.is_dir())
+        .expect("silo should be under --worktree-dir");
+    env.create_silo("regular");
+    assert!(env.silo_path("regular").starts_with(env.silo_dir.path()));
+
+    // Later commands find it without the flag
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(stdout.contains("huge"), "got: {}", stdout);
+    TestEnv::assert_success(&env.run_silo(&["gc", "--force"]));
+    assert!(silo.exists());
+    TestEnv::assert_success(&env.run_silo(&["rm", "huge", "--force"]));
+    assert!(!silo.exists());
+}
+
+#[test]
+fn test_no_config_ignores_config_files() {
+    let env = TestEnv::new();
+    env.create_silo("configured");
+    let home = env.silo_dir.path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    let home_env = [("HOME", home.to_str().unwrap())];
+
+    let output = env.run_silo_with_env(&["--no-config", "info", "--paths"], &home_env);
+
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    let storage = home.join(".local/var/silo");
+    assert!(
+        stdout.starts_with(&format!("storage={}\n", storage.display())),
+        "got: {}",
+        stdout
+    );
+    assert!(!stdout.contains("config="), "got: {}", stdout);
+
+    // The silo lives in the storage directory the repo's config sets
+    let output = env.run_silo_with_env(&["--no-config", "list"], &home_env);
+    TestEnv::assert_success(&output);
+    assert!(!TestEnv::stdout(&output).contains("configured"));
+}
+
+#[test]
+fn test_no_config_conflicts_with_config_file() {
+    let env = TestEnv::new();
+
+    let output = env.run_silo(&["--no-config", "--config-file", "x.toml", "info"]);
+
+    TestEnv::assert_failure(&output);
+}
+
+// =============================================================================
+// EXTERNAL COMMAND TESTS
+// =============================================================================
+
+/// Create a worktree of the test repo outside the storage directory.
+fn add_outside_worktree(
+    env: &TestEnv,
+    outside: &tempfile::TempDir,
+    name: &str,
+) -> std::path::PathBuf {
+    let path = outside.path().join(name);
+    let output = env.git(&["worktree", "add", "-b", name, path.to_str().unwrap()]);
+    TestEnv::assert_success(&output);
+    path.canonicalize().unwrap()
+}
+
+#[test]
+fn test_external_worktree_is_addressable_but_not_removed() {
+    let env = TestEnv::new();
+    let outside = tempfile::TempDir::new().unwrap();
+    let path = add_outside_worktree(&env, &outside, "legacy");
+    let path_str = path.to_str().unwrap();
+
+    TestEnv::assert_success(&env.run_silo(&["external", "add", path_str]));
+
+    let stdout = TestEnv::stdout(&env.run_silo(&["list"]));
+    assert!(
+        stdout
+            .lines()
+            .any(|l| l.contains("legacy") && l.contains("external")),
+        "got: {}",
+        stdout
+    );
+    let output = env.run_silo(&["cd", "legacy"]);
+    assert_eq!(TestEnv::stdout(&output).trim(), path_str);
+    let output = env.run_silo(&["exec", "legacy", "pwd"]);
+    assert!(TestEnv::stdout(&output).contains(path_str));
+    // Nothing is written next to a worktree silo didn't create
+    assert!(!outside.path().join(".tracking").exists());
+
+    TestEnv::assert_success(&env.run_silo(&["prune", "--force"]));
+    assert!(path.exists());
+    let output = env.run_silo(&["rm", "legacy"]);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("External worktree"));
+    assert!(path.exists());
+
+    TestEnv::assert_success(&env.run_silo(&["external", "rm", path_str]));
+    assert!(path.exists());
+    assert!(!TestEnv::stdout(&env.run_silo(&["list"])).contains("legacy"));
+}
+
+#[test]
+fn test_external_add_rejects_non_worktrees() {
+    let env = TestEnv::new();
+    let outside = tempfile::TempDir::new().unwrap();
+    env.create_silo("taken");
+    // Same directory name as the silo, on another branch
+    let clash = outside.path().join("taken");
+    TestEnv::assert_success(&env.git(&["worktree", "add", "-b", "clash", clash.to_str().unwrap()]));
+
+    let repo = env.repo_dir.path().to_str().unwrap();
+    let output = env.run_silo(&["external", "add", repo]);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("not a linked git worktree"));
+
+    let silo = env.silo_path("taken");
+    let output = env.run_silo(&["external", "add", silo.to_str().unwrap()]);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("a silo already"));
+
+    let output = env.run_silo(&["external", "add", clash.to_str().unwrap()]);
+    TestEnv::assert_failure(&output);
+    assert!(TestEnv::stderr(&output).contains("already has a silo named 'taken'"));
+}
+
+// =============================================================================
+// GC COMMAND TESTS
+// =============================================================================
+
+#[test]
+fn test_gc_removes_orphaned_silos() {
+    let env = TestEnv::new();
+    let orphan_path = env.create_orphaned_silo("orphan-silo");
+
+    // Verify the orphan exists
+    assert!(orphan_path.exists(), "Orphan should exist before gc");
+
+    let output = env.run_silo(&["gc", "--force"]);
+
+    TestEnv::assert_success(&output);
+    assert!(!orphan_path.exists(), "Orphan should be removed after gc");
+}
+
+#[test]
+fn test_gc_removes_empty_directories() {
+    let env = TestEnv::new();
+    let empty_dir = env.create_empty_repo_dir("empty-repo-dir");
+
+    // Verify the empty dir exists
+    assert!(empty_dir.exists(), "Empty dir should exist before gc");
+
+    let output = env.run_silo(&["gc", "--force"]);
+
+    TestEnv::assert_success(&output);
+    assert!(!empty_dir.exists(), "Empty dir should be removed after gc");
+}
+
+#[test]
+fn test_gc_removes_stale_tracking_directories() {
+    let env = TestEnv::new();
+    env.create_silo("valid-silo");
+
+    let repo_dir = env.silo_path("valid-silo").parent().unwrap().to_path_buf();
+    let stale = repo_dir.join(".tracking/deleted-silo/scratch/run-1");
+    std::fs::create_dir_all(&stale).unwrap();
+    let live = repo_dir.join(".tracking/valid-silo/scratch/run-1");
+    std::fs::create_dir_all(&live).unwrap();
+
+    let output = env.run_silo(&["gc", "--force"]);
+
+    TestEnv::assert_success(&output);
+    assert!(!repo_dir.join(".tracking/deleted-silo").exists());
+    assert!(live.exists(), "Tracking data of live silos should be kept");
+    env.assert_silo_exists("valid-silo");
+}
+
+#[test]
+fn test_gc_removes_stale_relocated_tracking_directories() {
+    let env = TestEnv::new();
+    let tracking = env.repo_dir.path().join(".git/silo-tracking");
+    env.append_config(&format!("tracking_dir = \"{}\"", tracking.display()));
+    env.create_silo("valid-silo");
+
+    let repo_dir_name = env
+        .silo_path("valid-silo")
+        .parent()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_owned();
+    let stale = tracking.join(&repo_dir_name).join("deleted-silo/scratch");
+    std::fs::create_dir_all(&stale).unwrap();
+    let gone_repo = tracking.join("gone-repo-abc123/feature");
+    std::fs::create_dir_all(&gone_repo).unwrap();
+
+    let output = env.run_silo(&["gc", "--force"]);
+
+    TestEnv::assert_success(&output);
+    assert!(!stale.exists());
+    assert!(!gone_repo.exists());
+    assert!(tracking.join(&repo_dir_name).join("valid-silo").exists());
+}
+
+#[test]
+fn test_relocated_tracking_keeps_slashed_branch_names_whole() {
+    let env = TestEnv::new();
+    let tracking = env.repo_dir.path().join(".git/silo-tracking");
+    env.append_config(&format!("tracking_dir = \"{}\"", tracking.display()));
+    env.create_silo("feature/x");
+    TestEnv::assert_success(&env.run_silo(&["exec", "feature/x", "true"]));
+
+    let repo_dir_name = env
+        .silo_path("feature/x")
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .file_name()
+        .unwrap()
+        .to_owned();
+    let repo_tracking = tracking.join(&repo_dir_name);
+    assert!(repo_tracking.join("feature/x/last-run").exists());
+    let stale = repo_tracking.join("feature/gone/scratch");
+    std::fs::create_dir_all(&stale).unwrap();
+
+    let output = env.run_silo(&["gc", "--dry-run"]);
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("1 stale tracking directory"),
+        "got: {}",
+        stdout
+    );
+    assert!(!stdout.contains("feature/x"), "got: {}", stdout);
+
+    TestEnv::assert_success(&env.run_silo(&["gc", "--force"]));
+    assert!(!stale.exists());
+    assert!(repo_tracking.join("feature/x/last-run").exists());
+}
+
+/// Mark a silo as being created by a running process, like an unfinished
+/// `silo new` does. The test process itself is the running process.
+fn mark_being_created(tracking_dir: &std::path::Path) {
+    std::fs::create_dir_all(tracking_dir).unwrap();
+    std::fs::write(
+        tracking_dir.join("creating"),
+        format!("pid={}\n", std::process::id()),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_gc_keeps_silos_being_created() {
+    let env = TestEnv::new();
+    // A first silo whose parent directory exists but whose worktree doesn't
+    let empty_dir = env.create_empty_repo_dir("new-repo-dir");
+    mark_being_created(&empty_dir.join(".tracking/feature"));
+    // Tracking data written before the worktree
+    env.create_silo("valid-silo");
+    let repo_dir = env.silo_path("valid-silo").parent().unwrap().to_path_buf();
+    mark_being_created(&repo_dir.join(".tracking/starting"));
+    // Left behind by a `silo new` that died
+    let dead = repo_dir.join(".tracking/killed");
+    std::fs::create_dir_all(&dead).unwrap();
+    std::fs::write(dead.join("creating"), "pid=999999999\n").unwrap();
+
+    let output = env.run_silo(&["gc", "--force"]);
+
+    TestEnv::assert_success(&output);
+    assert!(empty_dir.join(".tracking/feature/creating").exists());
+    assert!(repo_dir.join(".tracking/starting/creating").exists());
+    assert!(!dead.exists());
+}
+
+#[test]
+fn test_gc_dry_run_does_not_remove() {
+    let env = TestEnv::new();
+    let orphan_path = env.create_orphaned_silo("orphan-silo");
+    let empty_dir = env.create_empty_repo_dir("empty-repo-dir");
+
+    let output = env.run_silo(&["gc", "--dry-run"]);
+
+    // Everything found is left in place
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("Would remove"));
+    assert!(
+        orphan_path.exists(),
+        "Orphan should still exist after dry-run"
+    );
+    assert!(
+        empty_dir.exists(),
+        "Empty dir should still exist after dry-run"
+    );
 }
 
 #[test]
-fn test_merge_nonexistent_fails() {
+fn test_gc_no_orphans_reports_nothing() {
     let env = TestEnv::new();
+    // Create a valid silo (not orphaned)
+    env.create_silo("valid-silo");
 
-    let output = env.run_silo(&["merge", "nonexistent"]);
+    let output = env.run_silo(&["gc"]);
 
-    TestEnv::assert_failure(&output);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("No orphaned silos or empty directories"));
+    env.assert_silo_exists("valid-silo");
 }
 
 #[test]
-fn test_merge_dry_run() {
+fn test_gc_without_terminal_keeps_items_unless_yes() {
     let env = TestEnv::new();
-    env.create_silo("feature");
-    env.create_commit_in_silo("feature", "feature.txt", "content", "Feature");
+    let orphan_path = env.create_orphaned_silo("orphan-silo");
 
-    let output = env.run_silo(&["merge", "feature", "--dry-run"]);
+    // Not a terminal, so the prompt is declined
+    let output = env.run_silo(&["gc"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(orphan_path.exists());
 
+    let output = env.run_silo(&["gc", "--yes"]);
     TestEnv::assert_success(&output);
-    let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("Would merge"));
-
-    // File should not exist (dry run)
-    let feature_file = env.repo_dir.path().join("feature.txt");
-    assert!(!feature_file.exists());
+    assert!(!orphan_path.exists());
 }
 
 #[test]
-fn test_merge_from_silo_fails() {
+fn test_gc_json_reports_found_and_removed() {
     let env = TestEnv::new();
-    env.create_silo("feature");
+    let orphan_path = env.create_orphaned_silo("orphan-silo");
 
-    // Try to run merge from inside the silo (should fail - must run from main)
-    let output = env.run_silo_in("feature", &["merge", "feature"]);
+    let output = env.run_silo(&["gc", "--dry-run", "--json"]);
+    assert_eq!(output.status.code(), Some(3));
+    let report: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    let orphan = &report["orphaned_silos"][0];
+    assert_eq!(orphan["path"], orphan_path.to_str().unwrap());
+    assert!(orphan["missing_main_worktree"].is_string());
+    assert_eq!(orphan["removed"], false);
 
-    TestEnv::assert_failure(&output);
+    let output = env.run_silo(&["gc", "--yes", "--json"]);
+    TestEnv::assert_success(&output);
+    let report: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    assert_eq!(report["orphaned_silos"][0]["removed"], true);
 }
 
-// =============================================================================
-// RESET COMMAND TESTS
-// =============================================================================
-
 #[test]
-fn test_reset_success() {
+fn test_orphan_branches_reports_and_deletes_merged() {
     let env = TestEnv::new();
-    env.create_silo("feature");
-
-    // Create a commit in the silo
-    env.create_commit_in_silo("feature", "feature.txt", "feature content", "Add feature");
-
-    // Create a commit in main
-    env.create_commit("main.txt", "main content", "Update main");
+    env.create_silos(&["active", "wip"]);
+    env.create_commit_in_silo("wip", "wip.txt", "wip", "Add wip");
+    // The silo is gone, its branch left behind
+    let wip_path = env.silo_path("wip");
+    env.git(&["worktree", "remove", &wip_path.to_string_lossy()]);
+    env.git(&["branch", "leftover"]);
+
+    let output = env.run_silo(&["orphan-branches"]);
+    TestEnv::assert_success(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(
+        stdout.contains("leftover") && stdout.contains("merged"),
+        "got: {}",
+        stdout
+    );
+    assert!(stdout.contains("1 commit not merged"), "got: {}", stdout);
+    // Checked out in a silo or the main worktree
+    assert!(!stdout.contains("active"), "got: {}", stdout);
+    assert!(!stdout.contains("main"), "got: {}", stdout);
 
-    // Reset the silo to main's HEAD
-    let output = env.run_silo(&["reset", "feature", "--force"]);
+    let output = env.run_silo(&["orphan-branches", "--delete-merged", "--dry-run"]);
+    assert!(TestEnv::stdout(&output).contains("Would delete branch 'leftover'"));
+    let output = env.git(&["branch", "--list", "leftover"]);
+    assert!(!TestEnv::stdout(&output).is_empty());
 
+    let output = env.run_silo(&["orphan-branches", "--delete-merged", "--force", "--json"]);
     TestEnv::assert_success(&output);
+    let report: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    let deleted: Vec<(&str, bool)> = report
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| {
+            (
+                b["branch"].as_str().unwrap(),
+                b["deleted"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(deleted, [("leftover", true), ("wip", false)]);
+    let output = env.git(&["branch", "--list", "leftover", "wip"]);
+    assert_eq!(TestEnv::stdout(&output).trim(), "wip");
+}
 
-    // The silo should now have main.txt (from main's current commit)
-    let main_file = env.silo_path("feature").join("main.txt");
-    assert!(main_file.exists(), "Silo should have main.txt after reset");
-
-    // The feature.txt should be gone (it was only in the silo's commits)
-    let feature_file = env.silo_path("feature").join("feature.txt");
-    assert!(
-        !feature_file.exists(),
-        "feature.txt should be gone after reset"
+/// Make a silo look orphaned while git can still read its repository data:
+/// a relative gitdir is resolved by git against the silo, but silo looks
+/// for the main worktree relative to where it runs.
+fn orphan_with_readable_git_data(env: &TestEnv, name: &str) -> std::path::PathBuf {
+    let silo_path = env.silo_path(name);
+    let gitdir = env.repo_dir.path().join(".git/worktrees").join(name);
+    let common = silo_path
+        .ancestors()
+        .find(|dir| gitdir.starts_with(dir))
+        .unwrap();
+    let ups = silo_path.strip_prefix(common).unwrap().components().count();
+    let relative = format!(
+        "{}{}",
+        "../".repeat(ups),
+        gitdir.strip_prefix(common).unwrap().display()
     );
+    std::fs::write(silo_path.join(".git"), format!("gitdir: {}\n", relative)).unwrap();
+    silo_path
 }
 
 #[test]
-fn test_reset_nonexistent_fails() {
+fn test_gc_keeps_orphan_with_unpushed_commits() {
     let env = TestEnv::new();
+    env.create_silo("unpushed");
+    env.create_commit_in_silo("unpushed", "work.txt", "work", "Unpushed work");
+    let orphan_path = orphan_with_readable_git_data(&env, "unpushed");
 
-    let output = env.run_silo(&["reset", "nonexistent", "--force"]);
+    let output = env.run_silo(&["gc", "--yes"]);
 
-    TestEnv::assert_failure(&output);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(orphan_path.exists(), "unpushed commits must not be deleted");
+    assert!(TestEnv::stderr(&output).contains("not pushed to any remote"));
 }
 
 #[test]
-fn test_reset_dry_run() {
+fn test_gc_force_bundles_unpushed_commits_before_removing() {
     let env = TestEnv::new();
-    env.create_silo("feature");
-    env.create_commit_in_silo("feature", "feature.txt", "content", "Feature");
+    env.create_silo("unpushed");
+    env.create_commit_in_silo("unpushed", "work.txt", "work", "Unpushed work");
+    let orphan_path = orphan_with_readable_git_data(&env, "unpushed");
 
-    let output = env.run_silo(&["reset", "feature", "--dry-run", "--force"]);
+    let output = env.run_silo(&["gc", "--force", "--json"]);
 
     TestEnv::assert_success(&output);
-    let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("Would reset"));
-
-    // File should still exist (dry run)
-    let feature_file = env.silo_path("feature").join("feature.txt");
-    assert!(
-        feature_file.exists(),
-        "File should still exist after dry run"
-    );
+    assert!(!orphan_path.exists());
+    let report: serde_json::Value = serde_json::from_str(&TestEnv::stdout(&output)).unwrap();
+    let bundle = report["orphaned_silos"][0]["bundle"].as_str().unwrap();
+    let heads = env.git(&["bundle", "list-heads", bundle]);
+    TestEnv::assert_success(&heads);
 }
 
 #[test]
-fn test_reset_with_uncommitted_changes_requires_confirmation() {
-    let env = TestEnv::new();
-    env.create_silo("dirty-silo");
-    env.create_uncommitted_file("dirty-silo", "dirty.txt", "uncommitted");
+fn test_gc_relinks_orphans_of_moved_repository() {
+    let mut env = TestEnv::builder().with_remote().build();
+    env.create_silo("feature");
+    env.create_commit_in_silo("feature", "work.txt", "work", "Work");
+    let silo_path = env.silo_path("feature");
+    env.move_repo();
 
-    // Without --force, should ask for confirmation (fails in non-interactive)
-    let output = env.run_silo(&["reset", "dirty-silo"]);
-    // In non-interactive mode, confirmation defaults to no
-    TestEnv::assert_success(&output);
-    let stdout = TestEnv::stdout(&output);
-    assert!(
-        stdout.contains("Aborted"),
-        "Should abort without confirmation"
-    );
+    // Run from the repository's new location, which registers it
+    let output = env.run_silo(&["gc", "--yes"]);
 
-    // File should still exist
-    let dirty_file = env.silo_path("dirty-silo").join("dirty.txt");
-    assert!(dirty_file.exists(), "dirty.txt should still exist");
+    TestEnv::assert_success(&output);
+    assert!(silo_path.exists(), "relinked silo must not be removed");
+    assert!(TestEnv::stdout(&output).contains("Relinked orphaned silo"));
+    let list = TestEnv::stdout(&env.run_silo(&["list", "--json"]));
+    assert!(list.contains("\"name\": \"feature\""), "got: {}", list);
+    TestEnv::assert_success(&env.git_in_silo("feature", &["status"]));
 }
 
 #[test]
-fn test_reset_with_force_discards_uncommitted_changes() {
-    let env = TestEnv::new();
-    env.create_silo("dirty-silo");
-    env.create_uncommitted_file("dirty-silo", "dirty.txt", "uncommitted");
-
-    // With --force, should reset without asking
-    let output = env.run_silo(&["reset", "dirty-silo", "--force"]);
+fn test_gc_dry_run_reports_relink() {
+    let mut env = TestEnv::builder().with_remote().build();
+    env.create_silo("feature");
+    env.move_repo();
 
-    TestEnv::assert_success(&output);
+    let output = env.run_silo(&["gc", "--dry-run"]);
 
-    // dirty.txt should be gone
-    let dirty_file = env.silo_path("dirty-silo").join("dirty.txt");
-    assert!(
-        !dirty_file.exists(),
-        "dirty.txt should be removed after reset"
-    );
+    assert_eq!(output.status.code(), Some(3));
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("will be relinked"), "got: {}", stdout);
+    assert!(stdout.contains("Would relink 1 orphaned silo"));
 }
 
 #[test]
-fn test_reset_with_unmerged_commits_requires_confirmation() {
+fn test_gc_quiet_flag() {
     let env = TestEnv::new();
-    env.create_silo("has-commits");
-    env.create_commit_in_silo("has-commits", "new.txt", "content", "Add new file");
+    let _orphan_path = env.create_orphaned_silo("orphan-silo");
+
+    let output = env.run_silo(&["gc", "--force", "--quiet"]);
 
-    // Without --force, should ask for confirmation (fails in non-interactive)
-    let output = env.run_silo(&["reset", "has-commits"]);
     TestEnv::assert_success(&output);
     let stdout = TestEnv::stdout(&output);
     assert!(
-        stdout.contains("Aborted"),
-        "Should abort without confirmation"
+        stdout.trim().is_empty(),
+        "Quiet gc should produce no stdout"
     );
-
-    // File should still exist
-    let new_file = env.silo_path("has-commits").join("new.txt");
-    assert!(new_file.exists(), "new.txt should still exist");
 }
 
 #[test]
-fn test_reset_with_force_discards_unmerged_commits() {
+fn test_gc_preserves_valid_silos() {
     let env = TestEnv::new();
-    env.create_silo("has-commits");
-    env.create_commit_in_silo("has-commits", "new.txt", "content", "Add new file");
+    // Create both a valid silo and an orphaned one
+    env.create_silo("valid-silo");
+    let orphan_path = env.create_orphaned_silo("orphan-silo");
 
-    // With --force, should reset without asking
-    let output = env.run_silo(&["reset", "has-commits", "--force"]);
+    let output = env.run_silo(&["gc", "--force"]);
 
     TestEnv::assert_success(&output);
+    env.assert_silo_exists("valid-silo");
+    assert!(!orphan_path.exists(), "Orphan should be removed");
+}
 
-    // new.txt should be gone
-    let new_file = env.silo_path("has-commits").join("new.txt");
-    assert!(!new_file.exists(), "new.txt should be removed after reset");
+// =============================================================================
+// REUSE COMMAND TESTS
+// =============================================================================
+
+fn pool_size(env: &TestEnv) -> usize {
+    let worktrees = TestEnv::stdout(&env.git(&["worktree", "list"]));
+    worktrees.lines().filter(|l| l.contains("/.pool/")).count()
 }
 
 #[test]
-fn test_reset_quiet_flag() {
+fn test_reuse_takes_silo_from_pool_filled_by_gc() {
     let env = TestEnv::new();
-    env.create_silo("quiet-reset");
-    env.create_commit("main.txt", "content", "Add main file");
+    env.append_config("pool_size = 2");
 
-    let output = env.run_silo(&["reset", "quiet-reset", "--force", "--quiet"]);
+    let output = env.run_silo(&["gc", "--dry-run"]);
+    assert!(TestEnv::stdout(&output).contains("Would add 2 silos to the pool"));
+    assert_eq!(pool_size(&env), 0);
 
+    let output = env.run_silo(&["gc", "--yes"]);
     TestEnv::assert_success(&output);
-    let stdout = TestEnv::stdout(&output);
-    // Output should be minimal
-    assert!(
-        stdout.trim().is_empty(),
-        "Quiet reset should produce no stdout"
+    assert!(TestEnv::stdout(&output).contains("Added 2 silos to the pool"));
+    assert_eq!(pool_size(&env), 2);
+    // Pool worktrees aren't silos
+    assert!(!TestEnv::stdout(&env.run_silo(&["list"])).contains("pool"));
+
+    // The silo starts at the current HEAD, not where the pool was filled
+    env.git(&["commit", "--allow-empty", "-m", "Later"]);
+    let output = env.run_silo(&["reuse", "feature"]);
+    TestEnv::assert_success(&output);
+    assert!(TestEnv::stdout(&output).contains("Reused pooled silo"));
+    env.assert_silo_exists("feature");
+    assert_eq!(pool_size(&env), 1);
+    let head = |output: std::process::Output| TestEnv::stdout(&output).trim().to_string();
+    assert_eq!(
+        head(env.git_in_silo("feature", &["rev-parse", "HEAD"])),
+        head(env.git(&["rev-parse", "HEAD"]))
     );
-}
+    assert_eq!(
+        head(env.git_in_silo("feature", &["branch", "--show-current"])),
+        "feature"
+    );
+    assert!(TestEnv::stdout(&env.run_silo(&["list"])).contains("feature"));
 
-// =============================================================================
-// EDGE CASE TESTS
-// =============================================================================
+    TestEnv::assert_success(&env.run_silo(&["gc", "--yes"]));
+    assert_eq!(pool_size(&env), 2);
+}
 
 #[test]
-fn test_silo_name_with_slashes() {
+#[cfg(unix)]
+fn test_gc_creates_storage_for_pool_with_restrictive_mode() {
+    use std::os::unix::fs::PermissionsExt;
     let env = TestEnv::new();
+    let storage = env.silo_dir.path().join("pool-storage");
+    fs::write(
+        env.repo_dir.path().join(".silo.toml"),
+        format!("worktree_dir = \"{}\"\npool_size = 1", storage.display()),
+    )
+    .unwrap();
 
-    let output = env.run_silo(&["new", "feature/sub-feature"]);
+    TestEnv::assert_success(&env.run_silo(&["gc", "--yes"]));
 
-    TestEnv::assert_success(&output);
-    env.assert_silo_exists("feature/sub-feature");
+    let mode = fs::metadata(&storage).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode, 0o700);
 }
 
 #[test]
-fn test_quiet_flag_suppresses_output() {
+fn test_reuse_creates_silo_when_pool_is_empty() {
     let env = TestEnv::new();
 
-    let output = env.run_silo(&["new", "quiet-test", "--quiet"]);
+    let output = env.run_silo(&["reuse", "feature"]);
 
     TestEnv::assert_success(&output);
-    let stdout = TestEnv::stdout(&output);
-    // Output should be minimal/empty
-    assert!(stdout.trim().is_empty() || stdout.len() < 50);
+    assert!(TestEnv::stderr(&output).contains("No idle silo in the pool"));
+    assert!(TestEnv::stdout(&output).contains("Created silo"));
+    env.assert_silo_exists("feature");
 }
 
 // =============================================================================
-// GC COMMAND TESTS
+// VERIFY-STORAGE COMMAND TESTS
 // =============================================================================
 
 #[test]
-fn test_gc_removes_orphaned_silos() {
+fn test_verify_storage_reports_nothing_for_consistent_storage() {
     let env = TestEnv::new();
-    let orphan_path = env.create_orphaned_silo("orphan-silo");
-
-    // Verify the orphan exists
-    assert!(orphan_path.exists(), "Orphan should exist before gc");
+    env.create_silos(&["feature", "user/fix"]);
 
-    let output = env.run_silo(&["gc", "--force"]);
+    let output = env.run_silo(&["verify-storage"]);
 
     TestEnv::assert_success(&output);
-    assert!(!orphan_path.exists(), "Orphan should be removed after gc");
+    assert!(TestEnv::stdout(&output).contains("No problems found"));
 }
 
 #[test]
-fn test_gc_removes_empty_directories() {
+fn test_verify_storage_fix_prunes_worktrees_removed_by_hand() {
     let env = TestEnv::new();
-    let empty_dir = env.create_empty_repo_dir("empty-repo-dir");
+    env.create_silos(&["kept", "gone"]);
+    let gone = env.silo_path("gone");
+    fs::remove_dir_all(&gone).unwrap();
 
-    // Verify the empty dir exists
-    assert!(empty_dir.exists(), "Empty dir should exist before gc");
+    let output = env.run_silo(&["verify-storage"]);
 
-    let output = env.run_silo(&["gc", "--force"]);
+    TestEnv::assert_failure(&output);
+    let stdout = TestEnv::stdout(&output);
+    assert!(stdout.contains("but doesn't exist"), "got: {}", stdout);
+    assert!(
+        stdout.contains("tracking data of a silo that doesn't exist"),
+        "got: {}",
+        stdout
+    );
 
+    // A dry run changes nothing
+    let output = env.run_silo(&["--dry-run", "verify-storage", "--fix"]);
     TestEnv::assert_success(&output);
-    assert!(!empty_dir.exists(), "Empty dir should be removed after gc");
+    assert!(TestEnv::stdout(&output).contains("Would fix 2 problems."));
+    TestEnv::assert_failure(&env.run_silo(&["verify-storage"]));
+
+    let output = env.run_silo(&["verify-storage", "--fix"]);
+
+    TestEnv::assert_success(&output);
+    let worktrees = TestEnv::stdout(&env.git(&["worktree", "list"]));
+    assert!(
+        !worktrees.contains(gone.to_str().unwrap()),
+        "got: {}",
+        worktrees
+    );
+    env.assert_silo_exists("kept");
+    TestEnv::assert_success(&env.run_silo(&["verify-storage"]));
 }
 
 #[test]
-fn test_gc_dry_run_does_not_remove() {
+fn test_verify_storage_fix_repairs_renamed_and_unlisted_worktrees() {
     let env = TestEnv::new();
-    let orphan_path = env.create_orphaned_silo("orphan-silo");
-    let empty_dir = env.create_empty_repo_dir("empty-repo-dir");
+    env.create_silos(&["feature", "unlisted"]);
+    // Renamed by hand to differ in case only
+    let renamed = env.silo_path("feature").with_file_name("Feature");
+    fs::rename(env.silo_path("feature"), &renamed).unwrap();
+    // Git's record points somewhere else entirely
+    let admin = env.repo_dir.path().join(".git/worktrees/unlisted/gitdir");
+    fs::write(&admin, "/nonexistent/unlisted/.git\n").unwrap();
 
-    let output = env.run_silo(&["gc", "--dry-run"]);
+    let output = env.run_silo(&["verify-storage"]);
 
-    TestEnv::assert_success(&output);
+    TestEnv::assert_failure(&output);
     let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("Would remove"));
+    assert!(stdout.contains("git records it as"), "got: {}", stdout);
+    assert!(stdout.contains("doesn't list"), "got: {}", stdout);
+
+    let output = env.run_silo(&["verify-storage", "--fix"]);
+
+    TestEnv::assert_success(&output);
+    let worktrees = TestEnv::stdout(&env.git(&["worktree", "list"]));
     assert!(
-        orphan_path.exists(),
-        "Orphan should still exist after dry-run"
+        worktrees.contains(renamed.to_str().unwrap()),
+        "got: {}",
+        worktrees
     );
+    // Its tracking data is renamed with it rather than removed as stale
+    let tracking = renamed.parent().unwrap().join(".tracking");
+    assert!(tracking.join("Feature/created").exists());
+    assert!(!tracking.join("feature").exists());
     assert!(
-        empty_dir.exists(),
-        "Empty dir should still exist after dry-run"
+        worktrees.contains(env.silo_path("unlisted").to_str().unwrap()),
+        "got: {}",
+        worktrees
     );
+    TestEnv::assert_success(&env.run_silo(&["verify-storage"]));
 }
 
 #[test]
-fn test_gc_no_orphans_reports_nothing() {
+fn test_hooks_install_force_keeps_earlier_backup() {
     let env = TestEnv::new();
-    // Create a valid silo (not orphaned)
-    env.create_silo("valid-silo");
-
-    let output = env.run_silo(&["gc"]);
+    let hooks = env.repo_dir.path().join(".git/hooks");
+    fs::write(hooks.join("pre-commit"), "#!/bin/sh\nexit 0\n").unwrap();
+    TestEnv::assert_success(&env.run_silo(&["hooks", "install", "--force"]));
 
-    TestEnv::assert_success(&output);
-    let stdout = TestEnv::stdout(&output);
-    assert!(stdout.contains("No orphaned silos or empty directories"));
-    env.assert_silo_exists("valid-silo");
+    // Another hook replacing silo's is refused rather than overwriting the backup
+    fs::write(hooks.join("pre-commit"), "#!/bin/sh\nexit 1\n").unwrap();
+    let output = env.run_silo(&["hooks", "install", "--force"]);
+    TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("pre-commit.orig"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+    assert_eq!(
+        fs::read_to_string(hooks.join("pre-commit.orig")).unwrap(),
+        "#!/bin/sh\nexit 0\n"
+    );
+    assert_eq!(
+        fs::read_to_string(hooks.join("pre-commit")).unwrap(),
+        "#!/bin/sh\nexit 1\n"
+    );
 }
 
 #[test]
-fn test_gc_quiet_flag() {
-    let env = TestEnv::new();
-    let _orphan_path = env.create_orphaned_silo("orphan-silo");
-
-    let output = env.run_silo(&["gc", "--force", "--quiet"]);
-
+fn test_hooks_warn_or_block_commits_to_protected_branch() {
+    let env = TestEnv::builder().with_remote().build();
+    env.create_silo("feature");
+    let hooks = env.repo_dir.path().join(".git/hooks");
+    fs::write(hooks.join("pre-commit"), "#!/bin/sh\nexit 0\n").unwrap();
+
+    // Someone else's hook is only replaced with --force, and kept aside
+    TestEnv::assert_failure(&env.run_silo(&["hooks", "install"]));
+    TestEnv::assert_success(&env.run_silo(&["hooks", "install", "--force"]));
+    assert!(hooks.join("pre-commit.orig").exists());
+
+    // Warn (default): the commit goes through with a hint
+    let branch = TestEnv::stdout(&env.git(&["branch", "--show-current"]));
+    let branch = branch.trim();
+    fs::write(env.repo_dir.path().join("a.txt"), "a").unwrap();
+    env.git(&["add", "a.txt"]);
+    let output = env.git(&["commit", "-m", "direct"]);
     TestEnv::assert_success(&output);
-    let stdout = TestEnv::stdout(&output);
+    let stderr = TestEnv::stderr(&output);
     assert!(
-        stdout.trim().is_empty(),
-        "Quiet gc should produce no stdout"
+        stderr.contains(&format!("committing directly to '{}'", branch)),
+        "got: {}",
+        stderr
+    );
+    assert!(stderr.contains("silo new"), "got: {}", stderr);
+
+    // Silos aren't on a protected branch
+    env.create_commit_in_silo("feature", "b.txt", "b", "in silo");
+    let output = env.git_in_silo("feature", &["log", "-1", "--format=%s"]);
+    assert_eq!(TestEnv::stdout(&output).trim(), "in silo");
+
+    // Block: commits and pushes to the protected branch are refused
+    env.append_config(r#"protected_branch_policy = "block""#);
+    fs::write(env.repo_dir.path().join("c.txt"), "c").unwrap();
+    env.git(&["add", "c.txt"]);
+    let output = env.git(&["commit", "-m", "blocked"]);
+    TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains("is blocked"),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+    let output = env.git(&["push", "origin", branch]);
+    TestEnv::assert_failure(&output);
+    assert!(
+        TestEnv::stderr(&output).contains(&format!("Pushing directly to '{}'", branch)),
+        "got: {}",
+        TestEnv::stderr(&output)
+    );
+    TestEnv::assert_success(&env.git_in_silo("feature", &["push", "origin", "feature"]));
+
+    // Uninstalling restores the hook that was moved aside
+    TestEnv::assert_success(&env.run_silo(&["hooks", "uninstall"]));
+    assert_eq!(
+        fs::read_to_string(hooks.join("pre-commit")).unwrap(),
+        "#!/bin/sh\nexit 0\n"
     );
+    assert!(!hooks.join("pre-push").exists());
 }
 
 #[test]
-fn test_gc_preserves_valid_silos() {
+fn test_note_sets_branch_description_shown_by_list_long() {
     let env = TestEnv::new();
-    // Create both a valid silo and an orphaned one
-    env.create_silo("valid-silo");
-    let orphan_path = env.create_orphaned_silo("orphan-silo");
+    env.create_silo("described");
+
+    TestEnv::assert_success(&env.run_silo(&[
+        "note",
+        "described",
+        "Fix the login race\n\nSessions were shared across tabs.",
+    ]));
+
+    // It's git's own branch description
+    let output = env.git(&["config", "branch.described.description"]);
+    assert!(TestEnv::stdout(&output).starts_with("Fix the login race\n"));
+    let output = env.run_silo(&["note", "described"]);
+    assert_eq!(
+        TestEnv::stdout(&output),
+        "Fix the login race\n\nSessions were shared across tabs.\n"
+    );
 
-    let output = env.run_silo(&["gc", "--force"]);
+    let stdout = TestEnv::stdout(&env.run_silo(&["list", "--long"]));
+    assert!(
+        stdout.contains("\n      Fix the login race\n"),
+        "got: {}",
+        stdout
+    );
+    assert!(!stdout.contains("Sessions"), "got: {}", stdout);
+    assert!(!TestEnv::stdout(&env.run_silo(&["list"])).contains("login race"));
+    let json: serde_json::Value =
+        serde_json::from_str(&TestEnv::stdout(&env.run_silo(&["list", "--json"]))).unwrap();
+    assert!(
+        json[0]["description"]
+            .as_str()
+            .unwrap()
+            .starts_with("Fix the login race")
+    );
 
+    TestEnv::assert_success(&env.run_silo(&["note", "described", "--clear"]));
+    let output = env.run_silo(&["note", "described"]);
     TestEnv::assert_success(&output);
-    env.assert_silo_exists("valid-silo");
-    assert!(!orphan_path.exists(), "Orphan should be removed");
+    assert_eq!(TestEnv::stdout(&output), "");
 }